@@ -1,43 +1,303 @@
+use crate::backend_error::{Backend, BackendError};
+use crate::click_pressure::ClickPressure;
 use crate::config::PtpConfig;
-use crate::dimensions::Dimensions;
-use crate::heatmap::HeatmapFrame;
-use crate::input::TouchState;
-use crate::libinput_state::LibinputEvent;
+use crate::contact_swap::SwapDetector;
+use crate::cursor::CursorView;
+#[cfg(target_os = "linux")]
+use crate::device_properties::DeviceProperties;
+use crate::dimensions::{Dimensions, Rotation, ViewTransform};
+use crate::dwell::DwellMap;
+use crate::edge_swipe::EdgeSwipes;
+use crate::edge_test::EdgeTest;
+use crate::gesture_hooks::{GestureHooks, Trigger};
+use crate::gestures::GestureRecognizer;
+use crate::heatmap_ab::{AbTest, AbTestAction};
+use crate::heatmap_export::HeatmapHistory;
+use crate::heatmap_watch::{HeatmapEvent, HeatmapWatch};
+use crate::ink::Ink;
+use crate::jitter::{JitterTest, JitterTestAction};
+use crate::keys::{Action, Binding, KeyBindings};
+use crate::libinput_export::LibinputExporter;
 use crate::libinput_state::LibinputState;
-use crate::multitouch::{ButtonState, TouchData, MAX_TOUCH_POINTS};
-use crate::recording::{Recorder, Recording};
-use crate::render;
-use std::sync::mpsc;
-use std::time::Instant;
+use crate::libinput_state::{LibinputCommand, LibinputEvent};
+use crate::linearity::LinearityTest;
+#[cfg(feature = "midi")]
+use crate::midi::MidiOutput;
+use crate::notify::{Notice, Notifier};
+use crate::orientation_test::{OrientationTest, OrientationTestAction};
+use crate::outliers::OutlierCheck;
+use crate::overlay;
+use crate::palm::{PalmDetector, PalmThresholds};
+use crate::palm_test::{PalmTest, PalmTestAction};
+use crate::pen::{Arbitration, PenState};
+use crate::pressure_test::PressureTest;
+use crate::rates::Rates;
+use crate::recalibration::{Recalibration, RecalibrationAction};
+use crate::register_dumps::DumpComparison;
+use crate::render::{
+    self, ClickPressureAction, ClickZones, Colormap, HeatmapScale, HeatmapStyle, HeatmapTexture,
+    RegisterDumpAction, RegisterWatchAction, Theme, ThemeChoice,
+};
+use crate::report_rate::ReportRate;
+use crate::report_timing::ReportTiming;
+use crate::resolution_test::ResolutionTest;
+#[cfg(feature = "scripting")]
+use crate::script::ScriptHook;
+use crate::settings::SettingsStore;
+use crate::slot_plots::SlotPlots;
+use crate::tap_drag::TapDrag;
+use crate::tap_latency::TapLatency;
+use crate::tap_timing::TapTiming;
+use crate::threshold_sim::ThresholdSim;
+use crate::tracking::TrackingStats;
+use crate::trajectory::Trajectory;
+use crate::tuio::TuioOutput;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tapview_core::channel::{self, BoundedReceiver, BoundedSender, LatestReceiver};
+use tapview_core::heatmap::driver::DriverCommand;
+use tapview_core::heatmap::registers::{RegisterDump, RegisterWatch};
+use tapview_core::heatmap::{HeatmapFrame, HeatmapOrientation};
+use tapview_core::input::descriptor_tree::Node as DescriptorNode;
+#[cfg(target_os = "linux")]
+use tapview_core::input::replay::{ReplayDevice, ReplayPlayer};
+use tapview_core::input::{CommandSender, TouchState};
+use tapview_core::logging::LogLine;
+use tapview_core::multitouch::{ButtonState, TouchData, MAX_TOUCH_POINTS};
+use tapview_core::recording::{Recorder, Recording};
+use tapview_core::shutdown::Shutdown;
 
-const HISTORY_MAX: usize = 20;
+/// Log console lines kept in memory.
+const LOG_MAX: usize = 500;
+/// evdev BTN_LEFT; BTN_RIGHT and BTN_MIDDLE follow it.
+const BTN_LEFT: u32 = 0x110;
+/// Backend errors queued for the banner.
+const ERRORS_MAX: usize = 16;
+#[cfg(target_os = "linux")]
+const CURSOR_HINT: &str =
+    "Pointer followed from libinput motion; drifts if something else moves it";
+#[cfg(not(target_os = "linux"))]
+const CURSOR_HINT: &str = "System cursor position";
+
+/// Repaint interval with nothing moving, for clocks and rate meters.
+const IDLE_REPAINT: Duration = Duration::from_millis(250);
+
+/// Hard cap on stored trail snapshots, in case a device reports far faster
+/// than expected.
+const HISTORY_MAX: usize = 2048;
+
+/// How much of the background shows behind an `--overlay`.
+const OVERLAY_OPACITY: f32 = 0.25;
 
-#[allow(dead_code)]
 pub enum GrabCommand {
     Grab,
     Ungrab,
+    /// Play one click on a haptic touchpad
+    Pulse,
+}
+
+/// Backend threads, stopped and joined when the window closes, and the
+/// fatal errors they (or their setup) report.
+pub struct Workers {
+    pub shutdown: Shutdown,
+    pub threads: Vec<(&'static str, JoinHandle<()>)>,
+    pub errors_tx: BoundedSender<BackendError>,
+    errors_rx: BoundedReceiver<BackendError>,
+}
+
+impl Default for Workers {
+    fn default() -> Self {
+        let (errors_tx, errors_rx) = channel::bounded(ERRORS_MAX);
+        Self {
+            shutdown: Shutdown::default(),
+            threads: Vec::new(),
+            errors_tx,
+            errors_rx,
+        }
+    }
+}
+
+impl Workers {
+    fn stop(&mut self) {
+        self.shutdown.request();
+        for (name, thread) in self.threads.drain(..) {
+            if thread.join().is_err() {
+                log_warn!("{} thread panicked", name);
+            }
+        }
+    }
 }
 
+/// A started heatmap's frames, or why it still can't start.
+pub type HeatmapRetryReceiver = BoundedReceiver<Result<LatestReceiver<HeatmapFrame>, String>>;
+
 pub struct TapviewApp {
-    touch_rx: mpsc::Receiver<TouchState>,
-    #[allow(dead_code)]
-    grab_tx: mpsc::Sender<GrabCommand>,
-    libinput_rx: Option<mpsc::Receiver<LibinputEvent>>,
-    heatmap_rx: Option<mpsc::Receiver<HeatmapFrame>>,
+    touch_rx: BoundedReceiver<TouchState>,
+    grab_tx: CommandSender<GrabCommand>,
+    workers: Workers,
+    libinput_rx: Option<BoundedReceiver<LibinputEvent>>,
+    libinput_cmd_tx: Option<mpsc::Sender<LibinputCommand>>,
+    heatmap_rx: Option<LatestReceiver<HeatmapFrame>>,
     heatmap_frame: Option<HeatmapFrame>,
+    heatmap_texture: HeatmapTexture,
+    heatmap_orientation: HeatmapOrientation,
+    heatmap_colormap: Colormap,
+    heatmap_smooth: bool,
+    heatmap_scale: HeatmapScale,
+    /// Heatmap shown in its own window instead of the bottom panel
+    heatmap_popped: bool,
+    /// Recent frames for exporting as an animation, and how many to export
+    heatmap_history: HeatmapHistory,
+    ab_test: Option<AbTest>,
+    recalibration: Option<Recalibration>,
+    /// The difference a forced recalibration made
+    recalibration_texture: HeatmapTexture,
+    heatmap_export_frames: usize,
+    /// Calibration episodes and stuck cells, for notifications
+    heatmap_watch: HeatmapWatch,
+    /// Why `--heatmap` has no frames (yet), and the thread retrying it
+    heatmap_unavailable: Option<String>,
+    heatmap_retry: Option<HeatmapRetryReceiver>,
+    heatmap_unavailable_hidden: bool,
+    /// Current window inner size, remembered on exit.
+    window_size: Option<(f32, f32)>,
+    /// Window position on screen, where the platform tells.
+    window_pos: Option<(f32, f32)>,
+    /// Panel sizes as last laid out, remembered on exit.
+    libinput_panel_width: Option<f32>,
+    heatmap_panel_height: Option<f32>,
     ptp_config: Option<PtpConfig>,
+    log_rx: Option<BoundedReceiver<LogLine>>,
+    log_lines: Vec<LogLine>,
+    /// Fatal backend errors not dismissed yet
+    backend_errors: Vec<BackendError>,
+    show_log: bool,
+    show_slot_plots: bool,
+    show_cursor: bool,
+    /// Borderless see-through window with only the touch view
+    overlay: bool,
+    /// The overlay lets clicks through to what's under it
+    click_through: bool,
+    /// Window is kept above others
+    always_on_top: bool,
+    slot_plots: SlotPlots,
+    cursor: CursorView,
+    notifier: Notifier,
+    tuio: Option<TuioOutput>,
+    #[cfg(feature = "midi")]
+    midi: Option<MidiOutput>,
+    /// udev properties, quirks and hwdb entries of the device
+    #[cfg(target_os = "linux")]
+    device_properties: Option<DeviceProperties>,
+    /// Pen of a pen-and-touch device, its latest state and what it does to
+    /// touches
+    pen_rx: Option<BoundedReceiver<PenState>>,
+    pen: PenState,
+    arbitration: Arbitration,
     dims: Dimensions,
     current_touches: [TouchData; MAX_TOUCH_POINTS],
     buttons: ButtonState,
-    touch_history: Vec<[TouchData; MAX_TOUCH_POINTS]>,
+    /// Past touch snapshots, newest first, stamped with timeline seconds.
+    touch_history: VecDeque<(f64, [TouchData; MAX_TOUCH_POINTS])>,
+    /// Time base for live-mode history timestamps.
+    clock: Instant,
     libinput: LibinputState,
-    trails: usize,
+    tap_latency: TapLatency,
+    tap_timing: TapTiming,
+    click_pressure: ClickPressure,
+    /// The device can play a haptic pulse
+    haptics: bool,
+    tap_drag: TapDrag,
+    report_rate: ReportRate,
+    report_timing: ReportTiming,
+    tracking: TrackingStats,
+    swaps: SwapDetector,
+    outliers: OutlierCheck,
+    jitter_test: Option<JitterTest>,
+    linearity_test: Option<LinearityTest>,
+    edge_test: Option<EdgeTest>,
+    pressure_test: Option<PressureTest>,
+    palm_test: Option<PalmTest>,
+    resolution_test: Option<ResolutionTest>,
+    orientation_test: Option<OrientationTest>,
+    /// The device's report descriptor as a tree, or why it couldn't be read
+    descriptor: Option<Result<Vec<DescriptorNode>, String>>,
+    show_descriptor: bool,
+    /// Registers polled by the heatmap thread, when there is one
+    registers: Option<RegisterWatch>,
+    show_registers: bool,
+    /// The register being typed in to watch
+    register_input: String,
+    register_dumps: DumpComparison,
+    trajectory: Trajectory,
+    /// Built-in recognizer over the raw contacts: swipes and pinches Windows
+    /// doesn't report, a stand-in when libinput is unavailable, or running
+    /// next to libinput for comparison
+    gestures: Option<GestureRecognizer>,
+    /// The built-in recognizer's interpretation while comparing with libinput
+    builtin: Option<LibinputState>,
+    /// How long a trail takes to fade out, in milliseconds (0 disables trails).
+    trail_ms: u32,
+    ink: Ink,
+    ink_enabled: bool,
     #[allow(dead_code)]
     grabbed: bool,
+    settings: Option<SettingsStore>,
+    settings_open: bool,
+    keys: KeyBindings,
+    gesture_hooks: GestureHooks,
+    /// Action whose binding is being edited in the settings window.
+    capturing: Option<Action>,
+    theme: ThemeChoice,
+    show_mm_grid: bool,
+    pressure_scaling: bool,
+    show_slot_table: bool,
+    show_rate_hud: bool,
+    rates: Rates,
+    /// Where contacts have been this session; always accumulated.
+    dwell: DwellMap,
+    show_dwell: bool,
+    show_edge_zones: bool,
+    edge_swipes: EdgeSwipes,
+    /// ABS_MT_PRESSURE (min, max) from the device, if reported.
+    pressure_range: Option<(i32, i32)>,
+    /// Highest pressure seen so far, used when the range is unknown.
+    pressure_seen_max: i32,
+    /// ABS_MT_ORIENTATION value meaning a quarter turn, if reported.
+    orientation_max: Option<i32>,
+    /// ABS_MT_DISTANCE maximum on hover-capable devices, if reported.
+    distance_max: Option<i32>,
+    /// udev hwdb match line for this device, where hwdb applies
+    hwdb_match: Option<String>,
+    /// Device is a clickpad (INPUT_PROP_BUTTONPAD).
+    buttonpad: bool,
+    /// INPUT_PROP_SEMI_MT: two contacts are a bounding box, not fingers
+    semi_mt: bool,
+    /// Slots the device has, up to MAX_TOUCH_POINTS
+    slot_count: usize,
+    /// Which contacts libinput would likely treat as palms or thumbs.
+    palm: PalmDetector,
+    /// Contacts judged under slider thresholds, for writing quirks
+    threshold_sim: ThresholdSim,
+    click_zones: ClickZones,
     // Recording
     recorder: Option<Recorder>,
+    /// Where received libinput events are being written, if anywhere
+    libinput_export: Option<LibinputExporter>,
+    #[cfg(feature = "scripting")]
+    script: Option<ScriptHook>,
     // Playback
-    recording: Option<Recording>,
+    recording: Option<Arc<Recording>>,
+    /// Plays the recording into a virtual touchpad (--replay-uinput)
+    #[cfg(target_os = "linux")]
+    replay: Option<ReplayPlayer>,
+    /// Playback time and transport as the replay player last heard them,
+    /// to tell seeks apart from playing on
+    #[cfg(target_os = "linux")]
+    replay_synced: (f64, bool, f32),
     playback_time: f64,
     playback_speed: f32,
     playback_playing: bool,
@@ -47,32 +307,165 @@ pub struct TapviewApp {
 impl TapviewApp {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        touch_rx: mpsc::Receiver<TouchState>,
-        grab_tx: mpsc::Sender<GrabCommand>,
-        libinput_rx: Option<mpsc::Receiver<LibinputEvent>>,
-        heatmap_rx: Option<mpsc::Receiver<HeatmapFrame>>,
+        touch_rx: BoundedReceiver<TouchState>,
+        grab_tx: CommandSender<GrabCommand>,
+        workers: Workers,
+        libinput_rx: Option<BoundedReceiver<LibinputEvent>>,
+        libinput_cmd_tx: Option<mpsc::Sender<LibinputCommand>>,
+        heatmap_rx: Option<LatestReceiver<HeatmapFrame>>,
         ptp_config: Option<PtpConfig>,
+        log_rx: Option<BoundedReceiver<LogLine>>,
         evdev_extents: Option<(i32, i32)>,
-        trails: usize,
+        resolution: Option<(f32, f32)>,
+        pressure_range: Option<(i32, i32)>,
+        orientation_max: Option<i32>,
+        distance_max: Option<i32>,
+        hwdb_match: Option<String>,
+        buttonpad: bool,
+        semi_mt: bool,
+        slot_count: Option<usize>,
+        palm_thresholds: PalmThresholds,
+        trail_ms: u32,
         recorder: Option<Recorder>,
+        libinput_export: Option<LibinputExporter>,
         recording: Option<Recording>,
+        settings: Option<SettingsStore>,
+        theme: ThemeChoice,
     ) -> Self {
+        let device_settings = settings.as_ref().map(|s| s.device()).unwrap_or_default();
+        let mut dims = Dimensions::from_extents(evdev_extents);
+        dims.resolution = resolution;
+        if let (false, Some((x, y))) = (dims.extent_known, device_settings.learned_extents) {
+            dims.maybe_grow_touchpad_extent(x, y);
+        }
+        dims.set_transform(device_settings.view_transform);
+        let keys = settings.as_ref().map(|s| s.keys()).unwrap_or_default();
+        let gesture_hooks = settings
+            .as_ref()
+            .map(|s| GestureHooks::new(s.gestures()))
+            .unwrap_or_default();
+        let dwell = DwellMap::new(dims.touchpad_max_extent_x, dims.touchpad_max_extent_y);
+        let gestures =
+            (libinput_rx.is_none() && recording.is_none()).then(GestureRecognizer::default);
         Self {
             touch_rx,
             grab_tx,
+            workers,
             libinput_rx,
+            libinput_cmd_tx,
             heatmap_rx,
             heatmap_frame: None,
-            dims: Dimensions::from_extents(evdev_extents),
+            heatmap_texture: HeatmapTexture::default(),
+            heatmap_orientation: device_settings.heatmap_orientation,
+            heatmap_colormap: device_settings.heatmap_colormap,
+            heatmap_smooth: device_settings.heatmap_smooth,
+            heatmap_scale: device_settings.heatmap_scale,
+            heatmap_popped: false,
+            heatmap_history: HeatmapHistory::default(),
+            ab_test: None,
+            recalibration: None,
+            recalibration_texture: HeatmapTexture::default(),
+            heatmap_export_frames: HeatmapHistory::CAPACITY,
+            heatmap_watch: HeatmapWatch::default(),
+            heatmap_unavailable: None,
+            heatmap_retry: None,
+            heatmap_unavailable_hidden: false,
+            window_size: None,
+            window_pos: None,
+            libinput_panel_width: device_settings.libinput_panel_width,
+            heatmap_panel_height: device_settings.heatmap_panel_height,
+            dims,
             ptp_config,
+            log_rx,
+            log_lines: Vec::new(),
+            backend_errors: Vec::new(),
+            show_log: device_settings.log_console,
+            show_slot_plots: device_settings.slot_plots,
+            show_cursor: device_settings.cursor_view,
+            overlay: false,
+            click_through: false,
+            always_on_top: true,
+            slot_plots: SlotPlots::default(),
+            cursor: CursorView::default(),
+            notifier: Notifier::new(device_settings.notifications),
+            tuio: None,
+            #[cfg(feature = "midi")]
+            midi: None,
+            #[cfg(target_os = "linux")]
+            device_properties: None,
+            pen_rx: None,
+            pen: PenState::default(),
+            arbitration: Arbitration::default(),
             current_touches: [TouchData::default(); MAX_TOUCH_POINTS],
             buttons: ButtonState::default(),
-            touch_history: vec![[TouchData::default(); MAX_TOUCH_POINTS]; HISTORY_MAX],
+            touch_history: VecDeque::new(),
+            clock: Instant::now(),
             libinput: LibinputState::default(),
-            trails,
+            tap_latency: TapLatency::default(),
+            tap_timing: TapTiming::default(),
+            click_pressure: ClickPressure::default(),
+            haptics: false,
+            tap_drag: TapDrag::default(),
+            report_rate: ReportRate::default(),
+            report_timing: ReportTiming::default(),
+            tracking: TrackingStats::default(),
+            swaps: SwapDetector::default(),
+            outliers: OutlierCheck::new(evdev_extents, pressure_range, resolution),
+            jitter_test: None,
+            linearity_test: None,
+            edge_test: None,
+            pressure_test: None,
+            palm_test: None,
+            resolution_test: None,
+            descriptor: None,
+            show_descriptor: false,
+            registers: None,
+            show_registers: false,
+            register_input: String::new(),
+            register_dumps: DumpComparison::default(),
+            orientation_test: None,
+            trajectory: Trajectory::new(resolution),
+            gestures,
+            builtin: None,
+            trail_ms,
+            ink: Ink::default(),
+            ink_enabled: false,
             grabbed: false,
+            settings,
+            settings_open: false,
+            keys,
+            gesture_hooks,
+            capturing: None,
+            theme,
+            show_mm_grid: device_settings.mm_grid,
+            pressure_scaling: device_settings.pressure_scaling,
+            show_slot_table: device_settings.slot_table,
+            show_rate_hud: device_settings.rate_hud,
+            rates: Rates::default(),
+            dwell,
+            show_dwell: device_settings.dwell_map,
+            show_edge_zones: device_settings.edge_zones,
+            edge_swipes: EdgeSwipes::new(device_settings.edge_band_mm),
+            pressure_range,
+            pressure_seen_max: 0,
+            orientation_max,
+            distance_max,
+            hwdb_match,
+            buttonpad,
+            semi_mt,
+            slot_count: slot_count.map_or(MAX_TOUCH_POINTS, |c| c.min(MAX_TOUCH_POINTS)),
+            threshold_sim: ThresholdSim::new(pressure_range, &palm_thresholds),
+            palm: PalmDetector::new(palm_thresholds, buttonpad),
+            click_zones: device_settings.click_zones,
             recorder,
-            recording,
+            libinput_export,
+            #[cfg(feature = "scripting")]
+            script: None,
+            recording: recording.map(Arc::new),
+            #[cfg(target_os = "linux")]
+            replay: None,
+            #[cfg(target_os = "linux")]
+            replay_synced: (0.0, false, 1.0),
             playback_time: 0.0,
             playback_speed: 1.0,
             playback_playing: false,
@@ -82,15 +475,73 @@ impl TapviewApp {
 }
 
 impl eframe::App for TapviewApp {
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        if self.overlay {
+            [0.0; 4]
+        } else {
+            egui::Color32::from_rgba_unmultiplied(12, 12, 12, 180).to_normalized_gamma_f32()
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Learned extents, window geometry and panel sizes only change
+        // implicitly, so they're written here rather than on every change.
+        self.save_settings();
+        self.workers.stop();
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let is_playback = self.recording.is_some();
 
+        if let Some(rect) = ctx.input(|i| i.viewport().inner_rect) {
+            self.window_size = Some((rect.width(), rect.height()));
+        }
+        // Wayland doesn't let windows know where they are
+        if let Some(rect) = ctx.input(|i| i.viewport().outer_rect) {
+            self.window_pos = Some((rect.min.x, rect.min.y));
+        }
+
+        // Keyboard shortcuts (ignored while a text field has focus or a
+        // binding is being edited)
+        let actions: Vec<Action> = if ctx.wants_keyboard_input() || self.capturing.is_some() {
+            Vec::new()
+        } else {
+            ctx.input(|i| {
+                Action::ALL
+                    .into_iter()
+                    .filter(|&a| self.keys.pressed(a, i))
+                    .collect()
+            })
+        };
+        for &action in &actions {
+            self.handle_action(action, is_playback);
+        }
+        if self.overlay {
+            if actions.contains(&Action::ClickThrough) || overlay::toggled() {
+                self.click_through = !self.click_through;
+                ctx.send_viewport_cmd(egui::ViewportCommand::MousePassthrough(self.click_through));
+            }
+            // Nothing else wakes the UI for SIGUSR1
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+
         if is_playback {
             // --- Playback: advance time, look up frame ---
-            self.handle_playback_input(ctx);
 
             let duration = self.recording.as_ref().unwrap().duration_secs();
 
+            #[cfg(target_os = "linux")]
+            if let Some(replay) = &self.replay {
+                let (time, playing, speed) = self.replay_synced;
+                if (playing, speed) != (self.playback_playing, self.playback_speed) {
+                    replay.run(self.playback_playing, self.playback_speed);
+                }
+                // Anything but playing on moved the time since the last frame
+                if time != self.playback_time {
+                    replay.seek(self.playback_time);
+                }
+            }
+
             if self.playback_playing {
                 let now = Instant::now();
                 if let Some(last) = self.playback_last_wall {
@@ -110,6 +561,14 @@ impl eframe::App for TapviewApp {
             }
 
             self.playback_time = self.playback_time.clamp(0.0, duration);
+            #[cfg(target_os = "linux")]
+            {
+                self.replay_synced = (
+                    self.playback_time,
+                    self.playback_playing,
+                    self.playback_speed,
+                );
+            }
 
             // Look up frame
             if let Some(frame) = self
@@ -118,51 +577,372 @@ impl eframe::App for TapviewApp {
                 .unwrap()
                 .frame_at(self.playback_time)
             {
-                self.current_touches = frame.state.touches;
+                self.current_touches = self.dims.to_view(&frame.state.touches);
                 self.buttons = frame.state.buttons;
             }
+            if self.ink_enabled {
+                self.ink.update(&self.current_touches);
+            }
+
+            // Seeking backwards invalidates the trail
+            if self
+                .touch_history
+                .front()
+                .is_some_and(|(t, _)| *t > self.playback_time)
+            {
+                self.touch_history.clear();
+            }
+            if self
+                .touch_history
+                .front()
+                .is_none_or(|(t, _)| *t < self.playback_time)
+            {
+                self.push_history(self.playback_time);
+                #[cfg(feature = "scripting")]
+                if let Some(script) = &self.script {
+                    if let Some(frame) = self
+                        .recording
+                        .as_ref()
+                        .unwrap()
+                        .frame_at(self.playback_time)
+                    {
+                        script.on_touch(self.playback_time, &frame.state.touches, &self.buttons);
+                    }
+                }
+                self.dwell.add(&self.current_touches);
+                self.slot_plots
+                    .add(self.playback_time, &self.current_touches);
+                self.trajectory.on_touch(&self.current_touches);
+                let touches = self.current_touches;
+                self.update_edge_swipes(&touches, Instant::now());
+                self.palm.update(
+                    &self.current_touches,
+                    (
+                        self.dims.touchpad_max_extent_x,
+                        self.dims.touchpad_max_extent_y,
+                    ),
+                    self.dims.resolution,
+                );
+                self.threshold_sim.update(&self.current_touches);
+            }
         } else {
+            // Pen first, so touches dropped as it arrives are seen as such
+            if let Some(rx) = &self.pen_rx {
+                while let Ok(pen) = rx.try_recv() {
+                    self.pen = pen;
+                    self.arbitration.update_pen(&pen);
+                }
+            }
             // --- Live mode: drain touch events ---
-            while let Ok(state) = self.touch_rx.try_recv() {
+            while let Ok(mut state) = self.touch_rx.try_recv() {
+                // Scripts, recordings and the calibrations get device
+                // coordinates; everything else works in the view's
+                let raw = state.touches;
+                state.touches = self.dims.to_view(&raw);
                 self.current_touches = state.touches;
                 self.buttons = state.buttons;
+                #[cfg(feature = "scripting")]
+                if let Some(script) = &self.script {
+                    let t = self.clock.elapsed().as_secs_f64();
+                    script.on_touch(t, &raw, &state.buttons);
+                }
+                self.rates.touch_reports.add(state.reports);
+                let t = state.time.saturating_duration_since(self.clock);
+                self.slot_plots.add(t.as_secs_f64(), &state.touches);
+                if let Some(tuio) = &mut self.tuio {
+                    let extents = (
+                        self.dims.touchpad_max_extent_x,
+                        self.dims.touchpad_max_extent_y,
+                    );
+                    if let Err(e) = tuio.send(&state.touches, extents, t.as_secs_f64()) {
+                        log_error!("TUIO output error: {}", e);
+                        self.tuio = None;
+                    }
+                }
+                #[cfg(feature = "midi")]
+                if let Some(midi) = &mut self.midi {
+                    let extents = (
+                        self.dims.touchpad_max_extent_x,
+                        self.dims.touchpad_max_extent_y,
+                    );
+                    let pressure_range = self.pressure_range.unwrap_or((
+                        0,
+                        state
+                            .touches
+                            .iter()
+                            .map(|t| t.pressure)
+                            .fold(self.pressure_seen_max, i32::max),
+                    ));
+                    if let Err(e) = midi.send(&state.touches, extents, pressure_range) {
+                        log_error!("MIDI output error: {}", e);
+                        self.midi = None;
+                    }
+                }
+                for report in &state.report_times {
+                    self.report_rate.add(report.time, report.contacts);
+                }
+                self.report_timing
+                    .add_batch(&state.report_times, SystemTime::now());
+                self.tracking
+                    .update(&state.touches, self.dims.touchpad_max_extent_x, state.time);
+                self.swaps
+                    .update(&state.touches, self.dims.touchpad_max_extent_x);
+                let width = self.dims.touchpad_max_extent_x;
+                for outlier in self.outliers.update(&state.touches, state.reports, width) {
+                    log_warn!("outlier: {}", outlier);
+                }
+                if let Some(gestures) = &mut self.gestures {
+                    let width = self.dims.touchpad_max_extent_x;
+                    for event in gestures.update(&state.touches, width, state.time) {
+                        match &mut self.builtin {
+                            Some(builtin) => builtin.apply_event(&event),
+                            None => self.handle_libinput_event(event),
+                        }
+                    }
+                }
+                let fingers = state.touches.iter().filter(|t| t.used).count();
+                self.tap_latency.on_touch(fingers > 0, state.time);
+                self.tap_timing.on_touch(fingers > 0, state.time);
+                if self.buttonpad {
+                    let firmest = state
+                        .touches
+                        .iter()
+                        .filter(|t| t.used)
+                        .max_by_key(|t| t.pressure)
+                        .map(|t| (t.pressure, t.touch_major));
+                    // Saturation only means something against the advertised range
+                    let max = self.pressure_range.map_or(0, |(_, max)| max);
+                    self.click_pressure.update(firmest, state.buttons.left, max);
+                }
+                self.tap_drag.on_touch(fingers, state.time);
+                self.update_edge_swipes(&state.touches, state.time);
+                if self.pen_rx.is_some() {
+                    self.arbitration.update(&state.touches, state.time);
+                }
+                let contacts: Vec<(i32, i32, i32)> = state
+                    .touches
+                    .iter()
+                    .filter(|t| t.used)
+                    .map(|t| (t.tracking_id, t.position_x, t.position_y))
+                    .collect();
+                if let Some(test) = &mut self.jitter_test {
+                    test.update(&contacts, state.time);
+                }
+                if let Some(test) = &mut self.pressure_test {
+                    let contact = state
+                        .touches
+                        .iter()
+                        .find(|t| t.used)
+                        .map(|t| (t.pressure, t.touch_major));
+                    test.update(
+                        contact,
+                        contacts.len(),
+                        state.touches[0].pressed,
+                        state.buttons.left,
+                        state.time,
+                    );
+                }
+                if let Some(test) = &mut self.resolution_test {
+                    let contacts: Vec<(i32, i32, i32)> = raw
+                        .iter()
+                        .filter(|t| t.used)
+                        .map(|t| (t.tracking_id, t.position_x, t.position_y))
+                        .collect();
+                    test.update(&contacts);
+                }
+                if let Some(test) = &mut self.orientation_test {
+                    test.update(&raw, self.dims.device_extents());
+                }
+                if let Some(test) = &mut self.edge_test {
+                    let positions: Vec<(i32, i32)> =
+                        contacts.iter().map(|&(_, x, y)| (x, y)).collect();
+                    test.update(&positions);
+                }
+                if let Some(test) = &mut self.linearity_test {
+                    test.update(
+                        &contacts,
+                        (
+                            self.dims.touchpad_max_extent_x,
+                            self.dims.touchpad_max_extent_y,
+                        ),
+                        self.dims.resolution,
+                    );
+                }
+                self.dwell.add(&self.current_touches);
+                self.trajectory.on_touch(&self.current_touches);
+                self.palm.update(
+                    &self.current_touches,
+                    (
+                        self.dims.touchpad_max_extent_x,
+                        self.dims.touchpad_max_extent_y,
+                    ),
+                    self.dims.resolution,
+                );
+                self.threshold_sim.update(&self.current_touches);
+                if let Some(test) = &mut self.palm_test {
+                    let contacts: Vec<(i32, i32, bool)> = self
+                        .current_touches
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, t)| t.used)
+                        .map(|(slot, t)| {
+                            (
+                                t.tracking_id,
+                                t.tool_type,
+                                self.palm.suppression(slot).is_some(),
+                            )
+                        })
+                        .collect();
+                    test.update(&contacts);
+                }
+                self.push_history(self.clock.elapsed().as_secs_f64());
+                if self.ink_enabled {
+                    self.ink.update(&self.current_touches);
+                }
 
                 // Record each frame
                 if let Some(ref mut recorder) = self.recorder {
+                    state.touches = raw;
                     if let Err(e) = recorder.record(&state) {
-                        eprintln!("Recording error: {}", e);
+                        log_error!("Recording error: {}", e);
+                        self.notifier
+                            .notify(Notice::RecordingEnded, &format!("Recording error: {}", e));
                         self.recorder = None;
                     }
                 }
             }
         }
 
+        for error in self.workers.errors_rx.try_iter() {
+            self.notifier
+                .notify(Notice::DeviceError, &error.to_string());
+            // Nothing more is coming to record
+            if error.backend == Backend::Input {
+                if let Some(mut recorder) = self.recorder.take() {
+                    if let Err(e) = recorder.flush() {
+                        log_error!("Recording error: {}", e);
+                    }
+                    self.notifier
+                        .notify(Notice::RecordingEnded, "The touchpad stopped reporting");
+                }
+            }
+            self.backend_errors.push(error);
+        }
+
+        if let Some(rx) = &self.log_rx {
+            self.log_lines.extend(rx.try_iter());
+            if self.log_lines.len() > LOG_MAX {
+                self.log_lines.drain(..self.log_lines.len() - LOG_MAX);
+            }
+        }
+
+        #[cfg(feature = "scripting")]
+        if let Some(script) = &mut self.script {
+            script.poll();
+        }
+
         // Drain and apply libinput events
-        if let Some(rx) = &self.libinput_rx {
-            while let Ok(event) = rx.try_recv() {
-                self.libinput.apply_event(&event);
+        let events: Vec<LibinputEvent> = self
+            .libinput_rx
+            .as_ref()
+            .map(|rx| rx.try_iter().collect())
+            .unwrap_or_default();
+        for event in events {
+            self.handle_libinput_event(event);
+        }
+
+        if self.show_cursor {
+            #[cfg(target_os = "linux")]
+            if let Some((size, ppp)) =
+                ctx.input(|i| Some((i.viewport().monitor_size?, i.pixels_per_point)))
+            {
+                self.cursor
+                    .set_screen_size((size.x * ppp) as f64, (size.y * ppp) as f64);
             }
+            #[cfg(target_os = "windows")]
+            self.cursor.poll();
         }
 
-        // Drain heatmap frames, keep only the latest
-        if let Some(rx) = &self.heatmap_rx {
-            while let Ok(frame) = rx.try_recv() {
-                self.heatmap_frame = Some(frame);
+        // A heatmap that couldn't start may have come up since
+        if let Some(retry) = &self.heatmap_retry {
+            for result in retry.try_iter() {
+                match result {
+                    Ok(rx) => {
+                        let ctx = ctx.clone();
+                        rx.on_send(move || ctx.request_repaint());
+                        self.heatmap_rx = Some(rx);
+                        self.heatmap_unavailable = None;
+                    }
+                    Err(reason) => self.heatmap_unavailable = Some(reason),
+                }
+            }
+            if self.heatmap_rx.is_some() {
+                self.heatmap_retry = None;
             }
         }
 
-        // Handle grab/ungrab keys (Linux only — Windows doesn't support touchpad grab)
-        #[cfg(target_os = "linux")]
-        if !is_playback {
-            ctx.input(|i| {
-                if i.key_pressed(egui::Key::Enter) && !self.grabbed {
-                    let _ = self.grab_tx.send(GrabCommand::Grab);
-                    self.grabbed = true;
-                } else if i.key_pressed(egui::Key::Escape) && self.grabbed {
-                    let _ = self.grab_tx.send(GrabCommand::Ungrab);
-                    self.grabbed = false;
+        if let Some(dump) = self.registers.as_ref().and_then(|r| r.take_dump()) {
+            self.save_register_dump(dump);
+        }
+        if let Some((command, result)) = self
+            .registers
+            .as_ref()
+            .and_then(|r| r.take_command_result())
+        {
+            match command {
+                DriverCommand::SetAlc(_) => match &mut self.ab_test {
+                    Some(test) => test.switched(result, Instant::now()),
+                    None => {
+                        if let Err(e) = result {
+                            log_warn!(target: "heatmap", "couldn't switch ALC: {}", e);
+                        }
+                    }
+                },
+                DriverCommand::Recalibrate => {
+                    if let Some(recalibration) = &mut self.recalibration {
+                        recalibration.written(result, Instant::now());
+                    }
                 }
-            });
+            }
+        }
+
+        // Take the latest heatmap frame; older ones were already replaced.
+        // The raw frame goes back to the heatmap thread to be read into again.
+        if let Some(rx) = &self.heatmap_rx {
+            if let Some(frame) = rx.take() {
+                let mut oriented = self.heatmap_frame.take().unwrap_or_default();
+                self.heatmap_orientation.apply_into(&frame, &mut oriented);
+                rx.recycle(frame);
+                self.heatmap_history.push(&oriented);
+                if let Some(on) = self
+                    .ab_test
+                    .as_mut()
+                    .and_then(|test| test.update(&oriented, Instant::now()))
+                {
+                    self.send_driver_command(DriverCommand::SetAlc(on));
+                }
+                if self
+                    .recalibration
+                    .as_mut()
+                    .is_some_and(|r| r.update(&oriented, Instant::now()))
+                {
+                    self.send_driver_command(DriverCommand::Recalibrate);
+                }
+                let touching = self.current_touches.iter().any(|t| t.used);
+                for event in self.heatmap_watch.push(&oriented, touching) {
+                    log_warn!(target: "heatmap", "{}", event);
+                    // One notification per episode, once its length is known
+                    let notice = match event {
+                        HeatmapEvent::CalibrationStarted => continue,
+                        HeatmapEvent::CalibrationEnded { .. } => Notice::Calibration,
+                        HeatmapEvent::StuckCell { .. } => Notice::StuckCell,
+                    };
+                    self.notifier.notify(notice, &event.to_string());
+                }
+                self.heatmap_frame = Some(oriented);
+                self.heatmap_texture.invalidate();
+                self.rates.heatmap_frames.add(1);
+            }
         }
 
         // Grow touchpad extents from current touches (only when the
@@ -178,44 +958,20 @@ impl eframe::App for TapviewApp {
             }
         }
 
-        // Show config left panel if available
-        if let Some(config) = &mut self.ptp_config {
-            egui::SidePanel::left("config_panel")
-                .default_width(200.0)
-                .min_width(160.0)
-                .show(ctx, |ui| {
-                    render::draw_config_panel(ui, config);
-                });
-        }
-
-        // Show heatmap bottom panel if active
-        if let Some(frame) = &self.heatmap_frame {
-            egui::TopBottomPanel::bottom("heatmap_panel")
-                .default_height(200.0)
-                .min_height(100.0)
-                .show(ctx, |ui| {
-                    render::draw_heatmap_panel(ui, frame);
-                });
+        for touch in &self.current_touches {
+            if touch.used {
+                self.pressure_seen_max = self.pressure_seen_max.max(touch.pressure);
+            }
         }
 
-        // Show libinput side panel if we have a receiver
-        if self.libinput_rx.is_some() {
-            egui::SidePanel::right("libinput_panel")
-                .default_width(200.0)
-                .min_width(150.0)
-                .show(ctx, |ui| {
-                    render::draw_libinput_panel(ui, &self.libinput);
-                });
+        // An overlay shows only the touch view
+        if !self.overlay {
+            self.draw_panels(ctx, is_playback);
         }
 
         // Decay libinput values after rendering
         self.libinput.decay();
 
-        // Show playback controls panel if in playback mode
-        if is_playback {
-            self.draw_playback_panel(ctx);
-        }
-
         // Update dimensions from central panel area
         let central_rect = ctx.available_rect();
         self.dims.screen_width = central_rect.width();
@@ -227,8 +983,15 @@ impl eframe::App for TapviewApp {
         let cscale = scale.clamp(0.5, 2.0);
 
         egui::CentralPanel::default()
-            .frame(egui::Frame::NONE.fill(egui::Color32::WHITE))
+            .frame(egui::Frame::NONE.fill(if self.overlay {
+                Theme::current(ctx)
+                    .background
+                    .gamma_multiply(OVERLAY_OPACITY)
+            } else {
+                Theme::current(ctx).background
+            }))
             .show(ctx, |ui| {
+                let theme = Theme::current(ctx);
                 let painter = ui.painter();
 
                 // Draw touchpad boundary
@@ -236,6 +999,89 @@ impl eframe::App for TapviewApp {
                 let boundary_height = self.dims.touchpad_max_extent_y * scale;
                 render::draw_touchpad_boundary(painter, corner, boundary_width, boundary_height);
 
+                if let Some(res) = self.dims.resolution {
+                    if self.show_mm_grid {
+                        render::draw_mm_grid(
+                            painter,
+                            corner,
+                            scale,
+                            boundary_width,
+                            boundary_height,
+                            res,
+                        );
+                    }
+                    render::draw_mm_readout(
+                        painter,
+                        central_rect.min + egui::Vec2::new(8.0, 8.0),
+                        &self.current_touches,
+                        corner,
+                        scale,
+                        res,
+                    );
+                }
+
+                if self.show_edge_zones {
+                    let (rx, ry) = self.dims.resolution.unwrap_or((
+                        self.dims.touchpad_max_extent_x / 100.0,
+                        self.dims.touchpad_max_extent_x / 100.0,
+                    ));
+                    let band = egui::Vec2::new(rx, ry) * self.edge_swipes.band_mm * scale;
+                    let badge = self
+                        .edge_swipes
+                        .badge(Instant::now())
+                        .map(|(event, (x, y))| {
+                            (event.label(), corner + egui::Vec2::new(x, y) * scale)
+                        });
+                    render::draw_edge_zones(
+                        painter,
+                        corner,
+                        boundary_width,
+                        boundary_height,
+                        band,
+                        badge.as_ref().map(|(label, pos)| (label.as_str(), *pos)),
+                    );
+                    if badge.is_some() {
+                        ctx.request_repaint_after(Duration::from_millis(100));
+                    }
+                }
+
+                if self.pen_rx.is_some() {
+                    let device = self.dims.device_extents();
+                    let to_screen = |(x, y): (f32, f32)| corner + egui::Vec2::new(x, y) * scale;
+                    let pen = self.pen.proximity.then(|| {
+                        let pos = (self.pen.x * device.0, self.pen.y * device.1);
+                        to_screen(self.dims.transform.apply(pos, device))
+                    });
+                    let dropped: Vec<egui::Pos2> = self
+                        .arbitration
+                        .dropped
+                        .iter()
+                        .map(|(pos, _)| to_screen(*pos))
+                        .collect();
+                    render::draw_pen(painter, pen, &self.pen, &dropped);
+                    if !dropped.is_empty() {
+                        ctx.request_repaint_after(Duration::from_millis(100));
+                    }
+                }
+
+                if self.buttonpad {
+                    render::draw_click_zones(
+                        painter,
+                        corner,
+                        scale,
+                        boundary_width,
+                        boundary_height,
+                        self.dims.resolution,
+                        self.click_zones,
+                    );
+                }
+
+                render::draw_slot_legend(
+                    painter,
+                    egui::Pos2::new(central_rect.min.x + 8.0, central_rect.max.y - 12.0),
+                    &self.current_touches[..self.slot_count],
+                );
+
                 // Draw button indicators
                 render::draw_button_indicators(
                     painter,
@@ -244,30 +1090,117 @@ impl eframe::App for TapviewApp {
                     boundary_width,
                     boundary_height,
                 );
+                if self.libinput_rx.is_some() {
+                    render::draw_drag_badge(
+                        painter,
+                        egui::Pos2::new(corner.x, corner.y + boundary_height + 8.0),
+                        &self.tap_drag,
+                    );
+                }
+                if let Some(tool_fingers) = self.buttons.tool_fingers {
+                    let active = self.current_touches.iter().filter(|t| t.used).count();
+                    render::draw_finger_count(
+                        painter,
+                        egui::Pos2::new(
+                            corner.x + boundary_width,
+                            corner.y + boundary_height + 8.0,
+                        ),
+                        tool_fingers,
+                        active,
+                    );
+                }
+
+                if self.show_dwell {
+                    render::draw_dwell_map(
+                        painter,
+                        &self.dwell,
+                        corner,
+                        scale,
+                        self.heatmap_colormap,
+                    );
+                }
+                render::draw_ink(painter, &self.ink.strokes, corner, scale);
+                if let Some(test) = &self.edge_test {
+                    render::draw_edge_band(painter, test, corner, scale);
+                }
+                if self.ink_enabled {
+                    painter.text(
+                        egui::Pos2::new(central_rect.max.x - 8.0, central_rect.max.y - 8.0),
+                        egui::Align2::RIGHT_BOTTOM,
+                        "Ink on (I: toggle, C: clear)",
+                        egui::FontId::proportional(11.0),
+                        theme.text_secondary,
+                    );
+                }
 
-                // Draw historical touch data (trails)
-                for h in 0..self.trails.min(HISTORY_MAX) {
-                    for (i, touch) in self.touch_history[h].iter().enumerate() {
+                // Draw historical touch data (trails), fading with age
+                let now = if is_playback {
+                    self.playback_time
+                } else {
+                    self.clock.elapsed().as_secs_f64()
+                };
+                let trail_secs = self.trail_ms as f64 / 1000.0;
+                self.touch_history.retain(|(t, _)| now - *t < trail_secs);
+                for (t, touches) in &self.touch_history {
+                    if self.semi_mt && touches.iter().filter(|t| t.used).count() > 1 {
+                        continue;
+                    }
+                    let alpha = (1.0 - (now - t) / trail_secs) as f32;
+                    for (i, touch) in touches.iter().enumerate() {
                         if !touch.used {
                             continue;
                         }
-                        render::draw_trail(painter, touch, i, corner, scale, cscale);
+                        render::draw_trail(painter, touch, i, corner, scale, cscale, alpha);
                     }
                 }
 
+                render::draw_drop_marks(painter, &self.tracking.drops, corner, scale);
+                render::draw_swap_marks(painter, &self.swaps.swaps, corner, scale);
+                render::draw_outlier_marks(painter, &self.outliers.recent, corner, scale);
+                #[cfg(feature = "scripting")]
+                if let Some(script) = &self.script {
+                    render::draw_script_annotations(painter, &script.annotations, corner, scale);
+                }
+
                 // Draw current touch data
+                let mut used = self.current_touches.iter().filter(|t| t.used);
+                let semi_mt_box = match (self.semi_mt, used.next(), used.next()) {
+                    (true, Some(a), Some(b)) => Some((a, b)),
+                    _ => None,
+                };
+                if let Some((a, b)) = semi_mt_box {
+                    render::draw_semi_mt_box(
+                        painter,
+                        a,
+                        b,
+                        self.buttons.tool_fingers,
+                        corner,
+                        scale,
+                    );
+                }
                 for (i, touch) in self.current_touches.iter().enumerate() {
-                    if !touch.used {
+                    if !touch.used || semi_mt_box.is_some() {
                         continue;
                     }
-                    render::draw_touch(painter, touch, i, corner, scale, cscale);
-                }
-
-                // Pump history: shift everything down by one, newest at [0]
-                for h in (1..HISTORY_MAX).rev() {
-                    self.touch_history[h] = self.touch_history[h - 1];
+                    if touch.distance > 0 {
+                        let max = self.distance_max.unwrap_or(touch.distance);
+                        let distance = touch.distance as f32 / max.max(1) as f32;
+                        render::draw_hover(painter, touch, i, corner, scale, cscale, distance);
+                        continue;
+                    }
+                    let pressure = self.normalized_pressure(touch);
+                    let shape =
+                        render::ContactShape::from_touch(touch, scale, self.orientation_max);
+                    render::draw_touch(painter, touch, i, corner, scale, cscale, pressure, shape);
+                    // Simulated thresholds replace libinput's verdict while on
+                    if self.threshold_sim.enabled {
+                        if let Some(verdict) = self.threshold_sim.verdict(i) {
+                            render::draw_verdict(painter, touch, corner, scale, cscale, verdict);
+                        }
+                    } else if let Some(reason) = self.palm.suppression(i) {
+                        render::draw_suppression(painter, touch, corner, scale, cscale, reason);
+                    }
                 }
-                self.touch_history[0] = self.current_touches;
 
                 // Draw status text
                 let center = egui::Pos2::new(
@@ -276,29 +1209,42 @@ impl eframe::App for TapviewApp {
                 );
 
                 let text = if is_playback {
-                    "Space: play/pause, Left/Right: step"
+                    format!(
+                        "{}: play/pause, {}/{}: step",
+                        self.keys.describe(Action::PlayPause),
+                        self.keys.describe(Action::StepBack),
+                        self.keys.describe(Action::StepForward)
+                    )
                 } else if self.recorder.is_some() {
-                    "Recording... (touch the pad)"
+                    "Recording... (touch the pad)".to_string()
+                } else if self.overlay {
+                    let signal = if cfg!(target_os = "linux") {
+                        " or send SIGUSR1"
+                    } else {
+                        ""
+                    };
+                    format!(
+                        "Press {}{} to {} click-through",
+                        self.keys.describe(Action::ClickThrough).to_uppercase(),
+                        signal,
+                        if self.click_through { "end" } else { "restore" }
+                    )
+                } else if self.grabbed {
+                    format!(
+                        "Press {} to restore focus",
+                        self.keys.describe(Action::Ungrab).to_uppercase()
+                    )
                 } else {
-                    #[cfg(target_os = "linux")]
-                    {
-                        if self.grabbed {
-                            "Press ESC to restore focus"
-                        } else {
-                            "Press ENTER to grab touchpad"
-                        }
-                    }
-                    #[cfg(target_os = "windows")]
-                    {
-                        "Touch the touchpad to visualize"
-                    }
+                    format!(
+                        "Press {} to grab touchpad",
+                        self.keys.describe(Action::Grab).to_uppercase()
+                    )
                 };
 
                 // Choose font size based on available space
                 let font_size = {
                     let large_font = egui::FontId::proportional(30.0);
-                    let galley =
-                        painter.layout_no_wrap(text.to_string(), large_font, egui::Color32::GRAY);
+                    let galley = painter.layout_no_wrap(text.clone(), large_font, theme.hint);
                     if galley.size().x + self.dims.margin * 2.0
                         > self.dims.touchpad_max_extent_x * scale
                     {
@@ -313,57 +1259,1423 @@ impl eframe::App for TapviewApp {
                     egui::Align2::CENTER_CENTER,
                     text,
                     egui::FontId::proportional(font_size),
-                    egui::Color32::GRAY,
+                    theme.hint,
+                );
+
+                self.rates.tick();
+                if self.show_rate_hud {
+                    let mut meters = vec![("UI", &self.rates.ui_frames, 0)];
+                    if !is_playback {
+                        meters.push(("Touch", &self.rates.touch_reports, self.touch_rx.dropped()));
+                    }
+                    if let Some(rx) = &self.libinput_rx {
+                        meters.push(("libinput", &self.rates.libinput_events, rx.dropped()));
+                    }
+                    if let Some(rx) = &self.heatmap_rx {
+                        meters.push(("Heatmap", &self.rates.heatmap_frames, rx.dropped()));
+                    }
+                    render::draw_rate_hud(
+                        painter,
+                        egui::Pos2::new(central_rect.max.x - 8.0, central_rect.min.y + 36.0),
+                        &meters,
+                    );
+                }
+
+                if self.show_cursor {
+                    const CURSOR_VIEW_W: f32 = 160.0;
+                    let (_, _, w, h) = self.cursor.screen;
+                    let size = egui::Vec2::new(CURSOR_VIEW_W, CURSOR_VIEW_W * (h / w) as f32);
+                    let rect = egui::Rect::from_min_size(
+                        central_rect.max - size - egui::Vec2::splat(8.0),
+                        size,
+                    );
+                    let contact = self
+                        .current_touches
+                        .iter()
+                        .enumerate()
+                        .find(|(_, t)| t.used)
+                        .map(|(slot, t)| {
+                            (
+                                (
+                                    t.position_x as f32 / self.dims.touchpad_max_extent_x.max(1.0),
+                                    t.position_y as f32 / self.dims.touchpad_max_extent_y.max(1.0),
+                                ),
+                                render::SLOT_COLORS[slot % render::SLOT_COLORS.len()],
+                            )
+                        });
+                    render::draw_cursor_view(painter, rect, &self.cursor, contact);
+                }
+
+                // Settings toggle in the top-right corner
+                let gear_rect = egui::Rect::from_min_size(
+                    egui::Pos2::new(central_rect.max.x - 32.0, central_rect.min.y + 4.0),
+                    egui::Vec2::splat(28.0),
                 );
+                if ui
+                    .put(gear_rect, egui::Button::new("⚙").frame(false))
+                    .on_hover_text("Settings")
+                    .clicked()
+                {
+                    self.settings_open = !self.settings_open;
+                }
+                let clear_rect = gear_rect.translate(egui::Vec2::new(-28.0, 0.0));
+                let clear_hint = if self.dims.extent_known {
+                    "Clear trails and ink (X)"
+                } else {
+                    "Clear trails and ink (X)\nShift-click to also reset learned extents (Shift+X)"
+                };
+                if ui
+                    .put(clear_rect, egui::Button::new("⟲").frame(false))
+                    .on_hover_text(clear_hint)
+                    .clicked()
+                {
+                    let shift = ui.input(|i| i.modifiers.shift);
+                    self.clear_view(shift);
+                }
             });
 
-        // Request continuous repaint for animation
-        ctx.request_repaint();
+        // New data requests its own repaint (see repaint_on_data); only keep
+        // painting continuously while something is still moving on screen.
+        if self.animating() {
+            ctx.request_repaint();
+        } else {
+            ctx.request_repaint_after(IDLE_REPAINT);
+        }
     }
 }
 
 impl TapviewApp {
-    fn handle_playback_input(&mut self, ctx: &egui::Context) {
-        ctx.input(|i| {
-            if i.key_pressed(egui::Key::Space) {
-                self.playback_playing = !self.playback_playing;
-                // If at end and pressing play, restart
-                if self.playback_playing {
-                    let duration = self.recording.as_ref().unwrap().duration_secs();
-                    if self.playback_time >= duration {
-                        self.playback_time = 0.0;
-                    }
-                }
-            }
-            if i.key_pressed(egui::Key::ArrowLeft) {
-                self.playback_time = (self.playback_time - 0.1).max(0.0);
+    /// Everything around the touch view: error banner, side and bottom
+    /// panels, playback controls and tool windows.
+    fn draw_panels(&mut self, ctx: &egui::Context, is_playback: bool) {
+        if !self.backend_errors.is_empty() {
+            let errors = &self.backend_errors;
+            let dismissed = egui::TopBottomPanel::top("backend_errors")
+                .show(ctx, |ui| render::draw_error_banner(ui, errors))
+                .inner;
+            if let Some(i) = dismissed {
+                self.backend_errors.remove(i);
             }
-            if i.key_pressed(egui::Key::ArrowRight) {
-                let duration = self.recording.as_ref().unwrap().duration_secs();
-                self.playback_time = (self.playback_time + 0.1).min(duration);
+        }
+
+        // Show config left panel if available
+        if let Some(config) = &mut self.ptp_config {
+            egui::SidePanel::left("config_panel")
+                .default_width(200.0)
+                .min_width(160.0)
+                .show(ctx, |ui| {
+                    render::draw_config_panel(ui, config);
+                });
+        }
+
+        if self.show_log {
+            let lines = &self.log_lines;
+            let dropped = self.log_rx.as_ref().map_or(0, |rx| rx.dropped());
+            let clear = egui::TopBottomPanel::bottom("log_console")
+                .resizable(true)
+                .default_height(120.0)
+                .min_height(60.0)
+                .show(ctx, |ui| render::draw_log_console(ui, lines, dropped))
+                .inner;
+            if clear {
+                self.log_lines.clear();
             }
-        });
-    }
+        }
 
-    fn draw_playback_panel(&mut self, ctx: &egui::Context) {
-        let duration = self.recording.as_ref().unwrap().duration_secs();
+        if self.show_slot_plots {
+            let plots = &mut self.slot_plots;
+            let slot_count = self.slot_count;
+            egui::TopBottomPanel::bottom("slot_plots_panel")
+                .resizable(true)
+                .default_height(140.0)
+                .min_height(80.0)
+                .show(ctx, |ui| render::draw_slot_plots(ui, plots, slot_count));
+        }
 
-        egui::TopBottomPanel::bottom("playback_panel")
-            .exact_height(48.0)
-            .show(ctx, |ui| {
-                ui.horizontal_centered(|ui| {
-                    // Play/Pause button
-                    let label = if self.playback_playing {
-                        "Pause"
-                    } else {
-                        "Play"
-                    };
-                    if ui.button(label).clicked() {
-                        self.playback_playing = !self.playback_playing;
-                        if self.playback_playing && self.playback_time >= duration {
-                            self.playback_time = 0.0;
-                        }
-                    }
+        // Show heatmap bottom panel if active, or its own window if popped out
+        if let Some(frame) = &self.heatmap_frame {
+            let texture = &mut self.heatmap_texture;
+            let orientation = &mut self.heatmap_orientation;
+            let style = HeatmapStyle {
+                colormap: self.heatmap_colormap,
+                scale: self.heatmap_scale,
+                smooth: self.heatmap_smooth,
+            };
+            let is_popped = self.heatmap_popped;
+            let popped = &mut self.heatmap_popped;
+            let mut draw = |ui: &mut egui::Ui| {
+                render::draw_heatmap_panel(ui, frame, texture, orientation, style, popped)
+            };
+            let changed = if is_popped {
+                let (changed, closed) = draw_heatmap_window(ctx, draw);
+                if closed {
+                    self.heatmap_popped = false;
+                }
+                changed
+            } else {
+                let panel = egui::TopBottomPanel::bottom("heatmap_panel")
+                    .resizable(true)
+                    .default_height(self.heatmap_panel_height.unwrap_or(200.0))
+                    .min_height(100.0)
+                    .show(ctx, |ui| draw(ui));
+                self.heatmap_panel_height = Some(panel.response.rect.height());
+                panel.inner
+            };
+            if changed {
+                self.save_settings();
+            }
+        } else if let Some(reason) = &self.heatmap_unavailable {
+            if !self.heatmap_unavailable_hidden {
+                let error = BackendError::new(Backend::Heatmap, reason.clone());
+                egui::TopBottomPanel::bottom("heatmap_panel").show(ctx, |ui| {
+                    self.heatmap_unavailable_hidden = render::draw_heatmap_unavailable(ui, &error);
+                });
+            }
+        }
+
+        if self.show_slot_table {
+            egui::SidePanel::right("slot_table_panel")
+                .default_width(420.0)
+                .show(ctx, |ui| {
+                    render::draw_slot_table(ui, &self.current_touches[..self.slot_count]);
+                    ui.separator();
+                    if render::draw_report_rate(ui, &self.report_rate) {
+                        self.report_rate.clear();
+                    }
+                    ui.separator();
+                    if render::draw_report_timing(ui, &self.report_timing) {
+                        self.report_timing.clear();
+                    }
+                    ui.separator();
+                    if render::draw_tracking_stats(ui, &self.tracking, &self.swaps) {
+                        self.tracking.clear();
+                        self.swaps.clear();
+                    }
+                    ui.separator();
+                    if render::draw_outliers(ui, &self.outliers) {
+                        self.outliers.clear();
+                    }
+                });
+        }
+
+        // Show libinput side panel if we have a receiver or the built-in
+        // recognizer stands in for it
+        if self.libinput_rx.is_some() || self.gestures.is_some() {
+            let panel = egui::SidePanel::right("libinput_panel")
+                .default_width(self.libinput_panel_width.unwrap_or(200.0))
+                .min_width(150.0)
+                .show(ctx, |ui| {
+                    if self.libinput_rx.is_none() {
+                        ui.strong("Built-in recognizer");
+                        ui.label("libinput is unavailable; taps, scrolling, swipes and pinches are recognized from the raw contacts.");
+                        ui.separator();
+                    }
+                    if let Some(info) = &self.libinput.device_info {
+                        render::draw_libinput_device_info(ui, info);
+                        ui.separator();
+                    }
+                    if let (Some(config), Some(tx)) = (&self.libinput.config, &self.libinput_cmd_tx)
+                    {
+                        if let Some(command) = render::draw_libinput_config(ui, config) {
+                            let _ = tx.send(command);
+                        }
+                        ui.separator();
+                    }
+                    if render::draw_tap_latency(ui, &self.tap_latency) {
+                        self.tap_latency.clear();
+                    }
+                    if render::draw_tap_timing(ui, &mut self.tap_timing) {
+                        self.tap_timing.clear();
+                    }
+                    if self.buttonpad {
+                        match render::draw_click_pressure(
+                            ui,
+                            &self.click_pressure,
+                            self.pressure_max(),
+                            self.haptics,
+                        ) {
+                            Some(ClickPressureAction::Reset) => self.click_pressure.clear(),
+                            Some(ClickPressureAction::Pulse) => {
+                                let _ = self.grab_tx.send(GrabCommand::Pulse);
+                            }
+                            None => {}
+                        }
+                    }
+                    render::draw_threshold_sim(ui, &mut self.threshold_sim);
+                    #[cfg(target_os = "linux")]
+                    if let Some(properties) = &self.device_properties {
+                        render::draw_device_properties(ui, properties);
+                    }
+                    if self.pen_rx.is_some()
+                        && render::draw_pen_arbitration(ui, &self.arbitration, &self.pen)
+                    {
+                        self.arbitration.clear();
+                    }
+                    ui.separator();
+                    render::draw_trajectory(ui, &self.trajectory);
+                    ui.separator();
+                    render::draw_gesture_history(ui, &self.libinput, "Gestures");
+                    if self.libinput_rx.is_some() {
+                        let mut compare = self.builtin.is_some();
+                        if ui
+                            .checkbox(&mut compare, "Compare with built-in recognizer")
+                            .changed()
+                        {
+                            self.gestures = compare.then(GestureRecognizer::default);
+                            self.builtin = compare.then(LibinputState::default);
+                        }
+                        if let Some(builtin) = &self.builtin {
+                            render::draw_gesture_history(ui, builtin, "Built-in gestures");
+                        }
+                    }
+                    ui.separator();
+                    let exporting = self.libinput_export.is_some();
+                    if render::draw_libinput_panel(ui, &mut self.libinput, exporting) {
+                        self.toggle_libinput_export();
+                    }
+                });
+            self.libinput_panel_width = Some(panel.response.rect.width());
+        }
+
+        // Show playback controls panel if in playback mode
+        if is_playback {
+            self.draw_playback_panel(ctx);
+        }
+
+        self.draw_settings_window(ctx);
+        self.draw_jitter_window(ctx);
+        self.draw_ab_window(ctx);
+        self.draw_linearity_window(ctx);
+        self.draw_edge_window(ctx);
+        self.draw_pressure_window(ctx);
+        self.draw_palm_test_window(ctx);
+        self.draw_resolution_window(ctx);
+        self.draw_orientation_window(ctx);
+        self.draw_descriptor_window(ctx);
+        self.draw_register_window(ctx);
+        self.draw_recalibration_window(ctx);
+        #[cfg(feature = "scripting")]
+        self.draw_script_window(ctx);
+    }
+
+    /// Draw only the touch view, over a see-through background that lets
+    /// clicks through (`--overlay`).
+    pub fn overlay(&mut self) {
+        self.overlay = true;
+        self.click_through = true;
+    }
+
+    /// Whether the window was opened above others (`--no-always-on-top`).
+    pub fn always_on_top(&mut self, on_top: bool) {
+        self.always_on_top = on_top;
+    }
+
+    /// Turn on desktop notifications (`--notify`).
+    pub fn notify(&mut self) {
+        self.notifier.enabled = true;
+    }
+
+    /// Offer a haptic pulse button; the device can play force feedback.
+    pub fn haptics(&mut self) {
+        self.haptics = true;
+    }
+
+    /// The HID report descriptor to show as a tree, or why it couldn't be read.
+    pub fn descriptor(&mut self, tree: Result<Vec<DescriptorNode>, String>) {
+        self.descriptor = Some(tree);
+    }
+
+    /// Run a `--script` on what comes in and draw what it asks for.
+    #[cfg(feature = "scripting")]
+    pub fn script(&mut self, script: ScriptHook) {
+        self.script = Some(script);
+    }
+
+    /// Show why the heatmap asked for can't start, until `retry` hands over
+    /// its frames.
+    pub fn heatmap_unavailable(&mut self, reason: String, retry: HeatmapRetryReceiver) {
+        self.heatmap_unavailable = Some(reason);
+        self.heatmap_retry = Some(retry);
+    }
+
+    /// Offer watching firmware registers, read by the heatmap thread.
+    pub fn heatmap_registers(&mut self, registers: RegisterWatch) {
+        self.registers = Some(registers);
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn device_properties(&mut self, properties: DeviceProperties) {
+        self.device_properties = Some(properties);
+    }
+
+    /// Show the pen of a pen-and-touch device over the touches.
+    #[cfg(target_os = "linux")]
+    pub fn pen_input(&mut self, rx: BoundedReceiver<PenState>) {
+        self.pen_rx = Some(rx);
+    }
+
+    /// Send the contacts as TUIO (`--tuio`).
+    pub fn tuio_output(&mut self, output: TuioOutput) {
+        self.tuio = Some(output);
+    }
+
+    /// Send the contacts as MIDI control changes (`--midi`).
+    #[cfg(feature = "midi")]
+    pub fn midi_output(&mut self, output: MidiOutput) {
+        self.midi = Some(output);
+    }
+
+    /// Play the recording into `device` along with the playback.
+    #[cfg(target_os = "linux")]
+    pub fn replay_into(&mut self, device: ReplayDevice) {
+        if let Some(recording) = &self.recording {
+            self.replay = Some(ReplayPlayer::spawn(device, recording.clone()));
+        }
+    }
+
+    /// Repaint whenever a backend thread delivers touches, libinput events,
+    /// heatmap frames, log lines or errors.
+    pub fn repaint_on_data(&self, ctx: &egui::Context) {
+        let repaint = |ctx: &egui::Context| {
+            let ctx = ctx.clone();
+            move || ctx.request_repaint()
+        };
+        self.touch_rx.on_send(repaint(ctx));
+        if let Some(rx) = &self.libinput_rx {
+            rx.on_send(repaint(ctx));
+        }
+        if let Some(rx) = &self.heatmap_rx {
+            rx.on_send(repaint(ctx));
+        }
+        if let Some(rx) = &self.heatmap_retry {
+            rx.on_send(repaint(ctx));
+        }
+        if let Some(rx) = &self.log_rx {
+            rx.on_send(repaint(ctx));
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(script) = &self.script {
+            script.output().on_send(repaint(ctx));
+        }
+        self.workers.errors_rx.on_send(repaint(ctx));
+    }
+
+    /// Trails still fading, libinput values still decaying, playback
+    /// running or a countdown on screen.
+    fn animating(&self) -> bool {
+        !self.touch_history.is_empty()
+            || !self.libinput.is_settled()
+            || self.playback_playing
+            || matches!(self.jitter_test, Some(JitterTest::Running { .. }))
+            || matches!(
+                self.ab_test,
+                Some(
+                    AbTest::Switching { .. }
+                        | AbTest::RecordingA { .. }
+                        | AbTest::RecordingB { .. }
+                )
+            )
+            || self.recalibration.as_ref().is_some_and(|r| r.is_running())
+    }
+
+    /// Drop trails and ink; with `reset_extents`, also forget extents grown
+    /// from touches (e.g. after a palm inflated them).
+    /// Turn the touch view. Trails, ink and the dwell map are in view
+    /// coordinates, so they start over.
+    pub fn set_view_transform(&mut self, transform: ViewTransform) {
+        if transform == self.dims.transform {
+            return;
+        }
+        self.dims.set_transform(transform);
+        self.dwell = DwellMap::new(
+            self.dims.touchpad_max_extent_x,
+            self.dims.touchpad_max_extent_y,
+        );
+        self.current_touches = [TouchData::default(); MAX_TOUCH_POINTS];
+        self.clear_view(false);
+        self.save_settings();
+    }
+
+    fn clear_view(&mut self, reset_extents: bool) {
+        self.touch_history.clear();
+        self.ink.clear();
+        self.tracking.drops.clear();
+        self.swaps.swaps.clear();
+        self.outliers.recent.clear();
+        if reset_extents && !self.dims.extent_known {
+            self.dims.reset_learned_extent();
+            self.save_settings();
+        }
+    }
+
+    /// Apply an interpreted input event to everything that consumes them.
+    fn handle_libinput_event(&mut self, event: LibinputEvent) {
+        let finished = self.libinput.gestures_finished;
+        self.libinput.apply_event(&event);
+        if self.libinput.gestures_finished != finished {
+            let record = self.libinput.gesture_history.back();
+            if let Some(trigger) = record.and_then(Trigger::from_gesture) {
+                self.gesture_hooks.fire(trigger);
+            }
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(script) = &self.script {
+            script.on_libinput(self.clock.elapsed().as_secs_f64(), &event);
+        }
+        if let Some(ref mut export) = self.libinput_export {
+            if let Err(e) = export.write(&event) {
+                log_error!("libinput export error: {}", e);
+                self.libinput_export = None;
+            }
+        }
+        match event {
+            LibinputEvent::PointerButton {
+                button,
+                pressed,
+                time,
+            } => {
+                if pressed {
+                    self.tap_latency.on_button_press(time);
+                    // Tap-to-click maps one, two and three fingers to left,
+                    // right and middle
+                    let physical = self.buttons.left || self.buttons.right || self.buttons.middle;
+                    if let (false, Some(fingers)) = (physical, button.checked_sub(BTN_LEFT)) {
+                        if fingers < 3 {
+                            self.gesture_hooks.fire(Trigger::Tap {
+                                fingers: fingers + 1,
+                            });
+                        }
+                    }
+                }
+                self.tap_drag
+                    .on_button(button, pressed, self.buttons.left, time);
+            }
+            LibinputEvent::PointerMotion { dx, dy, .. } => {
+                self.trajectory.on_motion(dx, dy);
+                #[cfg(target_os = "linux")]
+                self.cursor.on_motion(dx, dy);
+                self.tap_drag.on_motion();
+                if let Some(test) = &mut self.palm_test {
+                    test.on_motion(dx, dy);
+                }
+            }
+            _ => {}
+        }
+        self.rates.libinput_events.add(1);
+    }
+
+    /// Feed the edge-swipe detector while its zones are shown.
+    fn update_edge_swipes(&mut self, touches: &[TouchData], now: Instant) {
+        if !self.show_edge_zones {
+            return;
+        }
+        let extents = (
+            self.dims.touchpad_max_extent_x,
+            self.dims.touchpad_max_extent_y,
+        );
+        if let Some(event) = self
+            .edge_swipes
+            .update(touches, extents, self.dims.resolution, now)
+        {
+            log_info!(target: "edges", "{}", event.label());
+        }
+    }
+
+    /// Record the current touches as the newest trail snapshot.
+    fn push_history(&mut self, time: f64) {
+        self.touch_history.push_front((time, self.current_touches));
+        self.touch_history.truncate(HISTORY_MAX);
+    }
+
+    /// Start writing libinput events to a timestamped file in the working
+    /// directory, or stop if already writing.
+    fn toggle_libinput_export(&mut self) {
+        if let Some(export) = self.libinput_export.take() {
+            log_info!("Stopped writing libinput events to: {}", export.path);
+            return;
+        }
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("tapview-libinput-{}.log", secs);
+        match LibinputExporter::new(&path) {
+            Ok(export) => {
+                log_info!("Writing libinput events to: {}", path);
+                self.libinput_export = Some(export);
+            }
+            Err(e) => log_error!("Failed to create {}: {}", path, e),
+        }
+    }
+
+    /// Write the last heatmap frames to a timestamped animated PNG in the
+    /// working directory.
+    fn export_heatmap(&self) {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("tapview-heatmap-{}.png", secs);
+        let written = std::fs::File::create(&path).and_then(|file| {
+            self.heatmap_history.write_apng(
+                std::io::BufWriter::new(file),
+                self.heatmap_export_frames,
+                self.heatmap_colormap,
+                self.heatmap_scale,
+            )
+        });
+        match written {
+            Ok(frames) => log_info!("Saved {} heatmap frames to: {}", frames, path),
+            Err(e) => log_error!("Failed to write {}: {}", path, e),
+        }
+    }
+
+    fn draw_ab_window(&mut self, ctx: &egui::Context) {
+        let Some(test) = &mut self.ab_test else {
+            return;
+        };
+        let mut open = true;
+        let heatmap = self.heatmap_rx.is_some() && self.registers.is_some();
+        let action = egui::Window::new("ALC A/B capture")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| render::draw_ab_test(ui, test, heatmap))
+            .and_then(|r| r.inner)
+            .flatten();
+        match action {
+            Some(AbTestAction::Start) => {
+                if let Some(on) = test.start() {
+                    self.send_driver_command(DriverCommand::SetAlc(on));
+                }
+            }
+            Some(AbTestAction::Save) => self.save_ab_result(),
+            Some(AbTestAction::Restart) => self.ab_test = Some(AbTest::default()),
+            None => {}
+        }
+        if !open {
+            self.stop_ab_test();
+        }
+    }
+
+    fn draw_recalibration_window(&mut self, ctx: &egui::Context) {
+        let Some(recalibration) = &mut self.recalibration else {
+            return;
+        };
+        let mut open = true;
+        let heatmap = self.heatmap_rx.is_some() && self.registers.is_some();
+        let texture = &mut self.recalibration_texture;
+        let action = egui::Window::new("Forced recalibration")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                render::draw_recalibration(ui, recalibration, texture, heatmap)
+            })
+            .and_then(|r| r.inner)
+            .flatten();
+        match action {
+            Some(RecalibrationAction::Confirm) => {
+                recalibration.confirm(Instant::now());
+                self.recalibration_texture.invalidate();
+            }
+            Some(RecalibrationAction::Cancel) => open = false,
+            Some(RecalibrationAction::Save) => self.save_recalibration(),
+            None => {}
+        }
+        if !open {
+            self.recalibration = None;
+        }
+    }
+
+    /// Close the ALC A/B capture, switching ALC back on if it was left off.
+    fn stop_ab_test(&mut self) {
+        if self
+            .ab_test
+            .take()
+            .is_some_and(|test| test.alc_may_be_off())
+        {
+            self.send_driver_command(DriverCommand::SetAlc(true));
+        }
+    }
+
+    /// Have the heatmap thread carry out `command` between frames.
+    fn send_driver_command(&self, command: DriverCommand) {
+        if let Some(registers) = &self.registers {
+            registers.send(command);
+        }
+    }
+
+    fn draw_jitter_window(&mut self, ctx: &egui::Context) {
+        let Some(test) = &self.jitter_test else {
+            return;
+        };
+        let mut open = true;
+        let resolution = self.dims.resolution;
+        let action = egui::Window::new("Jitter test")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| render::draw_jitter_test(ui, test, resolution))
+            .and_then(|r| r.inner)
+            .flatten();
+        match action {
+            Some(JitterTestAction::Save) => self.save_jitter_result(),
+            Some(JitterTestAction::Restart) => self.jitter_test = Some(JitterTest::Waiting),
+            None => {}
+        }
+        if !open {
+            self.jitter_test = None;
+        }
+    }
+
+    fn draw_linearity_window(&mut self, ctx: &egui::Context) {
+        let Some(test) = &self.linearity_test else {
+            return;
+        };
+        let mut open = true;
+        let unit = if self.dims.resolution.is_some() {
+            "mm"
+        } else {
+            "units"
+        };
+        let restart = egui::Window::new("Linearity test")
+            .open(&mut open)
+            .default_width(320.0)
+            .show(ctx, |ui| render::draw_linearity_test(ui, test, unit))
+            .and_then(|r| r.inner)
+            .unwrap_or(false);
+        if restart {
+            self.linearity_test = Some(LinearityTest::default());
+        }
+        if !open {
+            self.linearity_test = None;
+        }
+    }
+
+    fn new_edge_test(&self) -> EdgeTest {
+        EdgeTest::new((
+            self.dims.touchpad_max_extent_x,
+            self.dims.touchpad_max_extent_y,
+        ))
+    }
+
+    fn draw_edge_window(&mut self, ctx: &egui::Context) {
+        let Some(test) = &self.edge_test else {
+            return;
+        };
+        let mut open = true;
+        let resolution = self.dims.resolution;
+        let extent_known = self.dims.extent_known;
+        let reset = egui::Window::new("Edge test")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                render::draw_edge_test(ui, test, resolution, extent_known)
+            })
+            .and_then(|r| r.inner)
+            .unwrap_or(false);
+        if reset {
+            self.edge_test = Some(self.new_edge_test());
+        }
+        if !open {
+            self.edge_test = None;
+        }
+    }
+
+    fn draw_pressure_window(&mut self, ctx: &egui::Context) {
+        let Some(test) = &self.pressure_test else {
+            return;
+        };
+        let mut open = true;
+        let restart = egui::Window::new("Pressure test")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| render::draw_pressure_test(ui, test))
+            .and_then(|r| r.inner)
+            .unwrap_or(false);
+        if restart {
+            self.pressure_test = Some(PressureTest::Waiting);
+        }
+        if !open {
+            self.pressure_test = None;
+        }
+    }
+
+    fn draw_palm_test_window(&mut self, ctx: &egui::Context) {
+        let Some(test) = &self.palm_test else {
+            return;
+        };
+        let mut open = true;
+        let has_motion = self.libinput_rx.is_some();
+        let action = egui::Window::new("Palm rejection test")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| render::draw_palm_test(ui, test, has_motion))
+            .and_then(|r| r.inner)
+            .flatten();
+        match action {
+            Some(PalmTestAction::Next) => {
+                if let Some(test) = &mut self.palm_test {
+                    test.next_step();
+                }
+            }
+            Some(PalmTestAction::Restart) => self.palm_test = Some(PalmTest::default()),
+            None => {}
+        }
+        if !open {
+            self.palm_test = None;
+        }
+    }
+
+    fn draw_descriptor_window(&mut self, ctx: &egui::Context) {
+        if !self.show_descriptor {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("HID report descriptor")
+            .open(&mut open)
+            .default_width(520.0)
+            .default_height(480.0)
+            .show(ctx, |ui| match &self.descriptor {
+                Some(Ok(tree)) => render::draw_descriptor_tree(ui, tree),
+                Some(Err(e)) => {
+                    ui.colored_label(render::RED, e);
+                }
+                None => {
+                    ui.label("No HID device (playback or demo)");
+                }
+            });
+        self.show_descriptor = open;
+    }
+
+    fn draw_register_window(&mut self, ctx: &egui::Context) {
+        if !self.show_registers {
+            return;
+        }
+        let mut open = true;
+        let actions = egui::Window::new("Firmware registers")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| match &self.registers {
+                Some(registers) => {
+                    let watch = render::draw_register_watch(
+                        ui,
+                        &registers.registers(),
+                        registers.error().as_deref(),
+                        &mut self.register_input,
+                    );
+                    ui.separator();
+                    let dump = render::draw_register_dumps(
+                        ui,
+                        &mut self.register_dumps,
+                        registers.dump_progress(),
+                    );
+                    ui.separator();
+                    let recalibrate = ui
+                        .button("Recalibrate…")
+                        .on_hover_text("Force a new baseline with --recalibrate and compare")
+                        .clicked();
+                    (watch, dump, recalibrate)
+                }
+                None => {
+                    ui.label("No heatmap device to read registers from");
+                    (None, None, false)
+                }
+            })
+            .and_then(|r| r.inner);
+        if let (Some(registers), Some((watch, dump, recalibrate))) = (&self.registers, actions) {
+            if recalibrate && !self.recalibration.as_ref().is_some_and(|r| r.is_running()) {
+                self.recalibration = Some(Recalibration::Confirming);
+            }
+            match watch {
+                Some(RegisterWatchAction::Add(register)) => {
+                    registers.add(register);
+                    self.register_input.clear();
+                }
+                Some(RegisterWatchAction::Remove(register)) => registers.remove(register),
+                None => {}
+            }
+            match dump {
+                Some(RegisterDumpAction::Dump) => registers.request_dump(),
+                Some(RegisterDumpAction::Refresh) => self.register_dumps.refresh(Path::new(".")),
+                Some(RegisterDumpAction::Compare) => self.register_dumps.compare(),
+                None => {}
+            }
+        }
+        self.show_registers = open;
+    }
+
+    /// Write a finished register dump to a timestamped file in the working
+    /// directory and compare it with the one before.
+    fn save_register_dump(&mut self, dump: RegisterDump) {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let dir = Path::new(".");
+        let path = DumpComparison::path(dir, secs);
+        match std::fs::write(&path, dump.to_text()) {
+            Ok(()) => {
+                log_info!(
+                    "Saved {} registers to: {}",
+                    dump.values.len(),
+                    path.display()
+                );
+                self.register_dumps.saved(dir, path);
+            }
+            Err(e) => log_error!("Failed to write {}: {}", path.display(), e),
+        }
+    }
+
+    fn draw_orientation_window(&mut self, ctx: &egui::Context) {
+        let Some(test) = &self.orientation_test else {
+            return;
+        };
+        let mut open = true;
+        let action = egui::Window::new("Orientation calibration")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| render::draw_orientation_test(ui, test))
+            .and_then(|r| r.inner)
+            .flatten();
+        match action {
+            Some(OrientationTestAction::Apply) => {
+                if let Some(transform) = test.result {
+                    self.set_view_transform(transform);
+                }
+                self.orientation_test = None;
+            }
+            Some(OrientationTestAction::Restart) => {
+                self.orientation_test = Some(OrientationTest::default());
+            }
+            None => {}
+        }
+        if !open {
+            self.orientation_test = None;
+        }
+    }
+
+    fn draw_resolution_window(&mut self, ctx: &egui::Context) {
+        let Some(test) = &mut self.resolution_test else {
+            return;
+        };
+        let mut open = true;
+        let advertised = self.dims.resolution.map(|r| self.dims.transform.extents(r));
+        let hwdb_match = self.hwdb_match.as_deref();
+        let restart = egui::Window::new("Resolution test")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                render::draw_resolution_test(ui, test, advertised, hwdb_match)
+            })
+            .and_then(|r| r.inner)
+            .unwrap_or(false);
+        if restart {
+            self.resolution_test = Some(ResolutionTest::default());
+        }
+        if !open {
+            self.resolution_test = None;
+        }
+    }
+
+    /// The script's status and metrics. Closing the window stops the script.
+    #[cfg(feature = "scripting")]
+    fn draw_script_window(&mut self, ctx: &egui::Context) {
+        let Some(script) = &mut self.script else {
+            return;
+        };
+        let mut open = true;
+        let clear = egui::Window::new("Script")
+            .open(&mut open)
+            .default_width(260.0)
+            .show(ctx, |ui| render::draw_script_panel(ui, script))
+            .and_then(|r| r.inner)
+            .unwrap_or(false);
+        if clear {
+            script.annotations.clear();
+        }
+        if !open {
+            self.script = None;
+        }
+    }
+
+    /// Write the frames before and after a forced recalibration, and their
+    /// difference, to a timestamped text file in the working directory.
+    fn save_recalibration(&self) {
+        let Some(Recalibration::Done(comparison)) = &self.recalibration else {
+            return;
+        };
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("tapview-recalibration-{}.txt", secs);
+        match std::fs::write(&path, comparison.to_text()) {
+            Ok(()) => log_info!("Saved recalibration capture to: {}", path),
+            Err(e) => log_error!("Failed to write {}: {}", path, e),
+        }
+    }
+
+    /// Write the finished ALC A/B comparison to a timestamped text file in the
+    /// working directory.
+    fn save_ab_result(&self) {
+        let Some(AbTest::Done { a, b }) = &self.ab_test else {
+            return;
+        };
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("tapview-alc-ab-{}.txt", secs);
+        match std::fs::write(&path, crate::heatmap_ab::report(a, b) + "\n") {
+            Ok(()) => log_info!("Saved ALC A/B comparison to: {}", path),
+            Err(e) => log_error!("Failed to write {}: {}", path, e),
+        }
+    }
+
+    /// Write the finished jitter test to a timestamped JSON file in the
+    /// working directory.
+    fn save_jitter_result(&self) {
+        let Some(JitterTest::Done(result)) = &self.jitter_test else {
+            return;
+        };
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("tapview-jitter-{}.json", secs);
+        match std::fs::write(&path, result.to_json(self.dims.resolution) + "\n") {
+            Ok(()) => log_info!("Saved jitter test to: {}", path),
+            Err(e) => log_error!("Failed to write {}: {}", path, e),
+        }
+    }
+
+    /// Copy the current UI choices into the per-device settings and write them out.
+    fn save_settings(&mut self) {
+        if let Some(store) = &mut self.settings {
+            *store.keys_mut() = self.keys.clone();
+            store.app_mut().theme = self.theme;
+            let device = store.device_mut();
+            device.mm_grid = self.show_mm_grid;
+            device.pressure_scaling = self.pressure_scaling;
+            device.slot_table = self.show_slot_table;
+            device.rate_hud = self.show_rate_hud;
+            device.dwell_map = self.show_dwell;
+            device.edge_zones = self.show_edge_zones;
+            device.edge_band_mm = self.edge_swipes.band_mm;
+            device.log_console = self.show_log;
+            device.slot_plots = self.show_slot_plots;
+            device.cursor_view = self.show_cursor;
+            device.notifications = self.notifier.enabled;
+            device.click_zones = self.click_zones;
+            device.trail_ms = self.trail_ms;
+            device.heatmap_orientation = self.heatmap_orientation;
+            device.heatmap_colormap = self.heatmap_colormap;
+            device.heatmap_smooth = self.heatmap_smooth;
+            device.heatmap_scale = self.heatmap_scale;
+            device.view_transform = self.dims.transform;
+            if !self.dims.extent_known {
+                device.learned_extents = Some(self.dims.device_extents());
+            }
+            if self.window_size.is_some() {
+                device.window_size = self.window_size;
+            }
+            if self.window_pos.is_some() {
+                device.window_pos = self.window_pos;
+            }
+            if self.libinput_panel_width.is_some() {
+                device.libinput_panel_width = self.libinput_panel_width;
+            }
+            if self.heatmap_panel_height.is_some() {
+                device.heatmap_panel_height = self.heatmap_panel_height;
+            }
+            if let Err(e) = store.save() {
+                log_error!(target: "settings", "failed to save: {}", e);
+            }
+        }
+    }
+
+    /// Top of the pressure range, or the highest pressure seen so far when
+    /// the device doesn't say.
+    fn pressure_max(&self) -> i32 {
+        self.pressure_range
+            .map(|(_, max)| max)
+            .unwrap_or(self.pressure_seen_max)
+    }
+
+    /// Pressure as 0.0..=1.0 against the device range (or the highest value
+    /// seen so far), or None when pressure scaling is off or unavailable.
+    fn normalized_pressure(&self, touch: &TouchData) -> Option<f32> {
+        if !self.pressure_scaling {
+            return None;
+        }
+        let (min, max) = match self.pressure_range {
+            Some(range) => range,
+            None if self.pressure_seen_max > 0 => (0, self.pressure_seen_max),
+            None => return None,
+        };
+        Some((touch.pressure - min) as f32 / (max - min) as f32)
+    }
+
+    fn handle_action(&mut self, action: Action, is_playback: bool) {
+        match action {
+            Action::Grab if !is_playback && !self.grabbed => {
+                let _ = self.grab_tx.send(GrabCommand::Grab);
+                self.grabbed = true;
+            }
+            Action::Ungrab if !is_playback && self.grabbed => {
+                let _ = self.grab_tx.send(GrabCommand::Ungrab);
+                self.grabbed = false;
+            }
+            Action::ToggleInk => self.ink_enabled = !self.ink_enabled,
+            Action::ClearInk => self.ink.clear(),
+            Action::ToggleSlotTable => {
+                self.show_slot_table = !self.show_slot_table;
+                self.save_settings();
+            }
+            Action::ToggleRates => {
+                self.show_rate_hud = !self.show_rate_hud;
+                self.save_settings();
+            }
+            Action::ToggleDwell => {
+                self.show_dwell = !self.show_dwell;
+                self.save_settings();
+            }
+            Action::ToggleLog => {
+                self.show_log = !self.show_log;
+                self.save_settings();
+            }
+            Action::TogglePlots => {
+                self.show_slot_plots = !self.show_slot_plots;
+                self.save_settings();
+            }
+            Action::JitterTest if !is_playback => {
+                self.jitter_test = match self.jitter_test {
+                    Some(_) => None,
+                    None => Some(JitterTest::Waiting),
+                };
+            }
+            Action::AlcAbTest if !is_playback => match self.ab_test {
+                Some(_) => self.stop_ab_test(),
+                None => self.ab_test = Some(AbTest::default()),
+            },
+            Action::LinearityTest if !is_playback => {
+                self.linearity_test = match self.linearity_test {
+                    Some(_) => None,
+                    None => Some(LinearityTest::default()),
+                };
+            }
+            Action::EdgeTest if !is_playback => {
+                self.edge_test = match self.edge_test {
+                    Some(_) => None,
+                    None => Some(self.new_edge_test()),
+                };
+            }
+            Action::PressureTest if !is_playback => {
+                self.pressure_test = match self.pressure_test {
+                    Some(_) => None,
+                    None => Some(PressureTest::Waiting),
+                };
+            }
+            Action::PalmTest if !is_playback => {
+                self.palm_test = match self.palm_test {
+                    Some(_) => None,
+                    None => Some(PalmTest::default()),
+                };
+            }
+            Action::ResolutionTest if !is_playback => {
+                self.resolution_test = match self.resolution_test {
+                    Some(_) => None,
+                    None => Some(ResolutionTest::default()),
+                };
+            }
+            Action::DescriptorTree => self.show_descriptor = !self.show_descriptor,
+            Action::RegisterWatch => {
+                self.show_registers = !self.show_registers;
+                if self.show_registers {
+                    self.register_dumps.refresh(Path::new("."));
+                }
+            }
+            Action::OrientationTest if !is_playback => {
+                self.orientation_test = match self.orientation_test {
+                    Some(_) => None,
+                    None => Some(OrientationTest::default()),
+                };
+            }
+            Action::ClearView => self.clear_view(false),
+            Action::ResetExtents => self.clear_view(true),
+            Action::PlayPause if is_playback => {
+                self.playback_playing = !self.playback_playing;
+                // If at end and pressing play, restart
+                if self.playback_playing {
+                    let duration = self.recording.as_ref().unwrap().duration_secs();
+                    if self.playback_time >= duration {
+                        self.playback_time = 0.0;
+                    }
+                }
+            }
+            Action::StepBack if is_playback => {
+                self.playback_time = (self.playback_time - 0.1).max(0.0);
+            }
+            Action::StepForward if is_playback => {
+                let duration = self.recording.as_ref().unwrap().duration_secs();
+                self.playback_time = (self.playback_time + 0.1).min(duration);
+            }
+            _ => {}
+        }
+    }
+
+    fn draw_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.settings_open;
+        let mut changed = false;
+        let mut transform = self.dims.transform;
+        let mut calibrate = false;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.heading("Display");
+                egui::ComboBox::from_label("Theme")
+                    .selected_text(self.theme.label())
+                    .show_ui(ui, |ui| {
+                        for choice in ThemeChoice::ALL {
+                            changed |= ui
+                                .selectable_value(&mut self.theme, choice, choice.label())
+                                .changed();
+                        }
+                    });
+                let trail = ui.add(
+                    egui::Slider::new(&mut self.trail_ms, 0..=2000)
+                        .suffix(" ms")
+                        .text("Trail length"),
+                );
+                // Save once the drag ends rather than on every step
+                changed |= trail.drag_stopped() || (trail.changed() && !trail.dragged());
+                if ui
+                    .checkbox(&mut self.always_on_top, "Always on top")
+                    .changed()
+                {
+                    let level = if self.always_on_top {
+                        egui::WindowLevel::AlwaysOnTop
+                    } else {
+                        egui::WindowLevel::Normal
+                    };
+                    ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+                }
+                ui.add_enabled_ui(self.dims.resolution.is_some(), |ui| {
+                    changed |= ui
+                        .checkbox(&mut self.show_mm_grid, "Millimeter grid")
+                        .on_disabled_hover_text("Device does not report its resolution")
+                        .changed();
+                });
+                changed |= ui
+                    .checkbox(&mut self.pressure_scaling, "Scale contacts by pressure")
+                    .changed();
+                ui.checkbox(&mut self.ink_enabled, "Ink mode");
+                changed |= ui
+                    .checkbox(&mut self.show_slot_table, "Slot table")
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut self.show_rate_hud, "Frame/event rates")
+                    .changed();
+                changed |= ui.checkbox(&mut self.show_log, "Log console").changed();
+                changed |= ui
+                    .checkbox(&mut self.show_slot_plots, "Pressure/size plots")
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut self.show_cursor, "Cursor position")
+                    .on_hover_text(CURSOR_HINT)
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut self.notifier.enabled, "Desktop notifications")
+                    .on_hover_text("When the device fails or a recording ends")
+                    .changed();
+                ui.horizontal(|ui| {
+                    changed |= ui.checkbox(&mut self.show_dwell, "Dwell map").changed();
+                    if ui.button("Reset").clicked() {
+                        self.dwell.clear();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .checkbox(&mut self.show_edge_zones, "Edge-swipe zones")
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.edge_swipes.band_mm)
+                                .range(0.5..=30.0)
+                                .speed(0.1)
+                                .suffix(" mm"),
+                        )
+                        .on_hover_text("Width of the zones along each edge")
+                        .changed();
+                });
+                ui.add_enabled_ui(self.buttonpad, |ui| {
+                    egui::ComboBox::from_label("Click zones")
+                        .selected_text(self.click_zones.label())
+                        .show_ui(ui, |ui| {
+                            for zones in ClickZones::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut self.click_zones, zones, zones.label())
+                                    .changed();
+                            }
+                        })
+                        .response
+                        .on_disabled_hover_text("Device is not a buttonpad");
+                });
+
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("Rotate")
+                        .selected_text(transform.rotation.label())
+                        .show_ui(ui, |ui| {
+                            for rotation in Rotation::ALL {
+                                ui.selectable_value(
+                                    &mut transform.rotation,
+                                    rotation,
+                                    rotation.label(),
+                                );
+                            }
+                        });
+                    ui.checkbox(&mut transform.flip_x, "Invert X");
+                    ui.checkbox(&mut transform.flip_y, "Invert Y");
+                    if ui.button("Calibrate…").clicked() {
+                        calibrate = true;
+                    }
+                });
+
+                ui.separator();
+                ui.heading("Heatmap");
+                egui::ComboBox::from_label("Colormap")
+                    .selected_text(self.heatmap_colormap.label())
+                    .show_ui(ui, |ui| {
+                        for colormap in Colormap::ALL {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.heatmap_colormap,
+                                    colormap,
+                                    colormap.label(),
+                                )
+                                .changed();
+                        }
+                    });
+                changed |= ui
+                    .checkbox(
+                        &mut self.heatmap_smooth,
+                        "Smooth (interpolate between cells)",
+                    )
+                    .changed();
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .checkbox(&mut self.heatmap_scale.log, "Log scale")
+                        .on_hover_text("Bring out weak signals such as a hovering finger")
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::Slider::new(&mut self.heatmap_scale.gamma, 0.2..=3.0)
+                                .logarithmic(true)
+                                .text("Gamma"),
+                        )
+                        .on_hover_text("Below 1 brightens weak signals, above 1 darkens them")
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    changed |= render::draw_orientation_toggles(ui, &mut self.heatmap_orientation);
+                });
+                ui.add_enabled_ui(!self.heatmap_history.is_empty(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Last");
+                        ui.add(
+                            egui::DragValue::new(&mut self.heatmap_export_frames)
+                                .range(1..=HeatmapHistory::CAPACITY),
+                        );
+                        ui.label(format!("of {} frames", self.heatmap_history.len()));
+                        if ui
+                            .button("Export APNG")
+                            .on_hover_text(
+                                "Save them as an animated PNG, with the colormap applied",
+                            )
+                            .clicked()
+                        {
+                            self.export_heatmap();
+                        }
+                    });
+                });
+
+                ui.separator();
+                ui.heading("Hotkeys");
+                egui::Grid::new("hotkeys").num_columns(3).show(ui, |ui| {
+                    for action in Action::ALL {
+                        ui.label(action.label());
+                        let text = if self.capturing == Some(action) {
+                            "Press a key…".to_string()
+                        } else {
+                            self.keys.describe(action)
+                        };
+                        if ui.button(text).clicked() {
+                            self.capturing = Some(action);
+                        }
+                        if ui.small_button("✖").on_hover_text("Unbind").clicked() {
+                            self.keys.set(action, None);
+                            changed = true;
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        self.settings_open = open;
+        self.set_view_transform(transform);
+        if calibrate {
+            self.orientation_test = Some(OrientationTest::default());
+        }
+
+        if let Some(action) = self.capturing {
+            let pressed = ctx.input(|i| {
+                i.events.iter().find_map(|e| match e {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some(Binding {
+                        key: *key,
+                        shift: modifiers.shift,
+                    }),
+                    _ => None,
+                })
+            });
+            if let Some(binding) = pressed {
+                self.keys.set(action, Some(binding));
+                self.capturing = None;
+                changed = true;
+            } else if !self.settings_open {
+                self.capturing = None;
+            }
+        }
+
+        if changed {
+            ctx.set_theme(self.theme);
+            self.save_settings();
+        }
+    }
+
+    fn draw_playback_panel(&mut self, ctx: &egui::Context) {
+        let duration = self.recording.as_ref().unwrap().duration_secs();
+
+        egui::TopBottomPanel::bottom("playback_panel")
+            .exact_height(48.0)
+            .show(ctx, |ui| {
+                ui.horizontal_centered(|ui| {
+                    // Play/Pause button
+                    let label = if self.playback_playing {
+                        "Pause"
+                    } else {
+                        "Play"
+                    };
+                    if ui.button(label).clicked() {
+                        self.playback_playing = !self.playback_playing;
+                        if self.playback_playing && self.playback_time >= duration {
+                            self.playback_time = 0.0;
+                        }
+                    }
 
                     ui.separator();
 
@@ -400,3 +2712,34 @@ impl TapviewApp {
             });
     }
 }
+
+/// Show the heatmap in a window of its own: a native one where the backend
+/// supports several, an egui window inside the main one otherwise. Returns
+/// what `draw` returned, if it ran, and whether the window was closed.
+fn draw_heatmap_window(
+    ctx: &egui::Context,
+    mut draw: impl FnMut(&mut egui::Ui) -> bool,
+) -> (bool, bool) {
+    let viewport = egui::ViewportBuilder::default()
+        .with_title("Tapview - Heatmap")
+        .with_inner_size([640.0, 420.0]);
+    ctx.show_viewport_immediate(
+        egui::ViewportId::from_hash_of("heatmap_window"),
+        viewport,
+        |ctx, class| {
+            if class == egui::ViewportClass::Embedded {
+                let mut open = true;
+                let changed = egui::Window::new("Heatmap")
+                    .open(&mut open)
+                    .default_size([480.0, 320.0])
+                    .show(ctx, |ui| draw(ui))
+                    .and_then(|response| response.inner)
+                    .unwrap_or(false);
+                (changed, !open)
+            } else {
+                let changed = egui::CentralPanel::default().show(ctx, |ui| draw(ui)).inner;
+                (changed, ctx.input(|i| i.viewport().close_requested()))
+            }
+        },
+    )
+}
@@ -0,0 +1,111 @@
+//! Fatal errors from device setup and the backend threads, shown in a banner
+//! with likely fixes instead of only on stderr.
+
+use std::fmt;
+use tapview_core::channel::BoundedSender;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Input,
+    Libinput,
+    Heatmap,
+    Pen,
+}
+
+impl Backend {
+    pub fn label(self) -> &'static str {
+        match self {
+            Backend::Input => "Touch input",
+            Backend::Libinput => "libinput",
+            Backend::Heatmap => "Heatmap",
+            Backend::Pen => "Pen",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BackendError {
+    pub backend: Backend,
+    pub message: String,
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.backend.label(), self.message)
+    }
+}
+
+impl BackendError {
+    pub fn new(backend: Backend, message: impl Into<String>) -> Self {
+        Self {
+            backend,
+            message: message.into(),
+        }
+    }
+
+    /// Suggested fixes, most likely first.
+    pub fn hints(&self) -> Vec<&'static str> {
+        let mut hints = Vec::new();
+        if self.message.contains("Permission denied") {
+            #[cfg(target_os = "linux")]
+            hints.push(
+                "Run `tapview setup-permissions` once to allow access from your desktop session",
+            );
+            #[cfg(target_os = "linux")]
+            hints.push(match self.backend {
+                Backend::Heatmap => {
+                    "Run as root, or add your user to the hidraw group \
+                     (sudo usermod -aG hidraw $USER) and log in again"
+                }
+                _ => {
+                    "Run as root, or add your user to the input group \
+                     (sudo usermod -aG input $USER) and log in again"
+                }
+            });
+            #[cfg(target_os = "linux")]
+            hints.push("From a text console, --logind can take the device from systemd-logind");
+            #[cfg(target_os = "windows")]
+            hints.push("Run tapview as administrator");
+        }
+        if self.message.contains("No such file") || self.message.contains("No such device") {
+            hints.push("The device was unplugged or renamed; reconnect it and restart tapview");
+        }
+        if self.backend == Backend::Heatmap {
+            hints.push("Run without --heatmap to only use the heatmap when it's found");
+        }
+        if self.backend == Backend::Libinput {
+            hints.push("Run with --no-libinput to skip the libinput view");
+        }
+        if self.backend == Backend::Pen {
+            hints.push("Run with --no-pen to show touch only");
+        }
+        hints
+    }
+}
+
+/// Log `error` and queue it for the banner.
+pub fn report(errors: &BoundedSender<BackendError>, error: BackendError) {
+    log_error!("{}", error);
+    let _ = errors.send(error);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_fixes_by_error() {
+        let denied = BackendError::new(
+            Backend::Input,
+            "Failed to open device: /dev/input/event5: Permission denied (os error 13)",
+        );
+        assert!(!denied.hints().is_empty());
+
+        let gone = BackendError::new(Backend::Input, "Input error: No such device (os error 19)");
+        assert!(gone.hints()[0].contains("unplugged"));
+
+        let heatmap = BackendError::new(Backend::Heatmap, "no sibling hidraw device");
+        assert!(heatmap.hints()[0].contains("--heatmap"));
+        assert_eq!(heatmap.to_string(), "Heatmap: no sibling hidraw device");
+    }
+}
@@ -0,0 +1,130 @@
+//! How hard clicks are on a clickpad: pressure and touch_major of the
+//! firmest contact each time the physical button goes down, kept as a
+//! distribution.
+//!
+//! A clickpad's dome needs a certain force, while the pressure axis is only
+//! the contact's capacitance and often tops out long before that. Each click
+//! therefore also notes whether the touch had already reached the top of the
+//! pressure range before the button went down; if most have, pressure can't
+//! tell a resting finger from one about to click.
+//!
+//! Haptic touchpads have no dome: the firmware simulates a click once the
+//! force crosses a threshold, so the pressure at the click is that threshold
+//! and the low end of the distribution estimates it.
+
+/// Clicks kept.
+const SAMPLES_MAX: usize = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClickSample {
+    pub pressure: i32,
+    pub touch_major: i32,
+    /// Pressure hit the top of its range during this touch before the click
+    pub saturated: bool,
+}
+
+#[derive(Default)]
+pub struct ClickPressure {
+    pub samples: Vec<ClickSample>,
+    /// Firmest contact's pressure right now, for the live bar
+    pub current: Option<i32>,
+    button: bool,
+    /// Saturated since the last time no finger was down
+    saturated: bool,
+}
+
+impl ClickPressure {
+    /// Feed one report: the firmest contact's pressure and touch_major, if
+    /// any finger is down, the physical button and the top of the pressure
+    /// range (0 when unknown, which leaves saturation unchecked).
+    pub fn update(&mut self, contact: Option<(i32, i32)>, button: bool, pressure_max: i32) {
+        self.current = contact.map(|(pressure, _)| pressure);
+        match contact {
+            Some((pressure, touch_major)) => {
+                if button && !self.button {
+                    if self.samples.len() == SAMPLES_MAX {
+                        self.samples.remove(0);
+                    }
+                    self.samples.push(ClickSample {
+                        pressure,
+                        touch_major,
+                        saturated: self.saturated,
+                    });
+                }
+                self.saturated |= pressure_max > 0 && pressure >= pressure_max;
+            }
+            None => self.saturated = false,
+        }
+        self.button = button;
+    }
+
+    /// Nearest-rank percentile (0.0..=1.0) of the click pressures.
+    pub fn percentile(&self, p: f32) -> Option<i32> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<i32> = self.samples.iter().map(|s| s.pressure).collect();
+        sorted.sort_unstable();
+        let rank = ((p * sorted.len() as f32).ceil() as usize).clamp(1, sorted.len());
+        Some(sorted[rank - 1])
+    }
+
+    /// Estimated click threshold: the pressure nearly all clicks reached.
+    /// Saturated clicks are left out, they only show where the range ends.
+    pub fn threshold(&self) -> Option<i32> {
+        let mut sorted: Vec<i32> = self
+            .samples
+            .iter()
+            .filter(|s| !s.saturated)
+            .map(|s| s.pressure)
+            .collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_unstable();
+        Some(sorted[(sorted.len() - 1) / 10])
+    }
+
+    pub fn saturated_clicks(&self) -> usize {
+        self.samples.iter().filter(|s| s.saturated).count()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_pressure_at_each_click() {
+        let mut clicks = ClickPressure::default();
+        clicks.update(Some((40, 10)), false, 255);
+        clicks.update(Some((90, 12)), true, 255);
+        // Held down: still one click
+        clicks.update(Some((120, 14)), true, 255);
+        assert_eq!(
+            clicks.samples,
+            [ClickSample {
+                pressure: 90,
+                touch_major: 12,
+                saturated: false
+            }]
+        );
+
+        // Pressure maxes out before the dome goes
+        clicks.update(Some((255, 20)), false, 255);
+        clicks.update(Some((255, 20)), true, 255);
+        assert!(clicks.samples[1].saturated);
+        // Lifting resets the saturation flag
+        clicks.update(None, false, 255);
+        clicks.update(Some((60, 10)), true, 255);
+        assert!(!clicks.samples[2].saturated);
+
+        assert_eq!(clicks.saturated_clicks(), 1);
+        assert_eq!(clicks.percentile(0.5), Some(90));
+        assert_eq!(clicks.threshold(), Some(60));
+    }
+}
@@ -2,13 +2,13 @@ use super::{
     AxisPhysicalInfo, ConfigBackend, ConfigValues, PtpConfig, PtpFeatures, TouchpadPhysicalSize,
     ValueRange,
 };
-use crate::heatmap::discovery::find_sibling_hidraw;
-use crate::heatmap::hidraw::HidrawDevice;
-use crate::heatmap::HidDevice;
 use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::path::Path;
+use tapview_core::heatmap::discovery::find_sibling_hidraw;
+use tapview_core::heatmap::hidraw::HidrawDevice;
+use tapview_core::heatmap::HidDevice;
 
 // PTP usage IDs on Usage Page 0x0D (Digitizer)
 const USAGE_INPUT_MODE: u16 = 0x0052;
@@ -516,7 +516,7 @@ pub fn discover(evdev_path: &Path) -> Option<PtpConfig> {
     let hidraw_path = match find_sibling_hidraw(evdev_path) {
         Ok(p) => p,
         Err(e) => {
-            eprintln!("config: failed to find hidraw device: {}", e);
+            log_warn!(target: "config", "failed to find hidraw device: {}", e);
             return None;
         }
     };
@@ -526,7 +526,7 @@ pub fn discover(evdev_path: &Path) -> Option<PtpConfig> {
     let desc = match fs::read(&desc_path) {
         Ok(d) => d,
         Err(e) => {
-            eprintln!("config: failed to read report descriptor: {}", e);
+            log_warn!(target: "config", "failed to read report descriptor: {}", e);
             return None;
         }
     };
@@ -564,12 +564,12 @@ pub fn discover(evdev_path: &Path) -> Option<PtpConfig> {
     let device = match HidrawDevice::open(&hidraw_path) {
         Ok(d) => d,
         Err(e) => {
-            eprintln!("config: failed to open hidraw device: {}", e);
+            log_warn!(target: "config", "failed to open hidraw device: {}", e);
             return None;
         }
     };
 
-    eprintln!("config: found PTP features on {}", hidraw_path.display());
+    log_info!(target: "config", "found PTP features on {}", hidraw_path.display());
 
     let mut backend = LinuxConfigBackend {
         device,
@@ -1,9 +1,9 @@
 use super::{ConfigBackend, ConfigValues, PtpConfig, PtpFeatures, ValueRange};
-use crate::heatmap::discovery::{extract_parent_device_id, pcwstr_to_string};
-use crate::heatmap::windows_hid::WinHidDevice;
-use crate::heatmap::HidDevice;
 use std::io;
 use std::path::Path;
+use tapview_core::heatmap::discovery::{extract_parent_device_id, pcwstr_to_string};
+use tapview_core::heatmap::windows_hid::WinHidDevice;
+use tapview_core::heatmap::HidDevice;
 use windows::core::PCWSTR;
 use windows::Win32::Devices::DeviceAndDriverInstallation::*;
 use windows::Win32::Devices::HumanInterfaceDevice::*;
@@ -429,7 +429,7 @@ unsafe fn check_hid_device_for_config(
         }
     };
 
-    eprintln!("config: found PTP features on {}", device_path);
+    log_info!(target: "config", "found PTP features on {}", device_path);
 
     let mut backend = WindowsConfigBackend {
         device,
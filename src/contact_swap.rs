@@ -0,0 +1,148 @@
+//! Detects firmware swapping two contacts' tracking IDs, typically as the
+//! fingers cross: each slot's position teleports to where the other finger
+//! was heading.
+//!
+//! Per pair of contacts that kept their IDs since the last update, the
+//! positions predicted from each contact's last velocity are matched
+//! against the new positions both ways. If the crossed assignment explains
+//! the motion far better than the reported one, and the reported one needs
+//! a real jump, the IDs were swapped.
+
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// The crossed assignment has to cost less than this share of the reported one...
+const SWAP_RATIO: f32 = 0.5;
+/// ...and the reported one has to jump at least this share of the touchpad width.
+const MIN_JUMP: f32 = 0.03;
+/// Swap positions kept for marking.
+const SWAPS_MAX: usize = 200;
+
+#[derive(Clone, Copy)]
+struct Contact {
+    tracking_id: i32,
+    pos: (f32, f32),
+    velocity: (f32, f32),
+}
+
+impl Contact {
+    fn predicted(&self) -> (f32, f32) {
+        (self.pos.0 + self.velocity.0, self.pos.1 + self.velocity.1)
+    }
+}
+
+fn dist(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+#[derive(Default)]
+pub struct SwapDetector {
+    slots: [Option<Contact>; MAX_TOUCH_POINTS],
+    /// Where swaps happened, oldest first
+    pub swaps: Vec<(i32, i32)>,
+    pub swap_count: usize,
+}
+
+impl SwapDetector {
+    /// Feed the current touches. `width` is the touchpad's X extent in device
+    /// units.
+    pub fn update(&mut self, touches: &[TouchData; MAX_TOUCH_POINTS], width: f32) {
+        let now: Vec<Option<(i32, (f32, f32))>> = touches
+            .iter()
+            .map(|t| {
+                t.used
+                    .then_some((t.tracking_id, (t.position_x as f32, t.position_y as f32)))
+            })
+            .collect();
+        let prev = self.slots;
+        let continued = |slot: usize| match (prev[slot], now[slot]) {
+            (Some(prev), Some((id, pos))) if prev.tracking_id == id => Some((prev, pos)),
+            _ => None,
+        };
+
+        let mut swapped = [false; MAX_TOUCH_POINTS];
+        for a in 0..MAX_TOUCH_POINTS {
+            for b in a + 1..MAX_TOUCH_POINTS {
+                if swapped[a] || swapped[b] {
+                    continue;
+                }
+                let (Some((prev_a, pos_a)), Some((prev_b, pos_b))) = (continued(a), continued(b))
+                else {
+                    continue;
+                };
+                let (pa, pb) = (prev_a.predicted(), prev_b.predicted());
+                let reported = dist(pa, pos_a) + dist(pb, pos_b);
+                let crossed = dist(pa, pos_b) + dist(pb, pos_a);
+                if reported > width * MIN_JUMP && crossed < reported * SWAP_RATIO {
+                    swapped[a] = true;
+                    swapped[b] = true;
+                    self.swap_count += 1;
+                    if self.swaps.len() == SWAPS_MAX {
+                        self.swaps.remove(0);
+                    }
+                    let mid = ((pos_a.0 + pos_b.0) / 2.0, (pos_a.1 + pos_b.1) / 2.0);
+                    self.swaps.push((mid.0 as i32, mid.1 as i32));
+                }
+            }
+        }
+
+        for (slot, current) in now.iter().enumerate() {
+            self.slots[slot] = current.map(|(tracking_id, pos)| {
+                // A swap's jump isn't real motion
+                let velocity = match continued(slot) {
+                    Some((prev, _)) if !swapped[slot] => (pos.0 - prev.pos.0, pos.1 - prev.pos.1),
+                    _ => (0.0, 0.0),
+                };
+                Contact {
+                    tracking_id,
+                    pos,
+                    velocity,
+                }
+            });
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(tracking_id: i32, x: i32, y: i32) -> TouchData {
+        TouchData {
+            used: true,
+            tracking_id,
+            position_x: x,
+            position_y: y,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_swapped_ids_while_crossing() {
+        let mut detector = SwapDetector::default();
+        let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+        // Two fingers passing each other in X on different rows
+        for (a, b) in [(200, 800), (300, 700), (400, 600), (500, 500)] {
+            touches[0] = touch(1, a, 200);
+            touches[1] = touch(2, b, 400);
+            detector.update(&touches, 1000.0);
+        }
+        assert_eq!(detector.swap_count, 0);
+
+        // As they cross, each ID continues along the other finger's path
+        touches[0] = touch(1, 400, 400);
+        touches[1] = touch(2, 600, 200);
+        detector.update(&touches, 1000.0);
+        assert_eq!(detector.swap_count, 1);
+        assert_eq!(detector.swaps, vec![(500, 300)]);
+
+        // Carrying on from there isn't another swap
+        touches[0] = touch(1, 300, 400);
+        touches[1] = touch(2, 700, 200);
+        detector.update(&touches, 1000.0);
+        assert_eq!(detector.swap_count, 1);
+    }
+}
@@ -0,0 +1,108 @@
+//! Where the system pointer is, for a small screen widget drawn next to the
+//! touch view with the first contact mapped onto the same screen, so pointer
+//! jumps and absolute/relative mapping bugs show up against the finger.
+//!
+//! Windows is asked for the cursor position. Linux has no such query under
+//! Wayland, so there the pointer is followed from libinput's motion events,
+//! starting in the middle of the screen and stopping at its edges like the
+//! real one does; it drifts from the real pointer whenever something else
+//! moves that.
+
+use std::collections::VecDeque;
+
+/// Past positions kept for the trail.
+const TRAIL_MAX: usize = 64;
+
+pub struct CursorView {
+    /// Screen origin and size, in the same units as `pos`
+    pub screen: (f64, f64, f64, f64),
+    pub pos: Option<(f64, f64)>,
+    /// Recent positions, newest last
+    pub trail: VecDeque<(f64, f64)>,
+}
+
+impl Default for CursorView {
+    fn default() -> Self {
+        Self {
+            screen: (0.0, 0.0, 1920.0, 1080.0),
+            pos: None,
+            trail: VecDeque::new(),
+        }
+    }
+}
+
+impl CursorView {
+    /// Set the screen size, e.g. from the monitor the window is on.
+    #[cfg(target_os = "linux")]
+    pub fn set_screen_size(&mut self, width: f64, height: f64) {
+        if width > 0.0 && height > 0.0 {
+            self.screen = (0.0, 0.0, width, height);
+        }
+    }
+
+    /// Follow one relative pointer motion.
+    #[cfg(target_os = "linux")]
+    pub fn on_motion(&mut self, dx: f64, dy: f64) {
+        let (x0, y0, w, h) = self.screen;
+        let (x, y) = self.pos.unwrap_or((x0 + w / 2.0, y0 + h / 2.0));
+        self.set((
+            (x + dx).clamp(x0, x0 + w - 1.0),
+            (y + dy).clamp(y0, y0 + h - 1.0),
+        ));
+    }
+
+    /// Read the cursor position and the bounds of all monitors.
+    #[cfg(target_os = "windows")]
+    pub fn poll(&mut self) {
+        use windows::Win32::Foundation::POINT;
+        use windows::Win32::UI::WindowsAndMessaging::*;
+
+        let mut point = POINT::default();
+        unsafe {
+            self.screen = (
+                GetSystemMetrics(SM_XVIRTUALSCREEN) as f64,
+                GetSystemMetrics(SM_YVIRTUALSCREEN) as f64,
+                GetSystemMetrics(SM_CXVIRTUALSCREEN).max(1) as f64,
+                GetSystemMetrics(SM_CYVIRTUALSCREEN).max(1) as f64,
+            );
+            if GetCursorPos(&mut point).is_err() {
+                return;
+            }
+        }
+        let pos = (point.x as f64, point.y as f64);
+        if self.pos != Some(pos) {
+            self.set(pos);
+        }
+    }
+
+    fn set(&mut self, pos: (f64, f64)) {
+        self.pos = Some(pos);
+        if self.trail.len() == TRAIL_MAX {
+            self.trail.pop_front();
+        }
+        self.trail.push_back(pos);
+    }
+
+    /// `pos` as a share of the screen, 0.0..=1.0 on both axes.
+    pub fn normalized(&self, (x, y): (f64, f64)) -> (f32, f32) {
+        let (x0, y0, w, h) = self.screen;
+        (((x - x0) / w) as f32, ((y - y0) / h) as f32)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_motion_and_stops_at_the_edges() {
+        let mut cursor = CursorView::default();
+        cursor.set_screen_size(1000.0, 500.0);
+        cursor.on_motion(10.0, -5.0);
+        assert_eq!(cursor.pos, Some((510.0, 245.0)));
+        cursor.on_motion(-2000.0, 1000.0);
+        assert_eq!(cursor.pos, Some((0.0, 499.0)));
+        assert_eq!(cursor.trail.len(), 2);
+        assert_eq!(cursor.normalized((500.0, 125.0)), (0.5, 0.25));
+    }
+}
@@ -0,0 +1,108 @@
+//! What shapes the device's behavior besides its own reports: its udev
+//! properties, the libinput quirks that apply to it and the hwdb entries it
+//! matches. Two machines with the same touchpad behaving differently usually
+//! differ here.
+//!
+//! Quirks come from `libinput quirks list --verbose`, whose debug lines also
+//! name each matching section and its file.
+
+#[derive(Debug, Default)]
+pub struct DeviceProperties {
+    pub udev: Vec<(String, String)>,
+    /// Quirk attributes in effect
+    pub quirks: Vec<(String, String)>,
+    /// Quirks sections that matched, e.g. `Lenovo X1 Carbon (50-system-lenovo.quirks)`
+    pub quirk_sections: Vec<String>,
+    /// hwdb lookup key, property, value
+    pub hwdb: Vec<(String, String, String)>,
+}
+
+impl DeviceProperties {
+    /// Read everything for the evdev device at `device_path`. Parts that
+    /// can't be read (no libinput tool, no hwdb) stay empty.
+    #[cfg(target_os = "linux")]
+    pub fn read(device_path: &std::path::Path) -> Self {
+        use tapview_core::discovery::udev_discovery;
+        let verbose = std::process::Command::new("libinput")
+            .args(["quirks", "list", "--verbose"])
+            .arg(device_path)
+            .output()
+            .map(|output| {
+                let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                text
+            })
+            .unwrap_or_default();
+        let (quirks, quirk_sections) = parse_quirks(&verbose);
+        Self {
+            udev: udev_discovery::read_properties(device_path),
+            quirks,
+            quirk_sections,
+            hwdb: udev_discovery::read_hwdb_entries(device_path),
+        }
+    }
+
+    /// Everything as text, for pasting into a bug report.
+    pub fn report_text(&self) -> String {
+        let mut lines = vec!["# udev properties".to_string()];
+        lines.extend(self.udev.iter().map(|(k, v)| format!("{}={}", k, v)));
+        lines.push("# libinput quirks".to_string());
+        lines.extend(self.quirk_sections.iter().map(|s| format!("[{}]", s)));
+        lines.extend(self.quirks.iter().map(|(k, v)| format!("{}={}", k, v)));
+        lines.push("# hwdb".to_string());
+        lines.extend(
+            self.hwdb
+                .iter()
+                .map(|(key, k, v)| format!("{}: {}={}", key, k, v)),
+        );
+        lines.join("\n")
+    }
+}
+
+/// Split `libinput quirks list --verbose` output into the attributes in
+/// effect and the names of the sections that matched.
+fn parse_quirks(text: &str) -> (Vec<(String, String)>, Vec<String>) {
+    let mut quirks = Vec::new();
+    let mut sections = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some((section, _)) = line.split_once(" matches for ") {
+            let section = section.strip_prefix("quirks debug:").unwrap_or(section);
+            sections.push(section.trim().to_string());
+        } else if let Some((key, value)) = line.split_once('=') {
+            // Debug lines quote match rules too, but never as a bare key
+            if key.starts_with("Attr") || key.starts_with("Model") {
+                quirks.push((key.to_string(), value.to_string()));
+            }
+        }
+    }
+    (quirks, sections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separates_attributes_from_matched_sections() {
+        let output = "\
+quirks debug: /usr/share/libinput is data root
+quirks debug: Lenovo T480s Touchpad (50-system-lenovo.quirks) matches for MatchName
+quirks debug: match: MatchUdevType=touchpad
+ModelLenovoT480sTouchpad=1
+AttrPressureRange=10:8
+";
+        let (quirks, sections) = parse_quirks(output);
+        assert_eq!(
+            sections,
+            ["Lenovo T480s Touchpad (50-system-lenovo.quirks)"]
+        );
+        assert_eq!(
+            quirks,
+            [
+                ("ModelLenovoT480sTouchpad".to_string(), "1".to_string()),
+                ("AttrPressureRange".to_string(), "10:8".to_string()),
+            ]
+        );
+    }
+}
@@ -1,4 +1,75 @@
 use egui::Pos2;
+use serde::{Deserialize, Serialize};
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// Clockwise rotation of the touch view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Rotation {
+    #[default]
+    #[value(name = "0")]
+    #[serde(rename = "0")]
+    R0,
+    #[value(name = "90")]
+    #[serde(rename = "90")]
+    R90,
+    #[value(name = "180")]
+    #[serde(rename = "180")]
+    R180,
+    #[value(name = "270")]
+    #[serde(rename = "270")]
+    R270,
+}
+
+impl Rotation {
+    pub const ALL: [Rotation; 4] = [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Rotation::R0 => "0°",
+            Rotation::R90 => "90°",
+            Rotation::R180 => "180°",
+            Rotation::R270 => "270°",
+        }
+    }
+}
+
+/// How device coordinates are turned into the touch view, for pads mounted
+/// rotated or mirrored: a clockwise rotation, then the X/Y flips. Persisted
+/// per device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewTransform {
+    pub rotation: Rotation,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl ViewTransform {
+    /// Extents of the view for a device with extents `(w, h)`. Also maps
+    /// view extents back to the device's, as a quarter turn just swaps them.
+    pub fn extents(&self, (w, h): (f32, f32)) -> (f32, f32) {
+        match self.rotation {
+            Rotation::R90 | Rotation::R270 => (h, w),
+            Rotation::R0 | Rotation::R180 => (w, h),
+        }
+    }
+
+    /// Map a device position into the view, for a device with extents
+    /// `(w, h)`.
+    pub fn apply(&self, (x, y): (f32, f32), (w, h): (f32, f32)) -> (f32, f32) {
+        let (x, y) = match self.rotation {
+            Rotation::R0 => (x, y),
+            Rotation::R90 => (h - y, x),
+            Rotation::R180 => (w - x, h - y),
+            Rotation::R270 => (y, w - x),
+        };
+        let (vw, vh) = self.extents((w, h));
+        (
+            if self.flip_x { vw - x } else { x },
+            if self.flip_y { vh - y } else { y },
+        )
+    }
+}
 
 pub struct Dimensions {
     pub touchpad_max_extent_x: f32,
@@ -8,6 +79,10 @@ pub struct Dimensions {
     pub margin: f32,
     /// True when extents came from evdev absinfo.
     pub extent_known: bool,
+    /// Device units per millimeter (x, y), when known.
+    pub resolution: Option<(f32, f32)>,
+    /// Device to view mapping. Extents and resolution above are the view's.
+    pub transform: ViewTransform,
 }
 
 impl Default for Dimensions {
@@ -19,6 +94,8 @@ impl Default for Dimensions {
             screen_height: 432.0,
             margin: 15.0,
             extent_known: false,
+            resolution: None,
+            transform: ViewTransform::default(),
         }
     }
 }
@@ -56,6 +133,54 @@ impl Dimensions {
         )
     }
 
+    /// Touchpad extents in device coordinates.
+    pub fn device_extents(&self) -> (f32, f32) {
+        self.transform
+            .extents((self.touchpad_max_extent_x, self.touchpad_max_extent_y))
+    }
+
+    /// Switch to another device to view mapping, turning the extents and
+    /// resolution with it.
+    pub fn set_transform(&mut self, transform: ViewTransform) {
+        let device = self.device_extents();
+        (self.touchpad_max_extent_x, self.touchpad_max_extent_y) = transform.extents(device);
+        if let Some(resolution) = self.resolution {
+            let device = self.transform.extents(resolution);
+            self.resolution = Some(transform.extents(device));
+        }
+        self.transform = transform;
+    }
+
+    /// Touches with their positions in view coordinates.
+    pub fn to_view(
+        &self,
+        touches: &[TouchData; MAX_TOUCH_POINTS],
+    ) -> [TouchData; MAX_TOUCH_POINTS] {
+        if self.transform == ViewTransform::default() {
+            return *touches;
+        }
+        let device = self.device_extents();
+        let mut view = *touches;
+        for touch in view.iter_mut().filter(|t| t.used) {
+            let map = |x: i32, y: i32| {
+                let (x, y) = self.transform.apply((x as f32, y as f32), device);
+                (x.round() as i32, y.round() as i32)
+            };
+            (touch.position_x, touch.position_y) = map(touch.position_x, touch.position_y);
+            (touch.tool_x, touch.tool_y) = map(touch.tool_x, touch.tool_y);
+        }
+        view
+    }
+
+    /// Forget extents grown from touches. No-op when they came from the device.
+    pub fn reset_learned_extent(&mut self) {
+        if !self.extent_known {
+            let defaults = Self::default();
+            self.touchpad_max_extent_x = defaults.touchpad_max_extent_x;
+            self.touchpad_max_extent_y = defaults.touchpad_max_extent_y;
+        }
+    }
+
     pub fn maybe_grow_touchpad_extent(&mut self, x: f32, y: f32) {
         if self.touchpad_max_extent_x < x {
             self.touchpad_max_extent_x = x;
@@ -0,0 +1,78 @@
+//! Session-long histogram of where contacts have been.
+//!
+//! Cells are square in device units and sized from the touchpad extents when
+//! the map is created, so growing extents don't reshuffle earlier counts.
+
+use std::collections::HashMap;
+use tapview_core::multitouch::TouchData;
+
+/// Cells along the longer touchpad axis.
+const CELLS_LONG_AXIS: f32 = 64.0;
+
+pub struct DwellMap {
+    /// Cell edge length in device units.
+    pub cell: i32,
+    pub counts: HashMap<(i32, i32), u32>,
+    pub max: u32,
+}
+
+impl DwellMap {
+    pub fn new(extent_x: f32, extent_y: f32) -> Self {
+        Self {
+            cell: ((extent_x.max(extent_y) / CELLS_LONG_AXIS) as i32).max(1),
+            counts: HashMap::new(),
+            max: 0,
+        }
+    }
+
+    /// Count every active contact once.
+    pub fn add(&mut self, touches: &[TouchData]) {
+        for touch in touches.iter().filter(|t| t.used) {
+            let key = (
+                touch.position_x.div_euclid(self.cell),
+                touch.position_y.div_euclid(self.cell),
+            );
+            let count = self.counts.entry(key).or_insert(0);
+            *count += 1;
+            self.max = self.max.max(*count);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+        self.max = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bins_contacts_by_cell() {
+        let mut map = DwellMap::new(640.0, 400.0);
+        assert_eq!(map.cell, 10);
+        let mut touches = [TouchData::default(); 2];
+        touches[0] = TouchData {
+            used: true,
+            position_x: 15,
+            position_y: 5,
+            ..Default::default()
+        };
+        map.add(&touches);
+        map.add(&touches);
+        touches[1] = TouchData {
+            used: true,
+            position_x: 19,
+            position_y: 9,
+            ..Default::default()
+        };
+        map.add(&touches);
+        assert_eq!(map.counts[&(1, 0)], 4);
+        assert_eq!(map.max, 4);
+
+        map.clear();
+        assert!(map.counts.is_empty());
+        assert_eq!(map.max, 0);
+    }
+}
@@ -0,0 +1,261 @@
+//! Edge-swipe and hot-corner detection, the way compositors do it: a contact
+//! that starts within `band_mm` of an edge and moves `SWIPE_MM` inward is an
+//! edge swipe; one that starts inside a corner square is a corner touch.
+//!
+//! Edge gestures that don't fire usually started just outside the zone, so
+//! a swipe from within twice the band is reported as a near miss with how
+//! far outside it began. Positions are in view coordinates. Without a
+//! resolution, millimeters are taken as a hundredth of the width.
+
+use crate::edge_test::Side;
+use std::time::{Duration, Instant};
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// Inward movement that makes a swipe.
+pub const SWIPE_MM: f32 = 10.0;
+/// How long the badge for the last event stays up.
+pub const BADGE_TIME: Duration = Duration::from_millis(1500);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EdgeEvent {
+    Swipe {
+        side: Side,
+    },
+    /// A swipe that started `outside_mm` beyond the band
+    NearMiss {
+        side: Side,
+        outside_mm: f32,
+    },
+    Corner {
+        vertical: Side,
+        horizontal: Side,
+    },
+}
+
+impl EdgeEvent {
+    pub fn label(&self) -> String {
+        let arrow = |side: Side| match side {
+            Side::Left => "left →",
+            Side::Right => "right ←",
+            Side::Top => "top ↓",
+            Side::Bottom => "bottom ↑",
+        };
+        match self {
+            EdgeEvent::Swipe { side } => format!("EDGE SWIPE ({})", arrow(*side)),
+            EdgeEvent::NearMiss { side, outside_mm } => format!(
+                "NO EDGE SWIPE ({}, started {:.1} mm outside the zone)",
+                arrow(*side),
+                outside_mm
+            ),
+            EdgeEvent::Corner {
+                vertical,
+                horizontal,
+            } => format!(
+                "HOT CORNER ({}-{})",
+                vertical.label().to_lowercase(),
+                horizontal.label().to_lowercase()
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Start {
+    tracking_id: i32,
+    pos: (f32, f32),
+    /// Nearest edge and the distance to it in mm
+    side: Side,
+    distance_mm: f32,
+    /// Already reported, or too far from any edge to be
+    done: bool,
+}
+
+pub struct EdgeSwipes {
+    pub band_mm: f32,
+    starts: [Option<Start>; MAX_TOUCH_POINTS],
+    /// The latest event, where it started and when
+    pub last: Option<(EdgeEvent, (f32, f32), Instant)>,
+}
+
+impl EdgeSwipes {
+    pub fn new(band_mm: f32) -> Self {
+        Self {
+            band_mm,
+            starts: [None; MAX_TOUCH_POINTS],
+            last: None,
+        }
+    }
+
+    /// Feed one report in view coordinates for a view of `extents` with
+    /// `resolution` in units/mm.
+    pub fn update(
+        &mut self,
+        touches: &[TouchData],
+        extents: (f32, f32),
+        resolution: Option<(f32, f32)>,
+        now: Instant,
+    ) -> Option<EdgeEvent> {
+        let (rx, ry) = resolution.unwrap_or((extents.0 / 100.0, extents.0 / 100.0));
+        let (rx, ry) = (rx.max(f32::EPSILON), ry.max(f32::EPSILON));
+        let mut event = None;
+        for (slot, touch) in touches.iter().enumerate().take(MAX_TOUCH_POINTS) {
+            if !touch.used {
+                self.starts[slot] = None;
+                continue;
+            }
+            let pos = (touch.position_x as f32, touch.position_y as f32);
+            let start = match self.starts[slot] {
+                Some(start) if start.tracking_id == touch.tracking_id => start,
+                _ => {
+                    let start = self.touch_down(touch.tracking_id, pos, extents, (rx, ry));
+                    self.starts[slot] = Some(start);
+                    if let Some(corner) = self.corner(pos, extents, (rx, ry)) {
+                        self.last = Some((corner, pos, now));
+                        event = Some(corner);
+                    }
+                    continue;
+                }
+            };
+            if start.done {
+                continue;
+            }
+            let inward_mm = match start.side {
+                Side::Left => (pos.0 - start.pos.0) / rx,
+                Side::Right => (start.pos.0 - pos.0) / rx,
+                Side::Top => (pos.1 - start.pos.1) / ry,
+                Side::Bottom => (start.pos.1 - pos.1) / ry,
+            };
+            if inward_mm >= SWIPE_MM {
+                let swipe = if start.distance_mm <= self.band_mm {
+                    EdgeEvent::Swipe { side: start.side }
+                } else {
+                    EdgeEvent::NearMiss {
+                        side: start.side,
+                        outside_mm: start.distance_mm - self.band_mm,
+                    }
+                };
+                self.starts[slot] = Some(Start {
+                    done: true,
+                    ..start
+                });
+                self.last = Some((swipe, start.pos, now));
+                event = Some(swipe);
+            }
+        }
+        event
+    }
+
+    fn touch_down(
+        &self,
+        tracking_id: i32,
+        pos: (f32, f32),
+        (w, h): (f32, f32),
+        (rx, ry): (f32, f32),
+    ) -> Start {
+        let (side, distance_mm) = [
+            (Side::Left, pos.0 / rx),
+            (Side::Right, (w - pos.0) / rx),
+            (Side::Top, pos.1 / ry),
+            (Side::Bottom, (h - pos.1) / ry),
+        ]
+        .into_iter()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((Side::Left, f32::MAX));
+        let distance_mm = distance_mm.max(0.0);
+        Start {
+            tracking_id,
+            pos,
+            side,
+            distance_mm,
+            done: distance_mm > 2.0 * self.band_mm,
+        }
+    }
+
+    fn corner(
+        &self,
+        pos: (f32, f32),
+        (w, h): (f32, f32),
+        (rx, ry): (f32, f32),
+    ) -> Option<EdgeEvent> {
+        let horizontal = if pos.0 / rx <= self.band_mm {
+            Side::Left
+        } else if (w - pos.0) / rx <= self.band_mm {
+            Side::Right
+        } else {
+            return None;
+        };
+        let vertical = if pos.1 / ry <= self.band_mm {
+            Side::Top
+        } else if (h - pos.1) / ry <= self.band_mm {
+            Side::Bottom
+        } else {
+            return None;
+        };
+        Some(EdgeEvent::Corner {
+            vertical,
+            horizontal,
+        })
+    }
+
+    /// The latest event if its badge is still up.
+    pub fn badge(&self, now: Instant) -> Option<(EdgeEvent, (f32, f32))> {
+        self.last
+            .filter(|(_, _, at)| now.saturating_duration_since(*at) < BADGE_TIME)
+            .map(|(event, pos, _)| (event, pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finger(id: i32, x: i32, y: i32) -> [TouchData; 1] {
+        [TouchData {
+            used: true,
+            tracking_id: id,
+            position_x: x,
+            position_y: y,
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn tells_swipes_from_near_misses_and_corners() {
+        // 100 x 50 mm at 10 units/mm, 3 mm band
+        let extents = (1000.0, 500.0);
+        let res = Some((10.0, 10.0));
+        let now = Instant::now();
+        let mut edges = EdgeSwipes::new(3.0);
+
+        assert_eq!(edges.update(&finger(1, 20, 250), extents, res, now), None);
+        assert_eq!(edges.update(&finger(1, 80, 250), extents, res, now), None);
+        assert_eq!(
+            edges.update(&finger(1, 130, 250), extents, res, now),
+            Some(EdgeEvent::Swipe { side: Side::Left })
+        );
+        // Reported once per contact
+        assert_eq!(edges.update(&finger(1, 300, 250), extents, res, now), None);
+
+        // From 4.5 mm below the top: outside the band but close
+        edges.update(&finger(2, 500, 45), extents, res, now);
+        match edges.update(&finger(2, 500, 200), extents, res, now) {
+            Some(EdgeEvent::NearMiss {
+                side: Side::Top,
+                outside_mm,
+            }) => assert!((outside_mm - 1.5).abs() < 1e-4),
+            other => panic!("{:?}", other),
+        }
+        // Too far in to count at all
+        edges.update(&finger(3, 500, 250), extents, res, now);
+        assert_eq!(edges.update(&finger(3, 900, 250), extents, res, now), None);
+
+        assert_eq!(
+            edges.update(&finger(4, 990, 495), extents, res, now),
+            Some(EdgeEvent::Corner {
+                vertical: Side::Bottom,
+                horizontal: Side::Right
+            })
+        );
+        assert!(edges.badge(now + BADGE_TIME).is_none());
+    }
+}
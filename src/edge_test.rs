@@ -0,0 +1,133 @@
+//! Edge accuracy test: trace the touchpad's physical edges and compare the
+//! outermost reported coordinates with the advertised axis ranges.
+//!
+//! The touchpad is split into bins along each edge; per bin the outermost
+//! position reached is kept, so the unreachable band can be drawn with its
+//! real shape (dead zones are often wider in the corners). Ranges are taken
+//! as 0..=extent, matching how the rest of the view places contacts.
+
+/// Bins along each edge.
+pub const BINS: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Top, Side::Bottom, Side::Left, Side::Right];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Side::Top => "Top",
+            Side::Bottom => "Bottom",
+            Side::Left => "Left",
+            Side::Right => "Right",
+        }
+    }
+}
+
+/// Unreachable band on one side in device units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeadZone {
+    /// Gap at the best-reached bin
+    pub min: f32,
+    /// Gap at the worst visited bin
+    pub max: f32,
+    /// Share of bins along this side that were visited
+    pub coverage: f32,
+}
+
+pub struct EdgeTest {
+    extent: (f32, f32),
+    /// Per column: smallest and largest Y reached
+    columns: [Option<(i32, i32)>; BINS],
+    /// Per row: smallest and largest X reached
+    rows: [Option<(i32, i32)>; BINS],
+}
+
+fn bin(value: i32, extent: f32) -> usize {
+    ((value as f32 / extent.max(1.0) * BINS as f32) as usize).min(BINS - 1)
+}
+
+fn widen(range: &mut Option<(i32, i32)>, value: i32) {
+    *range = Some(match *range {
+        Some((lo, hi)) => (lo.min(value), hi.max(value)),
+        None => (value, value),
+    });
+}
+
+impl EdgeTest {
+    /// `extent` is the advertised position range in device units.
+    pub fn new(extent: (f32, f32)) -> Self {
+        Self {
+            extent,
+            columns: [None; BINS],
+            rows: [None; BINS],
+        }
+    }
+
+    /// Feed active contact positions.
+    pub fn update(&mut self, positions: &[(i32, i32)]) {
+        for &(x, y) in positions {
+            widen(&mut self.columns[bin(x, self.extent.0)], y);
+            widen(&mut self.rows[bin(y, self.extent.1)], x);
+        }
+    }
+
+    /// Per bin along `side`, how far from the advertised edge the outermost
+    /// contact stayed, or `None` if the bin wasn't visited.
+    pub fn gaps(&self, side: Side) -> [Option<f32>; BINS] {
+        let (ex, ey) = self.extent;
+        let mut gaps = [None; BINS];
+        for (i, gap) in gaps.iter_mut().enumerate() {
+            *gap = match side {
+                Side::Top => self.columns[i].map(|(lo, _)| lo.max(0) as f32),
+                Side::Bottom => self.columns[i].map(|(_, hi)| (ey - hi as f32).max(0.0)),
+                Side::Left => self.rows[i].map(|(lo, _)| lo.max(0) as f32),
+                Side::Right => self.rows[i].map(|(_, hi)| (ex - hi as f32).max(0.0)),
+            };
+        }
+        gaps
+    }
+
+    pub fn dead_zone(&self, side: Side) -> Option<DeadZone> {
+        let visited: Vec<f32> = self.gaps(side).into_iter().flatten().collect();
+        if visited.is_empty() {
+            return None;
+        }
+        Some(DeadZone {
+            min: visited.iter().copied().fold(f32::MAX, f32::min),
+            max: visited.iter().copied().fold(0.0, f32::max),
+            coverage: visited.len() as f32 / BINS as f32,
+        })
+    }
+
+    pub fn extent(&self) -> (f32, f32) {
+        self.extent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_gaps_per_side() {
+        let mut test = EdgeTest::new((320.0, 320.0));
+        // Along the top edge, closer in the middle than in the corner
+        test.update(&[(5, 30), (160, 4)]);
+        // Right edge reached exactly
+        test.update(&[(320, 160)]);
+
+        let top = test.dead_zone(Side::Top).unwrap();
+        assert_eq!((top.min, top.max), (4.0, 160.0));
+        assert_eq!(top.coverage, 3.0 / BINS as f32);
+        assert_eq!(test.gaps(Side::Top)[0], Some(30.0));
+        assert_eq!(test.dead_zone(Side::Right).unwrap().min, 0.0);
+        assert_eq!(test.gaps(Side::Left)[1], None);
+    }
+}
@@ -0,0 +1,259 @@
+//! Shell commands run on finished gestures and taps, for desktops where
+//! libinput's gestures aren't bound to anything.
+//!
+//! Bindings live in the `[gestures]` table of the config file, shared by all
+//! devices:
+//!
+//! ```toml
+//! [gestures]
+//! swipe-4-up = "wmctrl -s 1"
+//! pinch-2-in = "xdotool key ctrl+minus"
+//! tap-3 = "notify-send 'three-finger tap'"
+//! ```
+//!
+//! Triggers are `swipe-N-up|down|left|right`, `pinch-N-in|out`, `hold-N` and
+//! `tap-N` for N fingers. A swipe's direction is the axis it moved furthest
+//! along; cancelled gestures don't count. Taps are tap-to-click button
+//! presses while the physical button is up. Commands run through the shell
+//! without waiting for them, with `TAPVIEW_GESTURE` set to the trigger.
+
+use crate::libinput_state::{GestureKind, GestureRecord};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::process::Command;
+use std::str::FromStr;
+use std::thread;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    Swipe { fingers: u32, direction: Direction },
+    Pinch { fingers: u32, out: bool },
+    Hold { fingers: u32 },
+    Tap { fingers: u32 },
+}
+
+impl Trigger {
+    /// The trigger a finished gesture fires, if any.
+    pub fn from_gesture(record: &GestureRecord) -> Option<Self> {
+        if record.cancelled {
+            return None;
+        }
+        let fingers = record.fingers;
+        match record.kind {
+            GestureKind::Swipe if record.dx != 0.0 || record.dy != 0.0 => {
+                let direction = if record.dx.abs() > record.dy.abs() {
+                    if record.dx > 0.0 {
+                        Direction::Right
+                    } else {
+                        Direction::Left
+                    }
+                } else if record.dy > 0.0 {
+                    Direction::Down
+                } else {
+                    Direction::Up
+                };
+                Some(Trigger::Swipe { fingers, direction })
+            }
+            GestureKind::Pinch if record.scale != 1.0 => Some(Trigger::Pinch {
+                fingers,
+                out: record.scale > 1.0,
+            }),
+            GestureKind::Hold => Some(Trigger::Hold { fingers }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trigger::Swipe { fingers, direction } => {
+                write!(f, "swipe-{}-{}", fingers, direction.label())
+            }
+            Trigger::Pinch { fingers, out } => {
+                write!(f, "pinch-{}-{}", fingers, if *out { "out" } else { "in" })
+            }
+            Trigger::Hold { fingers } => write!(f, "hold-{}", fingers),
+            Trigger::Tap { fingers } => write!(f, "tap-{}", fingers),
+        }
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("unknown gesture trigger {:?}", s);
+        let mut parts = s.split('-');
+        let kind = parts.next().unwrap_or_default();
+        let fingers: u32 = parts
+            .next()
+            .and_then(|n| n.parse().ok())
+            .filter(|n| (1..=5).contains(n))
+            .ok_or_else(invalid)?;
+        let trigger = match (kind, parts.next()) {
+            ("swipe", Some(dir)) => Trigger::Swipe {
+                fingers,
+                direction: Direction::ALL
+                    .into_iter()
+                    .find(|d| d.label() == dir)
+                    .ok_or_else(invalid)?,
+            },
+            ("pinch", Some("in")) => Trigger::Pinch {
+                fingers,
+                out: false,
+            },
+            ("pinch", Some("out")) => Trigger::Pinch { fingers, out: true },
+            ("hold", None) => Trigger::Hold { fingers },
+            ("tap", None) => Trigger::Tap { fingers },
+            _ => return Err(invalid()),
+        };
+        match parts.next() {
+            None => Ok(trigger),
+            Some(_) => Err(invalid()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct GestureHooks {
+    bindings: Vec<(Trigger, String)>,
+}
+
+impl GestureHooks {
+    /// Parse the `[gestures]` table, skipping unknown triggers with a warning.
+    pub fn new(table: &BTreeMap<String, String>) -> Self {
+        let bindings = table
+            .iter()
+            .filter_map(|(name, command)| match name.parse() {
+                Ok(trigger) => Some((trigger, command.clone())),
+                Err(e) => {
+                    log_warn!(target: "gestures", "{}", e);
+                    None
+                }
+            })
+            .collect();
+        Self { bindings }
+    }
+
+    /// The command bound to `trigger`.
+    pub fn command(&self, trigger: Trigger) -> Option<&str> {
+        self.bindings
+            .iter()
+            .find(|(t, _)| *t == trigger)
+            .map(|(_, command)| command.as_str())
+    }
+
+    /// Run the command bound to `trigger`, if any.
+    pub fn fire(&self, trigger: Trigger) {
+        let Some(command) = self.command(trigger) else {
+            return;
+        };
+        log_info!(target: "gestures", "{}: {}", trigger, command);
+        #[cfg(unix)]
+        let mut shell = {
+            let mut c = Command::new("sh");
+            c.arg("-c");
+            c
+        };
+        #[cfg(windows)]
+        let mut shell = {
+            let mut c = Command::new("cmd");
+            c.arg("/C");
+            c
+        };
+        match shell
+            .arg(command)
+            .env("TAPVIEW_GESTURE", trigger.to_string())
+            .spawn()
+        {
+            // Reap it without holding up the UI
+            Ok(mut child) => {
+                thread::spawn(move || child.wait());
+            }
+            Err(e) => log_error!(target: "gestures", "failed to run {:?}: {}", command, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_triggers_and_classifies_gestures() {
+        for name in ["swipe-4-up", "pinch-2-in", "pinch-3-out", "hold-3", "tap-3"] {
+            assert_eq!(name.parse::<Trigger>().unwrap().to_string(), name);
+        }
+        for name in ["swipe-4", "swipe-4-sideways", "tap-0", "tap-3-up", "wave-2"] {
+            assert!(name.parse::<Trigger>().is_err(), "{}", name);
+        }
+
+        let swipe = GestureRecord {
+            kind: GestureKind::Swipe,
+            fingers: 4,
+            duration_ms: 200.0,
+            dx: 30.0,
+            dy: -120.0,
+            scale: 1.0,
+            angle: 0.0,
+            cancelled: false,
+        };
+        assert_eq!(
+            Trigger::from_gesture(&swipe).unwrap().to_string(),
+            "swipe-4-up"
+        );
+        let pinch = GestureRecord {
+            kind: GestureKind::Pinch,
+            fingers: 2,
+            scale: 0.5,
+            ..swipe.clone()
+        };
+        assert_eq!(
+            Trigger::from_gesture(&pinch),
+            Some(Trigger::Pinch {
+                fingers: 2,
+                out: false
+            })
+        );
+        let cancelled = GestureRecord {
+            cancelled: true,
+            ..swipe
+        };
+        assert_eq!(Trigger::from_gesture(&cancelled), None);
+
+        let table = BTreeMap::from([
+            ("tap-3".to_string(), "true".to_string()),
+            ("tap-9".to_string(), "false".to_string()),
+        ]);
+        let hooks = GestureHooks::new(&table);
+        assert_eq!(hooks.command(Trigger::Tap { fingers: 3 }), Some("true"));
+        assert_eq!(hooks.bindings.len(), 1);
+    }
+}
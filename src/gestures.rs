@@ -0,0 +1,369 @@
+//! A small gesture recognizer over raw contacts, producing the same events
+//! as libinput so they feed the same panel.
+//!
+//! Windows has no API that hands three/four-finger gestures to applications
+//! (the shell consumes them), and pinches only reach legacy apps as Ctrl+wheel.
+//! The raw PTP reports carry every contact though, so swipes and pinches are
+//! derived from them here. On Linux it stands in for libinput when that isn't
+//! available, adding taps and two-finger scrolling, or runs next to it to
+//! compare the two.
+//!
+//! Gestures follow libinput's rules: a gesture's finger count is fixed when
+//! it starts, changing the number of contacts ends it, and movement has to
+//! pass a threshold before anything is reported. Deltas are in device units.
+
+use crate::libinput_state::{LibinputEvent, ScrollSource};
+use std::time::{Duration, Instant};
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// Movement needed to start a gesture, as a fraction of the touchpad width.
+const START_FRACTION: f32 = 0.03;
+/// Rotation needed to start a pinch, in degrees.
+const START_ANGLE: f32 = 10.0;
+/// Longest touch that still counts as a tap (libinput's tap timeout).
+const TAP_TIMEOUT: Duration = Duration::from_millis(180);
+/// evdev BTN_LEFT, BTN_RIGHT and BTN_MIDDLE for one-, two- and three-finger
+/// taps (libinput's default LRM mapping)
+const TAP_BUTTONS: [u32; 3] = [0x110, 0x111, 0x112];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    Idle,
+    /// Fingers are down but haven't moved far enough to classify.
+    Pending,
+    Swipe,
+    Pinch,
+    /// Two fingers moving together: the OS reports that as scrolling.
+    Scroll,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Frame {
+    count: usize,
+    centroid: (f32, f32),
+    /// Distance and angle between the first two contacts.
+    spread: f32,
+    angle: f32,
+}
+
+/// A touch sequence from the first finger down to the last one up, for tap
+/// detection.
+#[derive(Clone, Copy, Debug)]
+struct Touch {
+    start: Instant,
+    fingers: usize,
+    /// Moved past the start threshold or became a gesture
+    moved: bool,
+}
+
+pub struct GestureRecognizer {
+    phase: Phase,
+    start: Frame,
+    last: Frame,
+    touch: Option<Touch>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self {
+            phase: Phase::Idle,
+            start: Frame::default(),
+            last: Frame::default(),
+            touch: None,
+        }
+    }
+}
+
+fn frame(touches: &[TouchData; MAX_TOUCH_POINTS]) -> Frame {
+    let points: Vec<(f32, f32)> = touches
+        .iter()
+        .filter(|t| t.used)
+        .map(|t| (t.position_x as f32, t.position_y as f32))
+        .collect();
+    let count = points.len();
+    if count == 0 {
+        return Frame::default();
+    }
+    let (sx, sy) = points
+        .iter()
+        .fold((0.0, 0.0), |(ax, ay), (x, y)| (ax + x, ay + y));
+    let (spread, angle) = match points.as_slice() {
+        [(x0, y0), (x1, y1), ..] => {
+            let (dx, dy) = (x1 - x0, y1 - y0);
+            (dx.hypot(dy), dy.atan2(dx).to_degrees())
+        }
+        _ => (0.0, 0.0),
+    };
+    Frame {
+        count,
+        centroid: (sx / count as f32, sy / count as f32),
+        spread,
+        angle,
+    }
+}
+
+/// Smallest signed difference between two angles in degrees.
+fn angle_delta(from: f32, to: f32) -> f32 {
+    (to - from + 540.0).rem_euclid(360.0) - 180.0
+}
+
+impl GestureRecognizer {
+    /// Feed the current contacts, read at `time`; returns the events they
+    /// produce. `width` is the touchpad's X extent in device units.
+    pub fn update(
+        &mut self,
+        touches: &[TouchData; MAX_TOUCH_POINTS],
+        width: f32,
+        time: Instant,
+    ) -> Vec<LibinputEvent> {
+        let now = frame(touches);
+        let mut events = Vec::new();
+        let threshold = width * START_FRACTION;
+
+        self.track_tap(&now, threshold, time, &mut events);
+
+        if now.count != self.last.count {
+            if let Some(end) = self.end_event() {
+                events.push(end);
+            }
+            self.phase = if now.count >= 2 {
+                Phase::Pending
+            } else {
+                Phase::Idle
+            };
+            self.start = now;
+            self.last = now;
+            return events;
+        }
+
+        let fingers = now.count as i32;
+        let (dx, dy) = (
+            (now.centroid.0 - self.last.centroid.0) as f64,
+            (now.centroid.1 - self.last.centroid.1) as f64,
+        );
+        match self.phase {
+            Phase::Pending => {
+                let moved = (now.centroid.0 - self.start.centroid.0)
+                    .hypot(now.centroid.1 - self.start.centroid.1);
+                let spread = (now.spread - self.start.spread).abs();
+                let turned = angle_delta(self.start.angle, now.angle).abs();
+                if now.count == 2 && (spread > threshold || turned > START_ANGLE) {
+                    self.phase = Phase::Pinch;
+                    events.push(LibinputEvent::GesturePinchBegin { fingers });
+                } else if moved > threshold {
+                    if now.count >= 3 {
+                        self.phase = Phase::Swipe;
+                        events.push(LibinputEvent::GestureSwipeBegin { fingers });
+                    } else {
+                        self.phase = Phase::Scroll;
+                    }
+                }
+            }
+            Phase::Swipe => events.push(LibinputEvent::GestureSwipeUpdate {
+                fingers,
+                dx,
+                dy,
+                dx_unaccel: dx,
+                dy_unaccel: dy,
+            }),
+            Phase::Pinch => events.push(LibinputEvent::GesturePinchUpdate {
+                fingers,
+                dx,
+                dy,
+                dx_unaccel: dx,
+                dy_unaccel: dy,
+                scale: (now.spread / self.start.spread.max(1.0)) as f64,
+                angle: angle_delta(self.last.angle, now.angle) as f64,
+            }),
+            Phase::Scroll => events.push(LibinputEvent::Scroll {
+                source: ScrollSource::Finger,
+                vert: dy,
+                horiz: dx,
+            }),
+            Phase::Idle => {}
+        }
+        if self.phase != Phase::Pending && self.phase != Phase::Idle {
+            if let Some(touch) = &mut self.touch {
+                touch.moved = true;
+            }
+        }
+        self.last = now;
+        events
+    }
+
+    /// A tap is all fingers down and up again within the tap timeout without
+    /// moving; it clicks the button for the most fingers that were down.
+    fn track_tap(
+        &mut self,
+        now: &Frame,
+        threshold: f32,
+        time: Instant,
+        events: &mut Vec<LibinputEvent>,
+    ) {
+        match (&mut self.touch, now.count) {
+            (None, 0) => {}
+            (None, fingers) => {
+                self.touch = Some(Touch {
+                    start: time,
+                    fingers,
+                    moved: false,
+                })
+            }
+            (Some(touch), 0) => {
+                let touch = *touch;
+                self.touch = None;
+                let button = TAP_BUTTONS.get(touch.fingers - 1);
+                if let (Some(&button), false) = (button, touch.moved) {
+                    if time.duration_since(touch.start) <= TAP_TIMEOUT {
+                        for pressed in [true, false] {
+                            events.push(LibinputEvent::PointerButton {
+                                button,
+                                pressed,
+                                time,
+                            });
+                        }
+                    }
+                }
+            }
+            (Some(touch), fingers) => {
+                touch.fingers = touch.fingers.max(fingers);
+                let moved = (now.centroid.0 - self.start.centroid.0)
+                    .hypot(now.centroid.1 - self.start.centroid.1);
+                if fingers == self.last.count && moved > threshold {
+                    touch.moved = true;
+                }
+            }
+        }
+    }
+
+    fn end_event(&self) -> Option<LibinputEvent> {
+        match self.phase {
+            Phase::Swipe => Some(LibinputEvent::GestureSwipeEnd),
+            Phase::Pinch => Some(LibinputEvent::GesturePinchEnd),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touches(points: &[(i32, i32)]) -> [TouchData; MAX_TOUCH_POINTS] {
+        let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+        for (slot, &(x, y)) in points.iter().enumerate() {
+            touches[slot] = TouchData {
+                used: true,
+                tracking_id: slot as i32,
+                position_x: x,
+                position_y: y,
+                ..Default::default()
+            };
+        }
+        touches
+    }
+
+    const WIDTH: f32 = 1000.0;
+
+    #[test]
+    fn three_finger_swipe() {
+        let mut g = GestureRecognizer::default();
+        assert!(g
+            .update(
+                &touches(&[(100, 100), (200, 100), (300, 100)]),
+                WIDTH,
+                Instant::now()
+            )
+            .is_empty());
+        // Below the threshold nothing happens yet
+        assert!(g
+            .update(
+                &touches(&[(110, 100), (210, 100), (310, 100)]),
+                WIDTH,
+                Instant::now()
+            )
+            .is_empty());
+        let events = g.update(
+            &touches(&[(150, 100), (250, 100), (350, 100)]),
+            WIDTH,
+            Instant::now(),
+        );
+        assert!(matches!(
+            events[..],
+            [LibinputEvent::GestureSwipeBegin { fingers: 3 }]
+        ));
+        let events = g.update(
+            &touches(&[(160, 100), (260, 100), (360, 100)]),
+            WIDTH,
+            Instant::now(),
+        );
+        assert!(matches!(
+            events[..],
+            [LibinputEvent::GestureSwipeUpdate { fingers: 3, dx, .. }] if dx == 10.0
+        ));
+        let events = g.update(&touches(&[(160, 100), (260, 100)]), WIDTH, Instant::now());
+        assert!(matches!(events[..], [LibinputEvent::GestureSwipeEnd]));
+    }
+
+    #[test]
+    fn two_fingers_pinch_or_scroll() {
+        let mut g = GestureRecognizer::default();
+        g.update(&touches(&[(400, 300), (600, 300)]), WIDTH, Instant::now());
+        let events = g.update(&touches(&[(350, 300), (650, 300)]), WIDTH, Instant::now());
+        assert!(matches!(
+            events[..],
+            [LibinputEvent::GesturePinchBegin { fingers: 2 }]
+        ));
+        let events = g.update(&touches(&[(300, 300), (700, 300)]), WIDTH, Instant::now());
+        assert!(matches!(
+            events[..],
+            [LibinputEvent::GesturePinchUpdate { scale, .. }] if scale == 2.0
+        ));
+        let events = g.update(&touches(&[]), WIDTH, Instant::now());
+        assert!(matches!(events[..], [LibinputEvent::GesturePinchEnd]));
+
+        // Moving together is a scroll, not a gesture
+        g.update(&touches(&[(400, 300), (600, 300)]), WIDTH, Instant::now());
+        assert!(g
+            .update(&touches(&[(400, 400), (600, 400)]), WIDTH, Instant::now())
+            .is_empty());
+        let events = g.update(&touches(&[(400, 500), (600, 500)]), WIDTH, Instant::now());
+        assert!(matches!(
+            events[..],
+            [LibinputEvent::Scroll { vert, horiz, .. }] if vert == 100.0 && horiz == 0.0
+        ));
+    }
+
+    #[test]
+    fn taps_click_by_finger_count() {
+        let mut g = GestureRecognizer::default();
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let buttons = |events: Vec<LibinputEvent>| -> Vec<(u32, bool)> {
+            events
+                .into_iter()
+                .filter_map(|e| match e {
+                    LibinputEvent::PointerButton {
+                        button, pressed, ..
+                    } => Some((button, pressed)),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        g.update(&touches(&[(400, 300)]), WIDTH, at(0));
+        g.update(&touches(&[(400, 300), (600, 300)]), WIDTH, at(20));
+        g.update(&touches(&[(600, 300)]), WIDTH, at(80));
+        let events = g.update(&touches(&[]), WIDTH, at(100));
+        assert_eq!(buttons(events), [(0x111, true), (0x111, false)]);
+
+        // Too long
+        g.update(&touches(&[(400, 300)]), WIDTH, at(1000));
+        assert!(buttons(g.update(&touches(&[]), WIDTH, at(1300))).is_empty());
+
+        // Moved
+        g.update(&touches(&[(400, 300)]), WIDTH, at(2000));
+        g.update(&touches(&[(500, 300)]), WIDTH, at(2050));
+        assert!(buttons(g.update(&touches(&[]), WIDTH, at(2100))).is_empty());
+    }
+}
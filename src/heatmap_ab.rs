@@ -0,0 +1,319 @@
+//! ALC A/B capture: heatmap statistics recorded for a set time with the
+//! controller's automatic level calibration on, then again with it off, and
+//! compared.
+//!
+//! The capture asks for each switch and waits for the heatmap thread to say
+//! it's done, then lets the baseline settle before recording; afterwards ALC
+//! is switched back on. Noise is each cell's standard deviation over time,
+//! averaged over the cells; drift is how fast the frame mean moved, from a
+//! least-squares line through it.
+
+use std::time::{Duration, Instant};
+use tapview_core::heatmap::HeatmapFrame;
+
+pub const DEFAULT_SECONDS: u64 = 10;
+
+/// Frames right after a switch are skipped while the baseline settles, along
+/// with any read before it.
+pub const SETTLE: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseStats {
+    pub frames: usize,
+    pub mean: f64,
+    pub noise: f64,
+    /// Change of the frame mean per second
+    pub drift: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    start: Instant,
+    /// Per-cell sum and sum of squares
+    sums: Vec<(f64, f64)>,
+    /// Seconds since `start` and mean of each frame
+    frame_means: Vec<(f64, f64)>,
+}
+
+impl Recording {
+    fn new(start: Instant) -> Self {
+        Self {
+            start,
+            sums: Vec::new(),
+            frame_means: Vec::new(),
+        }
+    }
+
+    /// Returns false if the frame doesn't have the cells the earlier ones had.
+    fn add(&mut self, frame: &HeatmapFrame, time: Instant) -> bool {
+        if self.sums.is_empty() {
+            self.sums = vec![(0.0, 0.0); frame.data.len()];
+        } else if self.sums.len() != frame.data.len() {
+            return false;
+        }
+        for (sum, &value) in self.sums.iter_mut().zip(&frame.data) {
+            sum.0 += value as f64;
+            sum.1 += (value as f64).powi(2);
+        }
+        let mean =
+            frame.data.iter().map(|&v| v as f64).sum::<f64>() / frame.data.len().max(1) as f64;
+        let t = time.saturating_duration_since(self.start).as_secs_f64();
+        self.frame_means.push((t, mean));
+        true
+    }
+
+    fn stats(&self) -> Option<PhaseStats> {
+        let n = self.frame_means.len() as f64;
+        if self.frame_means.len() < 2 || self.sums.is_empty() {
+            return None;
+        }
+        let noise = self
+            .sums
+            .iter()
+            .map(|&(sum, squares)| (squares / n - (sum / n).powi(2)).max(0.0).sqrt())
+            .sum::<f64>()
+            / self.sums.len() as f64;
+        let t_mean = self.frame_means.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean = self.frame_means.iter().map(|p| p.1).sum::<f64>() / n;
+        let (covariance, variance) = self.frame_means.iter().fold((0.0, 0.0), |(c, v), &(t, m)| {
+            (c + (t - t_mean) * (m - mean), v + (t - t_mean).powi(2))
+        });
+        Some(PhaseStats {
+            frames: self.frame_means.len(),
+            mean,
+            noise,
+            drift: if variance > 0.0 {
+                covariance / variance
+            } else {
+                0.0
+            },
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AbTest {
+    /// Choosing the phase length
+    Setup {
+        seconds: u64,
+    },
+    /// Waiting for ALC to be switched on, or off once `a` is recorded
+    Switching {
+        seconds: u64,
+        a: Option<PhaseStats>,
+    },
+    /// With ALC on
+    RecordingA {
+        seconds: u64,
+        recording: Recording,
+    },
+    /// With ALC off
+    RecordingB {
+        seconds: u64,
+        a: PhaseStats,
+        recording: Recording,
+    },
+    Done {
+        a: PhaseStats,
+        b: PhaseStats,
+    },
+    Failed(String),
+}
+
+/// What the capture window asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbTestAction {
+    Start,
+    Save,
+    Restart,
+}
+
+impl Default for AbTest {
+    fn default() -> Self {
+        AbTest::Setup {
+            seconds: DEFAULT_SECONDS,
+        }
+    }
+}
+
+impl AbTest {
+    /// Begin by switching ALC on; returns the setting to send.
+    pub fn start(&mut self) -> Option<bool> {
+        let AbTest::Setup { seconds } = *self else {
+            return None;
+        };
+        *self = AbTest::Switching { seconds, a: None };
+        Some(true)
+    }
+
+    /// The heatmap thread switched ALC as asked (or couldn't) at `now`.
+    pub fn switched(&mut self, result: Result<(), String>, now: Instant) {
+        let AbTest::Switching { seconds, a } = self else {
+            // Switching back on after the capture, or the capture was
+            // restarted meanwhile
+            if let Err(e) = result {
+                log_warn!(target: "heatmap", "couldn't switch ALC back on: {}", e);
+            }
+            return;
+        };
+        let recording = Recording::new(now + SETTLE);
+        *self = match (result, a.take()) {
+            (Err(e), _) => AbTest::Failed(format!("Couldn't switch ALC: {}", e)),
+            (Ok(()), None) => AbTest::RecordingA {
+                seconds: *seconds,
+                recording,
+            },
+            (Ok(()), Some(a)) => AbTest::RecordingB {
+                seconds: *seconds,
+                a,
+                recording,
+            },
+        };
+    }
+
+    /// Feed a heatmap frame that arrived at `time`. Returns the ALC setting
+    /// to send when a phase has ended.
+    pub fn update(&mut self, frame: &HeatmapFrame, time: Instant) -> Option<bool> {
+        let (seconds, recording) = match self {
+            AbTest::RecordingA { seconds, recording } => (*seconds, recording),
+            AbTest::RecordingB {
+                seconds, recording, ..
+            } => (*seconds, recording),
+            _ => return None,
+        };
+        if time < recording.start {
+            return None;
+        }
+        if !recording.add(frame, time) {
+            *self = AbTest::Failed("The heatmap changed shape during the capture".into());
+            return None;
+        }
+        if time.saturating_duration_since(recording.start) < Duration::from_secs(seconds) {
+            return None;
+        }
+        let Some(stats) = recording.stats() else {
+            *self = AbTest::Failed("Too few heatmap frames".into());
+            return None;
+        };
+        match std::mem::take(self) {
+            AbTest::RecordingA { seconds, .. } => {
+                *self = AbTest::Switching {
+                    seconds,
+                    a: Some(stats),
+                };
+                Some(false)
+            }
+            AbTest::RecordingB { a, .. } => {
+                *self = AbTest::Done { a, b: stats };
+                Some(true)
+            }
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+
+    /// Whether ALC may be left off if the capture is abandoned now.
+    pub fn alc_may_be_off(&self) -> bool {
+        matches!(
+            self,
+            AbTest::Switching { a: Some(_), .. } | AbTest::RecordingB { .. }
+        )
+    }
+
+    /// Seconds left in the phase being recorded, counting the settling.
+    pub fn remaining(&self, now: Instant) -> Option<f32> {
+        match self {
+            AbTest::RecordingA { seconds, recording }
+            | AbTest::RecordingB {
+                seconds, recording, ..
+            } => Some(
+                (recording.start + Duration::from_secs(*seconds))
+                    .saturating_duration_since(now)
+                    .as_secs_f32(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// The two phases side by side with their differences, as text for a bug
+/// report.
+pub fn report(a: &PhaseStats, b: &PhaseStats) -> String {
+    let row = |label: &str, a: f64, b: f64| {
+        format!("{:<16}{:>12.3}{:>12.3}{:>+12.3}", label, a, b, b - a)
+    };
+    [
+        format!(
+            "{:<16}{:>12}{:>12}{:>12}",
+            "", "ALC on", "ALC off", "change"
+        ),
+        format!("{:<16}{:>12}{:>12}", "Frames", a.frames, b.frames),
+        row("Mean", a.mean, b.mean),
+        row("Noise", a.noise, b.noise),
+        row("Drift (/s)", a.drift, b.drift),
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_alc_and_compares_noise_drift_and_mean() {
+        let t0 = Instant::now();
+        let at = |ms: u64| t0 + Duration::from_millis(ms);
+        let frame = |a, b| HeatmapFrame {
+            rows: 1,
+            cols: 2,
+            data: vec![a, b],
+        };
+        let mut test = AbTest::Setup { seconds: 1 };
+        assert_eq!(test.start(), Some(true));
+        test.switched(Ok(()), t0);
+        // Still settling
+        assert_eq!(test.update(&frame(99, 99), at(500)), None);
+        // Steady cells
+        test.update(&frame(10, 20), at(1000));
+        test.update(&frame(10, 20), at(1500));
+        assert_eq!(test.update(&frame(10, 20), at(2000)), Some(false));
+        let AbTest::Switching { a: Some(a), .. } = &test else {
+            panic!("phase A didn't finish: {:?}", test);
+        };
+        assert_eq!((a.frames, a.mean, a.noise, a.drift), (3, 15.0, 0.0, 0.0));
+        assert!(test.alc_may_be_off());
+
+        // One cell alternating, the mean climbing 2 per second
+        test.switched(Ok(()), at(2000));
+        test.update(&frame(12, 20), at(3000));
+        test.update(&frame(16, 18), at(3500));
+        assert_eq!(test.update(&frame(14, 22), at(4000)), Some(true));
+        let AbTest::Done { a, b } = &test else {
+            panic!("phase B didn't finish: {:?}", test);
+        };
+        assert_eq!(b.mean, 17.0);
+        assert!((b.drift - 2.0).abs() < 1e-9);
+        assert!(b.noise > 0.0);
+        assert!(report(a, b).contains("Mean                  15.000      17.000      +2.000"));
+        // Switching back on doesn't touch the result
+        test.switched(Ok(()), at(4100));
+        assert!(matches!(test, AbTest::Done { .. }));
+
+        let mut test = AbTest::Setup { seconds: 1 };
+        test.start();
+        test.switched(Err("no writes".into()), t0);
+        assert_eq!(
+            test,
+            AbTest::Failed("Couldn't switch ALC: no writes".into())
+        );
+
+        let mut test = AbTest::Setup { seconds: 1 };
+        test.start();
+        test.switched(Ok(()), t0);
+        test.update(&frame(1, 2), at(1000));
+        test.update(&HeatmapFrame::default(), at(1010));
+        assert!(matches!(test, AbTest::Failed(_)));
+    }
+}
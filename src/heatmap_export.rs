@@ -0,0 +1,171 @@
+//! The last heatmap frames as an animated PNG, colored as on screen, for
+//! attaching capacitive blob behavior to firmware bug reports.
+//!
+//! Every frame is scaled by the largest magnitude in the whole clip rather
+//! than its own, so a blob growing or fading looks like it does. Frames are
+//! kept as displayed, after orientation; each cell becomes a `CELL_PX`
+//! square and each frame stays up until the next one arrived.
+
+use crate::render::{Colormap, HeatmapLut, HeatmapScale};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use tapview_core::heatmap::HeatmapFrame;
+
+/// Side of a heatmap cell in the exported image, in pixels.
+const CELL_PX: usize = 12;
+
+#[derive(Default)]
+pub struct HeatmapHistory {
+    frames: VecDeque<(Instant, HeatmapFrame)>,
+}
+
+impl HeatmapHistory {
+    /// About ten seconds at the usual frame rates
+    pub const CAPACITY: usize = 600;
+
+    /// Keep a copy of `frame`, reusing the oldest one's buffer once full.
+    pub fn push(&mut self, frame: &HeatmapFrame) {
+        let mut copy = if self.frames.len() >= Self::CAPACITY {
+            self.frames.pop_front().map(|(_, f)| f).unwrap_or_default()
+        } else {
+            HeatmapFrame::default()
+        };
+        copy.rows = frame.rows;
+        copy.cols = frame.cols;
+        copy.data.clear();
+        copy.data.extend_from_slice(&frame.data);
+        self.frames.push_back((Instant::now(), copy));
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Write up to the last `count` frames as an endlessly looping APNG.
+    /// Frames from before the matrix last changed shape (e.g. when rotated)
+    /// are left out. Returns how many frames were written.
+    pub fn write_apng(
+        &self,
+        out: impl Write,
+        count: usize,
+        colormap: Colormap,
+        scale: HeatmapScale,
+    ) -> io::Result<usize> {
+        let Some((_, newest)) = self.frames.back() else {
+            return Err(io::Error::other("no heatmap frames yet"));
+        };
+        let (rows, cols) = (newest.rows, newest.cols);
+        if rows == 0 || cols == 0 {
+            return Err(io::Error::other("empty heatmap frame"));
+        }
+        let mut clip: Vec<_> = self
+            .frames
+            .iter()
+            .rev()
+            .take(count)
+            .take_while(|(_, f)| f.rows == rows && f.cols == cols)
+            .collect();
+        clip.reverse();
+
+        let max_abs = clip.iter().map(|(_, f)| f.max_abs()).max().unwrap_or(0);
+        let lut = HeatmapLut::new(colormap, scale);
+        let (width, height) = (cols * CELL_PX, rows * CELL_PX);
+
+        let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(clip.len() as u32, 0)?;
+        let mut writer = encoder.write_header()?;
+        let mut pixels = vec![0u8; width * height * 3];
+        for (i, (at, frame)) in clip.iter().enumerate() {
+            // The last frame repeats the delay before it
+            let delay = match (clip.get(i + 1), i.checked_sub(1).map(|j| clip[j].0)) {
+                (Some((next, _)), _) => next.duration_since(*at),
+                (None, Some(prev)) => at.duration_since(prev),
+                (None, None) => Duration::from_millis(100),
+            };
+            writer.set_frame_delay(delay.as_millis().clamp(1, u16::MAX as u128) as u16, 1000)?;
+
+            for (y, line) in pixels.chunks_exact_mut(width * 3).enumerate() {
+                for (x, pixel) in line.chunks_exact_mut(3).enumerate() {
+                    let cell = (y / CELL_PX) * cols + x / CELL_PX;
+                    let value = frame.data.get(cell).copied().unwrap_or(0);
+                    let color = lut.color(value, max_abs);
+                    pixel.copy_from_slice(&[color.r(), color.g(), color.b()]);
+                }
+            }
+            writer.write_image_data(&pixels)?;
+        }
+        writer.finish()?;
+        Ok(clip.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_recent_frames_of_the_current_size() {
+        let mut history = HeatmapHistory::default();
+        let frame = |rows, cols, value| HeatmapFrame {
+            rows,
+            cols,
+            data: vec![value; rows * cols],
+        };
+        history.push(&frame(3, 3, 5));
+        for value in [10, -20, 30] {
+            history.push(&frame(2, 3, value));
+        }
+
+        let mut out = Vec::new();
+        assert_eq!(
+            history
+                .write_apng(&mut out, 10, Colormap::Viridis, HeatmapScale::default())
+                .unwrap(),
+            3
+        );
+        let decoder = png::Decoder::new(std::io::Cursor::new(out));
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (36, 24));
+        assert_eq!(info.animation_control().unwrap().num_frames, 3);
+
+        let mut out = Vec::new();
+        assert_eq!(
+            history
+                .write_apng(&mut out, 2, Colormap::Viridis, HeatmapScale::default())
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn diverging_colormap_keeps_the_sign() {
+        let mut history = HeatmapHistory::default();
+        history.push(&HeatmapFrame {
+            rows: 1,
+            cols: 3,
+            data: vec![-100, 0, 100],
+        });
+        let mut out = Vec::new();
+        history
+            .write_apng(&mut out, 1, Colormap::Diverging, HeatmapScale::default())
+            .unwrap();
+        let mut reader = png::Decoder::new(std::io::Cursor::new(out))
+            .read_info()
+            .unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut pixels).unwrap();
+        let cell = |col: usize| &pixels[col * CELL_PX * 3..][..3];
+        let (negative, zero, positive) = (cell(0), cell(1), cell(2));
+        assert!(negative[2] > negative[0]);
+        assert_eq!(zero, [255, 255, 255]);
+        assert!(positive[0] > positive[2]);
+    }
+}
@@ -0,0 +1,213 @@
+//! Watches the heatmap for what's worth a notification when tapview runs in
+//! the background: firmware calibration episodes and stuck cells.
+//!
+//! A recalibration re-baselines the whole panel at once, so while nothing
+//! touches it the average change between frames jumps far above the usual
+//! noise; the episode ends once `QUIET_FRAMES` frames in a row are back to
+//! noise. A cell is stuck when it keeps the same value for `STUCK_FRAMES`
+//! frames in which most of the other cells moved.
+
+use std::fmt;
+use tapview_core::heatmap::HeatmapFrame;
+
+/// Smallest average change per cell between idle frames that counts as a
+/// calibration step...
+const CALIBRATION_MIN_STEP: f32 = 20.0;
+/// ...and how far above the usual noise it has to be.
+const CALIBRATION_NOISE_FACTOR: f32 = 8.0;
+/// Frames back at noise level that end an episode.
+const QUIET_FRAMES: u32 = 30;
+/// Frames a cell has to hold its value for to count as stuck, about ten
+/// seconds at the usual frame rates.
+const STUCK_FRAMES: u32 = 600;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeatmapEvent {
+    CalibrationStarted,
+    /// Lasted this many frames
+    CalibrationEnded {
+        frames: u32,
+    },
+    StuckCell {
+        row: usize,
+        col: usize,
+        value: i16,
+    },
+}
+
+impl fmt::Display for HeatmapEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeatmapEvent::CalibrationStarted => write!(f, "Firmware calibration started"),
+            HeatmapEvent::CalibrationEnded { frames } => {
+                write!(f, "Firmware calibration ended after {} frames", frames)
+            }
+            HeatmapEvent::StuckCell { row, col, value } => write!(
+                f,
+                "Heatmap cell at row {}, column {} is stuck at {}",
+                row, col, value
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct HeatmapWatch {
+    last: Option<HeatmapFrame>,
+    /// Running average change per cell between idle frames
+    noise: Option<f32>,
+    /// Frames into the current calibration episode and quiet ones since its
+    /// last step
+    calibration: Option<(u32, u32)>,
+    /// Per cell: frames it held its value while the others moved
+    unchanged: Vec<u32>,
+    stuck: Vec<bool>,
+}
+
+impl HeatmapWatch {
+    /// Look at the next frame; `touching` is whether any contact is down.
+    pub fn push(&mut self, frame: &HeatmapFrame, touching: bool) -> Vec<HeatmapEvent> {
+        let mut events = Vec::new();
+        let Some(last) = self
+            .last
+            .as_ref()
+            .filter(|l| (l.rows, l.cols) == (frame.rows, frame.cols) && !frame.data.is_empty())
+        else {
+            *self = Self {
+                last: Some(frame.clone()),
+                unchanged: vec![0; frame.data.len()],
+                stuck: vec![false; frame.data.len()],
+                ..Self::default()
+            };
+            return events;
+        };
+
+        let changed = frame
+            .data
+            .iter()
+            .zip(&last.data)
+            .filter(|(a, b)| a != b)
+            .count();
+        // Most cells moved: ones that didn't are suspect
+        if changed * 2 > frame.data.len() {
+            for (i, (a, b)) in frame.data.iter().zip(&last.data).enumerate() {
+                if a != b {
+                    self.unchanged[i] = 0;
+                    self.stuck[i] = false;
+                    continue;
+                }
+                self.unchanged[i] += 1;
+                if self.unchanged[i] >= STUCK_FRAMES && !self.stuck[i] {
+                    self.stuck[i] = true;
+                    events.push(HeatmapEvent::StuckCell {
+                        row: i / frame.cols,
+                        col: i % frame.cols,
+                        value: *a,
+                    });
+                }
+            }
+        }
+
+        if !touching {
+            let step = frame
+                .data
+                .iter()
+                .zip(&last.data)
+                .map(|(&a, &b)| (a as f32 - b as f32).abs())
+                .sum::<f32>()
+                / frame.data.len() as f32;
+            let noise = *self.noise.get_or_insert(step);
+            let calibrating = step >= CALIBRATION_MIN_STEP.max(noise * CALIBRATION_NOISE_FACTOR);
+            self.calibration = match (self.calibration, calibrating) {
+                (None, true) => {
+                    events.push(HeatmapEvent::CalibrationStarted);
+                    Some((1, 0))
+                }
+                (Some((frames, _)), true) => Some((frames + 1, 0)),
+                (Some((frames, quiet)), false) if quiet + 1 >= QUIET_FRAMES => {
+                    events.push(HeatmapEvent::CalibrationEnded {
+                        frames: frames + 1 - QUIET_FRAMES,
+                    });
+                    None
+                }
+                (Some((frames, quiet)), false) => Some((frames + 1, quiet + 1)),
+                (None, false) => None,
+            };
+            // Steps of an episode would teach it the wrong noise
+            if !calibrating {
+                self.noise = Some(noise * 0.95 + step * 0.05);
+            }
+        }
+
+        self.last
+            .as_mut()
+            .expect("checked above")
+            .data
+            .copy_from_slice(&frame.data);
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(data: Vec<i16>) -> HeatmapFrame {
+        HeatmapFrame {
+            rows: 2,
+            cols: 3,
+            data,
+        }
+    }
+
+    #[test]
+    fn reports_a_calibration_episode_once() {
+        let mut watch = HeatmapWatch::default();
+        let noisy = |n: i16| frame(vec![n % 2, 1 - n % 2, 0, 1, n % 3, 2]);
+        let mut events = Vec::new();
+        for n in 0..10 {
+            events.extend(watch.push(&noisy(n), false));
+        }
+        // The baseline drops by 100, over two frames
+        for _ in 0..2 {
+            events.extend(watch.push(&frame(vec![-100; 6]), false));
+            events.extend(watch.push(&frame(vec![-200; 6]), false));
+        }
+        // A touch isn't calibration
+        events.extend(watch.push(&frame(vec![500; 6]), true));
+        events.extend(watch.push(&frame(vec![-200; 6]), true));
+        for n in 0..QUIET_FRAMES as i16 {
+            events.extend(watch.push(&frame(vec![-200 + n % 2; 6]), false));
+        }
+        assert_eq!(
+            events,
+            [
+                HeatmapEvent::CalibrationStarted,
+                HeatmapEvent::CalibrationEnded { frames: 4 }
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_a_cell_that_holds_while_the_rest_move() {
+        let mut watch = HeatmapWatch::default();
+        let mut events = Vec::new();
+        for n in 0..=STUCK_FRAMES as i16 {
+            let v = n % 2;
+            events.extend(watch.push(&frame(vec![v, v, 42, v, v, v]), true));
+        }
+        assert_eq!(
+            events,
+            [HeatmapEvent::StuckCell {
+                row: 0,
+                col: 2,
+                value: 42
+            }]
+        );
+        // Frames that barely change don't count against a cell
+        let mut still = HeatmapWatch::default();
+        for _ in 0..=STUCK_FRAMES {
+            assert!(still.push(&frame(vec![1; 6]), true).is_empty());
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Ink mode: every contact's full trajectory kept as a polyline until cleared.
+//!
+//! Points are stored in device units so the drawing survives window resizes.
+//! A new stroke starts whenever a slot gets a new tracking ID.
+
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+pub struct InkStroke {
+    pub slot: usize,
+    pub tool_type: i32,
+    pub points: Vec<(i32, i32)>,
+}
+
+#[derive(Default)]
+pub struct Ink {
+    pub strokes: Vec<InkStroke>,
+    /// Per slot: tracking ID and index into `strokes` of the stroke in progress.
+    open: [Option<(i32, usize)>; MAX_TOUCH_POINTS],
+}
+
+impl Ink {
+    /// Extend strokes with the current touches.
+    pub fn update(&mut self, touches: &[TouchData; MAX_TOUCH_POINTS]) {
+        for (slot, touch) in touches.iter().enumerate() {
+            if !touch.used {
+                self.open[slot] = None;
+                continue;
+            }
+            let point = (touch.position_x, touch.position_y);
+            match self.open[slot] {
+                Some((id, index)) if id == touch.tracking_id => {
+                    let points = &mut self.strokes[index].points;
+                    if points.last() != Some(&point) {
+                        points.push(point);
+                    }
+                }
+                _ => {
+                    self.open[slot] = Some((touch.tracking_id, self.strokes.len()));
+                    self.strokes.push(InkStroke {
+                        slot,
+                        tool_type: touch.tool_type,
+                        points: vec![point],
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(tracking_id: i32, x: i32, y: i32) -> TouchData {
+        TouchData {
+            used: true,
+            tracking_id,
+            position_x: x,
+            position_y: y,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn new_tracking_id_starts_new_stroke() {
+        let mut ink = Ink::default();
+        let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+
+        touches[0] = touch(1, 10, 10);
+        ink.update(&touches);
+        ink.update(&touches);
+        touches[0] = touch(1, 20, 10);
+        ink.update(&touches);
+        assert_eq!(ink.strokes.len(), 1);
+        assert_eq!(ink.strokes[0].points, vec![(10, 10), (20, 10)]);
+
+        touches[0] = touch(2, 50, 50);
+        ink.update(&touches);
+        assert_eq!(ink.strokes.len(), 2);
+        assert_eq!(ink.strokes[1].points, vec![(50, 50)]);
+
+        // Lift and re-touch with the same ID still starts a fresh stroke
+        touches[0].used = false;
+        ink.update(&touches);
+        touches[0] = touch(2, 60, 60);
+        ink.update(&touches);
+        assert_eq!(ink.strokes.len(), 3);
+
+        ink.clear();
+        assert!(ink.strokes.is_empty());
+    }
+}
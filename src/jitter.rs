@@ -0,0 +1,203 @@
+//! Stationary-finger jitter test: one finger held still for a fixed time,
+//! reported positions summarized per axis.
+//!
+//! The first half second after the finger lands is skipped so the contact
+//! can settle. Lifting the finger or adding another one aborts the run.
+
+use std::time::{Duration, Instant};
+
+pub const TEST_DURATION: Duration = Duration::from_secs(10);
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// Per-axis statistics in device units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JitterResult {
+    pub samples: Vec<(i32, i32)>,
+    pub mean: (f32, f32),
+    pub std_dev: (f32, f32),
+    pub peak_to_peak: (i32, i32),
+}
+
+impl JitterResult {
+    fn from_samples(samples: Vec<(i32, i32)>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let n = samples.len() as f32;
+        let (sx, sy) = samples.iter().fold((0.0, 0.0), |(ax, ay), &(x, y)| {
+            (ax + x as f32, ay + y as f32)
+        });
+        let mean = (sx / n, sy / n);
+        let (vx, vy) = samples.iter().fold((0.0, 0.0), |(ax, ay), &(x, y)| {
+            (
+                ax + (x as f32 - mean.0).powi(2),
+                ay + (y as f32 - mean.1).powi(2),
+            )
+        });
+        let range = |axis: fn(&(i32, i32)) -> i32| {
+            let max = samples.iter().map(axis).max().unwrap_or(0);
+            let min = samples.iter().map(axis).min().unwrap_or(0);
+            max - min
+        };
+        Some(Self {
+            mean,
+            std_dev: ((vx / n).sqrt(), (vy / n).sqrt()),
+            peak_to_peak: (range(|s| s.0), range(|s| s.1)),
+            samples,
+        })
+    }
+
+    /// The numbers as a JSON object; millimeter values are included when the
+    /// resolution is known.
+    pub fn to_json(&self, resolution: Option<(f32, f32)>) -> String {
+        let mut fields = vec![
+            format!("\"samples\":{}", self.samples.len()),
+            format!("\"duration_s\":{}", TEST_DURATION.as_secs()),
+            format!("\"mean\":[{},{}]", self.mean.0, self.mean.1),
+            format!("\"std_dev\":[{},{}]", self.std_dev.0, self.std_dev.1),
+            format!(
+                "\"peak_to_peak\":[{},{}]",
+                self.peak_to_peak.0, self.peak_to_peak.1
+            ),
+        ];
+        if let Some((rx, ry)) = resolution {
+            fields.push(format!(
+                "\"std_dev_mm\":[{},{}]",
+                self.std_dev.0 / rx,
+                self.std_dev.1 / ry
+            ));
+            fields.push(format!(
+                "\"peak_to_peak_mm\":[{},{}]",
+                self.peak_to_peak.0 as f32 / rx,
+                self.peak_to_peak.1 as f32 / ry
+            ));
+        }
+        let points: Vec<String> = self
+            .samples
+            .iter()
+            .map(|(x, y)| format!("[{},{}]", x, y))
+            .collect();
+        fields.push(format!("\"positions\":[{}]", points.join(",")));
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JitterTest {
+    /// Waiting for exactly one finger to land
+    Waiting,
+    Running {
+        tracking_id: i32,
+        start: Instant,
+        samples: Vec<(i32, i32)>,
+    },
+    Done(JitterResult),
+    Failed(&'static str),
+}
+
+/// What the results window asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JitterTestAction {
+    Save,
+    Restart,
+}
+
+impl JitterTest {
+    /// Feed the active contacts (tracking ID and position) at `time`.
+    pub fn update(&mut self, contacts: &[(i32, i32, i32)], time: Instant) {
+        match self {
+            JitterTest::Waiting => {
+                if let [(tracking_id, _, _)] = contacts {
+                    *self = JitterTest::Running {
+                        tracking_id: *tracking_id,
+                        start: time,
+                        samples: Vec::new(),
+                    };
+                }
+            }
+            JitterTest::Running {
+                tracking_id,
+                start,
+                samples,
+            } => {
+                let [(id, x, y)] = contacts else {
+                    *self = JitterTest::Failed(if contacts.is_empty() {
+                        "Finger lifted before the end"
+                    } else {
+                        "More than one finger touched"
+                    });
+                    return;
+                };
+                if *id != *tracking_id {
+                    *self = JitterTest::Failed("Finger lifted before the end");
+                    return;
+                }
+                let elapsed = time.saturating_duration_since(*start);
+                if elapsed >= SETTLE + TEST_DURATION {
+                    *self = match JitterResult::from_samples(std::mem::take(samples)) {
+                        Some(result) => JitterTest::Done(result),
+                        None => JitterTest::Failed("No reports while holding"),
+                    };
+                } else if elapsed >= SETTLE {
+                    samples.push((*x, *y));
+                }
+            }
+            JitterTest::Done(_) | JitterTest::Failed(_) => {}
+        }
+    }
+
+    /// Seconds left in a running test, counting the settle time.
+    pub fn remaining(&self, now: Instant) -> Option<f32> {
+        match self {
+            JitterTest::Running { start, .. } => Some(
+                (SETTLE + TEST_DURATION)
+                    .saturating_sub(now.saturating_duration_since(*start))
+                    .as_secs_f32(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_held_finger() {
+        let t0 = Instant::now();
+        let at = |ms: u64| t0 + Duration::from_millis(ms);
+        let mut test = JitterTest::Waiting;
+        test.update(&[], at(0));
+        test.update(&[(7, 100, 200)], at(0));
+        // Still settling
+        test.update(&[(7, 150, 250)], at(100));
+        test.update(&[(7, 100, 200)], at(600));
+        test.update(&[(7, 102, 200)], at(700));
+        test.update(&[(7, 102, 200)], at(10_600));
+        let JitterTest::Done(result) = test else {
+            panic!("test didn't finish");
+        };
+        assert_eq!(result.samples, vec![(100, 200), (102, 200)]);
+        assert_eq!(result.mean, (101.0, 200.0));
+        assert_eq!(result.std_dev, (1.0, 0.0));
+        assert_eq!(result.peak_to_peak, (2, 0));
+        assert!(result
+            .to_json(Some((10.0, 10.0)))
+            .contains("\"peak_to_peak_mm\":[0.2,0]"));
+    }
+
+    #[test]
+    fn lifting_or_second_finger_fails() {
+        let t0 = Instant::now();
+        let mut test = JitterTest::Waiting;
+        test.update(&[(1, 0, 0)], t0);
+        test.update(&[(1, 0, 0), (2, 5, 5)], t0);
+        assert_eq!(test, JitterTest::Failed("More than one finger touched"));
+
+        let mut test = JitterTest::Waiting;
+        test.update(&[(1, 0, 0)], t0);
+        test.update(&[], t0);
+        assert_eq!(test, JitterTest::Failed("Finger lifted before the end"));
+    }
+}
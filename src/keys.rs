@@ -0,0 +1,253 @@
+//! Configurable keyboard shortcuts.
+//!
+//! Bindings are stored in the config file's `[keys]` table as
+//! `action = "Key"` or `action = "Shift+Key"` (egui key names); an empty
+//! string leaves the action unbound. Actions missing from the file keep their
+//! default binding.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Grab,
+    Ungrab,
+    ToggleInk,
+    ClearInk,
+    ToggleSlotTable,
+    ToggleRates,
+    ToggleDwell,
+    ToggleLog,
+    TogglePlots,
+    JitterTest,
+    LinearityTest,
+    EdgeTest,
+    PressureTest,
+    PalmTest,
+    ResolutionTest,
+    OrientationTest,
+    RegisterWatch,
+    AlcAbTest,
+    DescriptorTree,
+    ClearView,
+    ResetExtents,
+    PlayPause,
+    StepBack,
+    StepForward,
+    ClickThrough,
+}
+
+impl Action {
+    pub const ALL: [Action; 25] = [
+        Action::Grab,
+        Action::Ungrab,
+        Action::ToggleInk,
+        Action::ClearInk,
+        Action::ToggleSlotTable,
+        Action::ToggleRates,
+        Action::ToggleDwell,
+        Action::ToggleLog,
+        Action::TogglePlots,
+        Action::JitterTest,
+        Action::LinearityTest,
+        Action::EdgeTest,
+        Action::PressureTest,
+        Action::PalmTest,
+        Action::ResolutionTest,
+        Action::OrientationTest,
+        Action::RegisterWatch,
+        Action::AlcAbTest,
+        Action::DescriptorTree,
+        Action::ClearView,
+        Action::ResetExtents,
+        Action::PlayPause,
+        Action::StepBack,
+        Action::StepForward,
+        Action::ClickThrough,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Grab => "Grab touchpad",
+            Action::Ungrab => "Release grab",
+            Action::ToggleInk => "Toggle ink mode",
+            Action::ClearInk => "Clear ink",
+            Action::ToggleSlotTable => "Toggle slot table",
+            Action::ToggleRates => "Toggle frame/event rate overlay",
+            Action::ToggleDwell => "Toggle dwell map",
+            Action::ToggleLog => "Toggle log console",
+            Action::TogglePlots => "Toggle pressure/size plots",
+            Action::JitterTest => "Start/stop jitter test",
+            Action::LinearityTest => "Start/stop linearity test",
+            Action::EdgeTest => "Start/stop edge test",
+            Action::PressureTest => "Start/stop pressure test",
+            Action::PalmTest => "Start/stop palm rejection test",
+            Action::ResolutionTest => "Start/stop resolution test",
+            Action::OrientationTest => "Start/stop orientation calibration",
+            Action::RegisterWatch => "Show/hide the firmware register watch",
+            Action::AlcAbTest => "Start/stop ALC A/B heatmap capture",
+            Action::DescriptorTree => "Show/hide the HID report descriptor",
+            Action::ClearView => "Clear trails and ink",
+            Action::ResetExtents => "Also reset learned extents",
+            Action::PlayPause => "Play/pause (playback)",
+            Action::StepBack => "Step -100 ms (playback)",
+            Action::StepForward => "Step +100 ms (playback)",
+            Action::ClickThrough => "Toggle click-through (--overlay)",
+        }
+    }
+
+    fn default_binding(self) -> Option<Binding> {
+        use egui::Key;
+        let (key, shift) = match self {
+            Action::Grab => (Key::Enter, false),
+            Action::Ungrab => (Key::Escape, false),
+            Action::ToggleInk => (Key::I, false),
+            Action::ClearInk => (Key::C, false),
+            Action::ToggleSlotTable => (Key::T, false),
+            Action::ToggleRates => (Key::F, false),
+            Action::ToggleDwell => (Key::D, false),
+            Action::ToggleLog => (Key::L, false),
+            Action::TogglePlots => (Key::O, false),
+            Action::JitterTest => (Key::J, false),
+            Action::LinearityTest => (Key::S, false),
+            Action::EdgeTest => (Key::E, false),
+            Action::PressureTest => (Key::P, false),
+            Action::PalmTest => (Key::R, false),
+            Action::ResolutionTest => (Key::M, false),
+            Action::OrientationTest => (Key::N, false),
+            Action::RegisterWatch => (Key::W, false),
+            Action::AlcAbTest => (Key::B, false),
+            Action::DescriptorTree => (Key::H, false),
+            Action::ClearView => (Key::X, false),
+            Action::ResetExtents => (Key::X, true),
+            Action::PlayPause => (Key::Space, false),
+            Action::StepBack => (Key::ArrowLeft, false),
+            Action::StepForward => (Key::ArrowRight, false),
+            Action::ClickThrough => (Key::K, false),
+        };
+        Some(Binding { key, shift })
+    }
+}
+
+/// A key with an optional Shift modifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub key: egui::Key,
+    pub shift: bool,
+}
+
+impl Binding {
+    fn parse(s: &str) -> Option<Self> {
+        let (shift, name) = match s.strip_prefix("Shift+") {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        egui::Key::from_name(name).map(|key| Binding { key, shift })
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.key.name())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<Action, String>", into = "BTreeMap<Action, String>")]
+pub struct KeyBindings(BTreeMap<Action, Option<Binding>>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(
+            Action::ALL
+                .iter()
+                .map(|&a| (a, a.default_binding()))
+                .collect(),
+        )
+    }
+}
+
+impl From<BTreeMap<Action, String>> for KeyBindings {
+    fn from(map: BTreeMap<Action, String>) -> Self {
+        let mut bindings = Self::default();
+        for (action, text) in map {
+            if text.is_empty() {
+                bindings.0.insert(action, None);
+            } else if let Some(binding) = Binding::parse(&text) {
+                bindings.0.insert(action, Some(binding));
+            } else {
+                log_warn!(
+                    target: "settings", "unknown key {:?} for {:?}, using default",
+                    text,
+                    action
+                );
+            }
+        }
+        bindings
+    }
+}
+
+impl From<KeyBindings> for BTreeMap<Action, String> {
+    fn from(bindings: KeyBindings) -> Self {
+        bindings
+            .0
+            .into_iter()
+            .map(|(a, b)| (a, b.map(|b| b.to_string()).unwrap_or_default()))
+            .collect()
+    }
+}
+
+impl KeyBindings {
+    pub fn get(&self, action: Action) -> Option<Binding> {
+        self.0.get(&action).copied().flatten()
+    }
+
+    pub fn set(&mut self, action: Action, binding: Option<Binding>) {
+        self.0.insert(action, binding);
+    }
+
+    /// Whether `action`'s key was pressed this frame with exactly its Shift state.
+    pub fn pressed(&self, action: Action, input: &egui::InputState) -> bool {
+        self.get(action)
+            .is_some_and(|b| input.key_pressed(b.key) && input.modifiers.shift == b.shift)
+    }
+
+    /// Human-readable binding for `action`, e.g. for hints.
+    pub fn describe(&self, action: Action) -> String {
+        self.get(action)
+            .map(|b| b.to_string())
+            .unwrap_or_else(|| "(unbound)".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_formats_bindings() {
+        let b = Binding::parse("Shift+X").unwrap();
+        assert_eq!(b.key, egui::Key::X);
+        assert!(b.shift);
+        assert_eq!(b.to_string(), "Shift+X");
+        assert_eq!(Binding::parse("Enter").unwrap().to_string(), "Enter");
+        assert!(Binding::parse("NotAKey").is_none());
+    }
+
+    #[test]
+    fn partial_map_keeps_defaults() {
+        let mut map = BTreeMap::new();
+        map.insert(Action::Grab, "G".to_string());
+        map.insert(Action::Ungrab, String::new());
+        map.insert(Action::ToggleInk, "bogus".to_string());
+        let bindings = KeyBindings::from(map);
+        assert_eq!(bindings.describe(Action::Grab), "G");
+        assert_eq!(bindings.get(Action::Ungrab), None);
+        assert_eq!(bindings.describe(Action::ToggleInk), "I");
+        assert_eq!(bindings.describe(Action::ClearView), "X");
+    }
+}
@@ -1,29 +1,29 @@
 //! Libinput library backend for reading pointer, scroll, and gesture events.
 
-use crate::libinput_state::{LibinputEvent, ScrollSource};
-use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
-use std::path::Path;
+use crate::backend_error::{report, Backend, BackendError};
+use crate::libinput_state::{
+    AccelProfile, ClickMethod, LibinputCommand, LibinputConfig, LibinputDeviceInfo, LibinputEvent,
+    ScrollSource, SwitchKind, EVENT_CHANNEL_CAPACITY,
+};
+use std::os::unix::io::{AsRawFd, OwnedFd};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
-use std::thread;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use tapview_core::channel::{self, BoundedReceiver, BoundedSender};
+use tapview_core::seat;
+use tapview_core::shutdown::Shutdown;
 
 use input::event::gesture::{GestureEvent, GestureEventCoordinates, GesturePinchEventTrait};
 use input::event::pointer::{Axis, ButtonState, PointerEvent, PointerScrollEvent};
-use input::{Event, Libinput, LibinputInterface};
+use input::event::switch::{Switch, SwitchEvent, SwitchState};
+use input::{Device, DeviceCapability, Event, Libinput, LibinputInterface, ScrollMethod};
 
 struct Interface;
 
 impl LibinputInterface for Interface {
     fn open_restricted(&mut self, path: &Path, flags: i32) -> Result<OwnedFd, i32> {
-        let path_cstr = match std::ffi::CString::new(path.as_os_str().as_encoded_bytes()) {
-            Ok(c) => c,
-            Err(_) => return Err(libc::EINVAL),
-        };
-        let fd = unsafe { libc::open(path_cstr.as_ptr(), flags) };
-        if fd < 0 {
-            Err(unsafe { *libc::__errno_location() })
-        } else {
-            Ok(unsafe { OwnedFd::from_raw_fd(fd) })
-        }
+        seat::open(path, flags).map_err(|e| e.raw_os_error().unwrap_or(libc::EINVAL))
     }
 
     fn close_restricted(&mut self, fd: OwnedFd) {
@@ -32,23 +32,380 @@ impl LibinputInterface for Interface {
 }
 
 /// Spawn a thread that reads libinput events from the given device and sends
-/// structured events over the returned channel.
-pub fn spawn_libinput_thread(device_path: &Path) -> mpsc::Receiver<LibinputEvent> {
-    let (tx, rx) = mpsc::channel();
+/// structured events over the returned channel. Configuration changes sent on
+/// the returned command channel are applied to the device. The thread exits
+/// within a poll interval of `shutdown` being requested.
+pub fn spawn_libinput_thread(
+    device_path: &Path,
+    shutdown: Shutdown,
+    errors: BoundedSender<BackendError>,
+) -> (
+    BoundedReceiver<LibinputEvent>,
+    mpsc::Sender<LibinputCommand>,
+    JoinHandle<()>,
+) {
+    let (tx, rx) = channel::bounded(EVENT_CHANNEL_CAPACITY);
+    let (cmd_tx, cmd_rx) = mpsc::channel();
     let path = device_path.to_path_buf();
 
-    thread::spawn(move || {
-        if let Err(e) = run_libinput_loop(&path, &tx) {
-            eprintln!("libinput backend error: {}", e);
+    let thread = thread::spawn(move || {
+        if let Err(e) = run_libinput_loop(&path, &tx, &cmd_rx, &shutdown) {
+            let message = format!("libinput backend error: {}", e);
+            report(&errors, BackendError::new(Backend::Libinput, message));
         }
     });
 
-    rx
+    (rx, cmd_tx, thread)
+}
+
+fn accel_profile_to_input(profile: AccelProfile) -> input::AccelProfile {
+    match profile {
+        AccelProfile::Flat => input::AccelProfile::Flat,
+        AccelProfile::Adaptive => input::AccelProfile::Adaptive,
+    }
+}
+
+fn accel_profile_from_input(profile: input::AccelProfile) -> Option<AccelProfile> {
+    match profile {
+        input::AccelProfile::Flat => Some(AccelProfile::Flat),
+        input::AccelProfile::Adaptive => Some(AccelProfile::Adaptive),
+        _ => None,
+    }
+}
+
+fn click_method_to_input(method: ClickMethod) -> input::ClickMethod {
+    match method {
+        ClickMethod::ButtonAreas => input::ClickMethod::ButtonAreas,
+        ClickMethod::Clickfinger => input::ClickMethod::Clickfinger,
+    }
+}
+
+fn click_method_from_input(method: input::ClickMethod) -> Option<ClickMethod> {
+    match method {
+        input::ClickMethod::ButtonAreas => Some(ClickMethod::ButtonAreas),
+        input::ClickMethod::Clickfinger => Some(ClickMethod::Clickfinger),
+        _ => None,
+    }
+}
+
+/// Snapshot the device's current configuration.
+fn read_config(device: &Device) -> LibinputConfig {
+    let has_tap = device.config_tap_finger_count() > 0;
+    LibinputConfig {
+        tap: has_tap.then(|| device.config_tap_enabled()),
+        tap_drag: has_tap.then(|| device.config_tap_drag_enabled()),
+        natural_scroll: device
+            .config_scroll_has_natural_scroll()
+            .then(|| device.config_scroll_natural_scroll_enabled()),
+        accel_profile: device
+            .config_accel_profile()
+            .and_then(accel_profile_from_input),
+        accel_profiles: device
+            .config_accel_profiles()
+            .into_iter()
+            .filter_map(accel_profile_from_input)
+            .collect(),
+        dwt: device
+            .config_dwt_is_available()
+            .then(|| device.config_dwt_enabled()),
+        click_method: device
+            .config_click_method()
+            .and_then(click_method_from_input),
+        click_methods: device
+            .config_click_methods()
+            .into_iter()
+            .filter_map(click_method_from_input)
+            .collect(),
+    }
+}
+
+/// "enabled"/"disabled", noting the default when it differs, or "n/a".
+fn describe_toggle(available: bool, enabled: bool, default: bool) -> String {
+    let word = |on: bool| if on { "enabled" } else { "disabled" };
+    if !available {
+        "n/a".to_string()
+    } else if enabled == default {
+        word(enabled).to_string()
+    } else {
+        format!("{} (default {})", word(enabled), word(default))
+    }
+}
+
+/// Space-separated names of the supported entries, the current one marked `*`.
+fn describe_methods<T: PartialEq>(
+    names: &[(T, &str)],
+    supported: &[T],
+    current: Option<T>,
+) -> String {
+    let listed: Vec<String> = names
+        .iter()
+        .filter(|(m, _)| supported.contains(m))
+        .map(|(m, name)| {
+            if current.as_ref() == Some(m) {
+                format!("*{}", name)
+            } else {
+                name.to_string()
+            }
+        })
+        .collect();
+    if listed.is_empty() {
+        "none".to_string()
+    } else {
+        listed.join(" ")
+    }
+}
+
+/// Build the capability/config report, following `libinput list-devices`.
+fn read_device_info(device: &Device) -> LibinputDeviceInfo {
+    let mut rows = vec![
+        ("Device", device.name().to_string()),
+        ("Kernel", format!("/dev/input/{}", device.sysname())),
+        (
+            "Id",
+            format!("{:04x}:{:04x}", device.id_vendor(), device.id_product()),
+        ),
+        (
+            "Size",
+            device
+                .size()
+                .map(|(w, h)| format!("{:.0}x{:.0}mm", w, h))
+                .unwrap_or_else(|| "n/a".to_string()),
+        ),
+    ];
+
+    let capabilities: Vec<&str> = [
+        (DeviceCapability::Keyboard, "keyboard"),
+        (DeviceCapability::Pointer, "pointer"),
+        (DeviceCapability::Touch, "touch"),
+        (DeviceCapability::Gesture, "gesture"),
+        (DeviceCapability::Switch, "switch"),
+    ]
+    .into_iter()
+    .filter(|(cap, _)| device.has_capability(*cap))
+    .map(|(_, name)| name)
+    .collect();
+    rows.push(("Capabilities", capabilities.join(" ")));
+
+    let tap_fingers = device.config_tap_finger_count();
+    let has_tap = tap_fingers > 0;
+    rows.push(("Tap fingers", tap_fingers.to_string()));
+    rows.push((
+        "Tap-to-click",
+        describe_toggle(
+            has_tap,
+            device.config_tap_enabled(),
+            device.config_tap_default_enabled(),
+        ),
+    ));
+    rows.push((
+        "Tap-and-drag",
+        describe_toggle(
+            has_tap,
+            device.config_tap_drag_enabled(),
+            device.config_tap_default_drag_enabled(),
+        ),
+    ));
+    rows.push((
+        "Tap drag lock",
+        describe_toggle(
+            has_tap,
+            device.config_tap_drag_lock_enabled(),
+            device.config_tap_default_drag_lock_enabled(),
+        ),
+    ));
+    rows.push((
+        "Tap button map",
+        match device.config_tap_button_map() {
+            Some(input::TapButtonMap::LeftRightMiddle) => "left/right/middle",
+            Some(input::TapButtonMap::LeftMiddleRight) => "left/middle/right",
+            _ => "n/a",
+        }
+        .to_string(),
+    ));
+    rows.push((
+        "Left-handed",
+        describe_toggle(
+            device.config_left_handed_is_available(),
+            device.config_left_handed(),
+            device.config_left_handed_default(),
+        ),
+    ));
+    rows.push((
+        "Nat.scrolling",
+        describe_toggle(
+            device.config_scroll_has_natural_scroll(),
+            device.config_scroll_natural_scroll_enabled(),
+            device.config_scroll_default_natural_scroll_enabled(),
+        ),
+    ));
+    rows.push((
+        "Middle emulation",
+        describe_toggle(
+            device.config_middle_emulation_is_available(),
+            device.config_middle_emulation_enabled(),
+            device.config_middle_emulation_default_enabled(),
+        ),
+    ));
+    rows.push((
+        "Scroll methods",
+        describe_methods(
+            &[
+                (ScrollMethod::TwoFinger, "two-finger"),
+                (ScrollMethod::Edge, "edge"),
+                (ScrollMethod::OnButtonDown, "button"),
+            ],
+            &device.config_scroll_methods(),
+            device.config_scroll_method(),
+        ),
+    ));
+    rows.push((
+        "Click methods",
+        describe_methods(
+            &[
+                (input::ClickMethod::ButtonAreas, "button-areas"),
+                (input::ClickMethod::Clickfinger, "clickfinger"),
+            ],
+            &device.config_click_methods(),
+            device.config_click_method(),
+        ),
+    ));
+    rows.push((
+        "Disable-w-typing",
+        describe_toggle(
+            device.config_dwt_is_available(),
+            device.config_dwt_enabled(),
+            device.config_dwt_default_enabled(),
+        ),
+    ));
+    rows.push((
+        "Disable-w-trackpt",
+        describe_toggle(
+            device.config_dwtp_is_available(),
+            device.config_dwtp_enabled(),
+            device.config_dwtp_default_enabled(),
+        ),
+    ));
+    rows.push((
+        "Accel profiles",
+        describe_methods(
+            &[
+                (input::AccelProfile::Flat, "flat"),
+                (input::AccelProfile::Adaptive, "adaptive"),
+            ],
+            &device.config_accel_profiles(),
+            device.config_accel_profile(),
+        ),
+    ));
+    // libinput normalizes speed to [-1, 1] for every device
+    rows.push((
+        "Accel speed",
+        if device.config_accel_is_available() {
+            format!(
+                "{:.2} in -1..1 (default {:.2})",
+                device.config_accel_speed(),
+                device.config_accel_default_speed()
+            )
+        } else {
+            "n/a".to_string()
+        },
+    ));
+    rows.push((
+        "Rotation",
+        if device.config_rotation_is_available() {
+            format!("{}°", device.config_rotation_angle())
+        } else {
+            "n/a".to_string()
+        },
+    ));
+
+    LibinputDeviceInfo { rows }
+}
+
+/// Send the current config and capability report; false once the UI is gone.
+fn send_config(device: &Device, tx: &BoundedSender<LibinputEvent>) -> bool {
+    tx.send(LibinputEvent::Config(read_config(device))).is_ok()
+        && tx
+            .send(LibinputEvent::DeviceInfo(read_device_info(device)))
+            .is_ok()
+}
+
+fn apply_command(device: &mut Device, command: &LibinputCommand) {
+    let result = match *command {
+        LibinputCommand::Tap(on) => device.config_tap_set_enabled(on),
+        LibinputCommand::TapDrag(on) => device.config_tap_set_drag_enabled(on),
+        LibinputCommand::NaturalScroll(on) => device.config_scroll_set_natural_scroll_enabled(on),
+        LibinputCommand::AccelProfile(profile) => {
+            device.config_accel_set_profile(accel_profile_to_input(profile))
+        }
+        LibinputCommand::Dwt(on) => device.config_dwt_set_enabled(on),
+        LibinputCommand::ClickMethod(method) => {
+            device.config_click_set_method(click_method_to_input(method))
+        }
+    };
+    if let Err(e) = result {
+        log_warn!(target: "libinput", "{:?} failed: {:?}", command, e);
+    }
+}
+
+/// Output of `libinput quirks list` for the device, or None if the libinput
+/// tool isn't installed or has nothing to say.
+pub fn read_quirks(device_path: &Path) -> Option<String> {
+    let output = std::process::Command::new("libinput")
+        .args(["quirks", "list"])
+        .arg(device_path)
+        .output()
+        .ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+    (output.status.success() && !text.trim().is_empty()).then_some(text)
+}
+
+/// Event nodes of lid and tablet-mode switches. Adding them to the context
+/// lets libinput pair them with the touchpad, which it then disables while the
+/// lid is closed or the device is in tablet mode.
+fn find_switch_devices() -> Vec<PathBuf> {
+    let scan = || -> std::io::Result<Vec<PathBuf>> {
+        let mut enumerator = udev::Enumerator::new()?;
+        enumerator.match_subsystem("input")?;
+        enumerator.match_property("ID_INPUT_SWITCH", "1")?;
+        Ok(enumerator
+            .scan_devices()?
+            .filter(|d| d.syspath().to_string_lossy().contains("/event"))
+            .filter_map(|d| d.devnode().map(PathBuf::from))
+            .collect())
+    };
+    scan().unwrap_or_else(|e| {
+        log_warn!(target: "libinput", "switch lookup failed: {}", e);
+        Vec::new()
+    })
+}
+
+/// Current switch positions, since libinput only reports changes.
+fn initial_switch_states(path: &Path) -> Vec<LibinputEvent> {
+    let Ok(device) = evdev::Device::open(path) else {
+        return Vec::new();
+    };
+    let Ok(state) = device.get_switch_state() else {
+        return Vec::new();
+    };
+    let supported = device.supported_switches();
+    [
+        (evdev::SwitchType::SW_LID, SwitchKind::Lid),
+        (evdev::SwitchType::SW_TABLET_MODE, SwitchKind::TabletMode),
+    ]
+    .into_iter()
+    .filter(|(sw, _)| supported.is_some_and(|s| s.contains(*sw)))
+    .map(|(sw, switch)| LibinputEvent::SwitchToggle {
+        switch,
+        on: state.contains(sw),
+    })
+    .collect()
 }
 
 fn run_libinput_loop(
     device_path: &Path,
-    tx: &mpsc::Sender<LibinputEvent>,
+    tx: &BoundedSender<LibinputEvent>,
+    cmd_rx: &mpsc::Receiver<LibinputCommand>,
+    shutdown: &Shutdown,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut ctx = Libinput::new_from_path(Interface);
     let path_str = device_path
@@ -62,6 +419,25 @@ fn run_libinput_loop(
     if device.config_tap_finger_count() > 0 {
         let _ = device.config_tap_set_enabled(true);
     }
+    if !send_config(&device, tx) {
+        return Ok(());
+    }
+
+    for switch_path in find_switch_devices() {
+        let Some(path_str) = switch_path.to_str() else {
+            continue;
+        };
+        if ctx.path_add_device(path_str).is_none() {
+            log_warn!(target: "libinput", "failed to add switch {}", path_str);
+            continue;
+        }
+        log_info!(target: "libinput", "added switch {}", path_str);
+        for event in initial_switch_states(&switch_path) {
+            if tx.send(event).is_err() {
+                return Ok(());
+            }
+        }
+    }
 
     let poll_fd = ctx.as_raw_fd();
     let mut pollfd = libc::pollfd {
@@ -70,7 +446,7 @@ fn run_libinput_loop(
         revents: 0,
     };
 
-    loop {
+    while !shutdown.is_requested() {
         let ret = unsafe { libc::poll(&mut pollfd, 1, 100) };
         if ret < 0 {
             let err = std::io::Error::last_os_error();
@@ -80,6 +456,15 @@ fn run_libinput_loop(
             return Err(err.into());
         }
 
+        let mut config_changed = false;
+        while let Ok(command) = cmd_rx.try_recv() {
+            apply_command(&mut device, &command);
+            config_changed = true;
+        }
+        if config_changed && !send_config(&device, tx) {
+            return Ok(()); // UI closed
+        }
+
         ctx.dispatch()?;
 
         for event in &mut ctx {
@@ -96,6 +481,7 @@ fn run_libinput_loop(
                 Event::Pointer(PointerEvent::Button(b)) => Some(LibinputEvent::PointerButton {
                     button: b.button(),
                     pressed: b.button_state() == ButtonState::Pressed,
+                    time: Instant::now(),
                 }),
                 Event::Pointer(PointerEvent::ScrollWheel(s)) => Some(LibinputEvent::Scroll {
                     source: ScrollSource::Wheel,
@@ -202,6 +588,17 @@ fn run_libinput_loop(
                         _ => None,
                     }
                 }
+                Event::Switch(SwitchEvent::Toggle(toggle)) => {
+                    let switch = match toggle.switch() {
+                        Some(Switch::Lid) => Some(SwitchKind::Lid),
+                        Some(Switch::TabletMode) => Some(SwitchKind::TabletMode),
+                        _ => None,
+                    };
+                    switch.map(|switch| LibinputEvent::SwitchToggle {
+                        switch,
+                        on: toggle.switch_state() == SwitchState::On,
+                    })
+                }
                 _ => None,
             };
 
@@ -212,4 +609,5 @@ fn run_libinput_loop(
             }
         }
     }
+    Ok(())
 }
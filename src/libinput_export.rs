@@ -0,0 +1,180 @@
+//! Export of received libinput events to a file, so the event sequences
+//! produced by different libinput versions for the same input can be diffed.
+//!
+//! Paths ending in `.json` or `.jsonl` get one JSON object per line; anything
+//! else gets the same text as the panel log. Timestamps are seconds since the
+//! export started, taken when the UI drains the event, so events delivered in
+//! the same frame share a timestamp.
+
+use crate::libinput_state::{format_event, LibinputEvent, ScrollSource, SwitchKind};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+pub struct LibinputExporter {
+    writer: BufWriter<File>,
+    start: Instant,
+    json: bool,
+    pub path: String,
+}
+
+impl LibinputExporter {
+    pub fn new(path: &str) -> io::Result<Self> {
+        let json = matches!(
+            Path::new(path).extension().and_then(|e| e.to_str()),
+            Some("json" | "jsonl")
+        );
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+            json,
+            path: path.to_string(),
+        })
+    }
+
+    pub fn write(&mut self, event: &LibinputEvent) -> io::Result<()> {
+        let t = self.start.elapsed().as_secs_f64();
+        if self.json {
+            writeln!(self.writer, "{{\"t\":{:.6},{}}}", t, json_fields(event))
+        } else {
+            writeln!(self.writer, "{:10.6}  {}", t, format_event(event))
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl Drop for LibinputExporter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The event's `"type"` and payload, without the surrounding braces.
+pub fn json_fields(event: &LibinputEvent) -> String {
+    match event {
+        LibinputEvent::PointerMotion {
+            dx,
+            dy,
+            dx_unaccel,
+            dy_unaccel,
+        } => format!(
+            "\"type\":\"motion\",\"dx\":{},\"dy\":{},\"dx_unaccel\":{},\"dy_unaccel\":{}",
+            dx, dy, dx_unaccel, dy_unaccel
+        ),
+        LibinputEvent::PointerButton {
+            button, pressed, ..
+        } => format!(
+            "\"type\":\"button\",\"button\":{},\"pressed\":{}",
+            button, pressed
+        ),
+        LibinputEvent::Scroll {
+            source,
+            vert,
+            horiz,
+        } => {
+            let source = match source {
+                ScrollSource::Wheel => "wheel",
+                ScrollSource::Finger => "finger",
+                ScrollSource::Continuous => "continuous",
+            };
+            format!(
+                "\"type\":\"scroll\",\"source\":\"{}\",\"vert\":{},\"horiz\":{}",
+                source, vert, horiz
+            )
+        }
+        LibinputEvent::GestureSwipeBegin { fingers } => {
+            format!("\"type\":\"swipe_begin\",\"fingers\":{}", fingers)
+        }
+        LibinputEvent::GestureSwipeUpdate {
+            fingers,
+            dx,
+            dy,
+            dx_unaccel,
+            dy_unaccel,
+        } => format!(
+            "\"type\":\"swipe_update\",\"fingers\":{},\"dx\":{},\"dy\":{},\"dx_unaccel\":{},\"dy_unaccel\":{}",
+            fingers, dx, dy, dx_unaccel, dy_unaccel
+        ),
+        LibinputEvent::GestureSwipeEnd => "\"type\":\"swipe_end\"".to_string(),
+        LibinputEvent::GesturePinchBegin { fingers } => {
+            format!("\"type\":\"pinch_begin\",\"fingers\":{}", fingers)
+        }
+        LibinputEvent::GesturePinchUpdate {
+            fingers,
+            dx,
+            dy,
+            dx_unaccel,
+            dy_unaccel,
+            scale,
+            angle,
+        } => format!(
+            "\"type\":\"pinch_update\",\"fingers\":{},\"dx\":{},\"dy\":{},\"dx_unaccel\":{},\"dy_unaccel\":{},\"scale\":{},\"angle\":{}",
+            fingers, dx, dy, dx_unaccel, dy_unaccel, scale, angle
+        ),
+        LibinputEvent::GesturePinchEnd => "\"type\":\"pinch_end\"".to_string(),
+        LibinputEvent::GestureHoldBegin { fingers } => {
+            format!("\"type\":\"hold_begin\",\"fingers\":{}", fingers)
+        }
+        LibinputEvent::GestureHoldEnd { cancelled } => {
+            format!("\"type\":\"hold_end\",\"cancelled\":{}", cancelled)
+        }
+        LibinputEvent::SwitchToggle { switch, on } => {
+            let switch = match switch {
+                SwitchKind::Lid => "lid",
+                SwitchKind::TabletMode => "tablet_mode",
+            };
+            format!("\"type\":\"switch\",\"switch\":\"{}\",\"on\":{}", switch, on)
+        }
+        // The device report that follows every config change covers the
+        // same settings, so only mark where the change happened.
+        LibinputEvent::Config(_) => "\"type\":\"config\"".to_string(),
+        LibinputEvent::DeviceInfo(info) => {
+            let rows: Vec<String> = info
+                .rows
+                .iter()
+                .map(|(label, value)| format!("{}:{}", json_string(label), json_string(value)))
+                .collect();
+            format!("\"type\":\"device_info\",\"rows\":{{{}}}", rows.join(","))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libinput_state::LibinputDeviceInfo;
+
+    #[test]
+    fn json_lines() {
+        assert_eq!(
+            json_fields(&LibinputEvent::GestureSwipeBegin { fingers: 3 }),
+            "\"type\":\"swipe_begin\",\"fingers\":3"
+        );
+        let info = LibinputDeviceInfo {
+            rows: vec![("Device", "Pad \"X\"\\1".to_string())],
+        };
+        assert_eq!(
+            json_fields(&LibinputEvent::DeviceInfo(info)),
+            "\"type\":\"device_info\",\"rows\":{\"Device\":\"Pad \\\"X\\\"\\\\1\"}"
+        );
+    }
+}
@@ -5,6 +5,12 @@
 //! they can be shared across Linux (libinput) and Windows (RawInput mouse)
 //! backends.
 
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Events queued for the UI before further ones are dropped.
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
 /// Structured input event data, safe to send across threads.
 /// On Linux these come from libinput; on Windows from RawInput mouse data.
 #[derive(Clone, Debug)]
@@ -19,6 +25,8 @@ pub enum LibinputEvent {
     PointerButton {
         button: u32,
         pressed: bool,
+        /// When the backend received the event.
+        time: Instant,
     },
     Scroll {
         source: ScrollSource,
@@ -55,6 +63,67 @@ pub enum LibinputEvent {
     GestureHoldEnd {
         cancelled: bool,
     },
+    /// A lid or tablet-mode switch changed (or its state at startup).
+    SwitchToggle {
+        switch: SwitchKind,
+        on: bool,
+    },
+    /// Current device configuration, sent at startup and after each change.
+    Config(LibinputConfig),
+    /// Capability and configuration report, sent alongside `Config`.
+    DeviceInfo(LibinputDeviceInfo),
+}
+
+/// Device configuration as reported by libinput. `None` means the device
+/// doesn't support the option.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LibinputConfig {
+    pub tap: Option<bool>,
+    pub tap_drag: Option<bool>,
+    pub natural_scroll: Option<bool>,
+    pub accel_profile: Option<AccelProfile>,
+    pub accel_profiles: Vec<AccelProfile>,
+    pub dwt: Option<bool>,
+    pub click_method: Option<ClickMethod>,
+    pub click_methods: Vec<ClickMethod>,
+}
+
+/// What `libinput list-devices` would print for the device, as label/value
+/// rows. Lists are space-separated with the active entry marked `*`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LibinputDeviceInfo {
+    pub rows: Vec<(&'static str, String)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccelProfile {
+    Flat,
+    Adaptive,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickMethod {
+    ButtonAreas,
+    Clickfinger,
+}
+
+#[allow(dead_code)] // Reported by libinput on Linux only
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwitchKind {
+    Lid,
+    TabletMode,
+}
+
+/// A configuration change requested from the UI.
+#[allow(dead_code)] // Applied by the libinput backend on Linux only
+#[derive(Clone, Debug)]
+pub enum LibinputCommand {
+    Tap(bool),
+    TapDrag(bool),
+    NaturalScroll(bool),
+    AccelProfile(AccelProfile),
+    Dwt(bool),
+    ClickMethod(ClickMethod),
 }
 
 #[derive(Clone, Debug)]
@@ -66,6 +135,8 @@ pub enum ScrollSource {
 }
 
 const DECAY: f32 = 0.85;
+/// Below this every decayed value is drawn as at rest.
+const SETTLED: f32 = 0.01;
 
 const BTN_LEFT: u32 = 0x110;
 const BTN_RIGHT: u32 = 0x111;
@@ -90,8 +161,30 @@ pub struct LibinputState {
     /// Gesture type currently active
     pub gesture: GestureState,
 
+    /// Completed gestures, oldest first
+    pub gesture_history: VecDeque<GestureRecord>,
+    /// Gestures finished so far, including ones dropped from the history
+    pub gestures_finished: usize,
+    open_gesture: Option<(Instant, GestureRecord)>,
+
+    /// Lid switch (true = closed) and tablet-mode switch, once reported.
+    /// libinput disables the touchpad while either is on.
+    pub lid_closed: Option<bool>,
+    pub tablet_mode: Option<bool>,
+
     /// Recent log lines (kept for small text log)
-    pub log_lines: Vec<String>,
+    pub log_lines: VecDeque<LogEntry>,
+    /// Which log lines the panel shows
+    pub log_filter: LogFilter,
+    /// While paused, new lines are held back so the log can be read
+    log_paused: bool,
+    log_pending: VecDeque<LogEntry>,
+
+    /// Device configuration, if the backend reports it
+    pub config: Option<LibinputConfig>,
+
+    /// Capability report, if the backend provides one
+    pub device_info: Option<LibinputDeviceInfo>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -116,6 +209,33 @@ pub struct GestureState {
     pub angle: f32,
 }
 
+/// A finished gesture with its totals. Durations are measured when the UI
+/// drains the events, so they're accurate to about one frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GestureRecord {
+    pub kind: GestureKind,
+    pub fingers: u32,
+    pub duration_ms: f32,
+    /// Sum of accelerated deltas
+    pub dx: f32,
+    pub dy: f32,
+    /// Final pinch scale relative to the start (1.0 for non-pinches)
+    pub scale: f32,
+    /// Sum of pinch angle deltas in degrees
+    pub angle: f32,
+    pub cancelled: bool,
+}
+
+/// Per-kind summary of the gesture history.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GestureSummary {
+    pub kind: GestureKind,
+    pub count: usize,
+    pub median_ms: f32,
+    pub max_ms: f32,
+    pub cancelled: usize,
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub enum GestureKind {
     #[default]
@@ -125,7 +245,65 @@ pub enum GestureKind {
     Hold,
 }
 
-const LOG_MAX: usize = 50;
+/// Event classes the log can be filtered by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventClass {
+    Motion,
+    Button,
+    Scroll,
+    Gesture,
+    /// Switches, config and device reports; always shown.
+    Other,
+}
+
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub class: EventClass,
+    pub text: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct LogFilter {
+    pub motion: bool,
+    pub button: bool,
+    pub scroll: bool,
+    pub gesture: bool,
+    /// Case-insensitive substring to match; empty matches everything.
+    pub search: String,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            motion: true,
+            button: true,
+            scroll: true,
+            gesture: true,
+            search: String::new(),
+        }
+    }
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        let class_shown = match entry.class {
+            EventClass::Motion => self.motion,
+            EventClass::Button => self.button,
+            EventClass::Scroll => self.scroll,
+            EventClass::Gesture => self.gesture,
+            EventClass::Other => true,
+        };
+        class_shown
+            && (self.search.is_empty()
+                || entry
+                    .text
+                    .to_lowercase()
+                    .contains(&self.search.to_lowercase()))
+    }
+}
+
+const LOG_MAX: usize = 2000;
+const GESTURE_HISTORY_MAX: usize = 200;
 
 impl LibinputState {
     /// Apply per-frame decay to all values
@@ -150,9 +328,38 @@ impl LibinputState {
         }
     }
 
+    /// Whether decay has nothing visible left to animate.
+    pub fn is_settled(&self) -> bool {
+        let mut values = vec![
+            self.motion_accel.0,
+            self.motion_accel.1,
+            self.motion_unaccel.0,
+            self.motion_unaccel.1,
+            self.buttons.left,
+            self.buttons.middle,
+            self.buttons.right,
+            self.scroll_vert,
+            self.scroll_horiz,
+        ];
+        if self.gesture.active {
+            values.extend([
+                self.gesture.dx,
+                self.gesture.dy,
+                self.gesture.dx_unaccel,
+                self.gesture.dy_unaccel,
+                self.gesture.scale - 1.0,
+                self.gesture.angle,
+            ]);
+        }
+        values.iter().all(|v| v.abs() < SETTLED)
+    }
+
     /// Apply a structured libinput event to the state.
     pub fn apply_event(&mut self, event: &LibinputEvent) {
-        self.push_log(format_event(event));
+        self.push_log(LogEntry {
+            class: event_class(event),
+            text: format_event(event),
+        });
 
         match event {
             LibinputEvent::PointerMotion {
@@ -164,7 +371,9 @@ impl LibinputState {
                 self.motion_accel = (*dx as f32, *dy as f32);
                 self.motion_unaccel = (*dx_unaccel as f32, *dy_unaccel as f32);
             }
-            LibinputEvent::PointerButton { button, pressed } => {
+            LibinputEvent::PointerButton {
+                button, pressed, ..
+            } => {
                 let val = if *pressed { 1.0 } else { 0.0 };
                 match *button {
                     BTN_LEFT => self.buttons.left = val,
@@ -187,6 +396,7 @@ impl LibinputState {
                 self.scroll_horiz = *horiz as f32;
             }
             LibinputEvent::GestureSwipeBegin { fingers } => {
+                self.begin_gesture(GestureKind::Swipe, *fingers);
                 self.gesture.active = true;
                 self.gesture.kind = GestureKind::Swipe;
                 self.gesture.fingers = *fingers as u32;
@@ -200,6 +410,10 @@ impl LibinputState {
                 dx_unaccel,
                 dy_unaccel,
             } => {
+                if let Some((_, record)) = &mut self.open_gesture {
+                    record.dx += *dx as f32;
+                    record.dy += *dy as f32;
+                }
                 self.gesture.fingers = *fingers as u32;
                 self.gesture.dx = *dx as f32;
                 self.gesture.dy = *dy as f32;
@@ -207,10 +421,12 @@ impl LibinputState {
                 self.gesture.dy_unaccel = *dy_unaccel as f32;
             }
             LibinputEvent::GestureSwipeEnd => {
+                self.end_gesture(false);
                 self.gesture.active = false;
                 self.gesture.kind = GestureKind::None;
             }
             LibinputEvent::GesturePinchBegin { fingers } => {
+                self.begin_gesture(GestureKind::Pinch, *fingers);
                 self.gesture.active = true;
                 self.gesture.kind = GestureKind::Pinch;
                 self.gesture.fingers = *fingers as u32;
@@ -226,6 +442,12 @@ impl LibinputState {
                 scale,
                 angle,
             } => {
+                if let Some((_, record)) = &mut self.open_gesture {
+                    record.dx += *dx as f32;
+                    record.dy += *dy as f32;
+                    record.scale = *scale as f32;
+                    record.angle += *angle as f32;
+                }
                 self.gesture.fingers = *fingers as u32;
                 self.gesture.dx = *dx as f32;
                 self.gesture.dy = *dy as f32;
@@ -235,31 +457,140 @@ impl LibinputState {
                 self.gesture.angle = *angle as f32;
             }
             LibinputEvent::GesturePinchEnd => {
+                self.end_gesture(false);
                 self.gesture.active = false;
                 self.gesture.kind = GestureKind::None;
             }
             LibinputEvent::GestureHoldBegin { fingers } => {
+                self.begin_gesture(GestureKind::Hold, *fingers);
                 self.gesture.active = true;
                 self.gesture.kind = GestureKind::Hold;
                 self.gesture.fingers = *fingers as u32;
             }
-            LibinputEvent::GestureHoldEnd { .. } => {
+            LibinputEvent::GestureHoldEnd { cancelled } => {
+                self.end_gesture(*cancelled);
                 self.gesture.active = false;
                 self.gesture.kind = GestureKind::None;
             }
+            LibinputEvent::SwitchToggle { switch, on } => match switch {
+                SwitchKind::Lid => self.lid_closed = Some(*on),
+                SwitchKind::TabletMode => self.tablet_mode = Some(*on),
+            },
+            LibinputEvent::Config(config) => {
+                self.config = Some(config.clone());
+            }
+            LibinputEvent::DeviceInfo(info) => {
+                self.device_info = Some(info.clone());
+            }
+        }
+    }
+
+    fn begin_gesture(&mut self, kind: GestureKind, fingers: i32) {
+        let record = GestureRecord {
+            kind,
+            fingers: fingers as u32,
+            duration_ms: 0.0,
+            dx: 0.0,
+            dy: 0.0,
+            scale: 1.0,
+            angle: 0.0,
+            cancelled: false,
+        };
+        self.open_gesture = Some((Instant::now(), record));
+    }
+
+    fn end_gesture(&mut self, cancelled: bool) {
+        let Some((start, mut record)) = self.open_gesture.take() else {
+            return;
+        };
+        record.duration_ms = start.elapsed().as_secs_f32() * 1000.0;
+        record.cancelled = cancelled;
+        self.gesture_history.push_back(record);
+        self.gestures_finished += 1;
+        if self.gesture_history.len() > GESTURE_HISTORY_MAX {
+            self.gesture_history.pop_front();
+        }
+    }
+
+    /// Count and duration stats per gesture kind, for kinds that occurred.
+    pub fn gesture_summary(&self) -> Vec<GestureSummary> {
+        [GestureKind::Swipe, GestureKind::Pinch, GestureKind::Hold]
+            .into_iter()
+            .filter_map(|kind| {
+                let records: Vec<&GestureRecord> = self
+                    .gesture_history
+                    .iter()
+                    .filter(|r| r.kind == kind)
+                    .collect();
+                if records.is_empty() {
+                    return None;
+                }
+                let mut durations: Vec<f32> = records.iter().map(|r| r.duration_ms).collect();
+                durations.sort_by(f32::total_cmp);
+                Some(GestureSummary {
+                    count: records.len(),
+                    median_ms: durations[durations.len() / 2],
+                    max_ms: durations[durations.len() - 1],
+                    cancelled: records.iter().filter(|r| r.cancelled).count(),
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    fn push_log(&mut self, entry: LogEntry) {
+        let log = if self.log_paused {
+            &mut self.log_pending
+        } else {
+            &mut self.log_lines
+        };
+        log.push_back(entry);
+        if log.len() > LOG_MAX {
+            log.pop_front();
         }
     }
 
-    fn push_log(&mut self, line: String) {
-        self.log_lines.push(line);
-        if self.log_lines.len() > LOG_MAX {
-            self.log_lines.remove(0);
+    pub fn log_paused(&self) -> bool {
+        self.log_paused
+    }
+
+    /// Lines received since the log was paused.
+    pub fn log_pending(&self) -> usize {
+        self.log_pending.len()
+    }
+
+    /// Pause or resume the log; resuming appends the held-back lines.
+    pub fn set_log_paused(&mut self, paused: bool) {
+        self.log_paused = paused;
+        if !paused {
+            self.log_lines.append(&mut self.log_pending);
+            let excess = self.log_lines.len().saturating_sub(LOG_MAX);
+            self.log_lines.drain(..excess);
         }
     }
 }
 
+fn event_class(event: &LibinputEvent) -> EventClass {
+    match event {
+        LibinputEvent::PointerMotion { .. } => EventClass::Motion,
+        LibinputEvent::PointerButton { .. } => EventClass::Button,
+        LibinputEvent::Scroll { .. } => EventClass::Scroll,
+        LibinputEvent::GestureSwipeBegin { .. }
+        | LibinputEvent::GestureSwipeUpdate { .. }
+        | LibinputEvent::GestureSwipeEnd
+        | LibinputEvent::GesturePinchBegin { .. }
+        | LibinputEvent::GesturePinchUpdate { .. }
+        | LibinputEvent::GesturePinchEnd
+        | LibinputEvent::GestureHoldBegin { .. }
+        | LibinputEvent::GestureHoldEnd { .. } => EventClass::Gesture,
+        LibinputEvent::SwitchToggle { .. }
+        | LibinputEvent::Config(_)
+        | LibinputEvent::DeviceInfo(_) => EventClass::Other,
+    }
+}
+
 /// Format a LibinputEvent into a human-readable log line.
-fn format_event(event: &LibinputEvent) -> String {
+pub fn format_event(event: &LibinputEvent) -> String {
     match event {
         LibinputEvent::PointerMotion {
             dx,
@@ -272,7 +603,9 @@ fn format_event(event: &LibinputEvent) -> String {
                 dx, dy, dx_unaccel, dy_unaccel
             )
         }
-        LibinputEvent::PointerButton { button, pressed } => {
+        LibinputEvent::PointerButton {
+            button, pressed, ..
+        } => {
             let name = match *button {
                 BTN_LEFT => "LEFT",
                 BTN_RIGHT => "RIGHT",
@@ -338,5 +671,99 @@ fn format_event(event: &LibinputEvent) -> String {
                 "HOLD_END".to_string()
             }
         }
+        LibinputEvent::SwitchToggle { switch, on } => {
+            let name = match switch {
+                SwitchKind::Lid => "lid",
+                SwitchKind::TabletMode => "tablet-mode",
+            };
+            format!("SWITCH_TOGGLE {} {}", name, if *on { "on" } else { "off" })
+        }
+        LibinputEvent::Config(_) => "CONFIG updated".to_string(),
+        LibinputEvent::DeviceInfo(_) => "DEVICE info".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(pressed: bool) -> LibinputEvent {
+        LibinputEvent::PointerButton {
+            button: BTN_LEFT,
+            pressed,
+            time: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn filter_by_class_and_search() {
+        let mut state = LibinputState::default();
+        state.apply_event(&button(true));
+        state.apply_event(&LibinputEvent::GestureSwipeEnd);
+        let shown = |state: &LibinputState| {
+            state
+                .log_lines
+                .iter()
+                .filter(|e| state.log_filter.matches(e))
+                .count()
+        };
+        assert_eq!(shown(&state), 2);
+
+        state.log_filter.gesture = false;
+        assert_eq!(shown(&state), 1);
+
+        state.log_filter.gesture = true;
+        state.log_filter.search = "swipe".to_string();
+        assert_eq!(shown(&state), 1);
+    }
+
+    #[test]
+    fn gesture_history_totals() {
+        let mut state = LibinputState::default();
+        state.apply_event(&LibinputEvent::GesturePinchBegin { fingers: 2 });
+        for _ in 0..2 {
+            state.apply_event(&LibinputEvent::GesturePinchUpdate {
+                fingers: 2,
+                dx: 1.0,
+                dy: -2.0,
+                dx_unaccel: 1.0,
+                dy_unaccel: -2.0,
+                scale: 1.5,
+                angle: 10.0,
+            });
+        }
+        state.apply_event(&LibinputEvent::GesturePinchEnd);
+        state.apply_event(&LibinputEvent::GestureHoldBegin { fingers: 3 });
+        state.apply_event(&LibinputEvent::GestureHoldEnd { cancelled: true });
+        // An end without a begin is ignored
+        state.apply_event(&LibinputEvent::GestureSwipeEnd);
+
+        assert_eq!(state.gesture_history.len(), 2);
+        let pinch = &state.gesture_history[0];
+        assert_eq!(pinch.kind, GestureKind::Pinch);
+        assert_eq!(pinch.fingers, 2);
+        assert_eq!((pinch.dx, pinch.dy), (2.0, -4.0));
+        assert_eq!((pinch.scale, pinch.angle), (1.5, 20.0));
+
+        let summary = state.gesture_summary();
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[1].kind, GestureKind::Hold);
+        assert_eq!(summary[1].cancelled, 1);
+    }
+
+    #[test]
+    fn pause_holds_back_lines() {
+        let mut state = LibinputState::default();
+        state.apply_event(&button(true));
+        state.set_log_paused(true);
+        state.apply_event(&button(false));
+        assert_eq!(state.log_lines.len(), 1);
+        assert_eq!(state.log_pending(), 1);
+        // State still updates while the log is paused
+        assert_eq!(state.buttons.left, 0.0);
+
+        state.set_log_paused(false);
+        assert_eq!(state.log_lines.len(), 2);
+        assert_eq!(state.log_pending(), 0);
     }
 }
@@ -0,0 +1,239 @@
+//! Linearity test: guided horizontal, vertical and diagonal swipes, each
+//! fitted with a straight line and scored by how far the reported path
+//! strays from it.
+//!
+//! The fit is an orthogonal (total least squares) regression, so it doesn't
+//! matter which axis the swipe runs along. Points are scaled to millimeters
+//! when the resolution is known so both axes count equally. Points near the
+//! touchpad edges are scored separately since that's where sensors usually
+//! bend lines.
+
+/// Share of the touchpad's width/height counted as the edge region.
+const EDGE_FRACTION: f32 = 0.1;
+/// Swipes shorter than this share of the touchpad's smaller side are ignored.
+const MIN_LENGTH_FRACTION: f32 = 0.3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Horizontal,
+    Vertical,
+    Diagonal,
+}
+
+impl Direction {
+    pub fn prompt(self) -> &'static str {
+        match self {
+            Direction::Horizontal => "Swipe one finger left to right across the touchpad",
+            Direction::Vertical => "Swipe one finger top to bottom across the touchpad",
+            Direction::Diagonal => "Swipe one finger corner to corner across the touchpad",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Direction::Horizontal => "Horizontal",
+            Direction::Vertical => "Vertical",
+            Direction::Diagonal => "Diagonal",
+        }
+    }
+}
+
+pub const DIRECTIONS: [Direction; 3] = [
+    Direction::Horizontal,
+    Direction::Vertical,
+    Direction::Diagonal,
+];
+
+/// One swipe's fit. Distances are in mm, or device units without a resolution.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineFit {
+    pub direction: Direction,
+    /// Per point: position along the line, signed deviation from it, and
+    /// whether it's in the edge region.
+    pub profile: Vec<(f32, f32, bool)>,
+    pub max_deviation: f32,
+    pub rms_deviation: f32,
+    /// Largest deviation among edge points and among the rest, if any
+    pub max_edge: Option<f32>,
+    pub max_center: Option<f32>,
+}
+
+/// Fit a line through `points` (already scaled) and measure deviations.
+fn fit(direction: Direction, points: &[(f32, f32)], edge: &[bool]) -> Option<LineFit> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f32;
+    let (sx, sy) = points
+        .iter()
+        .fold((0.0, 0.0), |(ax, ay), (x, y)| (ax + x, ay + y));
+    let (cx, cy) = (sx / n, sy / n);
+    let (sxx, syy, sxy) = points.iter().fold((0.0, 0.0, 0.0), |(a, b, c), (x, y)| {
+        let (dx, dy) = (x - cx, y - cy);
+        (a + dx * dx, b + dy * dy, c + dx * dy)
+    });
+    let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let (sin, cos) = angle.sin_cos();
+
+    let profile: Vec<(f32, f32, bool)> = points
+        .iter()
+        .zip(edge)
+        .map(|(&(x, y), &edge)| {
+            let (dx, dy) = (x - cx, y - cy);
+            (dx * cos + dy * sin, dy * cos - dx * sin, edge)
+        })
+        .collect();
+    let max_of = |filter: &dyn Fn(bool) -> bool| {
+        profile
+            .iter()
+            .filter(|p| filter(p.2))
+            .map(|p| p.1.abs())
+            .reduce(f32::max)
+    };
+    let sum_sq: f32 = profile.iter().map(|p| p.1 * p.1).sum();
+    Some(LineFit {
+        direction,
+        max_deviation: max_of(&|_| true).unwrap_or(0.0),
+        rms_deviation: (sum_sq / n).sqrt(),
+        max_edge: max_of(&|e| e),
+        max_center: max_of(&|e| !e),
+        profile,
+    })
+}
+
+#[derive(Default)]
+pub struct LinearityTest {
+    /// Finished swipes, in `DIRECTIONS` order
+    pub results: Vec<LineFit>,
+    tracking_id: Option<i32>,
+    stroke: Vec<(i32, i32)>,
+    /// Why the last swipe wasn't counted, if it wasn't
+    pub rejected: Option<&'static str>,
+}
+
+impl LinearityTest {
+    /// The swipe being asked for, or `None` once all are done.
+    pub fn current(&self) -> Option<Direction> {
+        DIRECTIONS.get(self.results.len()).copied()
+    }
+
+    /// Feed the active contacts (tracking ID and position). `extent` is the
+    /// position range in device units and `resolution` its units per mm.
+    pub fn update(
+        &mut self,
+        contacts: &[(i32, i32, i32)],
+        extent: (f32, f32),
+        resolution: Option<(f32, f32)>,
+    ) {
+        let Some(direction) = self.current() else {
+            return;
+        };
+        match contacts {
+            [] => {
+                if self.tracking_id.take().is_some() {
+                    let stroke = std::mem::take(&mut self.stroke);
+                    self.finish(direction, &stroke, extent, resolution);
+                }
+            }
+            [(id, x, y)] => match self.tracking_id {
+                Some(current) if current != *id => {
+                    self.tracking_id = None;
+                    self.stroke.clear();
+                    self.rejected = Some("Finger changed mid-swipe");
+                }
+                _ => {
+                    self.tracking_id = Some(*id);
+                    if self.stroke.last() != Some(&(*x, *y)) {
+                        self.stroke.push((*x, *y));
+                    }
+                }
+            },
+            _ => {
+                if self.tracking_id.take().is_some() || !self.stroke.is_empty() {
+                    self.stroke.clear();
+                    self.rejected = Some("Use only one finger");
+                }
+            }
+        }
+    }
+
+    fn finish(
+        &mut self,
+        direction: Direction,
+        stroke: &[(i32, i32)],
+        extent: (f32, f32),
+        resolution: Option<(f32, f32)>,
+    ) {
+        let (rx, ry) = resolution.unwrap_or((1.0, 1.0));
+        let points: Vec<(f32, f32)> = stroke
+            .iter()
+            .map(|&(x, y)| (x as f32 / rx, y as f32 / ry))
+            .collect();
+        let edge: Vec<bool> = stroke
+            .iter()
+            .map(|&(x, y)| {
+                let fx = x as f32 / extent.0.max(1.0);
+                let fy = y as f32 / extent.1.max(1.0);
+                !(EDGE_FRACTION..=1.0 - EDGE_FRACTION).contains(&fx)
+                    || !(EDGE_FRACTION..=1.0 - EDGE_FRACTION).contains(&fy)
+            })
+            .collect();
+        let Some(result) = fit(direction, &points, &edge) else {
+            self.rejected = Some("Swipe too short, try again");
+            return;
+        };
+        let span = |p: &(f32, f32, bool)| p.0;
+        let length = result.profile.iter().map(span).fold(f32::MIN, f32::max)
+            - result.profile.iter().map(span).fold(f32::MAX, f32::min);
+        let min_side = (extent.0 / rx).min(extent.1 / ry);
+        if length < min_side * MIN_LENGTH_FRACTION {
+            self.rejected = Some("Swipe too short, try again");
+            return;
+        }
+        self.rejected = None;
+        self.results.push(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTENT: (f32, f32) = (1000.0, 600.0);
+
+    fn swipe(test: &mut LinearityTest, id: i32, points: &[(i32, i32)]) {
+        for &(x, y) in points {
+            test.update(&[(id, x, y)], EXTENT, None);
+        }
+        test.update(&[], EXTENT, None);
+    }
+
+    #[test]
+    fn scores_each_direction() {
+        let mut test = LinearityTest::default();
+        assert_eq!(test.current(), Some(Direction::Horizontal));
+
+        // Too short to count
+        swipe(&mut test, 1, &[(400, 300), (450, 300)]);
+        assert_eq!(test.results.len(), 0);
+        assert!(test.rejected.is_some());
+
+        // Bulges by 4 units in the middle, perfectly straight at the ends
+        swipe(
+            &mut test,
+            2,
+            &[(200, 300), (400, 300), (500, 304), (600, 300), (800, 300)],
+        );
+        let fit = &test.results[0];
+        assert!((fit.max_deviation - 3.2).abs() < 0.01);
+        assert_eq!(test.current(), Some(Direction::Vertical));
+
+        // A diagonal line fits exactly, and its ends are in the edge region
+        swipe(&mut test, 3, &[(500, 50), (500, 300), (500, 550)]);
+        swipe(&mut test, 4, &[(50, 50), (300, 300), (550, 550)]);
+        let fit = &test.results[2];
+        assert!(fit.max_deviation < 1e-3);
+        assert!(fit.max_edge.is_some() && fit.max_center.is_some());
+        assert_eq!(test.current(), None);
+    }
+}
@@ -1,45 +1,111 @@
+#[macro_use]
+extern crate tapview_core;
+
 mod app;
+mod backend_error;
+mod click_pressure;
 mod config;
+mod contact_swap;
+mod cursor;
+#[cfg(target_os = "linux")]
+mod device_properties;
 mod dimensions;
-mod discovery;
-mod heatmap;
-mod input;
+mod dwell;
+mod edge_swipe;
+mod edge_test;
+mod gesture_hooks;
+mod gestures;
+mod heatmap_ab;
+mod heatmap_export;
+mod heatmap_watch;
+mod ink;
+mod jitter;
+mod keys;
 #[cfg(target_os = "linux")]
 mod libinput_backend;
+mod libinput_export;
 mod libinput_state;
-mod multitouch;
-mod recording;
+mod linearity;
+#[cfg(feature = "midi")]
+mod midi;
+mod notify;
+mod orientation_test;
+mod outliers;
+mod overlay;
+mod palm;
+mod palm_test;
+mod pen;
+#[cfg(target_os = "linux")]
+mod permissions;
+mod pressure_test;
+mod rates;
+mod recalibration;
+mod register_dumps;
 mod render;
+mod report_rate;
+mod report_timing;
+mod resolution_test;
+#[cfg(feature = "scripting")]
+mod script;
+mod settings;
+mod slot_plots;
+mod tap_drag;
+mod tap_latency;
+mod tap_timing;
+mod threshold_sim;
+mod tracking;
+mod trajectory;
+mod tuio;
 #[cfg(target_os = "windows")]
 mod windows_input_backend;
 
-use app::{GrabCommand, TapviewApp};
+use app::{GrabCommand, TapviewApp, Workers};
+use backend_error::{report, Backend, BackendError};
 use clap::Parser;
 #[cfg(target_os = "linux")]
 use discovery::udev_discovery::UdevDiscovery;
 #[cfg(target_os = "windows")]
 use discovery::windows_discovery::WindowsDiscovery;
 use discovery::DeviceDiscovery;
+use input::demo_backend::DemoBackend;
 #[cfg(target_os = "linux")]
 use input::evdev_backend::EvdevBackend;
+#[cfg(target_os = "linux")]
+use input::hidraw_backend::HidrawBackend;
 #[cfg(target_os = "windows")]
 use input::windows_backend::WindowsBackend;
 use input::InputBackend;
-use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
+use tapview_core::heatmap::driver::{BurstLen, DriverOptions};
+use tapview_core::heatmap::registers::{RegisterWatch, RegisterWrite};
+use tapview_core::shutdown::Shutdown;
+use tapview_core::{channel, discovery, heatmap, input, logging, recording};
+
+/// Touch states queued for the UI before further ones are dropped; several
+/// seconds of reports at typical rates.
+const TOUCH_CHANNEL_CAPACITY: usize = 1024;
 
 #[derive(Parser)]
 #[command(name = "tapview", about = "Touchpad Visualizer")]
 struct Cli {
-    /// Number of trail frames to show (max 20)
-    #[arg(short, long, default_value_t = 20)]
-    trails: usize,
+    /// Trail fade-out time in milliseconds, 0 to disable (default: saved value, else 500)
+    #[arg(short = 't', long = "trail-ms", alias = "trails", value_name = "MS")]
+    trail_ms: Option<u32>,
 
-    /// Enable verbose event logging to stderr
+    /// Log raw kernel events, or HID reports on Windows (same as adding
+    /// input=trace to --log-level)
     #[arg(short, long)]
     verbose: bool,
 
+    /// What to log: a level (error, warn, info, debug, trace, off) and/or
+    /// target=level pairs for input, heatmap, libinput, discovery, config, seat
+    #[arg(long, value_name = "FILTER", default_value = "info", value_parser = logging::parse_filter)]
+    log_level: String,
+
+    /// Also append log lines to a file
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<std::path::PathBuf>,
+
     /// Force interpreted input panel (exit if unavailable). Auto-enabled by default.
     #[arg(short, long, conflicts_with = "no_libinput")]
     libinput: bool,
@@ -56,6 +122,10 @@ struct Cli {
     #[arg(long)]
     no_heatmap: bool,
 
+    /// Don't show the pen of a pen-and-touch device (e.g. a Wacom tablet). Auto-enabled when found.
+    #[arg(long)]
+    no_pen: bool,
+
     /// Force PTP configuration panel (exit if unavailable). Auto-enabled for compatible hardware.
     #[arg(long, conflicts_with = "no_config")]
     config: bool,
@@ -68,7 +138,33 @@ struct Cli {
     #[arg(long)]
     heatmap_cols: Option<usize>,
 
-    /// List detected touchpads and exit
+    /// Heatmap burst report length in bytes, for firmwares whose descriptor
+    /// gets it wrong, or "probe" to find it by trying likely lengths
+    #[arg(long, value_name = "N|probe")]
+    burst_len: Option<BurstLen>,
+
+    /// Try reading heatmap frames streamed as input report 0x44 where the
+    /// descriptor declares it, polling if none arrives (experimental)
+    #[arg(long)]
+    heatmap_stream: bool,
+
+    /// PixArt register writes that switch automatic level calibration on, as
+    /// bank:addr=value in hex separated by commas (U prefix for a user
+    /// bank), for the ALC A/B capture
+    #[arg(long, value_name = "WRITES", value_delimiter = ',')]
+    alc_on: Vec<RegisterWrite>,
+
+    /// PixArt register writes that switch automatic level calibration off,
+    /// like --alc-on
+    #[arg(long, value_name = "WRITES", value_delimiter = ',')]
+    alc_off: Vec<RegisterWrite>,
+
+    /// PixArt register writes that make the firmware take a new baseline,
+    /// like --alc-on, for the recalibrate button in the register window
+    #[arg(long, value_name = "WRITES", value_delimiter = ',')]
+    recalibrate: Vec<RegisterWrite>,
+
+    /// List detected touchpads, marking those with saved settings, and exit
     #[arg(long)]
     list: bool,
 
@@ -88,18 +184,173 @@ struct Cli {
     #[arg(long)]
     device: Option<String>,
 
+    /// Take devices you lack permission for from systemd-logind (needs a
+    /// session no compositor controls, e.g. a text console)
+    #[cfg(all(target_os = "linux", feature = "logind"))]
+    #[arg(long)]
+    logind: bool,
+
+    /// While grabbed, pass the touchpad's events on to the desktop through a
+    /// uinput device (needs /dev/uinput access); `no-clicks` holds back
+    /// button clicks
+    #[cfg(target_os = "linux")]
+    #[arg(
+        long,
+        value_name = "FILTER",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "all"
+    )]
+    forward: Option<input::forward::ForwardFilter>,
+
+    /// Where touches come from: evdev, or the hidraw node with the HID
+    /// reports parsed by tapview, to tell kernel bugs from firmware bugs
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_enum, default_value = "evdev")]
+    backend: InputSource,
+
+    /// Write the touchpad's HID collections, items and preparsed data to a
+    /// file and exit (Linux has the descriptor in sysfs)
+    #[cfg(target_os = "windows")]
+    #[arg(long, value_name = "PATH")]
+    dump_descriptor: Option<std::path::PathBuf>,
+
     /// Record touch session to a binary file
     #[arg(long, conflicts_with = "play")]
     record: Option<String>,
 
+    /// Write every libinput event to a file (JSON lines for .json/.jsonl, text otherwise)
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["no_libinput", "play"])]
+    libinput_log: Option<String>,
+
+    /// Send the contacts as TUIO 1.1 cursors over UDP
+    /// (default 127.0.0.1:3333)
+    #[arg(long, value_name = "HOST:PORT", num_args = 0..=1,
+          default_missing_value = tuio::DEFAULT_TARGET, conflicts_with = "play")]
+    tuio: Option<String>,
+
+    /// Send each slot's X, Y and pressure as MIDI control changes, to the
+    /// output port with this number or part of its name
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "PORT", conflicts_with = "play")]
+    midi: Option<String>,
+
+    /// Color theme (defaults to the saved choice, or following the system)
+    #[arg(long, value_enum)]
+    theme: Option<render::ThemeChoice>,
+
+    /// Run a Rhai script on touch reports and libinput events that can draw
+    /// annotations and report metrics (see src/script.rs)
+    #[cfg(feature = "scripting")]
+    #[arg(long, value_name = "FILE")]
+    script: Option<std::path::PathBuf>,
+
+    /// Rotate the touch view clockwise, for pads mounted turned (default:
+    /// saved value, else 0)
+    #[arg(long, value_enum, value_name = "DEGREES")]
+    rotate: Option<dimensions::Rotation>,
+
+    /// Mirror the touch view left to right
+    #[arg(long)]
+    invert_x: bool,
+
+    /// Mirror the touch view top to bottom
+    #[arg(long)]
+    invert_y: bool,
+
+    /// Don't keep the window above other windows (can be toggled in the
+    /// settings window)
+    #[arg(long)]
+    no_always_on_top: bool,
+
+    /// Send desktop notifications when the device fails or a recording
+    /// ends, for leaving tapview running in the background (also in the
+    /// settings window)
+    #[arg(long, conflicts_with = "play")]
+    notify: bool,
+
+    /// Float a borderless, click-through, see-through touch view over the
+    /// desktop (K or SIGUSR1 toggles click-through; close it from the
+    /// terminal)
+    #[arg(long, conflicts_with = "play")]
+    overlay: bool,
+
+    /// Show a made-up touchpad playing a loop of gestures (no device needed)
+    #[arg(
+        long,
+        conflicts_with_all = ["play", "device", "list", "info", "libinput", "heatmap", "config"]
+    )]
+    demo: bool,
+
     /// Play back a recorded touch session (no device needed)
     #[arg(long, conflicts_with_all = ["record", "device", "libinput", "heatmap", "config"])]
     play: Option<String>,
+
+    /// While playing, replay the recording into a new uinput touchpad that
+    /// libinput and the desktop pick up (needs /dev/uinput access)
+    #[cfg(target_os = "linux")]
+    #[arg(long, requires = "play")]
+    replay_uinput: bool,
+
+    #[cfg(target_os = "linux")]
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum InputSource {
+    Evdev,
+    Hidraw,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Install a udev rule that lets your desktop session open the touchpad
+    /// (event and hidraw nodes) without root, then re-trigger udev
+    SetupPermissions {
+        /// Install without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Only print the rule
+        #[arg(long, conflicts_with = "yes")]
+        print: bool,
+    },
 }
 
 fn main() {
-    let cli = Cli::parse();
-    let trails = cli.trails.min(20);
+    let mut cli = Cli::parse();
+    if cli.verbose {
+        cli.log_level.push_str(",input=trace");
+    }
+    // Everything logged from here on also shows up in the in-app console
+    let log_rx = match logging::init(&cli.log_level, cli.log_file.as_deref()) {
+        Ok(rx) => Some(rx),
+        Err(e) => {
+            eprintln!("Failed to open log file: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    #[cfg(all(target_os = "linux", feature = "logind"))]
+    if cli.logind {
+        tapview_core::seat::enable_logind();
+    }
+
+    // Any of the view flags replaces the saved rotation and mirroring
+    let view_transform =
+        (cli.rotate.is_some() || cli.invert_x || cli.invert_y).then(|| dimensions::ViewTransform {
+            rotation: cli.rotate.unwrap_or_default(),
+            flip_x: cli.invert_x,
+            flip_y: cli.invert_y,
+        });
+    let (window_level, on_top) = if cli.no_always_on_top {
+        (egui::WindowLevel::Normal, false)
+    } else {
+        (egui::WindowLevel::AlwaysOnTop, true)
+    };
 
     // --- Playback mode: no device needed ---
     if let Some(ref play_path) = cli.play {
@@ -110,7 +361,7 @@ fn main() {
                 std::process::exit(1);
             }
         };
-        eprintln!(
+        log_info!(
             "Loaded recording: {} frames, {:.1}s",
             rec.frames.len(),
             rec.duration_secs()
@@ -122,34 +373,111 @@ fn main() {
             None
         };
 
+        #[cfg(feature = "scripting")]
+        let script = spawn_script(cli.script.as_deref(), evdev_extents);
+
+        // The virtual touchpad, with libinput reading it back
+        #[allow(unused_mut)]
+        let mut workers = Workers::default();
+        #[cfg(target_os = "linux")]
+        let mut replay = None;
+        #[cfg(target_os = "linux")]
+        let (libinput_rx, libinput_cmd_tx) = if cli.replay_uinput {
+            let mut device = match input::replay::ReplayDevice::new(&rec) {
+                Ok(d) => d,
+                Err(e) => {
+                    eprintln!("Failed to create uinput device: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let channels = match device.devnode() {
+                Ok(node) if !cli.no_libinput => {
+                    log_info!("Replaying into {}", node.display());
+                    let (rx, cmd_tx, thread) = libinput_backend::spawn_libinput_thread(
+                        &node,
+                        workers.shutdown.clone(),
+                        workers.errors_tx.clone(),
+                    );
+                    workers.threads.push(("libinput", thread));
+                    (Some(rx), Some(cmd_tx))
+                }
+                Ok(_) => (None, None),
+                Err(e) => {
+                    log_warn!("No device node for the replay device: {}", e);
+                    (None, None)
+                }
+            };
+            replay = Some(device);
+            channels
+        } else {
+            (None, None)
+        };
+        #[cfg(target_os = "windows")]
+        let (libinput_rx, libinput_cmd_tx) = (None, None);
+
         // Dummy channels (not used during playback)
-        let (_touch_tx, touch_rx) = mpsc::channel();
-        let (grab_tx, _grab_rx) = mpsc::channel::<GrabCommand>();
+        let (_touch_tx, touch_rx) = channel::bounded(1);
+        let (grab_tx, _grab_rx) =
+            input::command_channel::<GrabCommand>().expect("Failed to create command channel");
 
         let options = eframe::NativeOptions {
             viewport: egui::ViewportBuilder::default()
                 .with_inner_size([672.0, 480.0])
                 .with_min_inner_size([320.0, 240.0])
                 .with_title("Tapview - Touchpad Visualizer (Playback)")
-                .with_always_on_top(),
+                .with_window_level(window_level),
             ..Default::default()
         };
 
+        let theme = cli.theme.unwrap_or(settings::app_settings().theme);
+        let trail_ms = cli
+            .trail_ms
+            .unwrap_or(settings::DeviceSettings::default().trail_ms);
         eframe::run_native(
             "Tapview",
             options,
-            Box::new(move |_cc| {
-                Ok(Box::new(TapviewApp::new(
+            Box::new(move |cc| {
+                cc.egui_ctx.set_theme(theme);
+                let mut app = TapviewApp::new(
                     touch_rx,
                     grab_tx,
+                    workers,
+                    libinput_rx,
+                    libinput_cmd_tx,
                     None,
                     None,
-                    None,
+                    log_rx,
                     evdev_extents,
-                    trails,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    false,
+                    None,
+                    palm::PalmThresholds::default(),
+                    trail_ms,
+                    None,
                     None,
                     Some(rec),
-                )))
+                    None,
+                    theme,
+                );
+                app.always_on_top(on_top);
+                if let Some(transform) = view_transform {
+                    app.set_view_transform(transform);
+                }
+                #[cfg(feature = "scripting")]
+                if let Some(script) = script {
+                    app.script(script);
+                }
+                #[cfg(target_os = "linux")]
+                if let Some(device) = replay {
+                    app.replay_into(device);
+                }
+                app.repaint_on_data(&cc.egui_ctx);
+                Ok(Box::new(app))
             }),
         )
         .expect("Failed to run eframe");
@@ -158,23 +486,39 @@ fn main() {
 
     // --- Normal / Recording mode: need a device ---
 
+    // The demo touchpad has nothing for libinput, heatmaps or configuration
+    if cli.demo {
+        cli.no_libinput = true;
+        cli.no_heatmap = true;
+        cli.no_config = true;
+    }
+
     // Discover touchpad
     #[cfg(target_os = "linux")]
-    let devices = UdevDiscovery::find_touchpads();
+    let devices = if cli.demo {
+        Ok(vec![input::demo_backend::device_info()])
+    } else {
+        UdevDiscovery::find_touchpads()
+    };
     #[cfg(target_os = "windows")]
-    let devices = WindowsDiscovery::find_touchpads();
+    let devices = if cli.demo {
+        Ok(vec![input::demo_backend::device_info()])
+    } else {
+        WindowsDiscovery::find_touchpads()
+    };
 
     let devices = match devices {
         Ok(d) => d,
         Err(e) => {
-            eprintln!("Unable to find touchpad: {}", e);
+            log_error!(target: "discovery", "Unable to find touchpad: {}", e);
             std::process::exit(1);
         }
     };
 
     if cli.list {
         for (i, d) in devices.iter().enumerate() {
-            println!("{}: {}", i, d);
+            let saved = settings::has_profile(d, device_name(d).as_deref());
+            println!("{}: {}{}", i, d, if saved { " [profile]" } else { "" });
         }
         std::process::exit(0);
     }
@@ -191,13 +535,109 @@ fn main() {
     } else {
         devices[0].clone()
     };
-    eprintln!("Found touchpad: {}", device);
+    log_info!("Found touchpad: {}", device);
+    let device_name = device_name(&device);
+
+    #[cfg(target_os = "windows")]
+    if let Some(ref path) = cli.dump_descriptor {
+        let dump = input::windows_descriptor::describe(&device.devnode)
+            .and_then(|text| std::fs::write(path, text));
+        if let Err(e) = dump {
+            eprintln!("Failed to dump descriptor: {}", e);
+            std::process::exit(1);
+        }
+        println!("Wrote {}", path.display());
+        std::process::exit(0);
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(Command::SetupPermissions { yes, print }) = cli.command {
+        if let Err(e) = permissions::setup(&device, yes, print) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
 
     // Read evdev axis extents (post-kernel-swap, matches actual event coordinates)
     #[cfg(target_os = "linux")]
     let evdev_extents = input::evdev_backend::read_axis_extents(&device.devnode);
     #[cfg(target_os = "windows")]
     let evdev_extents = None;
+    #[cfg(target_os = "linux")]
+    let evdev_resolution = input::evdev_backend::read_axis_resolution(&device.devnode);
+    #[cfg(target_os = "windows")]
+    let evdev_resolution: Option<(i32, i32)> = None;
+    #[cfg(target_os = "linux")]
+    let pressure_range = input::evdev_backend::read_pressure_range(&device.devnode);
+    #[cfg(target_os = "windows")]
+    let pressure_range: Option<(i32, i32)> = None;
+    #[cfg(target_os = "linux")]
+    let orientation_max = input::evdev_backend::read_orientation_max(&device.devnode);
+    #[cfg(target_os = "windows")]
+    let orientation_max: Option<i32> = None;
+    #[cfg(target_os = "linux")]
+    let distance_max = input::evdev_backend::read_distance_max(&device.devnode);
+    #[cfg(target_os = "windows")]
+    let distance_max: Option<i32> = None;
+    let (evdev_extents, evdev_resolution, pressure_range) = if cli.demo {
+        use input::demo_backend::{EXTENTS, PRESSURE_RANGE, RESOLUTION};
+        (Some(EXTENTS), Some(RESOLUTION), Some(PRESSURE_RANGE))
+    } else {
+        (evdev_extents, evdev_resolution, pressure_range)
+    };
+    // The Magic Trackpad's size is known where its driver doesn't say
+    let evdev_resolution = evdev_resolution.or_else(|| {
+        let (vendor, product) = device.vendor_id.zip(device.product_id)?;
+        input::magic_trackpad::is_magic_trackpad(vendor, product).then(|| {
+            let (rx, ry) = input::magic_trackpad::resolution();
+            (rx.round() as i32, ry.round() as i32)
+        })
+    });
+    // Resolution overrides go in the udev hwdb, which only exists on Linux
+    #[cfg(target_os = "linux")]
+    let hwdb_match = resolution_test::hwdb_match(
+        device.vendor_id.zip(device.product_id),
+        device_name.as_deref(),
+    );
+    #[cfg(target_os = "windows")]
+    let hwdb_match: Option<String> = None;
+    #[cfg(target_os = "linux")]
+    let buttonpad = input::evdev_backend::read_is_buttonpad(&device.devnode);
+    #[cfg(target_os = "windows")]
+    let buttonpad = false;
+    #[cfg(target_os = "linux")]
+    let semi_mt = input::evdev_backend::read_is_semi_mt(&device.devnode);
+    #[cfg(target_os = "windows")]
+    let semi_mt = false;
+    #[cfg(target_os = "linux")]
+    let slot_count = input::evdev_backend::read_slot_count(&device.devnode);
+    #[cfg(target_os = "windows")]
+    let slot_count: Option<usize> = None;
+    // libinput's palm/thumb thresholds, with device quirks if the tool is installed
+    #[cfg(target_os = "linux")]
+    let palm_thresholds = {
+        let mut thresholds = palm::PalmThresholds::defaults(pressure_range.is_some());
+        if let Some(quirks) = libinput_backend::read_quirks(&device.devnode) {
+            thresholds.apply_quirks(&quirks);
+        }
+        thresholds
+    };
+    #[cfg(target_os = "windows")]
+    let palm_thresholds = palm::PalmThresholds::defaults(false);
+
+    // The report descriptor, for the descriptor window
+    #[cfg(target_os = "linux")]
+    let descriptor = (!cli.demo)
+        .then(|| input::descriptor_tree::read(&device.devnode).map_err(|e| e.to_string()));
+    #[cfg(target_os = "windows")]
+    let descriptor = (!cli.demo)
+        .then(|| input::windows_descriptor::tree(&device.devnode).map_err(|e| e.to_string()));
+
+    // udev properties, quirks and hwdb entries, for the side panel
+    #[cfg(target_os = "linux")]
+    let device_properties =
+        (!cli.demo).then(|| device_properties::DeviceProperties::read(&device.devnode));
 
     // Discover PTP configuration features (auto-detected by default, forced with --config)
     let ptp_config = if cli.no_config && !cli.info {
@@ -205,7 +645,7 @@ fn main() {
     } else {
         let cfg = config::discover(&device.devnode);
         if cfg.is_none() && cli.config {
-            eprintln!("config: no PTP configuration features found");
+            log_error!(target: "config", "no PTP configuration features found");
             std::process::exit(1);
         }
         cfg
@@ -213,19 +653,29 @@ fn main() {
 
     // Log and compare axis ranges from both sources
     if let Some((ex, ey)) = &evdev_extents {
-        eprintln!("axis: evdev extents: x=0..{}, y=0..{}", ex, ey);
+        log_info!(target: "axis", "evdev extents: x=0..{}, y=0..{}", ex, ey);
+    }
+    if let Some((rx, ry)) = &evdev_resolution {
+        log_info!(
+            target: "axis", "evdev resolution: x={} units/mm, y={} units/mm",
+            rx,
+            ry
+        );
     }
     let axis_swap_detected = if let Some(cfg) = &ptp_config {
         if let Some(phys) = &cfg.physical_size {
-            eprintln!(
-                "axis: HID descriptor: x={}..{}, y={}..{}",
-                phys.x.logical_min, phys.x.logical_max, phys.y.logical_min, phys.y.logical_max
+            log_info!(
+                target: "axis", "HID descriptor: x={}..{}, y={}..{}",
+                phys.x.logical_min,
+                phys.x.logical_max,
+                phys.y.logical_min,
+                phys.y.logical_max
             );
             if let Some((ex, ey)) = &evdev_extents {
                 if *ex != phys.x.logical_max || *ey != phys.y.logical_max {
-                    eprintln!("axis: evdev and HID descriptor disagree!");
+                    log_warn!(target: "axis", "evdev and HID descriptor disagree!");
                     if *ex == phys.y.logical_max && *ey == phys.x.logical_max {
-                        eprintln!("axis: looks like a kernel axis swap");
+                        log_warn!(target: "axis", "looks like a kernel axis swap");
                         Some(true)
                     } else {
                         Some(false)
@@ -254,12 +704,33 @@ fn main() {
         if let Some(pid) = device.product_id {
             println!("  Product ID:       {:04x}", pid);
         }
+        if let Some(model) = device.model() {
+            println!("  Model:            {}", model);
+        }
         println!();
 
         if let Some((ex, ey)) = &evdev_extents {
             println!("Evdev axes");
             println!("  X range:          0..{}", ex);
             println!("  Y range:          0..{}", ey);
+            if let Some((rx, ry)) = &evdev_resolution {
+                println!("  X resolution:     {} units/mm", rx);
+                println!("  Y resolution:     {} units/mm", ry);
+            }
+            if let Some((pmin, pmax)) = &pressure_range {
+                println!("  Pressure range:   {}..{}", pmin, pmax);
+            }
+            println!(
+                "  Buttonpad:        {}",
+                if buttonpad { "yes" } else { "no" }
+            );
+            if let Some(count) = slot_count {
+                println!("  Slots:            {}", count);
+            }
+            println!(
+                "  Semi-MT:          {}",
+                if semi_mt { "yes (bounding box)" } else { "no" }
+            );
             println!();
         }
 
@@ -372,7 +843,7 @@ fn main() {
         let mut cfg = match ptp_config {
             Some(c) => c,
             None => {
-                eprintln!("config: device has no PTP/haptic configuration features");
+                log_error!(target: "config", "device has no PTP/haptic configuration features");
                 std::process::exit(1);
             }
         };
@@ -386,14 +857,14 @@ fn main() {
                 cfg.haptic_intensity_range.as_ref(),
             );
             if !matches!(value, 0 | 25 | 50 | 75 | 100) {
-                eprintln!(
-                    "config: haptic intensity must be one of 0, 25, 50, 75, 100 (got {})",
+                log_error!(
+                    target: "config", "haptic intensity must be one of 0, 25, 50, 75, 100 (got {})",
                     value
                 );
                 std::process::exit(1);
             }
             if let Err(e) = cfg.set_haptic_intensity(value) {
-                eprintln!("config: failed to set haptic intensity: {}", e);
+                log_error!(target: "config", "failed to set haptic intensity: {}", e);
                 std::process::exit(1);
             }
             println!("haptic intensity set to {}", value);
@@ -408,7 +879,7 @@ fn main() {
                 cfg.button_press_threshold_range.as_ref(),
             );
             if let Err(e) = cfg.set_button_press_threshold(value) {
-                eprintln!("config: failed to set click force: {}", e);
+                log_error!(target: "config", "failed to set click force: {}", e);
                 std::process::exit(1);
             }
             println!("click force set to {}", value);
@@ -416,6 +887,18 @@ fn main() {
         std::process::exit(0);
     }
 
+    // Resolution for mm display: prefer evdev, fall back to the HID descriptor
+    let resolution = evdev_resolution
+        .map(|(rx, ry)| (rx as f32, ry as f32))
+        .or_else(|| {
+            ptp_config.as_ref().and_then(|cfg| {
+                cfg.physical_size
+                    .as_ref()
+                    .filter(|phys| phys.x.resolution > 0.0 && phys.y.resolution > 0.0)
+                    .map(|phys| (phys.x.resolution as f32, phys.y.resolution as f32))
+            })
+        });
+
     // Create recorder if --record was specified
     // Resolve axis extents for recording: prefer evdev, fall back to PTP logical extents
     let record_extents = evdev_extents.or_else(|| {
@@ -429,9 +912,15 @@ fn main() {
     // Create recorder if --record was specified
     let recorder = if let Some(ref record_path) = cli.record {
         let (ex, ey) = record_extents.unwrap_or((0, 0));
-        match recording::Recorder::new(record_path, ex, ey) {
+        // The device as evdev describes it, so --replay-uinput can recreate it
+        #[cfg(target_os = "linux")]
+        let capabilities = input::evdev_backend::read_capabilities(&device.devnode);
+        #[cfg(target_os = "windows")]
+        let capabilities = None;
+        let capabilities = capabilities.filter(|_| !cli.demo).unwrap_or_default();
+        match recording::Recorder::new(record_path, ex, ey, &capabilities) {
             Ok(r) => {
-                eprintln!("Recording to: {}", record_path);
+                log_info!("Recording to: {}", record_path);
                 Some(r)
             }
             Err(e) => {
@@ -443,161 +932,415 @@ fn main() {
         None
     };
 
-    // Create channels
-    let (touch_tx, touch_rx) = mpsc::channel();
-    let (grab_tx, grab_rx) = mpsc::channel::<GrabCommand>();
-
-    // Spawn input thread
-    let device_path = device.devnode.clone();
-    let verbose = cli.verbose;
-
-    #[cfg(target_os = "linux")]
-    thread::spawn(move || {
-        let mut backend = match EvdevBackend::open_with_verbose(&device_path, verbose) {
-            Ok(b) => b,
+    let libinput_export = if let Some(ref log_path) = cli.libinput_log {
+        match libinput_export::LibinputExporter::new(log_path) {
+            Ok(e) => {
+                log_info!("Writing libinput events to: {}", log_path);
+                Some(e)
+            }
             Err(e) => {
-                eprintln!("Failed to open device: {}", e);
-                return;
+                eprintln!("Failed to create libinput log file: {}", e);
+                std::process::exit(1);
             }
-        };
+        }
+    } else {
+        None
+    };
 
-        loop {
-            // Check for grab/ungrab commands
-            if let Ok(cmd) = grab_rx.try_recv() {
-                match cmd {
-                    GrabCommand::Grab => {
-                        if let Err(e) = backend.grab() {
-                            eprintln!("Grab failed: {}", e);
-                        }
-                    }
-                    GrabCommand::Ungrab => {
-                        if let Err(e) = backend.ungrab() {
-                            eprintln!("Ungrab failed: {}", e);
-                        }
-                    }
-                }
+    let tuio = cli
+        .tuio
+        .as_deref()
+        .map(|target| match tuio::TuioOutput::new(target) {
+            Ok(output) => {
+                log_info!("Sending TUIO to: {}", output.target);
+                output
             }
+            Err(e) => {
+                eprintln!("Failed to set up TUIO output to {}: {}", target, e);
+                std::process::exit(1);
+            }
+        });
 
-            match backend.poll_events() {
-                Ok(Some(state)) => {
-                    let _ = touch_tx.send(state);
-                }
-                Ok(None) => {
-                    thread::sleep(Duration::from_millis(5));
-                }
-                Err(e) => {
-                    eprintln!("Input error: {}", e);
-                    break;
-                }
+    #[cfg(feature = "midi")]
+    let midi = cli
+        .midi
+        .as_deref()
+        .map(|device| match midi::MidiOutput::open(device) {
+            Ok(output) => {
+                log_info!("Sending MIDI to: {}", output.name);
+                output
             }
+            Err(e) => {
+                eprintln!("Failed to open MIDI output {}: {}", device, e);
+                std::process::exit(1);
+            }
+        });
+
+    #[cfg(feature = "scripting")]
+    let script = spawn_script(cli.script.as_deref(), record_extents);
+
+    let mut settings = settings::SettingsStore::load(&device, device_name.as_deref());
+    let theme = cli.theme.unwrap_or(settings.app().theme);
+    let trail_ms = cli.trail_ms.unwrap_or(settings.device().trail_ms);
+    if cli.heatmap_cols.is_some() {
+        settings.device_mut().heatmap_cols = cli.heatmap_cols;
+    }
+    let heatmap_cols = settings.device().heatmap_cols;
+    if let Some(BurstLen::Fixed(len)) = cli.burst_len {
+        settings.device_mut().heatmap_burst_len = Some(len);
+    }
+    let heatmap_options = DriverOptions {
+        burst_len: cli
+            .burst_len
+            .or(settings.device().heatmap_burst_len.map(BurstLen::Fixed)),
+        stream: cli.heatmap_stream,
+        alc_on: cli.alc_on.clone(),
+        alc_off: cli.alc_off.clone(),
+        recalibrate: cli.recalibrate.clone(),
+    };
+
+    // Create channels
+    let (touch_tx, touch_rx) = channel::bounded(TOUCH_CHANNEL_CAPACITY);
+    let (grab_tx, grab_rx) =
+        input::command_channel::<GrabCommand>().expect("Failed to create command channel");
+
+    // Spawn input thread. Shutdown wakes it like a command would; dropping
+    // the backend on the way out releases a grab and closes the device.
+    let mut workers = Workers::default();
+    let shutdown = workers.shutdown.clone();
+    let errors = workers.errors_tx.clone();
+    let waker = grab_rx.waker().clone();
+    workers.shutdown.on_request(move || waker.wake());
+    let device_path = device.devnode.clone();
+
+    let demo = cli.demo;
+    #[cfg(target_os = "linux")]
+    let (forward, source) = (cli.forward, cli.backend);
+    #[cfg(target_os = "linux")]
+    if forward.is_some() && source == InputSource::Hidraw {
+        log_warn!("--forward only works with the evdev backend");
+    }
+    // Only the evdev backend plays force feedback
+    #[cfg(target_os = "linux")]
+    let haptics = !demo
+        && source == InputSource::Evdev
+        && input::evdev_backend::read_has_haptics(&device_path);
+    #[cfg(target_os = "windows")]
+    let haptics = false;
+    #[cfg(target_os = "linux")]
+    let input_thread = thread::spawn(move || {
+        let opened = match source {
+            _ if demo => DemoBackend::open(&device_path)
+                .map(|backend| run_input(backend, &grab_rx, &touch_tx, &shutdown, &errors)),
+            InputSource::Evdev => EvdevBackend::open(&device_path).map(|mut backend| {
+                backend.set_forwarding(forward);
+                run_input(backend, &grab_rx, &touch_tx, &shutdown, &errors)
+            }),
+            InputSource::Hidraw => HidrawBackend::open(&device_path)
+                .map(|backend| run_input(backend, &grab_rx, &touch_tx, &shutdown, &errors)),
+        };
+        if let Err(e) = opened {
+            let message = format!("Failed to open device: {}", e);
+            report(&errors, BackendError::new(Backend::Input, message));
         }
     });
 
     #[cfg(target_os = "windows")]
-    thread::spawn(move || {
-        let _ = verbose; // verbose logging not yet implemented for Windows
-        let mut backend = match WindowsBackend::open(&device_path) {
-            Ok(b) => b,
-            Err(e) => {
-                eprintln!("Failed to open device: {}", e);
-                return;
-            }
+    let input_thread = thread::spawn(move || {
+        let opened = if demo {
+            DemoBackend::open(&device_path)
+                .map(|backend| run_input(backend, &grab_rx, &touch_tx, &shutdown, &errors))
+        } else {
+            WindowsBackend::open(&device_path)
+                .map(|backend| run_input(backend, &grab_rx, &touch_tx, &shutdown, &errors))
         };
-
-        loop {
-            if let Ok(cmd) = grab_rx.try_recv() {
-                match cmd {
-                    GrabCommand::Grab => {
-                        if let Err(e) = backend.grab() {
-                            eprintln!("Grab failed: {}", e);
-                        }
-                    }
-                    GrabCommand::Ungrab => {
-                        if let Err(e) = backend.ungrab() {
-                            eprintln!("Ungrab failed: {}", e);
-                        }
-                    }
-                }
-            }
-
-            match backend.poll_events() {
-                Ok(Some(state)) => {
-                    let _ = touch_tx.send(state);
-                }
-                Ok(None) => {
-                    thread::sleep(Duration::from_millis(5));
-                }
-                Err(e) => {
-                    eprintln!("Input error: {}", e);
-                    break;
-                }
-            }
+        if let Err(e) = opened {
+            let message = format!("Failed to open device: {}", e);
+            report(&errors, BackendError::new(Backend::Input, message));
         }
     });
 
+    workers.threads.push(("input", input_thread));
+
     // Spawn libinput/interpreted input backend thread (enabled by default)
     #[cfg(target_os = "linux")]
-    let libinput_rx = if !cli.no_libinput {
-        Some(libinput_backend::spawn_libinput_thread(&device.devnode))
+    let (libinput_rx, libinput_cmd_tx) = if !cli.no_libinput {
+        let (rx, cmd_tx, thread) = libinput_backend::spawn_libinput_thread(
+            &device.devnode,
+            workers.shutdown.clone(),
+            workers.errors_tx.clone(),
+        );
+        workers.threads.push(("libinput", thread));
+        (Some(rx), Some(cmd_tx))
     } else {
-        None
+        (None, None)
     };
 
     #[cfg(target_os = "windows")]
     let libinput_rx = if !cli.no_libinput {
-        Some(windows_input_backend::spawn_windows_input_thread())
+        let (rx, thread) = windows_input_backend::spawn_windows_input_thread(
+            workers.shutdown.clone(),
+            workers.errors_tx.clone(),
+        );
+        workers.threads.push(("mouse hook", thread));
+        Some(rx)
     } else {
         None
     };
+    // RawInput mouse data has no configuration to change
+    #[cfg(target_os = "windows")]
+    let libinput_cmd_tx = None;
+
+    // Spawn the pen thread when the touch node has a pen sibling
+    #[cfg(target_os = "linux")]
+    let pen_rx = if cli.no_pen || demo {
+        None
+    } else {
+        discovery::udev_discovery::find_sibling_pen(&device.devnode).map(|path| {
+            let (rx, thread) =
+                pen::spawn_pen_thread(&path, workers.shutdown.clone(), workers.errors_tx.clone());
+            workers.threads.push(("pen", thread));
+            rx
+        })
+    };
 
     // Spawn heatmap backend thread (auto-detected by default, forced with --heatmap)
+    let mut heatmap_unavailable = None;
+    let registers = RegisterWatch::default();
     let heatmap_rx = if cli.no_heatmap {
         None
     } else {
-        spawn_heatmap(&device, cli.heatmap_cols, cli.heatmap)
+        match spawn_heatmap(
+            &device,
+            &heatmap_options,
+            heatmap_cols,
+            &registers,
+            &workers.shutdown,
+        ) {
+            Ok((rx, thread)) => {
+                workers.threads.push(("heatmap", thread));
+                Some(rx)
+            }
+            // Only worth mentioning when asked for explicitly; the panel says
+            // why and the heatmap starts once a matching device turns up
+            Err(e) if cli.heatmap => {
+                log_warn!(target: "heatmap", "heatmap unavailable: {}", e);
+                let (rx, thread) = spawn_heatmap_retry(
+                    device.clone(),
+                    heatmap_options,
+                    heatmap_cols,
+                    registers.clone(),
+                    workers.shutdown.clone(),
+                );
+                workers.threads.push(("heatmap retry", thread));
+                heatmap_unavailable = Some((e, rx));
+                None
+            }
+            Err(_) => None,
+        }
     };
 
+    // Registers are read by the heatmap thread, so only offer them with one
+    let registers = (heatmap_rx.is_some() || heatmap_unavailable.is_some()).then_some(registers);
+
     // Run eframe
     let is_recording = recorder.is_some();
     let mut initial_width = if libinput_rx.is_some() { 1100.0 } else { 672.0 };
     if ptp_config.is_some() {
         initial_width += 220.0;
     }
-    let initial_height = if heatmap_rx.is_some() { 650.0 } else { 432.0 };
+    let mut initial_height = if heatmap_rx.is_some() { 650.0 } else { 432.0 };
+    if let Some((w, h)) = settings.device().window_size {
+        initial_width = w;
+        initial_height = h;
+    }
     let title = if is_recording {
         "Tapview - Touchpad Visualizer (Recording)"
+    } else if demo {
+        "Tapview - Touchpad Visualizer (Demo)"
     } else {
         "Tapview - Touchpad Visualizer"
     };
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size([initial_width, initial_height])
+        .with_min_inner_size([320.0, 240.0])
+        .with_title(title)
+        .with_window_level(window_level);
+    if let Some((x, y)) = settings.device().window_pos {
+        viewport = viewport.with_position([x, y]);
+    }
+    // winit has no layer-shell surfaces, so on Wayland this is a regular
+    // window that the compositor may still place and stack as it likes
+    if cli.overlay {
+        overlay::listen();
+        viewport = viewport
+            .with_decorations(false)
+            .with_transparent(true)
+            .with_mouse_passthrough(true);
+    }
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([initial_width, initial_height])
-            .with_min_inner_size([320.0, 240.0])
-            .with_title(title)
-            .with_always_on_top(),
+        viewport,
         ..Default::default()
     };
+    let overlay = cli.overlay;
 
     eframe::run_native(
         "Tapview",
         options,
-        Box::new(move |_cc| {
-            Ok(Box::new(TapviewApp::new(
+        Box::new(move |cc| {
+            cc.egui_ctx.set_theme(theme);
+            let mut app = TapviewApp::new(
                 touch_rx,
                 grab_tx,
+                workers,
                 libinput_rx,
+                libinput_cmd_tx,
                 heatmap_rx,
                 ptp_config,
+                log_rx,
                 evdev_extents,
-                trails,
+                resolution,
+                pressure_range,
+                orientation_max,
+                distance_max,
+                hwdb_match,
+                buttonpad,
+                semi_mt,
+                slot_count,
+                palm_thresholds,
+                trail_ms,
                 recorder,
+                libinput_export,
                 None,
-            )))
+                Some(settings),
+                theme,
+            );
+            app.always_on_top(on_top);
+            if let Some(transform) = view_transform {
+                app.set_view_transform(transform);
+            }
+            if cli.notify {
+                app.notify();
+            }
+            if haptics {
+                app.haptics();
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(rx) = pen_rx {
+                app.pen_input(rx);
+            }
+            if let Some(tree) = descriptor {
+                app.descriptor(tree);
+            }
+            #[cfg(feature = "scripting")]
+            if let Some(script) = script {
+                app.script(script);
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(properties) = device_properties {
+                app.device_properties(properties);
+            }
+            if let Some(output) = tuio {
+                app.tuio_output(output);
+            }
+            #[cfg(feature = "midi")]
+            if let Some(output) = midi {
+                app.midi_output(output);
+            }
+            if let Some((reason, rx)) = heatmap_unavailable {
+                app.heatmap_unavailable(reason, rx);
+            }
+            if let Some(registers) = registers {
+                app.heatmap_registers(registers);
+            }
+            if overlay {
+                app.overlay();
+            }
+            app.repaint_on_data(&cc.egui_ctx);
+            Ok(Box::new(app))
         }),
     )
     .expect("Failed to run eframe");
 }
 
+/// Read touches until shutdown or a read error, applying grab commands as
+/// they come; sending one wakes the wait. Dropping the backend on the way
+/// out releases a grab and closes the device.
+fn run_input(
+    mut backend: impl InputBackend,
+    grab_rx: &input::CommandReceiver<GrabCommand>,
+    touch_tx: &channel::BoundedSender<input::TouchState>,
+    shutdown: &Shutdown,
+    errors: &channel::BoundedSender<BackendError>,
+) {
+    while !shutdown.is_requested() {
+        while let Ok(cmd) = grab_rx.try_recv() {
+            match cmd {
+                GrabCommand::Grab => {
+                    if let Err(e) = backend.grab() {
+                        log_error!("Grab failed: {}", e);
+                    }
+                }
+                GrabCommand::Ungrab => {
+                    if let Err(e) = backend.ungrab() {
+                        log_error!("Ungrab failed: {}", e);
+                    }
+                }
+                GrabCommand::Pulse => {
+                    if let Err(e) = backend.pulse() {
+                        log_error!("{}", e);
+                    }
+                }
+            }
+        }
+
+        match backend.poll_events() {
+            Ok(Some(state)) => {
+                let _ = touch_tx.send(state);
+            }
+            Ok(None) => {
+                if let Err(e) = backend.wait(grab_rx.waker()) {
+                    let message = format!("Input error: {}", e);
+                    report(errors, BackendError::new(Backend::Input, message));
+                    break;
+                }
+            }
+            Err(e) => {
+                let message = format!("Input error: {}", e);
+                report(errors, BackendError::new(Backend::Input, message));
+                break;
+            }
+        }
+    }
+}
+
+/// The device's name, part of its settings profile key.
+#[cfg(target_os = "linux")]
+fn device_name(device: &discovery::DeviceInfo) -> Option<String> {
+    input::evdev_backend::read_device_name(&device.devnode)
+}
+
+#[cfg(target_os = "windows")]
+fn device_name(_device: &discovery::DeviceInfo) -> Option<String> {
+    None
+}
+
+/// Start `--script`, exiting if it can't be run.
+#[cfg(feature = "scripting")]
+fn spawn_script(
+    path: Option<&std::path::Path>,
+    extents: Option<(i32, i32)>,
+) -> Option<script::ScriptHook> {
+    let path = path?;
+    match script::ScriptHook::spawn(path, extents) {
+        Ok(script) => Some(script),
+        Err(e) => {
+            eprintln!("Failed to run script '{}': {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
 /// Validate a CLI-provided value against a feature's presence/writability/range.
 /// Exits the process with a clear error message on any check failure.
 fn check_set_value(
@@ -608,18 +1351,18 @@ fn check_set_value(
     range: Option<&config::ValueRange>,
 ) {
     if !has_feature {
-        eprintln!("config: device does not expose {}", label);
+        log_error!(target: "config", "device does not expose {}", label);
         std::process::exit(1);
     }
     if !writable {
-        eprintln!("config: {} is read-only on this device", label);
+        log_error!(target: "config", "{} is read-only on this device", label);
         std::process::exit(1);
     }
     if let Some(r) = range {
         let v = value as i32;
         if v < r.logical_min || v > r.logical_max {
-            eprintln!(
-                "config: {} value {} out of range ({}..={})",
+            log_error!(
+                target: "config", "{} value {} out of range ({}..={})",
                 label, value, r.logical_min, r.logical_max
             );
             std::process::exit(1);
@@ -627,68 +1370,68 @@ fn check_set_value(
     }
 }
 
-#[cfg(target_os = "linux")]
+type HeatmapThread = (
+    channel::LatestReceiver<heatmap::HeatmapFrame>,
+    thread::JoinHandle<()>,
+);
+
 fn spawn_heatmap(
     device: &discovery::DeviceInfo,
+    options: &DriverOptions,
     heatmap_cols: Option<usize>,
-    force: bool,
-) -> Option<std::sync::mpsc::Receiver<heatmap::HeatmapFrame>> {
-    match heatmap::discovery::find_sibling_hidraw(&device.devnode) {
-        Ok(hidraw_path) => {
-            eprintln!("heatmap: found hidraw device: {}", hidraw_path.display());
-            match heatmap::discovery::determine_burst_report_length(&hidraw_path) {
-                Ok(burst_len) => {
-                    eprintln!("heatmap: burst report length = {}", burst_len);
-                    Some(heatmap::backend::spawn_heatmap_thread(
-                        &hidraw_path,
-                        burst_len,
-                        heatmap_cols,
-                    ))
+    registers: &RegisterWatch,
+    shutdown: &Shutdown,
+) -> Result<HeatmapThread, String> {
+    let found = heatmap::discovery::find_heatmap_device(&device.devnode)
+        .map_err(|e| format!("failed to find a heatmap device: {}", e))?;
+    log_info!(
+        target: "heatmap", "found {} heatmap device: {}",
+        found.protocol.name,
+        found.path.display()
+    );
+    Ok(heatmap::backend::spawn_heatmap_thread(
+        found,
+        options.clone(),
+        heatmap_cols,
+        registers.clone(),
+        shutdown.clone(),
+    ))
+}
+
+/// Try starting the heatmap again whenever a HID device appears or changes,
+/// sending each new reason it's unavailable and finally its frames. The
+/// thread then waits for the heatmap thread it started.
+fn spawn_heatmap_retry(
+    device: discovery::DeviceInfo,
+    options: DriverOptions,
+    heatmap_cols: Option<usize>,
+    registers: RegisterWatch,
+    shutdown: Shutdown,
+) -> (app::HeatmapRetryReceiver, thread::JoinHandle<()>) {
+    let (tx, rx) = channel::bounded(4);
+    let thread = thread::spawn(move || {
+        let mut started = None;
+        let watched = heatmap::discovery::watch_hid_devices(&shutdown, || {
+            match spawn_heatmap(&device, &options, heatmap_cols, &registers, &shutdown) {
+                Ok((frames, thread)) => {
+                    log_info!(target: "heatmap", "heatmap device appeared");
+                    let _ = tx.send(Ok(frames));
+                    started = Some(thread);
+                    true
                 }
                 Err(e) => {
-                    if force {
-                        eprintln!("heatmap: failed to determine burst length: {}", e);
-                        std::process::exit(1);
-                    }
-                    None
+                    log_debug!(target: "heatmap", "still no heatmap: {}", e);
+                    let _ = tx.send(Err(e));
+                    false
                 }
             }
+        });
+        if let Err(e) = watched {
+            log_warn!(target: "heatmap", "can't watch for HID devices: {}", e);
         }
-        Err(e) => {
-            if force {
-                eprintln!("heatmap: failed to find sibling hidraw device: {}", e);
-                std::process::exit(1);
-            }
-            None
-        }
-    }
-}
-
-#[cfg(target_os = "windows")]
-fn spawn_heatmap(
-    device: &discovery::DeviceInfo,
-    heatmap_cols: Option<usize>,
-    force: bool,
-) -> Option<std::sync::mpsc::Receiver<heatmap::HeatmapFrame>> {
-    match heatmap::discovery::find_hid_device_for_heatmap(&device.devnode) {
-        Ok((hid_path, burst_len)) => {
-            eprintln!(
-                "heatmap: found HID device: {}, burst_len={}",
-                hid_path.display(),
-                burst_len
-            );
-            Some(heatmap::backend::spawn_heatmap_thread(
-                &hid_path,
-                burst_len,
-                heatmap_cols,
-            ))
-        }
-        Err(e) => {
-            if force {
-                eprintln!("heatmap: {}", e);
-                std::process::exit(1);
-            }
-            None
+        if let Some(thread) = started {
+            let _ = thread.join();
         }
-    }
+    });
+    (rx, thread)
 }
@@ -0,0 +1,175 @@
+//! `--midi`: the touchpad as a MIDI controller. Each slot sends on its own
+//! channel (slot 0 on channel 1, ...) three control changes: X as CC 16, Y
+//! as CC 17 (top = 127) and pressure as CC 18, all scaled to 0..=127 over
+//! the touch view. A value is only sent when it changes; a lift sends
+//! pressure 0 and leaves X and Y where they were, like letting go of a knob.
+//!
+//! The messages go out through [midir](https://docs.rs/midir) (ALSA
+//! sequencer on Linux, winmm on Windows, CoreMIDI on macOS) to an output port
+//! picked by number or by part of its name. Built with the `midi` feature.
+
+use std::io;
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// Controller numbers for X, Y and pressure (General Purpose 1-3).
+pub const CC_X: u8 = 16;
+pub const CC_Y: u8 = 17;
+pub const CC_PRESSURE: u8 = 18;
+const CONTROL_CHANGE: u8 = 0xB0;
+
+/// Last value sent per slot, in the order X, Y, pressure
+type Sent = [Option<u8>; 3];
+
+pub struct MidiOutput {
+    port: Port,
+    pub name: String,
+    sent: [Sent; MAX_TOUCH_POINTS],
+}
+
+impl MidiOutput {
+    pub fn open(device: &str) -> io::Result<Self> {
+        let (port, name) = Port::open(device)?;
+        Ok(Self {
+            port,
+            name,
+            sent: [[None; 3]; MAX_TOUCH_POINTS],
+        })
+    }
+
+    /// Send the changes for one report, for a touch view of `extents` and
+    /// pressure over `pressure_range`.
+    pub fn send(
+        &mut self,
+        touches: &[TouchData],
+        extents: (f32, f32),
+        pressure_range: (i32, i32),
+    ) -> io::Result<()> {
+        let messages = messages(&mut self.sent, touches, extents, pressure_range);
+        if messages.is_empty() {
+            return Ok(());
+        }
+        self.port.write(&messages)
+    }
+}
+
+/// Scale `value` within `min..=max` to 0..=127.
+fn scale(value: f32, min: f32, max: f32) -> u8 {
+    if max <= min {
+        return 0;
+    }
+    ((value - min) / (max - min) * 127.0)
+        .round()
+        .clamp(0.0, 127.0) as u8
+}
+
+/// The control changes that differ from `sent`, which is updated.
+fn messages(
+    sent: &mut [Sent; MAX_TOUCH_POINTS],
+    touches: &[TouchData],
+    (w, h): (f32, f32),
+    (pmin, pmax): (i32, i32),
+) -> Vec<[u8; 3]> {
+    let mut out = Vec::new();
+    for (slot, touch) in touches.iter().enumerate().take(MAX_TOUCH_POINTS.min(16)) {
+        let values = if touch.used {
+            [
+                Some(scale(touch.position_x as f32, 0.0, w)),
+                Some(127 - scale(touch.position_y as f32, 0.0, h)),
+                Some(scale(touch.pressure as f32, pmin as f32, pmax as f32)),
+            ]
+        } else {
+            [None, None, sent[slot][2].map(|_| 0)]
+        };
+        for (i, (value, cc)) in values
+            .into_iter()
+            .zip([CC_X, CC_Y, CC_PRESSURE])
+            .enumerate()
+        {
+            if let Some(value) = value {
+                if sent[slot][i] != Some(value) {
+                    sent[slot][i] = Some(value);
+                    out.push([CONTROL_CHANGE | slot as u8, cc, value]);
+                }
+            }
+        }
+    }
+    out
+}
+
+struct Port(midir::MidiOutputConnection);
+
+impl Port {
+    fn open(device: &str) -> io::Result<(Self, String)> {
+        let output = midir::MidiOutput::new("tapview").map_err(io::Error::other)?;
+        let ports = output.ports();
+        let names: Vec<String> = ports
+            .iter()
+            .map(|port| output.port_name(port).unwrap_or_default())
+            .collect();
+        let wanted = device.to_lowercase();
+        let id = device
+            .parse::<usize>()
+            .ok()
+            .filter(|&id| id < names.len())
+            .or_else(|| {
+                names
+                    .iter()
+                    .position(|n| n.to_lowercase().contains(&wanted))
+            })
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "no MIDI output {:?}; there are: {}",
+                        device,
+                        names.join(", ")
+                    ),
+                )
+            })?;
+        let connection = output
+            .connect(&ports[id], "tapview")
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok((Self(connection), names[id].clone()))
+    }
+
+    fn write(&mut self, messages: &[[u8; 3]]) -> io::Result<()> {
+        for message in messages {
+            self.0.send(message).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_changed_controls_per_slot_channel() {
+        let mut sent = [[None; 3]; MAX_TOUCH_POINTS];
+        let mut touches = [TouchData::default(); 2];
+        touches[1] = TouchData {
+            used: true,
+            position_x: 1000,
+            position_y: 0,
+            pressure: 50,
+            ..Default::default()
+        };
+        let extents = (1000.0, 500.0);
+        let out = messages(&mut sent, &touches, extents, (0, 100));
+        assert_eq!(
+            out,
+            vec![
+                [0xB1, CC_X, 127],
+                [0xB1, CC_Y, 127],
+                [0xB1, CC_PRESSURE, 64],
+            ]
+        );
+        // Nothing changed, nothing sent
+        assert!(messages(&mut sent, &touches, extents, (0, 100)).is_empty());
+
+        touches[1].used = false;
+        let out = messages(&mut sent, &touches, extents, (0, 100));
+        assert_eq!(out, vec![[0xB1, CC_PRESSURE, 0]]);
+    }
+}
@@ -0,0 +1,102 @@
+//! Desktop notifications for when tapview is left running in the background:
+//! a device that failed or went away, a recording that ended, and what the
+//! heatmap watch spots (firmware calibration episodes, stuck cells).
+//!
+//! They're sent with notify-rust: over D-Bus on Linux and as a toast on
+//! Windows. If one can't be shown, it's logged once and nothing more is
+//! tried. Each kind of notice is sent at most once per `COOLDOWN`, so a
+//! flapping device doesn't bury the desktop.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Notice {
+    DeviceError,
+    RecordingEnded,
+    Calibration,
+    StuckCell,
+}
+
+impl Notice {
+    fn summary(self) -> &'static str {
+        match self {
+            Notice::DeviceError => "tapview: device error",
+            Notice::RecordingEnded => "tapview: recording ended",
+            Notice::Calibration => "tapview: firmware calibration",
+            Notice::StuckCell => "tapview: stuck heatmap cell",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Notifier {
+    pub enabled: bool,
+    /// When each kind was last sent
+    sent: HashMap<Notice, Instant>,
+    /// A notification couldn't be shown
+    unavailable: Arc<AtomicBool>,
+}
+
+impl Notifier {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Self::default()
+        }
+    }
+
+    pub fn notify(&mut self, notice: Notice, body: &str) {
+        if !self.enabled
+            || self.unavailable.load(Ordering::Relaxed)
+            || !self.due(notice, Instant::now())
+        {
+            return;
+        }
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .appname("tapview")
+            .summary(notice.summary())
+            .body(body);
+        let unavailable = self.unavailable.clone();
+        // Showing one can block on D-Bus, so not on the UI thread
+        thread::spawn(move || {
+            if let Err(e) = notification.show() {
+                if !unavailable.swap(true, Ordering::Relaxed) {
+                    log_warn!("desktop notifications unavailable: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Whether `notice` may be sent at `now`, marking it sent if so.
+    fn due(&mut self, notice: Notice, now: Instant) -> bool {
+        match self.sent.get(&notice) {
+            Some(&last) if now.duration_since(last) < COOLDOWN => false,
+            _ => {
+                self.sent.insert(notice, now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_each_kind_once_per_cooldown() {
+        let mut notifier = Notifier::default();
+        let start = Instant::now();
+        assert!(notifier.due(Notice::DeviceError, start));
+        assert!(!notifier.due(Notice::DeviceError, start + Duration::from_secs(5)));
+        assert!(notifier.due(Notice::RecordingEnded, start + Duration::from_secs(5)));
+        assert!(notifier.due(Notice::DeviceError, start + COOLDOWN));
+    }
+}
@@ -0,0 +1,141 @@
+//! Orientation calibration: touch three corners of the pad as it's mounted
+//! and work out the rotation and mirroring that puts them where they belong
+//! in the touch view.
+//!
+//! Corners are taken in device coordinates, so the current view transform
+//! doesn't matter. Each is the last position of a single finger before it
+//! lifts. Of the eight ways to turn and mirror a rectangle, the one bringing
+//! the three touches closest to the view's top-left, top-right and
+//! bottom-left corners wins.
+
+use crate::dimensions::{Rotation, ViewTransform};
+use tapview_core::multitouch::TouchData;
+
+/// Corners to touch, in order.
+pub const CORNERS: [&str; 3] = ["top-left", "top-right", "bottom-left"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrientationTestAction {
+    Apply,
+    Restart,
+}
+
+#[derive(Debug, Default)]
+pub struct OrientationTest {
+    /// Device positions of the corners touched so far
+    pub corners: Vec<(f32, f32)>,
+    /// Position of the single finger down now
+    current: Option<(f32, f32)>,
+    /// More than one finger went down since the last lift; ignore this touch
+    spoiled: bool,
+    pub result: Option<ViewTransform>,
+}
+
+impl OrientationTest {
+    /// Feed one report in device coordinates, for a device with extents
+    /// `extents`.
+    pub fn update(&mut self, touches: &[TouchData], extents: (f32, f32)) {
+        if self.result.is_some() {
+            return;
+        }
+        let mut down = touches.iter().filter(|t| t.used);
+        match (down.next(), down.next()) {
+            (Some(touch), None) => {
+                self.current = Some((touch.position_x as f32, touch.position_y as f32));
+            }
+            (Some(_), Some(_)) => self.spoiled = true,
+            (None, _) => {
+                if let Some(corner) = self.current.take() {
+                    if !self.spoiled {
+                        self.corners.push(corner);
+                    }
+                }
+                self.spoiled = false;
+                if self.corners.len() == CORNERS.len() {
+                    self.result = Some(best_transform(&self.corners, extents));
+                }
+            }
+        }
+    }
+
+    /// The corner to touch next, if any.
+    pub fn next_corner(&self) -> Option<&'static str> {
+        CORNERS.get(self.corners.len()).copied()
+    }
+}
+
+/// The transform that moves `corners` (device positions touched as the
+/// view's top-left, top-right and bottom-left) nearest to those corners.
+fn best_transform(corners: &[(f32, f32)], extents: (f32, f32)) -> ViewTransform {
+    let candidates = Rotation::ALL.into_iter().flat_map(|rotation| {
+        [false, true].map(|flip_x| ViewTransform {
+            rotation,
+            flip_x,
+            flip_y: false,
+        })
+    });
+    let error = |transform: &ViewTransform| {
+        let (vw, vh) = transform.extents(extents);
+        let targets = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+        corners
+            .iter()
+            .zip(targets)
+            .map(|(&corner, (tx, ty))| {
+                let (x, y) = transform.apply(corner, extents);
+                (x / vw.max(1.0) - tx).powi(2) + (y / vh.max(1.0) - ty).powi(2)
+            })
+            .sum::<f32>()
+    };
+    candidates
+        .min_by(|a, b| error(a).total_cmp(&error(b)))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finger(x: i32, y: i32) -> [TouchData; 1] {
+        [TouchData {
+            used: true,
+            position_x: x,
+            position_y: y,
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn finds_a_quarter_turn_from_three_corners() {
+        let extents = (1000.0, 600.0);
+        // Mounted turned clockwise: the view's top-left is the device's
+        // bottom-left, its top-right the device's top-left
+        let mut test = OrientationTest::default();
+        for (x, y) in [(20, 580), (30, 10), (990, 590)] {
+            test.update(&finger(x, y), extents);
+            test.update(&[TouchData::default()], extents);
+        }
+        let result = test.result.unwrap();
+        assert_eq!(
+            result,
+            ViewTransform {
+                rotation: Rotation::R90,
+                flip_x: false,
+                flip_y: false
+            }
+        );
+        assert_eq!(result.extents(extents), (600.0, 1000.0));
+        assert_eq!(result.apply((0.0, 600.0), extents), (0.0, 0.0));
+    }
+
+    #[test]
+    fn ignores_touches_with_more_than_one_finger() {
+        let extents = (1000.0, 600.0);
+        let mut test = OrientationTest::default();
+        let two = [finger(10, 10)[0], finger(500, 300)[0]];
+        test.update(&two, extents);
+        test.update(&finger(10, 10), extents);
+        test.update(&[TouchData::default()], extents);
+        assert!(test.corners.is_empty());
+        assert_eq!(test.next_corner(), Some("top-left"));
+    }
+}
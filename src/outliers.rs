@@ -0,0 +1,219 @@
+//! Flags reported values that can't be real: coordinates or pressure outside
+//! the ranges the device advertises, and positions that jump further in one
+//! report than a finger can move (cursor "teleports").
+//!
+//! Position ranges are taken as 0..=extent like the rest of the view. A
+//! contact that stays out of range counts once, when it first leaves the
+//! range, so a stuck value doesn't flood the log.
+
+use std::fmt;
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// Fastest plausible finger motion per report, in mm...
+const MAX_JUMP_MM: f32 = 30.0;
+/// ...or as a share of the touchpad width without a resolution.
+const MAX_JUMP_FRACTION: f32 = 0.25;
+/// Outliers kept for marking and listing.
+const RECENT_MAX: usize = 200;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutlierKind {
+    /// An axis reported a value outside its advertised range
+    OutOfRange { axis: &'static str, value: i32 },
+    /// The contact moved this far (device units) in one report
+    Spike { distance: f32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outlier {
+    pub slot: usize,
+    pub tracking_id: i32,
+    pub position: (i32, i32),
+    pub kind: OutlierKind,
+}
+
+impl fmt::Display for Outlier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "slot {} (id {}) ", self.slot, self.tracking_id)?;
+        match self.kind {
+            OutlierKind::OutOfRange { axis, value } => {
+                write!(f, "{} out of range: {}", axis, value)
+            }
+            OutlierKind::Spike { distance } => write!(
+                f,
+                "jumped {:.0} units to {},{}",
+                distance, self.position.0, self.position.1
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct OutlierCheck {
+    extents: Option<(i32, i32)>,
+    pressure_range: Option<(i32, i32)>,
+    resolution: Option<(f32, f32)>,
+    /// Per slot: tracking ID and position at the last update
+    last: [Option<(i32, (i32, i32))>; MAX_TOUCH_POINTS],
+    /// Per slot: already out of range
+    flagged: [bool; MAX_TOUCH_POINTS],
+    /// Most recent outliers, oldest first
+    pub recent: Vec<Outlier>,
+    pub out_of_range: usize,
+    pub spikes: usize,
+}
+
+impl OutlierCheck {
+    /// `extents` and `pressure_range` are the advertised ranges, if known;
+    /// `resolution` is in units per mm.
+    pub fn new(
+        extents: Option<(i32, i32)>,
+        pressure_range: Option<(i32, i32)>,
+        resolution: Option<(f32, f32)>,
+    ) -> Self {
+        Self {
+            extents,
+            pressure_range,
+            resolution,
+            ..Default::default()
+        }
+    }
+
+    /// Feed the current touches; `reports` is how many device reports they
+    /// cover and `width` the touchpad's X extent. Returns the new outliers.
+    pub fn update(
+        &mut self,
+        touches: &[TouchData; MAX_TOUCH_POINTS],
+        reports: u32,
+        width: f32,
+    ) -> Vec<Outlier> {
+        let per_report = match self.resolution {
+            Some((rx, ry)) => MAX_JUMP_MM * rx.max(ry),
+            None => MAX_JUMP_FRACTION * width,
+        };
+        let max_jump = per_report * reports.max(1) as f32;
+
+        let mut found = Vec::new();
+        for (slot, touch) in touches.iter().enumerate() {
+            if !touch.used {
+                self.last[slot] = None;
+                self.flagged[slot] = false;
+                continue;
+            }
+            let position = (touch.position_x, touch.position_y);
+            let outlier = |kind| Outlier {
+                slot,
+                tracking_id: touch.tracking_id,
+                position,
+                kind,
+            };
+
+            let out_of_range = self.range_violation(touch);
+            match out_of_range {
+                Some(kind) if !self.flagged[slot] => found.push(outlier(kind)),
+                _ => {}
+            }
+            self.flagged[slot] = out_of_range.is_some();
+
+            if let Some((id, (x, y))) = self.last[slot] {
+                let distance = ((position.0 - x) as f32).hypot((position.1 - y) as f32);
+                if id == touch.tracking_id && distance > max_jump {
+                    found.push(outlier(OutlierKind::Spike { distance }));
+                }
+            }
+            self.last[slot] = Some((touch.tracking_id, position));
+        }
+
+        for o in &found {
+            match o.kind {
+                OutlierKind::OutOfRange { .. } => self.out_of_range += 1,
+                OutlierKind::Spike { .. } => self.spikes += 1,
+            }
+            if self.recent.len() == RECENT_MAX {
+                self.recent.remove(0);
+            }
+            self.recent.push(*o);
+        }
+        found
+    }
+
+    fn range_violation(&self, touch: &TouchData) -> Option<OutlierKind> {
+        let outside = |value: i32, (min, max): (i32, i32)| !(min..=max).contains(&value);
+        let mut checks = Vec::with_capacity(3);
+        if let Some((ex, ey)) = self.extents {
+            checks.push(("X", touch.position_x, (0, ex)));
+            checks.push(("Y", touch.position_y, (0, ey)));
+        }
+        if let Some(range) = self.pressure_range {
+            checks.push(("pressure", touch.pressure, range));
+        }
+        checks
+            .into_iter()
+            .find(|&(_, value, range)| outside(value, range))
+            .map(|(axis, value, _)| OutlierKind::OutOfRange { axis, value })
+    }
+
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.out_of_range = 0;
+        self.spikes = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(tracking_id: i32, x: i32, y: i32, pressure: i32) -> TouchData {
+        TouchData {
+            used: true,
+            tracking_id,
+            position_x: x,
+            position_y: y,
+            pressure,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flags_range_violations_and_spikes() {
+        let mut check = OutlierCheck::new(Some((1000, 600)), Some((0, 255)), Some((10.0, 10.0)));
+        let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+
+        touches[0] = touch(1, 100, 100, 50);
+        assert!(check.update(&touches, 1, 1000.0).is_empty());
+        // 200 units is 20 mm: fine
+        touches[0] = touch(1, 300, 100, 50);
+        assert!(check.update(&touches, 1, 1000.0).is_empty());
+        // 600 units in one report is a teleport
+        touches[0] = touch(1, 900, 100, 50);
+        let found = check.update(&touches, 1, 1000.0);
+        assert!(matches!(
+            found[..],
+            [Outlier {
+                kind: OutlierKind::Spike { .. },
+                ..
+            }]
+        ));
+        // ...but not spread over several reports
+        touches[0] = touch(1, 300, 100, 50);
+        assert!(check.update(&touches, 3, 1000.0).is_empty());
+
+        // Pressure past the range counts once while it stays there
+        touches[0] = touch(1, 300, 100, 300);
+        let found = check.update(&touches, 1, 1000.0);
+        assert_eq!(
+            found[0].kind,
+            OutlierKind::OutOfRange {
+                axis: "pressure",
+                value: 300
+            }
+        );
+        assert!(check.update(&touches, 1, 1000.0).is_empty());
+
+        // A new contact jumping from the old one's position isn't a spike
+        touches[0] = touch(2, 900, 500, 50);
+        assert!(check.update(&touches, 1, 1000.0).is_empty());
+        assert_eq!((check.spikes, check.out_of_range), (1, 1));
+    }
+}
@@ -0,0 +1,34 @@
+//! Turning `--overlay`'s click-through off and on from outside the window.
+//! While clicks go through, the window can't be clicked into focus, so its
+//! shortcut only works while it still has the focus it got when it opened.
+//! On Linux `kill -USR1 <pid>` toggles it from anywhere.
+
+#[cfg(target_os = "linux")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(target_os = "linux")]
+static TOGGLED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(target_os = "linux")]
+extern "C" fn on_sigusr1(_: libc::c_int) {
+    TOGGLED.store(true, Ordering::Relaxed);
+}
+
+/// Take SIGUSR1 as a toggle instead of letting it end the process.
+pub fn listen() {
+    #[cfg(target_os = "linux")]
+    unsafe {
+        libc::signal(
+            libc::SIGUSR1,
+            on_sigusr1 as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+/// Whether a toggle arrived since the last call.
+pub fn toggled() -> bool {
+    #[cfg(target_os = "linux")]
+    return TOGGLED.swap(false, Ordering::Relaxed);
+    #[cfg(target_os = "windows")]
+    false
+}
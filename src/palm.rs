@@ -0,0 +1,254 @@
+//! Approximation of libinput's touchpad palm and thumb detection, so contacts
+//! libinput would ignore can be marked.
+//!
+//! libinput doesn't expose its per-touch classification, so the heuristics
+//! are re-implemented: pressure and size thresholds (sticky for the life of
+//! the touch), edge zones on wide touchpads, and the thumb area at the bottom
+//! of tall clickpads. Timing-based exceptions (e.g. a touch leaving the edge
+//! zone within 200 ms) are simplified to "leaves the zone".
+
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// libinput's palm pressure threshold when no quirk sets one.
+const DEFAULT_PALM_PRESSURE: i32 = 130;
+/// Edge palm zones need a touchpad at least this wide.
+const EDGE_MIN_WIDTH_MM: f32 = 70.0;
+/// Left/right edge zone width and top edge zone height, as fractions.
+const EDGE_ZONE_X: f32 = 0.08;
+const EDGE_ZONE_TOP: f32 = 0.05;
+/// Thumb detection needs a clickpad at least this tall.
+const THUMB_MIN_HEIGHT_MM: f32 = 50.0;
+/// Touches landing below this line are thumb candidates...
+const UPPER_THUMB_LINE: f32 = 0.85;
+/// ...and are thumbs outright below this one.
+const LOWER_THUMB_LINE: f32 = 0.92;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Suppression {
+    Pressure,
+    Size,
+    Edge,
+    Thumb,
+}
+
+impl Suppression {
+    pub fn label(self) -> &'static str {
+        match self {
+            Suppression::Pressure => "palm: pressure",
+            Suppression::Size => "palm: size",
+            Suppression::Edge => "palm: edge",
+            Suppression::Thumb => "thumb",
+        }
+    }
+}
+
+/// Thresholds in device units; `None` disables the check.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PalmThresholds {
+    /// `AttrPressureRange`: a touch begins above the first value and ends
+    /// below the second
+    pub touch_pressure: Option<(i32, i32)>,
+    pub palm_pressure: Option<i32>,
+    pub palm_size: Option<i32>,
+    pub thumb_pressure: Option<i32>,
+    pub thumb_size: Option<i32>,
+}
+
+impl PalmThresholds {
+    /// libinput's defaults: only pressure, and only if the device reports it.
+    pub fn defaults(has_pressure: bool) -> Self {
+        Self {
+            palm_pressure: has_pressure.then_some(DEFAULT_PALM_PRESSURE),
+            ..Default::default()
+        }
+    }
+
+    /// Override with `Attr*Threshold` lines from `libinput quirks list`.
+    #[allow(dead_code)] // libinput quirks exist on Linux only
+    pub fn apply_quirks(&mut self, quirks: &str) {
+        for line in quirks.lines() {
+            let Some((key, value)) = line.trim().split_once('=') else {
+                continue;
+            };
+            if key.trim() == "AttrPressureRange" {
+                let range = value.trim().split_once(':');
+                if let Some((Ok(high), Ok(low))) = range.map(|(h, l)| (h.parse(), l.parse())) {
+                    self.touch_pressure = Some((high, low));
+                }
+                continue;
+            }
+            let Ok(value) = value.trim().parse::<i32>() else {
+                continue;
+            };
+            match key.trim() {
+                "AttrPalmPressureThreshold" => self.palm_pressure = Some(value),
+                "AttrPalmSizeThreshold" => self.palm_size = Some(value),
+                "AttrThumbPressureThreshold" => self.thumb_pressure = Some(value),
+                "AttrThumbSizeThreshold" => self.thumb_size = Some(value),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct SlotState {
+    tracking_id: i32,
+    /// Sticky pressure/size palm
+    palm: Option<Suppression>,
+    /// Still inside the edge zone it started in
+    in_edge: bool,
+    /// Started below the upper thumb line and hasn't left the thumb area
+    in_thumb_area: bool,
+}
+
+pub struct PalmDetector {
+    thresholds: PalmThresholds,
+    buttonpad: bool,
+    slots: [Option<SlotState>; MAX_TOUCH_POINTS],
+    verdicts: [Option<Suppression>; MAX_TOUCH_POINTS],
+}
+
+impl PalmDetector {
+    pub fn new(thresholds: PalmThresholds, buttonpad: bool) -> Self {
+        Self {
+            thresholds,
+            buttonpad,
+            slots: [None; MAX_TOUCH_POINTS],
+            verdicts: [None; MAX_TOUCH_POINTS],
+        }
+    }
+
+    /// Classify the current touches. `extent` is the position range in device
+    /// units and `resolution` its units per mm, if known.
+    pub fn update(
+        &mut self,
+        touches: &[TouchData; MAX_TOUCH_POINTS],
+        extent: (f32, f32),
+        resolution: Option<(f32, f32)>,
+    ) {
+        let size_mm = resolution.map(|(rx, ry)| (extent.0 / rx, extent.1 / ry));
+        let edges = size_mm.is_some_and(|(w, _)| w >= EDGE_MIN_WIDTH_MM);
+        let thumbs = self.buttonpad && size_mm.is_some_and(|(_, h)| h >= THUMB_MIN_HEIGHT_MM);
+
+        for (slot, touch) in touches.iter().enumerate() {
+            if !touch.used {
+                self.slots[slot] = None;
+                self.verdicts[slot] = None;
+                continue;
+            }
+            let fx = touch.position_x as f32 / extent.0.max(1.0);
+            let fy = touch.position_y as f32 / extent.1.max(1.0);
+            let in_edge_zone =
+                !(EDGE_ZONE_X..=1.0 - EDGE_ZONE_X).contains(&fx) || fy < EDGE_ZONE_TOP;
+
+            let mut state = match self.slots[slot] {
+                Some(s) if s.tracking_id == touch.tracking_id => s,
+                _ => SlotState {
+                    tracking_id: touch.tracking_id,
+                    palm: None,
+                    in_edge: edges && in_edge_zone,
+                    in_thumb_area: thumbs && fy > UPPER_THUMB_LINE,
+                },
+            };
+
+            let over = |value: i32, threshold: Option<i32>| threshold.is_some_and(|t| value > t);
+            if state.palm.is_none() {
+                if over(touch.pressure, self.thresholds.palm_pressure) {
+                    state.palm = Some(Suppression::Pressure);
+                } else if over(touch.touch_major, self.thresholds.palm_size) {
+                    state.palm = Some(Suppression::Size);
+                }
+            }
+            state.in_edge &= in_edge_zone;
+            state.in_thumb_area &= fy > UPPER_THUMB_LINE;
+
+            let thumb = state.in_thumb_area
+                && (fy > LOWER_THUMB_LINE
+                    || over(touch.pressure, self.thresholds.thumb_pressure)
+                    || over(touch.touch_major, self.thresholds.thumb_size));
+
+            self.verdicts[slot] = state
+                .palm
+                .or(state.in_edge.then_some(Suppression::Edge))
+                .or(thumb.then_some(Suppression::Thumb));
+            self.slots[slot] = Some(state);
+        }
+    }
+
+    /// Why libinput would ignore the contact in `slot`, if it would.
+    pub fn suppression(&self, slot: usize) -> Option<Suppression> {
+        self.verdicts.get(slot).copied().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(tracking_id: i32, x: i32, y: i32, pressure: i32) -> TouchData {
+        TouchData {
+            used: true,
+            tracking_id,
+            position_x: x,
+            position_y: y,
+            pressure,
+            ..Default::default()
+        }
+    }
+
+    // 100 x 60 mm at 10 units/mm
+    const EXTENT: (f32, f32) = (1000.0, 600.0);
+    const RES: Option<(f32, f32)> = Some((10.0, 10.0));
+
+    #[test]
+    fn quirks_override_defaults() {
+        let mut t = PalmThresholds::defaults(true);
+        t.apply_quirks("ModelFoo=1\nAttrPalmPressureThreshold=200\nAttrThumbSizeThreshold=7\n");
+        t.apply_quirks("AttrPressureRange=10:8\n");
+        assert_eq!(t.touch_pressure, Some((10, 8)));
+        assert_eq!(t.palm_pressure, Some(200));
+        assert_eq!(t.thumb_size, Some(7));
+        assert_eq!(PalmThresholds::defaults(false).palm_pressure, None);
+    }
+
+    #[test]
+    fn pressure_palm_is_sticky() {
+        let mut det = PalmDetector::new(PalmThresholds::defaults(true), false);
+        let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+        touches[0] = touch(1, 500, 300, 50);
+        det.update(&touches, EXTENT, RES);
+        assert_eq!(det.suppression(0), None);
+        touches[0].pressure = 150;
+        det.update(&touches, EXTENT, RES);
+        touches[0].pressure = 50;
+        det.update(&touches, EXTENT, RES);
+        assert_eq!(det.suppression(0), Some(Suppression::Pressure));
+    }
+
+    #[test]
+    fn edge_and_thumb_zones() {
+        let mut det = PalmDetector::new(PalmThresholds::default(), true);
+        let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+
+        // Starts in the left edge zone, then moves out for good
+        touches[0] = touch(1, 20, 300, 0);
+        det.update(&touches, EXTENT, RES);
+        assert_eq!(det.suppression(0), Some(Suppression::Edge));
+        touches[0].position_x = 500;
+        det.update(&touches, EXTENT, RES);
+        touches[0].position_x = 20;
+        det.update(&touches, EXTENT, RES);
+        assert_eq!(det.suppression(0), None);
+
+        // Lands at the very bottom of a tall clickpad
+        touches[1] = touch(2, 500, 590, 0);
+        det.update(&touches, EXTENT, RES);
+        assert_eq!(det.suppression(1), Some(Suppression::Thumb));
+
+        // Without a resolution there's no size to go on
+        let mut det = PalmDetector::new(PalmThresholds::default(), true);
+        det.update(&touches, EXTENT, None);
+        assert_eq!(det.suppression(1), None);
+    }
+}
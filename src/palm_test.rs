@@ -0,0 +1,198 @@
+//! Palm rejection benchmark: scripted postures where every contact is a palm,
+//! counting how many were flagged and how many moved the cursor anyway.
+//!
+//! A contact counts as flagged if the firmware marked it MT_TOOL_PALM or the
+//! libinput palm/thumb approximation (see `palm`) would suppress it. It
+//! leaked if pointer motion arrived while it was down and not flagged.
+//! Motion is only known with the libinput panel running.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const MT_TOOL_PALM: i32 = 0x02;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PalmStep {
+    RestPalm,
+    Typing,
+    PenGrip,
+}
+
+impl PalmStep {
+    pub fn prompt(self) -> &'static str {
+        match self {
+            PalmStep::RestPalm => "Rest your palm on the touchpad as you would while typing",
+            PalmStep::Typing => "Type on the keyboard, letting your palms brush the touchpad",
+            PalmStep::PenGrip => "Hold your hand as if gripping a pen, resting on the touchpad",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PalmStep::RestPalm => "Resting palm",
+            PalmStep::Typing => "Typing",
+            PalmStep::PenGrip => "Pen grip",
+        }
+    }
+}
+
+pub const STEPS: [PalmStep; 3] = [PalmStep::RestPalm, PalmStep::Typing, PalmStep::PenGrip];
+
+/// What the test window asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PalmTestAction {
+    Next,
+    Restart,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ContactRecord {
+    tool_palm: bool,
+    suppressed: bool,
+    /// Currently flagged either way
+    flagged: bool,
+    leaked: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepReport {
+    pub step: PalmStep,
+    pub contacts: usize,
+    /// Contacts marked MT_TOOL_PALM at any point
+    pub tool_palm: usize,
+    /// Contacts the libinput approximation suppressed at any point
+    pub suppressed: usize,
+    /// Contacts that moved the cursor while unflagged
+    pub leaked: usize,
+    /// Pointer motion while any contact was down, in pixels
+    pub motion: f32,
+}
+
+impl StepReport {
+    /// Share of contacts that didn't leak, 0..=1.
+    pub fn score(&self) -> Option<f32> {
+        (self.contacts > 0).then(|| 1.0 - self.leaked as f32 / self.contacts as f32)
+    }
+}
+
+#[derive(Default)]
+pub struct PalmTest {
+    pub reports: Vec<StepReport>,
+    contacts: HashMap<i32, ContactRecord>,
+    down: Vec<i32>,
+    motion: f32,
+}
+
+impl PalmTest {
+    /// The posture being asked for, or `None` once all are done.
+    pub fn current(&self) -> Option<PalmStep> {
+        STEPS.get(self.reports.len()).copied()
+    }
+
+    /// Feed the active contacts as (tracking ID, tool type, suppressed).
+    pub fn update(&mut self, contacts: &[(i32, i32, bool)]) {
+        if self.current().is_none() {
+            return;
+        }
+        self.down.clear();
+        for &(id, tool_type, suppressed) in contacts {
+            let record = self.contacts.entry(id).or_default();
+            let tool_palm = tool_type == MT_TOOL_PALM;
+            record.tool_palm |= tool_palm;
+            record.suppressed |= suppressed;
+            record.flagged = tool_palm || suppressed;
+            self.down.push(id);
+        }
+    }
+
+    /// Feed interpreted pointer motion.
+    pub fn on_motion(&mut self, dx: f64, dy: f64) {
+        if self.current().is_none() || self.down.is_empty() {
+            return;
+        }
+        self.motion += dx.hypot(dy) as f32;
+        for id in &self.down {
+            if let Some(record) = self.contacts.get_mut(id) {
+                record.leaked |= !record.flagged;
+            }
+        }
+    }
+
+    /// Close the current posture and move to the next.
+    pub fn next_step(&mut self) {
+        let Some(step) = self.current() else {
+            return;
+        };
+        let records = self.contacts.values();
+        self.reports.push(StepReport {
+            step,
+            contacts: records.len(),
+            tool_palm: records.clone().filter(|r| r.tool_palm).count(),
+            suppressed: records.clone().filter(|r| r.suppressed).count(),
+            leaked: records.filter(|r| r.leaked).count(),
+            motion: self.motion,
+        });
+        self.contacts.clear();
+        self.down.clear();
+        self.motion = 0.0;
+    }
+
+    /// The finished steps as a plain-text table for bug reports.
+    pub fn report_text(&self) -> String {
+        let mut out = String::from(
+            "posture        contacts  MT_TOOL_PALM  suppressed  leaked  motion  score\n",
+        );
+        for r in &self.reports {
+            let score = r
+                .score()
+                .map_or("-".to_string(), |s| format!("{:.0}%", s * 100.0));
+            let _ = writeln!(
+                out,
+                "{:<14} {:>8}  {:>12}  {:>10}  {:>6}  {:>6.0}  {:>5}",
+                r.step.label(),
+                r.contacts,
+                r.tool_palm,
+                r.suppressed,
+                r.leaked,
+                r.motion,
+                score
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_flagged_and_leaked_contacts() {
+        let mut test = PalmTest::default();
+        // Firmware-flagged palm: motion doesn't count against it
+        test.update(&[(1, MT_TOOL_PALM, false)]);
+        test.on_motion(3.0, 4.0);
+        // Unflagged contact moves the cursor, then gets suppressed
+        test.update(&[(2, 0, false)]);
+        test.on_motion(0.0, 1.0);
+        test.update(&[(2, 0, true)]);
+        test.update(&[]);
+        test.next_step();
+
+        let report = &test.reports[0];
+        assert_eq!(report.step, PalmStep::RestPalm);
+        assert_eq!(
+            (
+                report.contacts,
+                report.tool_palm,
+                report.suppressed,
+                report.leaked
+            ),
+            (2, 1, 1, 1)
+        );
+        assert_eq!(report.motion, 6.0);
+        assert_eq!(report.score(), Some(0.5));
+        assert_eq!(test.current(), Some(PalmStep::Typing));
+        assert!(test.report_text().contains("Resting palm"));
+    }
+}
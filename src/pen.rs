@@ -0,0 +1,231 @@
+//! The pen half of pen-and-touch devices like Wacom tablets, whose pen and
+//! touch are separate evdev nodes. The pen node is found next to the touch
+//! node, read on its own thread and drawn over the touch view; both cover
+//! the same surface, so its position is taken as a fraction of its axis
+//! ranges.
+//!
+//! While the pen is in proximity the driver drops touch contacts, so a hand
+//! resting on the tablet doesn't draw (pen-vs-touch arbitration).
+//! [`Arbitration`] notes each contact that ends while the pen is near, and
+//! counts reports where touches still come through.
+
+use std::time::{Duration, Instant};
+use tapview_core::multitouch::TouchData;
+
+/// How long a contact dropped for the pen stays marked.
+pub const GHOST_TIME: Duration = Duration::from_millis(1500);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PenState {
+    /// Pen or eraser close enough to be tracked
+    pub proximity: bool,
+    pub eraser: bool,
+    /// Tip on the surface
+    pub touching: bool,
+    /// Position as a fraction of the axis ranges
+    pub x: f32,
+    pub y: f32,
+    /// 0.0..=1.0, or 0 without a pressure axis
+    pub pressure: f32,
+}
+
+#[derive(Default)]
+pub struct Arbitration {
+    pen_near: bool,
+    /// Contacts down in the last report: tracking ID and position
+    last: Vec<(i32, (f32, f32))>,
+    /// Contacts that ended while the pen was near, where and when
+    pub dropped: Vec<((f32, f32), Instant)>,
+    pub dropped_total: usize,
+    /// Reports with contacts down while the pen was near
+    pub leaked_reports: usize,
+}
+
+impl Arbitration {
+    /// Feed the pen's latest state; before the touches of the same moment,
+    /// so contacts dropped as the pen arrives count.
+    pub fn update_pen(&mut self, pen: &PenState) {
+        self.pen_near = pen.proximity;
+    }
+
+    /// Feed one touch report.
+    pub fn update(&mut self, touches: &[TouchData], now: Instant) {
+        let current: Vec<_> = touches
+            .iter()
+            .filter(|t| t.used)
+            .map(|t| (t.tracking_id, (t.position_x as f32, t.position_y as f32)))
+            .collect();
+        if self.pen_near {
+            for (id, pos) in &self.last {
+                if !current.iter().any(|(c, _)| c == id) {
+                    self.dropped.push((*pos, now));
+                    self.dropped_total += 1;
+                }
+            }
+            if !current.is_empty() {
+                self.leaked_reports += 1;
+            }
+        }
+        self.dropped
+            .retain(|(_, at)| now.saturating_duration_since(*at) < GHOST_TIME);
+        self.last = current;
+    }
+
+    pub fn clear(&mut self) {
+        self.dropped.clear();
+        self.dropped_total = 0;
+        self.leaked_reports = 0;
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use reader::spawn_pen_thread;
+
+#[cfg(target_os = "linux")]
+mod reader {
+    use super::PenState;
+    use crate::backend_error::{report, Backend, BackendError};
+    use evdev::{AbsoluteAxisType, EventType, Key, Synchronization};
+    use std::io;
+    use std::os::fd::AsRawFd;
+    use std::path::Path;
+    use std::thread::{self, JoinHandle};
+    use tapview_core::channel::{self, BoundedReceiver, BoundedSender};
+    use tapview_core::input::evdev_device::EvdevDevice;
+    use tapview_core::shutdown::Shutdown;
+
+    const PEN_CHANNEL_CAPACITY: usize = 256;
+
+    pub fn spawn_pen_thread(
+        device_path: &Path,
+        shutdown: Shutdown,
+        errors: BoundedSender<BackendError>,
+    ) -> (BoundedReceiver<PenState>, JoinHandle<()>) {
+        let (tx, rx) = channel::bounded(PEN_CHANNEL_CAPACITY);
+        let path = device_path.to_path_buf();
+        let thread = thread::spawn(move || {
+            if let Err(e) = run_pen(&path, &tx, &shutdown) {
+                let message = format!("{}: {}", path.display(), e);
+                report(&errors, BackendError::new(Backend::Pen, message));
+            }
+        });
+        (rx, thread)
+    }
+
+    /// `value` as a fraction of `min..=max`.
+    fn fraction(value: i32, (min, max): (i32, i32)) -> f32 {
+        if max <= min {
+            return 0.0;
+        }
+        ((value - min) as f32 / (max - min) as f32).clamp(0.0, 1.0)
+    }
+
+    fn run_pen(path: &Path, tx: &BoundedSender<PenState>, shutdown: &Shutdown) -> io::Result<()> {
+        let device = EvdevDevice::open(path)?;
+        let range = |axis| {
+            device
+                .absinfo(axis)
+                .map(|info| (info.minimum, info.maximum))
+        };
+        let x_range = range(AbsoluteAxisType::ABS_X)?;
+        let y_range = range(AbsoluteAxisType::ABS_Y)?;
+        let pressure_range = range(AbsoluteAxisType::ABS_PRESSURE).unwrap_or((0, 0));
+        log_info!(target: "pen", "reading pen from {}", path.display());
+
+        let mut state = PenState::default();
+        let (mut x, mut y, mut pressure) = (0, 0, 0);
+        let mut pollfd = libc::pollfd {
+            fd: device.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        while !shutdown.is_requested() {
+            if unsafe { libc::poll(&mut pollfd, 1, 100) } < 0 {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(e);
+            }
+            let events = match device.read_events() {
+                Ok(events) => events,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e),
+            };
+            for event in events {
+                match event.event_type() {
+                    EventType::KEY => {
+                        let down = event.value() != 0;
+                        match Key::new(event.code()) {
+                            Key::BTN_TOOL_PEN => state.proximity = down,
+                            Key::BTN_TOOL_RUBBER => {
+                                state.proximity = down;
+                                state.eraser = down;
+                            }
+                            Key::BTN_TOUCH => state.touching = down,
+                            _ => {}
+                        }
+                    }
+                    EventType::ABSOLUTE => match AbsoluteAxisType(event.code()) {
+                        AbsoluteAxisType::ABS_X => x = event.value(),
+                        AbsoluteAxisType::ABS_Y => y = event.value(),
+                        AbsoluteAxisType::ABS_PRESSURE => pressure = event.value(),
+                        _ => {}
+                    },
+                    EventType::SYNCHRONIZATION if event.code() == Synchronization::SYN_REPORT.0 => {
+                        state.x = fraction(x, x_range);
+                        state.y = fraction(y, y_range);
+                        state.pressure = fraction(pressure, pressure_range);
+                        let _ = tx.send(state);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finger(id: i32, x: i32) -> TouchData {
+        TouchData {
+            used: true,
+            tracking_id: id,
+            position_x: x,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn marks_contacts_dropped_for_the_pen() {
+        let now = Instant::now();
+        let mut arb = Arbitration::default();
+        let near = PenState {
+            proximity: true,
+            ..Default::default()
+        };
+
+        // Lifting with the pen away isn't arbitration
+        arb.update(&[finger(1, 100), finger(2, 200)], now);
+        arb.update(&[finger(2, 200)], now);
+        assert_eq!(arb.dropped_total, 0);
+
+        // The pen arrives and the driver drops the palm
+        arb.update_pen(&near);
+        arb.update(&[], now);
+        assert_eq!(arb.dropped_total, 1);
+        assert_eq!(arb.dropped[0].0, (200.0, 0.0));
+
+        // A touch that gets through anyway
+        arb.update(&[finger(3, 300)], now);
+        assert_eq!(arb.leaked_reports, 1);
+
+        arb.update_pen(&PenState::default());
+        arb.update(&[], now + GHOST_TIME);
+        assert_eq!(arb.dropped_total, 1);
+        assert!(arb.dropped.is_empty());
+    }
+}
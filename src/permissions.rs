@@ -0,0 +1,185 @@
+//! `tapview setup-permissions`: install a udev rule so the touchpad's event
+//! and hidraw nodes can be opened without root.
+//!
+//! The rule tags the nodes `uaccess`, so logind grants the user of the
+//! active local session an ACL on them, as it does for game controllers.
+//! Nobody else gains access and nothing changes for remote logins.
+
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tapview_core::discovery::DeviceInfo;
+
+/// Has to sort before systemd's 73-seat-late.rules, which applies the ACLs.
+pub const RULE_PATH: &str = "/etc/udev/rules.d/70-tapview.rules";
+
+/// How the rule recognizes the touchpad.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeviceMatch {
+    /// HID touchpads by bus, vendor and product (`0018:093A:0274`), which
+    /// covers both the event node and its hidraw sibling.
+    Hid(String),
+    /// PS/2 and SMBus touchpads by input device name; they have no hidraw.
+    Name(String),
+}
+
+impl DeviceMatch {
+    fn for_devnode(devnode: &Path) -> Result<Self, String> {
+        let name = devnode
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| format!("bad device path: {}", devnode.display()))?;
+        let mut enumerator = udev::Enumerator::new().map_err(|e| e.to_string())?;
+        enumerator
+            .match_subsystem("input")
+            .map_err(|e| e.to_string())?;
+        enumerator.match_sysname(name).map_err(|e| e.to_string())?;
+        let event = enumerator
+            .scan_devices()
+            .map_err(|e| e.to_string())?
+            .next()
+            .ok_or_else(|| format!("{} not found in udev", devnode.display()))?;
+
+        let mut parent = event.parent();
+        while let Some(device) = parent {
+            if device.subsystem().is_some_and(|s| s == "hid") {
+                // The sysname ends in a per-boot instance number: "0018:093A:0274.0001"
+                let sysname = device.sysname().to_string_lossy();
+                let id = sysname.split('.').next().unwrap_or(&sysname);
+                return Ok(DeviceMatch::Hid(id.to_string()));
+            }
+            parent = device.parent();
+        }
+
+        event
+            .parent()
+            .and_then(|input| input.attribute_value("name").map(|n| n.to_owned()))
+            .map(|n| DeviceMatch::Name(n.to_string_lossy().into_owned()))
+            .ok_or_else(|| format!("{} has no HID parent or name", devnode.display()))
+    }
+}
+
+/// The rules file for `device`.
+pub fn rule(device: &DeviceMatch) -> String {
+    let mut rule = String::from("# Installed by tapview setup-permissions\n");
+    match device {
+        DeviceMatch::Hid(id) => {
+            rule += &format!(
+                "SUBSYSTEM==\"input\", KERNEL==\"event*\", KERNELS==\"{id}.*\", TAG+=\"uaccess\"\n"
+            );
+            rule += &format!("SUBSYSTEM==\"hidraw\", KERNELS==\"{id}.*\", TAG+=\"uaccess\"\n");
+        }
+        DeviceMatch::Name(name) => {
+            // udev strings can't escape quotes; '?' matches any one character
+            let name = name.replace('"', "?");
+            rule += &format!(
+                "SUBSYSTEM==\"input\", KERNEL==\"event*\", ATTRS{{name}}==\"{name}\", TAG+=\"uaccess\"\n"
+            );
+        }
+    }
+    rule
+}
+
+/// Show the rule for `device`, install it once confirmed (through sudo
+/// unless already root) and re-trigger the device so the ACLs apply now.
+pub fn setup(device: &DeviceInfo, assume_yes: bool, print_only: bool) -> Result<(), String> {
+    let device_match = DeviceMatch::for_devnode(&device.devnode)?;
+    let rule = rule(&device_match);
+    if print_only {
+        print!("{}", rule);
+        return Ok(());
+    }
+
+    println!("Touchpad: {}", device);
+    println!("Rule for {}:\n\n{}", RULE_PATH, rule);
+    if !assume_yes && !confirm("Install it?")? {
+        return Err("Cancelled".to_string());
+    }
+
+    let root = unsafe { libc::geteuid() } == 0;
+    let mut tee = privileged(root, "tee", &[RULE_PATH]);
+    let mut child = tee
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run tee: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(rule.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", RULE_PATH, e))?;
+    }
+    check(child.wait(), "tee")?;
+
+    check(
+        privileged(root, "udevadm", &["control", "--reload"]).status(),
+        "udevadm control",
+    )?;
+    let mut nodes = vec![device.devnode.clone()];
+    if matches!(device_match, DeviceMatch::Hid(_)) {
+        nodes.extend(sibling_hidraw(&device.devnode));
+    }
+    let mut trigger = vec!["trigger", "--action=change", "--settle"];
+    trigger.extend(nodes.iter().filter_map(|n| n.to_str()));
+    check(
+        privileged(root, "udevadm", &trigger).status(),
+        "udevadm trigger",
+    )?;
+
+    println!("Installed. tapview can now be run without sudo from your desktop session.");
+    Ok(())
+}
+
+fn sibling_hidraw(devnode: &Path) -> Option<PathBuf> {
+    tapview_core::heatmap::discovery::find_sibling_hidraw(devnode).ok()
+}
+
+fn confirm(question: &str) -> Result<bool, String> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| e.to_string())?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn privileged(root: bool, program: &str, args: &[&str]) -> Command {
+    let mut command = if root {
+        Command::new(program)
+    } else {
+        let mut sudo = Command::new("sudo");
+        sudo.arg(program);
+        sudo
+    };
+    command.args(args);
+    command
+}
+
+fn check(status: io::Result<std::process::ExitStatus>, what: &str) -> Result<(), String> {
+    match status {
+        Ok(s) if s.success() => Ok(()),
+        Ok(s) => Err(format!("{} failed ({})", what, s)),
+        Err(e) => Err(format!("Failed to run {}: {}", what, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_match_hid_id_or_name() {
+        let hid = rule(&DeviceMatch::Hid("0018:093A:0274".to_string()));
+        assert!(hid.contains(
+            "SUBSYSTEM==\"input\", KERNEL==\"event*\", KERNELS==\"0018:093A:0274.*\", TAG+=\"uaccess\""
+        ));
+        assert!(hid.contains("SUBSYSTEM==\"hidraw\", KERNELS==\"0018:093A:0274.*\""));
+
+        let ps2 = rule(&DeviceMatch::Name(
+            "SynPS/2 \"Synaptics\" TouchPad".to_string(),
+        ));
+        assert!(ps2.contains("ATTRS{name}==\"SynPS/2 ?Synaptics? TouchPad\""));
+        assert!(!ps2.contains("hidraw"));
+    }
+}
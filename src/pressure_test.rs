@@ -0,0 +1,168 @@
+//! Pressure response test: one slow press and release, recorded as pressure
+//! and touch_major over time with the points where BTN_TOUCH and the
+//! physical button changed.
+//!
+//! The pressures at those points are what libinput's `AttrPressureRange`
+//! and the hwdb/quirks thresholds need to be tuned against. The run starts
+//! when a finger lands and ends when it lifts.
+
+use std::time::Instant;
+
+/// Cap on samples, about a minute at typical report rates.
+const SAMPLES_MAX: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PressureSample {
+    /// Milliseconds since the finger landed
+    pub t_ms: f32,
+    pub pressure: i32,
+    pub touch_major: i32,
+    pub btn_touch: bool,
+    pub click: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Marker {
+    TouchDown,
+    TouchUp,
+    ClickDown,
+    ClickUp,
+}
+
+impl Marker {
+    pub fn label(self) -> &'static str {
+        match self {
+            Marker::TouchDown => "BTN_TOUCH on",
+            Marker::TouchUp => "BTN_TOUCH off",
+            Marker::ClickDown => "click",
+            Marker::ClickUp => "release",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PressureTest {
+    Waiting,
+    Recording {
+        start: Instant,
+        samples: Vec<PressureSample>,
+    },
+    Done(Vec<PressureSample>),
+    Failed(&'static str),
+}
+
+/// Where BTN_TOUCH and the button changed, with the time and the pressure
+/// reported at that moment.
+pub fn markers(samples: &[PressureSample]) -> Vec<(Marker, f32, i32)> {
+    let mut out = Vec::new();
+    let mut prev: Option<&PressureSample> = None;
+    for s in samples {
+        let (touch, click) = prev.map_or((false, false), |p| (p.btn_touch, p.click));
+        if s.btn_touch != touch {
+            let marker = if s.btn_touch {
+                Marker::TouchDown
+            } else {
+                Marker::TouchUp
+            };
+            out.push((marker, s.t_ms, s.pressure));
+        }
+        if s.click != click {
+            let marker = if s.click {
+                Marker::ClickDown
+            } else {
+                Marker::ClickUp
+            };
+            out.push((marker, s.t_ms, s.pressure));
+        }
+        prev = Some(s);
+    }
+    out
+}
+
+impl PressureTest {
+    /// Feed the single contact's (pressure, touch_major), or `None` if no
+    /// contact is down, along with BTN_TOUCH and the button state.
+    /// `contacts` is the number of contacts down.
+    pub fn update(
+        &mut self,
+        contact: Option<(i32, i32)>,
+        contacts: usize,
+        btn_touch: bool,
+        click: bool,
+        time: Instant,
+    ) {
+        if contacts > 1 {
+            if matches!(self, PressureTest::Recording { .. }) {
+                *self = PressureTest::Failed("Use only one finger");
+            }
+            return;
+        }
+        match self {
+            PressureTest::Waiting => {
+                if contact.is_some() {
+                    *self = PressureTest::Recording {
+                        start: time,
+                        samples: Vec::new(),
+                    };
+                    self.update(contact, contacts, btn_touch, click, time);
+                }
+            }
+            PressureTest::Recording { start, samples } => match contact {
+                Some((pressure, touch_major)) => {
+                    if samples.len() < SAMPLES_MAX {
+                        samples.push(PressureSample {
+                            t_ms: time.saturating_duration_since(*start).as_secs_f32() * 1000.0,
+                            pressure,
+                            touch_major,
+                            btn_touch,
+                            click,
+                        });
+                    }
+                }
+                None => {
+                    let samples = std::mem::take(samples);
+                    *self = if samples.len() < 2 {
+                        PressureTest::Failed("Too short, press more slowly")
+                    } else {
+                        PressureTest::Done(samples)
+                    };
+                }
+            },
+            PressureTest::Done(_) | PressureTest::Failed(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn records_press_and_marks_thresholds() {
+        let t0 = Instant::now();
+        let at = |ms: u64| t0 + Duration::from_millis(ms);
+        let mut test = PressureTest::Waiting;
+        test.update(None, 0, false, false, at(0));
+        test.update(Some((10, 3)), 1, false, false, at(0));
+        test.update(Some((40, 5)), 1, true, false, at(100));
+        test.update(Some((120, 8)), 1, true, true, at(200));
+        test.update(Some((30, 5)), 1, false, false, at(300));
+        test.update(None, 0, false, false, at(400));
+
+        let PressureTest::Done(samples) = &test else {
+            panic!("test didn't finish");
+        };
+        assert_eq!(samples.len(), 4);
+        assert_eq!(samples[2].t_ms, 200.0);
+        assert_eq!(
+            markers(samples),
+            vec![
+                (Marker::TouchDown, 100.0, 40),
+                (Marker::ClickDown, 200.0, 120),
+                (Marker::TouchUp, 300.0, 30),
+                (Marker::ClickUp, 300.0, 30),
+            ]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Event rate measurement for the rate HUD.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How often a rate sample is taken.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+/// Samples kept for the sparkline (15 s at 250 ms).
+const HISTORY_LEN: usize = 60;
+
+/// Counts events and turns them into a per-second rate, sampled at a fixed
+/// interval with a short history for sparklines.
+pub struct RateMeter {
+    count: u32,
+    last_sample: Instant,
+    /// Events per second, oldest first.
+    pub history: VecDeque<f32>,
+}
+
+impl Default for RateMeter {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            last_sample: Instant::now(),
+            history: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+}
+
+impl RateMeter {
+    pub fn add(&mut self, n: u32) {
+        self.count += n;
+    }
+
+    /// Close the current sample window if it has elapsed. Call once per UI frame.
+    pub fn tick(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last_sample);
+        if elapsed < SAMPLE_INTERVAL {
+            return;
+        }
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history
+            .push_back(self.count as f32 / elapsed.as_secs_f32());
+        self.count = 0;
+        self.last_sample = now;
+    }
+
+    /// Most recent rate in events per second.
+    pub fn rate(&self) -> f32 {
+        self.history.back().copied().unwrap_or(0.0)
+    }
+}
+
+/// Rate meters for every event source shown in the HUD.
+#[derive(Default)]
+pub struct Rates {
+    pub ui_frames: RateMeter,
+    pub touch_reports: RateMeter,
+    pub libinput_events: RateMeter,
+    pub heatmap_frames: RateMeter,
+}
+
+impl Rates {
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        self.ui_frames.add(1);
+        self.ui_frames.tick(now);
+        self.touch_reports.tick(now);
+        self.libinput_events.tick(now);
+        self.heatmap_frames.tick(now);
+    }
+}
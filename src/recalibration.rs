@@ -0,0 +1,267 @@
+//! Forced recalibration with a before/after capture: the untouched heatmap
+//! is averaged for a moment, the heatmap thread writes the `--recalibrate`
+//! registers, and once the new baseline has settled it's averaged again, so
+//! the difference shows what the recalibration changed.
+//!
+//! Unlike ALC, which keeps tracking the baseline, this makes the firmware
+//! take a new baseline from whatever is on the pad at that moment.
+
+use std::time::{Duration, Instant};
+use tapview_core::heatmap::HeatmapFrame;
+
+/// How long frames are averaged for each side of the comparison
+pub const AVERAGED: Duration = Duration::from_secs(1);
+
+/// Frames right after the write are skipped while the new baseline settles
+pub const SETTLE: Duration = Duration::from_secs(1);
+
+/// Frames summed per cell from `start` until `AVERAGED` has passed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capture {
+    start: Instant,
+    rows: usize,
+    cols: usize,
+    sums: Vec<i64>,
+    frames: usize,
+}
+
+impl Capture {
+    fn new(start: Instant) -> Self {
+        Self {
+            start,
+            rows: 0,
+            cols: 0,
+            sums: Vec::new(),
+            frames: 0,
+        }
+    }
+
+    /// Returns false if the frame doesn't have the shape the earlier ones had.
+    fn add(&mut self, frame: &HeatmapFrame) -> bool {
+        if self.frames == 0 {
+            (self.rows, self.cols) = (frame.rows, frame.cols);
+            self.sums = vec![0; frame.data.len()];
+        } else if (self.rows, self.cols) != (frame.rows, frame.cols)
+            || self.sums.len() != frame.data.len()
+        {
+            return false;
+        }
+        for (sum, &value) in self.sums.iter_mut().zip(&frame.data) {
+            *sum += value as i64;
+        }
+        self.frames += 1;
+        true
+    }
+
+    fn mean(&self) -> HeatmapFrame {
+        let n = self.frames.max(1) as i64;
+        HeatmapFrame {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.sums.iter().map(|&sum| (sum / n) as i16).collect(),
+        }
+    }
+}
+
+/// The averaged frames on either side of the recalibration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    pub before: HeatmapFrame,
+    pub after: HeatmapFrame,
+    /// After less before, per cell
+    pub diff: HeatmapFrame,
+}
+
+impl Comparison {
+    fn new(before: HeatmapFrame, after: HeatmapFrame) -> Self {
+        let diff = HeatmapFrame {
+            rows: after.rows,
+            cols: after.cols,
+            data: before
+                .data
+                .iter()
+                .zip(&after.data)
+                .map(|(&b, &a)| a.saturating_sub(b))
+                .collect(),
+        };
+        Self {
+            before,
+            after,
+            diff,
+        }
+    }
+
+    /// Mean change over the cells.
+    pub fn mean_shift(&self) -> f64 {
+        self.diff.data.iter().map(|&d| d as f64).sum::<f64>() / self.diff.data.len().max(1) as f64
+    }
+
+    /// The cell that changed most, as row, column and change.
+    pub fn largest_change(&self) -> Option<(usize, usize, i16)> {
+        let cols = self.diff.cols.max(1);
+        self.diff
+            .data
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, d)| d.unsigned_abs())
+            .map(|(i, &d)| (i / cols, i % cols, d))
+    }
+
+    /// The three frames as whitespace-separated matrices, for a bug report.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "# tapview forced recalibration, {}x{}, mean shift {:+.3}\n",
+            self.diff.rows,
+            self.diff.cols,
+            self.mean_shift()
+        );
+        for (name, frame) in [
+            ("before", &self.before),
+            ("after", &self.after),
+            ("diff", &self.diff),
+        ] {
+            text += &format!("\n# {}\n", name);
+            for row in frame.data.chunks(frame.cols.max(1)) {
+                let cells: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+                text += &cells.join(" ");
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recalibration {
+    /// Waiting for the user to go ahead
+    Confirming,
+    /// Averaging the baseline before
+    Before(Capture),
+    /// Waiting for the heatmap thread to write the registers
+    Writing {
+        before: HeatmapFrame,
+    },
+    /// Averaging the baseline after, once settled
+    After {
+        before: HeatmapFrame,
+        capture: Capture,
+    },
+    Done(Comparison),
+    Failed(String),
+}
+
+/// What the recalibration window asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecalibrationAction {
+    Confirm,
+    Cancel,
+    Save,
+}
+
+impl Recalibration {
+    /// Go ahead: start averaging the baseline before.
+    pub fn confirm(&mut self, now: Instant) {
+        if *self == Recalibration::Confirming {
+            *self = Recalibration::Before(Capture::new(now));
+        }
+    }
+
+    /// Feed a heatmap frame that arrived at `time`. Returns true once the
+    /// baseline before is captured and the registers should be written.
+    pub fn update(&mut self, frame: &HeatmapFrame, time: Instant) -> bool {
+        let capture = match self {
+            Recalibration::Before(capture) | Recalibration::After { capture, .. } => capture,
+            _ => return false,
+        };
+        if time < capture.start {
+            return false;
+        }
+        if !capture.add(frame) {
+            *self = Recalibration::Failed("The heatmap changed shape during the capture".into());
+            return false;
+        }
+        if time.saturating_duration_since(capture.start) < AVERAGED {
+            return false;
+        }
+        let mean = capture.mean();
+        match self {
+            Recalibration::Before(_) => {
+                *self = Recalibration::Writing { before: mean };
+                true
+            }
+            Recalibration::After { before, .. } => {
+                *self = Recalibration::Done(Comparison::new(std::mem::take(before), mean));
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// The heatmap thread wrote the registers (or couldn't) at `now`.
+    pub fn written(&mut self, result: Result<(), String>, now: Instant) {
+        let Recalibration::Writing { before } = self else {
+            return;
+        };
+        *self = match result {
+            Ok(()) => Recalibration::After {
+                before: std::mem::take(before),
+                capture: Capture::new(now + SETTLE),
+            },
+            Err(e) => Recalibration::Failed(format!("Couldn't recalibrate: {}", e)),
+        };
+    }
+
+    /// Whether frames are being captured or a write is under way.
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self,
+            Recalibration::Before(_) | Recalibration::Writing { .. } | Recalibration::After { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_the_baseline_either_side_of_the_write() {
+        let t0 = Instant::now();
+        let at = |ms: u64| t0 + Duration::from_millis(ms);
+        let frame = |a, b| HeatmapFrame {
+            rows: 1,
+            cols: 2,
+            data: vec![a, b],
+        };
+        let mut recal = Recalibration::Confirming;
+        assert!(!recal.update(&frame(0, 0), t0));
+        recal.confirm(t0);
+        assert!(!recal.update(&frame(10, 20), at(0)));
+        assert!(recal.update(&frame(12, 20), at(1000)));
+        assert!(recal.is_running());
+
+        recal.written(Ok(()), at(1100));
+        // Still settling
+        recal.update(&frame(-50, 90), at(1500));
+        recal.update(&frame(2, 21), at(2100));
+        recal.update(&frame(4, 19), at(3100));
+        let Recalibration::Done(comparison) = &recal else {
+            panic!("didn't finish: {:?}", recal);
+        };
+        assert_eq!(comparison.before.data, [11, 20]);
+        assert_eq!(comparison.after.data, [3, 20]);
+        assert_eq!(comparison.diff.data, [-8, 0]);
+        assert_eq!(comparison.mean_shift(), -4.0);
+        assert_eq!(comparison.largest_change(), Some((0, 0, -8)));
+        assert!(comparison.to_text().ends_with("# diff\n-8 0\n"));
+
+        let mut recal = Recalibration::Writing {
+            before: frame(1, 1),
+        };
+        recal.written(Err("no writes".into()), t0);
+        assert_eq!(
+            recal,
+            Recalibration::Failed("Couldn't recalibrate: no writes".into())
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Register dumps saved in the working directory and the two picked to
+//! compare, e.g. before and after a gesture or a firmware calibration.
+
+use std::path::{Path, PathBuf};
+use tapview_core::heatmap::registers::{RegisterChange, RegisterDump};
+
+const PREFIX: &str = "tapview-registers-";
+
+#[derive(Default)]
+pub struct DumpComparison {
+    /// Oldest first, as the names are timestamps
+    pub files: Vec<PathBuf>,
+    pub before: Option<PathBuf>,
+    pub after: Option<PathBuf>,
+    /// Differences between `before` and `after`, or why they couldn't be read
+    pub diff: Option<Result<Vec<RegisterChange>, String>>,
+}
+
+impl DumpComparison {
+    /// Where a dump taken `secs` after the epoch goes in `dir`.
+    pub fn path(dir: &Path, secs: u64) -> PathBuf {
+        dir.join(format!("{}{}.txt", PREFIX, secs))
+    }
+
+    /// List the dumps in `dir` again.
+    pub fn refresh(&mut self, dir: &Path) {
+        self.files = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(PREFIX) && n.ends_with(".txt"))
+            })
+            .collect();
+        self.files.sort();
+    }
+
+    /// A new dump was saved: compare it with the one before, if any.
+    pub fn saved(&mut self, dir: &Path, path: PathBuf) {
+        self.refresh(dir);
+        let index = self.files.iter().position(|p| *p == path);
+        self.before = index
+            .and_then(|i| i.checked_sub(1))
+            .map(|i| self.files[i].clone());
+        self.after = Some(path);
+        self.compare();
+    }
+
+    /// Diff the picked dumps.
+    pub fn compare(&mut self) {
+        let (Some(before), Some(after)) = (&self.before, &self.after) else {
+            self.diff = None;
+            return;
+        };
+        let read = |path: &Path| {
+            std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| RegisterDump::parse(&text))
+                .map_err(|e| format!("{}: {}", path.display(), e))
+        };
+        self.diff = Some(read(before).and_then(|before| Ok(before.diff(&read(after)?))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_a_new_dump_with_the_one_before() {
+        let dir = std::env::temp_dir().join(format!("tapview-dumps-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("unrelated.txt"), "").unwrap();
+        std::fs::write(DumpComparison::path(&dir, 100), "0:78 43\n0:79 03\n").unwrap();
+        let path = DumpComparison::path(&dir, 200);
+        std::fs::write(&path, "0:78 43\n0:79 02\n").unwrap();
+
+        let mut dumps = DumpComparison::default();
+        dumps.saved(&dir, path);
+        assert_eq!(dumps.files.len(), 2);
+        let diff = dumps.diff.clone().unwrap().unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].register.to_string(), "0:79");
+        assert_eq!((diff[0].before, diff[0].after), (Some(3), Some(2)));
+
+        std::fs::write(DumpComparison::path(&dir, 100), "garbage").unwrap();
+        dumps.compare();
+        assert!(dumps.diff.unwrap().is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,13 +1,161 @@
+use crate::backend_error::BackendError;
+use crate::click_pressure::ClickPressure;
 use crate::config::PtpConfig;
-use crate::heatmap::HeatmapFrame;
-use crate::libinput_state::{GestureKind, LibinputState};
-use crate::multitouch::{ButtonState, TouchData};
+use crate::contact_swap::SwapDetector;
+use crate::cursor::CursorView;
+#[cfg(target_os = "linux")]
+use crate::device_properties::DeviceProperties;
+use crate::dwell::DwellMap;
+use crate::edge_test::{self, EdgeTest, Side};
+use crate::heatmap_ab::{self, AbTest, AbTestAction};
+use crate::ink::InkStroke;
+use crate::jitter::{JitterTest, JitterTestAction};
+use crate::libinput_state::{
+    AccelProfile, ClickMethod, GestureKind, LibinputCommand, LibinputConfig, LibinputDeviceInfo,
+    LibinputState,
+};
+use crate::linearity::{self, LinearityTest};
+use crate::orientation_test::{self, OrientationTest, OrientationTestAction};
+use crate::outliers::{Outlier, OutlierCheck, OutlierKind};
+use crate::palm::Suppression;
+use crate::palm_test::{self, PalmTest, PalmTestAction};
+use crate::pen::{Arbitration, PenState};
+use crate::pressure_test::{self, Marker, PressureSample, PressureTest};
+use crate::rates::RateMeter;
+use crate::recalibration::{Recalibration, RecalibrationAction};
+use crate::register_dumps::DumpComparison;
+use crate::report_rate::{self, ReportRate};
+use crate::report_timing::{self, ReportTiming};
+use crate::resolution_test::{self, ResolutionTest};
+#[cfg(feature = "scripting")]
+use crate::script::{Annotation, ScriptHook};
+use crate::slot_plots::{self, Sample, Series, SlotPlots};
+use crate::tap_drag::{DragPhase, TapDrag};
+use crate::tap_latency::TapLatency;
+use crate::tap_timing::{self, TapTiming};
+use crate::threshold_sim::{ThresholdSim, Verdict};
+use crate::tracking::{self, TrackingStats};
+use crate::trajectory::Trajectory;
 use egui::{Color32, FontId, Painter, Pos2, Rect, Stroke, StrokeKind, Vec2};
+use serde::{Deserialize, Serialize};
+use tapview_core::heatmap::registers::{Register, RegisterWatch, WatchedRegister};
+use tapview_core::heatmap::{HeatmapFrame, HeatmapOrientation};
+use tapview_core::input::descriptor_tree::Node as DescriptorNode;
+use tapview_core::logging::{Level, LogLine};
+use tapview_core::multitouch::{ButtonState, TouchData};
 
 pub const MAGENTA: Color32 = Color32::from_rgb(255, 0, 182);
 pub const TEAL: Color32 = Color32::from_rgb(0, 213, 255);
 pub const ORANGE: Color32 = Color32::from_rgb(255, 101, 0);
 pub const PALM_GRAY: Color32 = Color32::from_rgb(160, 160, 160);
+pub const RED: Color32 = Color32::from_rgb(230, 40, 40);
+
+/// Per-slot contact colors. Slot 0 keeps the original magenta and slot 1 teal;
+/// the rest are picked to stay apart from each other and from palm gray.
+pub const SLOT_COLORS: [Color32; 10] = [
+    MAGENTA,
+    TEAL,
+    Color32::from_rgb(255, 196, 0),
+    Color32::from_rgb(76, 217, 100),
+    Color32::from_rgb(138, 92, 255),
+    Color32::from_rgb(255, 82, 82),
+    Color32::from_rgb(0, 150, 136),
+    Color32::from_rgb(255, 140, 200),
+    Color32::from_rgb(160, 120, 60),
+    Color32::from_rgb(60, 110, 255),
+];
+
+// --- theme ---
+
+/// User-selectable color theme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeChoice {
+    Light,
+    Dark,
+    /// Follow the desktop's light/dark preference.
+    #[default]
+    System,
+}
+
+impl ThemeChoice {
+    pub const ALL: [ThemeChoice; 3] = [ThemeChoice::Light, ThemeChoice::Dark, ThemeChoice::System];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemeChoice::Light => "Light",
+            ThemeChoice::Dark => "Dark",
+            ThemeChoice::System => "System",
+        }
+    }
+}
+
+impl From<ThemeChoice> for egui::ThemePreference {
+    fn from(choice: ThemeChoice) -> Self {
+        match choice {
+            ThemeChoice::Light => egui::ThemePreference::Light,
+            ThemeChoice::Dark => egui::ThemePreference::Dark,
+            ThemeChoice::System => egui::ThemePreference::System,
+        }
+    }
+}
+
+/// Colors that depend on the background. Accent colors (slot colors,
+/// orange, palm gray) read well on both and stay constant.
+pub struct Theme {
+    /// Touch view background.
+    pub background: Color32,
+    /// Labels, headings, and markers drawn on the background.
+    pub text: Color32,
+    /// Secondary labels (coordinates, legends).
+    pub text_secondary: Color32,
+    /// Status hint in the middle of the touch view.
+    pub hint: Color32,
+    /// Markers drawn on top of a filled contact circle.
+    pub contact_mark: Color32,
+    /// Buttons that are not currently pressed.
+    pub inactive: Color32,
+    /// Reference lines behind the motion crosses.
+    pub guide: Color32,
+    /// Unaccelerated motion bars.
+    pub unaccel: Color32,
+    /// Event log text.
+    pub log_text: Color32,
+}
+
+impl Theme {
+    pub const LIGHT: Theme = Theme {
+        background: Color32::WHITE,
+        text: Color32::BLACK,
+        text_secondary: Color32::DARK_GRAY,
+        hint: Color32::GRAY,
+        contact_mark: Color32::BLACK,
+        inactive: Color32::from_rgb(200, 200, 200),
+        guide: Color32::from_rgb(230, 230, 230),
+        unaccel: Color32::from_rgb(180, 180, 180),
+        log_text: Color32::from_rgb(80, 80, 80),
+    };
+
+    pub const DARK: Theme = Theme {
+        background: Color32::from_rgb(27, 27, 27),
+        text: Color32::from_rgb(230, 230, 230),
+        text_secondary: Color32::from_rgb(170, 170, 170),
+        hint: Color32::GRAY,
+        contact_mark: Color32::BLACK,
+        inactive: Color32::from_rgb(80, 80, 80),
+        guide: Color32::from_rgb(60, 60, 60),
+        unaccel: Color32::from_rgb(110, 110, 110),
+        log_text: Color32::from_rgb(170, 170, 170),
+    };
+
+    /// Palette matching the theme egui resolved for this context.
+    pub fn current(ctx: &egui::Context) -> &'static Theme {
+        match ctx.theme() {
+            egui::Theme::Light => &Theme::LIGHT,
+            egui::Theme::Dark => &Theme::DARK,
+        }
+    }
+}
 
 const MT_TOOL_PALM: i32 = 0x02;
 
@@ -16,12 +164,338 @@ fn fade(color: Color32, alpha: f32) -> Color32 {
 }
 
 fn touch_color_for_slot(slot: usize, touch: &TouchData) -> Color32 {
-    if touch.tool_type == MT_TOOL_PALM {
+    slot_color(slot, touch.tool_type)
+}
+
+fn slot_color(slot: usize, tool_type: i32) -> Color32 {
+    if tool_type == MT_TOOL_PALM {
         PALM_GRAY
-    } else if slot == 0 {
-        MAGENTA
     } else {
-        TEAL
+        SLOT_COLORS[slot % SLOT_COLORS.len()]
+    }
+}
+
+/// Draw a row of slot color swatches with slot numbers, left-aligned at
+/// `origin`, one per slot in `touches`. Slots without a contact are drawn
+/// faded.
+pub fn draw_slot_legend(painter: &Painter, origin: Pos2, touches: &[TouchData]) {
+    let theme = Theme::current(painter.ctx());
+    let font = FontId::monospace(11.0);
+    for (slot, color) in SLOT_COLORS.iter().enumerate().take(touches.len()) {
+        let x = origin.x + slot as f32 * 28.0;
+        let active = touches.get(slot).is_some_and(|t| t.used);
+        let (swatch, text) = if active {
+            (*color, theme.text)
+        } else {
+            (fade(*color, 0.3), theme.text_secondary)
+        };
+        painter.circle_filled(Pos2::new(x + 5.0, origin.y), 5.0, swatch);
+        painter.text(
+            Pos2::new(x + 13.0, origin.y),
+            egui::Align2::LEFT_CENTER,
+            slot.to_string(),
+            font.clone(),
+            text,
+        );
+    }
+}
+
+/// Draw the dwell map as a translucent layer. Counts are square-root scaled
+/// so rarely touched regions stay visible next to hot spots.
+pub fn draw_dwell_map(
+    painter: &Painter,
+    map: &DwellMap,
+    corner: Pos2,
+    scale: f32,
+    colormap: Colormap,
+) {
+    if map.max == 0 {
+        return;
+    }
+    let max = (map.max as f32).sqrt();
+    let size = map.cell as f32 * scale;
+    for (&(cx, cy), &count) in &map.counts {
+        let min = Pos2::new(corner.x + cx as f32 * size, corner.y + cy as f32 * size);
+        let color = colormap.color((count as f32).sqrt() / max);
+        painter.rect_filled(
+            Rect::from_min_size(min, Vec2::splat(size)),
+            0.0,
+            fade(color, 0.45),
+        );
+    }
+}
+
+/// Shade the band between the advertised edges and the outermost positions
+/// reached during the edge test, bin by bin.
+pub fn draw_edge_band(painter: &Painter, test: &EdgeTest, corner: Pos2, scale: f32) {
+    let (ex, ey) = test.extent();
+    let color = fade(RED, 0.35);
+    let (bw, bh) = (ex / edge_test::BINS as f32, ey / edge_test::BINS as f32);
+    for side in Side::ALL {
+        for (i, gap) in test.gaps(side).into_iter().enumerate() {
+            let Some(gap) = gap.filter(|g| *g > 0.0) else {
+                continue;
+            };
+            let i = i as f32;
+            let (min, max) = match side {
+                Side::Top => ((i * bw, 0.0), ((i + 1.0) * bw, gap)),
+                Side::Bottom => ((i * bw, ey - gap), ((i + 1.0) * bw, ey)),
+                Side::Left => ((0.0, i * bh), (gap, (i + 1.0) * bh)),
+                Side::Right => ((ex - gap, i * bh), (ex, (i + 1.0) * bh)),
+            };
+            painter.rect_filled(
+                Rect::from_min_max(
+                    Pos2::new(corner.x + min.0 * scale, corner.y + min.1 * scale),
+                    Pos2::new(corner.x + max.0 * scale, corner.y + max.1 * scale),
+                ),
+                0.0,
+                color,
+            );
+        }
+    }
+}
+
+/// Contents of the edge test window: per-side dead zone and coverage.
+/// Returns true if Reset was clicked.
+pub fn draw_edge_test(
+    ui: &mut egui::Ui,
+    test: &EdgeTest,
+    resolution: Option<(f32, f32)>,
+    extent_known: bool,
+) -> bool {
+    ui.label("Slowly trace all four physical edges of the touchpad with one finger.");
+    if !extent_known {
+        ui.colored_label(
+            ORANGE,
+            "The device doesn't advertise its ranges; comparing against learned extents.",
+        );
+    }
+    let (rx, ry) = resolution.unwrap_or((1.0, 1.0));
+    let unit = if resolution.is_some() { "mm" } else { "units" };
+    egui::Grid::new("edge_test")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.small(format!("best ({})", unit));
+            ui.small(format!("worst ({})", unit));
+            ui.small("covered");
+            ui.end_row();
+            for side in Side::ALL {
+                let per_unit = match side {
+                    Side::Top | Side::Bottom => ry,
+                    Side::Left | Side::Right => rx,
+                };
+                ui.label(side.label());
+                match test.dead_zone(side) {
+                    Some(zone) => {
+                        ui.monospace(format!("{:.2}", zone.min / per_unit));
+                        ui.monospace(format!("{:.2}", zone.max / per_unit));
+                        ui.monospace(format!("{:.0}%", zone.coverage * 100.0));
+                    }
+                    None => {
+                        ui.monospace("-");
+                        ui.monospace("-");
+                        ui.monospace("0%");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    ui.button("Reset").clicked()
+}
+
+/// Resolution wizard: distance entry, the swipe being asked for, measured
+/// versus advertised units per mm, and an hwdb entry when they disagree.
+/// Returns true if Run again was clicked.
+pub fn draw_resolution_test(
+    ui: &mut egui::Ui,
+    test: &mut ResolutionTest,
+    advertised: Option<(f32, f32)>,
+    hwdb_match: Option<&str>,
+) -> bool {
+    let started = test.measured.iter().any(Option::is_some);
+    ui.horizontal(|ui| {
+        ui.label("Distance between the marks:");
+        ui.add_enabled(
+            !started,
+            egui::DragValue::new(&mut test.distance_mm)
+                .range(10.0..=300.0)
+                .suffix(" mm"),
+        );
+    });
+    match test.current() {
+        Some(axis) => {
+            ui.label(format!("{}: {}.", axis.label(), axis.prompt()));
+        }
+        None => {
+            ui.label("Both axes measured");
+        }
+    }
+    if let Some(reason) = test.rejected {
+        ui.colored_label(ORANGE, reason);
+    }
+
+    egui::Grid::new("resolution_test")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.small("measured (units/mm)");
+            ui.small("kernel (units/mm)");
+            ui.end_row();
+            for axis in resolution_test::AXES {
+                let kernel = advertised.map(|(x, y)| match axis {
+                    resolution_test::Axis::X => x,
+                    resolution_test::Axis::Y => y,
+                });
+                ui.label(axis.label());
+                match test.measured[axis as usize] {
+                    Some(measured) => {
+                        let color = if resolution_test::disagrees(measured, kernel) {
+                            ORANGE
+                        } else {
+                            ui.visuals().text_color()
+                        };
+                        ui.label(
+                            egui::RichText::new(format!("{:.1}", measured))
+                                .monospace()
+                                .color(color),
+                        );
+                    }
+                    None => {
+                        ui.monospace("-");
+                    }
+                }
+                ui.monospace(kernel.map_or("none".to_string(), |r| format!("{:.0}", r)));
+                ui.end_row();
+            }
+        });
+
+    if test.current().is_none() {
+        let snippet = hwdb_match.and_then(|m| test.hwdb_snippet(m, advertised));
+        match (snippet, hwdb_match) {
+            (Some(snippet), _) => {
+                ui.colored_label(
+                    ORANGE,
+                    "The kernel's resolution is off; this hwdb entry corrects it:",
+                );
+                ui.label(egui::RichText::new(&snippet).monospace().small());
+                if ui.button("Copy hwdb entry").clicked() {
+                    ui.ctx().copy_text(snippet);
+                }
+            }
+            (None, Some(_)) => {
+                ui.label(format!(
+                    "Matches the kernel within {:.0}%",
+                    resolution_test::TOLERANCE * 100.0
+                ));
+            }
+            (None, None) => {}
+        }
+    }
+    ui.button("Run again").clicked()
+}
+
+/// Mark where contacts were dropped and re-created with a small red X.
+pub fn draw_drop_marks(painter: &Painter, drops: &[(i32, i32)], corner: Pos2, scale: f32) {
+    const ARM: f32 = 5.0;
+    let stroke = Stroke::new(2.0, RED);
+    for &(x, y) in drops {
+        let c = Pos2::new(corner.x + x as f32 * scale, corner.y + y as f32 * scale);
+        painter.line_segment([c + Vec2::new(-ARM, -ARM), c + Vec2::new(ARM, ARM)], stroke);
+        painter.line_segment([c + Vec2::new(-ARM, ARM), c + Vec2::new(ARM, -ARM)], stroke);
+    }
+}
+
+/// Mark where two contacts' tracking IDs were swapped with a warning
+/// triangle.
+pub fn draw_swap_marks(painter: &Painter, swaps: &[(i32, i32)], corner: Pos2, scale: f32) {
+    const SIZE: f32 = 7.0;
+    for &(x, y) in swaps {
+        let c = Pos2::new(corner.x + x as f32 * scale, corner.y + y as f32 * scale);
+        painter.add(egui::Shape::convex_polygon(
+            vec![
+                c + Vec2::new(0.0, -SIZE),
+                c + Vec2::new(SIZE, SIZE * 0.8),
+                c + Vec2::new(-SIZE, SIZE * 0.8),
+            ],
+            Color32::TRANSPARENT,
+            Stroke::new(2.0, ORANGE),
+        ));
+        painter.line_segment(
+            [
+                c + Vec2::new(0.0, -SIZE * 0.3),
+                c + Vec2::new(0.0, SIZE * 0.3),
+            ],
+            Stroke::new(1.5, ORANGE),
+        );
+    }
+}
+
+/// Circle where implausible values were reported: red for out-of-range
+/// values, orange for position spikes.
+pub fn draw_outlier_marks(painter: &Painter, outliers: &[Outlier], corner: Pos2, scale: f32) {
+    for o in outliers {
+        let (x, y) = o.position;
+        let c = Pos2::new(corner.x + x as f32 * scale, corner.y + y as f32 * scale);
+        let color = match o.kind {
+            OutlierKind::OutOfRange { .. } => RED,
+            OutlierKind::Spike { .. } => ORANGE,
+        };
+        painter.circle_stroke(c, 6.0, Stroke::new(2.0, color));
+        painter.circle_filled(c, 1.5, color);
+    }
+}
+
+/// Draw what a `--script` asked for, in touchpad coordinates.
+#[cfg(feature = "scripting")]
+pub fn draw_script_annotations(
+    painter: &Painter,
+    annotations: &[Annotation],
+    corner: Pos2,
+    scale: f32,
+) {
+    let color = TEAL;
+    let to_screen = |x: f32, y: f32| Pos2::new(corner.x + x * scale, corner.y + y * scale);
+    for annotation in annotations {
+        match annotation {
+            Annotation::Text { x, y, text } => {
+                painter.text(
+                    to_screen(*x, *y),
+                    egui::Align2::LEFT_BOTTOM,
+                    text,
+                    FontId::proportional(12.0),
+                    color,
+                );
+            }
+            Annotation::Circle { x, y, radius } => {
+                painter.circle_stroke(to_screen(*x, *y), radius * scale, Stroke::new(1.5, color));
+            }
+            Annotation::Line { from, to } => {
+                painter.line_segment(
+                    [to_screen(from.0, from.1), to_screen(to.0, to.1)],
+                    Stroke::new(1.5, color),
+                );
+            }
+        }
+    }
+}
+
+/// Draw ink strokes as polylines in their slot colors.
+pub fn draw_ink(painter: &Painter, strokes: &[InkStroke], corner: Pos2, scale: f32) {
+    for stroke in strokes {
+        let color = slot_color(stroke.slot, stroke.tool_type);
+        let points: Vec<Pos2> = stroke
+            .points
+            .iter()
+            .map(|&(x, y)| Pos2::new(corner.x + x as f32 * scale, corner.y + y as f32 * scale))
+            .collect();
+        if let [p] = points.as_slice() {
+            painter.circle_filled(*p, 1.5, color);
+        } else {
+            painter.add(egui::Shape::line(points, Stroke::new(1.5, color)));
+        }
     }
 }
 
@@ -53,12 +527,96 @@ pub fn draw_trail(
     corner: Pos2,
     scale: f32,
     cscale: f32,
+    alpha: f32,
 ) {
     let pos = touch_to_screen(touch, corner, scale);
-    let color = fade(touch_color_for_slot(slot, touch), 0.2);
+    let color = fade(
+        touch_color_for_slot(slot, touch),
+        0.2 * alpha.clamp(0.0, 1.0),
+    );
     draw_ring(painter, pos, 1.0, 36.0 * cscale, color);
 }
 
+/// Smallest on-screen semi-axis for an ellipse contact, so tiny reported
+/// geometry stays visible.
+const MIN_ELLIPSE_RADIUS: f32 = 4.0;
+
+/// Contact geometry in screen space: semi-axes along the contact's own X and Y
+/// and a clockwise rotation in radians.
+#[derive(Clone, Copy)]
+pub struct ContactShape {
+    pub radius_x: f32,
+    pub radius_y: f32,
+    pub angle: f32,
+}
+
+impl ContactShape {
+    /// Build the shape from ABS_MT_TOUCH_MAJOR/MINOR/ORIENTATION. Returns None
+    /// when the device doesn't report contact size. `orientation_max` is the
+    /// value meaning a quarter turn; orientation is ignored when it's unknown.
+    pub fn from_touch(touch: &TouchData, scale: f32, orientation_max: Option<i32>) -> Option<Self> {
+        if touch.touch_major <= 0 {
+            return None;
+        }
+        let major = touch.touch_major as f32;
+        let minor = if touch.touch_minor > 0 {
+            touch.touch_minor as f32
+        } else {
+            major
+        };
+        let angle = orientation_max
+            .map(|max| touch.orientation as f32 / max as f32 * std::f32::consts::FRAC_PI_2)
+            .unwrap_or(0.0);
+        // Orientation 0 means the major axis is aligned with the surface Y axis
+        Some(Self {
+            radius_x: (minor / 2.0 * scale).max(MIN_ELLIPSE_RADIUS),
+            radius_y: (major / 2.0 * scale).max(MIN_ELLIPSE_RADIUS),
+            angle,
+        })
+    }
+
+    fn points(&self, center: Pos2, factor: f32) -> Vec<Pos2> {
+        const SEGMENTS: usize = 32;
+        let (sin, cos) = self.angle.sin_cos();
+        (0..SEGMENTS)
+            .map(|i| {
+                let t = i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+                let x = self.radius_x * factor * t.cos();
+                let y = self.radius_y * factor * t.sin();
+                Pos2::new(center.x + x * cos - y * sin, center.y + x * sin + y * cos)
+            })
+            .collect()
+    }
+}
+
+/// Fill either the reported contact ellipse or the default circle, shrunk by `factor`.
+fn fill_contact(
+    painter: &Painter,
+    pos: Pos2,
+    shape: Option<ContactShape>,
+    radius: f32,
+    factor: f32,
+    color: Color32,
+) {
+    match shape {
+        Some(shape) => {
+            painter.add(egui::Shape::convex_polygon(
+                shape.points(pos, factor),
+                color,
+                Stroke::NONE,
+            ));
+        }
+        None => {
+            painter.circle_filled(pos, radius * factor, color);
+        }
+    }
+}
+
+/// Draw a contact as its reported ellipse, or a fixed-size circle when the
+/// device doesn't report contact size. When `pressure` (normalized
+/// 0.0..=1.0) is given, the shape is drawn faded with a solid inner shape
+/// sized by pressure.
+#[allow(clippy::too_many_arguments)]
 pub fn draw_touch(
     painter: &Painter,
     touch: &TouchData,
@@ -66,21 +624,36 @@ pub fn draw_touch(
     corner: Pos2,
     scale: f32,
     cscale: f32,
+    pressure: Option<f32>,
+    shape: Option<ContactShape>,
 ) {
+    let theme = Theme::current(painter.ctx());
     let pos = touch_to_screen(touch, corner, scale);
     let color = touch_color_for_slot(slot, touch);
+    let radius = 34.0 * cscale;
 
-    // Main circle
-    painter.circle_filled(pos, 34.0 * cscale, color);
+    // Main shape
+    if let Some(p) = pressure {
+        fill_contact(painter, pos, shape, radius, 1.0, fade(color, 0.35));
+        fill_contact(painter, pos, shape, radius, p.clamp(0.1, 1.0), color);
+    } else {
+        fill_contact(painter, pos, shape, radius, 1.0, color);
+    }
 
     // Double-tap ring
     if touch.pressed_double {
-        draw_ring(painter, pos, 14.0 * cscale, 20.0 * cscale, Color32::BLACK);
+        draw_ring(
+            painter,
+            pos,
+            14.0 * cscale,
+            20.0 * cscale,
+            theme.contact_mark,
+        );
     }
 
     // Pressed dot
     if touch.pressed {
-        painter.circle_filled(pos, 8.0 * cscale, Color32::BLACK);
+        painter.circle_filled(pos, 8.0 * cscale, theme.contact_mark);
     }
 
     // Slot number label
@@ -90,7 +663,7 @@ pub fn draw_touch(
         egui::Align2::LEFT_TOP,
         format!("{}", slot),
         FontId::monospace(40.0 * cscale),
-        Color32::BLACK,
+        theme.text,
     );
 
     // Coordinate label
@@ -100,7 +673,135 @@ pub fn draw_touch(
         egui::Align2::CENTER_TOP,
         format!("{},{}", touch.position_x, touch.position_y),
         FontId::monospace(12.0 * cscale),
-        Color32::DARK_GRAY,
+        theme.text_secondary,
+    );
+}
+
+/// Draw a hovering contact (non-zero ABS_MT_DISTANCE) as a hollow circle.
+/// The ring is thickest just above the surface and thins out towards the
+/// edge of the hover range; `distance` is normalized 0.0..=1.0.
+pub fn draw_hover(
+    painter: &Painter,
+    touch: &TouchData,
+    slot: usize,
+    corner: Pos2,
+    scale: f32,
+    cscale: f32,
+    distance: f32,
+) {
+    let theme = Theme::current(painter.ctx());
+    let pos = touch_to_screen(touch, corner, scale);
+    let color = touch_color_for_slot(slot, touch);
+    let radius = 34.0 * cscale;
+    let thickness = (1.0 - distance.clamp(0.0, 1.0)) * 0.5 * radius;
+    draw_ring(
+        painter,
+        pos,
+        radius - thickness.max(1.5 * cscale),
+        radius,
+        color,
+    );
+
+    painter.text(
+        Pos2::new(pos.x - 10.0 * cscale, pos.y - 70.0 * cscale),
+        egui::Align2::LEFT_TOP,
+        format!("{}", slot),
+        FontId::monospace(40.0 * cscale),
+        theme.text,
+    );
+    painter.text(
+        Pos2::new(pos.x, pos.y + 40.0 * cscale),
+        egui::Align2::CENTER_TOP,
+        format!("hover {}", touch.distance),
+        FontId::monospace(12.0 * cscale),
+        theme.text_secondary,
+    );
+}
+
+/// On INPUT_PROP_SEMI_MT devices the two slots are opposite corners of a box
+/// around all fingers, not fingers. Draw that box with the finger count from
+/// BTN_TOOL_*.
+pub fn draw_semi_mt_box(
+    painter: &Painter,
+    a: &TouchData,
+    b: &TouchData,
+    tool_fingers: Option<u8>,
+    corner: Pos2,
+    scale: f32,
+) {
+    let theme = Theme::current(painter.ctx());
+    let rect = Rect::from_two_pos(
+        touch_to_screen(a, corner, scale),
+        touch_to_screen(b, corner, scale),
+    );
+    painter.rect_filled(rect, 4.0, fade(MAGENTA, 0.2));
+    painter.rect_stroke(
+        rect,
+        4.0,
+        Stroke::new(2.0, MAGENTA),
+        egui::StrokeKind::Middle,
+    );
+    let fingers = match tool_fingers {
+        Some(n) if n >= 5 => "5+ fingers".to_string(),
+        Some(n) => format!("{} fingers", n),
+        None => "2+ fingers".to_string(),
+    };
+    painter.text(
+        Pos2::new(rect.center().x, rect.min.y - 6.0),
+        egui::Align2::CENTER_BOTTOM,
+        format!("{} (semi-MT bounding box)", fingers),
+        FontId::monospace(12.0),
+        theme.text_secondary,
+    );
+}
+
+/// Gray a contact out and name the reason libinput would likely ignore it.
+pub fn draw_suppression(
+    painter: &Painter,
+    touch: &TouchData,
+    corner: Pos2,
+    scale: f32,
+    cscale: f32,
+    reason: Suppression,
+) {
+    let pos = touch_to_screen(touch, corner, scale);
+    painter.circle_filled(pos, 34.0 * cscale, fade(PALM_GRAY, 0.6));
+    painter.text(
+        Pos2::new(pos.x, pos.y + 56.0 * cscale),
+        egui::Align2::CENTER_TOP,
+        reason.label(),
+        FontId::proportional(12.0 * cscale.max(0.5)),
+        ORANGE,
+    );
+}
+
+/// A contact as judged under simulated thresholds: a ring for accepted
+/// touches, a faint one for those too light to count, a palm shading.
+pub fn draw_verdict(
+    painter: &Painter,
+    touch: &TouchData,
+    corner: Pos2,
+    scale: f32,
+    cscale: f32,
+    verdict: Verdict,
+) {
+    let pos = touch_to_screen(touch, corner, scale);
+    let (label, color) = match verdict {
+        Verdict::Accepted => ("accepted", TEAL),
+        Verdict::TooLight => ("too light", PALM_GRAY),
+        Verdict::Palm => ("palm", RED),
+    };
+    if verdict == Verdict::Palm {
+        painter.circle_filled(pos, 34.0 * cscale, fade(RED, 0.4));
+    } else {
+        painter.circle_stroke(pos, 34.0 * cscale, Stroke::new(3.0 * cscale, color));
+    }
+    painter.text(
+        Pos2::new(pos.x, pos.y + 56.0 * cscale),
+        egui::Align2::CENTER_TOP,
+        label,
+        FontId::proportional(12.0 * cscale.max(0.5)),
+        color,
     );
 }
 
@@ -111,6 +812,7 @@ pub fn draw_button_indicators(
     boundary_width: f32,
     boundary_height: f32,
 ) {
+    let theme = Theme::current(painter.ctx());
     let y = corner.y + boundary_height + 8.0;
     let font = FontId::monospace(14.0);
     let labels = [
@@ -125,11 +827,7 @@ pub fn draw_button_indicators(
     for (i, (label, active)) in labels.iter().enumerate() {
         let x = start_x + i as f32 * 24.0;
         let center = Pos2::new(x, y);
-        let color = if *active {
-            MAGENTA
-        } else {
-            Color32::from_rgb(200, 200, 200)
-        };
+        let color = if *active { MAGENTA } else { theme.inactive };
         painter.text(
             center,
             egui::Align2::CENTER_TOP,
@@ -140,54 +838,743 @@ pub fn draw_button_indicators(
     }
 }
 
-fn touch_to_screen(touch: &TouchData, corner: Pos2, scale: f32) -> Pos2 {
-    Pos2::new(
-        corner.x + touch.position_x as f32 * scale,
-        corner.y + touch.position_y as f32 * scale,
-    )
+/// Draw one line per rate meter (label, events/s, sparkline), right-aligned
+/// at `origin`.
+/// `meters` are (label, meter, messages dropped on the way to the UI).
+pub fn draw_rate_hud(painter: &Painter, origin: Pos2, meters: &[(&str, &RateMeter, u64)]) {
+    const SPARK_W: f32 = 60.0;
+    const SPARK_H: f32 = 12.0;
+    const ROW_H: f32 = 16.0;
+    let theme = Theme::current(painter.ctx());
+    let font = FontId::monospace(11.0);
+    for (row, &(label, meter, dropped)) in meters.iter().enumerate() {
+        let y = origin.y + row as f32 * ROW_H;
+        let spark = Rect::from_min_size(
+            Pos2::new(origin.x - SPARK_W, y),
+            Vec2::new(SPARK_W, SPARK_H),
+        );
+        let (text, color) = if dropped > 0 {
+            (
+                format!("{} {:5.0}/s ({} dropped)", label, meter.rate(), dropped),
+                ORANGE,
+            )
+        } else {
+            (
+                format!("{} {:5.0}/s", label, meter.rate()),
+                theme.text_secondary,
+            )
+        };
+        painter.text(
+            Pos2::new(spark.min.x - 6.0, y + SPARK_H / 2.0),
+            egui::Align2::RIGHT_CENTER,
+            text,
+            font.clone(),
+            color,
+        );
+        let peak = meter.history.iter().copied().fold(1.0, f32::max);
+        let step = SPARK_W / (meter.history.len().max(2) - 1) as f32;
+        let points: Vec<Pos2> = meter
+            .history
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                Pos2::new(
+                    spark.min.x + i as f32 * step,
+                    spark.max.y - v / peak * SPARK_H,
+                )
+            })
+            .collect();
+        painter.rect_stroke(
+            spark,
+            0.0,
+            Stroke::new(1.0, theme.guide),
+            StrokeKind::Inside,
+        );
+        painter.add(egui::Shape::line(points, Stroke::new(1.0, TEAL)));
+    }
 }
 
-// --- libinput visualization ---
-
-const CROSS_SIZE: f32 = 40.0;
-const ACCEL_COLOR: Color32 = MAGENTA;
-const UNACCEL_COLOR: Color32 = Color32::from_rgb(180, 180, 180);
-
-/// Draw a cross widget showing a 2D vector.
-/// `accel` is drawn as filled bars, `unaccel` as outline bars.
-/// `scale_factor` maps raw values to pixels.
-fn draw_cross(
+/// The cursor widget: the screen as a small rectangle in `rect`, the pointer
+/// and its trail, and as a hollow ring where `contact` (a share of the
+/// touchpad, 0..1 on both axes) would put the pointer if the pad mapped
+/// absolutely onto the screen.
+pub fn draw_cursor_view(
     painter: &Painter,
-    center: Pos2,
-    accel: (f32, f32),
-    unaccel: (f32, f32),
-    scale_factor: f32,
-    bar_width: f32,
+    rect: Rect,
+    cursor: &CursorView,
+    contact: Option<((f32, f32), Color32)>,
 ) {
-    let max = CROSS_SIZE;
-
-    // Draw faint cross lines for reference
-    let guide_color = Color32::from_rgb(230, 230, 230);
-    painter.line_segment(
-        [
-            Pos2::new(center.x - max, center.y),
-            Pos2::new(center.x + max, center.y),
-        ],
-        Stroke::new(1.0, guide_color),
+    let theme = Theme::current(painter.ctx());
+    let at = |(x, y): (f32, f32)| rect.min + Vec2::new(x * rect.width(), y * rect.height());
+    painter.rect_filled(rect, 2.0, theme.background.gamma_multiply(0.8));
+    painter.rect_stroke(
+        rect,
+        2.0,
+        Stroke::new(1.0, theme.text_secondary),
+        StrokeKind::Inside,
     );
-    painter.line_segment(
-        [
-            Pos2::new(center.x, center.y - max),
-            Pos2::new(center.x, center.y + max),
-        ],
-        Stroke::new(1.0, guide_color),
+    painter.text(
+        rect.left_top() + Vec2::new(0.0, -2.0),
+        egui::Align2::LEFT_BOTTOM,
+        "Cursor",
+        FontId::proportional(11.0),
+        theme.text_secondary,
     );
 
-    // Draw unaccelerated (outline) first, then accelerated (filled) on top
-    let pairs = [(unaccel, UNACCEL_COLOR, false), (accel, ACCEL_COLOR, true)];
-
-    for &((dx, dy), color, filled) in &pairs {
-        let sx = (dx * scale_factor).clamp(-max, max);
+    if let Some((pos, color)) = contact {
+        painter.circle_stroke(at(pos), 5.0, Stroke::new(1.5, color));
+    }
+    let trail: Vec<Pos2> = cursor
+        .trail
+        .iter()
+        .map(|&p| at(cursor.normalized(p)))
+        .collect();
+    if trail.len() >= 2 {
+        painter.add(egui::Shape::line(
+            trail,
+            Stroke::new(1.0, theme.text_secondary),
+        ));
+    }
+    if let Some(pos) = cursor.pos {
+        painter.circle_filled(at(cursor.normalized(pos)), 3.0, theme.text);
+    }
+}
+
+/// Click method overlay for buttonpads (INPUT_PROP_BUTTONPAD), mirroring
+/// libinput's two click methods.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClickZones {
+    Off,
+    /// Software button areas along the bottom edge.
+    #[default]
+    Areas,
+    /// Button chosen by the number of fingers down.
+    Clickfinger,
+}
+
+impl ClickZones {
+    pub const ALL: [ClickZones; 3] = [ClickZones::Off, ClickZones::Areas, ClickZones::Clickfinger];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ClickZones::Off => "Off",
+            ClickZones::Areas => "Button areas",
+            ClickZones::Clickfinger => "Clickfinger",
+        }
+    }
+}
+
+/// Draw libinput's click regions over the touchpad. Button areas follow
+/// libinput's defaults: the bottom 10 mm (at most 15% of the height, or
+/// exactly 15% without a resolution), split into left and right with a
+/// centered middle area 25% wide.
+pub fn draw_click_zones(
+    painter: &Painter,
+    corner: Pos2,
+    scale: f32,
+    boundary_width: f32,
+    boundary_height: f32,
+    resolution: Option<(f32, f32)>,
+    zones: ClickZones,
+) {
+    let theme = Theme::current(painter.ctx());
+    let font = FontId::proportional(11.0);
+    match zones {
+        ClickZones::Off => {}
+        ClickZones::Areas => {
+            let max_h = boundary_height * 0.15;
+            let area_h = resolution
+                .map(|(_, ry)| (10.0 * ry * scale).min(max_h))
+                .unwrap_or(max_h);
+            let top = corner.y + boundary_height - area_h;
+            let bottom = corner.y + boundary_height;
+            let mid_l = corner.x + boundary_width * 0.375;
+            let mid_r = corner.x + boundary_width * 0.625;
+            let right = corner.x + boundary_width;
+            let areas = [
+                ("Left", corner.x, mid_l, MAGENTA),
+                ("Middle", mid_l, mid_r, ORANGE),
+                ("Right", mid_r, right, TEAL),
+            ];
+            for (label, x0, x1, color) in areas {
+                let rect = Rect::from_min_max(Pos2::new(x0, top), Pos2::new(x1, bottom));
+                painter.rect_filled(rect, 0.0, fade(color, 0.12));
+                painter.rect_stroke(
+                    rect,
+                    0.0,
+                    Stroke::new(1.0, fade(color, 0.5)),
+                    StrokeKind::Inside,
+                );
+                painter.text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    label,
+                    font.clone(),
+                    theme.text_secondary,
+                );
+            }
+        }
+        ClickZones::Clickfinger => {
+            painter.text(
+                Pos2::new(
+                    corner.x + boundary_width / 2.0,
+                    corner.y + boundary_height - 4.0,
+                ),
+                egui::Align2::CENTER_BOTTOM,
+                "Clickfinger: 1 finger = left, 2 = right, 3 = middle",
+                font,
+                theme.text_secondary,
+            );
+        }
+    }
+}
+
+/// The edge-swipe zones, `band` wide (screen pixels, x and y) along each
+/// edge with the corner squares darker, and the badge for the latest edge
+/// event with a marker where it started.
+pub fn draw_edge_zones(
+    painter: &Painter,
+    corner: Pos2,
+    boundary_width: f32,
+    boundary_height: f32,
+    band: Vec2,
+    badge: Option<(&str, Pos2)>,
+) {
+    let theme = Theme::current(painter.ctx());
+    let pad = Rect::from_min_size(corner, Vec2::new(boundary_width, boundary_height));
+    let fill = fade(ORANGE, 0.1);
+    let (bx, by) = (
+        band.x.min(pad.width() / 2.0),
+        band.y.min(pad.height() / 2.0),
+    );
+    let edges = [
+        Rect::from_min_max(pad.min, Pos2::new(pad.max.x, pad.min.y + by)),
+        Rect::from_min_max(Pos2::new(pad.min.x, pad.max.y - by), pad.max),
+        Rect::from_min_max(
+            Pos2::new(pad.min.x, pad.min.y + by),
+            Pos2::new(pad.min.x + bx, pad.max.y - by),
+        ),
+        Rect::from_min_max(
+            Pos2::new(pad.max.x - bx, pad.min.y + by),
+            Pos2::new(pad.max.x, pad.max.y - by),
+        ),
+    ];
+    for rect in edges {
+        painter.rect_filled(rect, 0.0, fill);
+    }
+    for (x, y) in [
+        (pad.min.x, pad.min.y),
+        (pad.max.x - bx, pad.min.y),
+        (pad.min.x, pad.max.y - by),
+        (pad.max.x - bx, pad.max.y - by),
+    ] {
+        painter.rect_filled(
+            Rect::from_min_size(Pos2::new(x, y), Vec2::new(bx, by)),
+            0.0,
+            fill,
+        );
+    }
+
+    if let Some((label, start)) = badge {
+        painter.circle_stroke(start, 6.0, Stroke::new(2.0, ORANGE));
+        let galley = painter.layout_no_wrap(label.to_string(), FontId::proportional(16.0), ORANGE);
+        let pos = Pos2::new(pad.center().x - galley.size().x / 2.0, pad.min.y + by + 8.0);
+        painter.rect_filled(
+            Rect::from_min_size(pos, galley.size()).expand(4.0),
+            4.0,
+            theme.background.gamma_multiply(0.85),
+        );
+        painter.galley(pos, galley, ORANGE);
+    }
+}
+
+/// The pen at `pos` while in proximity: a crosshair when hovering, a dot
+/// growing with pressure when the tip is down, and an X where each contact
+/// dropped for the pen was last seen.
+pub fn draw_pen(painter: &Painter, pos: Option<Pos2>, pen: &PenState, dropped: &[Pos2]) {
+    for &at in dropped {
+        let d = 6.0;
+        let stroke = Stroke::new(2.0, RED);
+        painter.line_segment([at + Vec2::new(-d, -d), at + Vec2::new(d, d)], stroke);
+        painter.line_segment([at + Vec2::new(-d, d), at + Vec2::new(d, -d)], stroke);
+    }
+    let Some(pos) = pos else {
+        return;
+    };
+    let color = if pen.eraser { ORANGE } else { TEAL };
+    if pen.touching {
+        painter.circle_filled(pos, 3.0 + 9.0 * pen.pressure, color);
+    } else {
+        let stroke = Stroke::new(1.5, color);
+        painter.line_segment(
+            [pos - Vec2::new(10.0, 0.0), pos + Vec2::new(10.0, 0.0)],
+            stroke,
+        );
+        painter.line_segment(
+            [pos - Vec2::new(0.0, 10.0), pos + Vec2::new(0.0, 10.0)],
+            stroke,
+        );
+        painter.circle_stroke(pos, 5.0, stroke);
+    }
+}
+
+/// Collapsible udev properties, libinput quirks and hwdb entries of the
+/// device, with a button copying them all.
+#[cfg(target_os = "linux")]
+pub fn draw_device_properties(ui: &mut egui::Ui, properties: &DeviceProperties) {
+    fn pairs(ui: &mut egui::Ui, id: &str, rows: &[(String, String)]) {
+        egui::Grid::new(id).striped(true).show(ui, |ui| {
+            for (key, value) in rows {
+                ui.monospace(key);
+                ui.monospace(value);
+                ui.end_row();
+            }
+        });
+    }
+
+    egui::CollapsingHeader::new("udev, quirks and hwdb")
+        .default_open(false)
+        .show(ui, |ui| {
+            let theme = Theme::current(ui.ctx());
+            egui::CollapsingHeader::new(format!("udev properties ({})", properties.udev.len()))
+                .show(ui, |ui| pairs(ui, "udev_properties", &properties.udev));
+            egui::CollapsingHeader::new(format!("libinput quirks ({})", properties.quirks.len()))
+                .default_open(true)
+                .show(ui, |ui| {
+                    for section in &properties.quirk_sections {
+                        ui.label(format!("[{}]", section));
+                    }
+                    if properties.quirks.is_empty() {
+                        ui.colored_label(
+                            theme.text_secondary,
+                            "None (or the libinput tool isn't installed)",
+                        );
+                    }
+                    pairs(ui, "libinput_quirks", &properties.quirks);
+                });
+            egui::CollapsingHeader::new(format!("hwdb entries ({})", properties.hwdb.len()))
+                .default_open(true)
+                .show(ui, |ui| {
+                    if properties.hwdb.is_empty() {
+                        ui.colored_label(theme.text_secondary, "None");
+                    }
+                    egui::Grid::new("hwdb_entries")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (key, name, value) in &properties.hwdb {
+                                ui.monospace(name).on_hover_text(key);
+                                ui.monospace(value);
+                                ui.end_row();
+                            }
+                        });
+                });
+            if ui.small_button("Copy all").clicked() {
+                ui.ctx().copy_text(properties.report_text());
+            }
+        });
+}
+
+/// A report descriptor tree: collections as collapsible headers, items as
+/// lines, with a button copying it all as text.
+pub fn draw_descriptor_tree(ui: &mut egui::Ui, tree: &[DescriptorNode]) {
+    fn node(ui: &mut egui::Ui, n: &DescriptorNode, path: &str) {
+        if n.children.is_empty() {
+            ui.monospace(&n.label);
+            return;
+        }
+        egui::CollapsingHeader::new(egui::RichText::new(&n.label).monospace())
+            .id_salt(path)
+            .default_open(true)
+            .show(ui, |ui| {
+                for (i, child) in n.children.iter().enumerate() {
+                    node(ui, child, &format!("{}/{}", path, i));
+                }
+            });
+    }
+
+    if ui.small_button("Copy as text").clicked() {
+        let mut text = String::new();
+        for n in tree {
+            n.write_text(&mut text, 0);
+        }
+        ui.ctx().copy_text(text);
+    }
+    egui::ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+        for (i, n) in tree.iter().enumerate() {
+            node(ui, n, &format!("descriptor/{}", i));
+        }
+    });
+}
+
+/// Collapsible threshold simulator: sliders for the pressure range and palm
+/// thresholds, the switch that tints contacts by them, and the quirk lines.
+pub fn draw_threshold_sim(ui: &mut egui::Ui, sim: &mut ThresholdSim) {
+    egui::CollapsingHeader::new("Threshold simulator")
+        .default_open(false)
+        .show(ui, |ui| {
+            ui.checkbox(&mut sim.enabled, "Tint contacts by these thresholds");
+            if sim.has_pressure {
+                let max = sim.pressure_max.max(1);
+                ui.add(
+                    egui::Slider::new(&mut sim.pressure_high, 0..=max).text("touch begins above"),
+                );
+                ui.add(egui::Slider::new(&mut sim.pressure_low, 0..=max).text("touch ends below"));
+                sim.pressure_low = sim.pressure_low.min(sim.pressure_high);
+                ui.add(egui::Slider::new(&mut sim.palm_pressure, 0..=max).text("palm pressure"));
+            } else {
+                ui.label("No pressure axis: only size applies");
+            }
+            let size_max = (sim.size_seen_max.max(sim.palm_size) * 3 / 2).max(10);
+            ui.add(egui::Slider::new(&mut sim.palm_size, 0..=size_max).text("palm size"))
+                .on_hover_text("touch_major; 0 disables, like the palm pressure");
+            let quirks = sim.quirks();
+            ui.label(egui::RichText::new(&quirks).monospace().small());
+            if ui.small_button("Copy quirk lines").clicked() {
+                ui.ctx().copy_text(quirks);
+            }
+        });
+}
+
+/// Collapsible pen-vs-touch panel: the pen's state, how many contacts were
+/// dropped while it was near and how many reports had touches anyway.
+/// Returns whether Reset was clicked.
+pub fn draw_pen_arbitration(ui: &mut egui::Ui, arbitration: &Arbitration, pen: &PenState) -> bool {
+    let mut reset = false;
+    egui::CollapsingHeader::new(format!("Pen ({} dropped)", arbitration.dropped_total))
+        .default_open(true)
+        .show(ui, |ui| {
+            let theme = Theme::current(ui.ctx());
+            let tool = if pen.eraser { "Eraser" } else { "Pen" };
+            match (pen.proximity, pen.touching) {
+                (false, _) => ui.colored_label(theme.text_secondary, "Pen out of range"),
+                (true, false) => ui.label(format!("{} hovering: touch suppressed", tool)),
+                (true, true) => ui.label(format!(
+                    "{} down, pressure {:.0}%",
+                    tool,
+                    pen.pressure * 100.0
+                )),
+            };
+            ui.monospace(format!(
+                "touches dropped for pen: {}",
+                arbitration.dropped_total
+            ));
+            if arbitration.leaked_reports > 0 {
+                ui.colored_label(
+                    ORANGE,
+                    format!(
+                        "{} reports had touches while the pen was near",
+                        arbitration.leaked_reports
+                    ),
+                );
+            }
+            reset = ui.small_button("Reset").clicked();
+        });
+    reset
+}
+
+/// Show the firmware's BTN_TOOL_* finger count next to the number of active
+/// MT slots, right-aligned at `origin`. A mismatch is drawn in red.
+pub fn draw_finger_count(painter: &Painter, origin: Pos2, tool_fingers: u8, active_slots: usize) {
+    let theme = Theme::current(painter.ctx());
+    // BTN_TOOL_QUINTTAP covers five or more fingers
+    let matches = if tool_fingers >= 5 {
+        active_slots >= 5
+    } else {
+        tool_fingers as usize == active_slots
+    };
+    let tool = if tool_fingers >= 5 {
+        "5+".to_string()
+    } else {
+        tool_fingers.to_string()
+    };
+    let color = if matches { theme.text_secondary } else { RED };
+    painter.text(
+        origin,
+        egui::Align2::RIGHT_TOP,
+        format!("BTN_TOOL: {}  slots: {}", tool, active_slots),
+        FontId::monospace(11.0),
+        color,
+    );
+}
+
+/// Tap-drag / drag-lock badge, or the last drag lock's hold time once it's
+/// released.
+pub fn draw_drag_badge(painter: &Painter, origin: Pos2, drag: &TapDrag) {
+    let theme = Theme::current(painter.ctx());
+    let (text, color) = match drag.state {
+        Some(state) => match state.label() {
+            Some(label) => (
+                label.to_string(),
+                if state.phase == DragPhase::Locked {
+                    ORANGE
+                } else {
+                    MAGENTA
+                },
+            ),
+            None => return,
+        },
+        None => match drag.last_lock_ms {
+            Some(ms) => (format!("last lock: {:.0} ms", ms), theme.text_secondary),
+            None => return,
+        },
+    };
+    painter.text(
+        origin,
+        egui::Align2::LEFT_TOP,
+        text,
+        FontId::monospace(14.0),
+        color,
+    );
+}
+
+const MM_GRID_STEP: f32 = 10.0;
+
+/// Draw a 10 mm grid over the touchpad area, labelled along the top and left edges.
+/// `resolution` is in device units per mm.
+pub fn draw_mm_grid(
+    painter: &Painter,
+    corner: Pos2,
+    scale: f32,
+    boundary_width: f32,
+    boundary_height: f32,
+    resolution: (f32, f32),
+) {
+    let theme = Theme::current(painter.ctx());
+    let stroke = Stroke::new(1.0, theme.guide);
+    let font = FontId::proportional(9.0);
+    let step_x = MM_GRID_STEP * resolution.0 * scale;
+    let step_y = MM_GRID_STEP * resolution.1 * scale;
+    // Too dense to be useful (tiny window or bogus resolution)
+    if step_x < 4.0 || step_y < 4.0 {
+        return;
+    }
+
+    let mut i = 1;
+    while (i as f32) * step_x < boundary_width {
+        let x = corner.x + i as f32 * step_x;
+        painter.line_segment(
+            [
+                Pos2::new(x, corner.y),
+                Pos2::new(x, corner.y + boundary_height),
+            ],
+            stroke,
+        );
+        painter.text(
+            Pos2::new(x + 2.0, corner.y + 2.0),
+            egui::Align2::LEFT_TOP,
+            format!("{}", i * MM_GRID_STEP as i32),
+            font.clone(),
+            theme.text_secondary,
+        );
+        i += 1;
+    }
+
+    let mut i = 1;
+    while (i as f32) * step_y < boundary_height {
+        let y = corner.y + i as f32 * step_y;
+        painter.line_segment(
+            [
+                Pos2::new(corner.x, y),
+                Pos2::new(corner.x + boundary_width, y),
+            ],
+            stroke,
+        );
+        painter.text(
+            Pos2::new(corner.x + 2.0, y + 2.0),
+            egui::Align2::LEFT_TOP,
+            format!("{}", i * MM_GRID_STEP as i32),
+            font.clone(),
+            theme.text_secondary,
+        );
+        i += 1;
+    }
+}
+
+/// Draw contact positions and pairwise distances in millimeters, starting at
+/// `origin`. With exactly two contacts a line is drawn between them.
+pub fn draw_mm_readout(
+    painter: &Painter,
+    origin: Pos2,
+    touches: &[TouchData],
+    corner: Pos2,
+    scale: f32,
+    resolution: (f32, f32),
+) {
+    let theme = Theme::current(painter.ctx());
+    let font = FontId::monospace(11.0);
+    let (rx, ry) = resolution;
+    let active: Vec<(usize, &TouchData)> =
+        touches.iter().enumerate().filter(|(_, t)| t.used).collect();
+
+    let mut lines = Vec::new();
+    for (slot, t) in &active {
+        lines.push(format!(
+            "{}: {:6.1}, {:6.1} mm",
+            slot,
+            t.position_x as f32 / rx,
+            t.position_y as f32 / ry
+        ));
+    }
+    for (a, (slot_a, ta)) in active.iter().enumerate() {
+        for (slot_b, tb) in &active[a + 1..] {
+            let dx = (tb.position_x - ta.position_x) as f32 / rx;
+            let dy = (tb.position_y - ta.position_y) as f32 / ry;
+            lines.push(format!(
+                "{}-{}: {:6.1} mm",
+                slot_a,
+                slot_b,
+                (dx * dx + dy * dy).sqrt()
+            ));
+        }
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        painter.text(
+            Pos2::new(origin.x, origin.y + i as f32 * 14.0),
+            egui::Align2::LEFT_TOP,
+            line,
+            font.clone(),
+            theme.text_secondary,
+        );
+    }
+
+    if let [(_, a), (_, b)] = active.as_slice() {
+        painter.line_segment(
+            [
+                touch_to_screen(a, corner, scale),
+                touch_to_screen(b, corner, scale),
+            ],
+            Stroke::new(1.0, theme.text_secondary),
+        );
+    }
+}
+
+fn touch_to_screen(touch: &TouchData, corner: Pos2, scale: f32) -> Pos2 {
+    Pos2::new(
+        corner.x + touch.position_x as f32 * scale,
+        corner.y + touch.position_y as f32 * scale,
+    )
+}
+
+// --- slot table ---
+
+fn tool_type_label(tool_type: i32) -> &'static str {
+    match tool_type {
+        0 => "finger",
+        1 => "pen",
+        MT_TOOL_PALM => "palm",
+        _ => "other",
+    }
+}
+
+const SLOT_TABLE_HEADER: [&str; 9] = [
+    "Slot", "ID", "X", "Y", "Pressure", "Major", "Minor", "Orient", "Tool",
+];
+
+fn slot_table_row(slot: usize, t: &TouchData) -> [String; 9] {
+    [
+        slot.to_string(),
+        t.tracking_id.to_string(),
+        t.position_x.to_string(),
+        t.position_y.to_string(),
+        t.pressure.to_string(),
+        t.touch_major.to_string(),
+        t.touch_minor.to_string(),
+        t.orientation.to_string(),
+        tool_type_label(t.tool_type).to_string(),
+    ]
+}
+
+/// Draw a table of raw values for every active slot, with a button that
+/// copies it as tab-separated text.
+pub fn draw_slot_table(ui: &mut egui::Ui, touches: &[TouchData]) {
+    let active: Vec<(usize, &TouchData)> =
+        touches.iter().enumerate().filter(|(_, t)| t.used).collect();
+    let rows: Vec<[String; 9]> = active
+        .iter()
+        .map(|(slot, t)| slot_table_row(*slot, t))
+        .collect();
+
+    ui.horizontal(|ui| {
+        ui.strong("Slots");
+        if ui.button("Copy").clicked() {
+            let mut text = SLOT_TABLE_HEADER.join("\t");
+            for row in &rows {
+                text.push('\n');
+                text.push_str(&row.join("\t"));
+            }
+            ui.ctx().copy_text(text);
+        }
+    });
+    ui.separator();
+
+    egui::Grid::new("slot_table")
+        .striped(true)
+        .num_columns(SLOT_TABLE_HEADER.len())
+        .show(ui, |ui| {
+            for label in SLOT_TABLE_HEADER {
+                ui.strong(label);
+            }
+            ui.end_row();
+            for (row, (slot, touch)) in rows.iter().zip(&active) {
+                let color = touch_color_for_slot(*slot, touch);
+                for (i, cell) in row.iter().enumerate() {
+                    if i == 0 {
+                        ui.colored_label(color, egui::RichText::new(cell).monospace());
+                    } else {
+                        ui.monospace(cell);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    if rows.is_empty() {
+        ui.label("No contacts");
+    }
+}
+
+// --- libinput visualization ---
+
+const CROSS_SIZE: f32 = 40.0;
+const ACCEL_COLOR: Color32 = MAGENTA;
+
+/// Draw a cross widget showing a 2D vector.
+/// `accel` is drawn as filled bars, `unaccel` as outline bars.
+/// `scale_factor` maps raw values to pixels.
+fn draw_cross(
+    painter: &Painter,
+    center: Pos2,
+    accel: (f32, f32),
+    unaccel: (f32, f32),
+    scale_factor: f32,
+    bar_width: f32,
+) {
+    let theme = Theme::current(painter.ctx());
+    let max = CROSS_SIZE;
+
+    // Draw faint cross lines for reference
+    let guide_color = theme.guide;
+    painter.line_segment(
+        [
+            Pos2::new(center.x - max, center.y),
+            Pos2::new(center.x + max, center.y),
+        ],
+        Stroke::new(1.0, guide_color),
+    );
+    painter.line_segment(
+        [
+            Pos2::new(center.x, center.y - max),
+            Pos2::new(center.x, center.y + max),
+        ],
+        Stroke::new(1.0, guide_color),
+    );
+
+    // Draw unaccelerated (outline) first, then accelerated (filled) on top
+    let pairs = [(unaccel, theme.unaccel, false), (accel, ACCEL_COLOR, true)];
+
+    for &((dx, dy), color, filled) in &pairs {
+        let sx = (dx * scale_factor).clamp(-max, max);
         let sy = (dy * scale_factor).clamp(-max, max);
 
         // Horizontal bar
@@ -232,11 +1619,15 @@ fn draw_cross(
     }
 
     // Center dot
-    painter.circle_filled(center, 2.0, Color32::BLACK);
+    painter.circle_filled(center, 2.0, theme.text);
 }
 
-/// Draw the full libinput visualization panel contents.
-pub fn draw_libinput_panel(ui: &mut egui::Ui, state: &LibinputState) {
+/// Draw the libinput panel: pointer, scroll and gesture widgets above a
+/// filterable, pausable event log. Returns true if the user toggled exporting
+/// the log to a file.
+pub fn draw_libinput_panel(ui: &mut egui::Ui, state: &mut LibinputState, exporting: bool) -> bool {
+    let mut toggle_export = false;
+    let theme = Theme::current(ui.ctx());
     let painter = ui.painter();
     let panel_rect = ui.available_rect_before_wrap();
     let panel_width = panel_rect.width();
@@ -251,7 +1642,7 @@ pub fn draw_libinput_panel(ui: &mut egui::Ui, state: &LibinputState) {
         egui::Align2::CENTER_TOP,
         "Pointer Motion",
         section_font.clone(),
-        Color32::BLACK,
+        theme.text,
     );
     y += 18.0;
 
@@ -277,12 +1668,12 @@ pub fn draw_libinput_panel(ui: &mut egui::Ui, state: &LibinputState) {
         egui::Align2::LEFT_TOP,
         "accel",
         label_font.clone(),
-        Color32::DARK_GRAY,
+        theme.text_secondary,
     );
     painter.rect_stroke(
         Rect::from_min_size(Pos2::new(cx + 10.0, y), Vec2::new(10.0, 10.0)),
         0.0,
-        Stroke::new(1.0, UNACCEL_COLOR),
+        Stroke::new(1.0, theme.unaccel),
         StrokeKind::Outside,
     );
     painter.text(
@@ -290,7 +1681,7 @@ pub fn draw_libinput_panel(ui: &mut egui::Ui, state: &LibinputState) {
         egui::Align2::LEFT_TOP,
         "raw",
         label_font.clone(),
-        Color32::DARK_GRAY,
+        theme.text_secondary,
     );
     y += 24.0;
 
@@ -300,7 +1691,7 @@ pub fn draw_libinput_panel(ui: &mut egui::Ui, state: &LibinputState) {
         egui::Align2::CENTER_TOP,
         "Buttons",
         section_font.clone(),
-        Color32::BLACK,
+        theme.text,
     );
     y += 18.0;
 
@@ -316,141 +1707,1874 @@ pub fn draw_libinput_panel(ui: &mut egui::Ui, state: &LibinputState) {
         let btn_font = FontId::monospace(16.0);
         let tap_font = FontId::proportional(9.0);
 
-        for (i, (label, tap_label, intensity)) in labels.iter().enumerate() {
-            let x = start_x + i as f32 * spacing;
-            let color = if *intensity > 0.1 {
-                fade(MAGENTA, intensity.clamp(0.0, 1.0))
+        for (i, (label, tap_label, intensity)) in labels.iter().enumerate() {
+            let x = start_x + i as f32 * spacing;
+            let color = if *intensity > 0.1 {
+                fade(MAGENTA, intensity.clamp(0.0, 1.0))
+            } else {
+                theme.inactive
+            };
+            painter.text(
+                Pos2::new(x, y),
+                egui::Align2::CENTER_TOP,
+                *label,
+                btn_font.clone(),
+                color,
+            );
+            painter.text(
+                Pos2::new(x, y + 18.0),
+                egui::Align2::CENTER_TOP,
+                *tap_label,
+                tap_font.clone(),
+                theme.text_secondary,
+            );
+        }
+    }
+    y += 38.0;
+
+    // --- Scroll ---
+    painter.text(
+        Pos2::new(cx, y),
+        egui::Align2::CENTER_TOP,
+        format!(
+            "Scroll{}",
+            if state.scroll_source.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", state.scroll_source)
+            }
+        ),
+        section_font.clone(),
+        theme.text,
+    );
+    y += 18.0;
+
+    let scroll_center = Pos2::new(cx, y + CROSS_SIZE);
+    draw_cross(
+        painter,
+        scroll_center,
+        (state.scroll_horiz, state.scroll_vert),
+        (0.0, 0.0), // no unaccel for scroll
+        3.0,
+        6.0,
+    );
+    y += CROSS_SIZE * 2.0 + 16.0;
+
+    // --- Gesture ---
+    let gesture_label = match state.gesture.kind {
+        GestureKind::Swipe => format!("Swipe ({}f)", state.gesture.fingers),
+        GestureKind::Pinch => format!("Pinch ({}f)", state.gesture.fingers),
+        GestureKind::Hold => format!("Hold ({}f)", state.gesture.fingers),
+        GestureKind::None => "Gesture".to_string(),
+    };
+    painter.text(
+        Pos2::new(cx, y),
+        egui::Align2::CENTER_TOP,
+        gesture_label,
+        section_font,
+        theme.text,
+    );
+    y += 18.0;
+
+    if state.gesture.active {
+        let gesture_center = Pos2::new(cx, y + CROSS_SIZE);
+
+        // Translation cross
+        draw_cross(
+            painter,
+            gesture_center,
+            (state.gesture.dx, state.gesture.dy),
+            (state.gesture.dx_unaccel, state.gesture.dy_unaccel),
+            4.0,
+            6.0,
+        );
+
+        if state.gesture.kind == GestureKind::Pinch {
+            // Scale ring: radius proportional to scale factor
+            let base_radius = 20.0;
+            let ring_radius = base_radius * state.gesture.scale;
+            painter.circle_stroke(
+                gesture_center,
+                ring_radius.clamp(4.0, CROSS_SIZE * 1.5),
+                Stroke::new(2.0, TEAL),
+            );
+
+            // Rotation indicator: a line from center at the angle
+            if state.gesture.angle.abs() > 0.1 {
+                let angle_rad = state.gesture.angle.to_radians();
+                let line_len = 30.0;
+                let end = Pos2::new(
+                    gesture_center.x + angle_rad.sin() * line_len,
+                    gesture_center.y - angle_rad.cos() * line_len,
+                );
+                painter.line_segment([gesture_center, end], Stroke::new(2.0, ORANGE));
+            }
+        }
+
+        y += CROSS_SIZE * 2.0 + 16.0;
+    } else {
+        // Show inactive placeholder
+        let gesture_center = Pos2::new(cx, y + CROSS_SIZE);
+        draw_cross(painter, gesture_center, (0.0, 0.0), (0.0, 0.0), 1.0, 6.0);
+        y += CROSS_SIZE * 2.0 + 16.0;
+    }
+
+    // --- Switches (only once a lid or tablet-mode switch has reported) ---
+    if state.lid_closed.is_some() || state.tablet_mode.is_some() {
+        let mut parts = Vec::new();
+        if let Some(closed) = state.lid_closed {
+            parts.push(format!("Lid {}", if closed { "closed" } else { "open" }));
+        }
+        if let Some(on) = state.tablet_mode {
+            parts.push(format!("Tablet mode {}", if on { "on" } else { "off" }));
+        }
+        let suppressing = state.lid_closed == Some(true) || state.tablet_mode == Some(true);
+        painter.text(
+            Pos2::new(cx, y),
+            egui::Align2::CENTER_TOP,
+            parts.join("  "),
+            label_font.clone(),
+            if suppressing { ORANGE } else { theme.text },
+        );
+        y += 18.0;
+    }
+
+    // --- Small text log at bottom ---
+    let log_top = y;
+    let log_rect = Rect::from_min_max(Pos2::new(panel_rect.min.x + 4.0, log_top), panel_rect.max);
+
+    ui.allocate_rect(
+        Rect::from_min_max(panel_rect.min, Pos2::new(panel_rect.max.x, log_top)),
+        egui::Sense::hover(),
+    );
+
+    ui.allocate_new_ui(egui::UiBuilder::new().max_rect(log_rect), |ui| {
+        ui.horizontal_wrapped(|ui| {
+            let filter = &mut state.log_filter;
+            ui.checkbox(&mut filter.motion, "motion");
+            ui.checkbox(&mut filter.button, "button");
+            ui.checkbox(&mut filter.scroll, "scroll");
+            ui.checkbox(&mut filter.gesture, "gesture");
+        });
+        ui.horizontal(|ui| {
+            let paused = state.log_paused();
+            let label = if paused {
+                format!("Resume ({})", state.log_pending())
+            } else {
+                "Pause".to_string()
+            };
+            if ui.small_button(label).clicked() {
+                state.set_log_paused(!paused);
+            }
+            let export_label = if exporting { "Stop export" } else { "Export" };
+            toggle_export = ui
+                .small_button(export_label)
+                .on_hover_text("Write every received event to a file in the working directory")
+                .clicked();
+            ui.add(
+                egui::TextEdit::singleline(&mut state.log_filter.search)
+                    .hint_text("Search")
+                    .desired_width(f32::INFINITY),
+            );
+        });
+
+        let font = FontId::monospace(9.0);
+        let row_height = ui.fonts(|f| f.row_height(&font));
+        let lines: Vec<&str> = state
+            .log_lines
+            .iter()
+            .filter(|e| state.log_filter.matches(e))
+            .map(|e| e.text.as_str())
+            .collect();
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, lines.len(), |ui, range| {
+                for line in &lines[range] {
+                    ui.label(
+                        egui::RichText::new(*line)
+                            .font(font.clone())
+                            .color(theme.log_text),
+                    );
+                }
+            });
+    });
+    toggle_export
+}
+
+/// Collapsible controls for the libinput device configuration. Returns the
+/// change the user made, if any.
+pub fn draw_libinput_config(ui: &mut egui::Ui, config: &LibinputConfig) -> Option<LibinputCommand> {
+    let mut command = None;
+    egui::CollapsingHeader::new("libinput config")
+        .default_open(false)
+        .show(ui, |ui| {
+            ui.label(
+                egui::RichText::new("Applies to tapview's libinput context only, not the desktop")
+                    .small()
+                    .color(Theme::current(ui.ctx()).text_secondary),
+            );
+            type Toggle = (&'static str, Option<bool>, fn(bool) -> LibinputCommand);
+            let toggles: [Toggle; 4] = [
+                ("Tap to click", config.tap, LibinputCommand::Tap),
+                ("Tap and drag", config.tap_drag, LibinputCommand::TapDrag),
+                (
+                    "Natural scrolling",
+                    config.natural_scroll,
+                    LibinputCommand::NaturalScroll,
+                ),
+                ("Disable while typing", config.dwt, LibinputCommand::Dwt),
+            ];
+            for (label, value, make) in toggles {
+                if let Some(mut on) = value {
+                    if ui.checkbox(&mut on, label).changed() {
+                        command = Some(make(on));
+                    }
+                }
+            }
+
+            if let Some(current) = config.accel_profile {
+                ui.horizontal(|ui| {
+                    ui.label("Accel");
+                    for (profile, label) in [
+                        (AccelProfile::Adaptive, "Adaptive"),
+                        (AccelProfile::Flat, "Flat"),
+                    ] {
+                        if config.accel_profiles.contains(&profile)
+                            && ui.radio(current == profile, label).clicked()
+                            && current != profile
+                        {
+                            command = Some(LibinputCommand::AccelProfile(profile));
+                        }
+                    }
+                });
+            }
+
+            if let Some(current) = config.click_method {
+                ui.horizontal(|ui| {
+                    ui.label("Click");
+                    for (method, label) in [
+                        (ClickMethod::ButtonAreas, "Areas"),
+                        (ClickMethod::Clickfinger, "Clickfinger"),
+                    ] {
+                        if config.click_methods.contains(&method)
+                            && ui.radio(current == method, label).clicked()
+                            && current != method
+                        {
+                            command = Some(LibinputCommand::ClickMethod(method));
+                        }
+                    }
+                });
+            }
+        });
+    command
+}
+
+#[cfg(not(target_os = "windows"))]
+const DEVICE_INFO_TITLE: &str = "libinput device";
+#[cfg(target_os = "windows")]
+const DEVICE_INFO_TITLE: &str = "Touchpad settings";
+
+/// Collapsible capability/config report for the libinput device (the
+/// Precision Touchpad settings on Windows), with a button that copies it as
+/// text.
+pub fn draw_libinput_device_info(ui: &mut egui::Ui, info: &LibinputDeviceInfo) {
+    egui::CollapsingHeader::new(DEVICE_INFO_TITLE)
+        .default_open(false)
+        .show(ui, |ui| {
+            if ui.small_button("Copy").clicked() {
+                let text: Vec<String> = info
+                    .rows
+                    .iter()
+                    .map(|(label, value)| format!("{:<18}{}", format!("{}:", label), value))
+                    .collect();
+                ui.ctx().copy_text(text.join("\n"));
+            }
+            egui::Grid::new("libinput_device_info")
+                .striped(true)
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (label, value) in &info.rows {
+                        ui.label(egui::RichText::new(*label).small());
+                        ui.monospace(value);
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+const GESTURE_ROWS: usize = 20;
+
+fn gesture_kind_label(kind: &GestureKind) -> &'static str {
+    match kind {
+        GestureKind::Swipe => "swipe",
+        GestureKind::Pinch => "pinch",
+        GestureKind::Hold => "hold",
+        GestureKind::None => "-",
+    }
+}
+
+/// Collapsible table of the most recent completed gestures, newest first,
+/// under per-kind count and duration stats. Cancelled gestures are orange.
+pub fn draw_gesture_history(ui: &mut egui::Ui, state: &LibinputState, title: &str) {
+    egui::CollapsingHeader::new(format!("{} ({})", title, state.gesture_history.len()))
+        .id_salt(title)
+        .default_open(false)
+        .show(ui, |ui| {
+            let summary = state.gesture_summary();
+            if summary.is_empty() {
+                ui.label("No gestures yet");
+                return;
+            }
+            for s in &summary {
+                ui.monospace(format!(
+                    "{:<5} ×{:<3} median {:.0} ms  max {:.0} ms{}",
+                    gesture_kind_label(&s.kind),
+                    s.count,
+                    s.median_ms,
+                    s.max_ms,
+                    if s.cancelled > 0 {
+                        format!("  {} cancelled", s.cancelled)
+                    } else {
+                        String::new()
+                    }
+                ));
+            }
+            ui.separator();
+            egui::Grid::new((title, "history"))
+                .striped(true)
+                .num_columns(6)
+                .show(ui, |ui| {
+                    for label in ["kind", "f", "ms", "dx/dy", "scale", "angle"] {
+                        ui.strong(label);
+                    }
+                    ui.end_row();
+                    for r in state.gesture_history.iter().rev().take(GESTURE_ROWS) {
+                        let kind = egui::RichText::new(gesture_kind_label(&r.kind)).monospace();
+                        if r.cancelled {
+                            ui.label(kind.color(ORANGE));
+                        } else {
+                            ui.label(kind);
+                        }
+                        ui.monospace(r.fingers.to_string());
+                        ui.monospace(format!("{:.0}", r.duration_ms));
+                        ui.monospace(format!("{:.0}/{:.0}", r.dx, r.dy));
+                        if r.kind == GestureKind::Pinch {
+                            ui.monospace(format!("{:.2}", r.scale));
+                            ui.monospace(format!("{:.0}°", r.angle));
+                        } else {
+                            ui.monospace("-");
+                            ui.monospace("-");
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+/// Collapsible raw vs interpreted trajectory plot for the latest stroke.
+/// Each path is scaled on its own to fill the plot around the shared start
+/// point, so only the shapes are compared.
+pub fn draw_trajectory(ui: &mut egui::Ui, traj: &Trajectory) {
+    egui::CollapsingHeader::new("Trajectory")
+        .default_open(false)
+        .show(ui, |ui| {
+            let theme = Theme::current(ui.ctx());
+            let (rect, _) = ui
+                .allocate_exact_size(Vec2::new(ui.available_width(), 150.0), egui::Sense::hover());
+            let painter = ui.painter();
+            painter.rect_stroke(rect, 0.0, Stroke::new(1.0, theme.guide), StrokeKind::Inside);
+            let origin = rect.center();
+            let half = rect.size() / 2.0 - Vec2::splat(4.0);
+
+            let mut scales = Vec::new();
+            for (path, color) in [(&traj.raw, TEAL), (&traj.interpreted, ACCEL_COLOR)] {
+                let reach = path.iter().fold(0.0f32, |m, &(x, y)| {
+                    m.max(x.abs() / half.x).max(y.abs() / half.y)
+                });
+                if path.len() < 2 || reach == 0.0 {
+                    scales.push(None);
+                    continue;
+                }
+                let points: Vec<Pos2> = path
+                    .iter()
+                    .map(|&(x, y)| origin + Vec2::new(x, y) / reach)
+                    .collect();
+                painter.add(egui::Shape::line(points, Stroke::new(1.5, color)));
+                scales.push(Some(reach));
+            }
+            painter.circle_filled(origin, 2.5, theme.text);
+
+            let describe = |scale: Option<f32>, unit: &str| match scale {
+                Some(reach) => format!("{:.1} {}/px", reach, unit),
+                None => "no motion".to_string(),
+            };
+            ui.horizontal_wrapped(|ui| {
+                ui.colored_label(TEAL, "■ raw");
+                ui.small(describe(scales[0], traj.raw_unit()));
+                ui.colored_label(ACCEL_COLOR, "■ libinput");
+                ui.small(describe(scales[1], "px"));
+            });
+        });
+}
+
+/// What was clicked in the register watch window.
+pub enum RegisterWatchAction {
+    Add(Register),
+    Remove(Register),
+}
+
+/// Contents of the register watch window: a field to add `bank:addr`
+/// registers and the watched ones with their last value, in orange if it
+/// changed since the poll before.
+pub fn draw_register_watch(
+    ui: &mut egui::Ui,
+    watched: &[WatchedRegister],
+    error: Option<&str>,
+    input: &mut String,
+) -> Option<RegisterWatchAction> {
+    let mut action = None;
+    let parsed = input.parse::<Register>();
+    ui.horizontal(|ui| {
+        let field = ui.add(
+            egui::TextEdit::singleline(input)
+                .hint_text("0:78 or U0:6E")
+                .desired_width(100.0),
+        );
+        let entered = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if (ui
+            .add_enabled(parsed.is_ok(), egui::Button::new("Watch"))
+            .clicked()
+            || entered)
+            && parsed.is_ok()
+        {
+            action = parsed.clone().ok().map(RegisterWatchAction::Add);
+        }
+    });
+    if let (Err(e), false) = (&parsed, input.trim().is_empty()) {
+        ui.small(e);
+    }
+    if let Some(e) = error {
+        ui.colored_label(RED, e);
+    }
+    if watched.is_empty() {
+        ui.label("No registers watched");
+        return action;
+    }
+    egui::Grid::new("register_watch")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.small("Register");
+            ui.small("Hex");
+            ui.small("Dec");
+            ui.label("");
+            ui.end_row();
+            for w in watched {
+                ui.monospace(w.register.to_string());
+                let (hex, dec) = match w.value {
+                    Some(v) => (format!("{:02X}", v), v.to_string()),
+                    None => ("--".into(), "--".into()),
+                };
+                if w.changed {
+                    ui.colored_label(ORANGE, egui::RichText::new(hex).monospace());
+                    ui.colored_label(ORANGE, egui::RichText::new(dec).monospace());
+                } else {
+                    ui.monospace(hex);
+                    ui.monospace(dec);
+                }
+                if ui
+                    .small_button("✖")
+                    .on_hover_text("Stop watching")
+                    .clicked()
+                {
+                    action = Some(RegisterWatchAction::Remove(w.register));
+                }
+                ui.end_row();
+            }
+        });
+    ui.small(format!(
+        "Polled every {} s",
+        RegisterWatch::INTERVAL.as_secs()
+    ));
+    action
+}
+
+/// What was clicked in the register dump section.
+pub enum RegisterDumpAction {
+    Dump,
+    Refresh,
+    /// Another dump was picked to compare
+    Compare,
+}
+
+/// Register dump section of the register window: a button to dump every
+/// readable register (with progress while that runs), two saved dumps to
+/// pick and the registers that differ between them.
+pub fn draw_register_dumps(
+    ui: &mut egui::Ui,
+    dumps: &mut DumpComparison,
+    progress: Option<f32>,
+) -> Option<RegisterDumpAction> {
+    let mut action = None;
+    ui.horizontal(|ui| match progress {
+        Some(done) => {
+            ui.add(
+                egui::ProgressBar::new(done)
+                    .desired_width(160.0)
+                    .show_percentage(),
+            );
+        }
+        None => {
+            if ui
+                .button("Dump all registers")
+                .on_hover_text("Read every bank and address into a timestamped file")
+                .clicked()
+            {
+                action = Some(RegisterDumpAction::Dump);
+            }
+            if ui
+                .small_button("⟳")
+                .on_hover_text("List dump files again")
+                .clicked()
+            {
+                action = Some(RegisterDumpAction::Refresh);
+            }
+        }
+    });
+    if dumps.files.is_empty() {
+        ui.label("No dumps in the working directory");
+        return action;
+    }
+    let name = |path: &Option<std::path::PathBuf>| {
+        path.as_ref()
+            .and_then(|p| p.file_name())
+            .map_or("–".into(), |n| n.to_string_lossy().into_owned())
+    };
+    let DumpComparison {
+        files,
+        before,
+        after,
+        diff,
+    } = dumps;
+    for (label, picked) in [("Before", before), ("After", after)] {
+        egui::ComboBox::from_label(label)
+            .selected_text(name(picked))
+            .width(220.0)
+            .show_ui(ui, |ui| {
+                for file in files.iter() {
+                    let text = name(&Some(file.clone()));
+                    if ui
+                        .selectable_label(picked.as_ref() == Some(file), text)
+                        .clicked()
+                    {
+                        *picked = Some(file.clone());
+                        action = Some(RegisterDumpAction::Compare);
+                    }
+                }
+            });
+    }
+    match diff {
+        Some(Ok(changes)) if changes.is_empty() => {
+            ui.label("No differences");
+        }
+        Some(Ok(changes)) => {
+            ui.label(format!("{} registers differ", changes.len()));
+            let value = |v: Option<u8>| v.map_or("--".into(), |v| format!("{:02X}", v));
+            egui::ScrollArea::vertical()
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("register_diff")
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.small("Register");
+                            ui.small("Before");
+                            ui.small("After");
+                            ui.end_row();
+                            for change in changes.iter() {
+                                ui.monospace(change.register.to_string());
+                                ui.monospace(value(change.before));
+                                ui.colored_label(
+                                    ORANGE,
+                                    egui::RichText::new(value(change.after)).monospace(),
+                                );
+                                ui.end_row();
+                            }
+                        });
+                });
+        }
+        Some(Err(e)) => {
+            ui.colored_label(RED, e.as_str());
+        }
+        None => {}
+    }
+    action
+}
+
+/// Contents of the ALC A/B capture window: the phase length before starting,
+/// a countdown per phase while ALC is switched and recorded, then the
+/// comparison.
+pub fn draw_ab_test(ui: &mut egui::Ui, test: &mut AbTest, heatmap: bool) -> Option<AbTestAction> {
+    let mut action = None;
+    if !heatmap && !matches!(test, AbTest::Done { .. }) {
+        ui.colored_label(ORANGE, "No heatmap; start with --heatmap.");
+    }
+    match test {
+        AbTest::Setup { seconds } => {
+            ui.label("Leave the touchpad untouched. ALC is switched on, then off,");
+            ui.label("with --alc-on and --alc-off, and back on at the end.");
+            ui.horizontal(|ui| {
+                ui.label("Record");
+                ui.add(egui::DragValue::new(seconds).range(1..=600).suffix(" s"));
+                ui.label("per phase");
+            });
+            if ui
+                .add_enabled(heatmap, egui::Button::new("Start"))
+                .clicked()
+            {
+                action = Some(AbTestAction::Start);
+            }
+        }
+        AbTest::Switching { a, .. } => {
+            let to = if a.is_some() { "off" } else { "on" };
+            ui.label(format!("Switching ALC {}…", to));
+        }
+        AbTest::RecordingA { .. } | AbTest::RecordingB { .. } => {
+            let phase = if matches!(test, AbTest::RecordingA { .. }) {
+                "ALC on"
+            } else {
+                "ALC off"
+            };
+            let left = test.remaining(std::time::Instant::now()).unwrap_or(0.0);
+            ui.label(format!(
+                "Recording with {}, don't touch… {:.1} s",
+                phase, left
+            ));
+        }
+        AbTest::Failed(reason) => {
+            ui.colored_label(ORANGE, reason.as_str());
+            if ui.button("Run again").clicked() {
+                action = Some(AbTestAction::Restart);
+            }
+        }
+        AbTest::Done { a, b } => {
+            let report = heatmap_ab::report(a, b);
+            ui.monospace(&report);
+            ui.small("Noise: per-cell std dev, averaged. Drift: slope of the frame mean.");
+            ui.horizontal(|ui| {
+                if ui.button("Copy").clicked() {
+                    ui.ctx().copy_text(report);
+                }
+                if ui.button("Save").clicked() {
+                    action = Some(AbTestAction::Save);
+                }
+                if ui.button("Run again").clicked() {
+                    action = Some(AbTestAction::Restart);
+                }
+            });
+        }
+    }
+    action
+}
+
+/// Contents of the forced recalibration window: a confirmation first, then
+/// progress through the captures, then the before/after numbers and the
+/// difference as a diverging heatmap.
+pub fn draw_recalibration(
+    ui: &mut egui::Ui,
+    recalibration: &Recalibration,
+    texture: &mut HeatmapTexture,
+    heatmap: bool,
+) -> Option<RecalibrationAction> {
+    let mut action = None;
+    match recalibration {
+        Recalibration::Confirming => {
+            ui.label("Write the --recalibrate registers to the controller?");
+            ui.label("The firmware drops its baseline and takes a new one from");
+            ui.label("whatever is on the touchpad, so keep it untouched.");
+            if !heatmap {
+                ui.colored_label(ORANGE, "No heatmap; start with --heatmap.");
+            }
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(heatmap, egui::Button::new("Recalibrate"))
+                    .clicked()
+                {
+                    action = Some(RecalibrationAction::Confirm);
+                }
+                if ui.button("Cancel").clicked() {
+                    action = Some(RecalibrationAction::Cancel);
+                }
+            });
+        }
+        Recalibration::Before(_) => {
+            ui.label("Capturing the baseline before, don't touch…");
+        }
+        Recalibration::Writing { .. } => {
+            ui.label("Recalibrating…");
+        }
+        Recalibration::After { .. } => {
+            ui.label("Capturing the new baseline, don't touch…");
+        }
+        Recalibration::Failed(reason) => {
+            ui.colored_label(ORANGE, reason.as_str());
+        }
+        Recalibration::Done(comparison) => {
+            ui.label(format!("Mean shift: {:+.2}", comparison.mean_shift()));
+            if let Some((row, col, change)) = comparison.largest_change() {
+                ui.label(format!(
+                    "Largest change: {:+} at row {}, column {}",
+                    change, row, col
+                ));
+            }
+            let diff = &comparison.diff;
+            if diff.rows > 0 && diff.cols > 0 {
+                let cell = (240.0 / diff.cols.max(diff.rows) as f32).min(16.0);
+                let (rect, _) = ui.allocate_exact_size(
+                    Vec2::new(cell * diff.cols as f32, cell * diff.rows as f32),
+                    egui::Sense::hover(),
+                );
+                let style = HeatmapStyle {
+                    colormap: Colormap::Diverging,
+                    scale: HeatmapScale::default(),
+                    smooth: false,
+                };
+                let texture = texture.update(ui.ctx(), diff, style);
+                ui.painter().image(
+                    texture.id(),
+                    rect,
+                    Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                    Color32::WHITE,
+                );
+                ui.small("After less before: blue fell, red rose.");
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Copy").clicked() {
+                    ui.ctx().copy_text(comparison.to_text());
+                }
+                if ui.button("Save").clicked() {
+                    action = Some(RecalibrationAction::Save);
+                }
+            });
+        }
+    }
+    action
+}
+
+/// Contents of the jitter test window: instructions while running, the
+/// per-axis numbers and a scatter plot of positions around the mean once done.
+pub fn draw_jitter_test(
+    ui: &mut egui::Ui,
+    test: &JitterTest,
+    resolution: Option<(f32, f32)>,
+) -> Option<JitterTestAction> {
+    let mut action = None;
+    match test {
+        JitterTest::Waiting => {
+            ui.label("Place one finger on the touchpad and hold it still.");
+        }
+        JitterTest::Running { .. } => {
+            let left = test.remaining(std::time::Instant::now()).unwrap_or(0.0);
+            ui.label(format!("Hold still… {:.1} s", left));
+        }
+        JitterTest::Failed(reason) => {
+            ui.colored_label(ORANGE, *reason);
+            if ui.button("Run again").clicked() {
+                action = Some(JitterTestAction::Restart);
+            }
+        }
+        JitterTest::Done(result) => {
+            let (rx, ry) = resolution.unwrap_or((1.0, 1.0));
+            let unit = if resolution.is_some() { "mm" } else { "units" };
+            egui::Grid::new("jitter_result")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.small("X");
+                    ui.small("Y");
+                    ui.end_row();
+                    ui.label(format!("Std dev ({})", unit));
+                    ui.monospace(format!("{:.3}", result.std_dev.0 / rx));
+                    ui.monospace(format!("{:.3}", result.std_dev.1 / ry));
+                    ui.end_row();
+                    ui.label(format!("Peak-to-peak ({})", unit));
+                    ui.monospace(format!("{:.3}", result.peak_to_peak.0 as f32 / rx));
+                    ui.monospace(format!("{:.3}", result.peak_to_peak.1 as f32 / ry));
+                    ui.end_row();
+                    ui.label("Std dev (units)");
+                    ui.monospace(format!("{:.2}", result.std_dev.0));
+                    ui.monospace(format!("{:.2}", result.std_dev.1));
+                    ui.end_row();
+                });
+            ui.small(format!("{} reports", result.samples.len()));
+
+            // Scatter around the mean, same scale on both axes
+            let theme = Theme::current(ui.ctx());
+            let (rect, _) = ui.allocate_exact_size(Vec2::splat(200.0), egui::Sense::hover());
+            let painter = ui.painter();
+            painter.rect_stroke(rect, 0.0, Stroke::new(1.0, theme.guide), StrokeKind::Inside);
+            let center = rect.center();
+            painter.line_segment(
+                [
+                    Pos2::new(rect.min.x, center.y),
+                    Pos2::new(rect.max.x, center.y),
+                ],
+                Stroke::new(1.0, theme.guide),
+            );
+            painter.line_segment(
+                [
+                    Pos2::new(center.x, rect.min.y),
+                    Pos2::new(center.x, rect.max.y),
+                ],
+                Stroke::new(1.0, theme.guide),
+            );
+            let offsets: Vec<Vec2> = result
+                .samples
+                .iter()
+                .map(|&(x, y)| {
+                    Vec2::new(
+                        (x as f32 - result.mean.0) / rx,
+                        (y as f32 - result.mean.1) / ry,
+                    )
+                })
+                .collect();
+            let reach = offsets
+                .iter()
+                .fold(0.0f32, |m, d| m.max(d.x.abs()).max(d.y.abs()))
+                .max(f32::EPSILON);
+            let px_per = (rect.width() / 2.0 - 6.0) / reach;
+            for d in &offsets {
+                painter.circle_filled(center + *d * px_per, 1.5, TEAL);
+            }
+            ui.small(format!("±{:.3} {}", reach, unit));
+
+            ui.horizontal(|ui| {
+                if ui.button("Save JSON").clicked() {
+                    action = Some(JitterTestAction::Save);
+                }
+                if ui.button("Run again").clicked() {
+                    action = Some(JitterTestAction::Restart);
+                }
+            });
+        }
+    }
+    action
+}
+
+/// Contents of the linearity test window: the next swipe to make, then a
+/// row of scores and a deviation profile per finished swipe (edge points in
+/// orange, deviation exaggerated to fill the plot). Returns true if Run again
+/// was clicked.
+pub fn draw_linearity_test(ui: &mut egui::Ui, test: &LinearityTest, unit: &str) -> bool {
+    let theme = Theme::current(ui.ctx());
+    match test.current() {
+        Some(direction) => {
+            ui.label(format!(
+                "{}/{}: {}",
+                test.results.len() + 1,
+                linearity::DIRECTIONS.len(),
+                direction.prompt()
+            ));
+        }
+        None => {
+            ui.label("All swipes done");
+        }
+    }
+    if let Some(reason) = test.rejected {
+        ui.colored_label(ORANGE, reason);
+    }
+
+    let describe = |v: Option<f32>| v.map_or("-".to_string(), |v| format!("{:.3}", v));
+    if !test.results.is_empty() {
+        egui::Grid::new("linearity_results")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+                ui.small(format!("max ({})", unit));
+                ui.small("RMS");
+                ui.small("edge max");
+                ui.small("center max");
+                ui.end_row();
+                for fit in &test.results {
+                    ui.label(fit.direction.label());
+                    ui.monospace(format!("{:.3}", fit.max_deviation));
+                    ui.monospace(format!("{:.3}", fit.rms_deviation));
+                    let edge_worse = fit
+                        .max_edge
+                        .zip(fit.max_center)
+                        .is_some_and(|(edge, center)| edge > center);
+                    let edge_color = if edge_worse {
+                        ORANGE
+                    } else {
+                        ui.visuals().text_color()
+                    };
+                    ui.label(
+                        egui::RichText::new(describe(fit.max_edge))
+                            .monospace()
+                            .color(edge_color),
+                    );
+                    ui.monospace(describe(fit.max_center));
+                    ui.end_row();
+                }
+            });
+    }
+
+    for fit in &test.results {
+        let (rect, _) =
+            ui.allocate_exact_size(Vec2::new(ui.available_width(), 40.0), egui::Sense::hover());
+        let painter = ui.painter();
+        painter.line_segment(
+            [rect.left_center(), rect.right_center()],
+            Stroke::new(1.0, theme.guide),
+        );
+        let (min, max) = fit
+            .profile
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(lo, hi), p| {
+                (lo.min(p.0), hi.max(p.0))
+            });
+        let span = (max - min).max(f32::EPSILON);
+        let reach = fit.max_deviation.max(f32::EPSILON);
+        for &(along, deviation, edge) in &fit.profile {
+            let pos = Pos2::new(
+                rect.min.x + (along - min) / span * rect.width(),
+                rect.center().y - deviation / reach * (rect.height() / 2.0 - 2.0),
+            );
+            painter.circle_filled(pos, 1.5, if edge { ORANGE } else { TEAL });
+        }
+        painter.text(
+            rect.left_top(),
+            egui::Align2::LEFT_TOP,
+            fit.direction.label(),
+            FontId::proportional(10.0),
+            theme.text_secondary,
+        );
+    }
+
+    ui.button("Run again").clicked()
+}
+
+/// Contents of the pressure test window: pressure (magenta) and touch_major
+/// (teal) over time, each scaled to its own peak, with a vertical line where
+/// BTN_TOUCH or the button changed. Returns true if Run again was clicked.
+pub fn draw_pressure_test(ui: &mut egui::Ui, test: &PressureTest) -> bool {
+    let samples = match test {
+        PressureTest::Waiting => {
+            ui.label(
+                "Press one finger down slowly until the touchpad clicks, then release slowly.",
+            );
+            return false;
+        }
+        PressureTest::Recording { samples, .. } => {
+            ui.label(format!(
+                "Recording… {} reports (lift to finish)",
+                samples.len()
+            ));
+            return false;
+        }
+        PressureTest::Failed(reason) => {
+            ui.colored_label(ORANGE, *reason);
+            return ui.button("Run again").clicked();
+        }
+        PressureTest::Done(samples) => samples,
+    };
+
+    let theme = Theme::current(ui.ctx());
+    let duration = samples.last().map_or(1.0, |s| s.t_ms).max(1.0);
+    let peak_pressure = samples.iter().map(|s| s.pressure).max().unwrap_or(0).max(1) as f32;
+    let peak_major = samples
+        .iter()
+        .map(|s| s.touch_major)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+    let (rect, _) =
+        ui.allocate_exact_size(Vec2::new(ui.available_width(), 160.0), egui::Sense::hover());
+    let painter = ui.painter();
+    painter.rect_stroke(rect, 0.0, Stroke::new(1.0, theme.guide), StrokeKind::Inside);
+    let point = |t: f32, v: f32| {
+        Pos2::new(
+            rect.min.x + t / duration * rect.width(),
+            rect.max.y - v * (rect.height() - 4.0),
+        )
+    };
+    let markers = pressure_test::markers(samples);
+    for &(marker, t, _) in &markers {
+        let x = point(t, 0.0).x;
+        let color = match marker {
+            Marker::TouchDown | Marker::TouchUp => theme.text_secondary,
+            Marker::ClickDown | Marker::ClickUp => ORANGE,
+        };
+        painter.line_segment(
+            [Pos2::new(x, rect.min.y), Pos2::new(x, rect.max.y)],
+            Stroke::new(1.0, color),
+        );
+    }
+    let plot = |value: fn(&PressureSample) -> i32, peak: f32, color: Color32| {
+        let line: Vec<Pos2> = samples
+            .iter()
+            .map(|s| point(s.t_ms, value(s) as f32 / peak))
+            .collect();
+        painter.add(egui::Shape::line(line, Stroke::new(1.5, color)));
+    };
+    plot(|s| s.pressure, peak_pressure, MAGENTA);
+    plot(|s| s.touch_major, peak_major, TEAL);
+
+    ui.horizontal_wrapped(|ui| {
+        ui.colored_label(MAGENTA, format!("■ pressure (max {})", peak_pressure));
+        ui.colored_label(TEAL, format!("■ touch_major (max {})", peak_major));
+        ui.small(format!("{:.0} ms", duration));
+    });
+    egui::Grid::new("pressure_markers")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            for (marker, t, pressure) in &markers {
+                ui.label(marker.label());
+                ui.monospace(format!("{:.0} ms", t));
+                ui.monospace(format!("pressure {}", pressure));
+                ui.end_row();
+            }
+        });
+    if markers.is_empty() {
+        ui.small("No BTN_TOUCH or button changes during the press");
+    }
+    ui.button("Run again").clicked()
+}
+
+/// Contents of the orientation calibration window: which corner to touch
+/// next, then the rotation and mirroring found with buttons to apply it or
+/// start over.
+pub fn draw_orientation_test(
+    ui: &mut egui::Ui,
+    test: &OrientationTest,
+) -> Option<OrientationTestAction> {
+    let Some(result) = test.result else {
+        let corner = test.next_corner().unwrap_or_default();
+        ui.label(format!(
+            "Touch the {} corner of the touchpad as it's mounted with one finger, then lift.",
+            corner
+        ));
+        ui.small(format!(
+            "{} of {} corners",
+            test.corners.len(),
+            orientation_test::CORNERS.len()
+        ));
+        return None;
+    };
+    let mut found = format!("Rotate {}", result.rotation.label());
+    if result.flip_x {
+        found += ", invert X";
+    }
+    ui.label(found);
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if ui.button("Apply").clicked() {
+            action = Some(OrientationTestAction::Apply);
+        }
+        if ui.button("Run again").clicked() {
+            action = Some(OrientationTestAction::Restart);
+        }
+    });
+    action
+}
+
+/// Contents of the palm rejection test window: the current posture with a
+/// button to move on, then a score table per posture with a copy button.
+pub fn draw_palm_test(
+    ui: &mut egui::Ui,
+    test: &PalmTest,
+    has_motion: bool,
+) -> Option<PalmTestAction> {
+    let mut action = None;
+    if !has_motion {
+        ui.colored_label(
+            ORANGE,
+            "Pointer motion is unknown without --libinput; leaks can't be detected.",
+        );
+    }
+    match test.current() {
+        Some(step) => {
+            ui.label(format!(
+                "{}/{}: {}, then press Next.",
+                test.reports.len() + 1,
+                palm_test::STEPS.len(),
+                step.prompt()
+            ));
+            if ui.button("Next").clicked() {
+                action = Some(PalmTestAction::Next);
+            }
+        }
+        None => {
+            ui.label("All postures done");
+        }
+    }
+
+    if !test.reports.is_empty() {
+        egui::Grid::new("palm_test")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                for header in [
+                    "",
+                    "contacts",
+                    "MT_TOOL_PALM",
+                    "suppressed",
+                    "leaked",
+                    "score",
+                ] {
+                    ui.small(header);
+                }
+                ui.end_row();
+                for r in &test.reports {
+                    ui.label(r.step.label());
+                    ui.monospace(r.contacts.to_string());
+                    ui.monospace(r.tool_palm.to_string());
+                    ui.monospace(r.suppressed.to_string());
+                    let leak_color = if r.leaked > 0 {
+                        ORANGE
+                    } else {
+                        ui.visuals().text_color()
+                    };
+                    ui.label(
+                        egui::RichText::new(r.leaked.to_string())
+                            .monospace()
+                            .color(leak_color),
+                    );
+                    ui.monospace(
+                        r.score()
+                            .map_or("-".to_string(), |s| format!("{:.0}%", s * 100.0)),
+                    );
+                    ui.end_row();
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui.button("Copy report").clicked() {
+                ui.ctx().copy_text(test.report_text());
+            }
+            if ui.button("Run again").clicked() {
+                action = Some(PalmTestAction::Restart);
+            }
+        });
+    }
+    action
+}
+
+/// Collapsible tap latency section: p50/p95 and a histogram in 20 ms bins.
+/// Returns true if the user asked to reset the samples.
+pub fn draw_tap_latency(ui: &mut egui::Ui, tap: &TapLatency) -> bool {
+    const BIN_MS: f32 = 20.0;
+    const BINS: usize = 25;
+    let mut reset = false;
+    egui::CollapsingHeader::new(format!("Tap latency ({})", tap.samples.len()))
+        .default_open(false)
+        .show(ui, |ui| {
+            let theme = Theme::current(ui.ctx());
+            match (tap.percentile(0.5), tap.percentile(0.95)) {
+                (Some(p50), Some(p95)) => {
+                    ui.monospace(format!("p50 {:.0} ms  p95 {:.0} ms", p50, p95));
+                }
+                _ => {
+                    ui.label("Tap the touchpad to measure");
+                }
+            }
+
+            let mut bins = [0u32; BINS];
+            for &ms in &tap.samples {
+                bins[((ms / BIN_MS) as usize).min(BINS - 1)] += 1;
+            }
+            let peak = bins.iter().copied().max().unwrap_or(0).max(1) as f32;
+            let (rect, _) =
+                ui.allocate_exact_size(Vec2::new(ui.available_width(), 50.0), egui::Sense::hover());
+            let painter = ui.painter();
+            let bar_w = rect.width() / BINS as f32;
+            for (i, &count) in bins.iter().enumerate() {
+                let h = count as f32 / peak * rect.height();
+                let x = rect.min.x + i as f32 * bar_w;
+                painter.rect_filled(
+                    Rect::from_min_max(
+                        Pos2::new(x, rect.max.y - h),
+                        Pos2::new(x + bar_w - 1.0, rect.max.y),
+                    ),
+                    0.0,
+                    MAGENTA,
+                );
+            }
+            painter.line_segment(
+                [rect.left_bottom(), rect.right_bottom()],
+                Stroke::new(1.0, theme.guide),
+            );
+            ui.horizontal(|ui| {
+                ui.small(format!("0–{:.0}+ ms", BIN_MS * (BINS - 1) as f32));
+                reset = ui.small_button("Reset").clicked();
+            });
+        });
+    reset
+}
+
+/// What was clicked in the click pressure panel.
+pub enum ClickPressureAction {
+    Reset,
+    Pulse,
+}
+
+/// Collapsible click pressure panel for clickpads: a live bar of the
+/// firmest contact's pressure with the median click pressure and the
+/// estimated click threshold marked, and the distribution of pressures at
+/// the moment of each click over `0..=max`. `haptics` adds a button that
+/// plays a click on the device.
+pub fn draw_click_pressure(
+    ui: &mut egui::Ui,
+    clicks: &ClickPressure,
+    max: i32,
+    haptics: bool,
+) -> Option<ClickPressureAction> {
+    const BINS: usize = 32;
+    let mut action = None;
+    egui::CollapsingHeader::new(format!("Click pressure ({})", clicks.samples.len()))
+        .default_open(false)
+        .show(ui, |ui| {
+            let theme = Theme::current(ui.ctx());
+            let max = max.max(1) as f32;
+            let median = clicks.percentile(0.5);
+            match (clicks.percentile(0.1), median, clicks.percentile(0.9)) {
+                (Some(p10), Some(p50), Some(p90)) => {
+                    ui.monospace(format!("p10 {}  p50 {}  p90 {}", p10, p50, p90));
+                    if let Some(threshold) = clicks.threshold() {
+                        ui.label(format!("Click threshold ≈ {}", threshold))
+                            .on_hover_text(
+                                "Where clicks start: the pressure a haptic pad would simulate a click at",
+                            );
+                    }
+                    let saturated = clicks.saturated_clicks();
+                    if saturated > 0 {
+                        ui.colored_label(
+                            ORANGE,
+                            format!(
+                                "{} of {} clicks hit maximum pressure ({}) before the button",
+                                saturated,
+                                clicks.samples.len(),
+                                max
+                            ),
+                        );
+                    }
+                }
+                _ => {
+                    ui.label("Click the touchpad to measure");
+                }
+            }
+
+            // Live force bar
+            let (bar, _) =
+                ui.allocate_exact_size(Vec2::new(ui.available_width(), 12.0), egui::Sense::hover());
+            let painter = ui.painter();
+            painter.rect_filled(bar, 2.0, theme.inactive);
+            let threshold = clicks.threshold();
+            if let Some(pressure) = clicks.current {
+                let w = (pressure as f32 / max).clamp(0.0, 1.0) * bar.width();
+                // Past the threshold a haptic pad would click
+                let color = if threshold.is_some_and(|t| pressure >= t) {
+                    ORANGE
+                } else {
+                    MAGENTA
+                };
+                painter.rect_filled(
+                    Rect::from_min_size(bar.min, Vec2::new(w, bar.height())),
+                    2.0,
+                    color,
+                );
+            }
+            if let Some(threshold) = threshold {
+                let x = bar.min.x + (threshold as f32 / max).clamp(0.0, 1.0) * bar.width();
+                painter.line_segment(
+                    [Pos2::new(x, bar.min.y - 2.0), Pos2::new(x, bar.max.y + 2.0)],
+                    Stroke::new(2.0, ORANGE),
+                );
+            }
+            if let Some(p50) = median {
+                let x = bar.min.x + (p50 as f32 / max).clamp(0.0, 1.0) * bar.width();
+                painter.line_segment(
+                    [Pos2::new(x, bar.min.y - 2.0), Pos2::new(x, bar.max.y + 2.0)],
+                    Stroke::new(2.0, theme.text),
+                );
+            }
+
+            let mut bins = [0u32; BINS];
+            for sample in &clicks.samples {
+                let bin = (sample.pressure as f32 / max * BINS as f32) as usize;
+                bins[bin.min(BINS - 1)] += 1;
+            }
+            let peak = bins.iter().copied().max().unwrap_or(0).max(1) as f32;
+            let (rect, _) =
+                ui.allocate_exact_size(Vec2::new(ui.available_width(), 40.0), egui::Sense::hover());
+            let painter = ui.painter();
+            let bar_w = rect.width() / BINS as f32;
+            for (i, &count) in bins.iter().enumerate() {
+                let h = count as f32 / peak * rect.height();
+                let x = rect.min.x + i as f32 * bar_w;
+                let color = if i == BINS - 1 { ORANGE } else { MAGENTA };
+                painter.rect_filled(
+                    Rect::from_min_max(
+                        Pos2::new(x, rect.max.y - h),
+                        Pos2::new(x + bar_w - 1.0, rect.max.y),
+                    ),
+                    0.0,
+                    color,
+                );
+            }
+            painter.line_segment(
+                [rect.left_bottom(), rect.right_bottom()],
+                Stroke::new(1.0, theme.guide),
+            );
+            ui.horizontal(|ui| {
+                ui.small(format!("pressure 0–{:.0}", max));
+                if ui.small_button("Reset").clicked() {
+                    action = Some(ClickPressureAction::Reset);
+                }
+                if haptics
+                    && ui
+                        .small_button("Haptic pulse")
+                        .on_hover_text("Play one click on the touchpad")
+                        .clicked()
+                {
+                    action = Some(ClickPressureAction::Pulse);
+                }
+            });
+        });
+    action
+}
+
+/// Collapsible tap duration and double-tap interval histograms. Returns
+/// whether Reset was clicked.
+pub fn draw_tap_timing(ui: &mut egui::Ui, taps: &mut TapTiming) -> bool {
+    let mut reset = false;
+    egui::CollapsingHeader::new(format!("Tap timing ({})", taps.durations.len()))
+        .default_open(false)
+        .show(ui, |ui| {
+            if taps.durations.is_empty() {
+                ui.label("Tap and double-tap the touchpad to measure");
+            }
+            ui.small(format!(
+                "Duration (tap timeout {:.0} ms): {} barely too long",
+                tap_timing::TAP_TIMEOUT_MS,
+                taps.near_miss_durations()
+            ));
+            draw_threshold_histogram(
+                ui,
+                &taps.durations,
+                tap_timing::DURATION_MAX_MS,
+                tap_timing::TAP_TIMEOUT_MS,
+            );
+            ui.small(format!(
+                "Double-tap interval: {} barely too slow",
+                taps.near_miss_intervals()
+            ));
+            draw_threshold_histogram(
+                ui,
+                &taps.intervals,
+                2.0 * taps.double_click_ms,
+                taps.double_click_ms,
+            );
+            ui.horizontal(|ui| {
+                ui.small("Double-click time");
+                ui.add(
+                    egui::DragValue::new(&mut taps.double_click_ms)
+                        .range(100.0..=2000.0)
+                        .speed(5.0)
+                        .suffix(" ms"),
+                );
+                reset = ui.small_button("Reset").clicked();
+            });
+        });
+    reset
+}
+
+/// Histogram of `samples` over 0..`max` ms with a line at `threshold`. Bars
+/// under it are magenta, those within the near-miss margin over it orange.
+fn draw_threshold_histogram(ui: &mut egui::Ui, samples: &[f32], max: f32, threshold: f32) {
+    const BINS: usize = 40;
+    let theme = Theme::current(ui.ctx());
+    let bin_ms = max / BINS as f32;
+    let mut bins = [0u32; BINS];
+    for &ms in samples {
+        bins[((ms / bin_ms) as usize).min(BINS - 1)] += 1;
+    }
+    let peak = bins.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let (rect, _) =
+        ui.allocate_exact_size(Vec2::new(ui.available_width(), 40.0), egui::Sense::hover());
+    let painter = ui.painter();
+    let bar_w = rect.width() / BINS as f32;
+    for (i, &count) in bins.iter().enumerate() {
+        let start = i as f32 * bin_ms;
+        let color = if start < threshold {
+            MAGENTA
+        } else if start < threshold + tap_timing::NEAR_MISS_MS {
+            ORANGE
+        } else {
+            theme.inactive
+        };
+        let h = count as f32 / peak * rect.height();
+        let x = rect.min.x + i as f32 * bar_w;
+        painter.rect_filled(
+            Rect::from_min_max(
+                Pos2::new(x, rect.max.y - h),
+                Pos2::new(x + bar_w - 1.0, rect.max.y),
+            ),
+            0.0,
+            color,
+        );
+    }
+    let x = rect.min.x + threshold / max * rect.width();
+    painter.line_segment(
+        [Pos2::new(x, rect.min.y), Pos2::new(x, rect.max.y)],
+        Stroke::new(1.0, theme.text_secondary),
+    );
+    painter.text(
+        Pos2::new(x + 2.0, rect.min.y),
+        egui::Align2::LEFT_TOP,
+        format!("{:.0} ms", threshold),
+        FontId::proportional(10.0),
+        theme.text_secondary,
+    );
+    painter.line_segment(
+        [rect.left_bottom(), rect.right_bottom()],
+        Stroke::new(1.0, theme.guide),
+    );
+}
+
+/// Collapsible report interval breakdown: percentiles per contact count and
+/// an interval histogram for each. Returns true if Reset was clicked.
+pub fn draw_report_rate(ui: &mut egui::Ui, rate: &ReportRate) -> bool {
+    const BIN_MS: f32 = 1.0;
+    const BINS: usize = 30;
+    let mut reset = false;
+    egui::CollapsingHeader::new("Report rate")
+        .default_open(false)
+        .show(ui, |ui| {
+            let theme = Theme::current(ui.ctx());
+            let mut any = false;
+            for bucket in 0..report_rate::BUCKETS {
+                let Some(stats) = rate.stats(bucket) else {
+                    continue;
+                };
+                any = true;
+                let fingers = if bucket + 1 == report_rate::BUCKETS {
+                    format!("{}+", bucket + 1)
+                } else {
+                    (bucket + 1).to_string()
+                };
+                ui.monospace(format!(
+                    "{} finger{}: {:.0} Hz  p50 {:.1} ms  p95 {:.1} ms  (n={})",
+                    fingers,
+                    if bucket == 0 { "" } else { "s" },
+                    stats.hz,
+                    stats.p50_ms,
+                    stats.p95_ms,
+                    stats.count
+                ));
+
+                let mut bins = [0u32; BINS];
+                for &ms in &rate.samples[bucket] {
+                    bins[((ms / BIN_MS) as usize).min(BINS - 1)] += 1;
+                }
+                let peak = bins.iter().copied().max().unwrap_or(0).max(1) as f32;
+                let (rect, _) = ui.allocate_exact_size(
+                    Vec2::new(ui.available_width(), 30.0),
+                    egui::Sense::hover(),
+                );
+                let painter = ui.painter();
+                let bar_w = rect.width() / BINS as f32;
+                let color = SLOT_COLORS[bucket % SLOT_COLORS.len()];
+                for (i, &count) in bins.iter().enumerate() {
+                    let h = count as f32 / peak * rect.height();
+                    let x = rect.min.x + i as f32 * bar_w;
+                    painter.rect_filled(
+                        Rect::from_min_max(
+                            Pos2::new(x, rect.max.y - h),
+                            Pos2::new(x + bar_w - 1.0, rect.max.y),
+                        ),
+                        0.0,
+                        color,
+                    );
+                }
+                painter.line_segment(
+                    [rect.left_bottom(), rect.right_bottom()],
+                    Stroke::new(1.0, theme.guide),
+                );
+            }
+            if !any {
+                ui.label("Move fingers on the touchpad to measure");
+            }
+            ui.horizontal(|ui| {
+                ui.small(format!("0–{:.0}+ ms", BIN_MS * (BINS - 1) as f32));
+                reset = ui.small_button("Reset").clicked();
+            });
+        });
+    reset
+}
+
+/// Collapsible firmware/kernel/UI timing breakdown. Jitter and batching are
+/// orange when they'd explain lag. Returns true if Reset was clicked.
+pub fn draw_report_timing(ui: &mut egui::Ui, timing: &ReportTiming) -> bool {
+    let mut reset = false;
+    egui::CollapsingHeader::new("Report timing")
+        .default_open(false)
+        .show(ui, |ui| {
+            let theme = Theme::current(ui.ctx());
+            if timing.reports == 0 {
+                ui.label("Touch the touchpad to measure");
+                return;
+            }
+            let jitter_abs: Vec<f32> = timing.jitter_ms.iter().map(|j| j.abs()).collect();
+            let jittery = report_timing::percentile(&jitter_abs, 0.95)
+                .is_some_and(|p95| p95 > report_timing::JITTER_WARN_MS);
+            let rows: [(&str, &[f32], bool); 4] = [
+                ("Firmware interval", &timing.firmware_ms, false),
+                ("Kernel interval", &timing.kernel_ms, false),
+                ("Jitter (abs)", &jitter_abs, jittery),
+                ("Kernel → UI", &timing.ui_delay_ms, false),
+            ];
+            egui::Grid::new("report_timing")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.small("p50");
+                    ui.small("p95");
+                    ui.end_row();
+                    for (label, samples, warn) in rows {
+                        let color = if warn {
+                            ORANGE
+                        } else {
+                            ui.visuals().text_color()
+                        };
+                        ui.label(egui::RichText::new(label).small().color(color));
+                        for p in [0.5, 0.95] {
+                            let text = report_timing::percentile(samples, p)
+                                .map(|ms| format!("{:.2} ms", ms))
+                                .unwrap_or_else(|| "n/a".into());
+                            ui.label(egui::RichText::new(text).monospace().color(color));
+                        }
+                        ui.end_row();
+                    }
+                });
+            if timing.firmware_ms.is_empty() {
+                ui.small("Device sends no MSC_TIMESTAMP");
+            }
+            let batched = timing.batched as f32 / timing.reports as f32 * 100.0;
+            let color = if timing.batched > 0 {
+                ORANGE
             } else {
-                Color32::from_rgb(200, 200, 200)
+                theme.text_secondary
             };
-            painter.text(
-                Pos2::new(x, y),
-                egui::Align2::CENTER_TOP,
-                *label,
-                btn_font.clone(),
-                color,
+            ui.label(
+                egui::RichText::new(format!(
+                    "Batched: {:.1}% of {} reports (max {} per read)",
+                    batched, timing.reports, timing.max_batch
+                ))
+                .small()
+                .color(color),
             );
-            painter.text(
-                Pos2::new(x, y + 18.0),
-                egui::Align2::CENTER_TOP,
-                *tap_label,
-                tap_font.clone(),
-                Color32::DARK_GRAY,
+            reset = ui.small_button("Reset").clicked();
+        });
+    reset
+}
+
+/// Collapsible tracking-ID diagnostics: IDs seen, lifetimes, short-lived
+/// contacts and drops (orange when any). Returns true if Reset was clicked.
+pub fn draw_tracking_stats(ui: &mut egui::Ui, stats: &TrackingStats, swaps: &SwapDetector) -> bool {
+    let mut reset = false;
+    egui::CollapsingHeader::new("Tracking IDs")
+        .default_open(false)
+        .show(ui, |ui| {
+            let warn = |ui: &mut egui::Ui, text: String, bad: bool| {
+                let color = if bad {
+                    ORANGE
+                } else {
+                    ui.visuals().text_color()
+                };
+                ui.label(egui::RichText::new(text).monospace().color(color));
+            };
+            ui.monospace(format!("IDs seen: {}", stats.ids_seen));
+            if let Some(median) = stats.median_lifetime_ms() {
+                ui.monospace(format!("Median lifetime: {:.0} ms", median));
+            }
+            warn(
+                ui,
+                format!(
+                    "Shorter than {} ms: {}",
+                    tracking::SHORT_LIFETIME.as_millis(),
+                    stats.short_lived
+                ),
+                stats.short_lived > 0,
             );
-        }
-    }
-    y += 38.0;
+            warn(
+                ui,
+                format!("Dropped and re-created: {}", stats.drop_count),
+                stats.drop_count > 0,
+            );
+            if stats.drop_count > 0 {
+                ui.small("Drops are marked with a red X on the touchpad");
+            }
+            warn(
+                ui,
+                format!("Swapped between contacts: {}", swaps.swap_count),
+                swaps.swap_count > 0,
+            );
+            if swaps.swap_count > 0 {
+                ui.small("Swaps are marked with an orange triangle on the touchpad");
+            }
+            reset = ui.small_button("Reset").clicked();
+        });
+    reset
+}
 
-    // --- Scroll ---
-    painter.text(
-        Pos2::new(cx, y),
-        egui::Align2::CENTER_TOP,
-        format!(
-            "Scroll{}",
-            if state.scroll_source.is_empty() {
-                String::new()
-            } else {
-                format!(" ({})", state.scroll_source)
+/// Collapsible outlier counts and the latest few outliers. Returns true if
+/// Reset was clicked.
+pub fn draw_outliers(ui: &mut egui::Ui, check: &OutlierCheck) -> bool {
+    const SHOWN: usize = 8;
+    let mut reset = false;
+    let total = check.out_of_range + check.spikes;
+    egui::CollapsingHeader::new(format!("Outliers ({})", total))
+        .id_salt("outliers")
+        .default_open(false)
+        .show(ui, |ui| {
+            let count = |ui: &mut egui::Ui, text: String, n: usize, color: Color32| {
+                let color = if n > 0 {
+                    color
+                } else {
+                    ui.visuals().text_color()
+                };
+                ui.label(egui::RichText::new(text).monospace().color(color));
+            };
+            count(
+                ui,
+                format!("Out of range: {}", check.out_of_range),
+                check.out_of_range,
+                RED,
+            );
+            count(
+                ui,
+                format!("Position spikes: {}", check.spikes),
+                check.spikes,
+                ORANGE,
+            );
+            for outlier in check.recent.iter().rev().take(SHOWN) {
+                ui.small(outlier.to_string());
             }
-        ),
-        section_font.clone(),
-        Color32::BLACK,
-    );
-    y += 18.0;
+            reset = ui.small_button("Reset").clicked();
+        });
+    reset
+}
 
-    let scroll_center = Pos2::new(cx, y + CROSS_SIZE);
-    draw_cross(
-        painter,
-        scroll_center,
-        (state.scroll_horiz, state.scroll_vert),
-        (0.0, 0.0), // no unaccel for scroll
-        3.0,
-        6.0,
-    );
-    y += CROSS_SIZE * 2.0 + 16.0;
+// --- script ---
 
-    // --- Gesture ---
-    let gesture_label = match state.gesture.kind {
-        GestureKind::Swipe => format!("Swipe ({}f)", state.gesture.fingers),
-        GestureKind::Pinch => format!("Pinch ({}f)", state.gesture.fingers),
-        GestureKind::Hold => format!("Hold ({}f)", state.gesture.fingers),
-        GestureKind::None => "Gesture".to_string(),
+/// The running `--script`: status and the metrics it reported. Returns true
+/// if the user asked to clear its annotations.
+#[cfg(feature = "scripting")]
+pub fn draw_script_panel(ui: &mut egui::Ui, script: &ScriptHook) -> bool {
+    ui.label(egui::RichText::new(script.path.display().to_string()).monospace());
+    match &script.error {
+        Some(error) => ui.colored_label(ORANGE, format!("Stopped: {}", error)),
+        None => ui.label("Running"),
     };
-    painter.text(
-        Pos2::new(cx, y),
-        egui::Align2::CENTER_TOP,
-        gesture_label,
-        section_font,
-        Color32::BLACK,
-    );
-    y += 18.0;
-
-    if state.gesture.active {
-        let gesture_center = Pos2::new(cx, y + CROSS_SIZE);
-
-        // Translation cross
-        draw_cross(
-            painter,
-            gesture_center,
-            (state.gesture.dx, state.gesture.dy),
-            (state.gesture.dx_unaccel, state.gesture.dy_unaccel),
-            4.0,
-            6.0,
+    let dropped = script.dropped();
+    if dropped > 0 {
+        ui.colored_label(
+            ORANGE,
+            format!("{} reports dropped (script too slow)", dropped),
         );
+    }
+    if !script.metrics.is_empty() {
+        ui.separator();
+        egui::Grid::new("script_metrics")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, value) in &script.metrics {
+                    ui.label(name);
+                    ui.label(egui::RichText::new(value).monospace());
+                    ui.end_row();
+                }
+            });
+    }
+    ui.separator();
+    ui.add_enabled(
+        !script.annotations.is_empty(),
+        egui::Button::new(format!("Clear {} annotations", script.annotations.len())),
+    )
+    .clicked()
+}
 
-        if state.gesture.kind == GestureKind::Pinch {
-            // Scale ring: radius proportional to scale factor
-            let base_radius = 20.0;
-            let ring_radius = base_radius * state.gesture.scale;
-            painter.circle_stroke(
-                gesture_center,
-                ring_radius.clamp(4.0, CROSS_SIZE * 1.5),
-                Stroke::new(2.0, TEAL),
-            );
+// --- backend errors ---
 
-            // Rotation indicator: a line from center at the angle
-            if state.gesture.angle.abs() > 0.1 {
-                let angle_rad = state.gesture.angle.to_radians();
-                let line_len = 30.0;
-                let end = Pos2::new(
-                    gesture_center.x + angle_rad.sin() * line_len,
-                    gesture_center.y - angle_rad.cos() * line_len,
-                );
-                painter.line_segment([gesture_center, end], Stroke::new(2.0, ORANGE));
-            }
+/// One row per error with its suggested fixes. Returns the index of an error
+/// the user dismissed.
+pub fn draw_error_banner(ui: &mut egui::Ui, errors: &[BackendError]) -> Option<usize> {
+    let mut dismissed = None;
+    for (i, error) in errors.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.colored_label(RED, format!("⚠ {}", error));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("×").on_hover_text("Dismiss").clicked() {
+                    dismissed = Some(i);
+                }
+            });
+        });
+        for hint in error.hints() {
+            ui.label(format!("    • {}", hint));
         }
+    }
+    dismissed
+}
 
-        y += CROSS_SIZE * 2.0 + 16.0;
-    } else {
-        // Show inactive placeholder
-        let gesture_center = Pos2::new(cx, y + CROSS_SIZE);
-        draw_cross(painter, gesture_center, (0.0, 0.0), (0.0, 0.0), 1.0, 6.0);
-        y += CROSS_SIZE * 2.0 + 16.0;
+/// Where the heatmap would be: why it isn't, with fixes. Returns whether the
+/// user hid it.
+pub fn draw_heatmap_unavailable(ui: &mut egui::Ui, error: &BackendError) -> bool {
+    let mut hidden = false;
+    ui.horizontal(|ui| {
+        ui.colored_label(RED, format!("heatmap unavailable: {}", error.message));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            hidden = ui.small_button("Hide").clicked();
+        });
+    });
+    for hint in error.hints() {
+        ui.label(format!("    • {}", hint));
     }
+    ui.weak("Retrying when a HID device appears");
+    hidden
+}
 
-    // --- Small text log at bottom ---
-    let log_top = y;
-    let log_rect = Rect::from_min_max(Pos2::new(panel_rect.min.x + 4.0, log_top), panel_rect.max);
+// --- log console ---
 
-    ui.allocate_rect(
-        Rect::from_min_max(panel_rect.min, Pos2::new(panel_rect.max.x, log_top)),
-        egui::Sense::hover(),
-    );
+/// Pressure, touch_major and touch_minor over time, side by side, one line
+/// per contact in its slot's color. The plots share their time axis:
+/// Ctrl+scrolling zooms, scrolling or dragging pans back through the history
+/// and hovering reads out the nearest value; clicking a slot in the legend
+/// hides it. Double-clicking, or
+/// Live, goes back to following the latest reports.
+pub fn draw_slot_plots(ui: &mut egui::Ui, plots: &mut SlotPlots, slot_count: usize) {
+    ui.horizontal(|ui| {
+        for (shown, series) in plots.shown.iter_mut().zip(Series::ALL) {
+            ui.checkbox(shown, series.label());
+        }
+        ui.separator();
+        if plots.follow {
+            ui.small(format!("last {:.0} s", slot_plots::WINDOW_S));
+        } else if ui.button("Live").clicked() {
+            plots.follow = true;
+        }
+    });
 
-    ui.allocate_new_ui(egui::UiBuilder::new().max_rect(log_rect), |ui| {
-        egui::ScrollArea::vertical()
-            .auto_shrink([false, false])
-            .stick_to_bottom(true)
-            .show(ui, |ui| {
-                for line in &state.log_lines {
-                    ui.label(
-                        egui::RichText::new(line)
-                            .font(FontId::monospace(9.0))
-                            .color(Color32::from_rgb(80, 80, 80)),
-                    );
-                }
-            });
+    let shown: Vec<Series> = Series::ALL
+        .into_iter()
+        .zip(plots.shown)
+        .filter_map(|(series, shown)| shown.then_some(series))
+        .collect();
+    if shown.is_empty() {
+        return;
+    }
+    let height = (ui.available_height() - 20.0).max(40.0);
+    let runs: Vec<Vec<Vec<Sample>>> = (0..slot_count).map(|slot| plots.runs(slot)).collect();
+    let mut follow = plots.follow;
+    ui.columns(shown.len(), |columns| {
+        for (ui, &series) in columns.iter_mut().zip(&shown) {
+            ui.small(series.label());
+            let response = egui_plot::Plot::new(("slot_plot", series.label()))
+                .height(height)
+                .link_axis("slot_plots", [true, false])
+                .link_cursor("slot_plots", [true, false])
+                .allow_double_click_reset(false)
+                .include_y(0.0)
+                .x_axis_formatter(|mark, _| format!("{:.1} s", mark.value))
+                .legend(egui_plot::Legend::default())
+                .show(ui, |plot_ui| {
+                    for (slot, runs) in runs.iter().enumerate() {
+                        let color = SLOT_COLORS[slot % SLOT_COLORS.len()];
+                        let name = format!("slot {}", slot);
+                        for run in runs {
+                            let points: Vec<[f64; 2]> =
+                                run.iter().map(|s| [s.t, series.value(s) as f64]).collect();
+                            // A single report still gets a mark
+                            if points.len() == 1 {
+                                plot_ui.points(
+                                    egui_plot::Points::new(points)
+                                        .radius(1.5)
+                                        .color(color)
+                                        .name(&name),
+                                );
+                            } else {
+                                plot_ui.line(
+                                    egui_plot::Line::new(points)
+                                        .width(1.5)
+                                        .color(color)
+                                        .name(&name),
+                                );
+                            }
+                        }
+                    }
+                    if follow {
+                        let peak = plots.recent_peak(series) as f64;
+                        plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                            [plots.now - slot_plots::WINDOW_S, 0.0],
+                            [plots.now, peak * 1.05],
+                        ));
+                    }
+                })
+                .response;
+            if response.dragged()
+                || (response.hovered()
+                    && ui.input(|i| i.zoom_delta() != 1.0 || i.smooth_scroll_delta != Vec2::ZERO))
+            {
+                follow = false;
+            }
+            if response.double_clicked() {
+                follow = true;
+            }
+        }
+    });
+    plots.follow = follow;
+}
+
+/// Draw the log console: a header with copy/clear buttons above the scrolling
+/// log. Returns true if the user asked to clear it.
+/// `dropped` counts lines that never reached the console because it fell behind.
+pub fn draw_log_console(ui: &mut egui::Ui, lines: &[LogLine], dropped: u64) -> bool {
+    let theme = Theme::current(ui.ctx());
+    let mut clear = false;
+    ui.horizontal(|ui| {
+        ui.strong(format!("Log ({})", lines.len()));
+        if dropped > 0 {
+            ui.colored_label(ORANGE, format!("{} dropped", dropped));
+        }
+        if ui.button("Copy").clicked() {
+            let text: Vec<&str> = lines.iter().map(|l| l.message.as_str()).collect();
+            ui.ctx().copy_text(text.join("\n"));
+        }
+        clear = ui.button("Clear").clicked();
     });
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for line in lines {
+                let color = match line.level {
+                    Level::Debug => ui.visuals().weak_text_color(),
+                    Level::Info => theme.log_text,
+                    Level::Warn => ORANGE,
+                    Level::Error => RED,
+                };
+                ui.label(
+                    egui::RichText::new(&line.message)
+                        .font(FontId::monospace(11.0))
+                        .color(color),
+                );
+            }
+        });
+    clear
 }
 
 // --- config panel ---
@@ -551,7 +3675,7 @@ pub fn draw_config_panel(ui: &mut egui::Ui, config: &mut PtpConfig) {
                     });
                 if new_mode != mode {
                     if let Err(e) = config.set_input_mode(new_mode) {
-                        eprintln!("config: failed to set input mode: {}", e);
+                        log_error!(target: "config", "failed to set input mode: {}", e);
                     }
                 }
             } else {
@@ -586,7 +3710,7 @@ pub fn draw_config_panel(ui: &mut egui::Ui, config: &mut PtpConfig) {
 
         if surface != surface_prev || button != button_prev {
             if let Err(e) = config.set_selective_reporting(surface, button) {
-                eprintln!("config: failed to set selective reporting: {}", e);
+                log_error!(target: "config", "failed to set selective reporting: {}", e);
             }
         }
     }
@@ -601,7 +3725,7 @@ pub fn draw_config_panel(ui: &mut egui::Ui, config: &mut PtpConfig) {
             );
             if high != prev {
                 if let Err(e) = config.set_latency_mode(high) {
-                    eprintln!("config: failed to set latency mode: {}", e);
+                    log_error!(target: "config", "failed to set latency mode: {}", e);
                 }
             }
         }
@@ -629,12 +3753,12 @@ pub fn draw_config_panel(ui: &mut egui::Ui, config: &mut PtpConfig) {
                 ui.label(
                     egui::RichText::new(format!("  {}..{} g", pmin, pmax))
                         .small()
-                        .color(egui::Color32::DARK_GRAY),
+                        .color(Theme::current(ui.ctx()).text_secondary),
                 );
             }
             if new_threshold != threshold {
                 if let Err(e) = config.set_button_press_threshold(new_threshold) {
-                    eprintln!("config: failed to set click force: {}", e);
+                    log_error!(target: "config", "failed to set click force: {}", e);
                 }
             }
         }
@@ -663,7 +3787,7 @@ pub fn draw_config_panel(ui: &mut egui::Ui, config: &mut PtpConfig) {
             );
             if intensity != prev {
                 if let Err(e) = config.set_haptic_intensity(intensity) {
-                    eprintln!("config: failed to set haptic intensity: {}", e);
+                    log_error!(target: "config", "failed to set haptic intensity: {}", e);
                 }
             }
         }
@@ -678,9 +3802,91 @@ pub fn draw_config_panel(ui: &mut egui::Ui, config: &mut PtpConfig) {
 
 // --- heatmap visualization ---
 
+/// Heatmap color scale.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Colormap {
+    /// Blue → green → yellow → red.
+    #[default]
+    Rainbow,
+    /// Perceptually uniform dark purple → yellow.
+    Viridis,
+    Grayscale,
+    /// Blue for negative, white at zero, red for positive. The heatmap keeps
+    /// the sign with it, so inverted cells and over-compensated baselines
+    /// stand out; elsewhere it runs blue → red.
+    Diverging,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [
+        Colormap::Rainbow,
+        Colormap::Viridis,
+        Colormap::Grayscale,
+        Colormap::Diverging,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Colormap::Rainbow => "Rainbow",
+            Colormap::Viridis => "Viridis",
+            Colormap::Grayscale => "Grayscale",
+            Colormap::Diverging => "Diverging (signed)",
+        }
+    }
+
+    /// Map a normalized value 0.0..=1.0 to a color.
+    pub fn color(self, t: f32) -> Color32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Colormap::Rainbow => rainbow_color(t),
+            Colormap::Viridis => viridis_color(t),
+            Colormap::Grayscale => {
+                let v = (t * 255.0) as u8;
+                Color32::from_rgb(v, v, v)
+            }
+            Colormap::Diverging => diverging_color(t),
+        }
+    }
+
+    /// Whether heatmap cells keep their sign instead of their magnitude.
+    pub fn signed(self) -> bool {
+        self == Colormap::Diverging
+    }
+}
+
+/// Blue at 0.0, white at 0.5, red at 1.0.
+fn diverging_color(t: f32) -> Color32 {
+    const BLUE: (f32, f32, f32) = (33.0, 102.0, 172.0);
+    const RED: (f32, f32, f32) = (178.0, 24.0, 43.0);
+    let (end, s) = if t < 0.5 {
+        (BLUE, 1.0 - t * 2.0)
+    } else {
+        (RED, t * 2.0 - 1.0)
+    };
+    let lerp = |c: f32| (255.0 + (c - 255.0) * s) as u8;
+    Color32::from_rgb(lerp(end.0), lerp(end.1), lerp(end.2))
+}
+
+/// Viridis sampled at five evenly spaced stops, linearly interpolated.
+fn viridis_color(t: f32) -> Color32 {
+    const STOPS: [(u8, u8, u8); 5] = [
+        (68, 1, 84),
+        (59, 82, 139),
+        (33, 145, 140),
+        (94, 201, 98),
+        (253, 231, 37),
+    ];
+    let pos = t * (STOPS.len() - 1) as f32;
+    let i = (pos as usize).min(STOPS.len() - 2);
+    let s = pos - i as f32;
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * s) as u8;
+    let (a, b) = (STOPS[i], STOPS[i + 1]);
+    Color32::from_rgb(lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+}
+
 /// Map a normalized value 0.0..=1.0 to a blue → green → yellow → red gradient.
-fn heatmap_color(t: f32) -> Color32 {
-    let t = t.clamp(0.0, 1.0);
+fn rainbow_color(t: f32) -> Color32 {
     let (r, g, b) = if t < 0.333 {
         // blue → green
         let s = t / 0.333;
@@ -697,39 +3903,138 @@ fn heatmap_color(t: f32) -> Color32 {
     Color32::from_rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
 }
 
-/// Draw the heatmap panel contents: a 2D grid of colored cells.
-pub fn draw_heatmap_panel(ui: &mut egui::Ui, frame: &HeatmapFrame) {
-    let panel_rect = ui.available_rect_before_wrap();
-    let painter = ui.painter();
+/// How cell magnitudes are spread over the colormap. Both stretch the low
+/// end, so a hovering finger shows next to a full press without the press
+/// being clipped.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeatmapScale {
+    /// Logarithmic instead of linear
+    pub log: bool,
+    /// Exponent applied after that; below 1 brightens weak signals
+    pub gamma: f32,
+}
 
-    // Dimension label at top
-    let label = format!("{}x{}", frame.rows, frame.cols);
-    let label_font = FontId::proportional(13.0);
-    let cx = panel_rect.center().x;
-    painter.text(
-        Pos2::new(cx, panel_rect.min.y + 4.0),
-        egui::Align2::CENTER_TOP,
-        &label,
-        label_font,
-        Color32::BLACK,
-    );
+impl Default for HeatmapScale {
+    fn default() -> Self {
+        Self {
+            log: false,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl HeatmapScale {
+    /// Spread of the log curve: a value at 1% of the maximum lands at 15%
+    const LOG_SPREAD: f32 = 100.0;
+
+    /// Map |value| / max (0.0..=1.0) to a position on the colormap.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = if self.log {
+            (1.0 + Self::LOG_SPREAD * t).ln() / (1.0 + Self::LOG_SPREAD).ln()
+        } else {
+            t
+        };
+        t.powf(self.gamma)
+    }
+}
+
+/// What the heatmap looks like, as picked in the settings window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeatmapStyle {
+    pub colormap: Colormap,
+    pub scale: HeatmapScale,
+    /// Linear filtering between cells instead of sharp cells
+    pub smooth: bool,
+}
+
+/// Steps from zero to the largest magnitude in a heatmap color table: fine
+/// enough that a weak signal isn't rounded to zero before the scale can
+/// stretch it.
+const HEATMAP_LUT_STEPS: usize = 1023;
+
+/// Heatmap colors precomputed for a colormap and scale.
+pub struct HeatmapLut {
+    /// Magnitude 0..=max, or for a signed colormap -max..=max
+    colors: Vec<Color32>,
+    signed: bool,
+}
+
+impl HeatmapLut {
+    pub fn new(colormap: Colormap, scale: HeatmapScale) -> Self {
+        let signed = colormap.signed();
+        let from = if signed {
+            -(HEATMAP_LUT_STEPS as i32)
+        } else {
+            0
+        };
+        let colors = (from..=HEATMAP_LUT_STEPS as i32)
+            .map(|i| {
+                let t = i as f32 / HEATMAP_LUT_STEPS as f32;
+                let t = scale.apply(t.abs()).copysign(t);
+                colormap.color(if signed { (1.0 + t) / 2.0 } else { t })
+            })
+            .collect();
+        Self { colors, signed }
+    }
+
+    /// The color of `value` in a frame whose largest magnitude is `max_abs`.
+    pub fn color(&self, value: i16, max_abs: u16) -> Color32 {
+        let step = value.unsigned_abs() as usize * HEATMAP_LUT_STEPS / max_abs.max(1) as usize;
+        let index = match (self.signed, value < 0) {
+            (false, _) => step,
+            (true, false) => HEATMAP_LUT_STEPS + step,
+            (true, true) => HEATMAP_LUT_STEPS - step,
+        };
+        self.colors[index]
+    }
+}
+
+/// Checkboxes for each heatmap orientation transform. Returns true on change.
+pub fn draw_orientation_toggles(ui: &mut egui::Ui, orientation: &mut HeatmapOrientation) -> bool {
+    let before = *orientation;
+    ui.checkbox(&mut orientation.transpose, "Transpose");
+    ui.checkbox(&mut orientation.rotate_90, "Rotate 90°");
+    ui.checkbox(&mut orientation.flip_x, "Flip X");
+    ui.checkbox(&mut orientation.flip_y, "Flip Y");
+    *orientation != before
+}
+
+/// Draw the heatmap panel contents: orientation toggles, a button flipping
+/// `popped` to move it between the bottom panel and its own window, and a
+/// 2D grid of colored cells. Returns true if the user changed the
+/// orientation.
+pub fn draw_heatmap_panel(
+    ui: &mut egui::Ui,
+    frame: &HeatmapFrame,
+    texture: &mut HeatmapTexture,
+    orientation: &mut HeatmapOrientation,
+    style: HeatmapStyle,
+    popped: &mut bool,
+) -> bool {
+    let changed = ui
+        .horizontal(|ui| {
+            ui.strong(format!("{}x{}", frame.rows, frame.cols));
+            ui.separator();
+            let changed = draw_orientation_toggles(ui, orientation);
+            ui.separator();
+            let label = if *popped { "Dock" } else { "Pop out" };
+            if ui.button(label).clicked() {
+                *popped = !*popped;
+            }
+            changed
+        })
+        .inner;
+
+    let panel_rect = ui.available_rect_before_wrap();
 
     if frame.rows == 0 || frame.cols == 0 || frame.data.is_empty() {
         ui.allocate_rect(panel_rect, egui::Sense::hover());
-        return;
+        return changed;
     }
 
-    // Find min/max for normalization
-    let max_abs = frame
-        .data
-        .iter()
-        .map(|v| v.unsigned_abs())
-        .max()
-        .unwrap_or(1)
-        .max(1) as f32;
-
-    // Grid area below the label
-    let grid_top = panel_rect.min.y + 22.0;
+    // Grid area below the toggles
+    let grid_top = panel_rect.min.y + 2.0;
     let grid_width = panel_rect.width() - 4.0;
     let grid_height = panel_rect.max.y - grid_top - 2.0;
 
@@ -743,22 +4048,81 @@ pub fn draw_heatmap_panel(ui: &mut egui::Ui, frame: &HeatmapFrame) {
     let offset_x = panel_rect.min.x + (panel_rect.width() - total_w) / 2.0;
     let offset_y = grid_top + (grid_height - total_h) / 2.0;
 
-    for row in 0..frame.rows {
-        for col in 0..frame.cols {
-            let idx = row * frame.cols + col;
-            let value = frame.data.get(idx).copied().unwrap_or(0);
-            let t = value.unsigned_abs() as f32 / max_abs;
-            let color = heatmap_color(t);
+    let texture = texture.update(ui.ctx(), frame, style);
+    ui.painter().image(
+        texture.id(),
+        Rect::from_min_size(Pos2::new(offset_x, offset_y), Vec2::new(total_w, total_h)),
+        Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+        Color32::WHITE,
+    );
 
-            let x = offset_x + col as f32 * cell_size;
-            let y = offset_y + row as f32 * cell_size;
-            painter.rect_filled(
-                Rect::from_min_size(Pos2::new(x, y), Vec2::new(cell_size, cell_size)),
-                0.0,
-                color,
-            );
-        }
+    ui.allocate_rect(panel_rect, egui::Sense::hover());
+    changed
+}
+
+/// The heatmap as a texture with one pixel per cell, so the panel is a
+/// single scaled image instead of a rectangle per cell. Only re-uploaded
+/// when a new frame arrived or the colormap or filtering changed.
+#[derive(Default)]
+pub struct HeatmapTexture {
+    handle: Option<egui::TextureHandle>,
+    stale: bool,
+    /// Linear filtering was used for the uploaded texture
+    smooth: bool,
+    lut: Option<HeatmapLut>,
+    /// What `lut` was built for
+    lut_style: Option<(Colormap, HeatmapScale)>,
+}
+
+impl HeatmapTexture {
+    /// Redraw from the frame on next use.
+    pub fn invalidate(&mut self) {
+        self.stale = true;
     }
 
-    ui.allocate_rect(panel_rect, egui::Sense::hover());
+    fn update(
+        &mut self,
+        ctx: &egui::Context,
+        frame: &HeatmapFrame,
+        style: HeatmapStyle,
+    ) -> &egui::TextureHandle {
+        let HeatmapStyle {
+            colormap,
+            scale,
+            smooth,
+        } = style;
+        if self.lut_style != Some((colormap, scale)) {
+            self.lut = Some(HeatmapLut::new(colormap, scale));
+            self.lut_style = Some((colormap, scale));
+            self.stale = true;
+        }
+        if smooth != self.smooth {
+            self.smooth = smooth;
+            self.stale = true;
+        }
+        if self.stale || self.handle.is_none() {
+            let lut = self.lut.as_ref().expect("built above");
+            let max_abs = frame.max_abs();
+            let pixels = (0..frame.rows * frame.cols)
+                .map(|i| lut.color(frame.data.get(i).copied().unwrap_or(0), max_abs))
+                .collect();
+            let image = egui::ColorImage {
+                size: [frame.cols, frame.rows],
+                pixels,
+            };
+            // Nearest shows the cells as the sensor reports them; linear
+            // blends between cell centers, which is easier on the eye
+            let options = if smooth {
+                egui::TextureOptions::LINEAR
+            } else {
+                egui::TextureOptions::NEAREST
+            };
+            match &mut self.handle {
+                Some(handle) => handle.set(image, options),
+                None => self.handle = Some(ctx.load_texture("heatmap", image, options)),
+            }
+            self.stale = false;
+        }
+        self.handle.as_ref().expect("texture loaded above")
+    }
 }
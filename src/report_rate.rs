@@ -0,0 +1,107 @@
+//! Report rate analyzer: the interval between consecutive device reports,
+//! broken down by how many contacts were down.
+//!
+//! Many touchpads lower their scan rate with three or more fingers, which an
+//! overall events/s figure hides. An interval only counts when both reports
+//! had the same number of contacts, so lifts and landings don't smear buckets,
+//! and gaps longer than `MAX_INTERVAL_MS` (the device idling) are dropped.
+
+use std::time::SystemTime;
+
+/// Buckets for 1, 2, 3, 4 and 5+ contacts.
+pub const BUCKETS: usize = 5;
+const MAX_INTERVAL_MS: f32 = 100.0;
+/// Samples kept per bucket.
+const SAMPLES_MAX: usize = 2000;
+
+#[derive(Default)]
+pub struct ReportRate {
+    last: Option<(SystemTime, u8)>,
+    /// Intervals in milliseconds per contact-count bucket, oldest first.
+    pub samples: [Vec<f32>; BUCKETS],
+}
+
+/// Percentiles and rate for one bucket.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateStats {
+    pub count: usize,
+    pub p50_ms: f32,
+    pub p95_ms: f32,
+    /// Reports per second at the median interval
+    pub hz: f32,
+}
+
+impl ReportRate {
+    /// Feed one report's timestamp and active contact count.
+    pub fn add(&mut self, time: SystemTime, contacts: u8) {
+        if let Some((last_time, last_contacts)) = self.last {
+            if contacts > 0 && contacts == last_contacts {
+                if let Ok(gap) = time.duration_since(last_time) {
+                    let ms = gap.as_secs_f32() * 1000.0;
+                    if ms > 0.0 && ms <= MAX_INTERVAL_MS {
+                        let bucket = &mut self.samples[(contacts as usize).min(BUCKETS) - 1];
+                        if bucket.len() == SAMPLES_MAX {
+                            bucket.remove(0);
+                        }
+                        bucket.push(ms);
+                    }
+                }
+            }
+        }
+        self.last = Some((time, contacts));
+    }
+
+    /// Stats for `bucket` (0 = one contact), if it has samples.
+    pub fn stats(&self, bucket: usize) -> Option<RateStats> {
+        let samples = self.samples.get(bucket)?;
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.clone();
+        sorted.sort_by(f32::total_cmp);
+        let rank = |p: f32| {
+            let r = ((p * sorted.len() as f32).ceil() as usize).clamp(1, sorted.len());
+            sorted[r - 1]
+        };
+        let p50_ms = rank(0.5);
+        Some(RateStats {
+            count: sorted.len(),
+            p50_ms,
+            p95_ms: rank(0.95),
+            hz: 1000.0 / p50_ms,
+        })
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn buckets_by_contact_count() {
+        let t0 = SystemTime::UNIX_EPOCH;
+        let ms = |n: u64| t0 + Duration::from_millis(n);
+        let mut rate = ReportRate::default();
+        rate.add(ms(0), 1);
+        rate.add(ms(7), 1);
+        rate.add(ms(14), 1);
+        // Landing a third finger: the transition interval is skipped
+        rate.add(ms(21), 3);
+        rate.add(ms(35), 3);
+        // An idle gap isn't an interval
+        rate.add(ms(500), 3);
+        rate.add(ms(514), 3);
+
+        assert_eq!(rate.samples[0], vec![7.0, 7.0]);
+        assert_eq!(rate.samples[2], vec![14.0, 14.0]);
+        let stats = rate.stats(2).unwrap();
+        assert_eq!(stats.count, 2);
+        assert!((stats.hz - 71.4).abs() < 0.1);
+        assert_eq!(rate.stats(1), None);
+    }
+}
@@ -0,0 +1,135 @@
+//! Report timing analysis: splits "touchpad lag" into firmware, kernel and UI
+//! parts.
+//!
+//! - Firmware interval: MSC_TIMESTAMP deltas, i.e. the scan period the
+//!   touchpad claims.
+//! - Kernel interval: deltas of the evdev event timestamps, i.e. when the
+//!   kernel received each report.
+//! - Jitter: how far the kernel interval strays from the firmware interval;
+//!   large values point at the bus or driver.
+//! - Batching: reports that reach the evdev thread together in one read.
+//! - UI delay: kernel timestamp to the UI draining the report, covering the
+//!   backend poll, the channel and the wait for the next frame.
+
+use std::time::SystemTime;
+use tapview_core::input::ReportTime;
+
+/// Gaps longer than this (the device idling, or the firmware timestamp
+/// resetting on a new touch) aren't intervals.
+const MAX_INTERVAL_MS: f32 = 100.0;
+const SAMPLES_MAX: usize = 2000;
+/// Jitter p95 above this is flagged.
+pub const JITTER_WARN_MS: f32 = 2.0;
+
+#[derive(Default)]
+pub struct ReportTiming {
+    last: Option<ReportTime>,
+    /// Milliseconds, oldest first
+    pub firmware_ms: Vec<f32>,
+    pub kernel_ms: Vec<f32>,
+    /// Kernel interval minus firmware interval
+    pub jitter_ms: Vec<f32>,
+    pub ui_delay_ms: Vec<f32>,
+    /// Reports seen, and how many of them arrived in a batch of two or more
+    pub reports: usize,
+    pub batched: usize,
+    pub max_batch: usize,
+}
+
+fn push(samples: &mut Vec<f32>, value: f32) {
+    if samples.len() == SAMPLES_MAX {
+        samples.remove(0);
+    }
+    samples.push(value);
+}
+
+/// Nearest-rank percentile (0.0..=1.0).
+pub fn percentile(samples: &[f32], p: f32) -> Option<f32> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let rank = ((p * sorted.len() as f32).ceil() as usize).clamp(1, sorted.len());
+    Some(sorted[rank - 1])
+}
+
+fn millis(later: SystemTime, earlier: SystemTime) -> Option<f32> {
+    later
+        .duration_since(earlier)
+        .ok()
+        .map(|d| d.as_secs_f32() * 1000.0)
+}
+
+impl ReportTiming {
+    /// Feed the reports the backend read together, and when the UI drained
+    /// them.
+    pub fn add_batch(&mut self, batch: &[ReportTime], drained: SystemTime) {
+        self.reports += batch.len();
+        if batch.len() > 1 {
+            self.batched += batch.len();
+        }
+        self.max_batch = self.max_batch.max(batch.len());
+
+        for report in batch {
+            if let Some(delay) = millis(drained, report.time) {
+                push(&mut self.ui_delay_ms, delay);
+            }
+            let Some(last) = self.last.replace(*report) else {
+                continue;
+            };
+            let Some(kernel) = millis(report.time, last.time) else {
+                continue;
+            };
+            if kernel > MAX_INTERVAL_MS {
+                continue;
+            }
+            push(&mut self.kernel_ms, kernel);
+            if let (Some(now), Some(prev)) = (report.firmware_us, last.firmware_us) {
+                let firmware = now.wrapping_sub(prev) as f32 / 1000.0;
+                if firmware <= MAX_INTERVAL_MS {
+                    push(&mut self.firmware_ms, firmware);
+                    push(&mut self.jitter_ms, kernel - firmware);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn report(ms: u64, firmware_us: u32) -> ReportTime {
+        ReportTime {
+            time: SystemTime::UNIX_EPOCH + Duration::from_millis(ms),
+            firmware_us: Some(firmware_us),
+            contacts: 1,
+        }
+    }
+
+    #[test]
+    fn splits_firmware_kernel_and_ui() {
+        let mut timing = ReportTiming::default();
+        let at = |ms: u64| SystemTime::UNIX_EPOCH + Duration::from_millis(ms);
+        timing.add_batch(&[report(0, u32::MAX - 999)], at(4));
+        // Two reports delivered together; the second one was delayed by the bus
+        timing.add_batch(&[report(10, 9_000), report(12, 19_000)], at(16));
+
+        // Firmware counter wrapped between the first two reports
+        assert_eq!(timing.firmware_ms, vec![10.0, 10.0]);
+        assert_eq!(timing.kernel_ms, vec![10.0, 2.0]);
+        assert_eq!(timing.jitter_ms, vec![0.0, -8.0]);
+        assert_eq!(timing.ui_delay_ms, vec![4.0, 6.0, 4.0]);
+        assert_eq!(
+            (timing.reports, timing.batched, timing.max_batch),
+            (3, 2, 2)
+        );
+        assert_eq!(percentile(&timing.ui_delay_ms, 0.5), Some(4.0));
+    }
+}
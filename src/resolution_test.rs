@@ -0,0 +1,229 @@
+//! Resolution wizard: swipe a known physical distance along each axis (e.g.
+//! along a ruler) to measure device units per millimeter, and compare with
+//! the resolution the kernel advertises.
+//!
+//! The swipe is measured from where the finger lands to where it lifts, so
+//! only the distance between the two ruler marks matters, not the path.
+//! When the measurement disagrees with the kernel, a udev hwdb entry
+//! overriding the resolution can be generated (see systemd's 60-evdev.hwdb).
+
+use std::fmt::Write;
+
+/// Measured and advertised resolutions further apart than this share of the
+/// advertised one disagree. Finger swipes along a ruler aren't more precise.
+pub const TOLERANCE: f32 = 0.1;
+/// Sideways drift allowed, as a share of the swipe length.
+const MAX_DRIFT: f32 = 0.2;
+pub const DEFAULT_DISTANCE_MM: f32 = 100.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+impl Axis {
+    pub fn prompt(self) -> &'static str {
+        match self {
+            Axis::X => "Place a finger on the first mark and swipe left/right to the second",
+            Axis::Y => "Place a finger on the first mark and swipe up/down to the second",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Axis::X => "X",
+            Axis::Y => "Y",
+        }
+    }
+
+    /// evdev axis codes (hex, as hwdb spells them) carrying this direction:
+    /// ABS_X/ABS_Y and ABS_MT_POSITION_X/Y.
+    fn hwdb_codes(self) -> [&'static str; 2] {
+        match self {
+            Axis::X => ["00", "35"],
+            Axis::Y => ["01", "36"],
+        }
+    }
+}
+
+pub const AXES: [Axis; 2] = [Axis::X, Axis::Y];
+
+/// Whether a measured resolution disagrees with the advertised one. No
+/// advertised resolution always disagrees.
+pub fn disagrees(measured: f32, advertised: Option<f32>) -> bool {
+    match advertised {
+        Some(adv) if adv > 0.0 => ((measured - adv) / adv).abs() > TOLERANCE,
+        _ => true,
+    }
+}
+
+/// hwdb match line for the device: by USB/HID IDs when known, otherwise by
+/// evdev name.
+#[allow(dead_code)] // hwdb exists on Linux only
+pub fn hwdb_match(ids: Option<(u16, u16)>, name: Option<&str>) -> Option<String> {
+    match (ids, name) {
+        (Some((vendor, product)), _) => {
+            Some(format!("evdev:input:b*v{:04X}p{:04X}*", vendor, product))
+        }
+        (None, Some(name)) => Some(format!("evdev:name:{}:*", name)),
+        (None, None) => None,
+    }
+}
+
+pub struct ResolutionTest {
+    /// Physical distance between the marks
+    pub distance_mm: f32,
+    /// Measured units per mm for X and Y
+    pub measured: [Option<f32>; 2],
+    tracking_id: Option<i32>,
+    start: (i32, i32),
+    last: (i32, i32),
+    /// Why the last swipe wasn't counted, if it wasn't
+    pub rejected: Option<&'static str>,
+}
+
+impl Default for ResolutionTest {
+    fn default() -> Self {
+        Self {
+            distance_mm: DEFAULT_DISTANCE_MM,
+            measured: [None; 2],
+            tracking_id: None,
+            start: (0, 0),
+            last: (0, 0),
+            rejected: None,
+        }
+    }
+}
+
+impl ResolutionTest {
+    /// The axis being asked for, or `None` once both are measured.
+    pub fn current(&self) -> Option<Axis> {
+        AXES.into_iter()
+            .find(|&a| self.measured[a as usize].is_none())
+    }
+
+    /// Feed the active contacts (tracking ID and position).
+    pub fn update(&mut self, contacts: &[(i32, i32, i32)]) {
+        let Some(axis) = self.current() else {
+            return;
+        };
+        match contacts {
+            [] => {
+                if self.tracking_id.take().is_some() {
+                    self.finish(axis);
+                }
+            }
+            [(id, x, y)] => match self.tracking_id {
+                Some(current) if current != *id => {
+                    self.tracking_id = None;
+                    self.rejected = Some("Finger changed mid-swipe");
+                }
+                Some(_) => self.last = (*x, *y),
+                None => {
+                    self.tracking_id = Some(*id);
+                    self.start = (*x, *y);
+                    self.last = (*x, *y);
+                }
+            },
+            _ => {
+                if self.tracking_id.take().is_some() {
+                    self.rejected = Some("Use only one finger");
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, axis: Axis) {
+        let dx = (self.last.0 - self.start.0).abs() as f32;
+        let dy = (self.last.1 - self.start.1).abs() as f32;
+        let (along, across) = match axis {
+            Axis::X => (dx, dy),
+            Axis::Y => (dy, dx),
+        };
+        if along < 1.0 {
+            self.rejected = Some("Swipe too short, try again");
+        } else if across > along * MAX_DRIFT {
+            self.rejected = Some("Swipe straight along the ruler");
+        } else {
+            self.rejected = None;
+            self.measured[axis as usize] = Some(along / self.distance_mm.max(1.0));
+        }
+    }
+
+    /// An hwdb entry setting the measured resolution on the axes that
+    /// disagree with `advertised`, or `None` if they all agree (or nothing
+    /// is measured yet).
+    pub fn hwdb_snippet(&self, match_line: &str, advertised: Option<(f32, f32)>) -> Option<String> {
+        let advertised = |axis: Axis| {
+            advertised.map(|(x, y)| match axis {
+                Axis::X => x,
+                Axis::Y => y,
+            })
+        };
+        let wrong: Vec<(Axis, f32)> = AXES
+            .into_iter()
+            .filter_map(|axis| Some((axis, self.measured[axis as usize]?)))
+            .filter(|&(axis, measured)| disagrees(measured, advertised(axis)))
+            .collect();
+        if wrong.is_empty() {
+            return None;
+        }
+        let mut out = String::from("# /etc/udev/hwdb.d/61-evdev-local.hwdb\n");
+        for &(axis, measured) in &wrong {
+            let kernel = advertised(axis).map_or("none".to_string(), |r| format!("{:.0}", r));
+            let _ = writeln!(
+                out,
+                "# {}: measured {:.1} units/mm, kernel {}",
+                axis.label(),
+                measured,
+                kernel
+            );
+        }
+        let _ = writeln!(out, "{}", match_line);
+        for &(axis, measured) in &wrong {
+            for code in axis.hwdb_codes() {
+                let _ = writeln!(out, " EVDEV_ABS_{}=::{:.0}", code, measured.round());
+            }
+        }
+        out.push_str("# then: sudo systemd-hwdb update && sudo udevadm trigger\n");
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swipe(test: &mut ResolutionTest, id: i32, from: (i32, i32), to: (i32, i32)) {
+        test.update(&[(id, from.0, from.1)]);
+        test.update(&[(id, to.0, to.1)]);
+        test.update(&[]);
+    }
+
+    #[test]
+    fn measures_axes_and_emits_hwdb_for_mismatches() {
+        let mut test = ResolutionTest::default();
+        // Drifts too far sideways
+        swipe(&mut test, 1, (100, 100), (1300, 500));
+        assert_eq!(test.measured, [None, None]);
+        assert!(test.rejected.is_some());
+
+        // 1200 units over 100 mm, then 1000 units
+        swipe(&mut test, 2, (100, 100), (1300, 150));
+        assert_eq!(test.current(), Some(Axis::Y));
+        swipe(&mut test, 3, (500, 900), (510, -100));
+        assert_eq!(test.measured, [Some(12.0), Some(10.0)]);
+        assert_eq!(test.current(), None);
+
+        // The kernel's X is off; Y is within tolerance
+        let snippet = test
+            .hwdb_snippet("evdev:input:b*v06CBpCE7E*", Some((10.0, 10.5)))
+            .unwrap();
+        assert!(
+            snippet.contains("evdev:input:b*v06CBpCE7E*\n EVDEV_ABS_00=::12\n EVDEV_ABS_35=::12\n")
+        );
+        assert!(!snippet.contains("EVDEV_ABS_01"));
+        assert!(test.hwdb_snippet("x", Some((12.0, 10.0))).is_none());
+    }
+}
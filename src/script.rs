@@ -0,0 +1,442 @@
+//! `--script <file>`: run a [Rhai](https://rhai.rs) script on touch reports
+//! and libinput events and draw what it asks for, so one-off analyses can be
+//! written without living in tapview. Built with the `scripting` feature.
+//!
+//! The script may define any of these, each called with `this` bound to a map
+//! that is kept between calls (it starts with the device's `x_max` and
+//! `y_max`):
+//!
+//! ```text
+//! fn on_touch(t, report)     report: #{left, right, middle, contacts: [#{slot,
+//!                            id, x, y, pressure, major, minor, orientation,
+//!                            distance, tool}]}
+//! fn on_libinput(t, event)   event: the object the libinput export writes
+//! ```
+//!
+//! `t` is seconds since start, or the playback position when playing a
+//! recording. They answer by calling, in touchpad coordinates:
+//!
+//! ```text
+//! text(x, y, message)        label
+//! circle(x, y, radius)       ring
+//! line(x0, y0, x1, y1)       segment
+//! metric(name, value)        row in the script window
+//! clear()                    remove the labels, rings and segments
+//! ```
+//!
+//! `print` goes to the log console. The script runs on a thread of its own;
+//! reports are dropped, and counted, while it falls behind. An error stops it.
+
+use crate::libinput_export::json_fields;
+use crate::libinput_state::LibinputEvent;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use tapview_core::channel::{self, BoundedReceiver, BoundedSender};
+use tapview_core::multitouch::{ButtonState, TouchData};
+
+/// Events queued for the script, and commands for the UI, before further
+/// ones are dropped.
+const CHANNEL_CAPACITY: usize = 1024;
+/// Oldest annotations go first beyond this.
+const MAX_ANNOTATIONS: usize = 512;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Annotation {
+    Text { x: f32, y: f32, text: String },
+    Circle { x: f32, y: f32, radius: f32 },
+    Line { from: (f32, f32), to: (f32, f32) },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ScriptCommand {
+    Annotate(Annotation),
+    Metric(String, String),
+    Clear,
+    /// The script hit an error and won't run again
+    Stopped(String),
+}
+
+enum ScriptEvent {
+    Touch {
+        t: f64,
+        touches: Vec<TouchData>,
+        buttons: ButtonState,
+    },
+    Libinput {
+        t: f64,
+        event: LibinputEvent,
+    },
+}
+
+/// One report as the map `on_touch` gets.
+fn touch_map(touches: &[TouchData], buttons: &ButtonState) -> Map {
+    let contacts: Array = touches
+        .iter()
+        .enumerate()
+        .filter(|(_, touch)| touch.used)
+        .map(|(slot, touch)| {
+            let fields: [(&str, i64); 10] = [
+                ("slot", slot as i64),
+                ("id", touch.tracking_id as i64),
+                ("x", touch.position_x as i64),
+                ("y", touch.position_y as i64),
+                ("pressure", touch.pressure as i64),
+                ("major", touch.touch_major as i64),
+                ("minor", touch.touch_minor as i64),
+                ("orientation", touch.orientation as i64),
+                ("distance", touch.distance as i64),
+                ("tool", touch.tool_type as i64),
+            ];
+            let contact: Map = fields
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect();
+            contact.into()
+        })
+        .collect();
+    let mut report = Map::new();
+    report.insert("left".into(), buttons.left.into());
+    report.insert("right".into(), buttons.right.into());
+    report.insert("middle".into(), buttons.middle.into());
+    report.insert("contacts".into(), contacts.into());
+    report
+}
+
+/// A number the script passed, integer or not.
+fn number(value: &Dynamic) -> Result<f32, Box<EvalAltResult>> {
+    value
+        .as_float()
+        .map(|v| v as f32)
+        .or_else(|_| value.as_int().map(|v| v as f32))
+        .map_err(|ty| format!("expected a number, got {}", ty).into())
+}
+
+/// An engine whose drawing functions queue commands on `output`, and which
+/// gives up once `stop` is set.
+fn engine(output: BoundedSender<ScriptCommand>, stop: Arc<AtomicBool>) -> Engine {
+    let mut engine = Engine::new();
+    engine.on_print(|message| log_info!(target: "script", "{}", message));
+    engine.on_debug(|message, _, pos| log_debug!(target: "script", "{}: {}", pos, message));
+    engine.on_progress(move |_| stop.load(Ordering::Relaxed).then_some(Dynamic::UNIT));
+
+    let tx = output.clone();
+    engine.register_fn(
+        "text",
+        move |x: Dynamic, y: Dynamic, text: &str| -> Result<(), Box<EvalAltResult>> {
+            let (x, y) = (number(&x)?, number(&y)?);
+            let text = text.to_string();
+            let _ = tx.send(ScriptCommand::Annotate(Annotation::Text { x, y, text }));
+            Ok(())
+        },
+    );
+    let tx = output.clone();
+    engine.register_fn(
+        "circle",
+        move |x: Dynamic, y: Dynamic, radius: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            let (x, y, radius) = (number(&x)?, number(&y)?, number(&radius)?);
+            let _ = tx.send(ScriptCommand::Annotate(Annotation::Circle { x, y, radius }));
+            Ok(())
+        },
+    );
+    let tx = output.clone();
+    engine.register_fn(
+        "line",
+        move |x0: Dynamic,
+              y0: Dynamic,
+              x1: Dynamic,
+              y1: Dynamic|
+              -> Result<(), Box<EvalAltResult>> {
+            let from = (number(&x0)?, number(&y0)?);
+            let to = (number(&x1)?, number(&y1)?);
+            let _ = tx.send(ScriptCommand::Annotate(Annotation::Line { from, to }));
+            Ok(())
+        },
+    );
+    let tx = output.clone();
+    engine.register_fn("metric", move |name: &str, value: Dynamic| {
+        let _ = tx.send(ScriptCommand::Metric(name.to_string(), value.to_string()));
+    });
+    engine.register_fn("clear", move || {
+        let _ = output.send(ScriptCommand::Clear);
+    });
+    engine
+}
+
+/// The compiled script and the state its functions share.
+struct Script {
+    engine: Engine,
+    ast: AST,
+    this: Dynamic,
+    on_touch: bool,
+    on_libinput: bool,
+}
+
+impl Script {
+    fn load(engine: Engine, path: &Path, extents: Option<(i32, i32)>) -> Result<Self, String> {
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| e.to_string())?;
+        let defines = |name: &str| {
+            ast.iter_functions()
+                .any(|f| f.name == name && f.params.len() == 2)
+        };
+        let (on_touch, on_libinput) = (defines("on_touch"), defines("on_libinput"));
+        let (x_max, y_max) = extents.unwrap_or((0, 0));
+        let mut this = Map::new();
+        this.insert("x_max".into(), (x_max as i64).into());
+        this.insert("y_max".into(), (y_max as i64).into());
+        // Run the top level once, for whatever it prints
+        engine.run_ast(&ast).map_err(|e| e.to_string())?;
+        Ok(Self {
+            engine,
+            ast,
+            this: this.into(),
+            on_touch,
+            on_libinput,
+        })
+    }
+
+    fn call(&mut self, name: &str, t: f64, arg: Map) -> Result<(), String> {
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.this);
+        self.engine
+            .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, name, (t, arg))
+            .map(|_| ())
+            .map_err(|e| format!("{}: {}", name, e))
+    }
+
+    fn handle(&mut self, event: ScriptEvent) -> Result<(), String> {
+        match event {
+            ScriptEvent::Touch {
+                t,
+                touches,
+                buttons,
+            } if self.on_touch => self.call("on_touch", t, touch_map(&touches, &buttons)),
+            ScriptEvent::Libinput { t, event } if self.on_libinput => {
+                let event = self
+                    .engine
+                    .parse_json(format!("{{{}}}", json_fields(&event)), true)
+                    .map_err(|e| e.to_string())?;
+                self.call("on_libinput", t, event)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub struct ScriptHook {
+    pub path: PathBuf,
+    input: Option<BoundedSender<ScriptEvent>>,
+    output: BoundedReceiver<ScriptCommand>,
+    stop: Arc<AtomicBool>,
+    pub annotations: Vec<Annotation>,
+    /// In the order the script first reported them
+    pub metrics: Vec<(String, String)>,
+    /// Why the script stopped, once it has
+    pub error: Option<String>,
+}
+
+impl ScriptHook {
+    /// Compile the script at `path` and run it on a thread of its own.
+    pub fn spawn(path: &Path, extents: Option<(i32, i32)>) -> io::Result<Self> {
+        let (input, input_rx) = channel::bounded::<ScriptEvent>(CHANNEL_CAPACITY);
+        let (output_tx, output) = channel::bounded(CHANNEL_CAPACITY);
+        let stop = Arc::new(AtomicBool::new(false));
+        let (loaded_tx, loaded_rx) = mpsc::channel();
+        let engine = {
+            let (output_tx, stop) = (output_tx.clone(), stop.clone());
+            move || engine(output_tx, stop)
+        };
+        let script_path = path.to_path_buf();
+        // The engine isn't Send, so it's made on the thread that runs it
+        thread::spawn(move || {
+            let mut script = match Script::load(engine(), &script_path, extents) {
+                Ok(script) => {
+                    let _ = loaded_tx.send(Ok(()));
+                    script
+                }
+                Err(e) => {
+                    let _ = loaded_tx.send(Err(e));
+                    return;
+                }
+            };
+            while let Ok(event) = input_rx.recv() {
+                if let Err(e) = script.handle(event) {
+                    let _ = output_tx.send(ScriptCommand::Stopped(e));
+                    return;
+                }
+            }
+        });
+        match loaded_rx.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(io::Error::new(io::ErrorKind::InvalidInput, e)),
+            Err(_) => return Err(io::Error::other("the script thread panicked")),
+        }
+
+        log_info!(target: "script", "started: {}", path.display());
+        Ok(Self {
+            path: path.to_path_buf(),
+            input: Some(input),
+            output,
+            stop,
+            annotations: Vec::new(),
+            metrics: Vec::new(),
+            error: None,
+        })
+    }
+
+    pub fn on_touch(&self, t: f64, touches: &[TouchData], buttons: &ButtonState) {
+        self.send(ScriptEvent::Touch {
+            t,
+            touches: touches.to_vec(),
+            buttons: *buttons,
+        });
+    }
+
+    pub fn on_libinput(&self, t: f64, event: &LibinputEvent) {
+        self.send(ScriptEvent::Libinput {
+            t,
+            event: event.clone(),
+        });
+    }
+
+    fn send(&self, event: ScriptEvent) {
+        if let (Some(input), None) = (&self.input, &self.error) {
+            let _ = input.send(event);
+        }
+    }
+
+    /// Reports dropped because the script fell behind.
+    pub fn dropped(&self) -> u64 {
+        self.input.as_ref().map_or(0, |i| i.dropped())
+    }
+
+    /// The script's output queue, to repaint when it draws something.
+    pub fn output(&self) -> &BoundedReceiver<ScriptCommand> {
+        &self.output
+    }
+
+    /// Apply what the script asked for since the last call.
+    pub fn poll(&mut self) {
+        for command in self.output.try_iter() {
+            match command {
+                ScriptCommand::Annotate(annotation) => {
+                    if self.annotations.len() >= MAX_ANNOTATIONS {
+                        self.annotations.remove(0);
+                    }
+                    self.annotations.push(annotation);
+                }
+                ScriptCommand::Metric(name, value) => {
+                    match self.metrics.iter_mut().find(|(n, _)| *n == name) {
+                        Some(metric) => metric.1 = value,
+                        None => self.metrics.push((name, value)),
+                    }
+                }
+                ScriptCommand::Clear => self.annotations.clear(),
+                ScriptCommand::Stopped(error) => {
+                    log_warn!(target: "script", "stopped: {}", error);
+                    self.error = Some(error);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ScriptHook {
+    fn drop(&mut self) {
+        // Ends the thread once it's done with the event at hand, or at once
+        // if the script is stuck in a loop
+        self.input = None;
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(name: &str, source: &str) -> io::Result<ScriptHook> {
+        let path = std::env::temp_dir().join(format!(
+            "tapview-script-{}-{}.rhai",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, source).unwrap();
+        let script = ScriptHook::spawn(&path, Some((1000, 500)));
+        std::fs::remove_file(&path).unwrap();
+        script
+    }
+
+    #[test]
+    fn draws_what_the_script_asks_for() {
+        let hook = script(
+            "draw",
+            r#"
+            fn on_touch(t, report) {
+                this.reports = (this.reports ?? 0) + 1;
+                for c in report.contacts {
+                    circle(c.x, c.y, 40);
+                    text(c.x, this.y_max - c.y, `id ${c.id}`);
+                }
+                metric("reports", this.reports);
+                if t > 1.0 { clear(); }
+            }
+            "#,
+        )
+        .unwrap();
+        let mut touches = [TouchData::default(); 2];
+        touches[1] = TouchData {
+            used: true,
+            tracking_id: 7,
+            position_x: 100,
+            position_y: 200,
+            ..Default::default()
+        };
+        hook.on_touch(0.5, &touches, &ButtonState::default());
+        hook.on_touch(1.5, &touches, &ButtonState::default());
+        let mut commands = Vec::new();
+        while commands.len() < 7 {
+            commands.push(hook.output().recv().unwrap());
+        }
+        assert_eq!(
+            commands[..3],
+            [
+                ScriptCommand::Annotate(Annotation::Circle {
+                    x: 100.0,
+                    y: 200.0,
+                    radius: 40.0
+                }),
+                ScriptCommand::Annotate(Annotation::Text {
+                    x: 100.0,
+                    y: 300.0,
+                    text: "id 7".to_string()
+                }),
+                ScriptCommand::Metric("reports".to_string(), "1".to_string()),
+            ]
+        );
+        assert_eq!(
+            commands[5..],
+            [
+                ScriptCommand::Metric("reports".to_string(), "2".to_string()),
+                ScriptCommand::Clear
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_on_an_error() {
+        assert!(script("syntax", "fn on_touch(t, report) {").is_err());
+
+        let hook = script("error", "fn on_touch(t, report) { circle(1, 2); }").unwrap();
+        hook.on_touch(0.0, &[], &ButtonState::default());
+        match hook.output().recv().unwrap() {
+            ScriptCommand::Stopped(e) => assert!(e.starts_with("on_touch"), "{}", e),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+}
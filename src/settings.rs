@@ -0,0 +1,417 @@
+//! Persistent per-device settings, and the few shared by all devices.
+//!
+//! Stored as TOML in `$XDG_CONFIG_HOME/tapview/config.toml` (falling back to
+//! `~/.config`), or `%APPDATA%\tapview\config.toml` on Windows. Each device
+//! gets its own profile keyed by USB/HID vendor and product ID plus its name,
+//! so a laptop with an external touchpad keeps separate settings for both,
+//! and each is applied when that device is picked. Tables from before names
+//! were part of the key are taken over by the first device with those IDs.
+
+use crate::dimensions::ViewTransform;
+use crate::keys::KeyBindings;
+use crate::render::{ClickZones, Colormap, HeatmapScale, ThemeChoice};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tapview_core::discovery::DeviceInfo;
+use tapview_core::heatmap::HeatmapOrientation;
+
+/// Settings that don't depend on the device, in the `[app]` table.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub theme: ThemeChoice,
+}
+
+/// Settings remembered for a single device.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+    /// Theme saved per device before it moved to `[app]`, taken over from
+    /// the first profile loaded.
+    #[serde(rename = "theme", skip_serializing)]
+    legacy_theme: Option<ThemeChoice>,
+    /// Overlay a millimeter grid when the device reports its resolution.
+    pub mm_grid: bool,
+    /// Size contact discs by ABS_MT_PRESSURE.
+    pub pressure_scaling: bool,
+    /// Show the per-slot data table panel.
+    pub slot_table: bool,
+    /// Show the frame/event rate overlay.
+    pub rate_hud: bool,
+    /// Show the session dwell map layer.
+    pub dwell_map: bool,
+    /// Show the log console panel.
+    pub log_console: bool,
+    /// Show the per-slot pressure/size plots panel.
+    pub slot_plots: bool,
+    /// Show the edge-swipe zones and badges.
+    pub edge_zones: bool,
+    /// Width of the edge-swipe zones in millimeters.
+    pub edge_band_mm: f32,
+    /// Show the system cursor widget.
+    pub cursor_view: bool,
+    /// Send desktop notifications.
+    pub notifications: bool,
+    /// Click method overlay, shown on buttonpads only.
+    pub click_zones: ClickZones,
+    /// Trail fade-out time in milliseconds.
+    pub trail_ms: u32,
+    /// Rotation and mirroring of the touch view.
+    pub view_transform: ViewTransform,
+    pub heatmap_orientation: HeatmapOrientation,
+    pub heatmap_colormap: Colormap,
+    /// Draw the heatmap with linear filtering instead of sharp cells.
+    pub heatmap_smooth: bool,
+    pub heatmap_scale: HeatmapScale,
+    /// Heatmap column count override (`--heatmap-cols`).
+    pub heatmap_cols: Option<usize>,
+    /// Heatmap burst report length override (`--burst-len`).
+    pub heatmap_burst_len: Option<usize>,
+    /// Touchpad extents grown from observed touches, for devices that don't
+    /// report their logical range.
+    pub learned_extents: Option<(f32, f32)>,
+    /// Window inner size in points at last exit.
+    pub window_size: Option<(f32, f32)>,
+    /// Window outer position in points at last exit.
+    pub window_pos: Option<(f32, f32)>,
+    /// libinput side panel width at last exit.
+    pub libinput_panel_width: Option<f32>,
+    /// Heatmap bottom panel height at last exit.
+    pub heatmap_panel_height: Option<f32>,
+}
+
+impl Default for DeviceSettings {
+    fn default() -> Self {
+        Self {
+            legacy_theme: None,
+            mm_grid: true,
+            pressure_scaling: true,
+            slot_table: false,
+            rate_hud: false,
+            dwell_map: false,
+            log_console: false,
+            slot_plots: false,
+            edge_zones: false,
+            edge_band_mm: 5.0,
+            cursor_view: false,
+            notifications: false,
+            click_zones: ClickZones::default(),
+            trail_ms: 500,
+            view_transform: ViewTransform::default(),
+            heatmap_orientation: HeatmapOrientation::default(),
+            heatmap_colormap: Colormap::default(),
+            heatmap_smooth: false,
+            heatmap_scale: HeatmapScale::default(),
+            heatmap_cols: None,
+            heatmap_burst_len: None,
+            learned_extents: None,
+            window_size: None,
+            window_pos: None,
+            libinput_panel_width: None,
+            heatmap_panel_height: None,
+        }
+    }
+}
+
+/// On-disk layout of the settings file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SettingsFile {
+    /// None in files from before there was an `[app]` table
+    app: Option<AppSettings>,
+    /// Keyboard shortcuts, shared by all devices.
+    keys: KeyBindings,
+    /// Commands run on gestures, shared by all devices.
+    gestures: BTreeMap<String, String>,
+    devices: BTreeMap<String, DeviceSettings>,
+}
+
+impl SettingsFile {
+    /// Pick the profile `key`, taking over the table from before names were
+    /// part of the key, and the theme from before it was shared.
+    fn take_over(&mut self, key: &str, legacy: &str) {
+        if !self.devices.contains_key(key) {
+            if let Some(settings) = self.devices.remove(legacy) {
+                log_info!(target: "settings", "moving settings of {} to {}", legacy, key);
+                self.devices.insert(key.to_string(), settings);
+            }
+        }
+        if let Some(device) = self.devices.get_mut(key) {
+            log_info!(target: "settings", "using profile {}", key);
+            let theme = device.legacy_theme.take();
+            if self.app.is_none() {
+                self.app = Some(AppSettings {
+                    theme: theme.unwrap_or_default(),
+                });
+            }
+        }
+    }
+}
+
+/// Loaded settings file plus the key of the device currently in use.
+pub struct SettingsStore {
+    path: Option<PathBuf>,
+    key: String,
+    file: SettingsFile,
+    /// The file couldn't be parsed or backed up, so it isn't saved over
+    read_only: bool,
+}
+
+impl SettingsStore {
+    /// Load the profile for `device`, whose name is `name` if known. A missing
+    /// or unreadable file yields defaults. A malformed one is reported and
+    /// copied to `config.toml.bak` before the next save replaces it, or kept
+    /// as it is if the copy fails.
+    pub fn load(device: &DeviceInfo, name: Option<&str>) -> Self {
+        let path = config_path();
+        let (mut file, read_only) = path.as_deref().map(load_file).unwrap_or_default();
+        let key = device_key(device, name);
+        file.take_over(&key, &device_key(device, None));
+        Self {
+            path,
+            key,
+            file,
+            read_only,
+        }
+    }
+
+    pub fn device(&self) -> DeviceSettings {
+        self.file
+            .devices
+            .get(&self.key)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn device_mut(&mut self) -> &mut DeviceSettings {
+        self.file.devices.entry(self.key.clone()).or_default()
+    }
+
+    pub fn app(&self) -> AppSettings {
+        self.file.app.clone().unwrap_or_default()
+    }
+
+    pub fn app_mut(&mut self) -> &mut AppSettings {
+        self.file.app.get_or_insert_with(AppSettings::default)
+    }
+
+    pub fn keys(&self) -> KeyBindings {
+        self.file.keys.clone()
+    }
+
+    pub fn gestures(&self) -> &BTreeMap<String, String> {
+        &self.file.gestures
+    }
+
+    pub fn keys_mut(&mut self) -> &mut KeyBindings {
+        &mut self.file.keys
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if self.read_only {
+            return Err(io::Error::other(format!(
+                "not overwriting {}, which couldn't be parsed or backed up",
+                path.display()
+            )));
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = toml::to_string_pretty(&self.file).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+}
+
+/// The settings shared by all devices, for when no device is opened.
+pub fn app_settings() -> AppSettings {
+    config_path()
+        .and_then(|path| read_file(&path).ok())
+        .and_then(|file| file.app)
+        .unwrap_or_default()
+}
+
+/// Whether `device` has a saved profile, under its name or its IDs alone.
+pub fn has_profile(device: &DeviceInfo, name: Option<&str>) -> bool {
+    let file = config_path()
+        .and_then(|path| read_file(&path).ok())
+        .unwrap_or_default();
+    file.devices.contains_key(&device_key(device, name))
+        || file.devices.contains_key(&device_key(device, None))
+}
+
+/// The settings in `path`, defaults if it can't be read, or why it couldn't
+/// be parsed.
+fn read_file(path: &Path) -> Result<SettingsFile, toml::de::Error> {
+    match fs::read_to_string(path) {
+        Ok(text) => toml::from_str(&text),
+        Err(_) => Ok(SettingsFile::default()),
+    }
+}
+
+/// [`read_file`], backing up a malformed file; true if it couldn't be, so it
+/// must not be saved over.
+fn load_file(path: &Path) -> (SettingsFile, bool) {
+    let e = match read_file(path) {
+        Ok(file) => return (file, false),
+        Err(e) => e,
+    };
+    log_warn!(target: "settings", "ignoring malformed {}: {}", path.display(), e);
+    let backup = backup_path(path);
+    match fs::copy(path, &backup) {
+        Ok(_) => {
+            log_warn!(target: "settings", "saved a copy as {}", backup.display());
+            (SettingsFile::default(), false)
+        }
+        Err(e) => {
+            log_warn!(
+                target: "settings", "couldn't back it up to {} ({}); settings won't be saved",
+                backup.display(), e
+            );
+            (SettingsFile::default(), true)
+        }
+    }
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Table name for a device: `vvvv:pppp` in hex, or `unknown` when the IDs
+/// couldn't be read, followed by the device name when known.
+fn device_key(device: &DeviceInfo, name: Option<&str>) -> String {
+    let ids = match (device.vendor_id, device.product_id) {
+        (Some(vid), Some(pid)) => format!("{:04x}:{:04x}", vid, pid),
+        _ => "unknown".to_string(),
+    };
+    match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => format!("{} {}", ids, name),
+        None => ids,
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("tapview").join("config.toml"))
+}
+
+#[cfg(target_os = "windows")]
+fn config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("APPDATA").map(PathBuf::from)?;
+    Some(base.join("tapview").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_file_round_trips() {
+        let mut file = SettingsFile::default();
+        file.devices
+            .insert("06cb:cdff".to_string(), DeviceSettings::default());
+        file.devices.insert(
+            "unknown".to_string(),
+            DeviceSettings {
+                learned_extents: Some((1200.0, 800.0)),
+                window_size: Some((900.0, 600.0)),
+                window_pos: Some((-1280.0, 40.0)),
+                heatmap_cols: Some(40),
+                ..Default::default()
+            },
+        );
+        let text = toml::to_string_pretty(&file).unwrap();
+        let parsed: SettingsFile = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.devices["06cb:cdff"].window_size, None);
+        assert_eq!(
+            parsed.devices["unknown"].learned_extents,
+            Some((1200.0, 800.0))
+        );
+        assert_eq!(parsed.devices["unknown"].heatmap_cols, Some(40));
+        assert_eq!(parsed.devices["unknown"].window_pos, Some((-1280.0, 40.0)));
+    }
+
+    #[test]
+    fn theme_moves_from_the_first_profile_to_the_app_table() {
+        let mut file: SettingsFile =
+            toml::from_str("[devices.\"1234:5678\"]\ntheme = \"dark\"\n").unwrap();
+        file.take_over("1234:5678 Touchpad", "1234:5678");
+        assert_eq!(file.app.as_ref().unwrap().theme, ThemeChoice::Dark);
+        let text = toml::to_string_pretty(&file).unwrap();
+        assert!(text.starts_with("[app]\ntheme = \"dark\""), "{}", text);
+        assert_eq!(text.matches("theme").count(), 1);
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let parsed: SettingsFile =
+            toml::from_str("[devices.\"1234:5678\"]\nmm_grid = false\n").unwrap();
+        let device = &parsed.devices["1234:5678"];
+        assert!(!device.mm_grid);
+        assert!(device.pressure_scaling);
+        assert_eq!(device.trail_ms, 500);
+    }
+
+    #[test]
+    fn backs_up_a_malformed_file_before_it_is_replaced() {
+        let dir = std::env::temp_dir().join(format!("tapview-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, "[devices.\"1234:5678\"\nmm_grid = ").unwrap();
+        let (file, read_only) = load_file(&path);
+        assert!(file.devices.is_empty());
+        assert!(!read_only);
+        assert_eq!(
+            fs::read_to_string(dir.join("config.toml.bak")).unwrap(),
+            fs::read_to_string(&path).unwrap()
+        );
+
+        // Without somewhere to put the copy, the file is left alone
+        fs::remove_file(dir.join("config.toml.bak")).unwrap();
+        fs::create_dir(dir.join("config.toml.bak")).unwrap();
+        let (file, read_only) = load_file(&path);
+        assert!(read_only);
+        let store = SettingsStore {
+            path: Some(path.clone()),
+            key: "1234:5678".to_string(),
+            file,
+            read_only,
+        };
+        assert!(store.save().is_err());
+        assert!(fs::read_to_string(&path).unwrap().ends_with("mm_grid = "));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn profiles_are_keyed_by_ids_and_name() {
+        let pad = |vendor_id, product_id| DeviceInfo {
+            devnode: PathBuf::from("/dev/input/event5"),
+            integration: tapview_core::discovery::Integration::Unknown,
+            vendor_id,
+            product_id,
+        };
+        let synaptics = pad(Some(0x06cb), Some(0xcdff));
+        assert_eq!(
+            device_key(&synaptics, Some("SYNA3602:00 06CB:CDFF Touchpad ")),
+            "06cb:cdff SYNA3602:00 06CB:CDFF Touchpad"
+        );
+        assert_eq!(device_key(&synaptics, None), "06cb:cdff");
+        assert_eq!(device_key(&pad(None, None), Some("")), "unknown");
+        assert_eq!(
+            device_key(&pad(None, Some(1)), Some("PS/2 Touchpad")),
+            "unknown PS/2 Touchpad"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Rolling per-slot history of pressure, touch_major and touch_minor for the
+//! plots panel.
+//!
+//! Pressure that overshoots on touch-down or drops out just before lift-off
+//! makes taps misfire, and none of it shows in the spatial view. Each slot
+//! keeps the last `HISTORY_S` seconds; a contact's samples are one run, so a
+//! lift and a new touch in the same slot plot as separate lines. The plots
+//! show `WINDOW_S` of that following the latest report until zoomed or
+//! dragged back in time.
+
+use std::collections::VecDeque;
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// Seconds of history kept.
+pub const HISTORY_S: f64 = 120.0;
+/// Seconds shown while following the latest report.
+pub const WINDOW_S: f64 = 5.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    pub t: f64,
+    pub tracking_id: i32,
+    pub pressure: i32,
+    pub touch_major: i32,
+    pub touch_minor: i32,
+}
+
+/// One value plotted per slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Series {
+    Pressure,
+    TouchMajor,
+    TouchMinor,
+}
+
+impl Series {
+    pub const ALL: [Series; 3] = [Series::Pressure, Series::TouchMajor, Series::TouchMinor];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Series::Pressure => "pressure",
+            Series::TouchMajor => "touch_major",
+            Series::TouchMinor => "touch_minor",
+        }
+    }
+
+    pub fn value(self, sample: &Sample) -> i32 {
+        match self {
+            Series::Pressure => sample.pressure,
+            Series::TouchMajor => sample.touch_major,
+            Series::TouchMinor => sample.touch_minor,
+        }
+    }
+}
+
+pub struct SlotPlots {
+    /// Samples per slot, oldest first
+    pub slots: [VecDeque<Sample>; MAX_TOUCH_POINTS],
+    /// Time of the latest report
+    pub now: f64,
+    /// Series shown, in `Series::ALL` order
+    pub shown: [bool; 3],
+    /// The plots keep the last `WINDOW_S` on screen, until zoomed or dragged
+    pub follow: bool,
+}
+
+impl Default for SlotPlots {
+    fn default() -> Self {
+        Self {
+            slots: std::array::from_fn(|_| VecDeque::new()),
+            now: 0.0,
+            shown: [true; 3],
+            follow: true,
+        }
+    }
+}
+
+impl SlotPlots {
+    /// Add one report at `t` seconds on the timeline.
+    pub fn add(&mut self, t: f64, touches: &[TouchData]) {
+        // Seeking back in playback starts over
+        if t < self.now {
+            self.clear();
+        }
+        self.now = t;
+        for (samples, touch) in self.slots.iter_mut().zip(touches) {
+            if touch.used {
+                samples.push_back(Sample {
+                    t,
+                    tracking_id: touch.tracking_id,
+                    pressure: touch.pressure,
+                    touch_major: touch.touch_major,
+                    touch_minor: touch.touch_minor,
+                });
+            }
+            while samples.front().is_some_and(|s| s.t < t - HISTORY_S) {
+                samples.pop_front();
+            }
+        }
+    }
+
+    /// The runs of one slot: consecutive samples of the same contact.
+    pub fn runs(&self, slot: usize) -> Vec<Vec<Sample>> {
+        let mut runs: Vec<Vec<Sample>> = Vec::new();
+        for sample in &self.slots[slot] {
+            match runs.last_mut() {
+                Some(run) if run[0].tracking_id == sample.tracking_id => run.push(*sample),
+                _ => runs.push(vec![*sample]),
+            }
+        }
+        runs
+    }
+
+    /// Largest value of `series` in the last `WINDOW_S`, at least 1.
+    pub fn recent_peak(&self, series: Series) -> i32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|s| s.t >= self.now - WINDOW_S)
+            .map(|s| series.value(s))
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    }
+
+    /// Drop the samples, keeping the view.
+    pub fn clear(&mut self) {
+        *self = Self {
+            shown: self.shown,
+            follow: self.follow,
+            ..Self::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: i32, pressure: i32) -> TouchData {
+        TouchData {
+            used: true,
+            tracking_id: id,
+            pressure,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn splits_contacts_and_drops_old_samples() {
+        let mut plots = SlotPlots::default();
+        plots.add(0.0, &[touch(1, 10)]);
+        plots.add(0.1, &[touch(1, 30)]);
+        plots.add(0.2, &[TouchData::default()]);
+        plots.add(0.3, &[touch(2, 20)]);
+
+        let runs = plots.runs(0);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].len(), 2);
+        assert_eq!(runs[1][0].tracking_id, 2);
+        assert_eq!(plots.recent_peak(Series::Pressure), 30);
+        assert_eq!(plots.recent_peak(Series::TouchMajor), 1);
+
+        plots.add(120.2, &[touch(2, 20)]);
+        assert_eq!(plots.slots[0].len(), 2);
+        plots.add(1.0, &[]);
+        assert!(plots.slots[0].is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+//! Tap-and-drag, drag-lock and three-finger-drag detection from the
+//! interpreted button and motion events.
+//!
+//! A left button press without the physical button held was generated by
+//! libinput (or the OS): with fewer than three fingers down it's a tap, with
+//! three or more it's a three-finger drag. Motion while that button is held
+//! is a drag. If all fingers lift and the button stays down, drag lock is
+//! holding it until a timeout or the next tap releases it.
+
+use std::time::Instant;
+
+const BTN_LEFT: u32 = 0x110;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DragPhase {
+    /// Tap button is held but nothing has moved yet
+    Pressed,
+    Dragging,
+    /// Fingers lifted with the button still held
+    Locked,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DragState {
+    pub phase: DragPhase,
+    pub three_finger: bool,
+}
+
+impl DragState {
+    /// Badge text; a tap that hasn't moved is just a click.
+    pub fn label(&self) -> Option<&'static str> {
+        match (self.phase, self.three_finger) {
+            (DragPhase::Pressed, _) => None,
+            (DragPhase::Dragging, false) => Some("DRAG"),
+            (DragPhase::Dragging, true) => Some("3FG DRAG"),
+            (DragPhase::Locked, _) => Some("DRAG LOCK"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TapDrag {
+    pub state: Option<DragState>,
+    fingers: usize,
+    locked_at: Option<Instant>,
+    /// How long the last drag lock held the button after the fingers lifted.
+    pub last_lock_ms: Option<f32>,
+}
+
+impl TapDrag {
+    /// Feed the number of contacts down at `time`.
+    pub fn on_touch(&mut self, fingers: usize, time: Instant) {
+        self.fingers = fingers;
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+        if fingers == 0 {
+            if state.phase == DragPhase::Dragging {
+                state.phase = DragPhase::Locked;
+                self.locked_at = Some(time);
+            }
+        } else if state.phase == DragPhase::Locked {
+            // Touching again continues the drag
+            state.phase = DragPhase::Dragging;
+            self.locked_at = None;
+        }
+    }
+
+    /// Feed an interpreted button event; `physical` is whether the touchpad's
+    /// own button is held.
+    pub fn on_button(&mut self, button: u32, pressed: bool, physical: bool, time: Instant) {
+        if button != BTN_LEFT {
+            return;
+        }
+        if pressed {
+            self.state = (!physical).then_some(DragState {
+                phase: DragPhase::Pressed,
+                three_finger: self.fingers >= 3,
+            });
+            self.locked_at = None;
+        } else if self.state.take().is_some() {
+            if let Some(locked) = self.locked_at.take() {
+                self.last_lock_ms =
+                    Some(time.saturating_duration_since(locked).as_secs_f32() * 1000.0);
+            }
+        }
+    }
+
+    pub fn on_motion(&mut self) {
+        if let Some(state) = self.state.as_mut() {
+            if state.phase == DragPhase::Pressed {
+                state.phase = DragPhase::Dragging;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn tap_drag_then_lock() {
+        let t0 = Instant::now();
+        let mut d = TapDrag::default();
+        // Tap, then touch again and move
+        d.on_touch(1, t0);
+        d.on_touch(0, t0);
+        d.on_touch(1, t0);
+        d.on_button(BTN_LEFT, true, false, t0);
+        assert_eq!(d.state.unwrap().label(), None);
+        d.on_motion();
+        assert_eq!(d.state.unwrap().label(), Some("DRAG"));
+
+        // Lift: drag lock keeps the button down until released
+        d.on_touch(0, t0);
+        assert_eq!(d.state.unwrap().phase, DragPhase::Locked);
+        d.on_button(BTN_LEFT, false, false, t0 + Duration::from_millis(300));
+        assert_eq!(d.state, None);
+        assert_eq!(d.last_lock_ms, Some(300.0));
+    }
+
+    #[test]
+    fn physical_click_and_three_fingers() {
+        let t0 = Instant::now();
+        let mut d = TapDrag::default();
+        d.on_touch(1, t0);
+        d.on_button(BTN_LEFT, true, true, t0);
+        d.on_motion();
+        assert_eq!(d.state, None);
+        d.on_button(BTN_LEFT, false, true, t0);
+
+        d.on_touch(3, t0);
+        d.on_button(BTN_LEFT, true, false, t0);
+        d.on_motion();
+        assert_eq!(d.state.unwrap().label(), Some("3FG DRAG"));
+    }
+}
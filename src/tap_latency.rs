@@ -0,0 +1,109 @@
+//! Tap-to-click latency: time from the last finger lifting after a short
+//! touch to the button press libinput (or the OS) emits for the tap.
+//!
+//! Both ends are stamped when the backend threads read them, so the result
+//! includes libinput's tap timeout but not compositor or client delays. The
+//! evdev thread polls every 5 ms when idle, which bounds the resolution.
+
+use std::time::{Duration, Instant};
+
+/// Longest touch still counted as a tap.
+const MAX_TAP_DURATION: Duration = Duration::from_millis(300);
+/// Longest gap between lift and button press that's attributed to the tap.
+const MAX_LATENCY: Duration = Duration::from_millis(1000);
+/// Samples kept for the histogram and percentiles.
+const SAMPLES_MAX: usize = 500;
+
+#[derive(Default)]
+pub struct TapLatency {
+    touch_down: Option<Instant>,
+    lifted: Option<Instant>,
+    /// Measured latencies in milliseconds, oldest first.
+    pub samples: Vec<f32>,
+}
+
+impl TapLatency {
+    /// Feed whether any contact is down at `time`.
+    pub fn on_touch(&mut self, any_down: bool, time: Instant) {
+        match (any_down, self.touch_down) {
+            (true, None) => {
+                self.touch_down = Some(time);
+                self.lifted = None;
+            }
+            (false, Some(down)) => {
+                self.touch_down = None;
+                if time.duration_since(down) <= MAX_TAP_DURATION {
+                    self.lifted = Some(time);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Feed a button press; completes a sample if a tap just ended.
+    pub fn on_button_press(&mut self, time: Instant) {
+        let Some(lifted) = self.lifted.take() else {
+            return;
+        };
+        // Backend threads can deliver slightly out of order; treat as zero
+        let latency = time.saturating_duration_since(lifted);
+        if latency <= MAX_LATENCY {
+            if self.samples.len() == SAMPLES_MAX {
+                self.samples.remove(0);
+            }
+            self.samples.push(latency.as_secs_f32() * 1000.0);
+        }
+    }
+
+    /// Nearest-rank percentile (0.0..=1.0) of the samples in milliseconds.
+    pub fn percentile(&self, p: f32) -> Option<f32> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_by(f32::total_cmp);
+        let rank = ((p * sorted.len() as f32).ceil() as usize).clamp(1, sorted.len());
+        Some(sorted[rank - 1])
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_lift_to_press() {
+        let t0 = Instant::now();
+        let ms = |n| t0 + Duration::from_millis(n);
+        let mut tap = TapLatency::default();
+
+        tap.on_touch(true, ms(0));
+        tap.on_touch(false, ms(80));
+        tap.on_button_press(ms(260));
+        assert_eq!(tap.samples.len(), 1);
+        assert!((tap.samples[0] - 180.0).abs() < 0.5);
+
+        // A long press isn't a tap
+        tap.on_touch(true, ms(1000));
+        tap.on_touch(false, ms(1600));
+        tap.on_button_press(ms(1700));
+        assert_eq!(tap.samples.len(), 1);
+
+        // A press with no preceding tap is ignored
+        tap.on_button_press(ms(1800));
+        assert_eq!(tap.samples.len(), 1);
+    }
+
+    #[test]
+    fn percentiles() {
+        let mut tap = TapLatency::default();
+        assert_eq!(tap.percentile(0.5), None);
+        tap.samples = (1..=20).map(|v| v as f32).collect();
+        assert_eq!(tap.percentile(0.5), Some(10.0));
+        assert_eq!(tap.percentile(0.95), Some(19.0));
+    }
+}
@@ -0,0 +1,153 @@
+//! Tap durations and double-tap intervals, against the thresholds that
+//! decide them: libinput's tap timeout for how long a tap may last, and the
+//! desktop's double-click time for how far apart two taps may be.
+//!
+//! Most "double-tap sometimes doesn't work" reports are taps that miss one
+//! of these by a few tens of milliseconds, so those are counted separately.
+//! The interval is taken lift to lift, since that's when tap-to-click sends
+//! each press.
+
+use std::time::Instant;
+
+/// libinput's tap timeout: touches longer than this aren't taps.
+pub const TAP_TIMEOUT_MS: f32 = 180.0;
+/// Touches up to this long are kept, to show the ones just over the timeout.
+pub const DURATION_MAX_MS: f32 = 400.0;
+/// How far over a threshold still counts as barely missing it.
+pub const NEAR_MISS_MS: f32 = 60.0;
+/// Samples kept for each histogram.
+const SAMPLES_MAX: usize = 500;
+
+pub struct TapTiming {
+    /// The desktop's double-click time
+    pub double_click_ms: f32,
+    touch_down: Option<Instant>,
+    /// Lift of the last tap, for the next interval
+    last_tap: Option<Instant>,
+    /// Durations of short touches and intervals between taps, in ms
+    pub durations: Vec<f32>,
+    pub intervals: Vec<f32>,
+}
+
+impl Default for TapTiming {
+    fn default() -> Self {
+        Self {
+            double_click_ms: double_click_time(),
+            touch_down: None,
+            last_tap: None,
+            durations: Vec::new(),
+            intervals: Vec::new(),
+        }
+    }
+}
+
+fn push(samples: &mut Vec<f32>, ms: f32) {
+    if samples.len() == SAMPLES_MAX {
+        samples.remove(0);
+    }
+    samples.push(ms);
+}
+
+impl TapTiming {
+    /// Feed whether any contact is down at `time`.
+    pub fn on_touch(&mut self, any_down: bool, time: Instant) {
+        match (any_down, self.touch_down) {
+            (true, None) => self.touch_down = Some(time),
+            (false, Some(down)) => {
+                self.touch_down = None;
+                let ms = time.saturating_duration_since(down).as_secs_f32() * 1000.0;
+                if ms > DURATION_MAX_MS {
+                    self.last_tap = None;
+                    return;
+                }
+                push(&mut self.durations, ms);
+                if ms > TAP_TIMEOUT_MS {
+                    self.last_tap = None;
+                    return;
+                }
+                if let Some(last) = self.last_tap {
+                    let interval = time.saturating_duration_since(last).as_secs_f32() * 1000.0;
+                    if interval <= 2.0 * self.double_click_ms {
+                        push(&mut self.intervals, interval);
+                    }
+                }
+                self.last_tap = Some(time);
+            }
+            _ => {}
+        }
+    }
+
+    /// Durations over the tap timeout by less than `NEAR_MISS_MS`.
+    pub fn near_miss_durations(&self) -> usize {
+        near_misses(&self.durations, TAP_TIMEOUT_MS)
+    }
+
+    /// Intervals over the double-click time by less than `NEAR_MISS_MS`.
+    pub fn near_miss_intervals(&self) -> usize {
+        near_misses(&self.intervals, self.double_click_ms)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self {
+            double_click_ms: self.double_click_ms,
+            ..Self::default()
+        };
+    }
+}
+
+fn near_misses(samples: &[f32], threshold: f32) -> usize {
+    samples
+        .iter()
+        .filter(|&&ms| ms > threshold && ms <= threshold + NEAR_MISS_MS)
+        .count()
+}
+
+/// The system double-click time in ms.
+#[cfg(target_os = "windows")]
+fn double_click_time() -> f32 {
+    unsafe { windows::Win32::UI::Input::KeyboardAndMouse::GetDoubleClickTime() as f32 }
+}
+
+/// GTK's default; desktops rarely change it and there's no common way to ask.
+#[cfg(not(target_os = "windows"))]
+fn double_click_time() -> f32 {
+    400.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn records_durations_and_intervals_and_near_misses() {
+        let t0 = Instant::now();
+        let ms = |n| t0 + Duration::from_millis(n);
+        let mut taps = TapTiming {
+            double_click_ms: 400.0,
+            ..Default::default()
+        };
+        // Two taps 420 ms apart lift to lift: a double-tap just missed
+        taps.on_touch(true, ms(0));
+        taps.on_touch(false, ms(100));
+        taps.on_touch(true, ms(420));
+        taps.on_touch(false, ms(520));
+        assert_eq!(taps.durations.len(), 2);
+        assert_eq!(taps.intervals.len(), 1);
+        assert!((taps.intervals[0] - 420.0).abs() < 0.5);
+        assert_eq!(taps.near_miss_intervals(), 1);
+
+        // Slightly too long to be a tap; doesn't start an interval
+        taps.on_touch(true, ms(700));
+        taps.on_touch(false, ms(900));
+        assert_eq!(taps.near_miss_durations(), 1);
+        taps.on_touch(true, ms(1000));
+        taps.on_touch(false, ms(1050));
+        assert_eq!(taps.intervals.len(), 1);
+
+        // Long presses aren't recorded at all
+        taps.on_touch(true, ms(2000));
+        taps.on_touch(false, ms(3000));
+        assert_eq!(taps.durations.len(), 4);
+    }
+}
@@ -0,0 +1,168 @@
+//! What-if pressure and size thresholds for writing libinput quirks: each
+//! contact is judged as libinput would under the slider values instead of the
+//! device's, and the values can be copied out as quirk lines.
+//!
+//! A touch begins once its pressure goes above the high end of
+//! `AttrPressureRange` and ends when it drops below the low end; until then
+//! it's too light. Pressure over `AttrPalmPressureThreshold` or touch_major
+//! over `AttrPalmSizeThreshold` makes it a palm for the rest of its life.
+
+use crate::palm::PalmThresholds;
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Accepted,
+    TooLight,
+    Palm,
+}
+
+#[derive(Clone, Copy)]
+struct SlotState {
+    tracking_id: i32,
+    /// Above the high pressure threshold and not yet below the low one
+    down: bool,
+    palm: bool,
+}
+
+pub struct ThresholdSim {
+    pub enabled: bool,
+    /// Without a pressure axis only the size threshold applies
+    pub has_pressure: bool,
+    /// `AttrPressureRange=high:low`
+    pub pressure_high: i32,
+    pub pressure_low: i32,
+    /// 0 disables, as in libinput
+    pub palm_pressure: i32,
+    pub palm_size: i32,
+    /// Top of the pressure sliders
+    pub pressure_max: i32,
+    /// Largest touch_major seen, for the size slider
+    pub size_seen_max: i32,
+    slots: [Option<SlotState>; MAX_TOUCH_POINTS],
+    verdicts: [Option<Verdict>; MAX_TOUCH_POINTS],
+}
+
+impl ThresholdSim {
+    /// Start from the device's current thresholds, or libinput's defaults of
+    /// 12% and 10% up the pressure range for touch begin and end.
+    pub fn new(pressure_range: Option<(i32, i32)>, thresholds: &PalmThresholds) -> Self {
+        let (min, max) = pressure_range.unwrap_or((0, 255));
+        let range = max - min;
+        let (high, low) = thresholds
+            .touch_pressure
+            .unwrap_or((min + range * 12 / 100, min + range * 10 / 100));
+        Self {
+            enabled: false,
+            has_pressure: pressure_range.is_some(),
+            pressure_high: high,
+            pressure_low: low,
+            palm_pressure: thresholds.palm_pressure.unwrap_or(0),
+            palm_size: thresholds.palm_size.unwrap_or(0),
+            pressure_max: max,
+            size_seen_max: 0,
+            slots: [None; MAX_TOUCH_POINTS],
+            verdicts: [None; MAX_TOUCH_POINTS],
+        }
+    }
+
+    pub fn update(&mut self, touches: &[TouchData; MAX_TOUCH_POINTS]) {
+        for (slot, touch) in touches.iter().enumerate() {
+            if !touch.used {
+                self.slots[slot] = None;
+                self.verdicts[slot] = None;
+                continue;
+            }
+            self.size_seen_max = self.size_seen_max.max(touch.touch_major);
+            let mut state = match self.slots[slot] {
+                Some(s) if s.tracking_id == touch.tracking_id => s,
+                _ => SlotState {
+                    tracking_id: touch.tracking_id,
+                    down: !self.has_pressure,
+                    palm: false,
+                },
+            };
+            if self.has_pressure {
+                if !state.down && touch.pressure > self.pressure_high {
+                    state.down = true;
+                } else if state.down && touch.pressure < self.pressure_low {
+                    state.down = false;
+                }
+            }
+            let over = |value: i32, threshold: i32| threshold > 0 && value > threshold;
+            state.palm |= (self.has_pressure && over(touch.pressure, self.palm_pressure))
+                || over(touch.touch_major, self.palm_size);
+
+            self.verdicts[slot] = Some(if state.palm {
+                Verdict::Palm
+            } else if state.down {
+                Verdict::Accepted
+            } else {
+                Verdict::TooLight
+            });
+            self.slots[slot] = Some(state);
+        }
+    }
+
+    /// How the contact in `slot` fares under the simulated thresholds.
+    pub fn verdict(&self, slot: usize) -> Option<Verdict> {
+        self.verdicts.get(slot).copied().flatten()
+    }
+
+    /// The thresholds as lines for a quirks file section.
+    pub fn quirks(&self) -> String {
+        let mut lines = Vec::new();
+        if self.has_pressure {
+            lines.push(format!(
+                "AttrPressureRange={}:{}",
+                self.pressure_high, self.pressure_low
+            ));
+            if self.palm_pressure > 0 {
+                lines.push(format!("AttrPalmPressureThreshold={}", self.palm_pressure));
+            }
+        }
+        if self.palm_size > 0 {
+            lines.push(format!("AttrPalmSizeThreshold={}", self.palm_size));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn judges_touches_against_simulated_thresholds() {
+        let mut sim = ThresholdSim::new(Some((0, 255)), &PalmThresholds::default());
+        assert_eq!((sim.pressure_high, sim.pressure_low), (30, 25));
+        let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+        let mut press = |sim: &mut ThresholdSim, pressure, touch_major| {
+            touches[0] = TouchData {
+                used: true,
+                tracking_id: 1,
+                pressure,
+                touch_major,
+                ..Default::default()
+            };
+            sim.update(&touches);
+            sim.verdict(0)
+        };
+
+        // Hysteresis: begins above 30, stays down until below 25
+        assert_eq!(press(&mut sim, 28, 5), Some(Verdict::TooLight));
+        assert_eq!(press(&mut sim, 40, 5), Some(Verdict::Accepted));
+        assert_eq!(press(&mut sim, 27, 5), Some(Verdict::Accepted));
+        assert_eq!(press(&mut sim, 20, 5), Some(Verdict::TooLight));
+
+        // A palm stays one even once it's small again
+        sim.palm_size = 10;
+        assert_eq!(press(&mut sim, 40, 12), Some(Verdict::Palm));
+        assert_eq!(press(&mut sim, 40, 5), Some(Verdict::Palm));
+        assert_eq!(sim.size_seen_max, 12);
+        assert_eq!(
+            sim.quirks(),
+            "AttrPressureRange=30:25\nAttrPalmSizeThreshold=10"
+        );
+    }
+}
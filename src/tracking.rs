@@ -0,0 +1,159 @@
+//! Tracking-ID lifetime statistics, to catch firmware that drops a contact
+//! and re-creates it mid-swipe.
+//!
+//! A drop is a contact ending and a new one starting close to where it ended
+//! within a few frames; to the user it's one continuous finger. Very short
+//! lifetimes are counted separately since they're either drops that landed
+//! elsewhere or bounce on touch-down.
+
+use std::time::{Duration, Instant};
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// A new ID starting this soon after another ended is a drop candidate...
+const REACQUIRE_WINDOW: Duration = Duration::from_millis(50);
+/// ...if it's this close, as a fraction of the touchpad width.
+const REACQUIRE_DISTANCE: f32 = 0.05;
+/// Lifetimes shorter than this are flagged.
+pub const SHORT_LIFETIME: Duration = Duration::from_millis(40);
+/// Drop positions kept for marking.
+const DROPS_MAX: usize = 200;
+const LIFETIMES_MAX: usize = 2000;
+
+#[derive(Clone, Copy)]
+struct LiveContact {
+    tracking_id: i32,
+    start: Instant,
+    last: (i32, i32),
+}
+
+#[derive(Default)]
+pub struct TrackingStats {
+    slots: [Option<LiveContact>; MAX_TOUCH_POINTS],
+    /// Recently ended contacts: end time and last position
+    ended: Vec<(Instant, (i32, i32))>,
+    /// Lifetimes of ended contacts in milliseconds, oldest first
+    pub lifetimes_ms: Vec<f32>,
+    pub ids_seen: usize,
+    pub short_lived: usize,
+    /// Positions where a contact was dropped and re-created, oldest first
+    pub drops: Vec<(i32, i32)>,
+    pub drop_count: usize,
+}
+
+impl TrackingStats {
+    /// Feed the current touches. `width` is the touchpad's X extent in device
+    /// units.
+    pub fn update(&mut self, touches: &[TouchData; MAX_TOUCH_POINTS], width: f32, time: Instant) {
+        self.ended
+            .retain(|(end, _)| time.saturating_duration_since(*end) <= REACQUIRE_WINDOW);
+
+        for (slot, touch) in touches.iter().enumerate() {
+            let live = touch.used.then_some(touch.tracking_id);
+            let current = self.slots[slot].map(|c| c.tracking_id);
+            if current.is_some() && current != live {
+                self.end(slot, time);
+            }
+            let Some(id) = live else {
+                continue;
+            };
+            let pos = (touch.position_x, touch.position_y);
+            match &mut self.slots[slot] {
+                Some(contact) => contact.last = pos,
+                None => {
+                    self.start(pos, width);
+                    self.slots[slot] = Some(LiveContact {
+                        tracking_id: id,
+                        start: time,
+                        last: pos,
+                    });
+                }
+            }
+        }
+    }
+
+    fn end(&mut self, slot: usize, time: Instant) {
+        let Some(contact) = self.slots[slot].take() else {
+            return;
+        };
+        let lifetime = time.saturating_duration_since(contact.start);
+        if lifetime < SHORT_LIFETIME {
+            self.short_lived += 1;
+        }
+        if self.lifetimes_ms.len() == LIFETIMES_MAX {
+            self.lifetimes_ms.remove(0);
+        }
+        self.lifetimes_ms.push(lifetime.as_secs_f32() * 1000.0);
+        self.ended.push((time, contact.last));
+    }
+
+    fn start(&mut self, pos: (i32, i32), width: f32) {
+        self.ids_seen += 1;
+        let near = |(x, y): (i32, i32)| {
+            ((x - pos.0) as f32).hypot((y - pos.1) as f32) <= width * REACQUIRE_DISTANCE
+        };
+        if let Some(i) = self.ended.iter().position(|(_, last)| near(*last)) {
+            self.ended.remove(i);
+            self.drop_count += 1;
+            if self.drops.len() == DROPS_MAX {
+                self.drops.remove(0);
+            }
+            self.drops.push(pos);
+        }
+    }
+
+    /// Median lifetime in milliseconds.
+    pub fn median_lifetime_ms(&self) -> Option<f32> {
+        if self.lifetimes_ms.is_empty() {
+            return None;
+        }
+        let mut sorted = self.lifetimes_ms.clone();
+        sorted.sort_by(f32::total_cmp);
+        Some(sorted[sorted.len() / 2])
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(tracking_id: i32, x: i32, y: i32) -> TouchData {
+        TouchData {
+            used: true,
+            tracking_id,
+            position_x: x,
+            position_y: y,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_drop_and_reacquire() {
+        let t0 = Instant::now();
+        let at = |ms: u64| t0 + Duration::from_millis(ms);
+        let mut stats = TrackingStats::default();
+        let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+
+        touches[0] = touch(1, 100, 100);
+        stats.update(&touches, 1000.0, at(0));
+        touches[0] = touch(1, 200, 100);
+        stats.update(&touches, 1000.0, at(200));
+        // Same finger comes back with a new ID one frame later
+        touches[0] = touch(2, 210, 100);
+        stats.update(&touches, 1000.0, at(210));
+        // A quick bounce far away
+        touches[1] = touch(3, 800, 500);
+        stats.update(&touches, 1000.0, at(220));
+        touches[1].used = false;
+        stats.update(&touches, 1000.0, at(230));
+
+        assert_eq!(stats.ids_seen, 3);
+        assert_eq!(stats.drop_count, 1);
+        assert_eq!(stats.drops, vec![(210, 100)]);
+        assert_eq!(stats.short_lived, 1);
+        assert_eq!(stats.lifetimes_ms, vec![210.0, 10.0]);
+    }
+}
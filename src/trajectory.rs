@@ -0,0 +1,126 @@
+//! Raw vs interpreted trajectory: the path integrated from the primary
+//! contact's evdev position deltas next to the path integrated from
+//! libinput's accelerated pointer motion, for the most recent stroke.
+//!
+//! Raw points are in millimeters when the resolution is known (so X and Y
+//! share a scale), device units otherwise. A stroke starts when a contact
+//! lands on an empty touchpad and both paths restart from the origin.
+
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// Cap on points per path, so a long drag can't grow without bound.
+const POINTS_MAX: usize = 5000;
+
+#[derive(Default)]
+pub struct Trajectory {
+    pub raw: Vec<(f32, f32)>,
+    pub interpreted: Vec<(f32, f32)>,
+    /// Units per mm on X and Y, if known.
+    resolution: Option<(f32, f32)>,
+    /// Tracking ID and position of the contact being integrated.
+    primary: Option<(i32, i32, i32)>,
+    touching: bool,
+}
+
+fn push_delta(path: &mut Vec<(f32, f32)>, dx: f32, dy: f32) {
+    if path.len() >= POINTS_MAX {
+        return;
+    }
+    let (x, y) = path.last().copied().unwrap_or((0.0, 0.0));
+    path.push((x + dx, y + dy));
+}
+
+impl Trajectory {
+    pub fn new(resolution: Option<(f32, f32)>) -> Self {
+        Self {
+            resolution,
+            ..Default::default()
+        }
+    }
+
+    /// Integrate the lowest active slot's movement. Switching to another
+    /// contact rebases without adding a jump, as libinput does.
+    pub fn on_touch(&mut self, touches: &[TouchData; MAX_TOUCH_POINTS]) {
+        let Some(touch) = touches.iter().find(|t| t.used) else {
+            self.touching = false;
+            self.primary = None;
+            return;
+        };
+        if !self.touching {
+            self.touching = true;
+            self.raw = vec![(0.0, 0.0)];
+            self.interpreted = vec![(0.0, 0.0)];
+        }
+        let (id, x, y) = (touch.tracking_id, touch.position_x, touch.position_y);
+        if let Some((last_id, last_x, last_y)) = self.primary {
+            if last_id == id && (x, y) != (last_x, last_y) {
+                let (sx, sy) = self.resolution.unwrap_or((1.0, 1.0));
+                push_delta(
+                    &mut self.raw,
+                    (x - last_x) as f32 / sx,
+                    (y - last_y) as f32 / sy,
+                );
+            }
+        }
+        self.primary = Some((id, x, y));
+    }
+
+    /// Integrate an accelerated libinput motion event.
+    pub fn on_motion(&mut self, dx: f64, dy: f64) {
+        if self.interpreted.is_empty() {
+            self.interpreted.push((0.0, 0.0));
+        }
+        push_delta(&mut self.interpreted, dx as f32, dy as f32);
+    }
+
+    /// Raw path unit label for the plot.
+    pub fn raw_unit(&self) -> &'static str {
+        if self.resolution.is_some() {
+            "mm"
+        } else {
+            "units"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(tracking_id: i32, x: i32, y: i32) -> TouchData {
+        TouchData {
+            used: true,
+            tracking_id,
+            position_x: x,
+            position_y: y,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn integrates_primary_contact_per_stroke() {
+        let mut traj = Trajectory::new(Some((10.0, 20.0)));
+        let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+
+        touches[0] = touch(1, 100, 100);
+        traj.on_touch(&touches);
+        touches[0] = touch(1, 120, 140);
+        traj.on_touch(&touches);
+        traj.on_motion(3.0, 4.0);
+        assert_eq!(traj.raw, vec![(0.0, 0.0), (2.0, 2.0)]);
+        assert_eq!(traj.interpreted, vec![(0.0, 0.0), (3.0, 4.0)]);
+
+        // A different contact taking over doesn't add a jump
+        touches[0] = touch(2, 500, 500);
+        traj.on_touch(&touches);
+        assert_eq!(traj.raw.len(), 2);
+
+        // Lifting and touching again starts a new stroke
+        touches[0].used = false;
+        traj.on_touch(&touches);
+        touches[0] = touch(3, 0, 0);
+        traj.on_touch(&touches);
+        assert_eq!(traj.raw, vec![(0.0, 0.0)]);
+        assert_eq!(traj.interpreted, vec![(0.0, 0.0)]);
+    }
+}
@@ -0,0 +1,214 @@
+//! `--tuio`: the contacts as TUIO 1.1 cursors (`/tuio/2Dcur`) in OSC bundles
+//! over UDP, for multitouch software that takes TUIO input.
+//!
+//! Every touch report becomes one bundle with `source`, `alive`, one `set`
+//! per contact and `fseq`, as TUIO trackers send them. Positions are a share
+//! of the touch view (0..1, origin top-left), so the view's rotation and
+//! mirroring carry over. Session IDs are the tracking IDs, velocities are in
+//! view widths/heights per second and the acceleration is the change of speed
+//! per second.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use tapview_core::multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+/// Where TUIO clients listen unless told otherwise.
+pub const DEFAULT_TARGET: &str = "127.0.0.1:3333";
+
+/// An OSC argument.
+#[derive(Clone, Debug, PartialEq)]
+enum Arg<'a> {
+    Int(i32),
+    Float(f32),
+    Str(&'a str),
+}
+
+/// Append `s` NUL-terminated and padded to a multiple of four bytes.
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(s.as_bytes());
+    let pad = 4 - s.len() % 4;
+    buf.extend(std::iter::repeat_n(0, pad));
+}
+
+/// One OSC message.
+fn message(address: &str, args: &[Arg]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    put_str(&mut buf, address);
+    let tags: String = std::iter::once(',')
+        .chain(args.iter().map(|arg| match arg {
+            Arg::Int(_) => 'i',
+            Arg::Float(_) => 'f',
+            Arg::Str(_) => 's',
+        }))
+        .collect();
+    put_str(&mut buf, &tags);
+    for arg in args {
+        match arg {
+            Arg::Int(i) => buf.extend_from_slice(&i.to_be_bytes()),
+            Arg::Float(f) => buf.extend_from_slice(&f.to_be_bytes()),
+            Arg::Str(s) => put_str(&mut buf, s),
+        }
+    }
+    buf
+}
+
+/// An OSC bundle of `messages`, to be handled immediately.
+fn bundle(messages: &[Vec<u8>]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    put_str(&mut buf, "#bundle");
+    buf.extend_from_slice(&1u64.to_be_bytes());
+    for message in messages {
+        buf.extend_from_slice(&(message.len() as i32).to_be_bytes());
+        buf.extend_from_slice(message);
+    }
+    buf
+}
+
+/// Last position and speed of a contact, for its velocity.
+#[derive(Clone, Copy, Debug, Default)]
+struct Motion {
+    tracking_id: i32,
+    pos: (f32, f32),
+    speed: f32,
+    t: f64,
+}
+
+pub struct TuioOutput {
+    socket: UdpSocket,
+    pub target: SocketAddr,
+    fseq: i32,
+    last: [Option<Motion>; MAX_TOUCH_POINTS],
+}
+
+impl TuioOutput {
+    /// Send to `target` (host:port) from an ephemeral port.
+    pub fn new(target: &str) -> io::Result<Self> {
+        let target = target.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no address for TUIO target")
+        })?;
+        let bind: SocketAddr = if target.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        Ok(Self {
+            socket: UdpSocket::bind(bind)?,
+            target,
+            fseq: 0,
+            last: [None; MAX_TOUCH_POINTS],
+        })
+    }
+
+    /// Send one report at `t` seconds, for a touch view of `extents`.
+    pub fn send(&mut self, touches: &[TouchData], extents: (f32, f32), t: f64) -> io::Result<()> {
+        let packet = self.frame(touches, extents, t);
+        self.socket.send_to(&packet, self.target)?;
+        Ok(())
+    }
+
+    /// The bundle for one report.
+    fn frame(&mut self, touches: &[TouchData], (w, h): (f32, f32), t: f64) -> Vec<u8> {
+        self.fseq = self.fseq.wrapping_add(1);
+        let mut alive = vec![Arg::Str("alive")];
+        let mut sets = Vec::new();
+        for (slot, touch) in touches.iter().enumerate().take(MAX_TOUCH_POINTS) {
+            if !touch.used {
+                self.last[slot] = None;
+                continue;
+            }
+            let pos = (
+                (touch.position_x as f32 / w.max(1.0)).clamp(0.0, 1.0),
+                (touch.position_y as f32 / h.max(1.0)).clamp(0.0, 1.0),
+            );
+            let (velocity, speed, accel) = match self.last[slot] {
+                Some(last) if last.tracking_id == touch.tracking_id && t > last.t => {
+                    let dt = (t - last.t) as f32;
+                    let velocity = ((pos.0 - last.pos.0) / dt, (pos.1 - last.pos.1) / dt);
+                    let speed = velocity.0.hypot(velocity.1);
+                    (velocity, speed, (speed - last.speed) / dt)
+                }
+                _ => ((0.0, 0.0), 0.0, 0.0),
+            };
+            self.last[slot] = Some(Motion {
+                tracking_id: touch.tracking_id,
+                pos,
+                speed,
+                t,
+            });
+            alive.push(Arg::Int(touch.tracking_id));
+            sets.push(message(
+                "/tuio/2Dcur",
+                &[
+                    Arg::Str("set"),
+                    Arg::Int(touch.tracking_id),
+                    Arg::Float(pos.0),
+                    Arg::Float(pos.1),
+                    Arg::Float(velocity.0),
+                    Arg::Float(velocity.1),
+                    Arg::Float(accel),
+                ],
+            ));
+        }
+
+        let mut messages = vec![
+            message("/tuio/2Dcur", &[Arg::Str("source"), Arg::Str("tapview")]),
+            message("/tuio/2Dcur", &alive),
+        ];
+        messages.extend(sets);
+        messages.push(message(
+            "/tuio/2Dcur",
+            &[Arg::Str("fseq"), Arg::Int(self.fseq)],
+        ));
+        bundle(&messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_osc_messages_and_bundles() {
+        let msg = message("/tuio/2Dcur", &[Arg::Str("fseq"), Arg::Int(7)]);
+        let mut expected = b"/tuio/2Dcur\0,si\0fseq\0\0\0\0".to_vec();
+        expected.extend_from_slice(&7i32.to_be_bytes());
+        assert_eq!(msg, expected);
+
+        let packet = bundle(std::slice::from_ref(&msg));
+        assert_eq!(&packet[..8], b"#bundle\0");
+        assert_eq!(&packet[8..16], &1u64.to_be_bytes());
+        assert_eq!(&packet[16..20], &(msg.len() as i32).to_be_bytes());
+        assert_eq!(&packet[20..], &msg[..]);
+    }
+
+    #[test]
+    fn reports_contacts_with_velocity() {
+        let mut tuio = TuioOutput::new("127.0.0.1:3333").unwrap();
+        let finger = |x| TouchData {
+            used: true,
+            tracking_id: 4,
+            position_x: x,
+            position_y: 50,
+            ..Default::default()
+        };
+        tuio.frame(&[finger(250)], (1000.0, 100.0), 1.0);
+        let packet = tuio.frame(&[finger(500)], (1000.0, 100.0), 1.5);
+        let set = message(
+            "/tuio/2Dcur",
+            &[
+                Arg::Str("set"),
+                Arg::Int(4),
+                Arg::Float(0.5),
+                Arg::Float(0.5),
+                Arg::Float(0.5),
+                Arg::Float(0.0),
+                Arg::Float(1.0),
+            ],
+        );
+        assert!(packet.windows(set.len()).any(|w| w == set.as_slice()));
+        assert_eq!(tuio.fseq, 2);
+
+        tuio.frame(&[TouchData::default()], (1000.0, 100.0), 2.0);
+        assert!(tuio.last[0].is_none());
+    }
+}
@@ -1,54 +1,232 @@
 //! Windows backend for mouse/scroll events, used for the libinput side panel.
 //!
-//! Uses a low-level mouse hook (WH_MOUSE_LL) to capture pointer movement,
-//! button clicks, and scroll events. This is the standard mechanism used by
-//! games and input utilities on Windows.
+//! Uses a low-level mouse hook (WH_MOUSE_LL) to capture pointer movement and
+//! button clicks. This is the standard mechanism used by games and input
+//! utilities on Windows.
 //!
-//! Pinch-to-zoom is detected as Ctrl+scroll (the standard Windows convention).
-//! Swipe and hold gestures are not available as the OS shell consumes them.
+//! A Precision Touchpad's two-finger scroll and pinch reach applications as
+//! wheel events Windows makes up, the pinch with Ctrl held. The hook can't
+//! tell them from a mouse's, so wheel events that come while the touchpad's
+//! RawInput reports have two or more contacts count as the touchpad's:
+//! finger scrolling, or with Ctrl a pinch. A mouse's Ctrl+wheel stays a wheel
+//! scroll. Three- and four-finger swipes go to the shell, which doesn't pass
+//! them on.
+//!
+//! The Precision Touchpad settings from the registry are sent once at startup
+//! as the device report, the counterpart of libinput's device config.
 
-use crate::libinput_state::LibinputEvent;
-use std::sync::mpsc;
+use crate::backend_error::{report, Backend, BackendError};
+use crate::libinput_state::{
+    LibinputDeviceInfo, LibinputEvent, ScrollSource, EVENT_CHANNEL_CAPACITY,
+};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tapview_core::channel::{self, BoundedReceiver, BoundedSender};
+use tapview_core::input::windows_backend;
+use tapview_core::shutdown::Shutdown;
+use windows::core::{w, PCWSTR};
 use windows::Win32::Foundation::*;
+use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
 use windows::Win32::UI::Input::KeyboardAndMouse::GetKeyState;
 use windows::Win32::UI::WindowsAndMessaging::*;
 
 /// Spawn a thread that captures mouse input via a low-level hook and sends
-/// structured events over the returned channel.
-pub fn spawn_windows_input_thread() -> mpsc::Receiver<LibinputEvent> {
-    let (tx, rx) = mpsc::channel();
+/// structured events over the returned channel. The thread unhooks and exits
+/// when `shutdown` is requested.
+pub fn spawn_windows_input_thread(
+    shutdown: Shutdown,
+    errors: BoundedSender<BackendError>,
+) -> (BoundedReceiver<LibinputEvent>, JoinHandle<()>) {
+    let (tx, rx) = channel::bounded(EVENT_CHANNEL_CAPACITY);
 
-    std::thread::spawn(move || {
-        if let Err(e) = run_mouse_hook_loop(tx) {
-            eprintln!("Windows input backend error: {}", e);
+    let thread = std::thread::spawn(move || {
+        let _ = tx.send(LibinputEvent::DeviceInfo(read_ptp_settings()));
+        if let Err(e) = run_mouse_hook_loop(tx, &shutdown) {
+            let message = format!("Windows input backend error: {}", e);
+            report(&errors, BackendError::new(Backend::Libinput, message));
         }
     });
 
-    rx
+    (rx, thread)
+}
+
+const PTP_KEY: PCWSTR = w!("Software\\Microsoft\\Windows\\CurrentVersion\\PrecisionTouchPad");
+
+/// A DWORD under the PrecisionTouchPad key, `None` if it isn't set.
+fn read_ptp_dword(name: PCWSTR) -> Option<u32> {
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            PTP_KEY,
+            name,
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut u32 as *mut std::ffi::c_void),
+            Some(&mut size as *mut u32),
+        )
+    };
+    (status == ERROR_SUCCESS).then_some(value)
+}
+
+fn describe_flag(value: Option<u32>) -> String {
+    match value {
+        Some(0) => "off".into(),
+        Some(_) => "on".into(),
+        None => "n/a".into(),
+    }
+}
+
+fn describe_choice(value: Option<u32>, choices: &[&str]) -> String {
+    match value {
+        Some(v) => choices
+            .get(v as usize)
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| format!("unknown ({})", v)),
+        None => "n/a".into(),
+    }
+}
+
+const SLIDE_ACTIONS: &[&str] = &[
+    "nothing",
+    "switch apps",
+    "switch desktops",
+    "audio and volume",
+];
+const TAP_ACTIONS: &[&str] = &[
+    "nothing",
+    "search",
+    "notification center",
+    "play/pause",
+    "middle button",
+];
+
+/// The user's Precision Touchpad settings as shown in the Settings app.
+fn read_ptp_settings() -> LibinputDeviceInfo {
+    let rows = vec![
+        ("Touchpad", describe_flag(read_ptp_dword(w!("Enabled")))),
+        (
+            "With mouse",
+            describe_flag(read_ptp_dword(w!("LeaveOnWithMouse"))),
+        ),
+        (
+            "Cursor speed",
+            read_ptp_dword(w!("CursorSpeed"))
+                .map(|v| format!("{} / 20", v))
+                .unwrap_or_else(|| "n/a".into()),
+        ),
+        (
+            "Sensitivity",
+            describe_choice(
+                read_ptp_dword(w!("AAPThreshold")),
+                &["most sensitive", "high", "medium", "low"],
+            ),
+        ),
+        (
+            "Tap to click",
+            describe_flag(read_ptp_dword(w!("TapsEnabled"))),
+        ),
+        (
+            "Two-finger tap",
+            describe_flag(read_ptp_dword(w!("TwoFingerTapEnabled"))),
+        ),
+        (
+            "Tap and drag",
+            describe_flag(read_ptp_dword(w!("TapAndDrag"))),
+        ),
+        (
+            "Right-click zone",
+            describe_flag(read_ptp_dword(w!("RightClickZoneEnabled"))),
+        ),
+        (
+            "Two-finger scroll",
+            describe_flag(read_ptp_dword(w!("PanEnabled"))),
+        ),
+        (
+            "Scroll direction",
+            match read_ptp_dword(w!("ScrollDirection")) {
+                Some(0) => "down scrolls up".into(),
+                Some(_) => "down scrolls down".into(),
+                None => "n/a".into(),
+            },
+        ),
+        (
+            "Pinch to zoom",
+            describe_flag(read_ptp_dword(w!("ZoomEnabled"))),
+        ),
+        (
+            "3-finger swipe",
+            describe_choice(read_ptp_dword(w!("ThreeFingerSlideEnabled")), SLIDE_ACTIONS),
+        ),
+        (
+            "3-finger tap",
+            describe_choice(read_ptp_dword(w!("ThreeFingerTapEnabled")), TAP_ACTIONS),
+        ),
+        (
+            "4-finger swipe",
+            describe_choice(read_ptp_dword(w!("FourFingerSlideEnabled")), SLIDE_ACTIONS),
+        ),
+        (
+            "4-finger tap",
+            describe_choice(read_ptp_dword(w!("FourFingerTapEnabled")), TAP_ACTIONS),
+        ),
+    ];
+    LibinputDeviceInfo { rows }
 }
 
 thread_local! {
-    static MOUSE_TX: std::cell::Cell<Option<mpsc::Sender<LibinputEvent>>> = const { std::cell::Cell::new(None) };
+    static MOUSE_TX: std::cell::Cell<Option<BoundedSender<LibinputEvent>>> = const { std::cell::Cell::new(None) };
     static LAST_PT: std::cell::Cell<Option<POINT>> = const { std::cell::Cell::new(None) };
-    /// Tracks cumulative pinch scale during a Ctrl+scroll (pinch-to-zoom) gesture.
-    /// None = no pinch active, Some(scale) = pinch in progress.
+    /// Scale of the pinch in progress, since its first wheel event
     static PINCH_SCALE: std::cell::Cell<Option<f64>> = const { std::cell::Cell::new(None) };
+    /// Thread timer ending the pinch, 0 for none
+    static PINCH_TIMER: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
 }
 
 /// Virtual key code for Ctrl
 const VK_CONTROL: i32 = 0x11;
+/// Ctrl+wheel zooms by about this much per notch (120 units) in browsers and
+/// Office, so a pinch is scaled the same way.
+const PINCH_SCALE_PER_NOTCH: f64 = 0.1;
+/// A pinch ends once no wheel event has come for this long.
+const PINCH_END_MS: u32 = 150;
+/// How recent two contacts on the touchpad have to be for a wheel event to
+/// count as the touchpad's.
+const GESTURE_GRACE: Duration = Duration::from_millis(100);
+
+fn send(event: LibinputEvent) {
+    MOUSE_TX.with(|cell| {
+        let tx = cell.take();
+        if let Some(ref sender) = tx {
+            let _ = sender.send(event);
+        }
+        cell.set(tx);
+    });
+}
 
-fn run_mouse_hook_loop(tx: mpsc::Sender<LibinputEvent>) -> Result<(), Box<dyn std::error::Error>> {
+fn run_mouse_hook_loop(
+    tx: BoundedSender<LibinputEvent>,
+    shutdown: &Shutdown,
+) -> Result<(), Box<dyn std::error::Error>> {
     unsafe {
         MOUSE_TX.set(Some(tx));
 
         let hook = SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_ll_proc), None, 0)
             .map_err(|e| format!("SetWindowsHookExW: {}", e))?;
 
-        eprintln!("Windows mouse input backend started (low-level hook)");
+        log_info!(target: "input", "Windows mouse input backend started (low-level hook)");
 
-        // A message pump is required for WH_MOUSE_LL to work.
+        // Make sure the thread has a message queue before anyone posts to it,
+        // then have shutdown end the message loop below.
         let mut msg = MSG::default();
+        let _ = PeekMessageW(&mut msg, None, 0, 0, PM_NOREMOVE);
+        let thread_id = windows::Win32::System::Threading::GetCurrentThreadId();
+        shutdown.on_request(move || {
+            let _ = PostThreadMessageW(thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        });
+
+        // A message pump is required for WH_MOUSE_LL to work.
         while GetMessageW(&mut msg, None, 0, 0).as_bool() {
             let _ = TranslateMessage(&msg);
             DispatchMessageW(&msg);
@@ -60,29 +238,78 @@ fn run_mouse_hook_loop(tx: mpsc::Sender<LibinputEvent>) -> Result<(), Box<dyn st
     Ok(())
 }
 
-fn end_pinch_if_active(sender: &mpsc::Sender<LibinputEvent>) {
-    PINCH_SCALE.with(|cell| {
-        if cell.get().is_some() {
-            cell.set(None);
-            let _ = sender.send(LibinputEvent::GesturePinchEnd);
-        }
+/// Scroll or, with Ctrl (which Windows holds down for a touchpad pinch),
+/// pinch by `delta` notches, `horizontal` for a tilted wheel.
+unsafe fn on_wheel(delta: f64, horizontal: bool) {
+    let touchpad = windows_backend::multi_touch_within(GESTURE_GRACE);
+    if touchpad && !horizontal && GetKeyState(VK_CONTROL) < 0 {
+        let scale = match PINCH_SCALE.get() {
+            Some(scale) => scale,
+            None => {
+                send(LibinputEvent::GesturePinchBegin { fingers: 2 });
+                1.0
+            }
+        } * (1.0 + delta * PINCH_SCALE_PER_NOTCH);
+        PINCH_SCALE.set(Some(scale));
+        send(LibinputEvent::GesturePinchUpdate {
+            fingers: 2,
+            dx: 0.0,
+            dy: 0.0,
+            dx_unaccel: 0.0,
+            dy_unaccel: 0.0,
+            scale,
+            angle: 0.0,
+        });
+        // A thread timer, run by the hook thread's message loop; passing
+        // its ID again restarts it
+        PINCH_TIMER.set(SetTimer(
+            None,
+            PINCH_TIMER.get(),
+            PINCH_END_MS,
+            Some(end_pinch),
+        ));
+        return;
+    }
+    let source = if touchpad {
+        ScrollSource::Finger
+    } else {
+        ScrollSource::Wheel
+    };
+    let (vert, horiz) = if horizontal {
+        (0.0, delta * 15.0)
+    } else {
+        (-delta * 15.0, 0.0)
+    };
+    send(LibinputEvent::Scroll {
+        source,
+        vert,
+        horiz,
     });
 }
 
+unsafe extern "system" fn end_pinch(_: HWND, _: u32, timer: usize, _: u32) {
+    let _ = KillTimer(None, timer);
+    PINCH_TIMER.set(0);
+    if PINCH_SCALE.take().is_some() {
+        send(LibinputEvent::GesturePinchEnd);
+    }
+}
+
 unsafe extern "system" fn mouse_ll_proc(ncode: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     if ncode >= 0 {
         let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
         let msg = wparam.0 as u32;
 
+        if msg == WM_MOUSEWHEEL || msg == WM_MOUSEHWHEEL {
+            let delta = (info.mouseData >> 16) as i16 as f64 / WHEEL_DELTA as f64;
+            on_wheel(delta, msg == WM_MOUSEHWHEEL);
+        }
+
         MOUSE_TX.with(|cell| {
             let tx = cell.take();
             if let Some(ref sender) = tx {
                 match msg {
                     WM_MOUSEMOVE => {
-                        // End pinch if Ctrl was released
-                        if PINCH_SCALE.with(|c| c.get().is_some()) && GetKeyState(VK_CONTROL) >= 0 {
-                            end_pinch_if_active(sender);
-                        }
                         // Compute delta from last known position
                         LAST_PT.with(|last| {
                             let prev = last.get();
@@ -105,79 +332,42 @@ unsafe extern "system" fn mouse_ll_proc(ncode: i32, wparam: WPARAM, lparam: LPAR
                         let _ = sender.send(LibinputEvent::PointerButton {
                             button: 0x110,
                             pressed: true,
+                            time: Instant::now(),
                         });
                     }
                     WM_LBUTTONUP => {
                         let _ = sender.send(LibinputEvent::PointerButton {
                             button: 0x110,
                             pressed: false,
+                            time: Instant::now(),
                         });
                     }
                     WM_RBUTTONDOWN => {
                         let _ = sender.send(LibinputEvent::PointerButton {
                             button: 0x111,
                             pressed: true,
+                            time: Instant::now(),
                         });
                     }
                     WM_RBUTTONUP => {
                         let _ = sender.send(LibinputEvent::PointerButton {
                             button: 0x111,
                             pressed: false,
+                            time: Instant::now(),
                         });
                     }
                     WM_MBUTTONDOWN => {
                         let _ = sender.send(LibinputEvent::PointerButton {
                             button: 0x112,
                             pressed: true,
+                            time: Instant::now(),
                         });
                     }
                     WM_MBUTTONUP => {
                         let _ = sender.send(LibinputEvent::PointerButton {
                             button: 0x112,
                             pressed: false,
-                        });
-                    }
-                    WM_MOUSEWHEEL => {
-                        let delta = (info.mouseData >> 16) as i16;
-                        let ctrl_down = GetKeyState(VK_CONTROL) < 0;
-
-                        if ctrl_down {
-                            // Ctrl+Scroll = pinch-to-zoom gesture
-                            let scale_delta = delta as f64 / 120.0 * 0.1;
-                            PINCH_SCALE.with(|cell| {
-                                let prev = cell.get();
-                                if prev.is_none() {
-                                    let _ = sender
-                                        .send(LibinputEvent::GesturePinchBegin { fingers: 2 });
-                                }
-                                let new_scale = prev.unwrap_or(1.0) + scale_delta;
-                                cell.set(Some(new_scale));
-                                let _ = sender.send(LibinputEvent::GesturePinchUpdate {
-                                    fingers: 2,
-                                    dx: 0.0,
-                                    dy: 0.0,
-                                    dx_unaccel: 0.0,
-                                    dy_unaccel: 0.0,
-                                    scale: new_scale,
-                                    angle: 0.0,
-                                });
-                            });
-                        } else {
-                            end_pinch_if_active(sender);
-                            let _ = sender.send(LibinputEvent::Scroll {
-                                source: crate::libinput_state::ScrollSource::Wheel,
-                                vert: -(delta as f64) / 120.0 * 15.0,
-                                horiz: 0.0,
-                            });
-                        }
-                    }
-                    WM_MOUSEHWHEEL => {
-                        let delta = (info.mouseData >> 16) as i16;
-                        end_pinch_if_active(sender);
-                        let _ = sender.send(LibinputEvent::Scroll {
-                            source: crate::libinput_state::ScrollSource::Wheel,
-                            vert: 0.0,
-                            horiz: (delta as f64) / 120.0 * 15.0,
+                            time: Instant::now(),
                         });
                     }
                     _ => {}
@@ -0,0 +1,313 @@
+//! Channels from backend threads to the UI that can't grow without limit.
+//!
+//! A bounded channel makes room for a new message when it's full by dropping
+//! the oldest one and counting it, so the last state sent (a lift-off, a
+//! button release) always arrives; a latest-value slot keeps only the newest
+//! message and counts the ones it replaced before they were taken. Either way
+//! a stalled UI shows up as a drop count instead of memory growth.
+//!
+//! The receiver can register a callback run after every send, so a GUI can
+//! repaint when data arrives instead of polling.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard, OnceLock};
+
+type OnSend = Arc<OnceLock<Box<dyn Fn() + Send + Sync>>>;
+
+fn notify(on_send: &OnSend) {
+    if let Some(f) = on_send.get() {
+        f();
+    }
+}
+
+struct Queue<T> {
+    items: Mutex<VecDeque<T>>,
+    /// Signalled on every send, and when the last sender goes
+    ready: Condvar,
+    capacity: usize,
+    senders: AtomicUsize,
+    receiver_gone: AtomicBool,
+    dropped: AtomicU64,
+    on_send: OnSend,
+}
+
+impl<T> Queue<T> {
+    fn items(&self) -> MutexGuard<'_, VecDeque<T>> {
+        self.items.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Sending half of [`bounded`]. Sends never block.
+pub struct BoundedSender<T>(Arc<Queue<T>>);
+
+impl<T> Clone for BoundedSender<T> {
+    fn clone(&self) -> Self {
+        self.0.senders.fetch_add(1, Ordering::Relaxed);
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Drop for BoundedSender<T> {
+    fn drop(&mut self) {
+        if self.0.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // Under the lock, so a receiver about to wait can't miss it
+            let _items = self.0.items();
+            self.0.ready.notify_all();
+        }
+    }
+}
+
+impl<T> BoundedSender<T> {
+    /// Queue `value`, dropping and counting the oldest message if the
+    /// channel is full. Fails only once the receiver is gone.
+    pub fn send(&self, value: T) -> Result<(), mpsc::SendError<T>> {
+        if self.0.receiver_gone.load(Ordering::Acquire) {
+            return Err(mpsc::SendError(value));
+        }
+        {
+            let mut items = self.0.items();
+            if items.len() >= self.0.capacity {
+                items.pop_front();
+                self.0.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            items.push_back(value);
+        }
+        self.0.ready.notify_one();
+        notify(&self.0.on_send);
+        Ok(())
+    }
+
+    /// Messages dropped because the channel was full.
+    pub fn dropped(&self) -> u64 {
+        self.0.dropped.load(Ordering::Relaxed)
+    }
+}
+
+pub struct BoundedReceiver<T>(Arc<Queue<T>>);
+
+impl<T> Drop for BoundedReceiver<T> {
+    fn drop(&mut self) {
+        self.0.receiver_gone.store(true, Ordering::Release);
+    }
+}
+
+impl<T> BoundedReceiver<T> {
+    pub fn try_recv(&self) -> Result<T, mpsc::TryRecvError> {
+        match self.0.items().pop_front() {
+            Some(value) => Ok(value),
+            None if self.0.senders.load(Ordering::Acquire) == 0 => {
+                Err(mpsc::TryRecvError::Disconnected)
+            }
+            None => Err(mpsc::TryRecvError::Empty),
+        }
+    }
+
+    /// Everything queued right now, oldest first.
+    pub fn try_iter(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.try_recv().ok())
+    }
+
+    /// Block for the next message; fails once every sender is gone.
+    pub fn recv(&self) -> Result<T, mpsc::RecvError> {
+        let mut items = self.0.items();
+        loop {
+            if let Some(value) = items.pop_front() {
+                return Ok(value);
+            }
+            if self.0.senders.load(Ordering::Acquire) == 0 {
+                return Err(mpsc::RecvError);
+            }
+            items = self.0.ready.wait(items).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Messages dropped because the channel was full.
+    pub fn dropped(&self) -> u64 {
+        self.0.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Run `f` on the sending thread after each queued message. Only the
+    /// first callback registered sticks.
+    pub fn on_send(&self, f: impl Fn() + Send + Sync + 'static) {
+        let _ = self.0.on_send.set(Box::new(f));
+    }
+}
+
+/// A channel holding at most `capacity` messages (at least one).
+pub fn bounded<T>(capacity: usize) -> (BoundedSender<T>, BoundedReceiver<T>) {
+    let queue = Arc::new(Queue {
+        items: Mutex::new(VecDeque::with_capacity(capacity.max(1))),
+        ready: Condvar::new(),
+        capacity: capacity.max(1),
+        senders: AtomicUsize::new(1),
+        receiver_gone: AtomicBool::new(false),
+        dropped: AtomicU64::new(0),
+        on_send: OnSend::default(),
+    });
+    (BoundedSender(queue.clone()), BoundedReceiver(queue))
+}
+
+/// Spent values kept for [`LatestSender::reuse`].
+const SPARES: usize = 2;
+
+struct Slot<T> {
+    value: Mutex<Option<T>>,
+    replaced: AtomicU64,
+    spares: Mutex<Vec<T>>,
+    on_send: OnSend,
+}
+
+impl<T> Slot<T> {
+    fn keep_spare(&self, value: T) {
+        let mut spares = self.spares.lock().unwrap_or_else(|e| e.into_inner());
+        if spares.len() < SPARES {
+            spares.push(value);
+        }
+    }
+}
+
+/// Sending half of [`latest`].
+pub struct LatestSender<T>(Arc<Slot<T>>);
+
+impl<T> LatestSender<T> {
+    /// Replace the held value. Fails once the receiver is gone.
+    pub fn send(&self, value: T) -> Result<(), T> {
+        if Arc::strong_count(&self.0) == 1 {
+            return Err(value);
+        }
+        let replaced = self
+            .0
+            .value
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(value);
+        if let Some(replaced) = replaced {
+            self.0.replaced.fetch_add(1, Ordering::Relaxed);
+            self.0.keep_spare(replaced);
+        }
+        notify(&self.0.on_send);
+        Ok(())
+    }
+
+    /// A value that was replaced untaken or handed back with
+    /// [`LatestReceiver::recycle`], to reuse its allocation for the next send.
+    pub fn reuse(&self) -> Option<T> {
+        self.0
+            .spares
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop()
+    }
+}
+
+pub struct LatestReceiver<T>(Arc<Slot<T>>);
+
+impl<T> LatestReceiver<T> {
+    /// The newest value, if one arrived since the last take.
+    pub fn take(&self) -> Option<T> {
+        self.0
+            .value
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+
+    /// Values replaced before they were taken.
+    pub fn dropped(&self) -> u64 {
+        self.0.replaced.load(Ordering::Relaxed)
+    }
+
+    /// Hand a value that's no longer needed back to the sender.
+    pub fn recycle(&self, value: T) {
+        self.0.keep_spare(value);
+    }
+
+    /// See [`BoundedReceiver::on_send`].
+    pub fn on_send(&self, f: impl Fn() + Send + Sync + 'static) {
+        let _ = self.0.on_send.set(Box::new(f));
+    }
+}
+
+/// A single-value slot that always holds the newest message.
+pub fn latest<T>() -> (LatestSender<T>, LatestReceiver<T>) {
+    let slot = Arc::new(Slot {
+        value: Mutex::new(None),
+        replaced: AtomicU64::new(0),
+        spares: Mutex::new(Vec::new()),
+        on_send: OnSend::default(),
+    });
+    (LatestSender(slot.clone()), LatestReceiver(slot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded_drops_the_oldest_when_full() {
+        let (tx, rx) = bounded(2);
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(rx.dropped(), 3);
+        assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Empty));
+        let tx2 = tx.clone();
+        tx2.send(5).unwrap();
+        drop(tx);
+        drop(tx2);
+        assert_eq!(rx.recv(), Ok(5));
+        assert_eq!(rx.recv(), Err(mpsc::RecvError));
+        assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Disconnected));
+
+        let (tx, rx) = bounded(1);
+        drop(rx);
+        assert!(tx.send(0).is_err());
+    }
+
+    #[test]
+    fn latest_keeps_newest() {
+        let (tx, rx) = latest();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx.take(), Some(2));
+        assert_eq!(rx.take(), None);
+        tx.send(3).unwrap();
+        assert_eq!(rx.take(), Some(3));
+        assert_eq!(rx.dropped(), 1);
+        drop(rx);
+        assert_eq!(tx.send(4), Err(4));
+    }
+
+    #[test]
+    fn latest_hands_back_spent_values() {
+        let (tx, rx) = latest();
+        assert_eq!(tx.reuse(), None);
+        tx.send(vec![1]).unwrap();
+        tx.send(vec![2]).unwrap();
+        assert_eq!(tx.reuse(), Some(vec![1]));
+        rx.recycle(rx.take().unwrap());
+        assert_eq!(tx.reuse(), Some(vec![2]));
+        for i in 0..5 {
+            rx.recycle(vec![i]);
+        }
+        assert!(tx.reuse().is_some() && tx.reuse().is_some());
+        assert_eq!(tx.reuse(), None);
+    }
+
+    #[test]
+    fn on_send_runs_per_queued_message() {
+        let (tx, rx) = bounded(1);
+        let sends = Arc::new(AtomicU64::new(0));
+        let counter = sends.clone();
+        rx.on_send(move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        tx.send(1).unwrap();
+        // Replaces the first: still something new for the receiver
+        tx.send(2).unwrap();
+        assert_eq!(sends.load(Ordering::Relaxed), 2);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![2]);
+    }
+}
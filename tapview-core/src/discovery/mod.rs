@@ -3,6 +3,7 @@ pub mod udev_discovery;
 #[cfg(target_os = "windows")]
 pub mod windows_discovery;
 
+use crate::input::magic_trackpad;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
@@ -41,6 +42,14 @@ impl std::fmt::Display for DiscoveryError {
 
 impl std::error::Error for DiscoveryError {}
 
+impl DeviceInfo {
+    /// Name of a touchpad we know by its IDs.
+    pub fn model(&self) -> Option<&'static str> {
+        let (vendor, product) = self.vendor_id.zip(self.product_id)?;
+        magic_trackpad::is_magic_trackpad(vendor, product).then_some("Apple Magic Trackpad")
+    }
+}
+
 impl std::fmt::Display for DeviceInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let label = match self.integration {
@@ -48,7 +57,11 @@ impl std::fmt::Display for DeviceInfo {
             Integration::External => " (external)",
             Integration::Unknown => "",
         };
-        write!(f, "{}{}", self.devnode.display(), label)
+        write!(f, "{}{}", self.devnode.display(), label)?;
+        if let Some(model) = self.model() {
+            write!(f, " {}", model)?;
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,211 @@
+use super::{DeviceDiscovery, DeviceInfo, DiscoveryError, Integration};
+use std::path::{Path, PathBuf};
+
+fn read_input_ids(device: &udev::Device) -> (Option<u16>, Option<u16>) {
+    // Try udev properties first (set for USB devices by usb_id builtin).
+    let from_props = || -> Option<(u16, u16)> {
+        let vid = u16::from_str_radix(device.property_value("ID_VENDOR_ID")?.to_str()?, 16).ok()?;
+        let pid = u16::from_str_radix(device.property_value("ID_MODEL_ID")?.to_str()?, 16).ok()?;
+        Some((vid, pid))
+    };
+    if let Some((v, p)) = from_props() {
+        return (Some(v), Some(p));
+    }
+
+    // Fall back to sysfs id/vendor + id/product on the parent inputX device.
+    // The eventX device's syspath is e.g. `.../input/input7/event6`; its parent
+    // is `input7` which carries the id/ directory.
+    let parent = match device.parent() {
+        Some(p) => p,
+        None => return (None, None),
+    };
+    let vid = parent
+        .attribute_value("id/vendor")
+        .and_then(|v| u16::from_str_radix(v.to_str()?, 16).ok());
+    let pid = parent
+        .attribute_value("id/product")
+        .and_then(|v| u16::from_str_radix(v.to_str()?, 16).ok());
+    (vid, pid)
+}
+
+/// Wacom's USB vendor ID
+const WACOM: u16 = 0x056a;
+
+/// The pen node of a pen-and-touch device like a Wacom tablet: an input
+/// device tagged ID_INPUT_TABLET on the same USB device (or HID device, for
+/// I2C) as the touch node `evdev_path`.
+pub fn find_sibling_pen(evdev_path: &Path) -> Option<PathBuf> {
+    let touch = input_device(evdev_path)?;
+    let parent = touch
+        .parent_with_subsystem_devtype("usb", "usb_device")
+        .ok()
+        .flatten()
+        .or_else(|| touch.parent_with_subsystem("hid").ok().flatten())?;
+
+    let mut enumerator = udev::Enumerator::new().ok()?;
+    enumerator.match_subsystem("input").ok()?;
+    enumerator.match_property("ID_INPUT_TABLET", "1").ok()?;
+    let pen = enumerator.scan_devices().ok()?.find(|d| {
+        d.syspath().starts_with(parent.syspath())
+            && d.sysname().to_string_lossy().starts_with("event")
+            && d.devnode().is_some()
+    })?;
+    pen.devnode().map(PathBuf::from)
+}
+
+fn input_device(evdev_path: &Path) -> Option<udev::Device> {
+    let name = evdev_path.file_name()?.to_str()?;
+    let mut enumerator = udev::Enumerator::new().ok()?;
+    enumerator.match_subsystem("input").ok()?;
+    enumerator.match_sysname(name).ok()?;
+    enumerator.scan_devices().ok()?.next()
+}
+
+/// udev properties of the evdev device at `evdev_path`, sorted by name.
+pub fn read_properties(evdev_path: &Path) -> Vec<(String, String)> {
+    let Some(device) = input_device(evdev_path) else {
+        return Vec::new();
+    };
+    let mut properties: Vec<_> = device
+        .properties()
+        .map(|p| {
+            (
+                p.name().to_string_lossy().into_owned(),
+                p.value().to_string_lossy().into_owned(),
+            )
+        })
+        .collect();
+    properties.sort();
+    properties
+}
+
+/// hwdb entries for the evdev device at `evdev_path` as (lookup key,
+/// property, value), looked up the way 60-evdev.rules does: by the input
+/// device's modalias, and by its name with the DMI modalias. Queried through
+/// `systemd-hwdb`, so empty where that isn't installed.
+pub fn read_hwdb_entries(evdev_path: &Path) -> Vec<(String, String, String)> {
+    let Some(input) = input_device(evdev_path).and_then(|d| d.parent()) else {
+        return Vec::new();
+    };
+    let attribute = |name| {
+        input
+            .attribute_value(name)
+            .map(|v| v.to_string_lossy().into_owned())
+    };
+    let dmi = std::fs::read_to_string("/sys/class/dmi/id/modalias").unwrap_or_default();
+    let keys = [
+        attribute("modalias").map(|m| format!("evdev:{}", m)),
+        attribute("name").map(|n| format!("evdev:name:{}:{}", n, dmi.trim())),
+    ];
+    let mut entries = Vec::new();
+    for key in keys.into_iter().flatten() {
+        let Ok(output) = std::process::Command::new("systemd-hwdb")
+            .args(["query", &key])
+            .output()
+        else {
+            break;
+        };
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if let Some((name, value)) = line.split_once('=') {
+                entries.push((key.clone(), name.to_string(), value.to_string()));
+            }
+        }
+    }
+    entries
+}
+
+pub struct UdevDiscovery;
+
+impl DeviceDiscovery for UdevDiscovery {
+    fn find_touchpads() -> Result<Vec<DeviceInfo>, DiscoveryError> {
+        let mut enumerator =
+            udev::Enumerator::new().map_err(|e| DiscoveryError::UdevError(e.to_string()))?;
+
+        enumerator
+            .match_subsystem("input")
+            .map_err(|e| DiscoveryError::UdevError(e.to_string()))?;
+
+        enumerator
+            .match_property("ID_INPUT_TOUCHPAD", "1")
+            .map_err(|e| DiscoveryError::UdevError(e.to_string()))?;
+
+        // Wacom's display tablets put the touch part of their pen and touch
+        // screens on a touchscreen node
+        let mut screens =
+            udev::Enumerator::new().map_err(|e| DiscoveryError::UdevError(e.to_string()))?;
+        screens
+            .match_subsystem("input")
+            .map_err(|e| DiscoveryError::UdevError(e.to_string()))?;
+        screens
+            .match_property("ID_INPUT_TOUCHSCREEN", "1")
+            .map_err(|e| DiscoveryError::UdevError(e.to_string()))?;
+        let wacom_screens = screens
+            .scan_devices()
+            .map_err(|e| DiscoveryError::UdevError(e.to_string()))?
+            .filter(|d| read_input_ids(d).0 == Some(WACOM));
+
+        let mut results = Vec::new();
+
+        for device in enumerator
+            .scan_devices()
+            .map_err(|e| DiscoveryError::UdevError(e.to_string()))?
+            .chain(wacom_screens)
+        {
+            let syspath = device.syspath().to_string_lossy().to_string();
+            if !syspath.contains("/event") {
+                continue;
+            }
+
+            if let Some(devnode) = device.devnode() {
+                let integration = match device.property_value("ID_INPUT_TOUCHPAD_INTEGRATION") {
+                    Some(v) if v == "internal" => Integration::Internal,
+                    Some(v) if v == "external" => Integration::External,
+                    _ => {
+                        // systemd's 70-touchpad.rules skips devices without ID_BUS
+                        // (e.g. I2C touchpads), so fall back to the bus type in the
+                        // sysfs path. I2C and SMBus touchpads are always built-in.
+                        if syspath.contains("/i2c-") || syspath.contains("/rmi4-") {
+                            Integration::Internal
+                        } else {
+                            Integration::Unknown
+                        }
+                    }
+                };
+
+                // USB devices expose ID_VENDOR_ID/ID_MODEL_ID as udev properties.
+                // I2C-HID devices don't, but the parent inputX device has the IDs
+                // in its sysfs id/vendor and id/product attributes.
+                let (vendor_id, product_id) = read_input_ids(&device);
+                log_debug!(
+                    target: "discovery",
+                    "{}: {:?}, {:04x?}:{:04x?} at {}",
+                    devnode.display(),
+                    integration,
+                    vendor_id,
+                    product_id,
+                    syspath
+                );
+
+                results.push(DeviceInfo {
+                    devnode: PathBuf::from(devnode),
+                    integration,
+                    vendor_id,
+                    product_id,
+                });
+            }
+        }
+
+        if results.is_empty() {
+            return Err(DiscoveryError::NotFound);
+        }
+
+        // Sort so internal touchpads come first, then unknown, then external.
+        results.sort_by_key(|d| match d.integration {
+            Integration::Internal => 0,
+            Integration::Unknown => 1,
+            Integration::External => 2,
+        });
+
+        Ok(results)
+    }
+}
@@ -0,0 +1,122 @@
+use super::discovery::HeatmapDevice;
+use super::driver::DriverOptions;
+use super::registers::RegisterWatch;
+use super::HeatmapFrame;
+use super::HidDevice;
+use crate::channel::{self, LatestReceiver, LatestSender};
+use crate::shutdown::Shutdown;
+use std::path::Path;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Spawn a background thread that continuously reads raw capacitive frames
+/// and sends them over a channel. Only the newest frame is kept; frames the
+/// UI didn't take in time are counted as dropped.
+///
+/// Frames are read by the driver of the protocol that claimed the device,
+/// set up with `options`. Between frames it also polls the registers on
+/// `registers` and carries out the command sent on it.
+///
+/// The thread exits, closing the device, within a frame read of `shutdown`
+/// being requested.
+pub fn spawn_heatmap_thread(
+    found: HeatmapDevice,
+    options: DriverOptions,
+    cols_override: Option<usize>,
+    registers: RegisterWatch,
+    shutdown: Shutdown,
+) -> (LatestReceiver<HeatmapFrame>, JoinHandle<()>) {
+    let (tx, rx) = channel::latest();
+
+    let thread = thread::spawn(move || {
+        let dev: Box<dyn HidDevice> = match open_hid_device(&found.path) {
+            Ok(d) => d,
+            Err(e) => {
+                log_error!(target: "heatmap", "failed to open {}: {}", found.path.display(), e);
+                return;
+            }
+        };
+
+        run_heatmap_loop(
+            &*dev,
+            &found,
+            &options,
+            cols_override,
+            &registers,
+            &tx,
+            &shutdown,
+        );
+    });
+
+    (rx, thread)
+}
+
+#[cfg(target_os = "linux")]
+fn open_hid_device(path: &Path) -> std::io::Result<Box<dyn HidDevice>> {
+    Ok(Box::new(super::hidraw::HidrawDevice::open(path)?))
+}
+
+#[cfg(target_os = "windows")]
+fn open_hid_device(path: &Path) -> std::io::Result<Box<dyn HidDevice>> {
+    Ok(Box::new(super::windows_hid::WinHidDevice::open(path)?))
+}
+
+fn run_heatmap_loop(
+    dev: &dyn HidDevice,
+    found: &HeatmapDevice,
+    options: &DriverOptions,
+    cols_override: Option<usize>,
+    registers: &RegisterWatch,
+    tx: &LatestSender<HeatmapFrame>,
+    shutdown: &Shutdown,
+) {
+    let mut driver = match (found.protocol.open)(dev, &found.descriptor, options) {
+        Ok(d) => d,
+        Err(e) => {
+            log_error!(target: "heatmap", "failed to set up {} reader: {}", found.protocol.name, e);
+            return;
+        }
+    };
+    log_info!(target: "heatmap", "{} detected", driver.describe());
+
+    // Display cols can be overridden for stride debugging
+    if let Some(cols) = cols_override {
+        log_info!(target: "heatmap", "display cols overridden to {}", cols);
+    }
+
+    while !shutdown.is_requested() {
+        if let Some(command) = registers.take_command() {
+            let result = command.apply(&mut *driver, dev).map_err(|e| {
+                log_warn!(target: "heatmap", "{:?} failed: {}", command, e);
+                e.to_string()
+            });
+            registers.finish_command(command, result);
+        }
+        registers.poll(Instant::now(), |register| {
+            driver.read_register(dev, register)
+        });
+
+        // Reuse a frame the UI is done with, or one it never took
+        let mut data = tx.reuse().map(|f| f.data).unwrap_or_default();
+
+        // Hardware read always uses the chip's own dimensions
+        match driver.read_frame(dev, &mut data) {
+            Ok(cols) => {
+                let display_cols = cols_override.unwrap_or(cols);
+                let frame = HeatmapFrame {
+                    rows: data.len() / display_cols,
+                    cols: display_cols,
+                    data,
+                };
+                if tx.send(frame).is_err() {
+                    // Receiver dropped, UI closed
+                    break;
+                }
+            }
+            Err(e) => {
+                log_error!(target: "heatmap", "frame read error: {}", e);
+                break;
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::protocol::{burst_read, read_reg, read_user_reg, write_reg};
+use super::protocol::{burst_read, read_reg, read_user_reg, stream_read, write_reg, ReadBuffers};
 use super::HidDevice;
 use std::io;
 
@@ -68,40 +68,80 @@ pub fn read_matrix_dims(dev: &dyn HidDevice, chip: ChipVariant) -> io::Result<(u
     }
 }
 
-/// Read one raw capacitive frame. Returns signed 16-bit values in row-major order.
+/// Read one raw capacitive frame into `out` as signed 16-bit values in
+/// row-major order.
 pub fn read_frame(
     dev: &dyn HidDevice,
     chip: ChipVariant,
     rows: usize,
     cols: usize,
     burst_len: usize,
-) -> io::Result<Vec<i16>> {
+    bufs: &mut ReadBuffers,
+    out: &mut Vec<i16>,
+) -> io::Result<()> {
     let total_bytes = rows * cols * 2;
 
     let raw = match chip {
         ChipVariant::PJP274 | ChipVariant::PJP343 | ChipVariant::PCT1036 => {
-            read_frame_pjp274(dev, rows, cols, total_bytes, burst_len)?
+            read_frame_pjp274(dev, bufs, rows, cols, total_bytes, burst_len)?
         }
         ChipVariant::PJP255 | ChipVariant::PJP215 => {
-            read_frame_pjp255(dev, total_bytes, burst_len)?
+            read_frame_pjp255(dev, bufs, total_bytes, burst_len)?
         }
-        ChipVariant::PLP239 => read_frame_plp239(dev, total_bytes, burst_len)?,
+        ChipVariant::PLP239 => read_frame_plp239(dev, bufs, total_bytes, burst_len)?,
     };
 
-    // Convert LE bytes to i16
-    Ok(raw
-        .chunks_exact(2)
-        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
-        .collect())
+    le_bytes_to_i16(raw, out);
+    Ok(())
 }
 
-fn read_frame_pjp274(
+/// Wait for one frame pushed by the firmware as input reports and read it
+/// into `out`. Returns `Ok(false)` if nothing arrived within `timeout_ms`.
+pub fn read_frame_streamed(
     dev: &dyn HidDevice,
     rows: usize,
     cols: usize,
+    stream_len: usize,
+    timeout_ms: i32,
+    bufs: &mut ReadBuffers,
+    out: &mut Vec<i16>,
+) -> io::Result<bool> {
+    match stream_read(dev, bufs, rows * cols * 2, stream_len, timeout_ms)? {
+        Some(raw) => {
+            le_bytes_to_i16(raw, out);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Replace `out` with `raw` read as little-endian i16 pairs. On little-endian
+/// hosts that's a plain copy.
+fn le_bytes_to_i16(raw: &[u8], out: &mut Vec<i16>) {
+    let count = raw.len() / 2;
+    out.clear();
+    out.reserve(count);
+    #[cfg(target_endian = "little")]
+    unsafe {
+        // i16 has no invalid bit patterns and `out` has room for `count`
+        std::ptr::copy_nonoverlapping(raw.as_ptr(), out.as_mut_ptr() as *mut u8, count * 2);
+        out.set_len(count);
+    }
+    #[cfg(target_endian = "big")]
+    out.extend(
+        raw.chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]])),
+    );
+}
+
+fn read_frame_pjp274<'a>(
+    dev: &dyn HidDevice,
+    bufs: &'a mut ReadBuffers,
+    rows: usize,
+    cols: usize,
     total_bytes: usize,
     burst_len: usize,
-) -> io::Result<Vec<u8>> {
+) -> io::Result<&'a [u8]> {
     // 1. Configure matrix dimensions in IO bank (Bank 6)
     //    0x0E = numDrives-1 (cols), 0x0F = numSenses-1 (rows)
     write_reg(dev, 6, 0x0E, (cols - 1) as u8)?;
@@ -114,7 +154,7 @@ fn read_frame_pjp274(
     write_reg(dev, 6, 0x0A, 0x00)?;
 
     // 4. Burst read
-    let data = burst_read(dev, total_bytes, burst_len)?;
+    let data = burst_read(dev, bufs, total_bytes, burst_len)?;
 
     // 5. Deassert NCS
     write_reg(dev, 6, 0x0A, 0x01)?;
@@ -122,11 +162,12 @@ fn read_frame_pjp274(
     Ok(data)
 }
 
-fn read_frame_pjp255(
+fn read_frame_pjp255<'a>(
     dev: &dyn HidDevice,
+    bufs: &'a mut ReadBuffers,
     total_bytes: usize,
     burst_len: usize,
-) -> io::Result<Vec<u8>> {
+) -> io::Result<&'a [u8]> {
     // 1. Enable frame buffer reading
     write_reg(dev, 1, 0x0D, 0x40)?;
     write_reg(dev, 1, 0x0E, 0x06)?;
@@ -136,7 +177,7 @@ fn read_frame_pjp255(
     write_reg(dev, 2, 0x0A, 0x00)?;
 
     // 3. Burst read
-    let data = burst_read(dev, total_bytes, burst_len)?;
+    let data = burst_read(dev, bufs, total_bytes, burst_len)?;
 
     // 4. Deassert NCS
     write_reg(dev, 2, 0x0A, 0x01)?;
@@ -144,11 +185,12 @@ fn read_frame_pjp255(
     Ok(data)
 }
 
-fn read_frame_plp239(
+fn read_frame_plp239<'a>(
     dev: &dyn HidDevice,
+    bufs: &'a mut ReadBuffers,
     total_bytes: usize,
     burst_len: usize,
-) -> io::Result<Vec<u8>> {
+) -> io::Result<&'a [u8]> {
     // 1. Unlock level-0 protection
     write_reg(dev, 6, 0x20, 0xCC)?;
 
@@ -174,10 +216,22 @@ fn read_frame_plp239(
     write_reg(dev, 6, 0x25, 0x11)?;
 
     // 7. Burst read
-    let data = burst_read(dev, total_bytes, burst_len)?;
+    let data = burst_read(dev, bufs, total_bytes, burst_len)?;
 
     // 8. Finalize
     write_reg(dev, 6, 0x25, 0xDD)?;
 
     Ok(data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_little_endian_pairs_into_reused_buffer() {
+        let mut out = vec![9; 8];
+        le_bytes_to_i16(&[0x01, 0x00, 0xFF, 0xFF, 0x34, 0x12, 0x00], &mut out);
+        assert_eq!(out, [1, -1, 0x1234]);
+    }
+}
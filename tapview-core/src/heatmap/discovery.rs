@@ -1,6 +1,32 @@
+use super::driver::{self, DescriptorSummary, Protocol};
+use crate::shutdown::Shutdown;
 use std::io;
 use std::path::{Path, PathBuf};
 
+/// A HID device some heatmap protocol knows how to read.
+#[derive(Debug)]
+pub struct HeatmapDevice {
+    pub path: PathBuf,
+    pub protocol: &'static Protocol,
+    pub descriptor: DescriptorSummary,
+}
+
+impl HeatmapDevice {
+    fn claim(path: PathBuf, descriptor: DescriptorSummary) -> io::Result<Self> {
+        let protocol = driver::claim(&descriptor).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("no heatmap protocol claims {}", path.display()),
+            )
+        })?;
+        Ok(Self {
+            path,
+            protocol,
+            descriptor,
+        })
+    }
+}
+
 // ── Linux: find sibling hidraw via udev ───────────────────────────────────
 
 #[cfg(target_os = "linux")]
@@ -71,6 +97,13 @@ pub fn find_sibling_hidraw(evdev_path: &Path) -> io::Result<PathBuf> {
 
     for hidraw_dev in hidraw_enum.scan_devices().map_err(io::Error::other)? {
         if let Some(devnode) = hidraw_dev.devnode() {
+            log_debug!(
+                target: "discovery",
+                "{} -> HID {} -> {}",
+                evdev_path.display(),
+                hid_path.display(),
+                devnode.display()
+            );
             return Ok(devnode.to_path_buf());
         }
     }
@@ -81,89 +114,55 @@ pub fn find_sibling_hidraw(evdev_path: &Path) -> io::Result<PathBuf> {
     ))
 }
 
+/// Find the hidraw node next to `evdev_path` and the heatmap protocol its
+/// report descriptor is claimed by.
 #[cfg(target_os = "linux")]
-pub fn determine_burst_report_length(hidraw_path: &Path) -> io::Result<usize> {
-    let hidraw_name = hidraw_path
+pub fn find_heatmap_device(evdev_path: &Path) -> io::Result<HeatmapDevice> {
+    let path = find_sibling_hidraw(evdev_path)?;
+    let hidraw_name = path
         .file_name()
         .and_then(|n| n.to_str())
         .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "bad hidraw path"))?;
-
     let desc_path = format!("/sys/class/hidraw/{}/device/report_descriptor", hidraw_name);
-    let desc = fs::read(desc_path)?;
-
-    parse_report_descriptor_for_burst_len(&desc).ok_or_else(|| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            "could not find Report ID 0x41 ReportCount in HID descriptor",
-        )
-    })
+    let descriptor = DescriptorSummary::parse(&fs::read(desc_path)?);
+    HeatmapDevice::claim(path, descriptor)
 }
 
+/// Block until shutdown or until `retry` returns true, calling it whenever a
+/// hidraw node is added or changes, e.g. once a udev rule has given it new
+/// permissions.
 #[cfg(target_os = "linux")]
-fn parse_report_descriptor_for_burst_len(desc: &[u8]) -> Option<usize> {
-    let mut i = 0;
-    let mut current_report_id: Option<u8> = None;
-    let mut current_report_count: Option<usize> = None;
-
-    while i < desc.len() {
-        let prefix = desc[i];
-
-        // Long item
-        if prefix == 0xFE {
-            if i + 2 >= desc.len() {
-                break;
+pub fn watch_hid_devices(shutdown: &Shutdown, mut retry: impl FnMut() -> bool) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let socket = udev::MonitorBuilder::new()?
+        .match_subsystem("hidraw")?
+        .listen()?;
+    while !shutdown.is_requested() {
+        let mut fd = libc::pollfd {
+            fd: socket.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // Wake up now and then to notice shutdown
+        if unsafe { libc::poll(&mut fd, 1, 500) } < 0 {
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
             }
-            let data_size = desc[i + 1] as usize;
-            i += 3 + data_size;
             continue;
         }
-
-        // Short item
-        let size = match prefix & 0x03 {
-            0 => 0,
-            1 => 1,
-            2 => 2,
-            3 => 4,
-            _ => unreachable!(),
-        };
-
-        if i + 1 + size > desc.len() {
+        let changed = socket.iter().any(|event| {
+            matches!(
+                event.event_type(),
+                udev::EventType::Add | udev::EventType::Change
+            )
+        });
+        if changed && retry() {
             break;
         }
-
-        let tag = prefix & 0xFC;
-        let data = &desc[i + 1..i + 1 + size];
-
-        match tag {
-            // Report ID (Global, tag = 0x84)
-            0x84 => {
-                if let Some(&id) = data.first() {
-                    current_report_id = Some(id);
-                }
-            }
-            // Report Count (Global, tag = 0x94)
-            0x94 => {
-                let count = match size {
-                    1 => data[0] as usize,
-                    2 => u16::from_le_bytes([data[0], data[1]]) as usize,
-                    4 => u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize,
-                    _ => 0,
-                };
-                current_report_count = Some(count);
-            }
-            // Feature (Main, tag = 0xB0)
-            0xB0 if current_report_id == Some(0x41) => {
-                if let Some(count) = current_report_count {
-                    return Some(count);
-                }
-            }
-            _ => {}
-        }
-
-        i += 1 + size;
     }
-
-    None
+    Ok(())
 }
 
 // ── Windows: find HID device for heatmap via SetupAPI ─────────────────────
@@ -179,13 +178,13 @@ use windows::Win32::Foundation::*;
 #[cfg(target_os = "windows")]
 use windows::Win32::Storage::FileSystem::*;
 
-/// Find a HID device suitable for heatmap feature reports and determine its
-/// burst report length. Returns (device_path, burst_len).
+/// Find a HID device a heatmap protocol claims.
 ///
 /// Enumerates all HID devices, looking for one on the same physical hardware
-/// as the touchpad that supports Report ID 0x41 feature reports.
+/// as the touchpad whose reports, as its value caps describe them, some
+/// protocol recognizes.
 #[cfg(target_os = "windows")]
-pub fn find_hid_device_for_heatmap(touchpad_path: &Path) -> io::Result<(PathBuf, usize)> {
+pub fn find_heatmap_device(touchpad_path: &Path) -> io::Result<HeatmapDevice> {
     let parent_id = extract_parent_device_id(touchpad_path);
     unsafe { find_hid_device_for_heatmap_inner(parent_id.as_deref()) }
 }
@@ -197,7 +196,7 @@ pub fn find_hid_device_for_heatmap(touchpad_path: &Path) -> io::Result<(PathBuf,
 /// 2. Internal: `\\?\hid#pixa3854&col02#4&10d8260e&0&0001#{guid}`
 ///    Returns: `pixa3854`
 #[cfg(target_os = "windows")]
-pub(crate) fn extract_parent_device_id(path: &Path) -> Option<String> {
+pub fn extract_parent_device_id(path: &Path) -> Option<String> {
     let path_str = path.to_str()?.to_lowercase();
 
     // Find the hardware ID portion after "hid#"
@@ -217,9 +216,7 @@ pub(crate) fn extract_parent_device_id(path: &Path) -> Option<String> {
 }
 
 #[cfg(target_os = "windows")]
-unsafe fn find_hid_device_for_heatmap_inner(
-    parent_id: Option<&str>,
-) -> io::Result<(PathBuf, usize)> {
+unsafe fn find_hid_device_for_heatmap_inner(parent_id: Option<&str>) -> io::Result<HeatmapDevice> {
     let hid_guid = HidD_GetHidGuid();
 
     let dev_info = SetupDiGetClassDevsW(
@@ -231,7 +228,7 @@ unsafe fn find_hid_device_for_heatmap_inner(
     .map_err(|e| io::Error::other(format!("SetupDiGetClassDevsW: {}", e)))?;
 
     let mut index = 0u32;
-    let mut best_result: Option<(PathBuf, usize)> = None;
+    let mut best_result: Option<HeatmapDevice> = None;
 
     loop {
         let mut interface_data = SP_DEVICE_INTERFACE_DATA {
@@ -259,7 +256,7 @@ unsafe fn find_hid_device_for_heatmap_inner(
     best_result.ok_or_else(|| {
         io::Error::new(
             io::ErrorKind::NotFound,
-            "no HID device with Report ID 0x41 feature report found",
+            "no HID device claimed by a heatmap protocol found",
         )
     })
 }
@@ -269,7 +266,7 @@ unsafe fn check_hid_device_for_heatmap(
     dev_info: HDEVINFO,
     interface_data: &mut SP_DEVICE_INTERFACE_DATA,
     parent_id: Option<&str>,
-) -> Option<(PathBuf, usize)> {
+) -> Option<HeatmapDevice> {
     // Get device path
     let mut required_size = 0u32;
     let _ = SetupDiGetDeviceInterfaceDetailW(
@@ -331,46 +328,8 @@ unsafe fn check_hid_device_for_heatmap(
 
     let mut preparsed_data = PHIDP_PREPARSED_DATA::default();
     let result = if HidD_GetPreparsedData(handle, &mut preparsed_data) {
-        let mut caps = HIDP_CAPS::default();
-        if HidP_GetCaps(preparsed_data, &mut caps) == HIDP_STATUS_SUCCESS {
-            // Check for Report ID 0x41 feature report by looking at feature report byte length
-            // If the device has feature reports, check for burst report
-            if caps.NumberFeatureValueCaps > 0 {
-                // Get feature value caps to find Report ID 0x41
-                let mut num_caps = caps.NumberFeatureValueCaps;
-                let mut value_caps = vec![HIDP_VALUE_CAPS::default(); num_caps as usize];
-                if HidP_GetValueCaps(
-                    HidP_Feature,
-                    value_caps.as_mut_ptr(),
-                    &mut num_caps,
-                    preparsed_data,
-                ) == HIDP_STATUS_SUCCESS
-                {
-                    // Look for a value cap with Report ID 0x41
-                    let burst_cap = value_caps[..num_caps as usize]
-                        .iter()
-                        .find(|vc| vc.ReportID == 0x41);
-
-                    if let Some(vc) = burst_cap {
-                        // ReportCount tells us the burst payload length
-                        let burst_len = vc.ReportCount as usize;
-                        if burst_len > 0 {
-                            Some((PathBuf::from(&device_path), burst_len))
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+        let descriptor = summarize_value_caps(preparsed_data);
+        HeatmapDevice::claim(PathBuf::from(&device_path), descriptor).ok()
     } else {
         None
     };
@@ -383,8 +342,80 @@ unsafe fn check_hid_device_for_heatmap(
     result
 }
 
+/// The reports a device declares, from its value caps.
+#[cfg(target_os = "windows")]
+unsafe fn summarize_value_caps(preparsed: PHIDP_PREPARSED_DATA) -> DescriptorSummary {
+    use super::driver::{DescriptorReport, ReportKind};
+
+    let mut summary = DescriptorSummary::default();
+    let mut caps = HIDP_CAPS::default();
+    if HidP_GetCaps(preparsed, &mut caps) != HIDP_STATUS_SUCCESS {
+        return summary;
+    }
+    for (report_type, kind, count) in [
+        (HidP_Input, ReportKind::Input, caps.NumberInputValueCaps),
+        (HidP_Output, ReportKind::Output, caps.NumberOutputValueCaps),
+        (
+            HidP_Feature,
+            ReportKind::Feature,
+            caps.NumberFeatureValueCaps,
+        ),
+    ] {
+        if count == 0 {
+            continue;
+        }
+        let mut num_caps = count;
+        let mut value_caps = vec![HIDP_VALUE_CAPS::default(); num_caps as usize];
+        if HidP_GetValueCaps(
+            report_type,
+            value_caps.as_mut_ptr(),
+            &mut num_caps,
+            preparsed,
+        ) != HIDP_STATUS_SUCCESS
+        {
+            continue;
+        }
+        for vc in &value_caps[..num_caps as usize] {
+            summary.add(DescriptorReport {
+                id: vc.ReportID,
+                kind,
+                usage_page: vc.UsagePage,
+                count: vc.ReportCount as usize,
+            });
+        }
+    }
+    summary
+}
+
+/// Block until shutdown or until `retry` returns true, calling it every few
+/// seconds. There's no cheap device-arrival notification without a window,
+/// so this polls instead.
+#[cfg(target_os = "windows")]
+pub fn watch_hid_devices(shutdown: &Shutdown, mut retry: impl FnMut() -> bool) -> io::Result<()> {
+    const RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+    const STEP: std::time::Duration = std::time::Duration::from_millis(250);
+
+    let mut waited = std::time::Duration::ZERO;
+    while !shutdown.is_requested() {
+        std::thread::sleep(STEP);
+        waited += STEP;
+        if waited >= RETRY_INTERVAL {
+            waited = std::time::Duration::ZERO;
+            if retry() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read a NUL-terminated UTF-16 string.
+///
+/// # Safety
+///
+/// `ptr` must point to a NUL-terminated UTF-16 string.
 #[cfg(target_os = "windows")]
-pub(crate) unsafe fn pcwstr_to_string(ptr: *const u16) -> String {
+pub unsafe fn pcwstr_to_string(ptr: *const u16) -> String {
     let mut len = 0;
     while *ptr.add(len) != 0 {
         len += 1;
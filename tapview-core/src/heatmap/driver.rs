@@ -0,0 +1,275 @@
+//! How heatmap readers plug in. Each vendor protocol claims devices by what
+//! their report descriptor declares (report IDs, their kind, usage page and
+//! size) and opens a [`ChipDriver`] that reads frames; discovery asks each
+//! protocol in [`PROTOCOLS`] in turn.
+//!
+//! Linux parses the raw descriptor into a [`DescriptorSummary`]; Windows
+//! builds the same summary from the preparsed data's value caps.
+
+use super::registers::{Register, RegisterWrite};
+use super::HidDevice;
+use std::io;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportKind {
+    Input,
+    Output,
+    Feature,
+}
+
+/// One report as declared: its first item's usage page and report count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DescriptorReport {
+    pub id: u8,
+    pub kind: ReportKind,
+    pub usage_page: u16,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DescriptorSummary {
+    pub reports: Vec<DescriptorReport>,
+}
+
+impl DescriptorSummary {
+    /// Summarize a raw report descriptor.
+    pub fn parse(desc: &[u8]) -> Self {
+        let mut summary = Self::default();
+        let (mut usage_page, mut report_id, mut report_count) = (0u16, 0u8, 0usize);
+        let mut stack = Vec::new();
+        let mut i = 0;
+        while i < desc.len() {
+            let prefix = desc[i];
+            if prefix == 0xFE {
+                // Long item
+                let Some(&len) = desc.get(i + 1) else { break };
+                i += 3 + len as usize;
+                continue;
+            }
+            let size = match prefix & 0x03 {
+                3 => 4,
+                n => n as usize,
+            };
+            let Some(data) = desc.get(i + 1..i + 1 + size) else {
+                break;
+            };
+            i += 1 + size;
+            let value = data
+                .iter()
+                .rev()
+                .fold(0u32, |acc, &b| (acc << 8) | b as u32);
+            let kind = match prefix & 0xFC {
+                0x04 => {
+                    usage_page = value as u16;
+                    None
+                }
+                0x84 => {
+                    report_id = value as u8;
+                    None
+                }
+                0x94 => {
+                    report_count = value as usize;
+                    None
+                }
+                0xA4 => {
+                    stack.push((usage_page, report_id, report_count));
+                    None
+                }
+                0xB4 => {
+                    (usage_page, report_id, report_count) = stack.pop().unwrap_or_default();
+                    None
+                }
+                0x80 => Some(ReportKind::Input),
+                0x90 => Some(ReportKind::Output),
+                0xB0 => Some(ReportKind::Feature),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                summary.add(DescriptorReport {
+                    id: report_id,
+                    kind,
+                    usage_page,
+                    count: report_count,
+                });
+            }
+        }
+        summary
+    }
+
+    /// Note a report, unless one with its ID and kind is already known.
+    pub fn add(&mut self, report: DescriptorReport) {
+        if self.report(report.id, report.kind).is_none() {
+            self.reports.push(report);
+        }
+    }
+
+    pub fn report(&self, id: u8, kind: ReportKind) -> Option<&DescriptorReport> {
+        self.reports.iter().find(|r| r.id == id && r.kind == kind)
+    }
+}
+
+/// How a protocol that reads frames in bursts of feature reports picks their
+/// length (`--burst-len`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BurstLen {
+    /// Use this many payload bytes, whatever the descriptor says
+    Fixed(usize),
+    /// Try likely lengths until one checks out
+    Probe,
+}
+
+impl std::str::FromStr for BurstLen {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "probe" => Ok(Self::Probe),
+            _ => match s.parse() {
+                Ok(0) | Err(_) => Err(format!(
+                    "expected a length in bytes or \"probe\", got \"{}\"",
+                    s
+                )),
+                Ok(len) => Ok(Self::Fixed(len)),
+            },
+        }
+    }
+}
+
+/// Overrides for workarounds when a descriptor can't be trusted, and
+/// register writes for what no protocol documents.
+#[derive(Clone, Debug, Default)]
+pub struct DriverOptions {
+    pub burst_len: Option<BurstLen>,
+    /// Try streamed frames where the descriptor declares a report for them
+    /// (`--heatmap-stream`)
+    pub stream: bool,
+    /// Writes that switch automatic level calibration (ALC) on and off
+    /// (`--alc-on`, `--alc-off`)
+    pub alc_on: Vec<RegisterWrite>,
+    pub alc_off: Vec<RegisterWrite>,
+    /// Writes that force a recalibration (`--recalibrate`)
+    pub recalibrate: Vec<RegisterWrite>,
+}
+
+/// Something the UI asks of the controller, carried out by the heatmap
+/// thread between frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DriverCommand {
+    /// Switch automatic level calibration on or off
+    SetAlc(bool),
+    /// Take a new baseline now
+    Recalibrate,
+}
+
+impl DriverCommand {
+    pub fn apply(self, driver: &mut dyn ChipDriver, dev: &dyn HidDevice) -> io::Result<()> {
+        match self {
+            DriverCommand::SetAlc(on) => driver.set_alc(dev, on),
+            DriverCommand::Recalibrate => driver.recalibrate(dev),
+        }
+    }
+}
+
+fn unsupported(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} isn't supported for this controller", what),
+    )
+}
+
+/// Reads frames from one family of controllers.
+pub trait ChipDriver: Send {
+    /// Chip and matrix, for the log.
+    fn describe(&self) -> String;
+
+    /// Read the next frame into `out` as row-major signed values and return
+    /// its column count.
+    fn read_frame(&mut self, dev: &dyn HidDevice, out: &mut Vec<i16>) -> io::Result<usize>;
+
+    /// Read one firmware register, for watching and dumping registers.
+    fn read_register(&mut self, _dev: &dyn HidDevice, _register: Register) -> io::Result<u8> {
+        Err(unsupported("register access"))
+    }
+
+    fn write_register(&mut self, _dev: &dyn HidDevice, _write: RegisterWrite) -> io::Result<()> {
+        Err(unsupported("register access"))
+    }
+
+    /// Switch automatic level calibration, which tracks the untouched
+    /// baseline, on or off.
+    fn set_alc(&mut self, _dev: &dyn HidDevice, _on: bool) -> io::Result<()> {
+        Err(unsupported("switching ALC"))
+    }
+
+    /// Make the firmware drop its baseline and take a new one from what's
+    /// on the pad now.
+    fn recalibrate(&mut self, _dev: &dyn HidDevice) -> io::Result<()> {
+        Err(unsupported("forcing a recalibration"))
+    }
+}
+
+pub type OpenFn =
+    fn(&dyn HidDevice, &DescriptorSummary, &DriverOptions) -> io::Result<Box<dyn ChipDriver>>;
+
+/// A vendor heatmap protocol.
+pub struct Protocol {
+    pub name: &'static str,
+    /// Whether a device with this descriptor speaks the protocol
+    pub claims: fn(&DescriptorSummary) -> bool,
+    /// Identify the chip and set up reading
+    pub open: OpenFn,
+}
+
+impl std::fmt::Debug for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+pub static PROTOCOLS: &[Protocol] = &[super::pixart::PROTOCOL];
+
+/// The first protocol claiming a device with this descriptor.
+pub fn claim(summary: &DescriptorSummary) -> Option<&'static Protocol> {
+    PROTOCOLS.iter().find(|p| (p.claims)(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_reports_and_finds_a_protocol() {
+        #[rustfmt::skip]
+        let desc = [
+            0x06, 0x00, 0xFF, // Usage Page (Vendor FF00)
+            0x85, 0x41,       // Report ID (0x41)
+            0x75, 0x08,       // Report Size (8)
+            0x96, 0x00, 0x02, // Report Count (512)
+            0xB1, 0x02,       // Feature
+            0xB1, 0x02,       // Feature, same report: not a new one
+            0x85, 0x44,       // Report ID (0x44)
+            0x95, 0x80,       // Report Count (128)
+            0x81, 0x02,       // Input
+        ];
+        let summary = DescriptorSummary::parse(&desc);
+        assert_eq!(
+            summary.reports,
+            [
+                DescriptorReport {
+                    id: 0x41,
+                    kind: ReportKind::Feature,
+                    usage_page: 0xFF00,
+                    count: 512
+                },
+                DescriptorReport {
+                    id: 0x44,
+                    kind: ReportKind::Input,
+                    usage_page: 0xFF00,
+                    count: 128
+                },
+            ]
+        );
+        assert_eq!(claim(&summary).map(|p| p.name), Some("PixArt"));
+        assert!(claim(&DescriptorSummary::default()).is_none());
+    }
+}
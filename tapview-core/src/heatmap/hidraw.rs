@@ -1,8 +1,6 @@
 use super::HidDevice;
-use std::fs::OpenOptions;
 use std::io;
 use std::os::fd::{AsRawFd, OwnedFd};
-use std::os::unix::fs::OpenOptionsExt;
 use std::path::Path;
 
 // HIDRAW ioctl numbers computed from the Linux _IOC macro:
@@ -35,13 +33,8 @@ pub struct HidrawDevice {
 
 impl HidrawDevice {
     pub fn open(path: &Path) -> io::Result<Self> {
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .custom_flags(libc::O_NONBLOCK)
-            .open(path)?;
         Ok(Self {
-            fd: OwnedFd::from(file),
+            fd: crate::seat::open(path, libc::O_RDWR | libc::O_NONBLOCK)?,
         })
     }
 }
@@ -76,4 +69,41 @@ impl HidDevice for HidrawDevice {
             Ok(ret as usize)
         }
     }
+
+    fn read_input(&self, buf: &mut [u8], timeout_ms: i32) -> io::Result<usize> {
+        let mut pollfd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ret = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+        if ret < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                return Ok(0);
+            }
+            return Err(err);
+        }
+        if ret == 0 {
+            return Ok(0);
+        }
+
+        let ret = unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        };
+        if ret < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::WouldBlock {
+                Ok(0)
+            } else {
+                Err(err)
+            }
+        } else {
+            Ok(ret as usize)
+        }
+    }
 }
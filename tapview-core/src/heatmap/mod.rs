@@ -0,0 +1,174 @@
+pub mod backend;
+pub mod chips;
+pub mod discovery;
+pub mod driver;
+#[cfg(target_os = "linux")]
+pub mod hidraw;
+pub mod pixart;
+pub mod protocol;
+pub mod registers;
+#[cfg(target_os = "windows")]
+pub mod windows_hid;
+
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// A single frame of raw capacitive heatmap data.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HeatmapFrame {
+    pub rows: usize,
+    pub cols: usize,
+    /// Row-major signed 16-bit capacitance values (rows * cols elements).
+    pub data: Vec<i16>,
+}
+
+impl HeatmapFrame {
+    fn at(&self, row: usize, col: usize) -> i16 {
+        self.data.get(row * self.cols + col).copied().unwrap_or(0)
+    }
+
+    /// Largest magnitude in the frame, for normalizing colors.
+    pub fn max_abs(&self) -> u16 {
+        self.data.iter().fold(0, |max, v| max.max(v.unsigned_abs()))
+    }
+}
+
+/// How a raw frame is reoriented before display, so the grid lines up with
+/// the physical pad and the evdev coordinate system. Persisted per device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeatmapOrientation {
+    pub transpose: bool,
+    pub rotate_90: bool,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl HeatmapOrientation {
+    /// Apply transpose, then a clockwise 90° rotation, then the X/Y flips.
+    pub fn apply(&self, frame: HeatmapFrame) -> HeatmapFrame {
+        let mut out = HeatmapFrame::default();
+        self.apply_into(&frame, &mut out);
+        out
+    }
+
+    /// [`apply`](Self::apply) in one pass, into `out`'s existing buffer.
+    pub fn apply_into(&self, frame: &HeatmapFrame, out: &mut HeatmapFrame) {
+        // Dimensions after the transpose and after the rotation; flips keep them
+        let (rows_t, cols_t) = if self.transpose {
+            (frame.cols, frame.rows)
+        } else {
+            (frame.rows, frame.cols)
+        };
+        let (rows, cols) = if self.rotate_90 {
+            (cols_t, rows_t)
+        } else {
+            (rows_t, cols_t)
+        };
+        out.rows = rows;
+        out.cols = cols;
+        out.data.clear();
+        out.data.reserve(rows * cols);
+        if *self == Self::default() {
+            out.data.extend_from_slice(&frame.data);
+            return;
+        }
+        for row in 0..rows {
+            for col in 0..cols {
+                // Walk each step back to the source cell
+                let (mut r, mut c) = (row, col);
+                if self.flip_y {
+                    r = rows - 1 - r;
+                }
+                if self.flip_x {
+                    c = cols - 1 - c;
+                }
+                if self.rotate_90 {
+                    (r, c) = (rows_t - 1 - c, r);
+                }
+                if self.transpose {
+                    (r, c) = (c, r);
+                }
+                out.data.push(frame.at(r, c));
+            }
+        }
+    }
+}
+
+/// Platform-independent trait for HID feature report I/O.
+/// Implemented by `HidrawDevice` on Linux and `WinHidDevice` on Windows.
+pub trait HidDevice {
+    /// Send a SetFeature report. `buf[0]` must be the report ID.
+    fn set_feature(&self, buf: &[u8]) -> io::Result<()>;
+
+    /// Send a GetFeature report. `buf[0]` must be set to the report ID before calling.
+    /// Returns the number of bytes actually read.
+    fn get_feature(&self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Read one input report, waiting at most `timeout_ms` for it to arrive.
+    /// `buf[0]` receives the report ID. Returns `Ok(0)` on timeout.
+    /// Backends that can't read input reports return `Unsupported`.
+    fn read_input(&self, buf: &mut [u8], timeout_ms: i32) -> io::Result<usize> {
+        let _ = (buf, timeout_ms);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "input reports not supported on this backend",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2x3 frame:
+    ///   1 2 3
+    ///   4 5 6
+    fn sample_frame() -> HeatmapFrame {
+        HeatmapFrame {
+            rows: 2,
+            cols: 3,
+            data: vec![1, 2, 3, 4, 5, 6],
+        }
+    }
+
+    fn oriented(o: HeatmapOrientation) -> (usize, usize, Vec<i16>) {
+        let f = o.apply(sample_frame());
+        (f.rows, f.cols, f.data)
+    }
+
+    #[test]
+    fn test_orientation_identity() {
+        assert_eq!(
+            oriented(HeatmapOrientation::default()),
+            (2, 3, vec![1, 2, 3, 4, 5, 6])
+        );
+    }
+
+    #[test]
+    fn test_orientation_transforms() {
+        let transpose = HeatmapOrientation {
+            transpose: true,
+            ..Default::default()
+        };
+        assert_eq!(oriented(transpose), (3, 2, vec![1, 4, 2, 5, 3, 6]));
+
+        let rotate = HeatmapOrientation {
+            rotate_90: true,
+            ..Default::default()
+        };
+        assert_eq!(oriented(rotate), (3, 2, vec![4, 1, 5, 2, 6, 3]));
+
+        let flip_x = HeatmapOrientation {
+            flip_x: true,
+            ..Default::default()
+        };
+        assert_eq!(oriented(flip_x), (2, 3, vec![3, 2, 1, 6, 5, 4]));
+
+        let flip_y = HeatmapOrientation {
+            flip_y: true,
+            ..Default::default()
+        };
+        assert_eq!(oriented(flip_y), (2, 3, vec![4, 5, 6, 1, 2, 3]));
+    }
+}
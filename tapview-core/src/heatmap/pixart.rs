@@ -0,0 +1,349 @@
+//! PixArt touchpads: register access and burst reads over feature reports
+//! 0x41-0x43, with frames optionally streamed as input report 0x44 (opt-in,
+//! see [`REPORT_STREAM`]).
+
+use super::chips::{identify_chip, read_frame, read_frame_streamed, read_matrix_dims, ChipVariant};
+use super::driver::{BurstLen, ChipDriver, DescriptorSummary, DriverOptions, Protocol, ReportKind};
+use super::protocol::{
+    probe_burst_len, read_reg, read_user_reg, write_reg, write_user_reg, ReadBuffers, REPORT_BURST,
+    REPORT_STREAM,
+};
+use super::registers::{Register, RegisterWrite};
+use super::HidDevice;
+use std::io;
+
+/// How long to wait for a streamed frame before polling for the rest of the
+/// session.
+const STREAM_TIMEOUT_MS: i32 = 500;
+
+/// Burst report lengths tried besides the advertised one: powers of two, and
+/// full-speed USB's 64-byte packets less the report ID.
+const BURST_LEN_CANDIDATES: [usize; 7] = [2048, 1024, 512, 256, 128, 64, 63];
+
+pub const PROTOCOL: Protocol = Protocol {
+    name: "PixArt",
+    claims: |summary| burst_len(summary).is_some(),
+    open,
+};
+
+fn burst_len(summary: &DescriptorSummary) -> Option<usize> {
+    summary
+        .report(REPORT_BURST, ReportKind::Feature)
+        .map(|r| r.count)
+        .filter(|&count| count > 0)
+}
+
+fn open(
+    dev: &dyn HidDevice,
+    summary: &DescriptorSummary,
+    options: &DriverOptions,
+) -> io::Result<Box<dyn ChipDriver>> {
+    let advertised = burst_len(summary);
+    let burst_len = match options.burst_len {
+        Some(BurstLen::Fixed(len)) => {
+            log_info!(target: "heatmap", "burst report length overridden to {}", len);
+            len
+        }
+        Some(BurstLen::Probe) => probe(dev, advertised)?,
+        None => match advertised {
+            Some(len) if probe_burst_len(dev, &[len]) == Some(len) => len,
+            _ => {
+                log_warn!(
+                    target: "heatmap", "advertised burst report length {:?} doesn't check out, probing",
+                    advertised
+                );
+                probe(dev, advertised)?
+            }
+        },
+    };
+    let stream_len = summary
+        .report(REPORT_STREAM, ReportKind::Input)
+        .map(|r| r.count)
+        .filter(|_| options.stream);
+
+    let chip = identify_chip(dev)?;
+    let mut dims = read_matrix_dims(dev, chip)?;
+    if chip == ChipVariant::PJP343 {
+        dims = detect_pjp343_dims(dev, burst_len, dims);
+    }
+    let (rows, cols) = dims;
+
+    Ok(Box::new(PixartDriver {
+        chip,
+        rows,
+        cols,
+        burst_len,
+        stream_len,
+        bufs: ReadBuffers::default(),
+        alc_on: options.alc_on.clone(),
+        alc_off: options.alc_off.clone(),
+        recalibrate: options.recalibrate.clone(),
+    }))
+}
+
+fn probe(dev: &dyn HidDevice, advertised: Option<usize>) -> io::Result<usize> {
+    let candidates: Vec<usize> = advertised.into_iter().chain(BURST_LEN_CANDIDATES).collect();
+    let len = probe_burst_len(dev, &candidates).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no burst report length checked out; set one with --burst-len",
+        )
+    })?;
+    log_info!(target: "heatmap", "probed burst report length = {}", len);
+    Ok(len)
+}
+
+struct PixartDriver {
+    chip: ChipVariant,
+    rows: usize,
+    cols: usize,
+    burst_len: usize,
+    /// Payload length of streamed frame reports, until one doesn't arrive
+    stream_len: Option<usize>,
+    bufs: ReadBuffers,
+    /// Where ALC is switched and a recalibration forced isn't documented
+    /// for any chip, so these come from the command line
+    alc_on: Vec<RegisterWrite>,
+    alc_off: Vec<RegisterWrite>,
+    recalibrate: Vec<RegisterWrite>,
+}
+
+impl ChipDriver for PixartDriver {
+    fn describe(&self) -> String {
+        let stream = match self.stream_len {
+            Some(len) => format!(", stream_len={}", len),
+            None => String::new(),
+        };
+        format!(
+            "{}, {}x{} matrix, burst_len={}{}",
+            self.chip, self.rows, self.cols, self.burst_len, stream
+        )
+    }
+
+    fn read_frame(&mut self, dev: &dyn HidDevice, out: &mut Vec<i16>) -> io::Result<usize> {
+        if let Some(len) = self.stream_len {
+            let streamed = match read_frame_streamed(
+                dev,
+                self.rows,
+                self.cols,
+                len,
+                STREAM_TIMEOUT_MS,
+                &mut self.bufs,
+                out,
+            ) {
+                // This backend can't read input reports at all
+                Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                    log_warn!(target: "heatmap", "can't read streamed frames: {}", e);
+                    self.stream_len = None;
+                    false
+                }
+                result => result?,
+            };
+            if streamed {
+                return Ok(self.cols);
+            }
+            // Waiting out the timeout again would stall every later frame
+            if self.stream_len.take().is_some() {
+                log_warn!(
+                    target: "heatmap", "no streamed frames, polling with feature reports from now on"
+                );
+            }
+        }
+        read_frame(
+            dev,
+            self.chip,
+            self.rows,
+            self.cols,
+            self.burst_len,
+            &mut self.bufs,
+            out,
+        )?;
+        Ok(self.cols)
+    }
+
+    fn read_register(&mut self, dev: &dyn HidDevice, register: Register) -> io::Result<u8> {
+        if register.user {
+            read_user_reg(dev, register.bank, register.addr)
+        } else {
+            read_reg(dev, register.bank, register.addr)
+        }
+    }
+
+    fn write_register(&mut self, dev: &dyn HidDevice, write: RegisterWrite) -> io::Result<()> {
+        write_register(dev, write)
+    }
+
+    fn set_alc(&mut self, dev: &dyn HidDevice, on: bool) -> io::Result<()> {
+        let writes = if on { &self.alc_on } else { &self.alc_off };
+        let state = if on { "on" } else { "off" };
+        write_all(
+            dev,
+            writes,
+            &format!("switch ALC {} (--alc-{})", state, state),
+        )?;
+        log_info!(target: "heatmap", "ALC switched {}", state);
+        Ok(())
+    }
+
+    fn recalibrate(&mut self, dev: &dyn HidDevice) -> io::Result<()> {
+        write_all(
+            dev,
+            &self.recalibrate,
+            "force a recalibration (--recalibrate)",
+        )?;
+        log_info!(target: "heatmap", "recalibration forced");
+        Ok(())
+    }
+}
+
+/// Write `writes` in order; with none, say which writes to `purpose` are
+/// missing.
+fn write_all(dev: &dyn HidDevice, writes: &[RegisterWrite], purpose: &str) -> io::Result<()> {
+    if writes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("no register writes given to {}", purpose),
+        ));
+    }
+    writes
+        .iter()
+        .try_for_each(|&write| write_register(dev, write))
+}
+
+fn write_register(dev: &dyn HidDevice, write: RegisterWrite) -> io::Result<()> {
+    let Register { user, bank, addr } = write.register;
+    if user {
+        write_user_reg(dev, bank, addr, write.value)
+    } else {
+        write_reg(dev, bank, addr, write.value)
+    }
+}
+
+/// Frames read per candidate when working out a PJP343's dimensions
+const DIM_PROBE_FRAMES: usize = 3;
+
+/// Where the PJP343 keeps its dimensions isn't known for sure, so every
+/// plausible pair from the registers other chips use is tried (both ways
+/// round) and the one whose frames look most like a real matrix wins.
+/// Falls back to the PJP274 registers if no frame says anything.
+fn detect_pjp343_dims(
+    dev: &dyn HidDevice,
+    burst_len: usize,
+    (rows, cols): (usize, usize),
+) -> (usize, usize) {
+    let user = |addr| read_user_reg(dev, 0, addr).ok().map(usize::from);
+    let bank9 = |addr| read_reg(dev, 9, addr).ok().map(|v| v as usize + 1);
+    let pairs = [
+        (Some(rows), Some(cols)),
+        // PJP255: drives, senses
+        (user(0x5A), user(0x59)),
+        (user(0x6C), user(0x6D)),
+        (user(0x70), user(0x71)),
+        // PLP239: senses, drives
+        (bank9(0x02), bank9(0x01)),
+    ];
+    let mut candidates: Vec<(usize, usize)> = Vec::new();
+    for (r, c) in pairs.into_iter().filter_map(|(r, c)| r.zip(c)) {
+        for dims in [(r, c), (c, r)] {
+            if (2..=64).contains(&dims.0)
+                && (2..=64).contains(&dims.1)
+                && !candidates.contains(&dims)
+            {
+                candidates.push(dims);
+            }
+        }
+    }
+
+    let mut bufs = ReadBuffers::default();
+    let mut frame = Vec::new();
+    let mut best: Option<((usize, usize), f64)> = None;
+    for (r, c) in candidates {
+        let scores: Vec<f64> = (0..DIM_PROBE_FRAMES)
+            .filter_map(|_| {
+                read_frame(
+                    dev,
+                    ChipVariant::PJP343,
+                    r,
+                    c,
+                    burst_len,
+                    &mut bufs,
+                    &mut frame,
+                )
+                .ok()?;
+                roughness(&frame, r, c)
+            })
+            .collect();
+        if scores.is_empty() {
+            log_debug!(target: "heatmap", "PJP343 {}x{}: no usable frame", r, c);
+            continue;
+        }
+        let score = scores.iter().sum::<f64>() / scores.len() as f64;
+        log_debug!(target: "heatmap", "PJP343 {}x{}: roughness {:.3}", r, c, score);
+        if best.is_none_or(|(_, s)| score < s) {
+            best = Some(((r, c), score));
+        }
+    }
+    match best {
+        Some((dims, _)) => {
+            log_info!(target: "heatmap", "PJP343 matrix detected as {}x{}", dims.0, dims.1);
+            dims
+        }
+        None => {
+            log_warn!(
+                target: "heatmap", "couldn't work out the PJP343 matrix from its frames, assuming {}x{}",
+                rows, cols
+            );
+            (rows, cols)
+        }
+    }
+}
+
+/// How much vertically neighbouring cells differ, relative to how much cells
+/// differ from the mean, when `data` is read as `rows`x`cols`. A real matrix
+/// varies smoothly, while a wrong stride puts unrelated cells above each
+/// other, so the right dimensions score lowest. None for a frame that's too
+/// short or flat, or has a constant row (read past the matrix), as those say
+/// nothing.
+fn roughness(data: &[i16], rows: usize, cols: usize) -> Option<f64> {
+    let cells = data.get(..rows * cols)?;
+    if rows < 2
+        || cells
+            .chunks_exact(cols)
+            .any(|row| row.iter().all(|&v| v == row[0]))
+    {
+        return None;
+    }
+    let n = cells.len() as f64;
+    let mean = cells.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let spread = cells.iter().map(|&v| (v as f64 - mean).abs()).sum::<f64>() / n;
+    if spread == 0.0 {
+        return None;
+    }
+    let vertical = cells
+        .iter()
+        .zip(&cells[cols..])
+        .map(|(&a, &b)| (a as f64 - b as f64).abs())
+        .sum::<f64>()
+        / (n - cols as f64);
+    Some(vertical / spread)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_right_stride_is_smoothest() {
+        // A 6x10 baseline: a gentle gradient with a little ripple
+        let (rows, cols) = (6, 10);
+        let data: Vec<i16> = (0..rows * cols)
+            .map(|i| (100 + (i / cols) * 3 + (i % cols) * 20 + (i * 7) % 5) as i16)
+            .collect();
+        let right = roughness(&data, rows, cols).unwrap();
+        for (r, c) in [(10, 6), (5, 12), (4, 15), (12, 5)] {
+            assert!(roughness(&data, r, c).unwrap() > right, "{}x{}", r, c);
+        }
+        assert!(roughness(&data, 7, 10).is_none());
+        assert!(roughness(&[5; 60], rows, cols).is_none());
+    }
+}
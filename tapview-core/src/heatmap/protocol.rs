@@ -0,0 +1,318 @@
+use super::HidDevice;
+use std::io;
+use std::time::{Duration, Instant};
+
+const REPORT_SINGLE: u8 = 0x42;
+const REPORT_USER: u8 = 0x43;
+pub const REPORT_BURST: u8 = 0x41;
+/// Input report that some firmwares are said to stream frames in instead of
+/// being polled. Neither the report nor the layout [`stream_read`] expects is
+/// documented or checked against a streaming firmware, so it's only tried
+/// with `--heatmap-stream`.
+pub const REPORT_STREAM: u8 = 0x44;
+const READ_FLAG: u8 = 0x10;
+
+/// Write a single register via Report 0x42.
+pub fn write_reg(dev: &dyn HidDevice, bank: u8, addr: u8, value: u8) -> io::Result<()> {
+    dev.set_feature(&[REPORT_SINGLE, addr, bank, value])
+}
+
+/// Read a single register via Report 0x42.
+/// Step 1: SetFeature with bank | 0x10 read flag.
+/// Step 2: GetFeature, result at buf[3].
+pub fn read_reg(dev: &dyn HidDevice, bank: u8, addr: u8) -> io::Result<u8> {
+    dev.set_feature(&[REPORT_SINGLE, addr, bank | READ_FLAG, 0x00])?;
+    let mut buf = [REPORT_SINGLE, 0, 0, 0];
+    dev.get_feature(&mut buf)?;
+    Ok(buf[3])
+}
+
+/// Write a user register via Report 0x43.
+pub fn write_user_reg(dev: &dyn HidDevice, bank: u8, addr: u8, value: u8) -> io::Result<()> {
+    dev.set_feature(&[REPORT_USER, addr, bank, value])
+}
+
+/// Read a user register via Report 0x43.
+pub fn read_user_reg(dev: &dyn HidDevice, bank: u8, addr: u8) -> io::Result<u8> {
+    dev.set_feature(&[REPORT_USER, addr, bank | READ_FLAG, 0x00])?;
+    let mut buf = [REPORT_USER, 0, 0, 0];
+    dev.get_feature(&mut buf)?;
+    Ok(buf[3])
+}
+
+/// Report and frame byte buffers kept across reads, so a steady stream of
+/// frames doesn't allocate.
+#[derive(Default)]
+pub struct ReadBuffers {
+    report: Vec<u8>,
+    frame: Vec<u8>,
+}
+
+/// Burst read via repeated GetFeature(Report 0x41).
+/// `report_len` is the payload bytes per report (excluding report ID byte).
+pub fn burst_read<'a>(
+    dev: &dyn HidDevice,
+    bufs: &'a mut ReadBuffers,
+    total_bytes: usize,
+    report_len: usize,
+) -> io::Result<&'a [u8]> {
+    let ReadBuffers { report, frame } = bufs;
+    frame.clear();
+    // Buffer: report ID + payload
+    let buf_size = 1 + report_len;
+    report.resize(buf_size, 0);
+
+    while frame.len() < total_bytes {
+        report[0] = REPORT_BURST;
+        let n = dev.get_feature(report)?;
+        if n <= 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "empty burst report",
+            ));
+        }
+        // Data starts at index 1
+        let payload_end = n.min(buf_size);
+        let remaining = total_bytes - frame.len();
+        let take = remaining.min(payload_end - 1);
+        frame.extend_from_slice(&report[1..1 + take]);
+    }
+
+    Ok(frame)
+}
+
+/// Find the payload length of Report 0x41 by reading it at each candidate
+/// length, longest first, keeping the first the device fills exactly. Where
+/// a longer read comes back short (hidraw passes the device's own length
+/// through), that length is checked instead.
+pub fn probe_burst_len(dev: &dyn HidDevice, candidates: &[usize]) -> Option<usize> {
+    let fills = |len: usize| {
+        let mut report = vec![0; 1 + len];
+        report[0] = REPORT_BURST;
+        dev.get_feature(&mut report)
+    };
+    let mut candidates = candidates.to_vec();
+    candidates.sort_unstable_by(|a, b| b.cmp(a));
+    candidates.dedup();
+    for len in candidates {
+        match fills(len) {
+            Ok(n) if n == 1 + len => return Some(len),
+            Ok(n) if n > 1 && n < 1 + len => {
+                log_debug!(target: "heatmap", "burst length {}: {} bytes came back", len, n - 1);
+                if fills(n - 1).is_ok_and(|m| m == n) {
+                    return Some(n - 1);
+                }
+            }
+            Ok(n) => log_debug!(target: "heatmap", "burst length {}: {} bytes came back", len, n),
+            Err(e) => log_debug!(target: "heatmap", "burst length {}: {}", len, e),
+        }
+    }
+    None
+}
+
+/// Read one frame streamed via Input Report 0x44.
+/// Each report carries a little-endian u16 byte offset into the frame, followed
+/// by payload bytes. A report at offset 0 starts a new frame; if a chunk goes
+/// missing the partial frame is dropped and assembly restarts at the next frame.
+/// Returns `Ok(None)` if no whole frame arrives within `timeout_ms`, however
+/// many other reports (e.g. the touchpad's own touch reports) do.
+pub fn stream_read<'a>(
+    dev: &dyn HidDevice,
+    bufs: &'a mut ReadBuffers,
+    total_bytes: usize,
+    report_len: usize,
+    timeout_ms: i32,
+) -> io::Result<Option<&'a [u8]>> {
+    // Whether `frame` holds the start of a frame being assembled
+    let mut assembling = false;
+    let ReadBuffers { report, frame } = bufs;
+    report.resize(1 + report_len, 0);
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms.max(0) as u64);
+
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Ok(None);
+        }
+        let n = dev.read_input(report, left.as_millis().clamp(1, i32::MAX as u128) as i32)?;
+        if n == 0 {
+            return Ok(None);
+        }
+        // Report ID + 2-byte offset + at least one payload byte
+        if report[0] != REPORT_STREAM || n < 4 {
+            continue;
+        }
+
+        let offset = u16::from_le_bytes([report[1], report[2]]) as usize;
+        if offset == 0 {
+            frame.clear();
+            assembling = true;
+        }
+
+        if assembling {
+            if offset != frame.len() {
+                // Lost a chunk, wait for the next frame start
+                assembling = false;
+                continue;
+            }
+            let take = (total_bytes - frame.len()).min(n - 3);
+            frame.extend_from_slice(&report[3..3 + take]);
+            if frame.len() >= total_bytes {
+                return Ok(Some(frame));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    /// Replays a fixed sequence of input reports, then times out.
+    struct ScriptedDevice {
+        reports: RefCell<VecDeque<Vec<u8>>>,
+    }
+
+    impl ScriptedDevice {
+        fn new(reports: Vec<Vec<u8>>) -> Self {
+            Self {
+                reports: RefCell::new(reports.into()),
+            }
+        }
+    }
+
+    impl HidDevice for ScriptedDevice {
+        fn set_feature(&self, _buf: &[u8]) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn get_feature(&self, _buf: &mut [u8]) -> io::Result<usize> {
+            Ok(0)
+        }
+
+        fn read_input(&self, buf: &mut [u8], _timeout_ms: i32) -> io::Result<usize> {
+            match self.reports.borrow_mut().pop_front() {
+                Some(r) => {
+                    let n = r.len().min(buf.len());
+                    buf[..n].copy_from_slice(&r[..n]);
+                    Ok(n)
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    /// A burst report of `len` payload bytes. Strict devices reject reads of
+    /// any other length, like Windows; others return at most `len`, like
+    /// hidraw.
+    struct BurstDevice {
+        len: usize,
+        strict: bool,
+    }
+
+    impl HidDevice for BurstDevice {
+        fn set_feature(&self, _buf: &[u8]) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn get_feature(&self, buf: &mut [u8]) -> io::Result<usize> {
+            if buf.len() == 1 + self.len || (!self.strict && buf.len() > 1 + self.len) {
+                Ok(1 + self.len)
+            } else if self.strict {
+                Err(io::Error::other("wrong length"))
+            } else {
+                Ok(buf.len())
+            }
+        }
+    }
+
+    #[test]
+    fn probes_burst_length() {
+        let candidates = [64, 512, 256];
+        let strict = BurstDevice {
+            len: 256,
+            strict: true,
+        };
+        assert_eq!(probe_burst_len(&strict, &candidates), Some(256));
+        // Longest first, so a short read doesn't pass for the full report
+        let hidraw = BurstDevice {
+            len: 300,
+            strict: false,
+        };
+        assert_eq!(probe_burst_len(&hidraw, &candidates), Some(300));
+        let unknown = BurstDevice {
+            len: 100,
+            strict: true,
+        };
+        assert_eq!(probe_burst_len(&unknown, &candidates), None);
+    }
+
+    #[test]
+    fn test_stream_read_reassembles_frame() {
+        let dev = ScriptedDevice::new(vec![
+            // Tail of a previous frame: ignored until a frame starts
+            vec![REPORT_STREAM, 4, 0, 9, 9],
+            // Unrelated report ID
+            vec![0x01, 0, 0, 7, 7],
+            vec![REPORT_STREAM, 0, 0, 1, 2, 3, 4],
+            vec![REPORT_STREAM, 4, 0, 5, 6, 0xAA, 0xAA],
+        ]);
+
+        let mut bufs = ReadBuffers::default();
+        let frame = stream_read(&dev, &mut bufs, 6, 6, 10).unwrap().unwrap();
+        assert_eq!(frame, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_stream_read_restarts_after_lost_chunk() {
+        let dev = ScriptedDevice::new(vec![
+            vec![REPORT_STREAM, 0, 0, 1, 2],
+            // Offset 2 went missing
+            vec![REPORT_STREAM, 4, 0, 5, 6],
+            vec![REPORT_STREAM, 0, 0, 7, 8],
+            vec![REPORT_STREAM, 2, 0, 9, 10],
+        ]);
+
+        let mut bufs = ReadBuffers::default();
+        let frame = stream_read(&dev, &mut bufs, 4, 4, 10).unwrap().unwrap();
+        assert_eq!(frame, [7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_stream_read_timeout() {
+        let dev = ScriptedDevice::new(vec![vec![REPORT_STREAM, 0, 0, 1, 2]]);
+        let mut bufs = ReadBuffers::default();
+        assert!(stream_read(&dev, &mut bufs, 4, 4, 10).unwrap().is_none());
+    }
+
+    /// Touch reports keep coming, as while a finger is down.
+    struct TouchingDevice;
+
+    impl HidDevice for TouchingDevice {
+        fn set_feature(&self, _buf: &[u8]) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn get_feature(&self, _buf: &mut [u8]) -> io::Result<usize> {
+            Ok(0)
+        }
+
+        fn read_input(&self, buf: &mut [u8], _timeout_ms: i32) -> io::Result<usize> {
+            std::thread::sleep(Duration::from_millis(1));
+            buf[..4].copy_from_slice(&[0x01, 0, 0, 7]);
+            Ok(4)
+        }
+    }
+
+    #[test]
+    fn test_stream_read_times_out_among_other_reports() {
+        let mut bufs = ReadBuffers::default();
+        let start = Instant::now();
+        assert!(stream_read(&TouchingDevice, &mut bufs, 4, 4, 20)
+            .unwrap()
+            .is_none());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,446 @@
+//! Firmware registers read while the heatmap runs, for working out what the
+//! firmware touches (e.g. during calibration).
+//!
+//! The heatmap thread owns the device, so it does the reading: between frames
+//! it polls the registers on a [`RegisterWatch`] shared with the UI, about
+//! once a second, and works through a requested [`RegisterDump`] a chunk at a
+//! time so the heatmap keeps updating. It also carries out the
+//! [`DriverCommand`] the UI last sent, and hands back how that went.
+
+use super::driver::DriverCommand;
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A register as `bank:addr` in hex, with a `U` prefix for the user banks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Register {
+    pub user: bool,
+    pub bank: u8,
+    pub addr: u8,
+}
+
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = if self.user { "U" } else { "" };
+        write!(f, "{}{:X}:{:02X}", prefix, self.bank, self.addr)
+    }
+}
+
+impl std::str::FromStr for Register {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (user, rest) = match s.strip_prefix(['U', 'u']) {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let hex = |v: &str| u8::from_str_radix(v.trim_start_matches("0x"), 16).ok();
+        match rest.split_once(':').map(|(b, a)| (hex(b), hex(a))) {
+            Some((Some(bank), Some(addr))) => Ok(Register { user, bank, addr }),
+            _ => Err(format!(
+                "expected bank:addr in hex (U prefix for a user bank), got \"{}\"",
+                s
+            )),
+        }
+    }
+}
+
+impl Register {
+    /// Every register a dump tries: banks 0-F (the next bit is the read
+    /// flag), plain then user, so the first 4096 are the plain banks.
+    pub const COUNT: usize = 2 * 16 * 256;
+
+    fn nth(i: usize) -> Self {
+        Register {
+            user: i >= Self::COUNT / 2,
+            bank: (i / 256 % 16) as u8,
+            addr: (i % 256) as u8,
+        }
+    }
+}
+
+/// A value to write to a register, as `bank:addr=value` in hex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterWrite {
+    pub register: Register,
+    pub value: u8,
+}
+
+impl std::fmt::Display for RegisterWrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={:02X}", self.register, self.value)
+    }
+}
+
+impl std::str::FromStr for RegisterWrite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (register, value) = s.split_once('=').ok_or_else(|| {
+            format!(
+                "expected bank:addr=value in hex (U prefix for a user bank), got \"{}\"",
+                s.trim()
+            )
+        })?;
+        let value = value.trim();
+        Ok(RegisterWrite {
+            register: register.parse()?,
+            value: u8::from_str_radix(value.trim_start_matches("0x"), 16)
+                .map_err(|_| format!("bad value \"{}\"", value))?,
+        })
+    }
+}
+
+/// Values of every register that could be read, saved as one `bank:addr
+/// value` line each so dumps also diff well outside tapview.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegisterDump {
+    pub values: BTreeMap<Register, u8>,
+}
+
+/// A register that differs between two dumps; None where it couldn't be
+/// read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterChange {
+    pub register: Register,
+    pub before: Option<u8>,
+    pub after: Option<u8>,
+}
+
+impl RegisterDump {
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# tapview register dump: bank:addr value, U for user banks\n");
+        for (register, value) in &self.values {
+            text += &format!("{} {:02X}\n", register, value);
+        }
+        text
+    }
+
+    /// Read back [`to_text`](Self::to_text); blank and `#` lines are skipped.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut dump = Self::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad = |e: String| format!("line {}: {}", n + 1, e);
+            let (register, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| bad(format!("expected a register and a value, got \"{}\"", line)))?;
+            let register = register.parse().map_err(bad)?;
+            let value = u8::from_str_radix(value.trim(), 16)
+                .map_err(|_| bad(format!("bad value \"{}\"", value.trim())))?;
+            dump.values.insert(register, value);
+        }
+        Ok(dump)
+    }
+
+    /// Registers whose value changed from `self` to `after`, or that only
+    /// one of them could read.
+    pub fn diff(&self, after: &RegisterDump) -> Vec<RegisterChange> {
+        let registers: std::collections::BTreeSet<_> =
+            self.values.keys().chain(after.values.keys()).collect();
+        registers
+            .into_iter()
+            .map(|&register| RegisterChange {
+                register,
+                before: self.values.get(&register).copied(),
+                after: after.values.get(&register).copied(),
+            })
+            .filter(|c| c.before != c.after)
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchedRegister {
+    pub register: Register,
+    /// None until read, or if the last read failed
+    pub value: Option<u8>,
+    /// Differs from the poll before
+    pub changed: bool,
+}
+
+#[derive(Default)]
+struct WatchState {
+    registers: Vec<WatchedRegister>,
+    last_poll: Option<Instant>,
+    error: Option<String>,
+    /// The dump being read and the index of the next register to try
+    dump: Option<(RegisterDump, usize)>,
+    finished_dump: Option<RegisterDump>,
+    /// Sent by the UI, not yet carried out
+    command: Option<DriverCommand>,
+    /// The last command carried out and why it failed, if it did
+    command_result: Option<(DriverCommand, Result<(), String>)>,
+}
+
+/// Registers to poll and commands to carry out, shared between the UI and
+/// the heatmap thread.
+#[derive(Clone, Default)]
+pub struct RegisterWatch(Arc<Mutex<WatchState>>);
+
+impl RegisterWatch {
+    pub const INTERVAL: Duration = Duration::from_secs(1);
+    /// Registers read per poll while dumping
+    const DUMP_CHUNK: usize = 128;
+
+    fn state(&self) -> MutexGuard<'_, WatchState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start watching `register`, read on the next poll.
+    pub fn add(&self, register: Register) {
+        let mut state = self.state();
+        if !state.registers.iter().any(|w| w.register == register) {
+            state.registers.push(WatchedRegister {
+                register,
+                value: None,
+                changed: false,
+            });
+            state.last_poll = None;
+        }
+    }
+
+    pub fn remove(&self, register: Register) {
+        self.state().registers.retain(|w| w.register != register);
+    }
+
+    pub fn registers(&self) -> Vec<WatchedRegister> {
+        self.state().registers.clone()
+    }
+
+    /// Why the last poll failed, if it did.
+    pub fn error(&self) -> Option<String> {
+        self.state().error.clone()
+    }
+
+    /// Read every register over the next polls, unless already dumping.
+    pub fn request_dump(&self) {
+        let mut state = self.state();
+        if state.dump.is_none() {
+            state.dump = Some((RegisterDump::default(), 0));
+        }
+    }
+
+    /// How far along the requested dump is, from 0 to 1.
+    pub fn dump_progress(&self) -> Option<f32> {
+        let state = self.state();
+        state
+            .dump
+            .as_ref()
+            .map(|(_, next)| *next as f32 / Register::COUNT as f32)
+    }
+
+    /// The dump once all of it has been read.
+    pub fn take_dump(&self) -> Option<RegisterDump> {
+        self.state().finished_dump.take()
+    }
+
+    /// Have the heatmap thread carry out `command` before its next frame. A
+    /// command it hasn't got to yet is replaced.
+    pub fn send(&self, command: DriverCommand) {
+        self.state().command = Some(command);
+    }
+
+    /// The command sent last, for the heatmap thread to carry out.
+    pub fn take_command(&self) -> Option<DriverCommand> {
+        self.state().command.take()
+    }
+
+    /// Report how the command taken last went.
+    pub fn finish_command(&self, command: DriverCommand, result: Result<(), String>) {
+        self.state().command_result = Some((command, result));
+    }
+
+    /// The command carried out last and how it went, once.
+    pub fn take_command_result(&self) -> Option<(DriverCommand, Result<(), String>)> {
+        self.state().command_result.take()
+    }
+
+    /// Read the watched registers with `read` if a poll is due at `now`, and
+    /// the next chunk of a requested dump. The lock isn't held while reading,
+    /// so the UI never waits on the device.
+    pub fn poll(&self, now: Instant, mut read: impl FnMut(Register) -> io::Result<u8>) {
+        self.poll_watched(now, &mut read);
+        self.poll_dump(&mut read);
+    }
+
+    fn poll_dump(&self, read: &mut impl FnMut(Register) -> io::Result<u8>) {
+        let Some(start) = self.state().dump.as_ref().map(|(_, next)| *next) else {
+            return;
+        };
+        let end = (start + Self::DUMP_CHUNK).min(Register::COUNT);
+        // Unreadable registers are left out
+        let values: Vec<(Register, u8)> = (start..end)
+            .map(Register::nth)
+            .filter_map(|register| read(register).ok().map(|v| (register, v)))
+            .collect();
+
+        let mut state = self.state();
+        let Some((dump, next)) = &mut state.dump else {
+            return;
+        };
+        dump.values.extend(values);
+        *next = end;
+        if end == Register::COUNT {
+            state.finished_dump = state.dump.take().map(|(dump, _)| dump);
+        }
+    }
+
+    fn poll_watched(&self, now: Instant, read: &mut impl FnMut(Register) -> io::Result<u8>) {
+        let registers: Vec<Register> = {
+            let mut state = self.state();
+            if state.registers.is_empty()
+                || state
+                    .last_poll
+                    .is_some_and(|last| now.saturating_duration_since(last) < Self::INTERVAL)
+            {
+                return;
+            }
+            state.last_poll = Some(now);
+            state.registers.iter().map(|w| w.register).collect()
+        };
+        let mut error = None;
+        let values: Vec<(Register, Option<u8>)> = registers
+            .into_iter()
+            .map(|register| match read(register) {
+                Ok(value) => (register, Some(value)),
+                Err(e) => {
+                    error.get_or_insert_with(|| format!("reading {}: {}", register, e));
+                    (register, None)
+                }
+            })
+            .collect();
+
+        let mut state = self.state();
+        for (register, value) in values {
+            // It may have been removed meanwhile
+            if let Some(w) = state.registers.iter_mut().find(|w| w.register == register) {
+                w.changed = w.value.is_some() && value.is_some() && w.value != value;
+                w.value = value;
+            }
+        }
+        state.error = error;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polls_at_most_once_per_interval_and_flags_changes() {
+        let reg: Register = "u0:6e".parse().unwrap();
+        assert_eq!(
+            reg,
+            Register {
+                user: true,
+                bank: 0,
+                addr: 0x6E
+            }
+        );
+        assert_eq!(reg.to_string(), "U0:6E");
+        assert_eq!("9:0x02".parse::<Register>().unwrap().to_string(), "9:02");
+        assert!("9".parse::<Register>().is_err());
+        assert!("0:100".parse::<Register>().is_err());
+        let write: RegisterWrite = "U0:6E=0x1".parse().unwrap();
+        assert_eq!((write.register, write.value), (reg, 1));
+        assert_eq!(write.to_string(), "U0:6E=01");
+        assert!("U0:6E".parse::<RegisterWrite>().is_err());
+        assert!("U0:6E=100".parse::<RegisterWrite>().is_err());
+
+        let watch = RegisterWatch::default();
+        watch.add(reg);
+        watch.add(reg);
+        let t0 = Instant::now();
+        let mut value = 5;
+        watch.poll(t0, |_| Ok(value));
+        assert_eq!(
+            watch.registers(),
+            [WatchedRegister {
+                register: reg,
+                value: Some(5),
+                changed: false
+            }]
+        );
+
+        // Too soon
+        value = 6;
+        watch.poll(t0 + Duration::from_millis(100), |_| Ok(value));
+        assert_eq!(watch.registers()[0].value, Some(5));
+
+        watch.poll(t0 + RegisterWatch::INTERVAL, |_| Ok(value));
+        assert_eq!(watch.registers()[0].value, Some(6));
+        assert!(watch.registers()[0].changed);
+
+        watch.poll(t0 + RegisterWatch::INTERVAL * 2, |_| {
+            Err(io::Error::from(io::ErrorKind::TimedOut))
+        });
+        assert_eq!(watch.registers()[0].value, None);
+        assert!(watch.error().unwrap().starts_with("reading U0:6E"));
+
+        watch.remove(reg);
+        assert!(watch.registers().is_empty());
+    }
+
+    #[test]
+    fn dumps_readable_registers_in_chunks_and_diffs_them() {
+        let watch = RegisterWatch::default();
+        watch.request_dump();
+        assert_eq!(watch.dump_progress(), Some(0.0));
+        // Only bank 0 answers
+        let read = |r: Register| match (r.user, r.bank) {
+            (false, 0) => Ok(r.addr),
+            _ => Err(io::Error::from(io::ErrorKind::Unsupported)),
+        };
+        watch.poll(Instant::now(), read);
+        assert!(watch.dump_progress().unwrap() > 0.0);
+        assert!(watch.take_dump().is_none());
+        while watch.dump_progress().is_some() {
+            watch.poll(Instant::now(), read);
+        }
+        let before = watch.take_dump().unwrap();
+        assert_eq!(before.values.len(), 256);
+        assert_eq!(RegisterDump::parse(&before.to_text()), Ok(before.clone()));
+
+        let mut after = before.clone();
+        after.values.insert("0:78".parse().unwrap(), 0x43);
+        after.values.remove(&"0:00".parse().unwrap());
+        after.values.insert("U1:02".parse().unwrap(), 7);
+        let change = |r: &str, before, after| RegisterChange {
+            register: r.parse().unwrap(),
+            before,
+            after,
+        };
+        assert_eq!(
+            before.diff(&after),
+            [
+                change("0:00", Some(0), None),
+                change("0:78", Some(0x78), Some(0x43)),
+                change("U1:02", None, Some(7)),
+            ]
+        );
+        assert!(RegisterDump::parse("0:78").is_err());
+    }
+
+    #[test]
+    fn hands_the_latest_command_over_and_its_result_back() {
+        let watch = RegisterWatch::default();
+        watch.send(DriverCommand::SetAlc(true));
+        watch.send(DriverCommand::SetAlc(false));
+        assert_eq!(watch.take_command(), Some(DriverCommand::SetAlc(false)));
+        assert_eq!(watch.take_command(), None);
+        assert_eq!(watch.take_command_result(), None);
+        watch.finish_command(DriverCommand::SetAlc(false), Err("no".into()));
+        assert_eq!(
+            watch.take_command_result(),
+            Some((DriverCommand::SetAlc(false), Err("no".into())))
+        );
+        assert_eq!(watch.take_command_result(), None);
+    }
+}
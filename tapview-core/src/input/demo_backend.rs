@@ -0,0 +1,184 @@
+//! `--demo`: a made-up touchpad for working on the UI without one. It plays
+//! a loop of a circling finger, a two-finger pinch and a five-finger swipe
+//! at 120 Hz. What's down at any moment depends only on the time since
+//! start, so the same instant always looks the same.
+
+use super::{InputBackend, InputError, ReportTime, TouchState, Waker};
+use crate::discovery::{DeviceInfo, Integration};
+use crate::multitouch::{TouchData, MAX_TOUCH_POINTS};
+use std::f64::consts::TAU;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Axis maxima and resolution (units/mm) of the demo touchpad: 100 × 67 mm
+pub const EXTENTS: (i32, i32) = (3000, 2000);
+pub const RESOLUTION: (i32, i32) = (30, 30);
+pub const PRESSURE_RANGE: (i32, i32) = (0, 100);
+
+const REPORT_INTERVAL: Duration = Duration::from_micros(8_333);
+/// Scenes as (start, end) in seconds within the loop; nothing's down after
+/// the last one until the loop starts over
+const SCENES: [(f64, f64); 3] = [(0.0, 4.0), (4.0, 8.0), (8.0, 11.5)];
+const LOOP: f64 = 12.0;
+/// Fingers are up this long at the start and end of each scene
+const LIFT: f64 = 0.25;
+
+/// Stands in for a discovered touchpad.
+pub fn device_info() -> DeviceInfo {
+    DeviceInfo {
+        devnode: PathBuf::from("demo"),
+        integration: Integration::Unknown,
+        vendor_id: None,
+        product_id: None,
+    }
+}
+
+/// The contacts `t` seconds into the demo.
+pub fn touches_at(t: f64) -> [TouchData; MAX_TOUCH_POINTS] {
+    let mut touches = [TouchData::default(); MAX_TOUCH_POINTS];
+    let round = (t / LOOP).floor();
+    let t = t - round * LOOP;
+    let Some((scene, &(start, end))) = SCENES
+        .iter()
+        .enumerate()
+        .find(|(_, (start, end))| (*start..*end).contains(&t))
+    else {
+        return touches;
+    };
+    if t < start + LIFT || t > end - LIFT {
+        return touches;
+    }
+    // Progress through the scene, 0 to 1
+    let s = (t - start - LIFT) / (end - start - 2.0 * LIFT);
+    let (cx, cy) = (EXTENTS.0 as f64 / 2.0, EXTENTS.1 as f64 / 2.0);
+
+    let positions: Vec<(f64, f64)> = match scene {
+        // Two turns around the middle
+        0 => {
+            let angle = s * 2.0 * TAU;
+            vec![(cx + 600.0 * angle.cos(), cy + 600.0 * angle.sin())]
+        }
+        // Spread apart and back while turning a little
+        1 => {
+            let spread = 300.0 + 450.0 * (s * std::f64::consts::PI).sin();
+            let angle = 0.3 + 0.8 * s;
+            let (dx, dy) = (spread * angle.cos(), spread * angle.sin());
+            vec![(cx - dx, cy - dy), (cx + dx, cy + dy)]
+        }
+        // A hand moving left to right, thumb lowest
+        _ => {
+            let x = 400.0 + 1100.0 * s;
+            [
+                (0.0, 350.0),
+                (300.0, 0.0),
+                (550.0, -80.0),
+                (800.0, -40.0),
+                (1050.0, 60.0),
+            ]
+            .iter()
+            .map(|(dx, dy)| (x + dx, cy - 200.0 + dy))
+            .collect()
+        }
+    };
+
+    let first_id = (round as i32 * SCENES.len() as i32 + scene as i32) * 5;
+    for (slot, (x, y)) in positions.into_iter().enumerate() {
+        touches[slot] = TouchData {
+            used: true,
+            pressed: true,
+            tracking_id: first_id + slot as i32,
+            position_x: x.round() as i32,
+            position_y: y.round() as i32,
+            pressure: (40.0 + 10.0 * (t * 3.0 + slot as f64).sin()).round() as i32,
+            touch_major: 110,
+            touch_minor: 80,
+            ..Default::default()
+        };
+    }
+    touches
+}
+
+pub struct DemoBackend {
+    start: Instant,
+    /// When the next report is due
+    next: Instant,
+}
+
+impl InputBackend for DemoBackend {
+    fn open(_device_path: &Path) -> Result<Self, InputError> {
+        let now = Instant::now();
+        Ok(Self {
+            start: now,
+            next: now,
+        })
+    }
+
+    // Nothing to grab; the real pointer doesn't move anyway
+    fn grab(&mut self) -> Result<(), InputError> {
+        Ok(())
+    }
+
+    fn ungrab(&mut self) -> Result<(), InputError> {
+        Ok(())
+    }
+
+    fn poll_events(&mut self) -> Result<Option<TouchState>, InputError> {
+        let now = Instant::now();
+        if now < self.next {
+            return Ok(None);
+        }
+        // After a stall, carry on from now rather than catching up
+        self.next = (self.next + REPORT_INTERVAL).max(now);
+        let elapsed = now - self.start;
+        let touches = touches_at(elapsed.as_secs_f64());
+        Ok(Some(TouchState {
+            touches,
+            buttons: Default::default(),
+            reports: 1,
+            time: now,
+            report_times: vec![ReportTime {
+                time: SystemTime::now(),
+                firmware_us: Some(elapsed.as_micros() as u32),
+                contacts: touches.iter().filter(|t| t.used).count() as u8,
+            }],
+        }))
+    }
+
+    fn wait(&mut self, waker: &Waker) -> Result<(), InputError> {
+        waker
+            .wait_timeout(self.next.saturating_duration_since(Instant::now()))
+            .map_err(|e| InputError::ReadError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down(t: f64) -> Vec<TouchData> {
+        touches_at(t).into_iter().filter(|t| t.used).collect()
+    }
+
+    #[test]
+    fn loops_through_the_scenes() {
+        assert!(down(0.1).is_empty());
+        assert_eq!(down(1.0).len(), 1);
+        assert_eq!(down(6.0).len(), 2);
+        assert_eq!(down(9.0).len(), 5);
+        assert!(down(11.8).is_empty());
+
+        // Contacts keep their IDs through a scene and get new ones after
+        assert_eq!(down(1.0)[0].tracking_id, down(3.0)[0].tracking_id);
+        assert_ne!(down(1.0)[0].tracking_id, down(13.0)[0].tracking_id);
+        // Every loop looks the same
+        let (a, b) = (down(6.0), down(6.0 + LOOP));
+        assert_eq!(
+            (a[1].position_x, a[1].position_y),
+            (b[1].position_x, b[1].position_y)
+        );
+        for touch in down(5.0).iter().chain(&down(9.0)).chain(&down(11.2)) {
+            assert!((0..=EXTENTS.0).contains(&touch.position_x));
+            assert!((0..=EXTENTS.1).contains(&touch.position_y));
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! A HID report descriptor as a tree of collections and their input, output
+//! and feature items, with the report IDs and their sizes, for reading
+//! without `hid-decode`.
+//!
+//! On Linux the descriptor comes from the hidraw node's sysfs
+//! `report_descriptor` and is parsed here. Windows doesn't hand it out, so
+//! [`windows_descriptor`](super::windows_descriptor) builds the same tree from
+//! the preparsed data.
+
+use std::fmt::Write as _;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Node {
+    pub label: String,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            children: Vec::new(),
+        }
+    }
+
+    /// The tree as indented lines.
+    pub fn write_text(&self, out: &mut String, depth: usize) {
+        let _ = writeln!(out, "{}{}", "  ".repeat(depth), self.label);
+        for child in &self.children {
+            child.write_text(out, depth + 1);
+        }
+    }
+}
+
+pub fn usage_page_name(page: u16) -> String {
+    let name = match page {
+        0x01 => "Generic Desktop",
+        0x06 => "Generic Device",
+        0x07 => "Keyboard",
+        0x08 => "LED",
+        0x09 => "Button",
+        0x0C => "Consumer",
+        0x0D => "Digitizer",
+        0x0E => "Haptics",
+        0x20 => "Sensor",
+        0xFF00..=0xFFFF => return format!("Vendor {:04X}", page),
+        _ => return format!("Page {:02X}", page),
+    };
+    name.to_string()
+}
+
+pub fn usage_name(page: u16, usage: u16) -> String {
+    let name = match (page, usage) {
+        (0x01, 0x01) => "Pointer",
+        (0x01, 0x02) => "Mouse",
+        (0x01, 0x06) => "Keyboard",
+        (0x01, 0x30) => "X",
+        (0x01, 0x31) => "Y",
+        (0x01, 0x32) => "Z",
+        (0x01, 0x38) => "Wheel",
+        (0x09, n) => return format!("Button {}", n),
+        (0x0C, 0x238) => "AC Pan",
+        (0x0D, 0x01) => "Digitizer",
+        (0x0D, 0x02) => "Pen",
+        (0x0D, 0x04) => "Touch Screen",
+        (0x0D, 0x05) => "Touch Pad",
+        (0x0D, 0x0E) => "Device Configuration",
+        (0x0D, 0x20) => "Stylus",
+        (0x0D, 0x22) => "Finger",
+        (0x0D, 0x23) => "Device Settings",
+        (0x0D, 0x30) => "Tip Pressure",
+        (0x0D, 0x32) => "In Range",
+        (0x0D, 0x42) => "Tip Switch",
+        (0x0D, 0x47) => "Confidence",
+        (0x0D, 0x48) => "Width",
+        (0x0D, 0x49) => "Height",
+        (0x0D, 0x51) => "Contact Identifier",
+        (0x0D, 0x52) => "Input Mode",
+        (0x0D, 0x53) => "Device Index",
+        (0x0D, 0x54) => "Contact Count",
+        (0x0D, 0x55) => "Contact Count Maximum",
+        (0x0D, 0x56) => "Scan Time",
+        (0x0D, 0x57) => "Surface Switch",
+        (0x0D, 0x58) => "Button Switch",
+        (0x0D, 0x59) => "Pad Type",
+        (0x0D, 0xC5) => "Certification Status",
+        (0x0E, 0x01) => "Simple Haptic Controller",
+        (0x0E, 0x20) => "Auto Trigger",
+        (0x0E, 0x21) => "Manual Trigger",
+        (0x0E, 0x23) => "Intensity",
+        _ => return format!("{:02X}:{:02X}", page, usage),
+    };
+    name.to_string()
+}
+
+pub(crate) fn collection_kind(kind: u8) -> &'static str {
+    match kind {
+        0 => "Physical",
+        1 => "Application",
+        2 => "Logical",
+        3 => "Report",
+        4 => "Named Array",
+        5 => "Usage Switch",
+        6 => "Usage Modifier",
+        _ => "Vendor",
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Globals {
+    usage_page: u16,
+    logical_min: i32,
+    logical_max: i32,
+    report_size: u32,
+    report_count: u32,
+    report_id: u8,
+}
+
+/// Bits of each report, by ID and Input/Output/Feature.
+#[derive(Default)]
+struct ReportSizes(Vec<(u8, [u32; 3])>);
+
+impl ReportSizes {
+    fn add(&mut self, id: u8, kind: usize, bits: u32) {
+        match self.0.iter_mut().find(|(i, _)| *i == id) {
+            Some((_, sizes)) => sizes[kind] += bits,
+            None => {
+                let mut sizes = [0; 3];
+                sizes[kind] = bits;
+                self.0.push((id, sizes));
+            }
+        }
+    }
+
+    fn node(&self) -> Node {
+        let mut reports = Node::new("Reports");
+        for (id, sizes) in &self.0 {
+            let kinds: Vec<String> = ["input", "output", "feature"]
+                .iter()
+                .zip(sizes)
+                .filter(|(_, &bits)| bits > 0)
+                .map(|(kind, bits)| {
+                    // The ID takes a byte of its own
+                    let bytes = bits.div_ceil(8) + u32::from(*id != 0);
+                    format!("{} {} bytes", kind, bytes)
+                })
+                .collect();
+            let name = if *id == 0 {
+                "unnumbered".to_string()
+            } else {
+                format!("ID {:#04x}", id)
+            };
+            reports
+                .children
+                .push(Node::new(format!("{}: {}", name, kinds.join(", "))));
+        }
+        reports
+    }
+}
+
+/// Parse a report descriptor into its top-level collections, followed by
+/// a summary of its reports.
+pub fn parse(desc: &[u8]) -> Vec<Node> {
+    let mut globals = Globals::default();
+    let mut stack: Vec<Globals> = Vec::new();
+    let mut usages: Vec<(u16, u16)> = Vec::new();
+    let mut usage_min: Option<(u16, u16)> = None;
+    let mut usage_max: Option<(u16, u16)> = None;
+    // Open collections, innermost last, under a root for the top level
+    let mut open = vec![Node::default()];
+    let mut sizes = ReportSizes::default();
+
+    let mut i = 0;
+    while i < desc.len() {
+        let prefix = desc[i];
+        if prefix == 0xFE {
+            // Long item
+            let Some(&len) = desc.get(i + 1) else { break };
+            i += 3 + len as usize;
+            continue;
+        }
+        let size = match prefix & 0x03 {
+            3 => 4,
+            n => n as usize,
+        };
+        let Some(data) = desc.get(i + 1..i + 1 + size) else {
+            break;
+        };
+        i += 1 + size;
+        let unsigned = data
+            .iter()
+            .rev()
+            .fold(0u32, |acc, &b| (acc << 8) | b as u32);
+        let signed = match size {
+            1 => data[0] as i8 as i32,
+            2 => i16::from_le_bytes([data[0], data[1]]) as i32,
+            4 => unsigned as i32,
+            _ => 0,
+        };
+        // A 4-byte usage carries its own page
+        let full_usage = |value: u32| {
+            if size == 4 {
+                ((value >> 16) as u16, value as u16)
+            } else {
+                (globals.usage_page, value as u16)
+            }
+        };
+
+        match prefix & 0xFC {
+            0x04 => globals.usage_page = unsigned as u16,
+            0x14 => globals.logical_min = signed,
+            // Unsigned when the minimum is, e.g. 0..255 in one byte
+            0x24 if globals.logical_min >= 0 => globals.logical_max = unsigned as i32,
+            0x24 => globals.logical_max = signed,
+            0x74 => globals.report_size = unsigned,
+            0x84 => globals.report_id = unsigned as u8,
+            0x94 => globals.report_count = unsigned,
+            0xA4 => stack.push(globals),
+            0xB4 => globals = stack.pop().unwrap_or_default(),
+            0x08 => usages.push(full_usage(unsigned)),
+            0x18 => usage_min = Some(full_usage(unsigned)),
+            0x28 => usage_max = Some(full_usage(unsigned)),
+            0xA0 => {
+                let kind = data.first().copied().unwrap_or(0);
+                let usage = match usages.first() {
+                    Some(&(page, usage)) => {
+                        format!("{} / {}", usage_page_name(page), usage_name(page, usage))
+                    }
+                    None => usage_page_name(globals.usage_page),
+                };
+                open.push(Node::new(format!(
+                    "{} Collection: {}",
+                    collection_kind(kind),
+                    usage
+                )));
+                usages.clear();
+                (usage_min, usage_max) = (None, None);
+            }
+            0xC0 if open.len() > 1 => {
+                let done = open.pop().unwrap_or_default();
+                if let Some(parent) = open.last_mut() {
+                    parent.children.push(done);
+                }
+            }
+            0x80 | 0x90 | 0xB0 => {
+                let (kind, name) = match prefix & 0xFC {
+                    0x80 => (0, "Input"),
+                    0x90 => (1, "Output"),
+                    _ => (2, "Feature"),
+                };
+                let flags = data.first().copied().unwrap_or(0);
+                let bits = globals.report_size * globals.report_count;
+                sizes.add(globals.report_id, kind, bits);
+                let report = if globals.report_id == 0 {
+                    String::new()
+                } else {
+                    format!(" report {:#04x}", globals.report_id)
+                };
+                let label = if flags & 0x01 != 0 {
+                    format!("{}{}: {} bits padding", name, report, bits)
+                } else {
+                    let named = match (usage_min, usage_max) {
+                        (Some(min), Some(max)) if usages.is_empty() => {
+                            format!("{}..{}", usage_name(min.0, min.1), usage_name(max.0, max.1))
+                        }
+                        _ => usages
+                            .iter()
+                            .map(|&(page, usage)| usage_name(page, usage))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    };
+                    format!(
+                        "{} ({}, {}){}: {}; {} bit{} × {}, logical {}..{}",
+                        name,
+                        if flags & 0x02 != 0 { "Var" } else { "Array" },
+                        if flags & 0x04 != 0 { "Rel" } else { "Abs" },
+                        report,
+                        named,
+                        globals.report_size,
+                        if globals.report_size == 1 { "" } else { "s" },
+                        globals.report_count,
+                        globals.logical_min,
+                        globals.logical_max
+                    )
+                };
+                if let Some(parent) = open.last_mut() {
+                    parent.children.push(Node::new(label));
+                }
+                usages.clear();
+                (usage_min, usage_max) = (None, None);
+            }
+            _ => {}
+        }
+    }
+
+    // Collections left open by a truncated descriptor
+    while open.len() > 1 {
+        let done = open.pop().unwrap_or_default();
+        if let Some(parent) = open.last_mut() {
+            parent.children.push(done);
+        }
+    }
+    let mut top = open.pop().unwrap_or_default().children;
+    top.push(sizes.node());
+    top
+}
+
+/// The tree for the touchpad with evdev node `evdev_path`, from its hidraw
+/// sibling's descriptor.
+#[cfg(target_os = "linux")]
+pub fn read(evdev_path: &std::path::Path) -> std::io::Result<Vec<Node>> {
+    let hidraw = crate::heatmap::discovery::find_sibling_hidraw(evdev_path)?;
+    let name = hidraw
+        .file_name()
+        .ok_or_else(|| std::io::Error::other("bad hidraw path"))?
+        .to_string_lossy();
+    let desc = std::fs::read(format!(
+        "/sys/class/hidraw/{}/device/report_descriptor",
+        name
+    ))?;
+    Ok(parse(&desc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_collection_tree_and_report_sizes() {
+        #[rustfmt::skip]
+        let desc = [
+            0x05, 0x0D,       // Usage Page (Digitizer)
+            0x09, 0x05,       // Usage (Touch Pad)
+            0xA1, 0x01,       // Collection (Application)
+            0x85, 0x03,       //   Report ID (3)
+            0x09, 0x22,       //   Usage (Finger)
+            0xA1, 0x02,       //   Collection (Logical)
+            0x09, 0x42,       //     Usage (Tip Switch)
+            0x09, 0x47,       //     Usage (Confidence)
+            0x15, 0x00,       //     Logical Minimum (0)
+            0x25, 0x01,       //     Logical Maximum (1)
+            0x75, 0x01,       //     Report Size (1)
+            0x95, 0x02,       //     Report Count (2)
+            0x81, 0x02,       //     Input (Data, Var, Abs)
+            0x95, 0x06,       //     Report Count (6)
+            0x81, 0x03,       //     Input (Const)
+            0xC0,             //   End Collection
+            0x09, 0x55,       //   Usage (Contact Count Maximum)
+            0x25, 0xFF,       //   Logical Maximum (255)
+            0x75, 0x08,       //   Report Size (8)
+            0x95, 0x01,       //   Report Count (1)
+            0xB1, 0x02,       //   Feature (Data, Var, Abs)
+            0xC0,             // End Collection
+        ];
+        let tree = parse(&desc);
+        assert_eq!(tree.len(), 2);
+        let app = &tree[0];
+        assert_eq!(app.label, "Application Collection: Digitizer / Touch Pad");
+        let finger = &app.children[0];
+        assert_eq!(finger.label, "Logical Collection: Digitizer / Finger");
+        assert_eq!(
+            finger.children[0].label,
+            "Input (Var, Abs) report 0x03: Tip Switch, Confidence; 1 bit × 2, logical 0..1"
+        );
+        assert_eq!(
+            finger.children[1].label,
+            "Input report 0x03: 6 bits padding"
+        );
+        assert_eq!(
+            app.children[1].label,
+            "Feature (Var, Abs) report 0x03: Contact Count Maximum; 8 bits × 1, logical 0..255"
+        );
+        assert_eq!(
+            tree[1].children[0].label,
+            "ID 0x03: input 2 bytes, feature 2 bytes"
+        );
+    }
+}
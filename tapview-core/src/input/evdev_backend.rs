@@ -0,0 +1,521 @@
+use super::evdev_device::EvdevDevice;
+use super::forward::{ForwardFilter, Forwarder};
+use super::{InputBackend, InputError, ReportTime, TouchState, Waker};
+use crate::multitouch::{self, MTStateMachine, Protocol, MAX_TOUCH_POINTS};
+use crate::recording::{AxisInfo, DeviceCapabilities};
+use evdev::{AbsoluteAxisType, EventType, MiscType, PropType, Synchronization};
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::time::Instant;
+
+/// The single-touch axis reported instead of an MT one on devices without
+/// MT axes.
+fn single_touch_axis(axis: AbsoluteAxisType) -> Option<AbsoluteAxisType> {
+    match axis {
+        AbsoluteAxisType::ABS_MT_POSITION_X => Some(AbsoluteAxisType::ABS_X),
+        AbsoluteAxisType::ABS_MT_POSITION_Y => Some(AbsoluteAxisType::ABS_Y),
+        AbsoluteAxisType::ABS_MT_PRESSURE => Some(AbsoluteAxisType::ABS_PRESSURE),
+        AbsoluteAxisType::ABS_MT_DISTANCE => Some(AbsoluteAxisType::ABS_DISTANCE),
+        _ => None,
+    }
+}
+
+/// Absinfo for each of `axes`, substituting the single-touch axis for MT
+/// axes the device lacks.
+fn read_absinfo(device_path: &Path, axes: &[AbsoluteAxisType]) -> Option<Vec<libc::input_absinfo>> {
+    let device = EvdevDevice::open(device_path).ok()?;
+    axes.iter()
+        .map(|&axis| {
+            let axis = match single_touch_axis(axis) {
+                Some(fallback) if !device.has_axis(axis) => fallback,
+                _ => axis,
+            };
+            device.absinfo(axis).ok()
+        })
+        .collect()
+}
+
+/// How the device reports contacts, from the axes it has.
+fn detect_protocol(device: &EvdevDevice) -> Protocol {
+    if device.has_axis(AbsoluteAxisType::ABS_MT_SLOT) {
+        Protocol::Slots
+    } else if device.has_axis(AbsoluteAxisType::ABS_MT_POSITION_X) {
+        Protocol::Anonymous
+    } else {
+        Protocol::SingleTouch
+    }
+}
+
+/// Number of contacts the device tracks: ABS_MT_SLOT's maximum plus one, or
+/// one for single-touch devices. None for MT protocol A devices, which don't
+/// say. May exceed [`MAX_TOUCH_POINTS`]; slots past that are ignored.
+pub fn read_slot_count(device_path: &Path) -> Option<usize> {
+    let device = EvdevDevice::open(device_path).ok()?;
+    slot_count(&device)
+}
+
+fn slot_count(device: &EvdevDevice) -> Option<usize> {
+    match detect_protocol(device) {
+        Protocol::Slots => {
+            let max = device.absinfo(AbsoluteAxisType::ABS_MT_SLOT).ok()?.maximum;
+            usize::try_from(max).ok().map(|max| max + 1)
+        }
+        Protocol::Anonymous => None,
+        Protocol::SingleTouch => Some(1),
+    }
+}
+
+/// Read ABS_MT_POSITION_X/Y axis extents from evdev absinfo (ABS_X/Y on
+/// single-touch devices).
+/// Returns (x_max, y_max) with positions counted from the axis minimum, as
+/// the backend reports them (see [`MTStateMachine::set_origin`]). The kernel
+/// applies any axis swaps before exposing the evdev device, so these always
+/// match the event coordinates.
+pub fn read_axis_extents(device_path: &Path) -> Option<(i32, i32)> {
+    let abs = read_absinfo(
+        device_path,
+        &[
+            AbsoluteAxisType::ABS_MT_POSITION_X,
+            AbsoluteAxisType::ABS_MT_POSITION_Y,
+        ],
+    )?;
+    let (x, y) = (abs[0], abs[1]);
+    let (w, h) = (x.maximum - x.minimum.min(0), y.maximum - y.minimum.min(0));
+    if w > 0 && h > 0 {
+        Some((w, h))
+    } else {
+        None
+    }
+}
+
+/// The X and Y minimums where they're below 0, like the Magic Trackpad's
+/// centered axes. Positive minimums are kept: the view shows the margin.
+fn axis_origin(device: &EvdevDevice, protocol: Protocol) -> (i32, i32) {
+    let (x, y) = match protocol {
+        Protocol::SingleTouch => (AbsoluteAxisType::ABS_X, AbsoluteAxisType::ABS_Y),
+        _ => (
+            AbsoluteAxisType::ABS_MT_POSITION_X,
+            AbsoluteAxisType::ABS_MT_POSITION_Y,
+        ),
+    };
+    let min = |axis| device.absinfo(axis).map_or(0, |info| info.minimum.min(0));
+    (min(x), min(y))
+}
+
+/// Read ABS_MT_POSITION_X/Y resolution (units per mm) from evdev absinfo
+/// (ABS_X/Y on single-touch devices).
+/// Returns (x_res, y_res), or None if the driver doesn't report a resolution.
+pub fn read_axis_resolution(device_path: &Path) -> Option<(i32, i32)> {
+    let abs = read_absinfo(
+        device_path,
+        &[
+            AbsoluteAxisType::ABS_MT_POSITION_X,
+            AbsoluteAxisType::ABS_MT_POSITION_Y,
+        ],
+    )?;
+    let (x, y) = (abs[0], abs[1]);
+    if x.resolution > 0 && y.resolution > 0 {
+        Some((x.resolution, y.resolution))
+    } else {
+        None
+    }
+}
+
+/// Read the ABS_MT_PRESSURE range from evdev absinfo.
+/// Returns (min, max), or None if the device doesn't report pressure.
+pub fn read_pressure_range(device_path: &Path) -> Option<(i32, i32)> {
+    let p = read_absinfo(device_path, &[AbsoluteAxisType::ABS_MT_PRESSURE])?[0];
+    if p.maximum > p.minimum {
+        Some((p.minimum, p.maximum))
+    } else {
+        None
+    }
+}
+
+/// Whether the device sets INPUT_PROP_BUTTONPAD (a clickpad with no
+/// separate physical buttons).
+pub fn read_is_buttonpad(device_path: &Path) -> bool {
+    EvdevDevice::open(device_path)
+        .map(|d| d.has_property(PropType::BUTTONPAD))
+        .unwrap_or(false)
+}
+
+/// Whether the device sets INPUT_PROP_SEMI_MT: with two or more fingers
+/// down its two slots hold the corners of a bounding box around them.
+pub fn read_is_semi_mt(device_path: &Path) -> bool {
+    EvdevDevice::open(device_path)
+        .map(|d| d.has_property(PropType::SEMI_MT))
+        .unwrap_or(false)
+}
+
+/// Force-feedback effect types (linux/input.h). FF_HAPTIC is the HID
+/// haptic waveform effect haptic touchpads expose on newer kernels.
+const FF_HAPTIC: u16 = 0x4f;
+const FF_RUMBLE: u16 = 0x50;
+/// Haptics page "Waveform Click" usage, for FF_HAPTIC
+const HID_WAVEFORM_CLICK: u16 = 0x1003;
+/// How long a rumble stands in for a click
+const RUMBLE_MS: u16 = 20;
+
+/// Whether the device can play a haptic pulse (see [`EvdevBackend::pulse`]).
+pub fn read_has_haptics(device_path: &Path) -> bool {
+    EvdevDevice::open(device_path)
+        .map(|d| d.has_ff(FF_HAPTIC) || d.has_ff(FF_RUMBLE))
+        .unwrap_or(false)
+}
+
+/// One click: the click waveform on a haptic touchpad, else a short rumble.
+fn click_effect(haptic: bool) -> libc::ff_effect {
+    let mut effect: libc::ff_effect = unsafe { std::mem::zeroed() };
+    // The union after the common header, as bytes
+    let mut params = [0u8; 12];
+    if haptic {
+        effect.type_ = FF_HAPTIC;
+        // ff_haptic_effect: hid_usage, vendor_id, vendor_waveform_page,
+        // intensity (percent), repeat_count, retrigger_period
+        params[0..2].copy_from_slice(&HID_WAVEFORM_CLICK.to_ne_bytes());
+        params[6..8].copy_from_slice(&100u16.to_ne_bytes());
+    } else {
+        effect.type_ = FF_RUMBLE;
+        effect.replay.length = RUMBLE_MS;
+        // ff_rumble_effect: strong_magnitude, weak_magnitude
+        params[0..2].copy_from_slice(&0xc000u16.to_ne_bytes());
+    }
+    let union = unsafe {
+        std::slice::from_raw_parts_mut(
+            effect.u.as_mut_ptr() as *mut u8,
+            std::mem::size_of_val(&effect.u),
+        )
+    };
+    union[..params.len()].copy_from_slice(&params);
+    effect
+}
+
+/// Read the ABS_MT_ORIENTATION maximum, which by kernel convention marks a
+/// quarter revolution clockwise. Returns None if the device doesn't report it.
+pub fn read_orientation_max(device_path: &Path) -> Option<i32> {
+    let o = read_absinfo(device_path, &[AbsoluteAxisType::ABS_MT_ORIENTATION])?[0];
+    (o.maximum > 0).then_some(o.maximum)
+}
+
+/// Read the device name the kernel reports (EVIOCGNAME).
+pub fn read_device_name(device_path: &Path) -> Option<String> {
+    EvdevDevice::open(device_path).ok()?.name().ok()
+}
+
+/// Read the ABS_MT_DISTANCE maximum for hover-capable devices. Returns None
+/// if the device doesn't report hover distance.
+pub fn read_distance_max(device_path: &Path) -> Option<i32> {
+    let d = read_absinfo(device_path, &[AbsoluteAxisType::ABS_MT_DISTANCE])?[0];
+    (d.maximum > 0).then_some(d.maximum)
+}
+
+/// The device's name, id, properties, keys and axes, for a recording to keep
+/// so its replay can recreate the device.
+pub fn read_capabilities(device_path: &Path) -> Option<DeviceCapabilities> {
+    let device = EvdevDevice::open(device_path).ok()?;
+    let id = device.input_id().ok()?;
+    let axes = device
+        .axes()
+        .ok()?
+        .into_iter()
+        .filter_map(|axis| {
+            let info = device.absinfo(axis).ok()?;
+            Some(AxisInfo {
+                code: axis.0,
+                minimum: info.minimum,
+                maximum: info.maximum,
+                fuzz: info.fuzz,
+                flat: info.flat,
+                resolution: info.resolution,
+            })
+        })
+        .collect();
+    Some(DeviceCapabilities {
+        name: device.name().ok()?,
+        id: [id.bus_type().0, id.vendor(), id.product(), id.version()],
+        properties: device.properties().ok()?.iter().map(|p| p.0).collect(),
+        keys: device.keys().ok()?.iter().map(|k| k.code()).collect(),
+        axes,
+    })
+}
+
+/// Single-touch axes read back after SYN_DROPPED.
+const SINGLE_TOUCH_AXES: [AbsoluteAxisType; 5] = [
+    AbsoluteAxisType::ABS_X,
+    AbsoluteAxisType::ABS_Y,
+    AbsoluteAxisType::ABS_PRESSURE,
+    AbsoluteAxisType::ABS_DISTANCE,
+    AbsoluteAxisType::ABS_TOOL_WIDTH,
+];
+
+/// MT axes read back with EVIOCGMTSLOTS after SYN_DROPPED.
+const MT_AXES: [AbsoluteAxisType; 13] = [
+    AbsoluteAxisType::ABS_MT_TRACKING_ID,
+    AbsoluteAxisType::ABS_MT_POSITION_X,
+    AbsoluteAxisType::ABS_MT_POSITION_Y,
+    AbsoluteAxisType::ABS_MT_PRESSURE,
+    AbsoluteAxisType::ABS_MT_DISTANCE,
+    AbsoluteAxisType::ABS_MT_TOUCH_MAJOR,
+    AbsoluteAxisType::ABS_MT_TOUCH_MINOR,
+    AbsoluteAxisType::ABS_MT_WIDTH_MAJOR,
+    AbsoluteAxisType::ABS_MT_WIDTH_MINOR,
+    AbsoluteAxisType::ABS_MT_ORIENTATION,
+    AbsoluteAxisType::ABS_MT_TOOL_X,
+    AbsoluteAxisType::ABS_MT_TOOL_Y,
+    AbsoluteAxisType::ABS_MT_TOOL_TYPE,
+];
+
+pub struct EvdevBackend {
+    /// Raw event stream, so SYN_DROPPED reaches us instead of being
+    /// compensated without the MT slots
+    device: EvdevDevice,
+    machine: MTStateMachine,
+    /// Events were lost; discard until the next SYN_REPORT, then resync
+    dropped: bool,
+    grabbed: bool,
+    /// Pass events on through uinput while grabbed
+    forward: Option<ForwardFilter>,
+    forwarder: Option<Forwarder>,
+    /// Id of the uploaded click effect
+    pulse_effect: Option<i16>,
+}
+
+impl EvdevBackend {
+    /// Forward events to a uinput copy of the device while it's grabbed,
+    /// starting with the next grab.
+    pub fn set_forwarding(&mut self, filter: Option<ForwardFilter>) {
+        self.forward = filter;
+    }
+
+    /// Re-read the absolute slot and key state from the kernel, as
+    /// recommended after SYN_DROPPED, and pass it on to the forwarded device.
+    fn resync(&mut self) -> std::io::Result<()> {
+        let axes: Vec<_> = SINGLE_TOUCH_AXES
+            .into_iter()
+            .filter(|&axis| self.device.has_axis(axis))
+            .map(|axis| Ok((axis, self.device.absinfo(axis)?.value)))
+            .collect::<std::io::Result<_>>()?;
+        let keys = self.device.key_state()?;
+        let (slot, slots, mt_axes) = if self.machine.protocol() == Protocol::Slots {
+            let slot = self.device.absinfo(AbsoluteAxisType::ABS_MT_SLOT)?.value;
+            let slots = slot_count(&self.device).unwrap_or(MAX_TOUCH_POINTS);
+            let mut mt_axes = Vec::with_capacity(MT_AXES.len());
+            for axis in MT_AXES {
+                mt_axes.push((axis, self.device.mt_slots(axis, slots)?));
+            }
+            self.machine
+                .resync(slot, slots, &mt_axes, |key| keys.contains(key));
+            (slot, slots, mt_axes)
+        } else {
+            self.machine
+                .resync_unslotted(&axes, |key| keys.contains(key));
+            (0, 0, Vec::new())
+        };
+        if let Some(forwarder) = &mut self.forwarder {
+            if let Err(e) = forwarder.resync(slot, slots, &mt_axes, &axes, |key| keys.contains(key))
+            {
+                log_warn!(target: "input", "stopped forwarding events: {}", e);
+                self.forwarder = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Closing the fd would release the grab too, but not before every other
+// handle to it (e.g. a forked child) is gone
+impl Drop for EvdevBackend {
+    fn drop(&mut self) {
+        if self.grabbed {
+            if let Err(e) = self.ungrab() {
+                log_warn!(target: "input", "{}", e);
+            }
+        }
+    }
+}
+
+impl InputBackend for EvdevBackend {
+    fn open(device_path: &Path) -> Result<Self, InputError> {
+        // Opened non-blocking so `wait` can also watch for commands
+        let device = EvdevDevice::open(device_path)
+            .map_err(|e| InputError::OpenFailed(format!("{}: {}", device_path.display(), e)))?;
+
+        let protocol = detect_protocol(&device);
+        let mut machine = MTStateMachine::with_protocol(protocol);
+        if let Some(count) = slot_count(&device).filter(|&c| c > MAX_TOUCH_POINTS) {
+            log_info!(
+                target: "input",
+                "device has {} slots; showing up to {} contacts in the first free ones",
+                count,
+                MAX_TOUCH_POINTS
+            );
+            machine.remap_slots();
+        }
+        let (x, y) = axis_origin(&device, protocol);
+        machine.set_origin(x, y);
+        match protocol {
+            Protocol::Slots => {}
+            Protocol::Anonymous => {
+                log_info!(target: "input", "device has no MT slots; reading MT protocol A")
+            }
+            Protocol::SingleTouch => {
+                log_info!(target: "input", "device has no MT axes; reading it as single-touch")
+            }
+        }
+        let mut backend = Self {
+            device,
+            machine,
+            dropped: false,
+            grabbed: false,
+            forward: None,
+            forwarder: None,
+            pulse_effect: None,
+        };
+        // Pick up contacts already down when the device was opened
+        if let Err(e) = backend.resync() {
+            log_warn!(target: "input", "initial slot state unavailable: {}", e);
+        }
+        Ok(backend)
+    }
+
+    fn grab(&mut self) -> Result<(), InputError> {
+        self.device
+            .set_grab(true)
+            .map_err(|e| InputError::GrabFailed(e.to_string()))?;
+        self.grabbed = true;
+        if let Some(filter) = self.forward {
+            match Forwarder::new(&self.device, filter) {
+                Ok(forwarder) => self.forwarder = Some(forwarder),
+                Err(e) => log_warn!(
+                    target: "input",
+                    "can't forward events through /dev/uinput: {}",
+                    e
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    fn ungrab(&mut self) -> Result<(), InputError> {
+        self.device
+            .set_grab(false)
+            .map_err(|e| InputError::GrabFailed(e.to_string()))?;
+        self.grabbed = false;
+        self.forwarder = None;
+        Ok(())
+    }
+
+    /// Upload the click effect on first use, then play it.
+    fn pulse(&mut self) -> Result<(), InputError> {
+        let id = match self.pulse_effect {
+            Some(id) => id,
+            None => {
+                let haptic = self.device.has_ff(FF_HAPTIC);
+                if !haptic && !self.device.has_ff(FF_RUMBLE) {
+                    return Err(InputError::PulseFailed(
+                        "device has no haptic or rumble effect".into(),
+                    ));
+                }
+                let id = self
+                    .device
+                    .upload_effect(&mut click_effect(haptic))
+                    .map_err(|e| InputError::PulseFailed(e.to_string()))?;
+                self.pulse_effect = Some(id);
+                id
+            }
+        };
+        self.device
+            .play_effect(id)
+            .map_err(|e| InputError::PulseFailed(e.to_string()))
+    }
+
+    fn poll_events(&mut self) -> Result<Option<TouchState>, InputError> {
+        // Collected so the device is free for a resync mid-batch
+        match self.device.read_events() {
+            Ok(events) => {
+                let mut reports = 0;
+                let mut report_times = Vec::new();
+                let mut firmware_us = None;
+                for event in events {
+                    // Only formatted with --verbose or input=trace
+                    multitouch::print_event(&event);
+                    if event.event_type() == EventType::SYNCHRONIZATION {
+                        if event.code() == Synchronization::SYN_DROPPED.0 {
+                            self.dropped = true;
+                            continue;
+                        }
+                        if self.dropped && event.code() == Synchronization::SYN_REPORT.0 {
+                            self.dropped = false;
+                            log_warn!(target: "input", "SYN_DROPPED, events lost; resyncing slot state");
+                            if let Err(e) = self.resync() {
+                                log_warn!(target: "input", "resync failed: {}", e);
+                            }
+                            continue;
+                        }
+                    }
+                    if self.dropped {
+                        continue;
+                    }
+                    if let Some(forwarder) = &mut self.forwarder {
+                        if let Err(e) = forwarder.forward(&event) {
+                            log_warn!(target: "input", "stopped forwarding events: {}", e);
+                            self.forwarder = None;
+                        }
+                    }
+                    self.machine.process(&event);
+                    if event.event_type() == EventType::MISC
+                        && event.code() == MiscType::MSC_TIMESTAMP.0
+                    {
+                        firmware_us = Some(event.value() as u32);
+                    }
+                    if event.event_type() == EventType::SYNCHRONIZATION
+                        && event.code() == Synchronization::SYN_REPORT.0
+                    {
+                        reports += 1;
+                        let contacts = self.machine.touches.iter().filter(|t| t.used).count();
+                        report_times.push(ReportTime {
+                            time: event.timestamp(),
+                            firmware_us: firmware_us.take(),
+                            contacts: contacts as u8,
+                        });
+                    }
+                }
+                Ok(Some(TouchState {
+                    touches: self.machine.touches,
+                    buttons: self.machine.buttons,
+                    reports,
+                    time: Instant::now(),
+                    report_times,
+                }))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(InputError::ReadError(e.to_string())),
+        }
+    }
+
+    fn wait(&mut self, waker: &Waker) -> Result<(), InputError> {
+        let mut fds = [
+            libc::pollfd {
+                fd: self.device.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: waker.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() != std::io::ErrorKind::Interrupted {
+                return Err(InputError::ReadError(e.to_string()));
+            }
+        }
+        if fds[1].revents & libc::POLLIN != 0 {
+            waker.drain();
+        }
+        Ok(())
+    }
+}
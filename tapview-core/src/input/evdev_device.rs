@@ -0,0 +1,295 @@
+//! The evdev ioctls and reads the backend needs, on a plain file descriptor.
+//! The evdev crate only opens devices by path, which doesn't cover
+//! descriptors handed over by logind (see [`crate::seat`]).
+
+use crate::seat;
+use evdev::{AbsoluteAxisType, BusType, EventType, InputEvent, InputId, Key, MiscType, PropType};
+use std::io;
+use std::mem::{size_of, MaybeUninit};
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::path::Path;
+
+// _IOC(dir, 'E', nr, size) = (dir << 30) | (size << 16) | ('E' << 8) | nr
+const IOC_WRITE: libc::c_ulong = 1;
+const IOC_READ: libc::c_ulong = 2;
+
+const fn ioc(dir: libc::c_ulong, nr: libc::c_ulong, size: usize) -> libc::c_ulong {
+    (dir << 30) | ((size as libc::c_ulong) << 16) | ((b'E' as libc::c_ulong) << 8) | nr
+}
+
+/// KEY_CNT bits
+const KEY_BYTES: usize = 0x300 / 8;
+/// ABS_CNT bits
+const ABS_BYTES: usize = 0x40 / 8;
+/// MSC_CNT bits
+const MSC_BYTES: usize = 1;
+/// INPUT_PROP_CNT bits
+const PROP_BYTES: usize = 0x20 / 8;
+/// FF_CNT bits
+const FF_BYTES: usize = 0x80 / 8;
+const NAME_LEN: usize = 256;
+/// Events read per read(2)
+const READ_BATCH: usize = 64;
+
+fn bit_set(bits: &[u8], bit: u16) -> bool {
+    bits.get(bit as usize / 8)
+        .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
+
+/// Key state from EVIOCGKEY.
+pub struct KeyState([u8; KEY_BYTES]);
+
+impl KeyState {
+    pub fn contains(&self, key: Key) -> bool {
+        bit_set(&self.0, key.code())
+    }
+}
+
+pub struct EvdevDevice {
+    fd: OwnedFd,
+}
+
+impl EvdevDevice {
+    /// Open non-blocking, read-write if allowed, else read-only (enough for
+    /// everything but writing events).
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let fd = seat::open(path, libc::O_RDWR | libc::O_NONBLOCK)
+            .or_else(|_| seat::open(path, libc::O_RDONLY | libc::O_NONBLOCK))?;
+        Ok(Self { fd })
+    }
+
+    fn ioctl<T>(&self, request: libc::c_ulong, arg: *mut T) -> io::Result<libc::c_int> {
+        let ret = unsafe { libc::ioctl(self.fd.as_raw_fd(), request as _, arg) };
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(ret)
+        }
+    }
+
+    /// EVIOCGABS: range, resolution and current value of one axis.
+    pub fn absinfo(&self, axis: AbsoluteAxisType) -> io::Result<libc::input_absinfo> {
+        let mut info = MaybeUninit::<libc::input_absinfo>::zeroed();
+        let request = ioc(
+            IOC_READ,
+            0x40 + axis.0 as libc::c_ulong,
+            size_of::<libc::input_absinfo>(),
+        );
+        self.ioctl(request, info.as_mut_ptr())?;
+        Ok(unsafe { info.assume_init() })
+    }
+
+    /// EVIOCGNAME
+    pub fn name(&self) -> io::Result<String> {
+        let mut buf = [0u8; NAME_LEN];
+        let len = self.ioctl(ioc(IOC_READ, 0x06, NAME_LEN), buf.as_mut_ptr())? as usize;
+        let name = &buf[..len.min(NAME_LEN)];
+        let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        Ok(String::from_utf8_lossy(&name[..end]).into_owned())
+    }
+
+    /// EVIOCGPROP
+    pub fn has_property(&self, prop: PropType) -> bool {
+        let mut bits = [0u8; PROP_BYTES];
+        self.ioctl(ioc(IOC_READ, 0x09, PROP_BYTES), bits.as_mut_ptr())
+            .is_ok_and(|_| bit_set(&bits, prop.0))
+    }
+
+    /// EVIOCGID
+    pub fn input_id(&self) -> io::Result<InputId> {
+        let mut id = MaybeUninit::<libc::input_id>::zeroed();
+        self.ioctl(
+            ioc(IOC_READ, 0x02, size_of::<libc::input_id>()),
+            id.as_mut_ptr(),
+        )?;
+        let id = unsafe { id.assume_init() };
+        Ok(InputId::new(
+            BusType(id.bustype),
+            id.vendor,
+            id.product,
+            id.version,
+        ))
+    }
+
+    /// EVIOCGBIT: the codes of `event_type` the device supports.
+    fn event_bits<const N: usize>(&self, event_type: EventType) -> io::Result<[u8; N]> {
+        let mut bits = [0u8; N];
+        self.ioctl(
+            ioc(IOC_READ, 0x20 + event_type.0 as libc::c_ulong, N),
+            bits.as_mut_ptr(),
+        )?;
+        Ok(bits)
+    }
+
+    /// Whether the device has `axis`.
+    pub fn has_axis(&self, axis: AbsoluteAxisType) -> bool {
+        self.event_bits::<ABS_BYTES>(EventType::ABSOLUTE)
+            .is_ok_and(|bits| bit_set(&bits, axis.0))
+    }
+
+    /// Every absolute axis the device has.
+    pub fn axes(&self) -> io::Result<Vec<AbsoluteAxisType>> {
+        let bits = self.event_bits::<ABS_BYTES>(EventType::ABSOLUTE)?;
+        Ok((0..ABS_BYTES as u16 * 8)
+            .filter(|&code| bit_set(&bits, code))
+            .map(AbsoluteAxisType)
+            .collect())
+    }
+
+    /// Every key and button the device can report.
+    pub fn keys(&self) -> io::Result<Vec<Key>> {
+        let bits = self.event_bits::<KEY_BYTES>(EventType::KEY)?;
+        Ok((0..KEY_BYTES as u16 * 8)
+            .filter(|&code| bit_set(&bits, code))
+            .map(Key::new)
+            .collect())
+    }
+
+    pub fn has_misc(&self, misc: MiscType) -> bool {
+        self.event_bits::<MSC_BYTES>(EventType::MISC)
+            .is_ok_and(|bits| bit_set(&bits, misc.0))
+    }
+
+    /// Whether the device can play force-feedback effects of `effect_type`.
+    pub fn has_ff(&self, effect_type: u16) -> bool {
+        self.event_bits::<FF_BYTES>(EventType::FORCEFEEDBACK)
+            .is_ok_and(|bits| bit_set(&bits, effect_type))
+    }
+
+    /// EVIOCSFF: upload `effect` as a new effect (id -1). Returns the id the
+    /// kernel assigned, which it also writes back into `effect`. Effects are
+    /// erased when the device is closed.
+    pub fn upload_effect(&self, effect: &mut libc::ff_effect) -> io::Result<i16> {
+        effect.id = -1;
+        self.ioctl(
+            ioc(IOC_WRITE, 0x80, size_of::<libc::ff_effect>()),
+            effect as *mut libc::ff_effect,
+        )?;
+        Ok(effect.id)
+    }
+
+    /// Play the uploaded effect `id` once. Needs the device open read-write.
+    pub fn play_effect(&self, id: i16) -> io::Result<()> {
+        let mut event: libc::input_event = unsafe { MaybeUninit::zeroed().assume_init() };
+        event.type_ = EventType::FORCEFEEDBACK.0;
+        event.code = id as u16;
+        event.value = 1;
+        let ret = unsafe {
+            libc::write(
+                self.fd.as_raw_fd(),
+                &event as *const libc::input_event as *const libc::c_void,
+                size_of::<libc::input_event>(),
+            )
+        };
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// EVIOCGPROP, all set properties.
+    pub fn properties(&self) -> io::Result<Vec<PropType>> {
+        let mut bits = [0u8; PROP_BYTES];
+        self.ioctl(ioc(IOC_READ, 0x09, PROP_BYTES), bits.as_mut_ptr())?;
+        Ok((0..PROP_BYTES as u16 * 8)
+            .filter(|&code| bit_set(&bits, code))
+            .map(PropType)
+            .collect())
+    }
+
+    /// EVIOCGKEY
+    pub fn key_state(&self) -> io::Result<KeyState> {
+        let mut bits = [0u8; KEY_BYTES];
+        self.ioctl(ioc(IOC_READ, 0x18, KEY_BYTES), bits.as_mut_ptr())?;
+        Ok(KeyState(bits))
+    }
+
+    /// EVIOCGMTSLOTS for one MT axis: its value in each of the device's
+    /// `slots`, which may be more than
+    /// [`MAX_TOUCH_POINTS`](crate::multitouch::MAX_TOUCH_POINTS). The buffer is
+    /// the axis code followed by one value per slot.
+    pub fn mt_slots(&self, axis: AbsoluteAxisType, slots: usize) -> io::Result<Vec<i32>> {
+        let mut buf = vec![0; slots + 1];
+        buf[0] = axis.0 as i32;
+        self.ioctl(
+            ioc(IOC_READ, 0x0a, size_of::<i32>() * buf.len()),
+            buf.as_mut_ptr(),
+        )?;
+        buf.remove(0);
+        Ok(buf)
+    }
+
+    /// EVIOCGRAB. The argument is passed by value, not through a pointer.
+    pub fn set_grab(&self, grab: bool) -> io::Result<()> {
+        let request = ioc(IOC_WRITE, 0x90, size_of::<libc::c_int>());
+        let ret = unsafe { libc::ioctl(self.fd.as_raw_fd(), request as _, grab as libc::c_int) };
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read everything queued. Fails with `WouldBlock` if nothing was.
+    pub fn read_events(&self) -> io::Result<Vec<InputEvent>> {
+        let mut events = Vec::new();
+        loop {
+            let mut buf = [MaybeUninit::<libc::input_event>::uninit(); READ_BATCH];
+            let ret = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    size_of_val(&buf),
+                )
+            };
+            if ret < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::WouldBlock && !events.is_empty() {
+                    return Ok(events);
+                }
+                return Err(err);
+            }
+            let count = ret as usize / size_of::<libc::input_event>();
+            events.extend(
+                buf[..count]
+                    .iter()
+                    .map(|raw| InputEvent::from(unsafe { raw.assume_init() })),
+            );
+            if count < READ_BATCH {
+                return Ok(events);
+            }
+        }
+    }
+}
+
+impl AsRawFd for EvdevDevice {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_codes_match_linux_headers() {
+        // EVIOCGRAB, EVIOCGABS(ABS_MT_SLOT), EVIOCGKEY(KEY_CNT / 8)
+        assert_eq!(ioc(IOC_WRITE, 0x90, size_of::<libc::c_int>()), 0x4004_4590);
+        assert_eq!(
+            ioc(IOC_READ, 0x40 + 0x2f, size_of::<libc::input_absinfo>()),
+            0x8018_456f
+        );
+        assert_eq!(ioc(IOC_READ, 0x18, KEY_BYTES), 0x8060_4518);
+        // EVIOCSFF, EVIOCGBIT(EV_FF, FF_CNT / 8)
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(
+            ioc(IOC_WRITE, 0x80, size_of::<libc::ff_effect>()),
+            0x4030_4580
+        );
+        assert_eq!(ioc(IOC_READ, 0x20 + 0x15, FF_BYTES), 0x8010_4535);
+        assert!(bit_set(&[0, 0b100], 10));
+        assert!(!bit_set(&[0, 0b100], 9));
+    }
+}
@@ -0,0 +1,242 @@
+//! Passing the touchpad's events on through a uinput device while it's
+//! grabbed, so the desktop keeps its pointer during a demo. The virtual
+//! device copies the touchpad's keys, axes, properties and IDs, so libinput
+//! treats it like the real one, quirks included.
+
+use super::evdev_device::EvdevDevice;
+use evdev::uinput::{VirtualDevice, VirtualDeviceBuilder};
+use evdev::{
+    AbsInfo, AbsoluteAxisType, AttributeSet, EventType, InputEvent, Key, MiscType, Synchronization,
+    UinputAbsSetup,
+};
+use std::io;
+use std::str::FromStr;
+
+/// Which events reach the desktop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForwardFilter {
+    All,
+    /// Everything but physical button clicks (BTN_LEFT/RIGHT/MIDDLE), so
+    /// pressing the pad doesn't click whatever is under the pointer
+    NoClicks,
+}
+
+impl FromStr for ForwardFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "all" => Ok(ForwardFilter::All),
+            "no-clicks" => Ok(ForwardFilter::NoClicks),
+            _ => Err(format!("unknown filter '{}' (all, no-clicks)", s)),
+        }
+    }
+}
+
+impl ForwardFilter {
+    fn passes(self, event: &InputEvent) -> bool {
+        match self {
+            ForwardFilter::All => true,
+            ForwardFilter::NoClicks => {
+                event.event_type() != EventType::KEY
+                    || ![Key::BTN_LEFT, Key::BTN_RIGHT, Key::BTN_MIDDLE]
+                        .contains(&Key::new(event.code()))
+            }
+        }
+    }
+}
+
+pub struct Forwarder {
+    device: VirtualDevice,
+    filter: ForwardFilter,
+    /// Events of the report being read; uinput gets whole reports
+    report: Vec<InputEvent>,
+    /// The source's keys, all set again after a resync
+    keys: Vec<Key>,
+}
+
+impl Forwarder {
+    /// Create a virtual copy of `source`. Needs write access to /dev/uinput.
+    pub fn new(source: &EvdevDevice, filter: ForwardFilter) -> io::Result<Self> {
+        let name = format!(
+            "{} (tapview)",
+            source.name().unwrap_or_else(|_| "Touchpad".to_string())
+        );
+        let source_keys = source.keys()?;
+        let keys: AttributeSet<Key> = source_keys.iter().copied().collect();
+        let properties: AttributeSet<_> = source.properties()?.into_iter().collect();
+        let mut builder = VirtualDeviceBuilder::new()?
+            .name(&name)
+            .input_id(source.input_id()?)
+            .with_keys(&keys)?
+            .with_properties(&properties)?;
+        for axis in source.axes()? {
+            let info = source.absinfo(axis)?;
+            builder = builder.with_absolute_axis(&UinputAbsSetup::new(
+                axis,
+                AbsInfo::new(
+                    info.value,
+                    info.minimum,
+                    info.maximum,
+                    info.fuzz,
+                    info.flat,
+                    info.resolution,
+                ),
+            ))?;
+        }
+        if source.has_misc(MiscType::MSC_TIMESTAMP) {
+            let misc: AttributeSet<MiscType> = [MiscType::MSC_TIMESTAMP].into_iter().collect();
+            builder = builder.with_msc(&misc)?;
+        }
+        Ok(Self {
+            device: builder.build()?,
+            filter,
+            report: Vec::new(),
+            keys: source_keys,
+        })
+    }
+
+    /// Queue `event`, emitting the report once its SYN_REPORT arrives.
+    pub fn forward(&mut self, event: &InputEvent) -> io::Result<()> {
+        if event.event_type() == EventType::SYNCHRONIZATION {
+            match Synchronization(event.code()) {
+                // emit() ends the batch with its own SYN_REPORT
+                Synchronization::SYN_REPORT => {
+                    let result = self.device.emit(&self.report);
+                    self.report.clear();
+                    return result;
+                }
+                Synchronization::SYN_MT_REPORT => {}
+                // SYN_DROPPED is between us and the kernel
+                _ => return Ok(()),
+            }
+        }
+        if self.filter.passes(event) {
+            self.report.push(*event);
+        }
+        Ok(())
+    }
+
+    /// After a SYN_DROPPED, drop the half-read report and bring the virtual
+    /// device up to the state read back from the kernel in one report, so
+    /// the desktop doesn't keep contacts or buttons that were lifted
+    /// meanwhile. Arguments as for [`MTStateMachine::resync`], plus the
+    /// current single-touch `axes`.
+    ///
+    /// [`MTStateMachine::resync`]: crate::multitouch::MTStateMachine::resync
+    pub fn resync(
+        &mut self,
+        current_slot: i32,
+        slots: usize,
+        mt_axes: &[(AbsoluteAxisType, Vec<i32>)],
+        axes: &[(AbsoluteAxisType, i32)],
+        pressed: impl Fn(Key) -> bool,
+    ) -> io::Result<()> {
+        self.report.clear();
+        let report: Vec<_> = state_report(current_slot, slots, mt_axes, axes, &self.keys, pressed)
+            .into_iter()
+            .filter(|event| self.filter.passes(event))
+            .collect();
+        self.device.emit(&report)
+    }
+}
+
+/// The events setting a device to the given state: each slot's MT axes, the
+/// current slot, the single-touch axes and every key.
+fn state_report(
+    current_slot: i32,
+    slots: usize,
+    mt_axes: &[(AbsoluteAxisType, Vec<i32>)],
+    axes: &[(AbsoluteAxisType, i32)],
+    keys: &[Key],
+    pressed: impl Fn(Key) -> bool,
+) -> Vec<InputEvent> {
+    let abs = |axis: AbsoluteAxisType, value| InputEvent::new(EventType::ABSOLUTE, axis.0, value);
+    let mut report = Vec::new();
+    if !mt_axes.is_empty() {
+        for slot in 0..slots {
+            report.push(abs(AbsoluteAxisType::ABS_MT_SLOT, slot as i32));
+            report.extend(
+                mt_axes
+                    .iter()
+                    .filter_map(|(axis, values)| Some(abs(*axis, *values.get(slot)?))),
+            );
+        }
+        report.push(abs(AbsoluteAxisType::ABS_MT_SLOT, current_slot));
+    }
+    report.extend(axes.iter().map(|(axis, value)| abs(*axis, *value)));
+    report.extend(
+        keys.iter()
+            .map(|&key| InputEvent::new(EventType::KEY, key.code(), pressed(key) as i32)),
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_clicks_holds_back_buttons_only() {
+        let key = |key: Key| InputEvent::new(EventType::KEY, key.code(), 1);
+        let filter: ForwardFilter = "no-clicks".parse().unwrap();
+        assert!(!filter.passes(&key(Key::BTN_LEFT)));
+        assert!(!filter.passes(&key(Key::BTN_MIDDLE)));
+        assert!(filter.passes(&key(Key::BTN_TOUCH)));
+        assert!(filter.passes(&key(Key::BTN_TOOL_DOUBLETAP)));
+        assert!(filter.passes(&InputEvent::new(EventType::ABSOLUTE, 0x35, 100)));
+        assert!(ForwardFilter::All.passes(&key(Key::BTN_LEFT)));
+        assert!("some".parse::<ForwardFilter>().is_err());
+    }
+
+    #[test]
+    fn state_report_sets_every_slot_and_key() {
+        // 16 slots, like the Magic Trackpad's, with fingers in 1 and 12
+        let mut ids = vec![-1; 16];
+        ids[1] = 7;
+        ids[12] = 8;
+        let mut xs = vec![0; 16];
+        xs[1] = 300;
+        xs[12] = 900;
+        let mt_axes = [
+            (AbsoluteAxisType::ABS_MT_TRACKING_ID, ids),
+            (AbsoluteAxisType::ABS_MT_POSITION_X, xs),
+        ];
+        let axes = [(AbsoluteAxisType::ABS_X, 300)];
+        let keys = [Key::BTN_LEFT, Key::BTN_TOUCH];
+        let report = state_report(1, 16, &mt_axes, &axes, &keys, |key| key == Key::BTN_TOUCH);
+        let events: Vec<_> = report
+            .iter()
+            .map(|e| (e.event_type(), e.code(), e.value()))
+            .collect();
+        let abs = |axis: AbsoluteAxisType, value| (EventType::ABSOLUTE, axis.0, value);
+        assert_eq!(
+            events[3..6],
+            [
+                abs(AbsoluteAxisType::ABS_MT_SLOT, 1),
+                abs(AbsoluteAxisType::ABS_MT_TRACKING_ID, 7),
+                abs(AbsoluteAxisType::ABS_MT_POSITION_X, 300),
+            ]
+        );
+        // Slots past the ones tapview shows stay down
+        assert_eq!(
+            events[36..39],
+            [
+                abs(AbsoluteAxisType::ABS_MT_SLOT, 12),
+                abs(AbsoluteAxisType::ABS_MT_TRACKING_ID, 8),
+                abs(AbsoluteAxisType::ABS_MT_POSITION_X, 900),
+            ]
+        );
+        assert_eq!(
+            events[events.len() - 4..],
+            [
+                abs(AbsoluteAxisType::ABS_MT_SLOT, 1),
+                abs(AbsoluteAxisType::ABS_X, 300),
+                (EventType::KEY, Key::BTN_LEFT.code(), 0),
+                (EventType::KEY, Key::BTN_TOUCH.code(), 1),
+            ]
+        );
+        // Protocol A and single-touch devices only get axes and keys
+        assert_eq!(state_report(0, 0, &[], &axes, &keys, |_| false).len(), 3);
+    }
+}
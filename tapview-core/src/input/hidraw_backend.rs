@@ -0,0 +1,711 @@
+//! `--backend hidraw`: read contacts from the touchpad's hidraw node and
+//! parse the digitizer input reports ourselves, the way the Windows backend
+//! does, instead of going through hid-multitouch and evdev. When the two
+//! disagree, the kernel is at fault rather than the firmware.
+//!
+//! Only the input report carrying the finger collections of the touchpad
+//! application collection (Digitizer / Touch Pad) is parsed. Contacts keep
+//! a slot for as long as their contact ID is reported, and get increasing
+//! tracking IDs like evdev's. Frames spread over several reports (hybrid
+//! mode, with the contact count in the first) are put back together first.
+//! The Magic Trackpad has no such collections; its vendor report is parsed
+//! instead (see [`magic_trackpad`]).
+
+use super::evdev_device::EvdevDevice;
+use super::magic_trackpad;
+use super::{InputBackend, InputError, ReportTime, TouchState, Waker};
+use crate::heatmap::discovery::find_sibling_hidraw;
+use crate::multitouch::{ButtonState, TouchData, MAX_TOUCH_POINTS};
+use std::fs;
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::path::Path;
+use std::time::{Instant, SystemTime};
+
+const GENERIC_DESKTOP_PAGE: u16 = 0x01;
+const BUTTON_PAGE: u16 = 0x09;
+const DIGITIZER_PAGE: u16 = 0x0D;
+const USAGE_TOUCH_PAD: u16 = 0x05;
+const USAGE_FINGER: u16 = 0x22;
+const MT_TOOL_PALM: i32 = 0x02;
+/// Largest input report hidraw hands out
+const MAX_REPORT: usize = 4096;
+
+/// One field of an input report. Offsets don't count the report ID byte.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Field {
+    bit_offset: usize,
+    bit_size: usize,
+    signed: bool,
+}
+
+impl Field {
+    fn read(&self, data: &[u8]) -> Option<i32> {
+        if self.bit_size == 0 || self.bit_size > 32 {
+            return None;
+        }
+        let end = self.bit_offset + self.bit_size;
+        if end > data.len() * 8 {
+            return None;
+        }
+        let mut value: u64 = 0;
+        for bit in (self.bit_offset..end).rev() {
+            value = (value << 1) | ((data[bit / 8] >> (bit % 8)) & 1) as u64;
+        }
+        if self.signed && self.bit_size < 32 && value & (1 << (self.bit_size - 1)) != 0 {
+            value |= !0u64 << self.bit_size;
+        }
+        Some(value as i32)
+    }
+}
+
+/// Fields of one finger collection.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ContactFields {
+    tip: Option<Field>,
+    confidence: Option<Field>,
+    id: Option<Field>,
+    x: Option<Field>,
+    y: Option<Field>,
+    pressure: Option<Field>,
+    width: Option<Field>,
+    height: Option<Field>,
+}
+
+/// Where the touchpad's input report keeps what we read.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ReportLayout {
+    /// 0 if the device doesn't number its reports
+    report_id: u8,
+    contacts: Vec<ContactFields>,
+    contact_count: Option<Field>,
+    scan_time: Option<Field>,
+    /// Left, right, middle
+    buttons: [Option<Field>; 3],
+}
+
+/// An input field as the descriptor declares it.
+struct DeclaredField {
+    report_id: u8,
+    field: Field,
+    page: u16,
+    usage: u16,
+    /// Index of the finger collection it's in, if any
+    finger: Option<usize>,
+}
+
+/// Collect the variable input fields inside the touchpad application
+/// collection of `desc` and sort them into a layout.
+fn parse_layout(desc: &[u8]) -> Option<ReportLayout> {
+    #[derive(Clone, Copy, Default)]
+    struct Globals {
+        usage_page: u16,
+        logical_min: i32,
+        report_size: usize,
+        report_count: usize,
+        report_id: u8,
+    }
+
+    let mut globals = Globals::default();
+    let mut stack: Vec<Globals> = Vec::new();
+    let mut usages: Vec<(u16, u16)> = Vec::new();
+    let mut usage_min: Option<(u16, u16)> = None;
+    let mut usage_max: Option<(u16, u16)> = None;
+    // (is touchpad application, finger collection index)
+    let mut collections: Vec<(bool, Option<usize>)> = Vec::new();
+    let mut collection_index = 0;
+    let mut input_offsets: Vec<(u8, usize)> = Vec::new();
+    let mut declared = Vec::new();
+
+    let mut i = 0;
+    while i < desc.len() {
+        let prefix = desc[i];
+        if prefix == 0xFE {
+            // Long item
+            let Some(&len) = desc.get(i + 1) else { break };
+            i += 3 + len as usize;
+            continue;
+        }
+        let size = match prefix & 0x03 {
+            3 => 4,
+            n => n as usize,
+        };
+        let Some(data) = desc.get(i + 1..i + 1 + size) else {
+            break;
+        };
+        i += 1 + size;
+        let unsigned = data
+            .iter()
+            .rev()
+            .fold(0u32, |acc, &b| (acc << 8) | b as u32);
+        let signed = match size {
+            1 => data[0] as i8 as i32,
+            2 => i16::from_le_bytes([data[0], data[1]]) as i32,
+            4 => unsigned as i32,
+            _ => 0,
+        };
+        // A 4-byte usage carries its own page
+        let full_usage = |value: u32| {
+            if size == 4 {
+                ((value >> 16) as u16, value as u16)
+            } else {
+                (globals.usage_page, value as u16)
+            }
+        };
+
+        match prefix & 0xFC {
+            0x04 => globals.usage_page = unsigned as u16,
+            0x14 => globals.logical_min = signed,
+            0x74 => globals.report_size = unsigned as usize,
+            0x84 => globals.report_id = unsigned as u8,
+            0x94 => globals.report_count = unsigned as usize,
+            0xA4 => stack.push(globals),
+            0xB4 => globals = stack.pop().unwrap_or_default(),
+            0x08 => usages.push(full_usage(unsigned)),
+            0x18 => usage_min = Some(full_usage(unsigned)),
+            0x28 => usage_max = Some(full_usage(unsigned)),
+            // Collection
+            0xA0 => {
+                let kind = data.first().copied().unwrap_or(0);
+                let usage = usages.first().copied().unwrap_or((globals.usage_page, 0));
+                let in_touchpad = collections.last().is_some_and(|c| c.0);
+                let touchpad =
+                    in_touchpad || (kind == 1 && usage == (DIGITIZER_PAGE, USAGE_TOUCH_PAD));
+                let finger = if kind == 2 && usage == (DIGITIZER_PAGE, USAGE_FINGER) {
+                    Some(collection_index)
+                } else {
+                    collections.last().and_then(|c| c.1)
+                };
+                collections.push((touchpad, finger));
+                collection_index += 1;
+            }
+            0xC0 => {
+                collections.pop();
+            }
+            // Input
+            0x80 => {
+                let flags = data.first().copied().unwrap_or(0);
+                let index = match input_offsets
+                    .iter()
+                    .position(|(id, _)| *id == globals.report_id)
+                {
+                    Some(index) => index,
+                    None => {
+                        input_offsets.push((globals.report_id, 0));
+                        input_offsets.len() - 1
+                    }
+                };
+                let offset = &mut input_offsets[index].1;
+                let (touchpad, finger) = collections.last().copied().unwrap_or((false, None));
+                // Constant padding and arrays only take up space
+                let variable = flags & 0x01 == 0 && flags & 0x02 != 0;
+                for n in 0..globals.report_count {
+                    let usage = if let Some(&usage) = usages.get(n).or(usages.last()) {
+                        Some(usage)
+                    } else if let (Some((page, min)), Some((_, max))) = (usage_min, usage_max) {
+                        Some((page, min.saturating_add(n as u16).min(max)))
+                    } else {
+                        None
+                    };
+                    if let (true, true, Some((page, usage))) = (touchpad, variable, usage) {
+                        declared.push(DeclaredField {
+                            report_id: globals.report_id,
+                            field: Field {
+                                bit_offset: *offset + n * globals.report_size,
+                                bit_size: globals.report_size,
+                                signed: globals.logical_min < 0,
+                            },
+                            page,
+                            usage,
+                            finger,
+                        });
+                    }
+                }
+                *offset += globals.report_count * globals.report_size;
+            }
+            _ => {}
+        }
+        // Main items end the local state
+        if prefix & 0x0C == 0x00 {
+            usages.clear();
+            (usage_min, usage_max) = (None, None);
+        }
+    }
+
+    let report_id = declared.iter().find(|f| f.finger.is_some())?.report_id;
+    let mut layout = ReportLayout {
+        report_id,
+        ..Default::default()
+    };
+    let mut fingers: Vec<usize> = Vec::new();
+    for f in declared.iter().filter(|f| f.report_id == report_id) {
+        match f.finger {
+            Some(finger) => {
+                let index = match fingers.iter().position(|&c| c == finger) {
+                    Some(index) => index,
+                    None => {
+                        fingers.push(finger);
+                        layout.contacts.push(ContactFields::default());
+                        fingers.len() - 1
+                    }
+                };
+                let contact = &mut layout.contacts[index];
+                let slot = match (f.page, f.usage) {
+                    (DIGITIZER_PAGE, 0x42) => &mut contact.tip,
+                    (DIGITIZER_PAGE, 0x47) => &mut contact.confidence,
+                    (DIGITIZER_PAGE, 0x51) => &mut contact.id,
+                    (GENERIC_DESKTOP_PAGE, 0x30) => &mut contact.x,
+                    (GENERIC_DESKTOP_PAGE, 0x31) => &mut contact.y,
+                    (DIGITIZER_PAGE, 0x30) => &mut contact.pressure,
+                    (DIGITIZER_PAGE, 0x48) => &mut contact.width,
+                    (DIGITIZER_PAGE, 0x49) => &mut contact.height,
+                    _ => continue,
+                };
+                slot.get_or_insert(f.field);
+            }
+            None => {
+                let slot = match (f.page, f.usage) {
+                    (DIGITIZER_PAGE, 0x54) => &mut layout.contact_count,
+                    (DIGITIZER_PAGE, 0x56) => &mut layout.scan_time,
+                    (BUTTON_PAGE, 1..=3) => &mut layout.buttons[f.usage as usize - 1],
+                    _ => continue,
+                };
+                slot.get_or_insert(f.field);
+            }
+        }
+    }
+    Some(layout)
+}
+
+/// One contact as reported.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Contact {
+    id: i32,
+    tip: bool,
+    confident: bool,
+    x: i32,
+    y: i32,
+    pressure: i32,
+    width: i32,
+    height: i32,
+    orientation: i32,
+}
+
+/// What one input report says.
+#[derive(Debug, Default)]
+struct Report {
+    contacts: Vec<Contact>,
+    /// Contacts in the frame; 0 in the later reports of a hybrid frame
+    contact_count: Option<usize>,
+    scan_time: Option<u16>,
+    buttons: ButtonState,
+}
+
+/// Parse `report`, which starts with the report ID if the device uses them.
+fn parse_report(layout: &ReportLayout, report: &[u8]) -> Option<Report> {
+    let data = if layout.report_id != 0 {
+        if report.first() != Some(&layout.report_id) {
+            return None;
+        }
+        &report[1..]
+    } else {
+        report
+    };
+    let read = |field: Option<Field>| field.and_then(|f| f.read(data));
+    let pressed = |field: Option<Field>| read(field).is_some_and(|v| v != 0);
+
+    let contact_count = read(layout.contact_count).map(|c| c.max(0) as usize);
+    let contacts = layout
+        .contacts
+        .iter()
+        .map(|fields| Contact {
+            id: read(fields.id).unwrap_or(0),
+            tip: pressed(fields.tip),
+            confident: fields.confidence.is_none() || pressed(fields.confidence),
+            x: read(fields.x).unwrap_or(0),
+            y: read(fields.y).unwrap_or(0),
+            pressure: read(fields.pressure).unwrap_or(0),
+            width: read(fields.width).unwrap_or(0),
+            height: read(fields.height).unwrap_or(0),
+            orientation: 0,
+        })
+        // Contact Count says how many of the collections are filled in
+        .take(
+            contact_count
+                .filter(|&c| c > 0)
+                .unwrap_or(layout.contacts.len()),
+        )
+        .collect();
+    Some(Report {
+        contacts,
+        contact_count,
+        scan_time: read(layout.scan_time).map(|t| t as u16),
+        buttons: ButtonState {
+            left: pressed(layout.buttons[0]),
+            right: pressed(layout.buttons[1]),
+            middle: pressed(layout.buttons[2]),
+            tool_fingers: None,
+        },
+    })
+}
+
+impl From<magic_trackpad::Frame> for Report {
+    fn from(frame: magic_trackpad::Frame) -> Self {
+        Self {
+            contacts: frame
+                .touches
+                .into_iter()
+                .map(|t| Contact {
+                    id: t.id,
+                    tip: t.down,
+                    confident: true,
+                    x: t.x,
+                    y: t.y,
+                    pressure: t.pressure,
+                    width: t.touch_major,
+                    height: t.touch_minor,
+                    orientation: t.orientation,
+                })
+                .collect(),
+            contact_count: None,
+            scan_time: None,
+            buttons: ButtonState {
+                left: frame.button,
+                ..ButtonState::default()
+            },
+        }
+    }
+}
+
+/// Turns reports into slot state.
+#[derive(Default)]
+struct Tracker {
+    touches: [TouchData; MAX_TOUCH_POINTS],
+    /// Contact ID held by each slot
+    ids: [Option<i32>; MAX_TOUCH_POINTS],
+    next_tracking_id: i32,
+    /// Contacts of a hybrid frame still to come, and those seen so far
+    pending: usize,
+    frame: Vec<Contact>,
+    /// ScanTime of the last frame and the unwrapped microseconds
+    scan_time: Option<u16>,
+    firmware_us: u32,
+}
+
+impl Tracker {
+    /// Fold in `report`. Returns true once a frame is complete.
+    fn apply(&mut self, report: Report) -> bool {
+        if report.contact_count.unwrap_or(0) > 0 || self.pending == 0 {
+            self.frame.clear();
+            self.pending = report.contact_count.unwrap_or(report.contacts.len());
+        }
+        let take = report.contacts.len().min(self.pending);
+        self.frame.extend_from_slice(&report.contacts[..take]);
+        self.pending -= take;
+        if self.pending > 0 && take > 0 {
+            return false;
+        }
+        self.pending = 0;
+
+        // Slots whose contact isn't in the frame, or lifted, are freed
+        for slot in 0..MAX_TOUCH_POINTS {
+            let Some(id) = self.ids[slot] else { continue };
+            if !self.frame.iter().any(|c| c.id == id && c.tip) {
+                self.ids[slot] = None;
+                self.touches[slot].reset();
+            }
+        }
+        for contact in self.frame.iter().filter(|c| c.tip) {
+            let slot = match self.ids.iter().position(|&id| id == Some(contact.id)) {
+                Some(slot) => slot,
+                None => {
+                    let Some(slot) = self.ids.iter().position(Option::is_none) else {
+                        continue;
+                    };
+                    self.ids[slot] = Some(contact.id);
+                    self.touches[slot].tracking_id = self.next_tracking_id;
+                    self.next_tracking_id = self.next_tracking_id.wrapping_add(1).max(0);
+                    slot
+                }
+            };
+            let touch = &mut self.touches[slot];
+            touch.used = true;
+            touch.pressed = true;
+            touch.position_x = contact.x;
+            touch.position_y = contact.y;
+            touch.pressure = contact.pressure;
+            touch.touch_major = contact.width.max(contact.height);
+            touch.touch_minor = contact.width.min(contact.height);
+            touch.orientation = contact.orientation;
+            touch.tool_type = if contact.confident { 0 } else { MT_TOOL_PALM };
+        }
+
+        if let Some(scan) = report.scan_time {
+            // 100µs units, wrapping at 16 bits
+            let delta = self.scan_time.map_or(0, |last| scan.wrapping_sub(last));
+            self.firmware_us = self.firmware_us.wrapping_add(delta as u32 * 100);
+            self.scan_time = Some(scan);
+        }
+        true
+    }
+}
+
+/// How the touchpad's input reports are laid out.
+enum Format {
+    /// Described by the report descriptor
+    Digitizer(ReportLayout),
+    MagicTrackpad,
+}
+
+impl Format {
+    fn parse(&self, report: &[u8]) -> Option<Report> {
+        match self {
+            Format::Digitizer(layout) => parse_report(layout, report),
+            Format::MagicTrackpad => magic_trackpad::parse_report(report).map(Report::from),
+        }
+    }
+}
+
+pub struct HidrawBackend {
+    fd: OwnedFd,
+    format: Format,
+    tracker: Tracker,
+    buttons: ButtonState,
+    /// The evdev node, only to grab it so the cursor stops moving
+    evdev: EvdevDevice,
+}
+
+impl InputBackend for HidrawBackend {
+    /// `device_path` is the touchpad's evdev node; its hidraw sibling is read.
+    fn open(device_path: &Path) -> Result<Self, InputError> {
+        let open_failed = |e: io::Error| InputError::OpenFailed(e.to_string());
+        let hidraw = find_sibling_hidraw(device_path).map_err(open_failed)?;
+        let name = hidraw
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| InputError::OpenFailed("bad hidraw path".to_string()))?;
+        let evdev = EvdevDevice::open(device_path).map_err(open_failed)?;
+        let format = match evdev.input_id() {
+            Ok(id) if magic_trackpad::is_magic_trackpad(id.vendor(), id.product()) => {
+                log_info!(
+                    target: "input",
+                    "reading {} directly: Magic Trackpad reports",
+                    hidraw.display()
+                );
+                Format::MagicTrackpad
+            }
+            _ => {
+                let desc = fs::read(format!(
+                    "/sys/class/hidraw/{}/device/report_descriptor",
+                    name
+                ))
+                .map_err(open_failed)?;
+                let layout = parse_layout(&desc).ok_or_else(|| {
+                    InputError::OpenFailed(format!(
+                        "{}: no touchpad finger collections in the report descriptor",
+                        hidraw.display()
+                    ))
+                })?;
+                log_info!(
+                    target: "input",
+                    "reading {} directly: report {:#04x}, {} contact collections",
+                    hidraw.display(),
+                    layout.report_id,
+                    layout.contacts.len()
+                );
+                Format::Digitizer(layout)
+            }
+        };
+        let fd = crate::seat::open(&hidraw, libc::O_RDONLY | libc::O_NONBLOCK)
+            .map_err(|e| InputError::OpenFailed(format!("{}: {}", hidraw.display(), e)))?;
+        Ok(Self {
+            fd,
+            format,
+            tracker: Tracker::default(),
+            buttons: ButtonState::default(),
+            evdev,
+        })
+    }
+
+    fn grab(&mut self) -> Result<(), InputError> {
+        self.evdev
+            .set_grab(true)
+            .map_err(|e| InputError::GrabFailed(e.to_string()))
+    }
+
+    fn ungrab(&mut self) -> Result<(), InputError> {
+        self.evdev
+            .set_grab(false)
+            .map_err(|e| InputError::GrabFailed(e.to_string()))
+    }
+
+    fn poll_events(&mut self) -> Result<Option<TouchState>, InputError> {
+        let mut buf = [0u8; MAX_REPORT];
+        let mut reports = 0;
+        let mut report_times = Vec::new();
+        loop {
+            let ret = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                )
+            };
+            if ret < 0 {
+                let e = io::Error::last_os_error();
+                match e.kind() {
+                    io::ErrorKind::WouldBlock => break,
+                    io::ErrorKind::Interrupted => continue,
+                    _ => return Err(InputError::ReadError(e.to_string())),
+                }
+            }
+            if ret == 0 {
+                break;
+            }
+            // Other reports, e.g. a mouse collection's, are skipped
+            let Some(report) = self.format.parse(&buf[..ret as usize]) else {
+                continue;
+            };
+            let buttons = report.buttons;
+            let has_scan_time = report.scan_time.is_some();
+            if self.tracker.apply(report) {
+                self.buttons = buttons;
+                reports += 1;
+                report_times.push(ReportTime {
+                    time: SystemTime::now(),
+                    firmware_us: has_scan_time.then_some(self.tracker.firmware_us),
+                    contacts: self.tracker.touches.iter().filter(|t| t.used).count() as u8,
+                });
+            }
+        }
+        if reports == 0 {
+            return Ok(None);
+        }
+        Ok(Some(TouchState {
+            touches: self.tracker.touches,
+            buttons: self.buttons,
+            reports,
+            time: Instant::now(),
+            report_times,
+        }))
+    }
+
+    fn wait(&mut self, waker: &Waker) -> Result<(), InputError> {
+        let mut fds = [
+            libc::pollfd {
+                fd: self.fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: waker.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(InputError::ReadError(e.to_string()));
+            }
+        }
+        if fds[1].revents & libc::POLLIN != 0 {
+            waker.drain();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Report 0x01: two finger collections of tip switch, confidence,
+    /// 6 bits padding, contact ID (8), X and Y (16 each), then scan time
+    /// (16), contact count (8) and button 1 with 7 bits padding.
+    const DESCRIPTOR: &[u8] = &[
+        0x05, 0x0D, // Usage Page (Digitizer)
+        0x09, 0x05, // Usage (Touch Pad)
+        0xA1, 0x01, // Collection (Application)
+        0x85, 0x01, //   Report ID (1)
+        0x09, 0x22, //   Usage (Finger)
+        0xA1, 0x02, //   Collection (Logical)
+        0x09, 0x42, 0x09, 0x47, 0x15, 0x00, 0x25, 0x01, 0x75, 0x01, 0x95, 0x02, 0x81,
+        0x02, //     Input (Tip Switch, Confidence)
+        0x95, 0x06, 0x81, 0x03, //     Input (padding)
+        0x09, 0x51, 0x26, 0xFF, 0x00, 0x75, 0x08, 0x95, 0x01, 0x81,
+        0x02, //     Input (Contact ID)
+        0x05, 0x01, 0x09, 0x30, 0x09, 0x31, 0x26, 0xFF, 0x0F, 0x75, 0x10, 0x95, 0x02, 0x81,
+        0x02, //     Input (X, Y)
+        0xC0, //   End Collection
+        0x05, 0x0D, 0x09, 0x22, 0xA1, 0x02, // Second finger, same layout
+        0x09, 0x42, 0x09, 0x47, 0x15, 0x00, 0x25, 0x01, 0x75, 0x01, 0x95, 0x02, 0x81, 0x02, 0x95,
+        0x06, 0x81, 0x03, 0x09, 0x51, 0x26, 0xFF, 0x00, 0x75, 0x08, 0x95, 0x01, 0x81, 0x02, 0x05,
+        0x01, 0x09, 0x30, 0x09, 0x31, 0x26, 0xFF, 0x0F, 0x75, 0x10, 0x95, 0x02, 0x81, 0x02, 0xC0,
+        0x05, 0x0D, 0x09, 0x56, 0x27, 0xFF, 0xFF, 0x00, 0x00, 0x75, 0x10, 0x95, 0x01, 0x81,
+        0x02, //   Input (Scan Time)
+        0x09, 0x54, 0x25, 0x05, 0x75, 0x08, 0x81, 0x02, //   Input (Contact Count)
+        0x05, 0x09, 0x09, 0x01, 0x25, 0x01, 0x75, 0x01, 0x81, 0x02, //   Input (Button 1)
+        0x95, 0x07, 0x81, 0x03, //   Input (padding)
+        0xC0, // End Collection
+    ];
+
+    fn report(contacts: &[(u8, u8, u16, u16)], scan: u16, count: u8, left: bool) -> Vec<u8> {
+        let mut r = vec![0x01];
+        for i in 0..2 {
+            let (tip, id, x, y) = contacts.get(i).copied().unwrap_or_default();
+            r.push(tip | 0x02);
+            r.push(id);
+            r.extend_from_slice(&x.to_le_bytes());
+            r.extend_from_slice(&y.to_le_bytes());
+        }
+        r.extend_from_slice(&scan.to_le_bytes());
+        r.push(count);
+        r.push(left as u8);
+        r
+    }
+
+    #[test]
+    fn parses_ptp_reports_into_slots() {
+        let layout = parse_layout(DESCRIPTOR).unwrap();
+        assert_eq!(layout.report_id, 1);
+        assert_eq!(layout.contacts.len(), 2);
+        assert_eq!(
+            layout.contacts[1].x,
+            Some(Field {
+                bit_offset: 64,
+                bit_size: 16,
+                signed: false
+            })
+        );
+
+        let mut tracker = Tracker::default();
+        let first = parse_report(&layout, &report(&[(1, 7, 100, 200)], 10, 1, false)).unwrap();
+        assert_eq!(first.contacts.len(), 1);
+        assert!(tracker.apply(first));
+        assert!(tracker.touches[0].used);
+        assert_eq!(tracker.touches[0].position_x, 100);
+        assert_eq!(tracker.touches[0].tracking_id, 0);
+
+        // Contact 9 arrives; 7 keeps its slot although it's listed second
+        let both = report(&[(1, 9, 300, 400), (1, 7, 110, 210)], 20, 2, true);
+        assert!(tracker.apply(parse_report(&layout, &both).unwrap()));
+        assert_eq!(tracker.touches[0].position_x, 110);
+        assert_eq!(tracker.touches[1].position_x, 300);
+        assert_eq!(tracker.touches[1].tracking_id, 1);
+        assert_eq!(tracker.firmware_us, 1000);
+
+        // Hybrid mode: three contacts over two reports, the count only in
+        // the first. 7 lifts and 11 takes its slot.
+        let first = report(&[(0, 7, 110, 210), (1, 9, 305, 405)], 30, 3, false);
+        assert!(!tracker.apply(parse_report(&layout, &first).unwrap()));
+        let rest = report(&[(1, 11, 500, 600)], 30, 0, false);
+        assert!(tracker.apply(parse_report(&layout, &rest).unwrap()));
+        assert_eq!(tracker.touches[0].position_x, 500);
+        assert_eq!(tracker.touches[0].tracking_id, 2);
+        assert_eq!(tracker.touches[1].position_x, 305);
+
+        // Other report IDs aren't ours
+        assert!(parse_report(&layout, &[0x02, 0, 0]).is_none());
+    }
+}
@@ -0,0 +1,157 @@
+//! Apple's Magic Trackpad 2 and its USB-C revision, the usual external
+//! reference touchpad. It doesn't describe its contacts as a HID digitizer:
+//! hid-magicmouse parses a vendor report instead, and `--backend hidraw`
+//! does the same with [`parse_report`].
+//!
+//! Through hid-magicmouse its evdev axes are centered on 0 and it has 16
+//! slots numbered by each finger's hardware ID, so the evdev backend shifts
+//! positions to start at 0 and maps slots onto ours as contacts come and go.
+//! Pressure is the force sensor's reading, 0 to [`PRESSURE_MAX`].
+
+/// Apple's USB vendor ID and the Bluetooth one it uses over Bluetooth
+pub const APPLE_USB: u16 = 0x05ac;
+pub const APPLE_BLUETOOTH: u16 = 0x004c;
+pub const MAGIC_TRACKPAD_2: u16 = 0x0265;
+pub const MAGIC_TRACKPAD_USB_C: u16 = 0x0324;
+
+/// Surface size in mm
+pub const SIZE_MM: (f32, f32) = (160.0, 114.9);
+/// Position ranges as hid-magicmouse gives them to evdev, Y pointing down
+pub const X_RANGE: (i32, i32) = (-3678, 3934);
+pub const Y_RANGE: (i32, i32) = (-2479, 2586);
+pub const PRESSURE_MAX: i32 = 253;
+
+const USB_REPORT_ID: u8 = 0x02;
+const BLUETOOTH_REPORT_ID: u8 = 0x31;
+/// Bytes before the first touch, report ID included
+const HEADER_LEN: usize = 12;
+const TOUCH_LEN: usize = 9;
+/// Touches a report can hold
+const TOUCHES_MAX: usize = 15;
+
+pub fn is_magic_trackpad(vendor: u16, product: u16) -> bool {
+    matches!(vendor, APPLE_USB | APPLE_BLUETOOTH)
+        && matches!(product, MAGIC_TRACKPAD_2 | MAGIC_TRACKPAD_USB_C)
+}
+
+/// Units per mm, for when the driver doesn't say.
+pub fn resolution() -> (f32, f32) {
+    (
+        (X_RANGE.1 - X_RANGE.0) as f32 / SIZE_MM.0,
+        (Y_RANGE.1 - Y_RANGE.0) as f32 / SIZE_MM.1,
+    )
+}
+
+/// One touch, scaled the way hid-magicmouse reports it to evdev, with
+/// positions counted from the axis minimums.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Touch {
+    /// Hardware finger ID, 0-15
+    pub id: i32,
+    pub down: bool,
+    pub x: i32,
+    pub y: i32,
+    pub pressure: i32,
+    pub touch_major: i32,
+    pub touch_minor: i32,
+    pub orientation: i32,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Frame {
+    pub button: bool,
+    pub touches: Vec<Touch>,
+}
+
+/// Sign-extend the low 13 bits.
+fn sign13(value: u32) -> i32 {
+    ((value << 19) as i32) >> 19
+}
+
+/// Parse a multitouch report (USB or Bluetooth), which starts with the
+/// report ID. Other reports give None.
+pub fn parse_report(report: &[u8]) -> Option<Frame> {
+    if !matches!(report.first(), Some(&(USB_REPORT_ID | BLUETOOTH_REPORT_ID)))
+        || report.len() < HEADER_LEN
+        || !(report.len() - HEADER_LEN).is_multiple_of(TOUCH_LEN)
+        || (report.len() - HEADER_LEN) / TOUCH_LEN > TOUCHES_MAX
+    {
+        return None;
+    }
+    let touches = report[HEADER_LEN..]
+        .chunks_exact(TOUCH_LEN)
+        .map(|t| {
+            let x = sign13((t[1] as u32) << 8 | t[0] as u32);
+            let y = -sign13((t[3] as u32) << 11 | (t[2] as u32) << 3 | (t[1] as u32) >> 5);
+            Touch {
+                id: (t[8] & 0x0f) as i32,
+                down: t[3] & 0xc0 == 0x80,
+                x: x - X_RANGE.0,
+                y: y - Y_RANGE.0,
+                pressure: t[7] as i32,
+                touch_major: (t[4] as i32) << 2,
+                touch_minor: (t[5] as i32) << 2,
+                orientation: 4 - (t[8] >> 5) as i32,
+            }
+        })
+        .collect();
+    Some(Frame {
+        button: report[1] & 1 != 0,
+        touches,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A touch as the trackpad sends it: 13-bit X, then 13-bit Y (up
+    /// positive), major, minor, size, pressure and ID with orientation.
+    fn touch(x: i32, y: i32, pressure: u8, id: u8, down: bool) -> [u8; TOUCH_LEN] {
+        let (x, y) = (x as u32 & 0x1fff, y as u32 & 0x1fff);
+        let state = if down { 0x80 } else { 0x40 };
+        [
+            x as u8,
+            (x >> 8) as u8 | (y << 5) as u8,
+            (y >> 3) as u8,
+            (y >> 11) as u8 | state,
+            20,
+            15,
+            0,
+            pressure,
+            id | 4 << 5,
+        ]
+    }
+
+    #[test]
+    fn parses_touches_and_button() {
+        let mut report = vec![BLUETOOTH_REPORT_ID, 1];
+        report.resize(HEADER_LEN, 0);
+        report.extend_from_slice(&touch(X_RANGE.0, -Y_RANGE.0, 90, 14, true));
+        report.extend_from_slice(&touch(100, -200, 10, 3, false));
+        let frame = parse_report(&report).unwrap();
+        assert!(frame.button);
+        assert_eq!(
+            frame.touches[0],
+            Touch {
+                id: 14,
+                down: true,
+                x: 0,
+                y: 0,
+                pressure: 90,
+                touch_major: 80,
+                touch_minor: 60,
+                orientation: 0,
+            }
+        );
+        assert_eq!(frame.touches[1].x, 100 - X_RANGE.0);
+        assert_eq!(frame.touches[1].y, 200 - Y_RANGE.0);
+        assert!(!frame.touches[1].down);
+
+        // A touch cut short, and another report
+        assert!(parse_report(&report[..report.len() - 1]).is_none());
+        assert!(parse_report(&[0x01; HEADER_LEN]).is_none());
+        assert!(is_magic_trackpad(APPLE_BLUETOOTH, MAGIC_TRACKPAD_2));
+        assert!(!is_magic_trackpad(APPLE_USB, 0x030e));
+    }
+}
@@ -0,0 +1,101 @@
+pub mod demo_backend;
+pub mod descriptor_tree;
+#[cfg(target_os = "linux")]
+pub mod evdev_backend;
+#[cfg(target_os = "linux")]
+pub mod evdev_device;
+#[cfg(target_os = "linux")]
+pub mod forward;
+#[cfg(target_os = "linux")]
+pub mod hidraw_backend;
+pub mod magic_trackpad;
+#[cfg(target_os = "linux")]
+pub mod replay;
+pub mod waker;
+#[cfg(target_os = "windows")]
+pub mod windows_backend;
+#[cfg(target_os = "windows")]
+pub mod windows_descriptor;
+
+pub use waker::{command_channel, CommandReceiver, CommandSender, Waker};
+
+use crate::multitouch::{ButtonState, TouchData, MAX_TOUCH_POINTS};
+use std::path::Path;
+use std::time::{Instant, SystemTime};
+
+#[derive(Debug, Clone)]
+pub struct TouchState {
+    pub touches: [TouchData; MAX_TOUCH_POINTS],
+    pub buttons: ButtonState,
+    /// Device reports (evdev SYN_REPORTs) folded into this state.
+    pub reports: u32,
+    /// When the backend read this state.
+    pub time: Instant,
+    /// Timing of each report folded into this state, oldest first.
+    pub report_times: Vec<ReportTime>,
+}
+
+/// When one device report happened, for the rate and timing analyzers.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportTime {
+    /// Kernel receive time (evdev event timestamp), or when the backend read
+    /// the report if there's no kernel timestamp.
+    pub time: SystemTime,
+    /// Firmware timestamp in microseconds (MSC_TIMESTAMP), wrapping at u32.
+    pub firmware_us: Option<u32>,
+    /// Contacts down after this report
+    pub contacts: u8,
+}
+
+impl Default for TouchState {
+    fn default() -> Self {
+        Self {
+            touches: [TouchData::default(); MAX_TOUCH_POINTS],
+            buttons: ButtonState::default(),
+            reports: 0,
+            time: Instant::now(),
+            report_times: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum InputError {
+    OpenFailed(String),
+    GrabFailed(String),
+    ReadError(String),
+    PulseFailed(String),
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::OpenFailed(msg) => write!(f, "open failed: {}", msg),
+            InputError::GrabFailed(msg) => write!(f, "grab failed: {}", msg),
+            InputError::ReadError(msg) => write!(f, "read error: {}", msg),
+            InputError::PulseFailed(msg) => write!(f, "haptic pulse failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+#[allow(dead_code)]
+pub trait InputBackend: Send + 'static {
+    fn open(device_path: &Path) -> Result<Self, InputError>
+    where
+        Self: Sized;
+    fn grab(&mut self) -> Result<(), InputError>;
+    fn ungrab(&mut self) -> Result<(), InputError>;
+    /// Play one click on a haptic touchpad.
+    fn pulse(&mut self) -> Result<(), InputError> {
+        Err(InputError::PulseFailed(
+            "not supported by this backend".into(),
+        ))
+    }
+    fn poll_events(&mut self) -> Result<Option<TouchState>, InputError>;
+    /// Block until `poll_events` has something to return or `waker` fires.
+    /// May return spuriously.
+    fn wait(&mut self, waker: &Waker) -> Result<(), InputError>;
+}
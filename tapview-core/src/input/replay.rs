@@ -0,0 +1,620 @@
+//! `--play <file> --replay-uinput`: feed a recording into a new uinput
+//! touchpad while it plays, so libinput and the desktop see the recorded
+//! session as if it happened again.
+//!
+//! The virtual touchpad copies the recorded device's name, id, properties,
+//! keys and axes. Version 1 recordings don't keep those, so for them it's
+//! made up from the recording: its extents, and pressure, touch size and tool
+//! type axes only if any frame uses them. It's a clickpad unless the
+//! recording presses the right or middle button. Without a resolution,
+//! libinput falls back to its defaults for anything in mm.
+//!
+//! [`ReplayPlayer`] plays every recorded frame from its own thread at the
+//! frame's offset into the recording, with that offset as MSC_TIMESTAMP.
+
+use crate::multitouch::{ButtonState, TouchData, MAX_TOUCH_POINTS};
+use crate::recording::{RecordedFrame, Recording};
+use evdev::uinput::{VirtualDevice, VirtualDeviceBuilder};
+use evdev::{
+    AbsInfo, AbsoluteAxisType, AttributeSet, BusType, EventType, InputEvent, InputId, Key,
+    MiscType, PropType, UinputAbsSetup,
+};
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const TOOL_KEYS: [Key; 5] = [
+    Key::BTN_TOOL_FINGER,
+    Key::BTN_TOOL_DOUBLETAP,
+    Key::BTN_TOOL_TRIPLETAP,
+    Key::BTN_TOOL_QUADTAP,
+    Key::BTN_TOOL_QUINTTAP,
+];
+
+/// MT axes replayed from [`TouchData`] besides the slot, tracking id and
+/// position.
+const MT_AXES: [AbsoluteAxisType; 10] = [
+    AbsoluteAxisType::ABS_MT_PRESSURE,
+    AbsoluteAxisType::ABS_MT_DISTANCE,
+    AbsoluteAxisType::ABS_MT_TOUCH_MAJOR,
+    AbsoluteAxisType::ABS_MT_TOUCH_MINOR,
+    AbsoluteAxisType::ABS_MT_WIDTH_MAJOR,
+    AbsoluteAxisType::ABS_MT_WIDTH_MINOR,
+    AbsoluteAxisType::ABS_MT_ORIENTATION,
+    AbsoluteAxisType::ABS_MT_TOOL_X,
+    AbsoluteAxisType::ABS_MT_TOOL_Y,
+    AbsoluteAxisType::ABS_MT_TOOL_TYPE,
+];
+
+/// What the virtual device reports beyond slots, tracking ids and positions.
+#[derive(Clone, Debug, Default)]
+struct Axes {
+    /// MT axes out of [`MT_AXES`] the device has
+    mt: Vec<AbsoluteAxisType>,
+    /// ABS_PRESSURE along with ABS_X/Y
+    pressure: bool,
+    /// X and Y minimums below 0, which recorded positions are counted from
+    origin: (i32, i32),
+}
+
+/// The value of an MT axis for one contact, in device coordinates.
+fn touch_value(t: &TouchData, axis: AbsoluteAxisType, origin: (i32, i32)) -> i32 {
+    match axis {
+        AbsoluteAxisType::ABS_MT_POSITION_X => t.position_x + origin.0,
+        AbsoluteAxisType::ABS_MT_POSITION_Y => t.position_y + origin.1,
+        AbsoluteAxisType::ABS_MT_PRESSURE => t.pressure,
+        AbsoluteAxisType::ABS_MT_DISTANCE => t.distance,
+        AbsoluteAxisType::ABS_MT_TOUCH_MAJOR => t.touch_major,
+        AbsoluteAxisType::ABS_MT_TOUCH_MINOR => t.touch_minor,
+        AbsoluteAxisType::ABS_MT_WIDTH_MAJOR => t.width_major,
+        AbsoluteAxisType::ABS_MT_WIDTH_MINOR => t.width_minor,
+        AbsoluteAxisType::ABS_MT_ORIENTATION => t.orientation,
+        AbsoluteAxisType::ABS_MT_TOOL_X => t.tool_x + origin.0,
+        AbsoluteAxisType::ABS_MT_TOOL_Y => t.tool_y + origin.1,
+        AbsoluteAxisType::ABS_MT_TOOL_TYPE => t.tool_type,
+        _ => 0,
+    }
+}
+
+fn abs(axis: AbsoluteAxisType, value: i32) -> InputEvent {
+    InputEvent::new(EventType::ABSOLUTE, axis.0, value)
+}
+
+fn key(key: Key, down: bool) -> InputEvent {
+    InputEvent::new(EventType::KEY, key.code(), down as i32)
+}
+
+/// Events taking the device from `prev` to `next`, without the SYN_REPORT.
+/// Empty if nothing changed.
+fn report_events(
+    axes: &Axes,
+    prev: &[TouchData; MAX_TOUCH_POINTS],
+    prev_buttons: &ButtonState,
+    next: &[TouchData; MAX_TOUCH_POINTS],
+    buttons: &ButtonState,
+) -> Vec<InputEvent> {
+    let positions = [
+        AbsoluteAxisType::ABS_MT_POSITION_X,
+        AbsoluteAxisType::ABS_MT_POSITION_Y,
+    ];
+    let mut events = Vec::new();
+    for (slot, (old, new)) in prev.iter().zip(next).enumerate() {
+        let mut changes = Vec::new();
+        let fresh = new.used && (!old.used || old.tracking_id != new.tracking_id);
+        if fresh {
+            changes.push(abs(
+                AbsoluteAxisType::ABS_MT_TRACKING_ID,
+                new.tracking_id.max(0),
+            ));
+        } else if old.used && !new.used {
+            changes.push(abs(AbsoluteAxisType::ABS_MT_TRACKING_ID, -1));
+        }
+        if new.used {
+            for &axis in positions.iter().chain(&axes.mt) {
+                let value = touch_value(new, axis, axes.origin);
+                if fresh || touch_value(old, axis, axes.origin) != value {
+                    changes.push(abs(axis, value));
+                }
+            }
+        }
+        if !changes.is_empty() {
+            events.push(abs(AbsoluteAxisType::ABS_MT_SLOT, slot as i32));
+            events.append(&mut changes);
+        }
+    }
+    let pressed = |b: &ButtonState| (b.left, b.right, b.middle);
+    if events.is_empty() && pressed(prev_buttons) == pressed(buttons) {
+        return events;
+    }
+
+    // Single-touch emulation, as the kernel does for MT devices
+    let count = next.iter().filter(|t| t.used).count();
+    events.push(key(Key::BTN_TOUCH, count > 0));
+    for (fingers, tool) in TOOL_KEYS.iter().enumerate() {
+        events.push(key(
+            *tool,
+            count == fingers + 1 || (fingers == 4 && count > 5),
+        ));
+    }
+    if let Some(first) = next.iter().find(|t| t.used) {
+        events.push(abs(
+            AbsoluteAxisType::ABS_X,
+            first.position_x + axes.origin.0,
+        ));
+        events.push(abs(
+            AbsoluteAxisType::ABS_Y,
+            first.position_y + axes.origin.1,
+        ));
+        if axes.pressure {
+            events.push(abs(AbsoluteAxisType::ABS_PRESSURE, first.pressure));
+        }
+    }
+    events.push(key(Key::BTN_LEFT, buttons.left));
+    events.push(key(Key::BTN_RIGHT, buttons.right));
+    events.push(key(Key::BTN_MIDDLE, buttons.middle));
+    events
+}
+
+/// How to build the virtual device.
+struct Setup {
+    name: String,
+    id: InputId,
+    keys: AttributeSet<Key>,
+    properties: AttributeSet<PropType>,
+    ranges: Vec<(AbsoluteAxisType, AbsInfo)>,
+    axes: Axes,
+}
+
+impl Setup {
+    /// A copy of the recorded device.
+    fn recorded(recording: &Recording) -> Self {
+        let caps = &recording.capabilities;
+        let [bus, vendor, product, version] = caps.id;
+        let ranges: Vec<_> = caps
+            .axes
+            .iter()
+            .map(|a| {
+                // No fuzz: the recorded values were already filtered once
+                let info = AbsInfo::new(0, a.minimum, a.maximum, 0, a.flat, a.resolution);
+                (AbsoluteAxisType(a.code), info)
+            })
+            .collect();
+        let has = |axis: AbsoluteAxisType| caps.axis(axis.0).is_some();
+        let min = |axis: AbsoluteAxisType| caps.axis(axis.0).map_or(0, |a| a.minimum.min(0));
+        let axes = Axes {
+            mt: MT_AXES.into_iter().filter(|&axis| has(axis)).collect(),
+            pressure: has(AbsoluteAxisType::ABS_PRESSURE),
+            origin: (
+                min(AbsoluteAxisType::ABS_MT_POSITION_X),
+                min(AbsoluteAxisType::ABS_MT_POSITION_Y),
+            ),
+        };
+        Self {
+            name: caps.name.clone(),
+            id: InputId::new(BusType(bus), vendor, product, version),
+            keys: caps.keys.iter().map(|&code| Key::new(code)).collect(),
+            properties: caps.properties.iter().map(|&p| PropType(p)).collect(),
+            ranges,
+            axes,
+        }
+    }
+
+    /// A touchpad made up from what the recording uses.
+    fn made_up(recording: &Recording) -> Self {
+        let mut pressure = None;
+        let mut touch = None;
+        let mut tool_type = false;
+        for t in recording
+            .frames
+            .iter()
+            .flat_map(|f| f.state.touches.iter())
+            .filter(|t| t.used)
+        {
+            if t.pressure > 0 {
+                pressure = pressure.max(Some(t.pressure));
+            }
+            if t.touch_major > 0 {
+                touch = touch.max(Some(t.touch_major.max(t.touch_minor)));
+            }
+            tool_type |= t.tool_type != 0;
+        }
+        let clickpad = !recording
+            .frames
+            .iter()
+            .any(|f| f.state.buttons.right || f.state.buttons.middle);
+
+        let mut keys: AttributeSet<Key> = [Key::BTN_TOUCH, Key::BTN_LEFT].into_iter().collect();
+        for tool in TOOL_KEYS {
+            keys.insert(tool);
+        }
+        if !clickpad {
+            keys.insert(Key::BTN_RIGHT);
+            keys.insert(Key::BTN_MIDDLE);
+        }
+        let mut properties: AttributeSet<PropType> = [PropType::POINTER].into_iter().collect();
+        if clickpad {
+            properties.insert(PropType::BUTTONPAD);
+        }
+
+        let (x_max, y_max) = (recording.extent_x.max(1), recording.extent_y.max(1));
+        let mut ranges = vec![
+            (AbsoluteAxisType::ABS_X, x_max),
+            (AbsoluteAxisType::ABS_Y, y_max),
+            (AbsoluteAxisType::ABS_MT_SLOT, MAX_TOUCH_POINTS as i32 - 1),
+            (AbsoluteAxisType::ABS_MT_TRACKING_ID, 0xFFFF),
+            (AbsoluteAxisType::ABS_MT_POSITION_X, x_max),
+            (AbsoluteAxisType::ABS_MT_POSITION_Y, y_max),
+        ];
+        let mut mt = Vec::new();
+        if let Some(max) = pressure {
+            ranges.push((AbsoluteAxisType::ABS_PRESSURE, max));
+            ranges.push((AbsoluteAxisType::ABS_MT_PRESSURE, max));
+            mt.push(AbsoluteAxisType::ABS_MT_PRESSURE);
+        }
+        if let Some(max) = touch {
+            ranges.push((AbsoluteAxisType::ABS_MT_TOUCH_MAJOR, max));
+            ranges.push((AbsoluteAxisType::ABS_MT_TOUCH_MINOR, max));
+            mt.push(AbsoluteAxisType::ABS_MT_TOUCH_MAJOR);
+            mt.push(AbsoluteAxisType::ABS_MT_TOUCH_MINOR);
+        }
+        if tool_type {
+            ranges.push((AbsoluteAxisType::ABS_MT_TOOL_TYPE, 0x0F));
+            mt.push(AbsoluteAxisType::ABS_MT_TOOL_TYPE);
+        }
+
+        Self {
+            name: "tapview replay".to_string(),
+            id: InputId::new(BusType::BUS_VIRTUAL, 0, 0, 0),
+            keys,
+            properties,
+            ranges: ranges
+                .into_iter()
+                .map(|(axis, max)| (axis, AbsInfo::new(0, 0, max, 0, 0, 0)))
+                .collect(),
+            axes: Axes {
+                mt,
+                pressure: pressure.is_some(),
+                origin: (0, 0),
+            },
+        }
+    }
+}
+
+pub struct ReplayDevice {
+    device: VirtualDevice,
+    axes: Axes,
+    /// State the device was last put in
+    touches: [TouchData; MAX_TOUCH_POINTS],
+    buttons: ButtonState,
+}
+
+impl ReplayDevice {
+    /// Create the virtual touchpad. Needs write access to /dev/uinput.
+    pub fn new(recording: &Recording) -> io::Result<Self> {
+        let setup = if recording.capabilities.axes.is_empty() {
+            Setup::made_up(recording)
+        } else {
+            Setup::recorded(recording)
+        };
+        let misc: AttributeSet<MiscType> = [MiscType::MSC_TIMESTAMP].into_iter().collect();
+        let mut builder = VirtualDeviceBuilder::new()?
+            .name(&setup.name)
+            .input_id(setup.id)
+            .with_keys(&setup.keys)?
+            .with_properties(&setup.properties)?
+            .with_msc(&misc)?;
+        for (axis, info) in setup.ranges {
+            builder = builder.with_absolute_axis(&UinputAbsSetup::new(axis, info))?;
+        }
+        Ok(Self {
+            device: builder.build()?,
+            axes: setup.axes,
+            touches: [TouchData::default(); MAX_TOUCH_POINTS],
+            buttons: ButtonState::default(),
+        })
+    }
+
+    /// The device's /dev/input/event* node, once udev has created it.
+    pub fn devnode(&mut self) -> io::Result<PathBuf> {
+        self.device
+            .enumerate_dev_nodes_blocking()?
+            .next()
+            .unwrap_or_else(|| Err(io::Error::new(io::ErrorKind::NotFound, "no device node")))
+    }
+
+    /// Put the device in the state of a recorded frame as one report,
+    /// stamped with the frame's offset into the recording.
+    fn play(&mut self, frame: &RecordedFrame) -> io::Result<()> {
+        let mut events = report_events(
+            &self.axes,
+            &self.touches,
+            &self.buttons,
+            &frame.state.touches,
+            &frame.state.buttons,
+        );
+        // Microseconds, wrapping like the kernel's
+        events.push(InputEvent::new(
+            EventType::MISC,
+            MiscType::MSC_TIMESTAMP.0,
+            frame.timestamp_us as u32 as i32,
+        ));
+        self.set(events, &frame.state.touches, &frame.state.buttons)
+    }
+
+    /// Put the device in the given state. Does nothing if it already is.
+    fn jump(
+        &mut self,
+        touches: &[TouchData; MAX_TOUCH_POINTS],
+        buttons: &ButtonState,
+    ) -> io::Result<()> {
+        let events = report_events(&self.axes, &self.touches, &self.buttons, touches, buttons);
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.set(events, touches, buttons)
+    }
+
+    fn set(
+        &mut self,
+        events: Vec<InputEvent>,
+        touches: &[TouchData; MAX_TOUCH_POINTS],
+        buttons: &ButtonState,
+    ) -> io::Result<()> {
+        self.device.emit(&events)?;
+        self.touches = *touches;
+        self.buttons = *buttons;
+        Ok(())
+    }
+}
+
+impl Drop for ReplayDevice {
+    fn drop(&mut self) {
+        // Lift everything so nothing stays pressed until the device is gone
+        let _ = self.jump(
+            &[TouchData::default(); MAX_TOUCH_POINTS],
+            &ButtonState::default(),
+        );
+    }
+}
+
+enum PlayerCommand {
+    /// Continue from this many seconds into the recording
+    Seek(f64),
+    Run {
+        playing: bool,
+        speed: f32,
+    },
+}
+
+/// Plays a recording into a [`ReplayDevice`] from its own thread, so every
+/// frame goes out at its own time rather than whenever the UI repaints.
+/// Starts paused at the beginning; the UI drives it with [`Self::run`] and
+/// [`Self::seek`].
+pub struct ReplayPlayer {
+    tx: Option<mpsc::Sender<PlayerCommand>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ReplayPlayer {
+    pub fn spawn(device: ReplayDevice, recording: Arc<Recording>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            if let Err(e) = play_loop(device, &recording, rx) {
+                log_warn!(target: "input", "stopped replaying into uinput: {}", e);
+            }
+        });
+        Self {
+            tx: Some(tx),
+            thread: Some(thread),
+        }
+    }
+
+    /// Start or pause playback, at `speed` times real time.
+    pub fn run(&self, playing: bool, speed: f32) {
+        self.send(PlayerCommand::Run { playing, speed });
+    }
+
+    /// Jump to `time_secs` into the recording, putting the device in the
+    /// state of the frame there right away.
+    pub fn seek(&self, time_secs: f64) {
+        self.send(PlayerCommand::Seek(time_secs));
+    }
+
+    fn send(&self, command: PlayerCommand) {
+        if let Some(tx) = &self.tx {
+            // The thread is gone if the device failed; that's been logged
+            let _ = tx.send(command);
+        }
+    }
+}
+
+impl Drop for ReplayPlayer {
+    fn drop(&mut self) {
+        // Closing the channel ends the thread, which lifts every contact
+        self.tx = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Where the player is in the recording: at `offset_us` as of `since`,
+/// moving at `speed` while playing.
+struct Clock {
+    offset_us: f64,
+    since: Instant,
+    playing: bool,
+    speed: f64,
+}
+
+impl Clock {
+    fn now_us(&self) -> f64 {
+        if self.playing {
+            self.offset_us + self.since.elapsed().as_secs_f64() * 1e6 * self.speed
+        } else {
+            self.offset_us
+        }
+    }
+
+    fn rebase(&mut self, offset_us: f64) {
+        self.offset_us = offset_us;
+        self.since = Instant::now();
+    }
+}
+
+fn play_loop(
+    mut device: ReplayDevice,
+    recording: &Recording,
+    rx: mpsc::Receiver<PlayerCommand>,
+) -> io::Result<()> {
+    let frames = &recording.frames;
+    let mut clock = Clock {
+        offset_us: 0.0,
+        since: Instant::now(),
+        playing: false,
+        speed: 1.0,
+    };
+    // Index of the next frame to play
+    let mut next = 0;
+    loop {
+        let command = match frames.get(next) {
+            Some(frame) if clock.playing => {
+                let ahead_us = frame.timestamp_us as f64 - clock.now_us();
+                if ahead_us <= 0.0 {
+                    device.play(frame)?;
+                    next += 1;
+                    continue;
+                }
+                let wait = Duration::from_secs_f64(ahead_us / 1e6 / clock.speed);
+                match rx.recv_timeout(wait) {
+                    Ok(command) => command,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                }
+            }
+            _ => match rx.recv() {
+                Ok(command) => command,
+                Err(_) => return Ok(()),
+            },
+        };
+        match command {
+            PlayerCommand::Run { playing, speed } => {
+                clock.rebase(clock.now_us());
+                clock.playing = playing;
+                clock.speed = f64::from(speed).max(0.01);
+            }
+            PlayerCommand::Seek(time_secs) => {
+                let offset_us = (time_secs * 1e6).max(0.0);
+                clock.rebase(offset_us);
+                next = frames.partition_point(|f| (f.timestamp_us as f64) <= offset_us);
+                if let Some(frame) = recording.frame_at(time_secs) {
+                    device.jump(&frame.state.touches, &frame.state.buttons)?;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(events: &[InputEvent]) -> Vec<(u16, u16, i32)> {
+        events
+            .iter()
+            .map(|e| (e.event_type().0, e.code(), e.value()))
+            .collect()
+    }
+
+    #[test]
+    fn emits_only_what_changed() {
+        let axes = Axes::default();
+        let none = [TouchData::default(); MAX_TOUCH_POINTS];
+        let mut one = none;
+        one[1] = TouchData {
+            used: true,
+            tracking_id: 4,
+            position_x: 10,
+            position_y: 20,
+            ..Default::default()
+        };
+        let buttons = ButtonState::default();
+        let abs_event = |axis: AbsoluteAxisType, value| (EventType::ABSOLUTE.0, axis.0, value);
+        let key_event = |key: Key, value| (EventType::KEY.0, key.code(), value);
+
+        let down = fields(&report_events(&axes, &none, &buttons, &one, &buttons));
+        assert_eq!(down[0], abs_event(AbsoluteAxisType::ABS_MT_SLOT, 1));
+        assert_eq!(down[1], abs_event(AbsoluteAxisType::ABS_MT_TRACKING_ID, 4));
+        assert!(down.contains(&key_event(Key::BTN_TOOL_FINGER, 1)));
+
+        let mut moved = one;
+        moved[1].position_y = 25;
+        let motion = fields(&report_events(&axes, &one, &buttons, &moved, &buttons));
+        assert_eq!(
+            motion[..2],
+            [
+                abs_event(AbsoluteAxisType::ABS_MT_SLOT, 1),
+                abs_event(AbsoluteAxisType::ABS_MT_POSITION_Y, 25)
+            ]
+        );
+
+        assert!(report_events(&axes, &moved, &buttons, &moved, &buttons).is_empty());
+
+        let up = fields(&report_events(&axes, &moved, &buttons, &none, &buttons));
+        assert_eq!(up[1], abs_event(AbsoluteAxisType::ABS_MT_TRACKING_ID, -1));
+        assert!(up.contains(&key_event(Key::BTN_TOUCH, 0)));
+    }
+
+    #[test]
+    fn recorded_device_puts_positions_back_on_its_axes() {
+        use crate::recording::{AxisInfo, DeviceCapabilities};
+        let axis = |axis: AbsoluteAxisType, minimum, maximum| AxisInfo {
+            code: axis.0,
+            minimum,
+            maximum,
+            ..Default::default()
+        };
+        let recording = Recording {
+            frames: Vec::new(),
+            extent_x: 7356,
+            extent_y: 5666,
+            capabilities: DeviceCapabilities {
+                name: "Apple Inc. Magic Trackpad".to_string(),
+                id: [0x05, 0x05ac, 0x0324, 0x0001],
+                axes: vec![
+                    axis(AbsoluteAxisType::ABS_MT_SLOT, 0, 15),
+                    axis(AbsoluteAxisType::ABS_MT_POSITION_X, -3678, 3678),
+                    axis(AbsoluteAxisType::ABS_MT_POSITION_Y, -2833, 2833),
+                    axis(AbsoluteAxisType::ABS_MT_TOUCH_MAJOR, 0, 1020),
+                ],
+                ..Default::default()
+            },
+        };
+        let setup = Setup::recorded(&recording);
+        assert_eq!(setup.name, "Apple Inc. Magic Trackpad");
+        assert_eq!(setup.axes.mt, [AbsoluteAxisType::ABS_MT_TOUCH_MAJOR]);
+        assert!(!setup.axes.pressure);
+
+        let none = [TouchData::default(); MAX_TOUCH_POINTS];
+        let mut one = none;
+        one[0] = TouchData {
+            used: true,
+            position_x: 3678,
+            position_y: 100,
+            touch_major: 300,
+            ..Default::default()
+        };
+        let buttons = ButtonState::default();
+        let events = fields(&report_events(&setup.axes, &none, &buttons, &one, &buttons));
+        let abs_event = |axis: AbsoluteAxisType, value| (EventType::ABSOLUTE.0, axis.0, value);
+        assert!(events.contains(&abs_event(AbsoluteAxisType::ABS_MT_POSITION_X, 0)));
+        assert!(events.contains(&abs_event(AbsoluteAxisType::ABS_MT_POSITION_Y, -2733)));
+        assert!(events.contains(&abs_event(AbsoluteAxisType::ABS_MT_TOUCH_MAJOR, 300)));
+        assert!(!events
+            .iter()
+            .any(|&(_, code, _)| code == AbsoluteAxisType::ABS_MT_PRESSURE.0));
+    }
+}
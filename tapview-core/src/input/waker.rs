@@ -0,0 +1,208 @@
+//! Waking an input thread blocked in [`InputBackend::wait`](super::InputBackend::wait),
+//! so commands such as grab/ungrab are picked up immediately instead of on
+//! the next device event.
+//!
+//! On Linux this is an eventfd polled alongside the device; on Windows an
+//! auto-reset event waited on alongside the backend's data event.
+
+use std::io;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+struct Handle(std::os::fd::OwnedFd);
+
+#[cfg(target_os = "windows")]
+struct Handle(windows::Win32::Foundation::HANDLE);
+
+// Event handles can be signaled and waited on from any thread
+#[cfg(target_os = "windows")]
+unsafe impl Send for Handle {}
+#[cfg(target_os = "windows")]
+unsafe impl Sync for Handle {}
+
+#[cfg(target_os = "windows")]
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = windows::Win32::Foundation::CloseHandle(self.0);
+        }
+    }
+}
+
+/// A cloneable wake-up signal. Wakes coalesce: several `wake` calls before
+/// the waiter runs wake it once.
+#[derive(Clone)]
+pub struct Waker(Arc<Handle>);
+
+impl Waker {
+    #[cfg(target_os = "linux")]
+    pub fn new() -> io::Result<Self> {
+        use std::os::fd::FromRawFd;
+        let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self(Arc::new(Handle(unsafe {
+            std::os::fd::OwnedFd::from_raw_fd(fd)
+        }))))
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn new() -> io::Result<Self> {
+        use windows::core::PCWSTR;
+        use windows::Win32::System::Threading::CreateEventW;
+        let handle = unsafe { CreateEventW(None, false, false, PCWSTR::null()) }
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(Self(Arc::new(Handle(handle))))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn wake(&self) {
+        use std::os::fd::AsRawFd;
+        let one: u64 = 1;
+        unsafe {
+            libc::write(
+                self.0 .0.as_raw_fd(),
+                &one as *const u64 as *const libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn wake(&self) {
+        unsafe {
+            let _ = windows::Win32::System::Threading::SetEvent(self.0 .0);
+        }
+    }
+
+    /// Reset after a wake was seen. The Windows event resets itself.
+    #[cfg(target_os = "linux")]
+    pub fn drain(&self) {
+        use std::os::fd::AsRawFd;
+        let mut count: u64 = 0;
+        unsafe {
+            libc::read(
+                self.0 .0.as_raw_fd(),
+                &mut count as *mut u64 as *mut libc::c_void,
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+
+    /// Block until woken or `timeout` passes, for backends that have no
+    /// device to wait on.
+    #[cfg(target_os = "linux")]
+    pub fn wait_timeout(&self, timeout: Duration) -> io::Result<()> {
+        let mut fd = libc::pollfd {
+            fd: self.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ms = timeout.as_millis().min(i32::MAX as u128) as i32;
+        if unsafe { libc::poll(&mut fd, 1, ms) } < 0 {
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        }
+        if fd.revents & libc::POLLIN != 0 {
+            self.drain();
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn wait_timeout(&self, timeout: Duration) -> io::Result<()> {
+        use windows::Win32::Foundation::WAIT_FAILED;
+        use windows::Win32::System::Threading::WaitForSingleObject;
+        let ms = timeout.as_millis().min(u32::MAX as u128 - 1) as u32;
+        if unsafe { WaitForSingleObject(self.0 .0, ms) } == WAIT_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        use std::os::fd::AsRawFd;
+        self.0 .0.as_raw_fd()
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn handle(&self) -> windows::Win32::Foundation::HANDLE {
+        self.0 .0
+    }
+}
+
+/// Sending half of a command channel: every send also wakes the receiving
+/// input thread.
+pub struct CommandSender<T> {
+    tx: mpsc::Sender<T>,
+    waker: Waker,
+}
+
+impl<T> CommandSender<T> {
+    pub fn send(&self, cmd: T) -> Result<(), mpsc::SendError<T>> {
+        self.tx.send(cmd)?;
+        self.waker.wake();
+        Ok(())
+    }
+}
+
+pub struct CommandReceiver<T> {
+    rx: mpsc::Receiver<T>,
+    waker: Waker,
+}
+
+impl<T> CommandReceiver<T> {
+    pub fn try_recv(&self) -> Result<T, mpsc::TryRecvError> {
+        self.rx.try_recv()
+    }
+
+    /// Pass to [`InputBackend::wait`](super::InputBackend::wait).
+    pub fn waker(&self) -> &Waker {
+        &self.waker
+    }
+}
+
+/// An mpsc channel whose sends wake the receiver's input thread.
+pub fn command_channel<T>() -> io::Result<(CommandSender<T>, CommandReceiver<T>)> {
+    let (tx, rx) = mpsc::channel();
+    let waker = Waker::new()?;
+    Ok((
+        CommandSender {
+            tx,
+            waker: waker.clone(),
+        },
+        CommandReceiver { rx, waker },
+    ))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    fn readable(waker: &Waker) -> bool {
+        let mut fd = libc::pollfd {
+            fd: waker.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut fd, 1, 0) == 1 }
+    }
+
+    #[test]
+    fn send_wakes_until_drained() {
+        let (tx, rx) = command_channel().unwrap();
+        assert!(!readable(rx.waker()));
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert!(readable(rx.waker()));
+        rx.waker().drain();
+        assert!(!readable(rx.waker()));
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Ok(2));
+    }
+}
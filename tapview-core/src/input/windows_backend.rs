@@ -1,51 +1,73 @@
-use super::{InputBackend, InputError, TouchState};
+use super::{InputBackend, InputError, ReportTime, TouchState, Waker};
 use crate::multitouch::{ButtonState, TouchData, MAX_TOUCH_POINTS};
 use std::path::Path;
-use std::sync::mpsc;
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use windows::core::PCWSTR;
 use windows::Win32::Devices::HumanInterfaceDevice::*;
 use windows::Win32::Foundation::*;
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Threading::{GetThreadId, WaitForMultipleObjects, INFINITE};
 use windows::Win32::UI::Input::*;
 use windows::Win32::UI::WindowsAndMessaging::*;
 
 const HID_USAGE_PAGE_DIGITIZER: u16 = 0x0D;
 const HID_USAGE_DIGITIZER_TOUCHPAD: u16 = 0x05;
 const MT_TOOL_PALM: i32 = 0x02;
+/// Posted to the RawInput thread by grab()/ungrab(), wParam 1 to grab
+const WM_TAPVIEW_GRAB: u32 = WM_APP + 1;
 
 /// Windows RawInput-based touch backend.
 ///
 /// Unlike the Linux evdev backend which processes events one at a time,
 /// Windows delivers complete HID reports via WM_INPUT messages. Each report
 /// contains all active contacts atomically.
+///
+/// Windows has no exclusive access to a touchpad, so grabbing installs a
+/// low-level mouse hook that swallows pointer input from physical devices.
+/// The hook can't tell which device moved the pointer, so a mouse is held
+/// still too; RawInput still gets the touchpad's reports and the keyboard
+/// still works to release the grab.
 pub struct WindowsBackend {
     touch_rx: mpsc::Receiver<TouchState>,
-    _thread: Option<std::thread::JoinHandle<()>>,
+    /// Signaled by the RawInput thread after each report it sends
+    data_ready: Waker,
+    thread: Option<std::thread::JoinHandle<()>>,
+    grabbed: bool,
 }
 
 impl InputBackend for WindowsBackend {
     fn open(device_path: &Path) -> Result<Self, InputError> {
         let _ = device_path; // device_path is used for discovery; RawInput receives from all touchpads
         let (tx, rx) = mpsc::channel();
+        let data_ready = Waker::new().map_err(|e| InputError::OpenFailed(e.to_string()))?;
+        let thread_ready = data_ready.clone();
 
         let thread = std::thread::spawn(move || {
-            if let Err(e) = run_rawinput_loop(tx) {
-                eprintln!("RawInput thread error: {}", e);
+            if let Err(e) = run_rawinput_loop(tx, thread_ready) {
+                log_error!(target: "input", "RawInput thread error: {}", e);
             }
         });
 
         Ok(Self {
             touch_rx: rx,
-            _thread: Some(thread),
+            data_ready,
+            thread: Some(thread),
+            grabbed: false,
         })
     }
 
     fn grab(&mut self) -> Result<(), InputError> {
-        // Not implemented on Windows - would need RIDEV_NOLEGACY or similar
+        self.post(WM_TAPVIEW_GRAB, 1)
+            .map_err(|e| InputError::GrabFailed(e.to_string()))?;
+        self.grabbed = true;
         Ok(())
     }
 
     fn ungrab(&mut self) -> Result<(), InputError> {
+        self.post(WM_TAPVIEW_GRAB, 0)
+            .map_err(|e| InputError::GrabFailed(e.to_string()))?;
+        self.grabbed = false;
         Ok(())
     }
 
@@ -58,9 +80,50 @@ impl InputBackend for WindowsBackend {
             }
         }
     }
+
+    fn wait(&mut self, waker: &Waker) -> Result<(), InputError> {
+        let handles = [self.data_ready.handle(), waker.handle()];
+        let result = unsafe { WaitForMultipleObjects(&handles, false, INFINITE) };
+        if result == WAIT_FAILED {
+            return Err(InputError::ReadError(
+                windows::core::Error::from_thread().to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl WindowsBackend {
+    /// Post a thread message to the RawInput thread's message loop.
+    fn post(&self, msg: u32, wparam: usize) -> windows::core::Result<()> {
+        use std::os::windows::io::AsRawHandle;
+        let Some(thread) = &self.thread else {
+            return Err(windows::core::Error::from(E_HANDLE));
+        };
+        unsafe {
+            let id = GetThreadId(HANDLE(thread.as_raw_handle()));
+            PostThreadMessageW(id, msg, WPARAM(wparam), LPARAM(0))
+        }
+    }
+}
+
+impl Drop for WindowsBackend {
+    fn drop(&mut self) {
+        // Ends the RawInput thread's GetMessageW loop, which also removes
+        // the hook of a grab still in place
+        if self.post(WM_QUIT, 0).is_err() && self.grabbed {
+            log_warn!(target: "input", "RawInput thread gone while grabbed");
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
 }
 
-fn run_rawinput_loop(tx: mpsc::Sender<TouchState>) -> Result<(), Box<dyn std::error::Error>> {
+fn run_rawinput_loop(
+    tx: mpsc::Sender<TouchState>,
+    data_ready: Waker,
+) -> Result<(), Box<dyn std::error::Error>> {
     unsafe {
         let hinstance = GetModuleHandleW(PCWSTR::null())?;
 
@@ -103,21 +166,79 @@ fn run_rawinput_loop(tx: mpsc::Sender<TouchState>) -> Result<(), Box<dyn std::er
             .map_err(|e| format!("RegisterRawInputDevices: {}", e))?;
 
         // Store sender in thread-local for the wndproc
-        TX.set(Some(tx));
+        TX.set(Some((tx, data_ready)));
 
-        // Message loop
+        // Message loop, until WM_QUIT from Drop. The hook runs on this thread,
+        // so it's installed and removed here too.
+        let mut hook: Option<HHOOK> = None;
         let mut msg = MSG::default();
         while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+            if msg.hwnd.0.is_null() && msg.message == WM_TAPVIEW_GRAB {
+                set_pointer_hook(&mut hook, msg.wParam.0 != 0);
+                continue;
+            }
             let _ = TranslateMessage(&msg);
             DispatchMessageW(&msg);
         }
+        set_pointer_hook(&mut hook, false);
+        let _ = DestroyWindow(hwnd);
     }
 
     Ok(())
 }
 
+/// Install or remove the hook that holds the pointer still while grabbed.
+unsafe fn set_pointer_hook(hook: &mut Option<HHOOK>, grab: bool) {
+    match (grab, hook.take()) {
+        (true, None) => match SetWindowsHookExW(WH_MOUSE_LL, Some(block_pointer_proc), None, 0) {
+            Ok(h) => {
+                log_info!(target: "input", "grabbed: pointer input is blocked");
+                *hook = Some(h);
+            }
+            Err(e) => log_error!(target: "input", "Failed to grab: {}", e),
+        },
+        (false, Some(h)) => {
+            let _ = UnhookWindowsHookEx(h);
+            log_info!(target: "input", "released grab");
+        }
+        (_, current) => *hook = current,
+    }
+}
+
+/// Swallow pointer input from physical devices, so the cursor doesn't move
+/// and nothing is clicked. Injected input, e.g. from a remote desktop
+/// session, still passes.
+unsafe extern "system" fn block_pointer_proc(
+    ncode: i32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if ncode >= 0 {
+        let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+        if info.flags & LLMHF_INJECTED == 0 {
+            return LRESULT(1);
+        }
+    }
+    CallNextHookEx(None, ncode, wparam, lparam)
+}
+
+/// When the touchpad last reported two or more contacts.
+static LAST_MULTI_TOUCH: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Whether the touchpad reported two or more contacts within `within`, i.e.
+/// whether a wheel event now is likely one Windows made of a two-finger
+/// scroll or pinch rather than a mouse's. A touchpad keeps reporting while
+/// fingers rest on it, so this only misses scrolling that coasts on after
+/// they lift.
+pub fn multi_touch_within(within: Duration) -> bool {
+    LAST_MULTI_TOUCH
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_some_and(|t| t.elapsed() < within)
+}
+
 thread_local! {
-    static TX: std::cell::Cell<Option<mpsc::Sender<TouchState>>> = const { std::cell::Cell::new(None) };
+    static TX: std::cell::Cell<Option<(mpsc::Sender<TouchState>, Waker)>> = const { std::cell::Cell::new(None) };
     static PREPARSED_CACHE: std::cell::RefCell<Option<PreparsedCache>> = const { std::cell::RefCell::new(None) };
 }
 
@@ -125,7 +246,6 @@ struct PreparsedCache {
     data: Vec<u8>,
     #[allow(dead_code)]
     caps: HIDP_CAPS,
-    #[allow(dead_code)]
     value_caps: Vec<HIDP_VALUE_CAPS>,
     button_caps: Vec<HIDP_BUTTON_CAPS>,
     max_contacts: u32,
@@ -195,12 +315,21 @@ unsafe fn handle_raw_input(hrawinput: HRAWINPUT) {
         for report_idx in 0..report_count {
             let report_offset = report_idx * report_size;
             let report = std::slice::from_raw_parts(raw_data_ptr.add(report_offset), report_size);
+            // Only formatted with --verbose or input=trace
+            if tracing::enabled!(target: "input", tracing::Level::TRACE) {
+                trace_report(cache, report);
+            }
 
             if let Some(state) = parse_touchpad_report(cache, report) {
+                if state.touches.iter().filter(|t| t.used).count() >= 2 {
+                    *LAST_MULTI_TOUCH.lock().unwrap_or_else(|e| e.into_inner()) =
+                        Some(Instant::now());
+                }
                 TX.with(|cell| {
                     let tx = cell.take();
-                    if let Some(ref sender) = tx {
+                    if let Some((ref sender, ref data_ready)) = tx {
                         let _ = sender.send(state);
+                        data_ready.wake();
                     }
                     cell.set(tx);
                 });
@@ -375,7 +504,101 @@ unsafe fn parse_touchpad_report(cache: &PreparsedCache, report: &[u8]) -> Option
         slot += 1;
     }
 
-    Some(TouchState { touches, buttons })
+    let contacts = touches.iter().filter(|t| t.used).count() as u8;
+    Some(TouchState {
+        touches,
+        buttons,
+        reports: 1,
+        time: Instant::now(),
+        report_times: vec![ReportTime {
+            time: SystemTime::now(),
+            firmware_us: None,
+            contacts,
+        }],
+    })
+}
+
+/// Log `report` as hex followed by every value and pressed button in it, by
+/// link collection, the counterpart of the evdev event log on Linux.
+unsafe fn trace_report(cache: &PreparsedCache, report: &[u8]) {
+    let preparsed = PHIDP_PREPARSED_DATA(cache.data.as_ptr() as isize);
+    let hex: Vec<String> = report.iter().map(|b| format!("{:02x}", b)).collect();
+    tracing::trace!(target: "input", "HID report ({} bytes): {}", report.len(), hex.join(" "));
+
+    let mut fields = Vec::new();
+    for vc in &cache.value_caps {
+        // bool or BOOLEAN depending on the windows crate version
+        let is_range: bool = vc.IsRange;
+        let usages = if is_range {
+            vc.Anonymous.Range.UsageMin..=vc.Anonymous.Range.UsageMax
+        } else {
+            vc.Anonymous.NotRange.Usage..=vc.Anonymous.NotRange.Usage
+        };
+        for usage in usages {
+            if let Some(value) =
+                get_usage_value(preparsed, vc.UsagePage, vc.LinkCollection, usage, report)
+            {
+                fields.push(format!(
+                    "{}:{}={}",
+                    vc.LinkCollection,
+                    usage_name(vc.UsagePage, usage),
+                    value
+                ));
+            }
+        }
+    }
+    let mut collections: Vec<u16> = cache
+        .button_caps
+        .iter()
+        .map(|bc| bc.LinkCollection)
+        .collect();
+    collections.sort_unstable();
+    collections.dedup();
+    for link_collection in collections {
+        let mut usage_list = [USAGE_AND_PAGE::default(); 64];
+        let mut usage_count = usage_list.len() as u32;
+        let status = HidP_GetUsagesEx(
+            HidP_Input,
+            Some(link_collection),
+            usage_list.as_mut_ptr(),
+            &mut usage_count,
+            preparsed,
+            report,
+        );
+        if status != HIDP_STATUS_SUCCESS {
+            continue;
+        }
+        for u in &usage_list[..usage_count as usize] {
+            fields.push(format!(
+                "{}:{}",
+                link_collection,
+                usage_name(u.UsagePage, u.Usage)
+            ));
+        }
+    }
+    tracing::trace!(target: "input", "  {}", fields.join(" "));
+}
+
+/// Name of the usages a touchpad report uses, else `page:usage` in hex.
+pub(crate) fn usage_name(page: u16, usage: u16) -> String {
+    let name = match (page, usage) {
+        (0x01, 0x30) => "X",
+        (0x01, 0x31) => "Y",
+        (0x09, 0x01) => "Button1",
+        (0x09, 0x02) => "Button2",
+        (0x09, 0x03) => "Button3",
+        (0x0D, 0x30) => "TipPressure",
+        (0x0D, 0x42) => "TipSwitch",
+        (0x0D, 0x47) => "Confidence",
+        (0x0D, 0x48) => "Width",
+        (0x0D, 0x49) => "Height",
+        (0x0D, 0x51) => "ContactID",
+        (0x0D, 0x54) => "ContactCount",
+        (0x0D, 0x55) => "ContactCountMax",
+        (0x0D, 0x56) => "ScanTime",
+        _ => return format!("{:02X}:{:02X}", page, usage),
+    };
+    name.to_string()
 }
 
 unsafe fn get_usage_value(
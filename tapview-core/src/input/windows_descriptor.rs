@@ -0,0 +1,296 @@
+//! `--dump-descriptor` and the descriptor window: what Windows knows about a
+//! touchpad's HID report descriptor. Windows doesn't hand out the descriptor
+//! itself, so this lists the preparsed data's link collections as a tree with
+//! the input, output and feature items of each, the closest thing to the
+//! `report_descriptor` file Linux has in sysfs, followed by the preparsed
+//! data itself in hex.
+
+use super::descriptor_tree::{collection_kind, usage_name, Node};
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use windows::core::PCWSTR;
+use windows::Win32::Devices::HumanInterfaceDevice::*;
+use windows::Win32::Foundation::*;
+use windows::Win32::Storage::FileSystem::*;
+use windows::Win32::UI::Input::*;
+
+/// Open the HID device at `path` (a `\\?\HID#...` interface path) and get
+/// its preparsed data. The caller frees both.
+unsafe fn open_preparsed(path: &str) -> io::Result<(HANDLE, PHIDP_PREPARSED_DATA)> {
+    let wide_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+    // No access needed for the preparsed data and attributes
+    let handle = CreateFileW(
+        PCWSTR(wide_path.as_ptr()),
+        0,
+        FILE_SHARE_READ | FILE_SHARE_WRITE,
+        None,
+        OPEN_EXISTING,
+        FILE_FLAGS_AND_ATTRIBUTES(0),
+        None,
+    )
+    .map_err(io::Error::other)?;
+
+    let mut preparsed = PHIDP_PREPARSED_DATA::default();
+    if !HidD_GetPreparsedData(handle, &mut preparsed) {
+        let _ = CloseHandle(handle);
+        return Err(io::Error::other("HidD_GetPreparsedData failed"));
+    }
+    Ok((handle, preparsed))
+}
+
+/// Describe the HID device at `device_path` (a `\\?\HID#...` interface path).
+pub fn describe(device_path: &Path) -> io::Result<String> {
+    let path = device_path.to_string_lossy().into_owned();
+    unsafe {
+        let (handle, preparsed) = open_preparsed(&path)?;
+        let mut attrs = HIDD_ATTRIBUTES {
+            Size: std::mem::size_of::<HIDD_ATTRIBUTES>() as u32,
+            ..Default::default()
+        };
+        let has_attrs = HidD_GetAttributes(handle, &mut attrs);
+        let result = collections(preparsed);
+        let _ = HidD_FreePreparsedData(preparsed);
+        let _ = CloseHandle(handle);
+        let (caps, tree) = result?;
+
+        let mut out = String::new();
+        let _ = writeln!(out, "Device:   {}", path);
+        if has_attrs {
+            let _ = writeln!(
+                out,
+                "ID:       {:04x}:{:04x} version {:04x}",
+                attrs.VendorID, attrs.ProductID, attrs.VersionNumber
+            );
+        }
+        let _ = writeln!(out, "Usage:    {}", usage_name(caps.UsagePage, caps.Usage));
+        let _ = writeln!(
+            out,
+            "Reports:  input {} bytes, output {} bytes, feature {} bytes",
+            caps.InputReportByteLength, caps.OutputReportByteLength, caps.FeatureReportByteLength
+        );
+        let _ = writeln!(out, "\nCollections");
+        for node in &tree {
+            node.write_text(&mut out, 1);
+        }
+
+        match raw_preparsed_data(&path) {
+            Some(blob) => {
+                let _ = writeln!(out, "\nPreparsed data ({} bytes)", blob.len());
+                for (i, row) in blob.chunks(16).enumerate() {
+                    let hex: Vec<String> = row.iter().map(|b| format!("{:02x}", b)).collect();
+                    let _ = writeln!(out, "  {:04x}: {}", i * 16, hex.join(" "));
+                }
+            }
+            None => {
+                let _ = writeln!(out, "\nPreparsed data: not available through RawInput");
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// The collection tree for the descriptor window, followed by the report
+/// sizes. Windows pads every report to the longest of its kind.
+pub fn tree(device_path: &Path) -> io::Result<Vec<Node>> {
+    let path = device_path.to_string_lossy().into_owned();
+    unsafe {
+        let (handle, preparsed) = open_preparsed(&path)?;
+        let result = collections(preparsed);
+        let _ = HidD_FreePreparsedData(preparsed);
+        let _ = CloseHandle(handle);
+        let (caps, mut tree) = result?;
+        let mut reports = Node::new("Reports");
+        reports.children.push(Node::new(format!(
+            "input {} bytes, output {} bytes, feature {} bytes",
+            caps.InputReportByteLength, caps.OutputReportByteLength, caps.FeatureReportByteLength
+        )));
+        tree.push(reports);
+        Ok(tree)
+    }
+}
+
+/// The link collections as a tree with the input, output and feature items
+/// of each.
+unsafe fn collections(preparsed: PHIDP_PREPARSED_DATA) -> io::Result<(HIDP_CAPS, Vec<Node>)> {
+    let mut caps = HIDP_CAPS::default();
+    if HidP_GetCaps(preparsed, &mut caps) != HIDP_STATUS_SUCCESS {
+        return Err(io::Error::other("HidP_GetCaps failed"));
+    }
+
+    let mut node_count = caps.NumberLinkCollectionNodes as u32;
+    let mut nodes = vec![HIDP_LINK_COLLECTION_NODE::default(); node_count as usize];
+    if node_count > 0
+        && HidP_GetLinkCollectionNodes(nodes.as_mut_ptr(), &mut node_count, preparsed)
+            != HIDP_STATUS_SUCCESS
+    {
+        return Err(io::Error::other("HidP_GetLinkCollectionNodes failed"));
+    }
+    nodes.truncate(node_count as usize);
+
+    // Items by the link collection they're in
+    let mut items: Vec<Vec<Node>> = (0..nodes.len().max(1)).map(|_| Vec::new()).collect();
+    for (report_type, kind, value_count, button_count) in [
+        (
+            HidP_Input,
+            "Input",
+            caps.NumberInputValueCaps,
+            caps.NumberInputButtonCaps,
+        ),
+        (
+            HidP_Output,
+            "Output",
+            caps.NumberOutputValueCaps,
+            caps.NumberOutputButtonCaps,
+        ),
+        (
+            HidP_Feature,
+            "Feature",
+            caps.NumberFeatureValueCaps,
+            caps.NumberFeatureButtonCaps,
+        ),
+    ] {
+        let mut count = value_count;
+        let mut value_caps = vec![HIDP_VALUE_CAPS::default(); count as usize];
+        if count > 0
+            && HidP_GetValueCaps(report_type, value_caps.as_mut_ptr(), &mut count, preparsed)
+                == HIDP_STATUS_SUCCESS
+        {
+            for vc in &value_caps[..count as usize] {
+                let is_range: bool = vc.IsRange;
+                let usage = if is_range {
+                    usage_range(
+                        vc.UsagePage,
+                        vc.Anonymous.Range.UsageMin,
+                        vc.Anonymous.Range.UsageMax,
+                    )
+                } else {
+                    usage_name(vc.UsagePage, vc.Anonymous.NotRange.Usage)
+                };
+                let caps = format!(
+                    "report {:#04x} value  {} size {} count {} logical {}..{} physical {}..{} \
+                     unit {:#x} exp {}",
+                    vc.ReportID,
+                    usage,
+                    vc.BitSize,
+                    vc.ReportCount,
+                    vc.LogicalMin,
+                    vc.LogicalMax,
+                    vc.PhysicalMin,
+                    vc.PhysicalMax,
+                    vc.Units,
+                    vc.UnitsExp
+                );
+                push_item(&mut items, vc.LinkCollection, kind, caps);
+            }
+        }
+
+        let mut count = button_count;
+        let mut button_caps = vec![HIDP_BUTTON_CAPS::default(); count as usize];
+        if count > 0
+            && HidP_GetButtonCaps(report_type, button_caps.as_mut_ptr(), &mut count, preparsed)
+                == HIDP_STATUS_SUCCESS
+        {
+            for bc in &button_caps[..count as usize] {
+                let is_range: bool = bc.IsRange;
+                let usage = if is_range {
+                    usage_range(
+                        bc.UsagePage,
+                        bc.Anonymous.Range.UsageMin,
+                        bc.Anonymous.Range.UsageMax,
+                    )
+                } else {
+                    usage_name(bc.UsagePage, bc.Anonymous.NotRange.Usage)
+                };
+                let caps = format!("report {:#04x} button {}", bc.ReportID, usage);
+                push_item(&mut items, bc.LinkCollection, kind, caps);
+            }
+        }
+    }
+
+    let tree = if nodes.is_empty() {
+        items.swap_remove(0)
+    } else {
+        vec![collection_node(&nodes, &items, 0)]
+    };
+    Ok((caps, tree))
+}
+
+fn push_item(items: &mut [Vec<Node>], link_collection: u16, kind: &'static str, caps: String) {
+    let index = (link_collection as usize).min(items.len() - 1);
+    items[index].push(Node::new(format!("{:7} {}", kind, caps)));
+}
+
+/// Collection `index` with its items and, depth first, the ones below it.
+fn collection_node(nodes: &[HIDP_LINK_COLLECTION_NODE], items: &[Vec<Node>], index: usize) -> Node {
+    let node = &nodes[index];
+    let mut tree = Node::new(format!(
+        "Collection {} ({}) {}",
+        index,
+        collection_kind((node._bitfield & 0xFF) as u8),
+        usage_name(node.LinkUsagePage, node.LinkUsage)
+    ));
+    tree.children.extend(items[index].iter().cloned());
+    // Children are linked through NextSibling, newest first
+    let mut children = Vec::new();
+    let mut child = node.FirstChild as usize;
+    while child != 0 && child < nodes.len() && children.len() < nodes.len() {
+        children.push(child);
+        child = nodes[child].NextSibling as usize;
+    }
+    for &child in children.iter().rev() {
+        tree.children.push(collection_node(nodes, items, child));
+    }
+    tree
+}
+
+fn usage_range(page: u16, min: u16, max: u16) -> String {
+    format!("{}..{}", usage_name(page, min), usage_name(page, max))
+}
+
+/// The preparsed data blob of the RawInput device with this interface path.
+/// HidD_GetPreparsedData doesn't say how large it is; RawInput does.
+unsafe fn raw_preparsed_data(path: &str) -> Option<Vec<u8>> {
+    let entry_size = std::mem::size_of::<RAWINPUTDEVICELIST>() as u32;
+    let mut count = 0u32;
+    if GetRawInputDeviceList(None, &mut count, entry_size) == u32::MAX {
+        return None;
+    }
+    let mut devices = vec![RAWINPUTDEVICELIST::default(); count as usize];
+    let read = GetRawInputDeviceList(Some(devices.as_mut_ptr()), &mut count, entry_size);
+    if read == u32::MAX {
+        return None;
+    }
+    devices.truncate(read as usize);
+
+    for device in devices.iter().filter(|d| d.dwType == RIM_TYPEHID) {
+        let mut len = 0u32;
+        GetRawInputDeviceInfoW(Some(device.hDevice), RIDI_DEVICENAME, None, &mut len);
+        let mut name = vec![0u16; len as usize];
+        let read = GetRawInputDeviceInfoW(
+            Some(device.hDevice),
+            RIDI_DEVICENAME,
+            Some(name.as_mut_ptr() as *mut std::ffi::c_void),
+            &mut len,
+        );
+        if read == u32::MAX {
+            continue;
+        }
+        let name = String::from_utf16_lossy(&name);
+        if !name.trim_end_matches('\0').eq_ignore_ascii_case(path) {
+            continue;
+        }
+
+        let mut size = 0u32;
+        GetRawInputDeviceInfoW(Some(device.hDevice), RIDI_PREPARSEDDATA, None, &mut size);
+        let mut blob = vec![0u8; size as usize];
+        let read = GetRawInputDeviceInfoW(
+            Some(device.hDevice),
+            RIDI_PREPARSEDDATA,
+            Some(blob.as_mut_ptr() as *mut std::ffi::c_void),
+            &mut size,
+        );
+        return (read != u32::MAX).then_some(blob);
+    }
+    None
+}
@@ -0,0 +1,27 @@
+//! Touchpad access without the GUI: device discovery, the evdev/RawInput
+//! backends and the multitouch slot state machine, the vendor heatmap
+//! readers, and the recording format. The `tapview` binary is built on top
+//! of this.
+
+#[macro_use]
+pub mod logging;
+
+pub mod channel;
+pub mod discovery;
+pub mod heatmap;
+pub mod input;
+pub mod multitouch;
+pub mod recording;
+#[cfg(target_os = "linux")]
+pub mod seat;
+pub mod shutdown;
+
+// Re-export commonly used types
+pub use discovery::{DeviceDiscovery, DeviceInfo, DiscoveryError};
+pub use heatmap::HeatmapFrame;
+pub use input::{InputBackend, InputError, TouchState};
+pub use multitouch::{TouchData, MAX_TOUCH_POINTS};
+
+// For the log_* macros
+#[doc(hidden)]
+pub use tracing;
@@ -0,0 +1,214 @@
+//! Logging through `tracing`: [`init`] installs `tracing-subscriber`'s `fmt`
+//! output to stderr and optionally a file, plus a layer forwarding lines to
+//! the in-app log panel.
+//!
+//! Backends log under a subsystem target (`input`, `heatmap`, `libinput`,
+//! `discovery`, `config`, `seat`), so an `EnvFilter` like `warn,heatmap=debug`
+//! can single one out. Programs embedding the library can install their own
+//! `tracing` subscriber instead.
+
+use crate::channel::{self, BoundedReceiver, BoundedSender};
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::{self, LineWriter};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::{EnvFilter, ParseError};
+use tracing_subscriber::fmt;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+/// Target of log lines that don't belong to a subsystem; it isn't printed.
+pub const DEFAULT_TARGET: &str = "tapview";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<tracing::Level> for Level {
+    fn from(level: tracing::Level) -> Self {
+        match level {
+            tracing::Level::ERROR => Level::Error,
+            tracing::Level::WARN => Level::Warn,
+            tracing::Level::INFO => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LogLine {
+    pub level: Level,
+    pub target: &'static str,
+    /// The message, prefixed with its target unless that's [`DEFAULT_TARGET`]
+    pub message: String,
+}
+
+/// Lines queued for the console before further ones are dropped.
+const SINK_CAPACITY: usize = 1024;
+
+/// Check a filter like `info` or `warn,heatmap=debug,input=trace` in
+/// [`EnvFilter`] syntax, for use as a clap value parser.
+pub fn parse_filter(s: &str) -> Result<String, String> {
+    env_filter(s).map_err(|e| e.to_string())?;
+    Ok(s.to_string())
+}
+
+fn env_filter(s: &str) -> Result<EnvFilter, ParseError> {
+    EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .parse(s)
+}
+
+/// Install a `tracing` subscriber logging what `filter` lets through to
+/// stderr, appending to `file` if given, and return the console's end of the
+/// log channel.
+pub fn init(filter: &str, file: Option<&Path>) -> io::Result<BoundedReceiver<LogLine>> {
+    let filter = env_filter(filter).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let file = match file {
+        Some(path) => {
+            let f = OpenOptions::new().create(true).append(true).open(path)?;
+            Some(
+                fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(LineWriter::new(f))),
+            )
+        }
+        None => None,
+    };
+    let (sink, rx) = channel::bounded(SINK_CAPACITY);
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(io::stderr))
+        .with(file)
+        .with(Console { sink });
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| io::Error::new(io::ErrorKind::AlreadyExists, e))?;
+    Ok(rx)
+}
+
+/// Forwards events to the in-app log panel.
+struct Console {
+    sink: BoundedSender<LogLine>,
+}
+
+impl<S: Subscriber> Layer<S> for Console {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let target = metadata.target();
+        let message = if target == DEFAULT_TARGET {
+            fields.0
+        } else {
+            format!("{}: {}", target, fields.0)
+        };
+        let _ = self.sink.send(LogLine {
+            level: (*metadata.level()).into(),
+            target,
+            message,
+        });
+    }
+}
+
+/// The message followed by any other fields as `name=value`.
+#[derive(Default)]
+struct Fields(String);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0.insert_str(0, value);
+        } else {
+            let _ = write!(self.0, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::tracing::debug!(target: $target, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::tracing::debug!(target: $crate::logging::DEFAULT_TARGET, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::tracing::info!(target: $target, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::tracing::info!(target: $crate::logging::DEFAULT_TARGET, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::tracing::warn!(target: $target, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::tracing::warn!(target: $crate::logging::DEFAULT_TARGET, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! log_error {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::tracing::error!(target: $target, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::tracing::error!(target: $crate::logging::DEFAULT_TARGET, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_env_filter_directives() {
+        assert!(parse_filter("warn,heatmap=debug,input=trace").is_ok());
+        assert!(parse_filter("error,tapview_core::heatmap=info").is_ok());
+        assert!(parse_filter("input=loud").is_err());
+    }
+
+    #[test]
+    fn console_gets_filtered_lines_with_their_target() {
+        let (sink, rx) = channel::bounded(SINK_CAPACITY);
+        let subscriber = tracing_subscriber::registry()
+            .with(env_filter("warn,heatmap=debug").unwrap())
+            .with(Console { sink });
+        tracing::subscriber::with_default(subscriber, || {
+            crate::log_info!("hidden");
+            crate::log_warn!("shown {}", 1);
+            crate::log_debug!(target: "heatmap", "frame");
+            crate::log_debug!(target: "input", "hidden");
+        });
+        let lines: Vec<_> = rx.try_iter().map(|l| (l.level, l.message)).collect();
+        assert_eq!(
+            lines,
+            [
+                (Level::Warn, "shown 1".to_string()),
+                (Level::Debug, "heatmap: frame".to_string())
+            ]
+        );
+    }
+}
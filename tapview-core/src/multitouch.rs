@@ -0,0 +1,791 @@
+#[cfg(target_os = "linux")]
+use evdev::{AbsoluteAxisType, EventType, InputEvent, Key, Synchronization};
+
+pub const MAX_TOUCH_POINTS: usize = 10;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ButtonState {
+    pub left: bool,
+    pub right: bool,
+    pub middle: bool,
+    /// Finger count from the held BTN_TOOL_* key (5 means five or more),
+    /// or None if the device hasn't sent any.
+    pub tool_fingers: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+#[allow(dead_code)]
+pub struct TouchData {
+    pub used: bool,
+    pub pressed: bool,
+    pub pressed_double: bool,
+    pub tracking_id: i32,
+    pub position_x: i32,
+    pub position_y: i32,
+    pub pressure: i32,
+    pub distance: i32,
+    pub touch_major: i32,
+    pub touch_minor: i32,
+    pub width_major: i32,
+    pub width_minor: i32,
+    pub orientation: i32,
+    pub tool_x: i32,
+    pub tool_y: i32,
+    pub tool_type: i32,
+}
+
+impl TouchData {
+    #[allow(dead_code)]
+    pub fn reset(&mut self) {
+        *self = TouchData::default();
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_used(&mut self) {
+        self.used = true;
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+enum MTState {
+    Loading,
+    ReadReady,
+    NeedsReset,
+}
+
+/// How a device reports its contacts.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    /// MT protocol B: ABS_MT_SLOT picks the slot later axis updates apply to.
+    #[default]
+    Slots,
+    /// MT protocol A: every report lists all contacts, each ended by
+    /// SYN_MT_REPORT and identified by ABS_MT_TRACKING_ID if at all.
+    Anonymous,
+    /// No MT axes: one contact at ABS_X/ABS_Y while BTN_TOUCH is held.
+    SingleTouch,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct MTStateMachine {
+    state: MTState,
+    protocol: Protocol,
+    slot: Option<usize>,
+    /// The device selected a slot past MAX_TOUCH_POINTS; its updates are
+    /// dropped rather than applied to the previous slot
+    slot_out_of_range: bool,
+    /// Device slot held by each of our slots, for devices with more slots
+    /// than we have (see [`remap_slots`](Self::remap_slots))
+    slot_owners: Option<[Option<usize>; MAX_TOUCH_POINTS]>,
+    /// Device slot selected that doesn't hold one of ours yet
+    pending_slot: Option<usize>,
+    /// Axis minimums of X and Y, subtracted so positions start at 0
+    origin: (i32, i32),
+    pub touches: [TouchData; MAX_TOUCH_POINTS],
+    pub buttons: ButtonState,
+    /// Protocol A contact being read, and its tracking ID if it sent one
+    contact: TouchData,
+    contact_id: Option<i32>,
+    /// Protocol A contacts of the current report
+    report: Vec<(Option<i32>, TouchData)>,
+    /// Tracking IDs for contacts the device doesn't number itself
+    next_tracking_id: i32,
+}
+
+#[cfg(target_os = "linux")]
+impl Default for MTStateMachine {
+    fn default() -> Self {
+        Self::with_protocol(Protocol::default())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl MTStateMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_protocol(protocol: Protocol) -> Self {
+        Self {
+            state: MTState::Loading,
+            protocol,
+            slot: None,
+            slot_out_of_range: false,
+            slot_owners: None,
+            pending_slot: None,
+            origin: (0, 0),
+            touches: [TouchData::default(); MAX_TOUCH_POINTS],
+            buttons: ButtonState::default(),
+            contact: TouchData::default(),
+            contact_id: None,
+            report: Vec::new(),
+            next_tracking_id: 0,
+        }
+    }
+
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    /// For devices with more slots than MAX_TOUCH_POINTS that don't fill
+    /// them in order, like the Magic Trackpad, whose slot is the finger's
+    /// 0-15 hardware ID: each new contact takes its own slot number if free,
+    /// else the first free one, until it lifts.
+    pub fn remap_slots(&mut self) {
+        self.slot_owners = Some([None; MAX_TOUCH_POINTS]);
+    }
+
+    /// Report X and Y relative to these axis minimums, for devices whose
+    /// axes don't start at 0.
+    pub fn set_origin(&mut self, x: i32, y: i32) {
+        self.origin = (x, y);
+    }
+
+    pub fn reset(&mut self) {
+        self.state = MTState::Loading;
+        self.slot = None;
+        self.slot_out_of_range = false;
+        self.pending_slot = None;
+        if let Some(owners) = &mut self.slot_owners {
+            *owners = [None; MAX_TOUCH_POINTS];
+        }
+        for t in &mut self.touches {
+            t.used = false;
+        }
+        self.contact = TouchData::default();
+        self.contact_id = None;
+        self.report.clear();
+    }
+
+    pub fn process(&mut self, event: &InputEvent) {
+        match event.event_type() {
+            EventType::KEY => {
+                let code = Key(event.code());
+                match code {
+                    Key::BTN_TOUCH => {
+                        self.touches[0].pressed = event.value() == 1;
+                        if self.protocol == Protocol::SingleTouch {
+                            self.set_single_touch(event.value() == 1);
+                        }
+                    }
+                    Key::BTN_TOOL_DOUBLETAP => {
+                        self.touches[0].pressed_double = event.value() == 1;
+                        self.set_tool_fingers(2, event.value());
+                    }
+                    Key::BTN_TOOL_FINGER => self.set_tool_fingers(1, event.value()),
+                    Key::BTN_TOOL_TRIPLETAP => self.set_tool_fingers(3, event.value()),
+                    Key::BTN_TOOL_QUADTAP => self.set_tool_fingers(4, event.value()),
+                    Key::BTN_TOOL_QUINTTAP => self.set_tool_fingers(5, event.value()),
+                    Key::BTN_LEFT => {
+                        self.buttons.left = event.value() == 1;
+                    }
+                    Key::BTN_RIGHT => {
+                        self.buttons.right = event.value() == 1;
+                    }
+                    Key::BTN_MIDDLE => {
+                        self.buttons.middle = event.value() == 1;
+                    }
+                    _ => {}
+                }
+            }
+            EventType::ABSOLUTE => {
+                match self.state {
+                    MTState::Loading => {}
+                    MTState::NeedsReset => self.reset(),
+                    MTState::ReadReady => {}
+                }
+
+                let code = AbsoluteAxisType(event.code());
+                let value = match code {
+                    AbsoluteAxisType::ABS_MT_POSITION_X | AbsoluteAxisType::ABS_X => {
+                        event.value() - self.origin.0
+                    }
+                    AbsoluteAxisType::ABS_MT_POSITION_Y | AbsoluteAxisType::ABS_Y => {
+                        event.value() - self.origin.1
+                    }
+                    _ => event.value(),
+                };
+                match self.protocol {
+                    Protocol::Slots => self.process_slotted(code, value),
+                    Protocol::Anonymous => match code {
+                        AbsoluteAxisType::ABS_MT_TRACKING_ID => self.contact_id = Some(value),
+                        _ => set_mt_axis(&mut self.contact, code, value),
+                    },
+                    Protocol::SingleTouch => {
+                        let touch = &mut self.touches[0];
+                        match code {
+                            AbsoluteAxisType::ABS_X => touch.position_x = value,
+                            AbsoluteAxisType::ABS_Y => touch.position_y = value,
+                            AbsoluteAxisType::ABS_PRESSURE => touch.pressure = value,
+                            AbsoluteAxisType::ABS_DISTANCE => touch.distance = value,
+                            AbsoluteAxisType::ABS_TOOL_WIDTH => touch.width_major = value,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            EventType::MISC => {}
+            EventType::SYNCHRONIZATION if self.protocol == Protocol::Anonymous => {
+                if event.code() == Synchronization::SYN_MT_REPORT.0 {
+                    let contact = std::mem::take(&mut self.contact);
+                    if contact.used {
+                        self.report.push((self.contact_id.take(), contact));
+                    }
+                    self.contact_id = None;
+                } else if event.code() == Synchronization::SYN_REPORT.0 {
+                    self.assign_anonymous_contacts();
+                    self.state = MTState::ReadReady;
+                }
+            }
+            EventType::SYNCHRONIZATION => {
+                self.state = MTState::ReadReady;
+            }
+            _ => {}
+        }
+    }
+
+    fn process_slotted(&mut self, code: AbsoluteAxisType, value: i32) {
+        if self.slot_owners.is_some() {
+            self.process_remapped(code, value);
+            return;
+        }
+        if code == AbsoluteAxisType::ABS_MT_SLOT {
+            match usize::try_from(value)
+                .ok()
+                .filter(|&s| s < MAX_TOUCH_POINTS)
+            {
+                Some(slot) => {
+                    self.slot = Some(slot);
+                    self.slot_out_of_range = false;
+                    self.touches[slot].used = true;
+                }
+                None => self.slot_out_of_range = true,
+            }
+            return;
+        }
+        if self.slot_out_of_range {
+            return;
+        }
+        let slot = self.slot.unwrap_or(0);
+        match code {
+            AbsoluteAxisType::ABS_MT_TRACKING_ID => {
+                if value < 0 {
+                    self.touches[slot].used = false;
+                } else {
+                    self.touches[slot].tracking_id = value;
+                }
+            }
+            _ => set_mt_axis(&mut self.touches[slot], code, value),
+        }
+    }
+
+    /// [`process_slotted`](Self::process_slotted) with device slots mapped
+    /// to ours. A device slot only gets one of ours once its tracking ID
+    /// says a contact started, and gives it back when the contact lifts.
+    fn process_remapped(&mut self, code: AbsoluteAxisType, value: i32) {
+        let Some(owners) = &mut self.slot_owners else {
+            return;
+        };
+        if code == AbsoluteAxisType::ABS_MT_SLOT {
+            let device_slot = usize::try_from(value).ok();
+            self.slot = device_slot.and_then(|d| owners.iter().position(|&o| o == Some(d)));
+            self.pending_slot = device_slot.filter(|_| self.slot.is_none());
+            return;
+        }
+        if code == AbsoluteAxisType::ABS_MT_TRACKING_ID && value >= 0 && self.slot.is_none() {
+            let Some(device_slot) = self.pending_slot else {
+                return;
+            };
+            let free = |s: usize| owners[s].is_none();
+            self.slot = Some(device_slot)
+                .filter(|&s| s < MAX_TOUCH_POINTS && free(s))
+                .or_else(|| (0..MAX_TOUCH_POINTS).find(|&s| free(s)));
+            if let Some(slot) = self.slot {
+                owners[slot] = Some(device_slot);
+                self.pending_slot = None;
+                self.touches[slot] = TouchData {
+                    pressed: self.touches[slot].pressed,
+                    pressed_double: self.touches[slot].pressed_double,
+                    ..TouchData::default()
+                };
+            }
+        }
+        // Updates for a device slot with none of ours are dropped
+        let Some(slot) = self.slot else {
+            return;
+        };
+        match code {
+            AbsoluteAxisType::ABS_MT_TRACKING_ID => {
+                if value < 0 {
+                    self.touches[slot].used = false;
+                    owners[slot] = None;
+                    self.slot = None;
+                } else {
+                    self.touches[slot].used = true;
+                    self.touches[slot].tracking_id = value;
+                }
+            }
+            _ => set_mt_axis(&mut self.touches[slot], code, value),
+        }
+    }
+
+    /// Single-touch devices have no tracking ID, so each touch gets the next
+    /// one when it starts.
+    fn set_single_touch(&mut self, down: bool) {
+        let touch = &mut self.touches[0];
+        if down && !touch.used {
+            touch.tracking_id = self.next_tracking_id;
+            self.next_tracking_id = self.next_tracking_id.wrapping_add(1).max(0);
+        }
+        touch.used = down;
+    }
+
+    /// Place the contacts of a protocol A report in slots. A contact keeps
+    /// the slot its tracking ID had; without IDs, contacts are matched by
+    /// their position in the list, as the kernel's own protocol A users do.
+    /// New contacts take the first free slot.
+    fn assign_anonymous_contacts(&mut self) {
+        let previous = self.touches;
+        let mut next = [TouchData::default(); MAX_TOUCH_POINTS];
+        let mut unplaced = Vec::new();
+        for (index, (id, contact)) in self.report.drain(..).enumerate() {
+            let slot = match id {
+                Some(id) => previous
+                    .iter()
+                    .position(|t| t.used && t.tracking_id == id)
+                    .filter(|&s| !next[s].used),
+                None => Some(index).filter(|&s| s < MAX_TOUCH_POINTS && previous[s].used),
+            };
+            match slot {
+                Some(slot) => {
+                    next[slot] = TouchData {
+                        tracking_id: previous[slot].tracking_id,
+                        ..contact
+                    }
+                }
+                None => unplaced.push((id, contact)),
+            }
+        }
+        for (id, contact) in unplaced {
+            let Some(slot) = next.iter().position(|t| !t.used) else {
+                break;
+            };
+            let tracking_id = id.unwrap_or_else(|| {
+                let id = self.next_tracking_id;
+                self.next_tracking_id = self.next_tracking_id.wrapping_add(1).max(0);
+                id
+            });
+            next[slot] = TouchData {
+                tracking_id,
+                ..contact
+            };
+        }
+        next[0].pressed = previous[0].pressed;
+        next[0].pressed_double = previous[0].pressed_double;
+        self.touches = next;
+    }
+
+    /// BTN_TOOL_* keys are mutually exclusive: a press sets the count, and a
+    /// release only clears it if no other tool key has taken over.
+    fn set_tool_fingers(&mut self, count: u8, value: i32) {
+        if value != 0 {
+            self.buttons.tool_fingers = Some(count);
+        } else if self.buttons.tool_fingers == Some(count) || self.buttons.tool_fingers.is_none() {
+            self.buttons.tool_fingers = Some(0);
+        }
+    }
+
+    /// Replace the slot and button state with values read back from the
+    /// kernel, after a SYN_DROPPED lost events. `axes` holds each MT axis's
+    /// value in every one of the device's `slots`, including those past
+    /// [`MAX_TOUCH_POINTS`] that [`remap_slots`](Self::remap_slots) maps to
+    /// ours; a tracking ID of -1 marks an empty slot.
+    pub fn resync(
+        &mut self,
+        current_slot: i32,
+        slots: usize,
+        axes: &[(AbsoluteAxisType, Vec<i32>)],
+        pressed: impl Fn(Key) -> bool,
+    ) {
+        if let Some(owners) = &mut self.slot_owners {
+            *owners = [None; MAX_TOUCH_POINTS];
+        }
+        self.touches = [TouchData::default(); MAX_TOUCH_POINTS];
+        for slot in 0..slots {
+            self.process(&InputEvent::new(
+                EventType::ABSOLUTE,
+                AbsoluteAxisType::ABS_MT_SLOT.0,
+                slot as i32,
+            ));
+            // Tracking ID last, so an empty slot ends up unused; first when
+            // remapping, where it's what claims a slot
+            let (ids, values): (Vec<_>, Vec<_>) = axes
+                .iter()
+                .partition(|(axis, _)| *axis == AbsoluteAxisType::ABS_MT_TRACKING_ID);
+            let ordered: Vec<_> = if self.slot_owners.is_some() {
+                ids.into_iter().chain(values).collect()
+            } else {
+                values.into_iter().chain(ids).collect()
+            };
+            for (axis, per_slot) in ordered {
+                if let Some(&value) = per_slot.get(slot) {
+                    self.process(&InputEvent::new(EventType::ABSOLUTE, axis.0, value));
+                }
+            }
+        }
+        if self.slot_owners.is_some() {
+            self.process_remapped(AbsoluteAxisType::ABS_MT_SLOT, current_slot);
+        } else {
+            self.slot = usize::try_from(current_slot)
+                .ok()
+                .filter(|&s| s < MAX_TOUCH_POINTS);
+            self.slot_out_of_range = self.slot.is_none() && current_slot >= 0;
+        }
+        self.resync_buttons(pressed);
+    }
+
+    /// Like [`resync`](Self::resync), for devices without slots. A protocol A
+    /// device repeats every contact in its next report, so only single-touch
+    /// devices need `axes`: the current ABS_X, ABS_Y, etc.
+    pub fn resync_unslotted(
+        &mut self,
+        axes: &[(AbsoluteAxisType, i32)],
+        pressed: impl Fn(Key) -> bool,
+    ) {
+        self.touches = [TouchData::default(); MAX_TOUCH_POINTS];
+        self.contact = TouchData::default();
+        self.contact_id = None;
+        self.report.clear();
+        if self.protocol == Protocol::SingleTouch {
+            for (axis, value) in axes {
+                self.process(&InputEvent::new(EventType::ABSOLUTE, axis.0, *value));
+            }
+            self.set_single_touch(pressed(Key::BTN_TOUCH));
+        }
+        self.resync_buttons(pressed);
+    }
+
+    fn resync_buttons(&mut self, pressed: impl Fn(Key) -> bool) {
+        self.buttons.left = pressed(Key::BTN_LEFT);
+        self.buttons.right = pressed(Key::BTN_RIGHT);
+        self.buttons.middle = pressed(Key::BTN_MIDDLE);
+        self.touches[0].pressed = pressed(Key::BTN_TOUCH);
+        self.touches[0].pressed_double = pressed(Key::BTN_TOOL_DOUBLETAP);
+        if self.buttons.tool_fingers.is_some() {
+            let tools = [
+                (Key::BTN_TOOL_FINGER, 1),
+                (Key::BTN_TOOL_DOUBLETAP, 2),
+                (Key::BTN_TOOL_TRIPLETAP, 3),
+                (Key::BTN_TOOL_QUADTAP, 4),
+                (Key::BTN_TOOL_QUINTTAP, 5),
+            ];
+            let held = tools.iter().find(|(key, _)| pressed(*key));
+            self.buttons.tool_fingers = Some(held.map_or(0, |(_, count)| *count));
+        }
+        self.state = MTState::ReadReady;
+    }
+
+    #[allow(dead_code)]
+    pub fn is_read_ready(&self) -> bool {
+        self.state == MTState::ReadReady
+    }
+}
+
+/// Store an MT axis value other than the slot and tracking ID.
+#[cfg(target_os = "linux")]
+fn set_mt_axis(touch: &mut TouchData, code: AbsoluteAxisType, value: i32) {
+    let field = match code {
+        AbsoluteAxisType::ABS_MT_POSITION_X => &mut touch.position_x,
+        AbsoluteAxisType::ABS_MT_POSITION_Y => &mut touch.position_y,
+        AbsoluteAxisType::ABS_MT_PRESSURE => &mut touch.pressure,
+        AbsoluteAxisType::ABS_MT_DISTANCE => &mut touch.distance,
+        AbsoluteAxisType::ABS_MT_TOUCH_MAJOR => &mut touch.touch_major,
+        AbsoluteAxisType::ABS_MT_TOUCH_MINOR => &mut touch.touch_minor,
+        AbsoluteAxisType::ABS_MT_WIDTH_MAJOR => &mut touch.width_major,
+        AbsoluteAxisType::ABS_MT_WIDTH_MINOR => &mut touch.width_minor,
+        AbsoluteAxisType::ABS_MT_ORIENTATION => &mut touch.orientation,
+        AbsoluteAxisType::ABS_MT_TOOL_X => &mut touch.tool_x,
+        AbsoluteAxisType::ABS_MT_TOOL_Y => &mut touch.tool_y,
+        AbsoluteAxisType::ABS_MT_TOOL_TYPE => &mut touch.tool_type,
+        _ => return,
+    };
+    *field = value;
+    touch.set_used();
+}
+
+#[cfg(target_os = "linux")]
+pub fn print_event(event: &InputEvent) {
+    let type_name = match event.event_type() {
+        EventType::KEY => "EV_KEY",
+        EventType::ABSOLUTE => "EV_ABS",
+        EventType::MISC => "EV_MSC",
+        EventType::SYNCHRONIZATION => "EV_SYN",
+        _ => "EV_???",
+    };
+    let code_name = code_lookup(event.code());
+    match code_name {
+        Some(name) => {
+            tracing::trace!(target: "input", "{}({}, {})", type_name, name, event.value())
+        }
+        None => tracing::trace!(
+            target: "input",
+            "{}(0x{:X}, {})",
+            type_name,
+            event.code(),
+            event.value()
+        ),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn code_lookup(code: u16) -> Option<&'static str> {
+    match code {
+        0x00 => Some("X"),
+        0x01 => Some("Y"),
+        0x2f => Some("SLOT"),
+        0x30 => Some("TOUCH_MAJOR"),
+        0x31 => Some("TOUCH_MINOR"),
+        0x32 => Some("WIDTH_MAJOR"),
+        0x33 => Some("WIDTH_MINOR"),
+        0x34 => Some("ORIENTATION"),
+        0x35 => Some("POSITION_X"),
+        0x36 => Some("POSITION_Y"),
+        0x37 => Some("TOOL_TYPE"),
+        0x38 => Some("BLOB_ID"),
+        0x39 => Some("TRACKING_ID"),
+        0x3a => Some("PRESSURE"),
+        0x3b => Some("DISTANCE"),
+        0x3c => Some("TOOL_X"),
+        0x3d => Some("TOOL_Y"),
+        0x110 => Some("BTN_LEFT"),
+        0x111 => Some("BTN_RIGHT"),
+        0x112 => Some("BTN_MIDDLE"),
+        0x145 => Some("BTN_TOOL_FINGER"),
+        0x14a => Some("BTN_TOUCH"),
+        0x14d => Some("BTN_TOOL_DOUBLETAP"),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resync_replaces_slot_state() {
+        let mut machine = MTStateMachine::new();
+        // Stale contact in slot 3 whose lift was lost
+        machine.process(&InputEvent::new(
+            EventType::ABSOLUTE,
+            AbsoluteAxisType::ABS_MT_SLOT.0,
+            3,
+        ));
+        machine.process(&InputEvent::new(
+            EventType::ABSOLUTE,
+            AbsoluteAxisType::ABS_MT_TRACKING_ID.0,
+            5,
+        ));
+
+        let mut ids = vec![-1; MAX_TOUCH_POINTS];
+        ids[1] = 9;
+        let mut xs = vec![0; MAX_TOUCH_POINTS];
+        xs[1] = 420;
+        machine.resync(
+            1,
+            MAX_TOUCH_POINTS,
+            &[
+                (AbsoluteAxisType::ABS_MT_TRACKING_ID, ids),
+                (AbsoluteAxisType::ABS_MT_POSITION_X, xs),
+            ],
+            |key| key == Key::BTN_LEFT,
+        );
+
+        let used: Vec<usize> = (0..MAX_TOUCH_POINTS)
+            .filter(|&s| machine.touches[s].used)
+            .collect();
+        assert_eq!(used, vec![1]);
+        assert_eq!(machine.touches[1].tracking_id, 9);
+        assert_eq!(machine.touches[1].position_x, 420);
+        assert!(machine.buttons.left);
+        assert_eq!(machine.slot, Some(1));
+    }
+
+    #[test]
+    fn resync_leaves_slots_past_the_device_empty() {
+        for remap in [false, true] {
+            let mut machine = MTStateMachine::new();
+            if remap {
+                machine.remap_slots();
+            }
+            let ids = vec![-1, 7, -1, -1];
+            let xs = vec![100; 4];
+            machine.resync(
+                1,
+                4,
+                &[
+                    (AbsoluteAxisType::ABS_MT_TRACKING_ID, ids),
+                    (AbsoluteAxisType::ABS_MT_POSITION_X, xs),
+                ],
+                |_| false,
+            );
+            let used: Vec<i32> = machine
+                .touches
+                .iter()
+                .filter(|t| t.used)
+                .map(|t| t.tracking_id)
+                .collect();
+            assert_eq!(used, [7], "remap: {}", remap);
+        }
+    }
+
+    #[test]
+    fn resync_picks_up_contacts_in_high_device_slots() {
+        // A Magic Trackpad's 16 slots, with fingers in hardware slots 12 and 3
+        let mut machine = MTStateMachine::new();
+        machine.remap_slots();
+        let mut ids = vec![-1; 16];
+        ids[12] = 40;
+        ids[3] = 41;
+        let mut xs = vec![0; 16];
+        xs[12] = 1200;
+        xs[3] = 300;
+        machine.resync(
+            12,
+            16,
+            &[
+                (AbsoluteAxisType::ABS_MT_TRACKING_ID, ids),
+                (AbsoluteAxisType::ABS_MT_POSITION_X, xs),
+            ],
+            |_| false,
+        );
+        assert_eq!(used_slots(&machine), [(0, 40, 1200), (3, 41, 300)]);
+        // Later updates to device slot 12 land in the slot it was given
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_POSITION_X, 1250));
+        machine.process(&syn(Synchronization::SYN_REPORT));
+        assert_eq!(machine.touches[0].position_x, 1250);
+    }
+
+    fn abs(axis: AbsoluteAxisType, value: i32) -> InputEvent {
+        InputEvent::new(EventType::ABSOLUTE, axis.0, value)
+    }
+
+    fn syn(code: Synchronization) -> InputEvent {
+        InputEvent::new(EventType::SYNCHRONIZATION, code.0, 0)
+    }
+
+    fn used_slots(machine: &MTStateMachine) -> Vec<(usize, i32, i32)> {
+        (0..MAX_TOUCH_POINTS)
+            .filter(|&s| machine.touches[s].used)
+            .map(|s| {
+                (
+                    s,
+                    machine.touches[s].tracking_id,
+                    machine.touches[s].position_x,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn protocol_a_contacts_keep_their_slots() {
+        let mut machine = MTStateMachine::with_protocol(Protocol::Anonymous);
+        let mut report = |contacts: &[(Option<i32>, i32)]| {
+            for &(id, x) in contacts {
+                if let Some(id) = id {
+                    machine.process(&abs(AbsoluteAxisType::ABS_MT_TRACKING_ID, id));
+                }
+                machine.process(&abs(AbsoluteAxisType::ABS_MT_POSITION_X, x));
+                machine.process(&syn(Synchronization::SYN_MT_REPORT));
+            }
+            if contacts.is_empty() {
+                machine.process(&syn(Synchronization::SYN_MT_REPORT));
+            }
+            machine.process(&syn(Synchronization::SYN_REPORT));
+            used_slots(&machine)
+        };
+
+        assert_eq!(
+            report(&[(Some(7), 100), (Some(8), 200)]),
+            [(0, 7, 100), (1, 8, 200)]
+        );
+        // Listed in a different order, and the first finger lifted
+        assert_eq!(report(&[(Some(8), 210)]), [(1, 8, 210)]);
+        assert_eq!(
+            report(&[(Some(9), 300), (Some(8), 220)]),
+            [(0, 9, 300), (1, 8, 220)]
+        );
+        assert_eq!(report(&[]), []);
+
+        // Without IDs, list position decides and IDs are made up
+        assert_eq!(report(&[(None, 100)]), [(0, 0, 100)]);
+        assert_eq!(
+            report(&[(None, 110), (None, 500)]),
+            [(0, 0, 110), (1, 1, 500)]
+        );
+    }
+
+    #[test]
+    fn single_touch_follows_btn_touch() {
+        let mut machine = MTStateMachine::with_protocol(Protocol::SingleTouch);
+        let touch = |value| InputEvent::new(EventType::KEY, Key::BTN_TOUCH.code(), value);
+        // Other MT protocols' axes are ignored
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_POSITION_X, 999));
+        machine.process(&abs(AbsoluteAxisType::ABS_X, 100));
+        machine.process(&abs(AbsoluteAxisType::ABS_Y, 50));
+        machine.process(&touch(1));
+        machine.process(&syn(Synchronization::SYN_REPORT));
+        assert_eq!(used_slots(&machine), [(0, 0, 100)]);
+        assert_eq!(machine.touches[0].position_y, 50);
+
+        machine.process(&touch(0));
+        assert!(used_slots(&machine).is_empty());
+        machine.process(&abs(AbsoluteAxisType::ABS_X, 300));
+        machine.process(&touch(1));
+        assert_eq!(used_slots(&machine), [(0, 1, 300)]);
+    }
+
+    #[test]
+    fn remaps_sparse_device_slots() {
+        let mut machine = MTStateMachine::new();
+        machine.remap_slots();
+        machine.set_origin(-3678, -2479);
+        let mut touch = |device_slot: i32, id: i32, x: i32| {
+            machine.process(&abs(AbsoluteAxisType::ABS_MT_SLOT, device_slot));
+            machine.process(&abs(AbsoluteAxisType::ABS_MT_TRACKING_ID, id));
+            if id >= 0 {
+                machine.process(&abs(AbsoluteAxisType::ABS_MT_POSITION_X, x));
+            }
+            machine.process(&syn(Synchronization::SYN_REPORT));
+            used_slots(&machine)
+        };
+        // Hardware finger 14 gets a free slot, 3 keeps its own number
+        assert_eq!(touch(14, 1, -3678), [(0, 1, 0)]);
+        assert_eq!(touch(3, 2, 0), [(0, 1, 0), (3, 2, 3678)]);
+        // 0 is taken by 14, so finger 0 goes to the next free slot
+        assert_eq!(touch(0, 3, 100), [(0, 1, 0), (1, 3, 3778), (3, 2, 3678)]);
+        assert_eq!(touch(14, -1, 0), [(1, 3, 3778), (3, 2, 3678)]);
+        // Updates for a device slot we never mapped are dropped
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_SLOT, 9));
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_POSITION_X, 500));
+        assert_eq!(used_slots(&machine).len(), 2);
+        assert!(!machine.touches[9].used);
+    }
+
+    #[test]
+    fn ignores_slots_past_the_limit() {
+        let mut machine = MTStateMachine::new();
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_SLOT, 2));
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_POSITION_X, 100));
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_SLOT, MAX_TOUCH_POINTS as i32));
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_TRACKING_ID, 40));
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_POSITION_X, 900));
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_SLOT, 2));
+        machine.process(&abs(AbsoluteAxisType::ABS_MT_POSITION_Y, 50));
+        assert_eq!(used_slots(&machine), [(2, 0, 100)]);
+        assert_eq!(machine.touches[2].position_y, 50);
+    }
+}
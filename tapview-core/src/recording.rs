@@ -5,7 +5,8 @@ use std::io::{self, BufReader, BufWriter, Read, Write};
 use std::time::Instant;
 
 const MAGIC: &[u8; 4] = b"TAPV";
-const VERSION: u32 = 1;
+/// Version 2 added the device's capabilities after the extents
+const VERSION: u32 = 2;
 
 fn write_bool(w: &mut impl Write, v: bool) -> io::Result<()> {
     w.write_all(&[v as u8])
@@ -17,6 +18,16 @@ fn read_bool(r: &mut impl Read) -> io::Result<bool> {
     Ok(buf[0] != 0)
 }
 
+fn write_u16(w: &mut impl Write, v: u16) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn read_u16(r: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
 fn write_i32(w: &mut impl Write, v: i32) -> io::Result<()> {
     w.write_all(&v.to_le_bytes())
 }
@@ -47,6 +58,93 @@ fn read_u64(r: &mut impl Read) -> io::Result<u64> {
     Ok(u64::from_le_bytes(buf))
 }
 
+/// The evdev capabilities of the recorded device, so a replay can recreate
+/// it. Empty for version 1 recordings and where there's no evdev device.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeviceCapabilities {
+    pub name: String,
+    /// Bus type, vendor, product and version
+    pub id: [u16; 4],
+    pub properties: Vec<u16>,
+    pub keys: Vec<u16>,
+    pub axes: Vec<AxisInfo>,
+}
+
+/// One absolute axis and its absinfo.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AxisInfo {
+    pub code: u16,
+    pub minimum: i32,
+    pub maximum: i32,
+    pub fuzz: i32,
+    pub flat: i32,
+    pub resolution: i32,
+}
+
+impl DeviceCapabilities {
+    pub fn axis(&self, code: u16) -> Option<&AxisInfo> {
+        self.axes.iter().find(|a| a.code == code)
+    }
+}
+
+fn write_codes(w: &mut impl Write, codes: &[u16]) -> io::Result<()> {
+    write_u32(w, codes.len() as u32)?;
+    codes.iter().try_for_each(|&code| write_u16(w, code))
+}
+
+fn read_codes(r: &mut impl Read) -> io::Result<Vec<u16>> {
+    let len = read_u32(r)?;
+    (0..len).map(|_| read_u16(r)).collect()
+}
+
+fn write_capabilities(w: &mut impl Write, caps: &DeviceCapabilities) -> io::Result<()> {
+    write_u32(w, caps.name.len() as u32)?;
+    w.write_all(caps.name.as_bytes())?;
+    caps.id.iter().try_for_each(|&v| write_u16(w, v))?;
+    write_codes(w, &caps.properties)?;
+    write_codes(w, &caps.keys)?;
+    write_u32(w, caps.axes.len() as u32)?;
+    for axis in &caps.axes {
+        write_u16(w, axis.code)?;
+        write_i32(w, axis.minimum)?;
+        write_i32(w, axis.maximum)?;
+        write_i32(w, axis.fuzz)?;
+        write_i32(w, axis.flat)?;
+        write_i32(w, axis.resolution)?;
+    }
+    Ok(())
+}
+
+fn read_capabilities(r: &mut impl Read) -> io::Result<DeviceCapabilities> {
+    let mut name = vec![0u8; read_u32(r)? as usize];
+    r.read_exact(&mut name)?;
+    let mut id = [0u16; 4];
+    for v in &mut id {
+        *v = read_u16(r)?;
+    }
+    let properties = read_codes(r)?;
+    let keys = read_codes(r)?;
+    let axes = (0..read_u32(r)?)
+        .map(|_| {
+            Ok(AxisInfo {
+                code: read_u16(r)?,
+                minimum: read_i32(r)?,
+                maximum: read_i32(r)?,
+                fuzz: read_i32(r)?,
+                flat: read_i32(r)?,
+                resolution: read_i32(r)?,
+            })
+        })
+        .collect::<io::Result<_>>()?;
+    Ok(DeviceCapabilities {
+        name: String::from_utf8_lossy(&name).into_owned(),
+        id,
+        properties,
+        keys,
+        axes,
+    })
+}
+
 fn write_touch_data(w: &mut impl Write, t: &TouchData) -> io::Result<()> {
     write_bool(w, t.used)?;
     write_bool(w, t.pressed)?;
@@ -105,8 +203,15 @@ fn read_touch_state(r: &mut impl Read) -> io::Result<TouchState> {
         left: read_bool(r)?,
         right: read_bool(r)?,
         middle: read_bool(r)?,
+        tool_fingers: None,
     };
-    Ok(TouchState { touches, buttons })
+    Ok(TouchState {
+        touches,
+        buttons,
+        reports: 1,
+        time: Instant::now(),
+        report_times: Vec::new(),
+    })
 }
 
 /// Records touch frames to a binary file with timestamps.
@@ -116,13 +221,19 @@ pub struct Recorder {
 }
 
 impl Recorder {
-    pub fn new(path: &str, extent_x: i32, extent_y: i32) -> io::Result<Self> {
+    pub fn new(
+        path: &str,
+        extent_x: i32,
+        extent_y: i32,
+        capabilities: &DeviceCapabilities,
+    ) -> io::Result<Self> {
         let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
         writer.write_all(MAGIC)?;
         write_u32(&mut writer, VERSION)?;
         write_i32(&mut writer, extent_x)?;
         write_i32(&mut writer, extent_y)?;
+        write_capabilities(&mut writer, capabilities)?;
         Ok(Self {
             writer,
             start: Instant::now(),
@@ -157,6 +268,7 @@ pub struct Recording {
     pub frames: Vec<RecordedFrame>,
     pub extent_x: i32,
     pub extent_y: i32,
+    pub capabilities: DeviceCapabilities,
 }
 
 impl Recording {
@@ -174,7 +286,7 @@ impl Recording {
         }
 
         let version = read_u32(&mut reader)?;
-        if version != VERSION {
+        if !(1..=VERSION).contains(&version) {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("unsupported version: {}", version),
@@ -183,6 +295,11 @@ impl Recording {
 
         let extent_x = read_i32(&mut reader)?;
         let extent_y = read_i32(&mut reader)?;
+        let capabilities = if version >= 2 {
+            read_capabilities(&mut reader)?
+        } else {
+            DeviceCapabilities::default()
+        };
 
         let mut frames = Vec::new();
         loop {
@@ -209,6 +326,7 @@ impl Recording {
             frames,
             extent_x,
             extent_y,
+            capabilities,
         })
     }
 
@@ -259,6 +377,23 @@ mod tests {
         }
     }
 
+    fn sample_capabilities() -> DeviceCapabilities {
+        DeviceCapabilities {
+            name: "PIXA3854:00 093A:0274 Touchpad".to_string(),
+            id: [0x18, 0x093a, 0x0274, 0x0100],
+            properties: vec![0, 2],
+            keys: vec![0x110, 0x145, 0x14a],
+            axes: vec![AxisInfo {
+                code: 0x35,
+                minimum: 0,
+                maximum: 3840,
+                fuzz: 0,
+                flat: 0,
+                resolution: 31,
+            }],
+        }
+    }
+
     fn assert_touch_data_eq(a: &TouchData, b: &TouchData) {
         assert_eq!(a.used, b.used);
         assert_eq!(a.pressed, b.pressed);
@@ -321,10 +456,12 @@ mod tests {
     #[test]
     fn test_load_sample_recording() {
         let rec = Recording::load("testdata/sample.tapv").unwrap();
-        assert!(rec.frames.len() > 0, "expected frames, got 0");
+        assert!(!rec.frames.is_empty(), "expected frames, got 0");
         assert!(rec.duration_secs() > 0.0);
         assert_eq!(rec.extent_x, 3841);
         assert_eq!(rec.extent_y, 2392);
+        // Version 1: no capabilities
+        assert_eq!(rec.capabilities, DeviceCapabilities::default());
 
         // frame_at boundaries
         let first = rec.frame_at(0.0).unwrap();
@@ -346,7 +483,7 @@ mod tests {
         };
 
         {
-            let mut rec = Recorder::new(path, 1920, 1080).unwrap();
+            let mut rec = Recorder::new(path, 1920, 1080, &sample_capabilities()).unwrap();
             rec.record(&state).unwrap();
             std::thread::sleep(std::time::Duration::from_millis(10));
             rec.record(&state).unwrap();
@@ -356,6 +493,7 @@ mod tests {
         let loaded = Recording::load(path).unwrap();
         assert_eq!(loaded.extent_x, 1920);
         assert_eq!(loaded.extent_y, 1080);
+        assert_eq!(loaded.capabilities, sample_capabilities());
         assert_eq!(loaded.frames.len(), 2);
         assert!(loaded.frames[1].timestamp_us > loaded.frames[0].timestamp_us);
         assert_touch_state_eq(&loaded.frames[0].state, &state);
@@ -370,7 +508,7 @@ mod tests {
         let path = dir.to_str().unwrap();
 
         {
-            let mut rec = Recorder::new(path, 800, 600).unwrap();
+            let mut rec = Recorder::new(path, 800, 600, &DeviceCapabilities::default()).unwrap();
             let state = TouchState::default();
             for _ in 0..10 {
                 rec.record(&state).unwrap();
@@ -383,7 +521,10 @@ mod tests {
 
         // Truncate mid-frame: keep header + 5 full frames + partial 6th
         let file_len = std::fs::metadata(path).unwrap().len();
-        let header_size: u64 = 4 + 4 + 4 + 4; // MAGIC + VERSION + extent_x + extent_y
+        let mut caps = Vec::new();
+        write_capabilities(&mut caps, &DeviceCapabilities::default()).unwrap();
+        // MAGIC + VERSION + extent_x + extent_y + capabilities
+        let header_size = 4 + 4 + 4 + 4 + caps.len() as u64;
         let frame_size = (file_len - header_size) / 10;
         let truncated_len = header_size + frame_size * 5 + frame_size / 2;
         let data = std::fs::read(path).unwrap();
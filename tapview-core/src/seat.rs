@@ -0,0 +1,120 @@
+//! Opening device nodes the user has no permission for by taking them from
+//! systemd-logind (`TakeDevice`), the way compositors get their input
+//! devices. Built with the `logind` feature, and off unless [`enable_logind`]
+//! is called; otherwise [`open`] is a plain open(2).
+//!
+//! logind only hands devices to the session's controller, so this works
+//! where tapview can become it: from a text console, or an X session nobody
+//! else controls. Under a Wayland compositor that already controls the
+//! session `TakeControl` fails, and the plain open's error is returned.
+//! Devices are revoked when the session is switched away from; reads then
+//! fail like for an unplugged device.
+
+use std::ffi::CString;
+use std::io;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+#[cfg(feature = "logind")]
+pub use logind::enable_logind;
+
+/// open(2) `path` with `flags` (plus O_CLOEXEC). If that's refused and logind
+/// is enabled, take the device from logind instead, with O_NONBLOCK set as
+/// in `flags`.
+pub fn open(path: &Path, flags: i32) -> io::Result<OwnedFd> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let fd = unsafe { libc::open(c_path.as_ptr(), flags | libc::O_CLOEXEC) };
+    if fd >= 0 {
+        return Ok(unsafe { OwnedFd::from_raw_fd(fd) });
+    }
+    let err = io::Error::last_os_error();
+    #[cfg(feature = "logind")]
+    if logind::enabled() && err.kind() == io::ErrorKind::PermissionDenied {
+        match logind::take_device(path, flags) {
+            Ok(fd) => {
+                log_info!(target: "seat", "took {} from logind", path.display());
+                return Ok(fd);
+            }
+            Err(e) => {
+                log_warn!(target: "seat", "logind can't provide {}: {}", path.display(), e)
+            }
+        }
+    }
+    Err(err)
+}
+
+#[cfg(feature = "logind")]
+mod logind {
+    use std::os::fd::{AsRawFd, OwnedFd};
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::OnceLock;
+    use zbus::blocking::Connection;
+
+    const LOGIND: &str = "org.freedesktop.login1";
+    /// Resolves to the caller's own session
+    const SESSION_PATH: &str = "/org/freedesktop/login1/session/auto";
+    const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+    static USE_LOGIND: AtomicBool = AtomicBool::new(false);
+    /// The bus connection holding session control; dropping it would release
+    /// every device taken.
+    static SESSION: OnceLock<Result<Connection, String>> = OnceLock::new();
+
+    /// Fall back to logind when opening a device is refused.
+    pub fn enable_logind() {
+        USE_LOGIND.store(true, Ordering::Relaxed);
+    }
+
+    pub(super) fn enabled() -> bool {
+        USE_LOGIND.load(Ordering::Relaxed)
+    }
+
+    fn session() -> Result<&'static Connection, String> {
+        SESSION
+            .get_or_init(|| {
+                let conn = Connection::system().map_err(|e| e.to_string())?;
+                conn.call_method(
+                    Some(LOGIND),
+                    SESSION_PATH,
+                    Some(SESSION_INTERFACE),
+                    "TakeControl",
+                    &(false,),
+                )
+                .map_err(|e| format!("TakeControl: {}", e))?;
+                Ok(conn)
+            })
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    pub(super) fn take_device(path: &Path, flags: i32) -> Result<OwnedFd, String> {
+        let rdev = std::fs::metadata(path).map_err(|e| e.to_string())?.rdev();
+        let (major, minor) = (libc::major(rdev), libc::minor(rdev));
+        let reply = session()?
+            .call_method(
+                Some(LOGIND),
+                SESSION_PATH,
+                Some(SESSION_INTERFACE),
+                "TakeDevice",
+                &(major, minor),
+            )
+            .map_err(|e| format!("TakeDevice: {}", e))?;
+        let (fd, _inactive): (zbus::zvariant::OwnedFd, bool) =
+            reply.body().deserialize().map_err(|e| e.to_string())?;
+        let fd = OwnedFd::from(fd);
+
+        // logind opens devices non-blocking; match what the caller asked for
+        let raw = fd.as_raw_fd();
+        unsafe {
+            let current = libc::fcntl(raw, libc::F_GETFL);
+            let wanted = (current & !libc::O_NONBLOCK) | (flags & libc::O_NONBLOCK);
+            if current >= 0 && current != wanted {
+                libc::fcntl(raw, libc::F_SETFL, wanted);
+            }
+        }
+        Ok(fd)
+    }
+}
@@ -0,0 +1,83 @@
+//! Stopping backend threads when the GUI closes, so grabs are released and
+//! devices closed instead of leaking until the process dies.
+//!
+//! Threads that poll with a timeout check [`Shutdown::is_requested`];
+//! threads blocked indefinitely (a `poll()` without timeout, a Windows
+//! message loop) register a callback that wakes them.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+type Callback = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Inner {
+    requested: AtomicBool,
+    callbacks: Mutex<Vec<Callback>>,
+}
+
+/// A cloneable, one-way shutdown signal.
+#[derive(Clone, Default)]
+pub struct Shutdown(Arc<Inner>);
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Signal shutdown and run the registered callbacks.
+    pub fn request(&self) {
+        self.0.requested.store(true, Ordering::SeqCst);
+        let callbacks = std::mem::take(&mut *self.lock());
+        for callback in callbacks {
+            callback();
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.0.requested.load(Ordering::SeqCst)
+    }
+
+    /// Run `f` when shutdown is requested, or right away if it already was.
+    pub fn on_request(&self, f: impl FnOnce() + Send + 'static) {
+        let mut callbacks = self.lock();
+        if self.is_requested() {
+            drop(callbacks);
+            f();
+        } else {
+            callbacks.push(Box::new(f));
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Callback>> {
+        self.0.callbacks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn callbacks_run_once_including_late_ones() {
+        let shutdown = Shutdown::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = runs.clone();
+        shutdown.on_request(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        assert!(!shutdown.is_requested());
+
+        shutdown.request();
+        shutdown.request();
+        assert!(shutdown.is_requested());
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let counter = runs.clone();
+        shutdown.on_request(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+}